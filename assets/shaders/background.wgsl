@@ -4,6 +4,8 @@
 var image_texture: texture_2d<f32>;
 @group(1) @binding(1)
 var image_sampler: sampler;
+@group(1) @binding(2)
+var<uniform> tint: vec4<f32>;
 
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
@@ -14,5 +16,5 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     tiled_uv_x = fract(in.uv.x * 3.0);
     tiled_uv_y = fract(in.uv.y * 3.0);
     tiled_uv = vec2(tiled_uv_x,tiled_uv_y);
-    return textureSample(image_texture, image_sampler, tiled_uv);
+    return textureSample(image_texture, image_sampler, tiled_uv) * tint;
 }
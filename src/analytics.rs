@@ -0,0 +1,162 @@
+//! Stats on the house rules used in our own games, to help groups find the rules that make
+//! games better.
+//!
+//! Nothing is kept unless the rule stats setting is turned on. Only the rules, result and
+//! length of each game are saved, never who was playing, and they stay on this device.
+
+use crate::{
+    history::RoundStart,
+    profile::{Profile, Profiles},
+    rules::{GameRules, Rule},
+    screens::win::Win,
+    settings::Settings,
+    storage::Storage,
+    transport::GameTransport,
+    ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, io};
+
+/// Games played with one set of rules.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PresetStats {
+    pub rules: GameRules,
+    pub games: u32,
+    pub wins: u32,
+    /// Total length of the games, in minutes.
+    pub minutes: f32,
+}
+
+impl PresetStats {
+    /// Average length of a game with these rules, in minutes.
+    pub fn average_minutes(&self) -> f32 {
+        self.minutes / self.games.max(1) as f32
+    }
+
+    /// Share of the games that were won, from 0 to 1.
+    pub fn win_rate(&self) -> f32 {
+        self.wins as f32 / self.games.max(1) as f32
+    }
+}
+
+/// Games played with each set of rules by a profile.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleStats {
+    pub presets: Vec<PresetStats>,
+}
+
+impl RuleStats {
+    /// Loads the profile's rule stats, or empty stats if they haven't kept any.
+    pub fn load(storage: &Storage, profile: &Profile) -> Self {
+        storage.get(&profile.key("rule_stats")).unwrap_or_default()
+    }
+
+    /// Saves the profile's rule stats.
+    pub fn save(&self, storage: &mut Storage, profile: &Profile) -> io::Result<()> {
+        storage.set(&profile.key("rule_stats"), self)
+    }
+
+    /// Counts a finished game.
+    pub fn record(&mut self, rules: &GameRules, won: bool, minutes: f32) {
+        let index = match self.presets.iter().position(|p| p.rules == *rules) {
+            Some(index) => index,
+            None => {
+                self.presets.push(PresetStats {
+                    rules: rules.clone(),
+                    ..default()
+                });
+                self.presets.len() - 1
+            }
+        };
+        let preset = &mut self.presets[index];
+        preset.games += 1;
+        preset.wins += won as u32;
+        preset.minutes += minutes;
+    }
+
+    /// Total number of games counted.
+    pub fn games(&self) -> u32 {
+        self.presets.iter().map(|p| p.games).sum()
+    }
+
+    /// Number of games played with the rule turned on.
+    pub fn games_with(&self, rule: Rule) -> u32 {
+        self.presets
+            .iter()
+            .filter(|p| rule.is_enabled(&p.rules))
+            .map(|p| p.games)
+            .sum()
+    }
+
+    /// Returns the sets of rules, most played first.
+    pub fn most_played(&self) -> Vec<&PresetStats> {
+        let mut presets: Vec<&PresetStats> = self.presets.iter().collect();
+        presets.sort_by_key(|p| Reverse(p.games));
+        presets
+    }
+}
+
+/// Counts the round in the profile's rule stats once someone wins, if they're being kept.
+fn record_game(
+    mut events: EventReader<Win>,
+    mut socket: ResMut<GameTransport>,
+    mut storage: ResMut<Storage>,
+    profiles: Res<Profiles>,
+    rules: Res<GameRules>,
+    round_start: Res<RoundStart>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let Some(Win(winner)) = events.read().next() else { return; };
+    if !settings.rule_stats {
+        return;
+    }
+    let mut stats = RuleStats::load(&storage, profiles.active());
+    let minutes = (time.elapsed_seconds() - round_start.0) / 60.0;
+    stats.record(&rules, socket.id() == Some(*winner), minutes);
+    if let Err(err) = stats.save(&mut storage, profiles.active()) {
+        error!("Error saving rule stats: {err}");
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            record_game
+                .run_if(in_state(ScreenState::Game))
+                .run_if(resource_exists::<RoundStart>())
+                .run_if(resource_exists::<GameTransport>()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that games are counted under their rules, and averaged per set of rules.
+    #[test]
+    fn test_rule_stats() {
+        let stacking = GameRules {
+            stacking: true,
+            ..default()
+        };
+        let mut stats = RuleStats::default();
+        stats.record(&GameRules::default(), true, 10.0);
+        stats.record(&stacking, false, 6.0);
+        stats.record(&stacking, true, 10.0);
+
+        assert_eq!(stats.games(), 3);
+        assert_eq!(stats.games_with(Rule::Stacking), 2);
+        assert_eq!(stats.games_with(Rule::HandCap), 0);
+
+        let most_played = stats.most_played();
+        assert_eq!(most_played[0].rules, stacking);
+        assert_eq!(most_played[0].average_minutes(), 8.0);
+        assert_eq!(most_played[0].win_rate(), 0.5);
+    }
+}
@@ -0,0 +1,200 @@
+//! Time based animation of transforms.
+//!
+//! Anything that glides somewhere, like cards moving around the table or buttons growing when
+//! hovered over, gets a [`Tween`] and has its target changed as things move around. Every tween
+//! takes the same time no matter the frame rate, and always ends exactly on its target.
+
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    transform::TransformSystem,
+};
+
+/// Shape of a tween's progress over time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    /// Starts fast and slows down as it arrives.
+    EaseOut,
+    /// Starts and ends slowly.
+    EaseInOut,
+}
+
+impl Easing {
+    /// Returns how far along (0 to 1) the tween is at a point through its duration.
+    pub fn apply(self, progress: f32) -> f32 {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            Easing::EaseOut => 1.0 - (1.0 - progress).powi(3),
+            Easing::EaseInOut => progress * progress * (3.0 - 2.0 * progress),
+        }
+    }
+}
+
+/// Parts of a transform a tween moves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TweenParts {
+    /// Translation and rotation, for things placed on the table.
+    Placement,
+    /// Only the scale, for UI nodes that are placed by the layout.
+    Scale,
+}
+
+/// Animates an entity's transform towards a target.
+///
+/// Changing the target starts a new animation from wherever the entity is at the time.
+#[derive(Component, Clone, Debug)]
+pub struct Tween {
+    /// Transform when the animation started, filled in on its first frame.
+    start: Option<Transform>,
+    target: Transform,
+    parts: TweenParts,
+    /// How long (in seconds) the animation takes.
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+}
+
+impl Tween {
+    /// Creates a tween moving and turning an entity to `translation` and `rotation`.
+    pub fn placement(translation: Vec3, rotation: Quat, duration: f32, easing: Easing) -> Self {
+        Self::new(
+            Transform::from_translation(translation).with_rotation(rotation),
+            TweenParts::Placement,
+            duration,
+            easing,
+        )
+    }
+
+    /// Creates a tween resizing an entity to `scale`.
+    pub fn scale(scale: Vec3, duration: f32, easing: Easing) -> Self {
+        Self::new(
+            Transform::from_scale(scale),
+            TweenParts::Scale,
+            duration,
+            easing,
+        )
+    }
+
+    fn new(target: Transform, parts: TweenParts, duration: f32, easing: Easing) -> Self {
+        Self {
+            start: None,
+            target,
+            parts,
+            duration,
+            elapsed: 0.0,
+            easing,
+        }
+    }
+
+    /// Moves the target, starting over from the current transform if it changed.
+    pub fn retarget(&mut self, target: Transform) {
+        let moved = match self.parts {
+            TweenParts::Placement => {
+                target.translation.distance(self.target.translation) > 0.01
+                    || target.rotation.angle_between(self.target.rotation) > 0.001
+            }
+            TweenParts::Scale => target.scale.distance(self.target.scale) > 0.001,
+        };
+        if moved {
+            self.target = target;
+            self.start = None;
+            self.elapsed = 0.0;
+        }
+    }
+
+    /// Returns `true` if the entity has reached its target.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Moves the animation forward by `delta` seconds, updating `transform`.
+    fn advance(&mut self, transform: &mut Transform, delta: f32) {
+        let start = *self.start.get_or_insert(*transform);
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+        let progress = if self.duration > 0.0 {
+            self.easing.apply(self.elapsed / self.duration)
+        } else {
+            1.0
+        };
+        match self.parts {
+            TweenParts::Placement => {
+                transform.translation = start.translation.lerp(self.target.translation, progress);
+                transform.rotation = start.rotation.slerp(self.target.rotation, progress);
+            }
+            TweenParts::Scale => {
+                transform.scale = start.scale.lerp(self.target.scale, progress);
+            }
+        }
+    }
+}
+
+/// Moves every unfinished tween along.
+pub fn drive_tweens(mut tweens: Query<(&mut Transform, &mut Tween)>, time: Res<Time>) {
+    for (mut transform, mut tween) in &mut tweens {
+        if tween.is_finished() {
+            continue;
+        }
+        tween.advance(&mut transform, time.delta_seconds());
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        // after everything has picked its targets for the frame
+        app.add_systems(
+            PostUpdate,
+            drive_tweens.before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that easing starts at 0, ends at 1, and never goes backwards.
+    #[test]
+    fn test_easing() {
+        for easing in [Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            assert_eq!(easing.apply(2.0), 1.0);
+            let mut last = 0.0;
+            for step in 1..=10 {
+                let eased = easing.apply(step as f32 / 10.0);
+                assert!(eased >= last);
+                last = eased;
+            }
+        }
+        assert!(Easing::EaseOut.apply(0.5) > Easing::EaseInOut.apply(0.5));
+    }
+
+    /// Ensures that a tween arrives exactly on time, and starts over when retargeted.
+    #[test]
+    fn test_tween() {
+        let mut transform = Transform::default();
+        let mut tween = Tween::placement(
+            Vec3::new(10.0, 0.0, 0.0),
+            Quat::IDENTITY,
+            1.0,
+            Easing::EaseInOut,
+        );
+        tween.advance(&mut transform, 0.5);
+        assert!((transform.translation.x - 5.0).abs() < 0.001);
+        tween.advance(&mut transform, 0.6);
+        assert!(tween.is_finished());
+        assert_eq!(transform.translation.x, 10.0);
+
+        // the same target doesn't start over
+        tween.retarget(Transform::from_xyz(10.0, 0.0, 0.0));
+        assert!(tween.is_finished());
+
+        tween.retarget(Transform::from_xyz(0.0, 0.0, 0.0));
+        assert!(!tween.is_finished());
+        tween.advance(&mut transform, 0.5);
+        assert!((transform.translation.x - 5.0).abs() < 0.001);
+        // scale is left alone by placement tweens
+        assert_eq!(transform.scale, Vec3::ONE);
+    }
+}
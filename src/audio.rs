@@ -0,0 +1,65 @@
+//! Sound effects.
+//!
+//! Sounds are generated tones, so they don't need any audio assets.
+
+use crate::settings::Settings;
+use bevy::audio::{PitchBundle, Volume};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use std::time::Duration;
+
+/// A sound effect.
+#[derive(Clone, Copy, Debug)]
+pub enum Sound {
+    /// Gentle reminder that it's our turn.
+    Nudge,
+    /// The direction of play changing.
+    Reverse,
+    /// The deck being shuffled before a deal.
+    Shuffle,
+    /// A card being dealt into our hand.
+    Deal,
+}
+
+impl Sound {
+    /// Returns the frequency (in hertz), length, and volume of the tone.
+    fn tone(&self) -> (f32, Duration, f32) {
+        match self {
+            Sound::Nudge => (660.0, Duration::from_millis(150), 0.3),
+            Sound::Reverse => (440.0, Duration::from_millis(90), 0.25),
+            Sound::Shuffle => (220.0, Duration::from_millis(250), 0.2),
+            Sound::Deal => (880.0, Duration::from_millis(40), 0.15),
+        }
+    }
+}
+
+/// Event for playing a sound effect.
+#[derive(Event)]
+pub struct PlaySound(pub Sound);
+
+/// Plays requested sound effects.
+fn handle_play_sound(
+    mut events: EventReader<PlaySound>,
+    mut pitches: ResMut<Assets<Pitch>>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    for PlaySound(sound) in events.read() {
+        let (frequency, duration, volume) = sound.tone();
+        commands.spawn(PitchBundle {
+            source: pitches.add(Pitch::new(frequency, duration)),
+            settings: PlaybackSettings::DESPAWN
+                .with_volume(Volume::new_relative(volume * settings.volume)),
+        });
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySound>().add_systems(
+            Update,
+            handle_play_sound.run_if(resource_exists::<Settings>()),
+        );
+    }
+}
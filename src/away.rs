@@ -0,0 +1,209 @@
+//! Pausing games against bots while the window is in the background.
+//!
+//! Nobody else is waiting on us in a challenge, so when the window loses focus (which it does
+//! when it's minimized or hidden) the game's clock stops along with the bots. Once we're back, a
+//! short countdown plays before they carry on. Bevy doesn't tell us when the window is covered,
+//! so losing focus stands in for it.
+
+use crate::{challenge::ActiveChallenge, despawn_screen, settings::Settings, ScreenState};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    window::WindowFocused,
+};
+
+/// How long (in seconds) the countdown runs before the game carries on.
+const RESUME_COUNTDOWN: f32 = 3.0;
+
+/// Whether the game is paused because we stepped away.
+#[derive(Resource, Default)]
+pub struct Away {
+    /// Whether the window is in the background.
+    hidden: bool,
+    /// Counts down once we're back, until the game carries on.
+    countdown: Option<Timer>,
+}
+
+impl Away {
+    pub fn is_paused(&self) -> bool {
+        self.hidden || self.countdown.is_some()
+    }
+}
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+struct OnScreen;
+
+/// Text on the pause banner.
+#[derive(Component)]
+struct BannerText;
+
+/// Stops the game's clock when the window goes into the background during a challenge, and
+/// starts the countdown when it comes back.
+fn handle_focus(
+    mut focus_events: EventReader<WindowFocused>,
+    mut away: ResMut<Away>,
+    mut time: ResMut<Time<Virtual>>,
+    challenge: Res<ActiveChallenge>,
+) {
+    for WindowFocused { focused, .. } in focus_events.read() {
+        if !focused && challenge.is_active() {
+            away.hidden = true;
+            away.countdown = None;
+            time.pause();
+        } else if *focused && away.hidden {
+            away.hidden = false;
+            away.countdown = Some(Timer::from_seconds(RESUME_COUNTDOWN, TimerMode::Once));
+        }
+    }
+}
+
+/// Carries on with the game once the countdown is over.
+///
+/// The game's clock is stopped, so the countdown goes by real time.
+fn count_down(mut away: ResMut<Away>, mut time: ResMut<Time<Virtual>>, real: Res<Time<Real>>) {
+    let Some(countdown) = away.countdown.as_mut() else { return; };
+    if countdown.tick(real.delta()).finished() {
+        away.countdown = None;
+        time.unpause();
+    }
+}
+
+/// Shows a banner while paused, with the countdown once we're back.
+fn update_banner(
+    banner: Query<Entity, With<OnScreen>>,
+    mut text: Query<&mut Text, With<BannerText>>,
+    away: Res<Away>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    if !away.is_paused() {
+        for entity in banner.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let message = match &away.countdown {
+        Some(countdown) => {
+            let remaining = countdown.remaining_secs().ceil();
+            settings.language.tr_with("Resuming in {}", remaining)
+        }
+        None => String::from(settings.language.tr("Paused")),
+    };
+    if let Ok(mut text) = text.get_single_mut() {
+        if text.sections[0].value != message {
+            text.sections[0].value = message;
+        }
+        return;
+    }
+    if !banner.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    message,
+                    TextStyle {
+                        font: asset_server.load("fonts/Lato-Black.ttf"),
+                        font_size: 48.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                BannerText,
+            ));
+        });
+}
+
+/// Starts the game's clock again when leaving the game.
+fn reset_away(mut away: ResMut<Away>, mut time: ResMut<Time<Virtual>>) {
+    *away = Away::default();
+    time.unpause();
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Away>()
+            .add_systems(
+                Update,
+                (handle_focus, count_down, update_banner)
+                    .chain()
+                    .run_if(in_state(ScreenState::Game)),
+            )
+            .add_systems(
+                OnExit(ScreenState::Game),
+                (reset_away, despawn_screen::<OnScreen>),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::GameRules;
+    use bevy::{ecs::system::RunSystemOnce, utils::Instant};
+    use std::time::Duration;
+
+    /// Ensures that only games against bots pause, and that they carry on after the countdown.
+    #[test]
+    fn test_pause_while_away() {
+        let mut world = World::new();
+        let start = Instant::now();
+        world.init_resource::<Away>();
+        world.init_resource::<ActiveChallenge>();
+        world.init_resource::<Time<Virtual>>();
+        world.insert_resource(Time::<Real>::new(start));
+        world.init_resource::<Events<WindowFocused>>();
+        let focus = |world: &mut World, focused| {
+            world.send_event(WindowFocused {
+                window: Entity::PLACEHOLDER,
+                focused,
+            });
+            world.run_system_once(handle_focus);
+        };
+
+        // games with other players carry on without us
+        focus(&mut world, false);
+        assert!(!world.resource::<Away>().is_paused());
+
+        world.resource_mut::<ActiveChallenge>().start(0, &mut GameRules::default());
+        focus(&mut world, false);
+        assert!(world.resource::<Time<Virtual>>().is_paused());
+        focus(&mut world, true);
+        assert!(world.resource::<Away>().is_paused());
+
+        // the countdown runs on real time, since the game's clock is stopped
+        let wait = |world: &mut World, secs| {
+            let instant = start + Duration::from_secs_f32(secs);
+            world.resource_mut::<Time<Real>>().update_with_instant(instant);
+            world.run_system_once(count_down);
+        };
+        wait(&mut world, 0.0);
+        wait(&mut world, 1.0);
+        wait(&mut world, RESUME_COUNTDOWN - 0.5);
+        assert!(world.resource::<Away>().is_paused());
+        wait(&mut world, RESUME_COUNTDOWN + 0.5);
+        assert!(!world.resource::<Away>().is_paused());
+        assert!(!world.resource::<Time<Virtual>>().is_paused());
+    }
+}
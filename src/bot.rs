@@ -0,0 +1,359 @@
+//! Bots that take over the seats of players who disconnected, so the round can carry on, and
+//! that play against us in challenges.
+//!
+//! The host runs the bots, sending their moves to everyone as if the bot had made them. Every
+//! peer keeps track of the bots' cards, so whoever takes over the room can keep them playing.
+
+use crate::{
+    card::{Card, CardColor, CardPosition, CardType, SpawnCard},
+    deck::{Deck, DiscardCards, GameRng},
+    disconnect::not_paused,
+    info::GameInfo,
+    network::{OpponentPlayed, ServerState, SocketEvent},
+    profile::Stats,
+    rules::GameRules,
+    GameScreenState, GameSet, ScreenState,
+};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    utils::HashMap,
+};
+use bevy_matchbox::prelude::*;
+
+/// How long (in seconds) a bot waits before making its move.
+const THINK_TIME: f32 = 1.5;
+
+/// Cards held by each seat a bot has taken over.
+#[derive(Resource, Default)]
+pub struct Bots(pub HashMap<PeerId, Vec<Card>>);
+
+/// How often the bots slip up, set from how we've been doing when a challenge starts.
+///
+/// Bots that take over for disconnected players never make mistakes on purpose.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct BotSkill {
+    /// Chance of a bot making a mistake on its turn, from 0 to 1.
+    pub mistake_chance: f32,
+}
+
+impl BotSkill {
+    /// Mistake chance against someone who wins about half their games.
+    const EVEN_MISTAKES: f32 = 0.15;
+    /// Most the bots will slip up, however badly we've been doing.
+    const MOST_MISTAKES: f32 = 0.4;
+    /// Games needed before the win rate counts.
+    const MIN_GAMES: u32 = 3;
+
+    /// Returns the skill that keeps games close against a player with these stats.
+    ///
+    /// Bots make fewer mistakes the more we win, and our current streak pushes them further, so
+    /// a run of wins or losses is answered sooner than the overall win rate would.
+    pub fn adapted_to(stats: &Stats) -> Self {
+        if stats.games < Self::MIN_GAMES {
+            return Self {
+                mistake_chance: Self::EVEN_MISTAKES,
+            };
+        }
+        let win_rate = stats.wins as f32 / stats.games as f32;
+        let mistake_chance =
+            Self::EVEN_MISTAKES + (0.5 - win_rate) * 0.4 - stats.streak as f32 * 0.05;
+        Self {
+            mistake_chance: mistake_chance.clamp(0.0, Self::MOST_MISTAKES),
+        }
+    }
+}
+
+/// Packets waiting to be sent on behalf of the bots, which are handled by the host as well.
+#[derive(Resource, Default)]
+pub struct BotMoves(pub Vec<(PeerId, Vec<u8>)>);
+
+/// Returns how much the bot wants to keep a card, based on the rest of its hand.
+///
+/// Wilds are saved for when nothing else can be played, and otherwise cards of the color the bot
+/// holds the most of are worth more, since that color can be followed for longest.
+fn keep_score(hand: &[Card], card: &Card) -> (bool, usize) {
+    let same_color = hand.iter().filter(|held| held.color == card.color).count();
+    (card.color == CardColor::Wild, same_color)
+}
+
+/// Returns the cards the bot plays this turn, or nothing if it has to draw.
+///
+/// The bot plays the card it least wants to keep, along with any others of the same value if
+/// the rules allow it, holding one back if it isn't allowed to go out on them. A careless bot
+/// plays the card it most wants to keep instead, such as a wild it could have saved.
+fn choose_play(
+    hand: &[Card],
+    top_card: Option<&Card>,
+    game_info: &GameInfo,
+    rules: &GameRules,
+    careless: bool,
+) -> Vec<Card> {
+    let playable = hand
+        .iter()
+        .filter(|card| game_info.can_play_together(&[**card], hand.len(), top_card, rules));
+    let first = if careless {
+        playable.max_by_key(|card| keep_score(hand, card))
+    } else {
+        playable.min_by_key(|card| keep_score(hand, card))
+    };
+    let Some(first) = first.copied() else { return Vec::new(); };
+    let mut cards = vec![first];
+    if rules.play_multiple && first.color != CardColor::Wild {
+        cards.extend(hand.iter().filter(|card| {
+            **card != first && card.value == first.value && card.color != CardColor::Wild
+        }));
+        if !game_info.can_play_together(&cards, hand.len(), top_card, rules) {
+            cards.pop();
+        }
+    }
+    cards
+}
+
+/// Returns the color the bot picks for a wild, which is the one it holds the most of.
+fn choose_color(hand: &[Card]) -> CardColor {
+    [
+        CardColor::Red,
+        CardColor::Yellow,
+        CardColor::Green,
+        CardColor::Blue,
+    ]
+    .into_iter()
+    .max_by_key(|color| hand.iter().filter(|card| card.color == *color).count())
+    .unwrap_or(CardColor::Red)
+}
+
+/// Returns the cards the bot puts back under the deck when it's holding too many.
+fn choose_returns(hand: &[Card], count: usize) -> Vec<Card> {
+    let mut cards = hand.to_vec();
+    cards.sort_by_key(|card| keep_score(hand, card));
+    cards.truncate(count);
+    cards
+}
+
+/// Follows the cards the bots are dealt, draw and play.
+fn track_bot_hands(
+    mut spawn_events: EventReader<SpawnCard>,
+    mut played_events: EventReader<OpponentPlayed>,
+    mut bots: ResMut<Bots>,
+    game_info: Res<GameInfo>,
+    game_rng: Res<GameRng>,
+    rules: Res<GameRules>,
+) {
+    let mut dealt = false;
+    for SpawnCard {
+        card,
+        position,
+        card_type,
+    } in spawn_events.read()
+    {
+        // bots playing from the start are dealt their hands the same way as everyone else
+        if matches!(position, CardPosition::Deal) && !dealt {
+            dealt = true;
+            let deck = Deck::shuffled(&rules, &mut GameRng::new(game_rng.seed()));
+            for (player, hand) in bots.0.iter_mut() {
+                let order = &game_info.order;
+                let Some(seat) = order.iter().position(|pid| pid == player) else { continue; };
+                *hand = deck.clone().deal(order.len(), seat, rules.hand_size.cards());
+            }
+        }
+        let CardType::Opponent(player) = card_type else { continue; };
+        if let Some(hand) = bots.0.get_mut(player) {
+            hand.push(*card);
+        }
+    }
+    for OpponentPlayed { player, cards } in played_events.read() {
+        let Some(hand) = bots.0.get_mut(player) else { continue; };
+        for card in cards {
+            if let Some(index) = hand.iter().position(|held| held == card) {
+                hand.remove(index);
+            }
+        }
+    }
+    // a bot's seat only lasts until it's left out of the order
+    bots.0.retain(|player, _| game_info.order.contains(player));
+}
+
+/// Makes moves for the bots when it's their turn, if we're hosting.
+fn play_for_bots(
+    mut bots: ResMut<Bots>,
+    mut moves: ResMut<BotMoves>,
+    mut thinking: Local<(u32, Option<Card>, f32)>,
+    mut game_rng: ResMut<GameRng>,
+    game_info: Res<GameInfo>,
+    discard_pile: Res<DiscardCards>,
+    rules: Res<GameRules>,
+    skill: Res<BotSkill>,
+    server_state: Res<State<ServerState>>,
+    game_screen_state: Res<State<GameScreenState>>,
+    time: Res<Time>,
+) {
+    let ServerState::Server(_) = **server_state else { return; };
+    if matches!(
+        **game_screen_state,
+        GameScreenState::Win | GameScreenState::Champion
+    ) {
+        return;
+    }
+
+    // bots holding too many put the extra cards back once their turn is over
+    for (player, hand) in bots.0.iter_mut() {
+        let over = rules.cards_over_cap(hand.len());
+        if over == 0 || game_info.current_player == Some(*player) {
+            continue;
+        }
+        let returned = choose_returns(hand, over);
+        hand.retain(|card| !returned.contains(card));
+        let mut packet = vec![SocketEvent::ReturnCards.into(), returned.len() as u8];
+        packet.extend(returned.iter().map(|card| -> u8 { (*card).into() }));
+        moves.0.push((*player, packet));
+    }
+
+    let Some(player) = game_info.current_player else { return; };
+    let Some(hand) = bots.0.get(&player) else { return; };
+    // wait for whoever played a wild to pick its color
+    let top_card = discard_pile.cards.last();
+    if top_card.is_some_and(|card| card.color == CardColor::Wild) {
+        return;
+    }
+    // drawing a card that can be played gives the bot another move
+    if thinking.0 != game_info.turn || thinking.1 != game_info.drawn_card {
+        *thinking = (game_info.turn, game_info.drawn_card, 0.0);
+    }
+    thinking.2 += time.delta_seconds();
+    if thinking.2 < THINK_TIME {
+        return;
+    }
+    // only one move at a time, while it's on its way
+    thinking.2 = f32::NEG_INFINITY;
+
+    // a careless bot plays a card it should have kept and forgets to call one
+    let careless = skill.mistake_chance > 0.0 && game_rng.chance(skill.mistake_chance);
+    // a card the bot drew is always played if it can be
+    let cards = match game_info.drawn_card {
+        Some(card) => vec![card],
+        None => choose_play(hand, top_card, &game_info, &rules, careless),
+    };
+    let Some(first) = cards.first() else {
+        moves.0.push((player, vec![SocketEvent::Draw.into()]));
+        return;
+    };
+    let packet = if let [card] = cards.as_slice() {
+        vec![SocketEvent::Play.into(), (*card).into()]
+    } else {
+        let mut packet = vec![SocketEvent::PlayMulti.into(), cards.len() as u8];
+        packet.extend(cards.iter().map(|card| -> u8 { (*card).into() }));
+        packet
+    };
+    moves.0.push((player, packet));
+    if first.color == CardColor::Wild {
+        let color = choose_color(hand);
+        moves.0.push((player, vec![SocketEvent::Wild.into(), color.into()]));
+    }
+    if hand.len() == cards.len() + 1 && !careless {
+        moves.0.push((player, vec![SocketEvent::CallOne.into()]));
+    }
+}
+
+/// Forgets the bots when leaving the game.
+fn reset_bots(mut bots: ResMut<Bots>, mut moves: ResMut<BotMoves>, mut skill: ResMut<BotSkill>) {
+    bots.0.clear();
+    moves.0.clear();
+    *skill = BotSkill::default();
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bots>()
+            .init_resource::<BotMoves>()
+            .init_resource::<BotSkill>()
+            .add_systems(
+                Update,
+                (track_bot_hands, play_for_bots.run_if(not_paused))
+                    .chain()
+                    .in_set(GameSet::Rules)
+                    .run_if(in_state(ScreenState::Game)),
+            )
+            .add_systems(OnExit(ScreenState::Game), reset_bots);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardValue;
+
+    /// Ensures that bots hold onto wilds and follow the color they have the most of.
+    #[test]
+    fn test_choose_play() {
+        let game_info = GameInfo::from_world(&mut World::new());
+        let mut rules = GameRules::default();
+        let top = Card::new(CardColor::Red, CardValue::Five, 0);
+        let wild = Card::new(CardColor::Wild, CardValue::Seven, 0);
+        let red = Card::new(CardColor::Red, CardValue::Two, 0);
+        let blue = Card::new(CardColor::Blue, CardValue::Five, 0);
+        let other_blue = Card::new(CardColor::Blue, CardValue::Nine, 0);
+        let green = Card::new(CardColor::Green, CardValue::Five, 0);
+
+        // red is played before the wild, keeping the blues together
+        let hand = [wild, red, blue, other_blue];
+        assert_eq!(choose_play(&hand, Some(&top), &game_info, &rules, false), vec![red]);
+        assert_eq!(choose_play(&[wild], Some(&top), &game_info, &rules, false), vec![wild]);
+        assert!(choose_play(&[other_blue], Some(&top), &game_info, &rules, false).is_empty());
+
+        // fives are played together when the rules allow it
+        rules.play_multiple = true;
+        let hand = [green, blue, other_blue];
+        assert_eq!(
+            choose_play(&hand, Some(&top), &game_info, &rules, false),
+            vec![green, blue]
+        );
+
+        // a skip can't be the last card out with the house rule
+        rules.no_action_finish = true;
+        let skip = Card::new(CardColor::Red, CardValue::Skip, 0);
+        let other_skip = Card::new(CardColor::Green, CardValue::Skip, 0);
+        assert!(choose_play(&[skip], Some(&top), &game_info, &rules, false).is_empty());
+        assert_eq!(
+            choose_play(&[skip, other_skip], Some(&top), &game_info, &rules, false),
+            vec![skip]
+        );
+
+        // a careless bot gives up the wild it was saving
+        rules = GameRules::default();
+        let hand = [wild, red, blue, other_blue];
+        assert_eq!(
+            choose_play(&hand, Some(&top), &game_info, &rules, true),
+            vec![wild]
+        );
+
+        assert_eq!(choose_color(&[wild, blue, other_blue, red]), CardColor::Blue);
+        assert_eq!(choose_returns(&[wild, blue, other_blue, red], 1), vec![red]);
+    }
+
+    /// Ensures that bots slip up less against players who keep winning.
+    #[test]
+    fn test_adapted_skill() {
+        let stats = |games, wins, streak| Stats {
+            games,
+            wins,
+            streak,
+            best_streak: 0,
+        };
+        let new = BotSkill::adapted_to(&Stats::default());
+        let even = BotSkill::adapted_to(&stats(10, 5, 0));
+        let winning = BotSkill::adapted_to(&stats(10, 8, 3));
+        let losing = BotSkill::adapted_to(&stats(10, 2, -3));
+        assert_eq!(new, even);
+        assert!(winning.mistake_chance < even.mistake_chance);
+        assert!(losing.mistake_chance > even.mistake_chance);
+
+        // however lopsided the record, the chance stays within bounds
+        assert_eq!(BotSkill::adapted_to(&stats(50, 50, 50)).mistake_chance, 0.0);
+        assert_eq!(
+            BotSkill::adapted_to(&stats(50, 0, -50)).mistake_chance,
+            BotSkill::MOST_MISTAKES
+        );
+    }
+}
@@ -1,8 +1,14 @@
 //! Button handling.
 
-use crate::card::CARD_ANIMATION_SPEED;
+use crate::animation::{Easing, Tween};
 use bevy::prelude::{Plugin as BevyPlugin, *};
 
+/// How long (in seconds) buttons take to grow or shrink.
+const BUTTON_ANIMATION_TIME: f32 = 0.15;
+/// Size of buttons being hovered over or pressed.
+const HOVERED_SCALE: f32 = 1.05;
+const PRESSED_SCALE: f32 = 0.95;
+
 /// Indicates whether a button is enabled or not.
 ///
 /// If a button is disabled, it won't respond to interactions.
@@ -61,61 +67,23 @@ fn button_system(
     }
 }
 
-/// Resizes button to the normal size.
-fn animate_button_default(
-    mut buttons: Query<&mut Transform, (With<Button>, Without<Hovered>, Without<Pressed>)>,
-    time: Res<Time>,
-) {
-    let card_speed = CARD_ANIMATION_SPEED * time.delta_seconds();
-    let target = 1.0;
-
-    for mut transform in &mut buttons {
-        let current = transform.scale.x;
-        let distance = target - current;
-        if distance.abs() < 0.01 {
-            continue;
-        }
-        transform.scale.x += distance * card_speed;
-        transform.scale.y += distance * card_speed;
-    }
-}
-
-/// Scales up buttons that are being hovered over.
-fn animate_button_hover(
-    // hand: Query<&GlobalTransform, With<PlayerHand>>,
-    mut buttons: Query<&mut Transform, (With<Button>, With<Hovered>)>,
-    time: Res<Time>,
+/// Grows buttons that are being hovered over and shrinks ones being pressed.
+fn animate_buttons(
+    mut buttons: Query<(&Interaction, &mut Tween), Changed<Interaction>>,
+    new_buttons: Query<Entity, (With<Button>, Without<Tween>)>,
+    mut commands: Commands,
 ) {
-    let card_speed = CARD_ANIMATION_SPEED * time.delta_seconds();
-    let target = 1.05;
-
-    for mut transform in &mut buttons {
-        let current = transform.scale.x;
-        let distance = target - current;
-        if distance.abs() < 0.01 {
-            continue;
-        }
-        transform.scale.x += distance * card_speed;
-        transform.scale.y += distance * card_speed;
+    for entity in &new_buttons {
+        let tween = Tween::scale(Vec3::ONE, BUTTON_ANIMATION_TIME, Easing::EaseInOut);
+        commands.entity(entity).insert(tween);
     }
-}
-
-/// Scales down buttons that are being pressed.
-fn animate_button_press(
-    mut buttons: Query<&mut Transform, (With<Button>, With<Pressed>)>,
-    time: Res<Time>,
-) {
-    let card_speed = CARD_ANIMATION_SPEED * time.delta_seconds();
-    let target = 0.95;
-
-    for mut transform in &mut buttons {
-        let current = transform.scale.x;
-        let distance = target - current;
-        if distance.abs() < 0.01 {
-            continue;
-        }
-        transform.scale.x += distance * card_speed;
-        transform.scale.y += distance * card_speed;
+    for (interaction, mut tween) in &mut buttons {
+        let scale = match interaction {
+            Interaction::Pressed => PRESSED_SCALE,
+            Interaction::Hovered => HOVERED_SCALE,
+            Interaction::None => 1.0,
+        };
+        tween.retarget(Transform::from_scale(Vec3::new(scale, scale, 1.0)));
     }
 }
 
@@ -123,14 +91,6 @@ pub struct Plugin;
 
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                button_system,
-                animate_button_default,
-                animate_button_hover,
-                animate_button_press,
-            ),
-        );
+        app.add_systems(Update, (button_system, animate_buttons));
     }
 }
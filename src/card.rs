@@ -1,29 +1,68 @@
-//! Card struct and spawn handling.
+//! Card struct, spawn handling, and the flip and wobble effects played on cards.
 
+use crate::animation::{self, Easing, Tween};
+use crate::audio::{PlaySound, Sound};
+use crate::card_art::CardFaces;
+use crate::deck::{DeckComposition, DiscardCards, HandCardId};
 use crate::game_ui::board::OnScreen;
 use crate::game_ui::hand::HandCard;
+use crate::game_ui::opponent::OpponentCard;
+use crate::pointer::Clickable;
 use crate::{
-    game_ui::board::{DiscardCard, DISCARD_PILE_POS, DRAW_PILE_POS, HAND_POS},
-    ScreenState,
+    game_ui::board::{DiscardCard, DISCARD_PILE_POS, DRAW_PILE_POS},
+    layout::Layout,
+    settings::Settings,
+    theme::{Theme, CARD_SHADOW_OFFSET},
+    toast::Toast,
+    GameSet, ScreenState,
 };
-use bevy::prelude::{Plugin as BevyPlugin, *};
+use bevy::{
+    asset::LoadState,
+    prelude::{Plugin as BevyPlugin, *},
+    transform::TransformSystem,
+};
+use bevy_matchbox::prelude::PeerId;
+use std::{collections::VecDeque, f32::consts::PI};
 
 pub const CARD_SIZE: Vec2 = Vec2::new(156.0, 204.0);
-pub const CARD_ANIMATION_SPEED: f32 = 7.0;
+/// How long (in seconds) cards take to move to a new spot.
+pub const CARD_ANIMATION_TIME: f32 = 0.4;
+/// Most cards spawned in a single frame, so big deals don't cause a hitch.
+const MAX_SPAWNS_PER_FRAME: usize = 4;
+/// Texture showing the back of a card, which is also used for the draw pile.
+const CARD_BACK_TEXTURE: &str = "textures/drawpile.png";
+/// How long (in seconds) a drawn card takes to turn over.
+const FLIP_DURATION: f32 = 0.4;
+/// How long (in seconds) a card wobbles after landing on the discard pile.
+const WOBBLE_DURATION: f32 = 0.35;
+/// Furthest (in radians) a landing card tilts, and how much bigger it gets.
+const WOBBLE_ANGLE: f32 = 0.08;
+const WOBBLE_SCALE: f32 = 0.08;
+/// Byte the wilds start from when sending cards, after every copy of the colored cards that can
+/// be in play with the most decks.
+const WILD_BYTE: u8 = 52 * DeckComposition::MAX_COPIES * DeckComposition::MAX_DECKS;
+/// Size of the badge showing a card's color symbol.
+const SYMBOL_BADGE_SIZE: Vec2 = Vec2::new(30.0, 30.0);
+/// Position of the symbol badge relative to the center of the card, below the corner value.
+const SYMBOL_BADGE_OFFSET: Vec3 = Vec3::new(-53.0, 32.0, 0.001);
 
 /// Card sprite component.
 #[derive(Component)]
 pub struct CardSprite(pub Card);
 
 /// Place where card should be.
+#[derive(Clone, Copy)]
 pub enum CardType {
-    /// Local player's hand.
-    Hand,
-    /// Discard pile.
-    Discard,
+    /// Local player's hand, showing the card with the given id.
+    Hand(HandCardId),
+    /// Discard pile, at the given index counting from the bottom.
+    Discard(usize),
+    /// An opponent's hand, which the card flies to face down.
+    Opponent(PeerId),
 }
 
 /// Location for card to spawn in.
+#[derive(Clone, Copy)]
 pub enum CardPosition {
     /// Hand position.
     Hand,
@@ -33,18 +72,107 @@ pub enum CardPosition {
     OpponentDiscard(usize),
     /// Discard pile position.
     Discard(usize),
+    /// Dealt from the draw pile into the hand, one at a time at the host's deal speed.
+    Deal,
     // Custom position.
     // Custom(Vec3),
 }
 
 /// Event for spawning a card.
-#[derive(Event)]
+#[derive(Event, Clone, Copy)]
 pub struct SpawnCard {
     pub card: Card,
     pub position: CardPosition,
     pub card_type: CardType,
 }
 
+/// Cards waiting to be spawned, in the order they were requested.
+#[derive(Resource, Default)]
+pub struct SpawnQueue(VecDeque<SpawnCard>);
+
+/// How fast the opening hand is dealt, picked by the host in the lobby.
+///
+/// This is sent to peers in the start game packet, so the deal takes the same time for everyone
+/// and the first turn starts together.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DealSpeed {
+    /// The whole hand is put down at once.
+    Instant,
+    #[default]
+    Fast,
+    /// Slow enough to watch each card turn over.
+    Cinematic,
+}
+
+impl DealSpeed {
+    /// All deal speeds, in the order they're cycled through in the lobby.
+    pub const ALL: [DealSpeed; 3] = [DealSpeed::Instant, DealSpeed::Fast, DealSpeed::Cinematic];
+
+    /// Returns the deal speed after this one.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|s| s == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Name shown in the lobby.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DealSpeed::Instant => "Instant",
+            DealSpeed::Fast => "Fast",
+            DealSpeed::Cinematic => "Cinematic",
+        }
+    }
+
+    /// Returns the time (in seconds) between cards being dealt, or `None` if they're all put
+    /// down at once.
+    fn interval(&self) -> Option<f32> {
+        match self {
+            DealSpeed::Instant => None,
+            DealSpeed::Fast => Some(0.1),
+            DealSpeed::Cinematic => Some(0.35),
+        }
+    }
+
+    /// Returns how long (in seconds) dealing a hand of `cards` takes, until the last one lands.
+    pub fn deal_length(&self, cards: usize) -> f32 {
+        match self.interval() {
+            Some(interval) => interval * cards as f32 + CARD_ANIMATION_TIME,
+            None => 0.0,
+        }
+    }
+}
+
+impl Into<u8> for DealSpeed {
+    fn into(self) -> u8 {
+        match self {
+            DealSpeed::Instant => 0,
+            DealSpeed::Fast => 1,
+            DealSpeed::Cinematic => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for DealSpeed {
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Instant),
+            1 => Ok(Self::Fast),
+            2 => Ok(Self::Cinematic),
+            _ => Err(()),
+        }
+    }
+
+    type Error = ();
+}
+
+/// Cards still to be dealt into our hand.
+#[derive(Resource, Default)]
+pub struct DealQueue {
+    cards: VecDeque<SpawnCard>,
+    /// Time until the next card is dealt, running while a deal is under way.
+    timer: Option<Timer>,
+}
+
 /// Card color.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CardColor {
@@ -55,6 +183,32 @@ pub enum CardColor {
     Wild,
 }
 
+impl CardColor {
+    /// Returns a letter identifying the color without relying on the color itself.
+    pub fn symbol(&self) -> Option<&'static str> {
+        match self {
+            CardColor::Red => Some("R"),
+            CardColor::Yellow => Some("Y"),
+            CardColor::Green => Some("G"),
+            CardColor::Blue => Some("B"),
+            CardColor::Wild => None,
+        }
+    }
+}
+
+impl CardColor {
+    /// Returns the color used to draw cards of this color without their texture.
+    pub fn display_color(&self) -> Color {
+        match self {
+            CardColor::Red => Color::rgb(0.83, 0.25, 0.33),
+            CardColor::Yellow => Color::rgb(0.93, 0.72, 0.2),
+            CardColor::Green => Color::rgb(0.25, 0.65, 0.4),
+            CardColor::Blue => Color::rgb(0.23, 0.45, 0.8),
+            CardColor::Wild => Color::rgb(0.15, 0.15, 0.18),
+        }
+    }
+}
+
 impl Into<u8> for CardColor {
     fn into(self) -> u8 {
         match self {
@@ -98,6 +252,27 @@ pub enum CardValue {
     DrawTwo,
 }
 
+impl CardValue {
+    /// Returns the label for the value shown in menus.
+    pub fn label(&self) -> &'static str {
+        match self {
+            CardValue::Zero => "0",
+            CardValue::One => "1",
+            CardValue::Two => "2",
+            CardValue::Three => "3",
+            CardValue::Four => "4",
+            CardValue::Five => "5",
+            CardValue::Six => "6",
+            CardValue::Seven => "7",
+            CardValue::Eight => "8",
+            CardValue::Nine => "9",
+            CardValue::Skip => "Skip",
+            CardValue::Reverse => "Reverse",
+            CardValue::DrawTwo => "+2",
+        }
+    }
+}
+
 /// Card struct.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Card {
@@ -109,7 +284,7 @@ pub struct Card {
 
 impl Card {
     /// Creates a new card with the given color, value, and iteration.
-    pub fn new(color: CardColor, value: CardValue, iteration: u8) -> Self {
+    pub const fn new(color: CardColor, value: CardValue, iteration: u8) -> Self {
         Self {
             color,
             value,
@@ -127,42 +302,17 @@ impl Card {
             && card.color != CardColor::Wild
     }
 
-    // pub fn text(&self, font: Handle<Font>) -> Text2dBundle {
-    //     Text2dBundle {
-    //         text: Text::from_section(
-    //             match self.value {
-    //                 CardValue::Zero => "0",
-    //                 CardValue::One => "1",
-    //                 CardValue::Two => "2",
-    //                 CardValue::Three => "3",
-    //                 CardValue::Four => "4",
-    //                 CardValue::Five => "5",
-    //                 CardValue::Six => "6",
-    //                 CardValue::Seven => "7",
-    //                 CardValue::Eight => "8",
-    //                 CardValue::Nine => "9",
-    //                 CardValue::Skip => "Skip",
-    //                 CardValue::Reverse => "Reverse",
-    //                 CardValue::DrawTwo => "+2",
-    //             },
-    //             TextStyle {
-    //                 font,
-    //                 font_size: 40.0,
-    //                 color: Color::WHITE,
-    //             },
-    //         )
-    //         .with_alignment(TextAlignment::Center),
-    //         transform: Transform::from_xyz(
-    //             -CARD_SIZE.x / 2.0 + 20.0,
-    //             CARD_SIZE.y / 2.0 - 20.0,
-    //             0.0005,
-    //         ),
-    //         ..default()
-    //     }
-    // }
+    /// Returns a number identifying the physical card, ignoring any color chosen for a wild.
+    pub fn id(&self) -> u8 {
+        if self.value == CardValue::Seven {
+            WILD_BYTE + self.iteration
+        } else {
+            (*self).into()
+        }
+    }
 
-    /// Returns a sprite bundle for the card.
-    pub fn sprite(&self, position: Vec3, asset_server: &Res<AssetServer>) -> SpriteBundle {
+    /// Returns the path to the card's texture.
+    pub fn texture_path(&self) -> String {
         let file_name = {
             let value = match self.value {
                 CardValue::Zero => "0",
@@ -188,6 +338,44 @@ impl Card {
             };
             format!("{}{}", color, value)
         };
+        format!("textures/cards/{file_name}.png")
+    }
+
+    /// Spawns a badge with the card's color symbol as a child of the card sprite.
+    pub fn spawn_symbol(&self, parent: &mut ChildBuilder, asset_server: &Res<AssetServer>) {
+        let Some(symbol) = self.color.symbol() else { return; };
+        parent
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(SYMBOL_BADGE_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_translation(SYMBOL_BADGE_OFFSET),
+                ..default()
+            })
+            .with_children(|parent| {
+                parent.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        symbol,
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-Black.ttf"),
+                            font_size: 24.0,
+                            color: Color::BLACK,
+                        },
+                    ),
+                    transform: Transform::from_xyz(0.0, 0.0, 0.001),
+                    ..default()
+                });
+            });
+    }
+
+    /// Returns a sprite bundle for the card.
+    pub fn sprite(
+        &self,
+        position: Vec3,
+        faces: &CardFaces,
+        asset_server: &AssetServer,
+    ) -> SpriteBundle {
         SpriteBundle {
             sprite: Sprite {
                 // color: match self.color {
@@ -199,7 +387,7 @@ impl Card {
                 custom_size: Some(CARD_SIZE),
                 ..default()
             },
-            texture: asset_server.load(format!("textures/cards/{file_name}.png")),
+            texture: faces.texture(self, asset_server),
             transform: Transform::from_translation(position),
             ..default()
         }
@@ -207,14 +395,15 @@ impl Card {
 }
 
 impl Into<u8> for Card {
-    // returns a number from 0 to 103
+    // colored cards are `copy * 52 + index`, where `index` is `color * 13 + value` and `copy`
+    // counts from zero, and wilds are `WILD_BYTE` plus their iteration
     fn into(self) -> u8 {
         let color = match self.color {
             CardColor::Red => 0,
             CardColor::Yellow => 1,
             CardColor::Green => 2,
             CardColor::Blue => 3,
-            CardColor::Wild => return 104 + self.iteration,
+            CardColor::Wild => return WILD_BYTE + self.iteration,
         };
         let value = match self.value {
             CardValue::Zero => 0,
@@ -237,18 +426,14 @@ impl Into<u8> for Card {
 
 impl From<u8> for Card {
     fn from(value: u8) -> Self {
-        if value >= 104 {
+        if value >= WILD_BYTE {
             return Self {
                 color: CardColor::Wild,
                 value: CardValue::Seven,
-                iteration: value - 104,
+                iteration: value - WILD_BYTE,
             };
         }
-        let (value, iteration) = if value <= 51 {
-            (value, 1)
-        } else {
-            (value - 52, 2)
-        };
+        let (value, iteration) = (value % 52, value / 52 + 1);
         let color = match value / 13 {
             0 => CardColor::Red,
             1 => CardColor::Yellow,
@@ -276,46 +461,305 @@ impl From<u8> for Card {
     }
 }
 
-/// Recieves card spawn events and spawns cards.
+/// A short effect played on a card on top of its movement, removed once it's done.
+#[derive(Component)]
+pub struct CardTween {
+    effect: TweenEffect,
+    timer: Timer,
+}
+
+/// Effects that can be played on a card.
+enum TweenEffect {
+    /// Turns the card over from its back, showing its face halfway through.
+    Flip { face: Option<Handle<Image>> },
+    /// Tilts and grows the card a little, then settles it back down.
+    Wobble,
+}
+
+impl CardTween {
+    /// Turns a card showing its back over to the given face.
+    pub fn flip(face: Handle<Image>) -> Self {
+        Self {
+            effect: TweenEffect::Flip { face: Some(face) },
+            timer: Timer::from_seconds(FLIP_DURATION, TimerMode::Once),
+        }
+    }
+
+    /// Wobbles a card as it lands.
+    pub fn wobble() -> Self {
+        Self {
+            effect: TweenEffect::Wobble,
+            timer: Timer::from_seconds(WOBBLE_DURATION, TimerMode::Once),
+        }
+    }
+
+    /// Returns the card's scale and tilt (in radians) at a point (0 to 1) through the effect.
+    fn sample(&self, progress: f32) -> (Vec3, f32) {
+        match self.effect {
+            // narrow to nothing, then widen again with the other side showing
+            TweenEffect::Flip { .. } => (Vec3::new((PI * progress).cos().abs(), 1.0, 1.0), 0.0),
+            TweenEffect::Wobble => {
+                let settle = 1.0 - progress;
+                let tilt = WOBBLE_ANGLE * (2.0 * PI * progress).sin() * settle;
+                let scale = 1.0 + WOBBLE_SCALE * (PI * progress).sin();
+                (Vec3::new(scale, scale, 1.0), tilt)
+            }
+        }
+    }
+}
+
+/// Parent of the parts of a card's face that are hidden while the card shows its back.
+#[derive(Component)]
+struct HiddenFace;
+
+/// Recieves card spawn events and spawns a few of the queued cards each frame.
+///
+/// Cards that are spawned later start their animation later, so they fly in one after another.
 fn handle_spawn_card(
     mut events: EventReader<SpawnCard>,
+    mut queue: ResMut<SpawnQueue>,
+    mut deal: ResMut<DealQueue>,
+    discard_pile: Res<DiscardCards>,
     asset_server: Res<AssetServer>,
+    faces: Res<CardFaces>,
+    theme: Res<Theme>,
+    settings: Res<Settings>,
+    layout: Res<Layout>,
     mut commands: Commands,
 ) {
     for event in events.read() {
+        match event.position {
+            CardPosition::Deal => deal.cards.push_back(*event),
+            _ => queue.0.push_back(*event),
+        }
+    }
+    let count = queue.0.len().min(MAX_SPAWNS_PER_FRAME);
+    for event in queue.0.drain(..count) {
         let position = match event.position {
             CardPosition::Draw => DRAW_PILE_POS,
             CardPosition::OpponentDiscard(count) => {
-                Vec3::new(0.0, -300.0, (count + 1) as f32 * 0.01)
+                Vec3::new(0.0, -300.0, discard_pile.depth(count))
             }
             CardPosition::Discard(count) => {
                 let mut position = DISCARD_PILE_POS;
-                position.z = (count + 1) as f32 * 0.01;
+                position.z = discard_pile.depth(count);
                 position
             }
-            CardPosition::Hand => HAND_POS,
+            CardPosition::Hand => layout.hand_position(),
+            // dealt cards are moved into the spawn queue as they're dealt
+            CardPosition::Deal => continue,
             // CardPosition::Custom(pos) => pos,
         };
+        // cards drawn into the hand start face down and turn over on the way
+        let flip = matches!(event.position, CardPosition::Draw)
+            && matches!(event.card_type, CardType::Hand(_))
+            && !settings.reduce_motion;
+        // opponents' cards stay face down the whole way
+        let face_down = flip || matches!(event.card_type, CardType::Opponent(_));
+        let mut sprite = event.card.sprite(position, &faces, &asset_server);
+        let mut tween = None;
+        if face_down {
+            let face = std::mem::replace(&mut sprite.texture, asset_server.load(CARD_BACK_TEXTURE));
+            sprite.sprite.color = settings.card_back.tint();
+            if flip {
+                tween = Some(CardTween::flip(face));
+            }
+        }
+
         let mut entity = commands.spawn((
-            event.card.sprite(position, &asset_server),
+            sprite,
             CardSprite(event.card),
+            Tween::placement(position, Quat::IDENTITY, CARD_ANIMATION_TIME, Easing::EaseOut),
             OnScreen,
         ));
+        entity.with_children(|parent| {
+            parent.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: theme.card_shadow,
+                    custom_size: Some(CARD_SIZE * 1.15),
+                    ..default()
+                },
+                texture: asset_server.load("textures/table/shadow.png"),
+                transform: Transform::from_translation(CARD_SHADOW_OFFSET),
+                ..default()
+            });
+            if settings.color_symbols {
+                let visibility = if face_down {
+                    Visibility::Hidden
+                } else {
+                    Visibility::Inherited
+                };
+                parent
+                    .spawn((
+                        SpatialBundle {
+                            visibility,
+                            ..default()
+                        },
+                        HiddenFace,
+                    ))
+                    .with_children(|parent| event.card.spawn_symbol(parent, &asset_server));
+            }
+        });
+        if let Some(tween) = tween {
+            entity.insert(tween);
+        }
         match event.card_type {
-            CardType::Hand => entity.insert(HandCard::new(event.card)),
-            CardType::Discard => entity.insert(DiscardCard),
+            CardType::Hand(id) => {
+                entity.insert((HandCard::new(id, event.card), Clickable(CARD_SIZE)))
+            }
+            CardType::Discard(index) => entity.insert(DiscardCard(index)),
+            CardType::Opponent(id) => entity.insert(OpponentCard(id)),
         };
     }
 }
 
+/// Plays card effects, putting the card back to normal once they're done.
+///
+/// This runs after the cards have been moved, so the effects are layered on top.
+fn animate_card_tweens(
+    mut cards: Query<(
+        Entity,
+        &mut CardTween,
+        &mut Transform,
+        &mut Sprite,
+        &mut Handle<Image>,
+    )>,
+    children: Query<&Children>,
+    mut faces: Query<&mut Visibility, With<HiddenFace>>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut tween, mut transform, mut sprite, mut texture) in &mut cards {
+        tween.timer.tick(time.delta());
+        let progress = tween.timer.percent();
+        let (scale, tilt) = tween.sample(progress);
+        transform.scale = scale;
+        if tilt != 0.0 {
+            transform.rotation = Quat::from_rotation_z(tilt);
+        }
+
+        // show the face once the card is edge on
+        if let TweenEffect::Flip { face } = &mut tween.effect {
+            if progress >= 0.5 {
+                if let Some(face) = face.take() {
+                    *texture = face;
+                    sprite.color = Color::WHITE;
+                    for child in children.iter_descendants(entity) {
+                        if let Ok(mut visibility) = faces.get_mut(child) {
+                            *visibility = Visibility::Inherited;
+                        }
+                    }
+                }
+            }
+        }
+
+        if tween.timer.finished() {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<CardTween>();
+        }
+    }
+}
+
+/// Shows the drawn face of cards whose image failed to load.
+fn apply_texture_fallback(
+    mut cards: Query<(&CardSprite, &mut Handle<Image>)>,
+    mut toast_events: EventWriter<Toast>,
+    mut warned: Local<bool>,
+    faces: Res<CardFaces>,
+    asset_server: Res<AssetServer>,
+) {
+    for (CardSprite(card), mut texture) in &mut cards {
+        if asset_server.load_state(texture.id()) != LoadState::Failed {
+            continue;
+        }
+        *texture = faces.drawn(card);
+
+        if !*warned {
+            toast_events.send(Toast(String::from(
+                "Some card images are missing, so drawn cards are shown instead",
+            )));
+            *warned = true;
+        }
+    }
+}
+
+/// Drops any cards still waiting to be spawned or dealt, so they don't turn up in the next round.
+pub fn clear_queued_cards(queue: &mut SpawnQueue, deal: &mut DealQueue) {
+    queue.0.clear();
+    *deal = DealQueue::default();
+}
+
+/// Drops any cards still waiting to be spawned when leaving the game.
+fn clear_spawn_queue(mut queue: ResMut<SpawnQueue>, mut deal: ResMut<DealQueue>) {
+    clear_queued_cards(&mut queue, &mut deal);
+}
+
+/// Deals the queued cards from the draw pile one at a time, starting with a shuffle.
+fn deal_cards(
+    mut deal: ResMut<DealQueue>,
+    mut queue: ResMut<SpawnQueue>,
+    mut sound_events: EventWriter<PlaySound>,
+    deal_speed: Res<DealSpeed>,
+    time: Res<Time>,
+) {
+    if deal.cards.is_empty() {
+        if deal.timer.is_some() {
+            deal.timer = None;
+        }
+        return;
+    }
+
+    let Some(interval) = deal_speed.interval() else {
+        // put the whole hand down at once
+        sound_events.send(PlaySound(Sound::Shuffle));
+        let cards: Vec<SpawnCard> = deal.cards.drain(..).collect();
+        queue.0.extend(cards.into_iter().map(|event| SpawnCard {
+            position: CardPosition::Hand,
+            ..event
+        }));
+        return;
+    };
+
+    let deal = &mut *deal;
+    let timer = deal.timer.get_or_insert_with(|| {
+        sound_events.send(PlaySound(Sound::Shuffle));
+        Timer::from_seconds(interval, TimerMode::Repeating)
+    });
+    let count = timer.tick(time.delta()).times_finished_this_tick() as usize;
+    if count == 0 {
+        return;
+    }
+    sound_events.send(PlaySound(Sound::Deal));
+    let count = count.min(deal.cards.len());
+    queue.0.extend(deal.cards.drain(..count).map(|event| SpawnCard {
+        position: CardPosition::Draw,
+        ..event
+    }));
+}
+
 pub struct Plugin;
 
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<SpawnCard>().add_systems(
-            Update,
-            handle_spawn_card.run_if(in_state(ScreenState::Game)),
-        );
+        app.add_event::<SpawnCard>()
+            .init_resource::<SpawnQueue>()
+            .init_resource::<DealQueue>()
+            .init_resource::<DealSpeed>()
+            .add_systems(
+                Update,
+                (deal_cards, handle_spawn_card, apply_texture_fallback)
+                    .chain()
+                    .in_set(GameSet::Animate)
+                    .run_if(in_state(ScreenState::Game)),
+            )
+            .add_systems(
+                PostUpdate,
+                animate_card_tweens
+                    .after(animation::drive_tweens)
+                    .before(TransformSystem::TransformPropagate)
+                    .run_if(in_state(ScreenState::Game)),
+            )
+            .add_systems(OnExit(ScreenState::Game), clear_spawn_queue);
     }
 }
 
@@ -323,15 +767,57 @@ impl BevyPlugin for Plugin {
 mod tests {
     use super::super::deck::Deck;
     use super::*;
+    use bevy::utils::HashSet;
+
+    /// Ensures that card effects leave the card as they found it, and that a flip turns the card
+    /// edge on halfway through.
+    #[test]
+    fn test_card_tweens() {
+        for tween in [CardTween::flip(Handle::default()), CardTween::wobble()] {
+            for progress in [0.0, 1.0] {
+                let (scale, tilt) = tween.sample(progress);
+                assert!((scale - Vec3::ONE).length() < 0.001);
+                assert!(tilt.abs() < 0.001);
+            }
+        }
+        let (scale, _) = CardTween::flip(Handle::default()).sample(0.5);
+        assert!(scale.x < 0.001);
+    }
 
-    /// Ensures that all cards can be serialized and then deserialized back to themselves.
+    /// Ensures that all cards can be serialized and then deserialized back to themselves, even
+    /// in the biggest deck.
     #[test]
     fn test_card_serialization() {
-        let deck = Deck::new();
-        for card in deck.cards {
-            let serialized: u8 = card.into();
-            let deserialized = Card::from(serialized);
-            assert_eq!(card, deserialized);
+        let biggest = DeckComposition {
+            copies: [DeckComposition::MAX_COPIES; 12],
+            wilds: DeckComposition::MAX_WILDS,
+        };
+        for composition in [
+            DeckComposition::default(),
+            biggest.combined(DeckComposition::MAX_DECKS),
+        ] {
+            let deck = Deck::from_composition(&composition);
+            let mut ids = HashSet::new();
+            for card in deck.cards {
+                let serialized: u8 = card.into();
+                let deserialized = Card::from(serialized);
+                assert_eq!(card, deserialized);
+                assert!(ids.insert(card.id()));
+            }
+        }
+    }
+
+    /// Ensures that deal speeds survive being sent in a packet, and slower deals take longer.
+    #[test]
+    fn test_deal_speed() {
+        for speed in DealSpeed::ALL {
+            let byte: u8 = speed.into();
+            assert_eq!(DealSpeed::try_from(byte), Ok(speed));
         }
+        assert_eq!(DealSpeed::try_from(3), Err(()));
+
+        assert_eq!(DealSpeed::Instant.deal_length(5), 0.0);
+        assert!(DealSpeed::Cinematic.deal_length(5) > DealSpeed::Fast.deal_length(5));
+        assert!(DealSpeed::Fast.deal_length(7) > DealSpeed::Fast.deal_length(5));
     }
 }
@@ -0,0 +1,340 @@
+//! Card faces drawn when the game starts, so cards can be shown without their images.
+//!
+//! Each face is a rounded card filled with the card's color, with a white oval in the middle
+//! holding a big numeral and smaller pips in two corners. Numerals come from a small pixel font,
+//! so new values only need a new glyph, and new colors only need a display color.
+
+use crate::{
+    card::{Card, CardColor, CardValue, CARD_SIZE},
+    settings::Settings,
+};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+/// Card colors, in the order their faces are stored.
+const COLORS: [CardColor; 5] = [
+    CardColor::Red,
+    CardColor::Yellow,
+    CardColor::Green,
+    CardColor::Blue,
+    CardColor::Wild,
+];
+/// Card values, in the order they're declared, which is the order their faces are stored in.
+const VALUES: [CardValue; 13] = [
+    CardValue::Zero,
+    CardValue::One,
+    CardValue::Two,
+    CardValue::Three,
+    CardValue::Four,
+    CardValue::Five,
+    CardValue::Six,
+    CardValue::Seven,
+    CardValue::Eight,
+    CardValue::Nine,
+    CardValue::Skip,
+    CardValue::Reverse,
+    CardValue::DrawTwo,
+];
+/// Corner radius (in pixels) of the card.
+const CORNER_RADIUS: f32 = 14.0;
+/// Width (in pixels) of the white border around the card.
+const BORDER_WIDTH: f32 = 7.0;
+/// Size of the oval, as a fraction of the card's size, and how far (in radians) it's tilted.
+const OVAL_SIZE: Vec2 = Vec2::new(0.36, 0.42);
+const OVAL_TILT: f32 = 0.35;
+/// Largest cell (in pixels) of the pixel font used for the big numeral, and the widest the
+/// numeral can be as a fraction of the card's width.
+const NUMERAL_CELL: i32 = 14;
+const NUMERAL_WIDTH: f32 = 0.6;
+/// Cell size (in pixels) of the pixel font used for corner pips, and how far they're inset.
+const PIP_CELL: i32 = 3;
+const PIP_INSET: i32 = 12;
+
+/// Glyph in the pixel font, one string per row with `#` for filled cells.
+type Glyph = [&'static str; 7];
+
+const ZERO: Glyph = [
+    " ### ", "#   #", "#  ##", "# # #", "##  #", "#   #", " ### ",
+];
+const ONE: Glyph = [
+    "  #  ", " ##  ", "  #  ", "  #  ", "  #  ", "  #  ", " ### ",
+];
+const TWO: Glyph = [
+    " ### ", "#   #", "    #", "   # ", "  #  ", " #   ", "#####",
+];
+const THREE: Glyph = [
+    "#####", "   # ", "  #  ", "   # ", "    #", "#   #", " ### ",
+];
+const FOUR: Glyph = [
+    "   # ", "  ## ", " # # ", "#  # ", "#####", "   # ", "   # ",
+];
+const FIVE: Glyph = [
+    "#####", "#    ", "#### ", "    #", "    #", "#   #", " ### ",
+];
+const SIX: Glyph = [
+    "  ## ", " #   ", "#    ", "#### ", "#   #", "#   #", " ### ",
+];
+const SEVEN: Glyph = [
+    "#####", "    #", "   # ", "  #  ", " #   ", " #   ", " #   ",
+];
+const EIGHT: Glyph = [
+    " ### ", "#   #", "#   #", " ### ", "#   #", "#   #", " ### ",
+];
+const NINE: Glyph = [
+    " ### ", "#   #", "#   #", " ####", "    #", "   # ", " ##  ",
+];
+const PLUS: Glyph = [
+    "     ", "  #  ", "  #  ", "#####", "  #  ", "  #  ", "     ",
+];
+const SKIP: Glyph = [
+    "  ###  ", " #   # ", "#   # #", "#  #  #", "# #   #", " #   # ", "  ###  ",
+];
+const REVERSE: Glyph = [
+    "    #  ", "#######", "    #  ", "       ", "  #    ", "#######", "  #    ",
+];
+
+/// Returns the glyphs written on a card with the given value.
+fn glyphs(value: CardValue) -> &'static [Glyph] {
+    match value {
+        CardValue::Zero => &[ZERO],
+        CardValue::One => &[ONE],
+        CardValue::Two => &[TWO],
+        CardValue::Three => &[THREE],
+        CardValue::Four => &[FOUR],
+        CardValue::Five => &[FIVE],
+        CardValue::Six => &[SIX],
+        CardValue::Seven => &[SEVEN],
+        CardValue::Eight => &[EIGHT],
+        CardValue::Nine => &[NINE],
+        CardValue::Skip => &[SKIP],
+        CardValue::Reverse => &[REVERSE],
+        CardValue::DrawTwo => &[PLUS, TWO],
+    }
+}
+
+/// Returns the width (in cells) of glyphs written side by side, with a cell between each.
+fn text_width(glyphs: &[Glyph]) -> i32 {
+    let widths: i32 = glyphs.iter().map(|glyph| glyph[0].len() as i32).sum();
+    widths + glyphs.len().saturating_sub(1) as i32
+}
+
+/// Returns `true` if the point is inside the card inset by `inset` on each side, with its
+/// corners rounded by `radius`.
+fn in_rounded_rect(point: Vec2, size: Vec2, inset: f32, radius: f32) -> bool {
+    let half = size / 2.0 - Vec2::splat(inset);
+    let corner = (point - size / 2.0).abs() - half + Vec2::splat(radius);
+    corner.max(Vec2::ZERO).length() + corner.x.max(corner.y).min(0.0) <= radius
+}
+
+/// RGBA pixels of a card face, with the top row first.
+struct Canvas {
+    size: IVec2,
+    data: Vec<u8>,
+}
+
+impl Canvas {
+    /// Creates a transparent canvas.
+    fn new(size: IVec2) -> Self {
+        Self {
+            size,
+            data: vec![0; (size.x * size.y * 4) as usize],
+        }
+    }
+
+    /// Colors a pixel, ignoring pixels off the canvas.
+    fn set(&mut self, x: i32, y: i32, color: [u8; 4]) {
+        if x < 0 || y < 0 || x >= self.size.x || y >= self.size.y {
+            return;
+        }
+        let index = ((y * self.size.x + x) * 4) as usize;
+        self.data[index..index + 4].copy_from_slice(&color);
+    }
+
+    /// Writes glyphs side by side with their top left corner at `origin`, each cell `cell`
+    /// pixels across. Flipped text is turned upside down around the middle of the canvas.
+    fn write(&mut self, glyphs: &[Glyph], origin: IVec2, cell: i32, color: [u8; 4], flip: bool) {
+        let mut left = origin.x;
+        for glyph in glyphs {
+            for (row, line) in glyph.iter().enumerate() {
+                for (column, _) in line.chars().enumerate().filter(|(_, c)| *c == '#') {
+                    let x = left + column as i32 * cell;
+                    let y = origin.y + row as i32 * cell;
+                    for dy in 0..cell {
+                        for dx in 0..cell {
+                            let (x, y) = if flip {
+                                (self.size.x - 1 - (x + dx), self.size.y - 1 - (y + dy))
+                            } else {
+                                (x + dx, y + dy)
+                            };
+                            self.set(x, y, color);
+                        }
+                    }
+                }
+            }
+            left += (glyph[0].len() as i32 + 1) * cell;
+        }
+    }
+}
+
+/// Draws the face of a card with the given color and value.
+fn paint(color: CardColor, value: CardValue, size: IVec2) -> Canvas {
+    let mut canvas = Canvas::new(size);
+    let card_size = size.as_vec2();
+    let white = Color::WHITE.as_rgba_u8();
+    let fill = color.display_color().as_rgba_u8();
+    let oval = card_size * OVAL_SIZE;
+    let rotation = Mat2::from_angle(-OVAL_TILT);
+
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let point = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            if !in_rounded_rect(point, card_size, 0.0, CORNER_RADIUS) {
+                continue;
+            }
+            let inner_radius = CORNER_RADIUS - BORDER_WIDTH / 2.0;
+            if !in_rounded_rect(point, card_size, BORDER_WIDTH, inner_radius) {
+                canvas.set(x, y, white);
+                continue;
+            }
+            let offset = rotation * (point - card_size / 2.0);
+            let pixel = if (offset / oval).length_squared() > 1.0 {
+                fill
+            } else if color == CardColor::Wild {
+                // a quarter of the oval in each color
+                let quarter = match (offset.x < 0.0, offset.y < 0.0) {
+                    (true, true) => CardColor::Red,
+                    (false, true) => CardColor::Blue,
+                    (true, false) => CardColor::Yellow,
+                    (false, false) => CardColor::Green,
+                };
+                quarter.display_color().as_rgba_u8()
+            } else {
+                white
+            };
+            canvas.set(x, y, pixel);
+        }
+    }
+
+    let glyphs = glyphs(value);
+    let width = text_width(glyphs);
+    let max_width = (card_size.x * NUMERAL_WIDTH) as i32;
+    let cell = NUMERAL_CELL.min(max_width / width);
+    let origin = (size - IVec2::new(width, 7) * cell) / 2;
+    canvas.write(glyphs, origin, cell, fill, false);
+
+    for flip in [false, true] {
+        canvas.write(glyphs, IVec2::splat(PIP_INSET), PIP_CELL, white, flip);
+    }
+    canvas
+}
+
+/// Textures for the faces of cards, either loaded from their images or drawn at startup.
+#[derive(Resource)]
+pub struct CardFaces {
+    /// Drawn faces, by color and then value.
+    drawn: Vec<Handle<Image>>,
+    /// Whether drawn faces are used in place of the card images.
+    enabled: bool,
+}
+
+impl CardFaces {
+    /// Returns the texture to show on the card.
+    pub fn texture(&self, card: &Card, asset_server: &AssetServer) -> Handle<Image> {
+        if self.enabled {
+            self.drawn(card)
+        } else {
+            asset_server.load(card.texture_path())
+        }
+    }
+
+    /// Returns the card's drawn face.
+    pub fn drawn(&self, card: &Card) -> Handle<Image> {
+        let color: u8 = card.color.into();
+        self.drawn[color as usize * VALUES.len() + card.value as usize].clone()
+    }
+}
+
+/// Draws a face for every color and value.
+fn setup(mut images: ResMut<Assets<Image>>, settings: Res<Settings>, mut commands: Commands) {
+    let size = CARD_SIZE.as_ivec2();
+    let mut drawn = Vec::with_capacity(COLORS.len() * VALUES.len());
+    for color in COLORS {
+        for value in VALUES {
+            let canvas = paint(color, value, size);
+            let image = Image::new(
+                Extent3d {
+                    width: size.x as u32,
+                    height: size.y as u32,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                canvas.data,
+                TextureFormat::Rgba8UnormSrgb,
+            );
+            drawn.push(images.add(image));
+        }
+    }
+    commands.insert_resource(CardFaces {
+        drawn,
+        enabled: settings.drawn_cards,
+    });
+}
+
+/// Switches between drawn faces and card images when the setting changes.
+fn follow_setting(mut faces: ResMut<CardFaces>, settings: Res<Settings>) {
+    if faces.enabled != settings.drawn_cards {
+        faces.enabled = settings.drawn_cards;
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostStartup, setup).add_systems(
+            Update,
+            follow_setting.run_if(resource_exists::<CardFaces>()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that every glyph is a full rectangle of cells.
+    #[test]
+    fn test_glyphs() {
+        for value in VALUES {
+            for glyph in glyphs(value) {
+                assert!(glyph.iter().all(|row| row.len() == glyph[0].len()));
+            }
+        }
+        assert_eq!(text_width(glyphs(CardValue::DrawTwo)), 11);
+    }
+
+    /// Ensures that a face has rounded corners, a white border, and the card's color inside.
+    #[test]
+    fn test_paint() {
+        let size = CARD_SIZE.as_ivec2();
+        let canvas = paint(CardColor::Blue, CardValue::Five, size);
+        let pixel = |x: i32, y: i32| {
+            let index = ((y * size.x + x) * 4) as usize;
+            canvas.data[index..index + 4].to_vec()
+        };
+        assert_eq!(canvas.data.len(), (size.x * size.y * 4) as usize);
+        assert_eq!(pixel(0, 0)[3], 0);
+        assert_eq!(pixel(size.x - 1, size.y - 1)[3], 0);
+        assert_eq!(pixel(size.x / 2, 2), Color::WHITE.as_rgba_u8());
+        assert_eq!(
+            pixel(size.x / 2, 10),
+            CardColor::Blue.display_color().as_rgba_u8()
+        );
+        // the pips are upside down copies of each other
+        for (x, y) in [(13, 13), (20, 25), (26, 32)] {
+            assert_eq!(pixel(x, y), pixel(size.x - 1 - x, size.y - 1 - y));
+        }
+    }
+}
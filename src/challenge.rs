@@ -0,0 +1,491 @@
+//! Challenges played alone against bots, each with a twist on the usual rules.
+//!
+//! A challenge is hosted in a room of its own on this device, without connecting to the server,
+//! and the game starts as soon as the room is open. Challenges unlock one at a time, and the ones completed are saved
+//! to the active profile.
+//! With adaptive bots on, the bots slip up more or less often depending on the profile's record.
+//!
+//! The daily deal is played the same way, with a deck shuffled from the date so everyone gets
+//! the same cards that day. The first result each day is kept, along with a line to share it.
+//! The tutorial is played the same way too, with a deck that deals the cards it walks through.
+
+use crate::{
+    bot::{BotSkill, Bots},
+    i18n::Language,
+    info::{GameInfo, Opponent, Opponents},
+    match_mode::MatchFormat,
+    menu::MenuState,
+    network::{ServerState, StartGame},
+    platform,
+    profile::{Profile, Profiles},
+    rules::GameRules,
+    screens::win::Win,
+    settings::Settings,
+    storage::Storage,
+    toast::Toast,
+    transport::GameTransport,
+    tutorial::TUTORIAL,
+    GameSet, ScreenState,
+};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    utils::Uuid,
+};
+use bevy_matchbox::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// A game against bots with its own rules, and sometimes a limit on how long we can take.
+pub struct Challenge {
+    /// Name shown on the challenge select screen, which is also what it's saved as.
+    pub name: &'static str,
+    pub description: &'static str,
+    pub bots: usize,
+    /// Cards dealt to everyone on top of the usual starting hand.
+    pub extra_cards: u8,
+    /// The deck only has action cards and wilds.
+    pub action_deck: bool,
+    /// Most turns we can take to win, if there's a limit.
+    pub turn_limit: Option<u32>,
+}
+
+impl Challenge {
+    /// Returns the rules the challenge is played with.
+    pub fn rules(&self) -> GameRules {
+        GameRules {
+            extra_cards: self.extra_cards,
+            action_deck: self.action_deck,
+            ..default()
+        }
+    }
+}
+
+/// Every challenge, in the order they unlock.
+pub const CHALLENGES: [Challenge; 6] = [
+    Challenge {
+        name: "Warm up",
+        description: "Beat a bot.",
+        bots: 1,
+        extra_cards: 0,
+        action_deck: false,
+        turn_limit: None,
+    },
+    Challenge {
+        name: "Full hands",
+        description: "Everyone starts with 10 cards.",
+        bots: 2,
+        extra_cards: 5,
+        action_deck: false,
+        turn_limit: None,
+    },
+    Challenge {
+        name: "All action",
+        description: "The deck only has skips, reverses, draw twos and wilds.",
+        bots: 2,
+        extra_cards: 0,
+        action_deck: true,
+        turn_limit: None,
+    },
+    Challenge {
+        name: "Quick draw",
+        description: "Win in 8 turns or fewer.",
+        bots: 1,
+        extra_cards: 0,
+        action_deck: false,
+        turn_limit: Some(8),
+    },
+    Challenge {
+        name: "Crowded table",
+        description: "Beat three bots in 12 turns or fewer.",
+        bots: 3,
+        extra_cards: 0,
+        action_deck: false,
+        turn_limit: Some(12),
+    },
+    Challenge {
+        name: "Gauntlet",
+        description: "Start with 10 cards against three bots, and win in 16 turns or fewer.",
+        bots: 3,
+        extra_cards: 5,
+        action_deck: false,
+        turn_limit: Some(16),
+    },
+];
+
+/// The daily deal, whose deck is shuffled from the day it's played.
+pub const DAILY_DEAL: Challenge = Challenge {
+    name: "Daily deal",
+    description: "Everyone gets the same deck today. Win in as few turns as you can.",
+    bots: 2,
+    extra_cards: 0,
+    action_deck: false,
+    turn_limit: None,
+};
+
+/// Day the first daily deal was played, counted in days since the Unix epoch.
+const FIRST_DAILY_DEAL: u32 = 20742;
+
+/// Returns the number of the daily deal for the given day, counting from 1.
+pub fn daily_number(day: u32) -> u32 {
+    day.saturating_sub(FIRST_DAILY_DEAL) + 1
+}
+
+/// Challenge being played, if any.
+#[derive(Resource, Default)]
+pub struct ActiveChallenge {
+    /// Index into [`CHALLENGES`].
+    index: Option<usize>,
+    /// Day of the daily deal being played.
+    daily: Option<u32>,
+    /// Whether the tutorial is being played.
+    tutorial: bool,
+    /// Whether the game has been started.
+    started: bool,
+    /// Rules from the lobby, put back once the challenge is over.
+    lobby_rules: GameRules,
+}
+
+impl ActiveChallenge {
+    /// Sets up the given challenge's rules, remembering the ones they replace.
+    pub fn start(&mut self, index: usize, rules: &mut GameRules) {
+        *self = Self {
+            index: Some(index),
+            daily: None,
+            tutorial: false,
+            started: false,
+            lobby_rules: rules.clone(),
+        };
+        *rules = CHALLENGES[index].rules();
+    }
+
+    /// Sets up today's daily deal, remembering the rules it replaces.
+    pub fn start_daily(&mut self, rules: &mut GameRules) {
+        *self = Self {
+            index: None,
+            daily: Some(platform::today()),
+            tutorial: false,
+            started: false,
+            lobby_rules: rules.clone(),
+        };
+        *rules = DAILY_DEAL.rules();
+    }
+
+    /// Sets up the tutorial and its scripted deck, remembering the rules they replace.
+    pub fn start_tutorial(&mut self, rules: &mut GameRules) {
+        *self = Self {
+            index: None,
+            daily: None,
+            tutorial: true,
+            started: false,
+            lobby_rules: rules.clone(),
+        };
+        *rules = GameRules {
+            scripted_deck: true,
+            ..TUTORIAL.rules()
+        };
+    }
+
+    /// Returns the challenge being played.
+    pub fn challenge(&self) -> Option<&'static Challenge> {
+        match (self.index, self.daily) {
+            (Some(index), _) => Some(&CHALLENGES[index]),
+            (None, Some(_)) => Some(&DAILY_DEAL),
+            (None, None) if self.tutorial => Some(&TUTORIAL),
+            (None, None) => None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.challenge().is_some()
+    }
+
+    pub fn is_tutorial(&self) -> bool {
+        self.tutorial
+    }
+}
+
+/// Names of the challenges a profile has completed.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChallengeProgress {
+    completed: Vec<String>,
+}
+
+impl ChallengeProgress {
+    /// Loads the profile's progress, or no progress if they haven't played a challenge.
+    pub fn load(storage: &Storage, profile: &Profile) -> Self {
+        storage.get(&profile.key("challenges")).unwrap_or_default()
+    }
+
+    /// Saves the profile's progress.
+    pub fn save(&self, storage: &mut Storage, profile: &Profile) -> io::Result<()> {
+        storage.set(&profile.key("challenges"), self)
+    }
+
+    pub fn is_completed(&self, index: usize) -> bool {
+        self.completed
+            .iter()
+            .any(|name| name == CHALLENGES[index].name)
+    }
+
+    /// Returns whether the challenge can be played, which it can once the one before it is done.
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        index == 0 || self.is_completed(index - 1)
+    }
+
+    /// Marks a challenge as completed.
+    pub fn complete(&mut self, index: usize) {
+        if !self.is_completed(index) {
+            self.completed.push(String::from(CHALLENGES[index].name));
+        }
+    }
+}
+
+/// How a daily deal went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyResult {
+    /// Day it was played, counted in days since the Unix epoch.
+    pub day: u32,
+    pub won: bool,
+    /// Turns we took.
+    pub turns: u32,
+    /// Points left in the bots' hands, if we won.
+    pub points: u32,
+}
+
+impl DailyResult {
+    /// Returns a line describing the result, for sharing with others who played the same deal.
+    pub fn share_text(&self, language: Language) -> String {
+        let outcome = if self.won {
+            format!(
+                "{}, {}",
+                language.tr_with("Won in {} turns", self.turns),
+                language.tr_with("{} points", self.points)
+            )
+        } else {
+            language.tr_with("Lost after {} turns", self.turns)
+        };
+        format!(
+            "crazy 7s {}: {outcome}",
+            language.tr_with("Daily deal #{}", daily_number(self.day))
+        )
+    }
+}
+
+/// Results of the daily deals a profile has played.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailyResults {
+    results: Vec<DailyResult>,
+}
+
+impl DailyResults {
+    /// Loads the profile's daily results, or none if they haven't played a daily deal.
+    pub fn load(storage: &Storage, profile: &Profile) -> Self {
+        storage.get(&profile.key("daily")).unwrap_or_default()
+    }
+
+    /// Saves the profile's daily results.
+    pub fn save(&self, storage: &mut Storage, profile: &Profile) -> io::Result<()> {
+        storage.set(&profile.key("daily"), self)
+    }
+
+    /// Returns the result of the given day's deal, if it's been played.
+    pub fn get(&self, day: u32) -> Option<&DailyResult> {
+        self.results.iter().find(|result| result.day == day)
+    }
+
+    /// Records a result, unless the day's deal was already played, since only the first try
+    /// counts.
+    pub fn record(&mut self, result: DailyResult) -> bool {
+        if self.get(result.day).is_some() {
+            return false;
+        }
+        self.results.push(result);
+        true
+    }
+}
+
+/// Starts the challenge against its bots as soon as our room is open.
+fn start_challenge(
+    mut challenge: ResMut<ActiveChallenge>,
+    mut socket: ResMut<GameTransport>,
+    mut start_events: EventWriter<StartGame>,
+    mut opponents: ResMut<Opponents>,
+    mut bots: ResMut<Bots>,
+    mut match_format: ResMut<MatchFormat>,
+    mut skill: ResMut<BotSkill>,
+    profiles: Res<Profiles>,
+    settings: Res<Settings>,
+) {
+    if challenge.started {
+        return;
+    }
+    let Some(current) = challenge.challenge() else { return; };
+    let Some(own_pid) = socket.id() else { return; };
+    challenge.started = true;
+
+    // we always go first
+    let mut order = vec![own_pid];
+    opponents.0.clear();
+    bots.0.clear();
+    let hand_size = current.rules().hand_size.cards();
+    for number in 1..=current.bots {
+        let bot = PeerId(Uuid::new_v4());
+        let name = settings.language.tr_with("Bot {}", number);
+        opponents.0.push(Opponent::new(bot, name, hand_size));
+        bots.0.insert(bot, Vec::new());
+        order.push(bot);
+    }
+    *match_format = MatchFormat::Single;
+    // the daily deal and the tutorial play the same for everyone
+    if challenge.index.is_some() && settings.adaptive_bots {
+        *skill = BotSkill::adapted_to(&profiles.active().stats);
+    }
+    // the daily deal is shuffled from the day, so everyone gets the same deck
+    start_events.send(StartGame {
+        order,
+        restart: false,
+        seed: challenge.daily.map(u64::from),
+    });
+}
+
+/// Checks whether we completed the challenge once the round is won, saving it if we did.
+///
+/// The first daily deal of the day is saved whether it was won or not.
+fn judge_challenge(
+    mut win_events: EventReader<Win>,
+    mut toasts: EventWriter<Toast>,
+    mut socket: ResMut<GameTransport>,
+    mut storage: ResMut<Storage>,
+    challenge: Res<ActiveChallenge>,
+    game_info: Res<GameInfo>,
+    bots: Res<Bots>,
+    rules: Res<GameRules>,
+    profiles: Res<Profiles>,
+    settings: Res<Settings>,
+) {
+    let Some(Win(winner)) = win_events.read().next() else { return; };
+    let Some(own_pid) = socket.id() else { return; };
+    let language = settings.language;
+    let won = *winner == own_pid;
+    let turns = game_info
+        .history
+        .iter()
+        .filter(|record| record.player == own_pid)
+        .count() as u32;
+
+    if let Some(day) = challenge.daily {
+        let points = if won {
+            bots.0
+                .values()
+                .map(|hand| rules.scoring().hand_points(hand))
+                .sum()
+        } else {
+            0
+        };
+        let result = DailyResult {
+            day,
+            won,
+            turns,
+            points,
+        };
+        let profile = profiles.active();
+        let mut results = DailyResults::load(&storage, profile);
+        if !results.record(result) {
+            toasts.send(Toast(String::from(language.tr("Only your first try each day counts"))));
+            return;
+        }
+        if let Err(err) = results.save(&mut storage, profile) {
+            error!("Error saving daily result: {err}");
+        }
+        toasts.send(Toast(result.share_text(language)));
+        return;
+    }
+
+    let Some(index) = challenge.index else { return; };
+    if !won {
+        toasts.send(Toast(String::from(language.tr("Challenge failed"))));
+        return;
+    }
+    if let Some(limit) = CHALLENGES[index].turn_limit.filter(|limit| turns > *limit) {
+        toasts.send(Toast(language.tr_with("Too slow! Win in {} turns or fewer", limit)));
+        return;
+    }
+
+    let profile = profiles.active();
+    let mut progress = ChallengeProgress::load(&storage, profile);
+    progress.complete(index);
+    if let Err(err) = progress.save(&mut storage, profile) {
+        error!("Error saving challenge progress: {err}");
+    }
+    toasts.send(Toast(String::from(language.tr("Challenge complete!"))));
+}
+
+/// Puts the lobby's rules back once the challenge's room is closed.
+fn end_challenge(mut challenge: ResMut<ActiveChallenge>, mut rules: ResMut<GameRules>) {
+    if !challenge.is_active() {
+        return;
+    }
+    *rules = std::mem::take(&mut challenge.lobby_rules);
+    *challenge = ActiveChallenge::default();
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveChallenge>()
+            .add_systems(
+                Update,
+                start_challenge
+                    .run_if(in_state(MenuState::Lobby))
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(
+                Update,
+                judge_challenge
+                    .in_set(GameSet::Ui)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(OnEnter(ServerState::None), end_challenge);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that challenges unlock in order as the ones before them are completed.
+    #[test]
+    fn test_unlock_order() {
+        let mut progress = ChallengeProgress::default();
+        assert!(progress.is_unlocked(0));
+        assert!(!progress.is_unlocked(1));
+
+        progress.complete(0);
+        progress.complete(0);
+        assert_eq!(progress.completed.len(), 1);
+        assert!(progress.is_unlocked(1));
+        assert!(!progress.is_unlocked(2));
+    }
+
+    /// Ensures that only the first daily deal of the day is kept, and shared with its number.
+    #[test]
+    fn test_daily_results() {
+        let mut results = DailyResults::default();
+        let first = DailyResult {
+            day: FIRST_DAILY_DEAL + 2,
+            won: true,
+            turns: 9,
+            points: 41,
+        };
+        assert!(results.record(first));
+        assert!(!results.record(DailyResult { won: false, ..first }));
+        assert_eq!(results.get(first.day), Some(&first));
+        assert_eq!(results.get(first.day + 1), None);
+        assert_eq!(
+            first.share_text(Language::English),
+            "crazy 7s Daily deal #3: Won in 9 turns, 41 points"
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! Game controller input, for playing on handhelds without a mouse or touch screen.
+//!
+//! The d-pad or left stick moves a focus ring between the buttons on screen and the cards on the
+//! table, and the face buttons click or long press whatever has focus. The ring and a bar of
+//! button hints only show up once a controller is used, and go away again when the mouse or
+//! touch screen is.
+
+use crate::{
+    pointer::{Click, Clickable, LongPress, Pointer},
+    settings::Settings,
+    GameScreenState, MainCamera,
+};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    ui::UiSystem,
+    window::CursorMoved,
+};
+
+/// How far (from 0 to 1) the stick has to be pushed to move the focus.
+const STICK_THRESHOLD: f32 = 0.5;
+/// How long (in seconds) the stick is held before the focus moves again.
+const STICK_REPEAT_TIME: f32 = 0.25;
+/// How much further away things off to the side count as, when moving the focus.
+const SIDEWAYS_WEIGHT: f32 = 2.0;
+/// Space between the focus ring and what it's around.
+const RING_PADDING: f32 = 4.0;
+
+/// Whether the player is using a controller or the mouse and touch screen.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputMode {
+    #[default]
+    Pointer,
+    Controller,
+}
+
+/// The button or object that controller presses go to.
+#[derive(Resource, Default)]
+pub struct Focus(pub Option<Entity>);
+
+/// Ring drawn around the focused button or object.
+#[derive(Component)]
+struct FocusRing;
+
+/// Bar along the bottom of the screen showing what the controller buttons do.
+#[derive(Component)]
+struct HintBar;
+
+/// Direction the focus is moved in, with y going down the screen.
+fn nav_direction(
+    gamepads: &Gamepads,
+    buttons: &Input<GamepadButton>,
+    axes: &Axis<GamepadAxis>,
+) -> (Option<Vec2>, Option<Vec2>) {
+    let mut pressed = None;
+    let mut held = None;
+    for gamepad in gamepads.iter() {
+        for (button, direction) in [
+            (GamepadButtonType::DPadUp, Vec2::NEG_Y),
+            (GamepadButtonType::DPadDown, Vec2::Y),
+            (GamepadButtonType::DPadLeft, Vec2::NEG_X),
+            (GamepadButtonType::DPadRight, Vec2::X),
+        ] {
+            if buttons.just_pressed(GamepadButton::new(gamepad, button)) {
+                pressed = Some(direction);
+            }
+        }
+        let stick = Vec2::new(
+            axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+                .unwrap_or(0.0),
+            -axes
+                .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+                .unwrap_or(0.0),
+        );
+        if stick.length() >= STICK_THRESHOLD {
+            // snap to the closest of the four directions
+            held = Some(if stick.x.abs() > stick.y.abs() {
+                Vec2::new(stick.x.signum(), 0.0)
+            } else {
+                Vec2::new(0.0, stick.y.signum())
+            });
+        }
+    }
+    (pressed, held)
+}
+
+/// Returns the target to move the focus to from `from` in a direction, picking the closest one
+/// that's mostly in that direction.
+pub fn next_target(
+    from: Rect,
+    direction: Vec2,
+    targets: impl Iterator<Item = (Entity, Rect)>,
+) -> Option<Entity> {
+    targets
+        .filter_map(|(entity, rect)| {
+            let offset = rect.center() - from.center();
+            let along = offset.dot(direction);
+            if along <= 1.0 {
+                return None;
+            }
+            let sideways = (offset - direction * along).length();
+            Some((entity, along + sideways * SIDEWAYS_WEIGHT))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+/// Returns the buttons and objects on screen that can take focus, with where they are in UI
+/// coordinates.
+fn focus_targets(
+    buttons: &Query<(Entity, &Node, &GlobalTransform, &ViewVisibility), With<Button>>,
+    objects: &Query<(Entity, &Transform, &Clickable, &ViewVisibility)>,
+    camera: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    ui_scale: f32,
+    include_objects: bool,
+) -> Vec<(Entity, Rect)> {
+    let mut targets: Vec<(Entity, Rect)> = buttons
+        .iter()
+        .filter(|(_, node, _, visibility)| visibility.get() && node.size() != Vec2::ZERO)
+        .map(|(entity, node, transform, _)| (entity, node.logical_rect(transform)))
+        .collect();
+
+    let Ok((camera, camera_transform)) = camera.get_single() else { return targets; };
+    if include_objects {
+        targets.extend(
+            objects
+                .iter()
+                .filter(|(.., visibility)| visibility.get())
+                .filter_map(|(entity, transform, Clickable(size), _)| {
+                    let center = transform.translation;
+                    let corner = center + (*size / 2.0).extend(0.0);
+                    let center = camera.world_to_viewport(camera_transform, center)?;
+                    let corner = camera.world_to_viewport(camera_transform, corner)?;
+                    let half_size = (corner - center).abs() / ui_scale;
+                    Some((
+                        entity,
+                        Rect::from_center_half_size(center / ui_scale, half_size),
+                    ))
+                }),
+        );
+    }
+    targets
+}
+
+/// Switches between controller and pointer input, depending on which was used last.
+fn detect_input_mode(
+    mut mode: ResMut<InputMode>,
+    mut cursor_events: EventReader<CursorMoved>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    pointer: Res<Pointer>,
+) {
+    let moved = cursor_events.read().count() > 0;
+    let new_mode = if gamepad_buttons.get_just_pressed().next().is_some() {
+        InputMode::Controller
+    } else if moved || pointer.just_pressed {
+        InputMode::Pointer
+    } else {
+        return;
+    };
+    if *mode != new_mode {
+        *mode = new_mode;
+    }
+}
+
+/// Moves the focus with the d-pad and stick, and sends clicks and long presses for the face
+/// buttons.
+fn navigate(
+    mut focus: ResMut<Focus>,
+    mut stick_held: Local<Option<f32>>,
+    mut clicks: EventWriter<Click>,
+    mut long_presses: EventWriter<LongPress>,
+    buttons: Query<(Entity, &Node, &GlobalTransform, &ViewVisibility), With<Button>>,
+    objects: Query<(Entity, &Transform, &Clickable, &ViewVisibility)>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    game_screen: Res<State<GameScreenState>>,
+    ui_scale: Res<UiScale>,
+    time: Res<Time>,
+) {
+    // cards on the table are covered by the other game screens
+    let include_objects = **game_screen == GameScreenState::Game;
+    let targets = focus_targets(
+        &buttons,
+        &objects,
+        &camera,
+        ui_scale.0 as f32,
+        include_objects,
+    );
+
+    // keep the focus on something that's still on screen
+    let current = focus
+        .0
+        .and_then(|entity| targets.iter().find(|(target, _)| *target == entity));
+    let Some((focused, focused_rect)) = current.copied().or_else(|| {
+        targets
+            .iter()
+            .min_by(|(_, a), (_, b)| {
+                a.min
+                    .y
+                    .total_cmp(&b.min.y)
+                    .then(a.min.x.total_cmp(&b.min.x))
+            })
+            .copied()
+    }) else {
+        focus.0 = None;
+        return;
+    };
+    if focus.0 != Some(focused) {
+        focus.0 = Some(focused);
+    }
+
+    let (pressed, held) = nav_direction(&gamepads, &gamepad_buttons, &axes);
+    let direction = pressed.or_else(|| {
+        let Some(direction) = held else {
+            *stick_held = None;
+            return None;
+        };
+        // move right away when the stick is pushed, then every so often while it's held
+        let since = stick_held.get_or_insert(STICK_REPEAT_TIME);
+        *since += time.delta_seconds();
+        if *since < STICK_REPEAT_TIME {
+            return None;
+        }
+        *since = 0.0;
+        Some(direction)
+    });
+    if let Some(direction) = direction {
+        if let Some(next) = next_target(focused_rect, direction, targets.iter().copied()) {
+            focus.0 = Some(next);
+        }
+    }
+
+    for gamepad in gamepads.iter() {
+        if gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South)) {
+            clicks.send(Click(focused));
+        }
+        if gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::West)) {
+            long_presses.send(LongPress(focused));
+        }
+    }
+}
+
+/// Draws the focus ring and the hint bar, which stay on top of every screen.
+fn setup(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                border: UiRect::all(Val::Px(3.0)),
+                ..default()
+            },
+            border_color: Color::rgb(1.0, 0.85, 0.2).into(),
+            z_index: ZIndex::Global(100),
+            ..default()
+        },
+        FocusRing,
+    ));
+
+    commands.spawn((
+        TextBundle {
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            z_index: ZIndex::Global(100),
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-Black.ttf"),
+                    font_size: 18.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_style(Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(6.0),
+                right: Val::Px(20.0),
+                padding: UiRect::horizontal(Val::Px(8.0)),
+                ..default()
+            })
+        },
+        HintBar,
+    ));
+}
+
+/// Shows what the controller buttons do, in the chosen language.
+fn update_hint_bar(mut text: Query<&mut Text, With<HintBar>>, settings: Res<Settings>) {
+    if !settings.is_changed() {
+        return;
+    }
+    let language = settings.language;
+    for mut text in &mut text {
+        text.sections[0].value = [
+            ("D-pad", language.tr("Move")),
+            ("A", language.tr("Select")),
+            ("X", language.tr("Hold")),
+        ]
+        .map(|(glyph, action)| format!("({glyph}) {action}"))
+        .join("     ");
+    }
+}
+
+/// Moves the focus ring onto the focused button or object, and shows the ring and hints only
+/// while a controller is being used.
+fn update_focus_ring(
+    mut ring: Query<&mut Style, (With<FocusRing>, Without<HintBar>)>,
+    mut hint_bar: Query<&mut Style, (With<HintBar>, Without<FocusRing>)>,
+    buttons: Query<(Entity, &Node, &GlobalTransform, &ViewVisibility), With<Button>>,
+    objects: Query<(Entity, &Transform, &Clickable, &ViewVisibility)>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    focus: Res<Focus>,
+    mode: Res<InputMode>,
+    ui_scale: Res<UiScale>,
+) {
+    let controller = *mode == InputMode::Controller;
+    if let Ok(mut style) = hint_bar.get_single_mut() {
+        let display = if controller {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+
+    let Ok(mut style) = ring.get_single_mut() else { return; };
+    let rect = focus.0.filter(|_| controller).and_then(|entity| {
+        focus_targets(&buttons, &objects, &camera, ui_scale.0 as f32, true)
+            .into_iter()
+            .find(|(target, _)| *target == entity)
+            .map(|(_, rect)| rect)
+    });
+    let Some(rect) = rect else {
+        if style.display != Display::None {
+            style.display = Display::None;
+        }
+        return;
+    };
+    let rect = rect.inset(RING_PADDING);
+    style.display = Display::Flex;
+    style.left = Val::Px(rect.min.x);
+    style.top = Val::Px(rect.min.y);
+    style.width = Val::Px(rect.width());
+    style.height = Val::Px(rect.height());
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputMode>()
+            .init_resource::<Focus>()
+            .add_systems(Startup, setup)
+            .add_systems(
+                PreUpdate,
+                (detect_input_mode, navigate).chain().after(UiSystem::Focus),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    update_focus_ring,
+                    update_hint_bar.run_if(resource_exists::<Settings>()),
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that the focus moves to the closest target in the direction pressed, and stays
+    /// put when there's nothing that way.
+    #[test]
+    fn test_next_target() {
+        let rect = |x: f32, y: f32| Rect::from_center_size(Vec2::new(x, y), Vec2::splat(40.0));
+        let targets = [
+            (Entity::from_raw(1), rect(200.0, 100.0)),
+            (Entity::from_raw(2), rect(120.0, 300.0)),
+            (Entity::from_raw(3), rect(400.0, 110.0)),
+        ];
+        let from = rect(100.0, 100.0);
+
+        assert_eq!(
+            next_target(from, Vec2::X, targets.into_iter()),
+            Some(Entity::from_raw(1))
+        );
+        assert_eq!(
+            next_target(from, Vec2::Y, targets.into_iter()),
+            Some(Entity::from_raw(2))
+        );
+        assert_eq!(next_target(from, Vec2::NEG_X, targets.into_iter()), None);
+    }
+}
@@ -0,0 +1,310 @@
+//! Overlay showing the game state, for comparing screens when players' games get out of sync.
+//!
+//! It also keeps count of the traffic sent and received, so changes to the protocol can be
+//! measured, and of the live entities and assets, so leaks show up over many rematches. Only
+//! built with the `debug_overlay` feature, and toggled with F3.
+
+use crate::{
+    deck::{Deck, DiscardCards, GameRng, MainPlayer},
+    info::{GameInfo, Opponents},
+    network::SocketEvent,
+    transport::GameTransport,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use bevy_matchbox::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    sync::Mutex,
+};
+
+/// Number of received packets shown in the overlay.
+const PACKET_LOG_LEN: usize = 5;
+/// Number of event types shown in the traffic breakdown, busiest first.
+const TRAFFIC_EVENTS_LEN: usize = 6;
+
+/// Event code and length of each packet sent since the traffic was last counted.
+///
+/// Packets are sent from all over the network code, so they're noted here rather than through
+/// an event.
+static SENT: Mutex<Vec<(Option<u8>, usize)>> = Mutex::new(Vec::new());
+
+/// Notes a packet being sent, to be counted in the traffic stats.
+pub fn record_sent(packet: &[u8]) {
+    SENT.lock()
+        .unwrap()
+        .push((packet.first().copied(), packet.len()));
+}
+
+/// Posted by the network for each packet received from a peer.
+#[derive(Event)]
+pub struct PacketReceived {
+    pub peer: PeerId,
+    pub packet: Box<[u8]>,
+}
+
+/// The most recently received packets, oldest first.
+#[derive(Resource, Default)]
+struct PacketLog(VecDeque<String>);
+
+/// Traffic going one way.
+#[derive(Default)]
+struct TrafficStats {
+    total_bytes: usize,
+    /// Bytes counted in the current second.
+    window_bytes: usize,
+    /// Bytes counted in the last full second.
+    bytes_per_second: usize,
+    /// Length and event of the largest packet seen.
+    largest: usize,
+    largest_event: String,
+    /// Number of packets of each event type.
+    packets: BTreeMap<String, u32>,
+}
+
+impl TrafficStats {
+    /// Counts a packet.
+    fn record(&mut self, code: Option<u8>, len: usize) {
+        let event = event_name(code);
+        self.total_bytes += len;
+        self.window_bytes += len;
+        if len > self.largest {
+            self.largest = len;
+            self.largest_event = event.clone();
+        }
+        *self.packets.entry(event).or_default() += 1;
+    }
+
+    /// Describes the traffic on one line.
+    fn summary(&self) -> String {
+        let packets: u32 = self.packets.values().sum();
+        format!(
+            "{:.1} KB/s, {packets} packets, {:.1} KB total, largest {} B ({})",
+            self.bytes_per_second as f32 / 1024.0,
+            self.total_bytes as f32 / 1024.0,
+            self.largest,
+            self.largest_event,
+        )
+    }
+}
+
+/// Traffic sent and received since the game started.
+#[derive(Resource)]
+struct Traffic {
+    sent: TrafficStats,
+    received: TrafficStats,
+    /// Rolls the bytes per second over.
+    timer: Timer,
+}
+
+impl Default for Traffic {
+    fn default() -> Self {
+        Self {
+            sent: default(),
+            received: default(),
+            timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Text showing the game state.
+#[derive(Component)]
+struct Overlay;
+
+/// Returns the start of a peer id, which is enough to tell peers apart.
+fn short_id(peer: &PeerId) -> String {
+    peer.0.to_string()[..8].to_string()
+}
+
+/// Returns the name of a packet's event.
+fn event_name(code: Option<u8>) -> String {
+    match code.map(SocketEvent::try_from) {
+        Some(Ok(event)) => format!("{event:?}"),
+        Some(Err(_)) => String::from("Invalid"),
+        None => String::from("Empty"),
+    }
+}
+
+/// Spawns the overlay, hidden.
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                right: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 14.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(100),
+            ..default()
+        },
+        Overlay,
+    ));
+}
+
+/// Shows or hides the overlay when F3 is pressed.
+fn toggle_overlay(mut overlay: Query<&mut Visibility, With<Overlay>>, keys: Res<Input<KeyCode>>) {
+    if !keys.just_pressed(KeyCode::F3) {
+        return;
+    }
+    for mut visibility in &mut overlay {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+/// Describes received packets by their event and length.
+fn log_packets(mut events: EventReader<PacketReceived>, mut log: ResMut<PacketLog>) {
+    for PacketReceived { peer, packet } in events.read() {
+        let event = event_name(packet.first().copied());
+        log.0.push_back(format!(
+            "{} {event} ({} bytes)",
+            short_id(peer),
+            packet.len()
+        ));
+        if log.0.len() > PACKET_LOG_LEN {
+            log.0.pop_front();
+        }
+    }
+}
+
+/// Counts the packets sent and received, and works out the bytes per second.
+fn count_traffic(
+    mut events: EventReader<PacketReceived>,
+    mut traffic: ResMut<Traffic>,
+    time: Res<Time>,
+) {
+    for PacketReceived { packet, .. } in events.read() {
+        traffic
+            .received
+            .record(packet.first().copied(), packet.len());
+    }
+    for (code, len) in SENT.lock().unwrap().drain(..) {
+        traffic.sent.record(code, len);
+    }
+
+    let traffic = &mut *traffic;
+    if traffic.timer.tick(time.delta()).just_finished() {
+        for stats in [&mut traffic.sent, &mut traffic.received] {
+            stats.bytes_per_second = stats.window_bytes;
+            stats.window_bytes = 0;
+        }
+    }
+}
+
+/// Fills in the overlay with the current game state.
+fn update_overlay(
+    mut overlay: Query<(&mut Text, &Visibility), With<Overlay>>,
+    socket: Option<ResMut<GameTransport>>,
+    game_info: Res<GameInfo>,
+    deck: Res<Deck>,
+    game_rng: Res<GameRng>,
+    discard_pile: Res<DiscardCards>,
+    player: Res<MainPlayer>,
+    opponents: Res<Opponents>,
+    log: Res<PacketLog>,
+    traffic: Res<Traffic>,
+    entities: Query<Entity>,
+    meshes: Res<Assets<Mesh>>,
+    materials: Res<Assets<ColorMaterial>>,
+    images: Res<Assets<Image>>,
+) {
+    let Ok((mut text, visibility)) = overlay.get_single_mut() else { return; };
+    if visibility == Visibility::Hidden {
+        return;
+    }
+
+    let id = |peer: Option<PeerId>| peer.as_ref().map_or(String::from("-"), short_id);
+    let mut lines = vec![
+        format!("me: {}", id(socket.and_then(|mut socket| socket.id()))),
+        format!("turn {}: {}", game_info.turn, id(game_info.current_player)),
+        format!(
+            "order: {}",
+            game_info
+                .order
+                .iter()
+                .map(short_id)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        format!("direction: {:?}", game_info.direction),
+        format!("pending draw: {}", game_info.pending_draw),
+        format!("deck: {} cards", deck.cards.len()),
+        format!("seed: {}", game_rng.seed()),
+        format!("discard top: {:?}", discard_pile.cards.last()),
+        format!("hand: {} cards", player.len()),
+    ];
+    for opponent in &opponents.0 {
+        lines.push(format!(
+            "{} ({}): {} cards",
+            opponent.name,
+            short_id(&opponent.id),
+            opponent.card_count
+        ));
+    }
+    lines.push(format!(
+        "live: {} entities, {} meshes, {} materials, {} images",
+        entities.iter().count(),
+        meshes.len(),
+        materials.len(),
+        images.len()
+    ));
+    lines.push(format!("sent: {}", traffic.sent.summary()));
+    lines.push(format!("received: {}", traffic.received.summary()));
+
+    // busiest events first, with the packets sent and received of each
+    let count = |stats: &TrafficStats, event: &str| stats.packets.get(event).copied().unwrap_or(0);
+    let mut events: Vec<&String> = traffic
+        .sent
+        .packets
+        .keys()
+        .chain(traffic.received.packets.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    events.sort_by_key(|event| {
+        std::cmp::Reverse(count(&traffic.sent, event) + count(&traffic.received, event))
+    });
+    for event in events.into_iter().take(TRAFFIC_EVENTS_LEN) {
+        lines.push(format!(
+            "  {event}: {} sent, {} received",
+            count(&traffic.sent, event),
+            count(&traffic.received, event)
+        ));
+    }
+
+    lines.push(String::from("received:"));
+    lines.extend(log.0.iter().cloned());
+
+    let value = lines.join("\n");
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PacketReceived>()
+            .init_resource::<PacketLog>()
+            .init_resource::<Traffic>()
+            .add_systems(Startup, setup)
+            .add_systems(
+                Update,
+                (toggle_overlay, log_packets, count_traffic, update_overlay).chain(),
+            );
+    }
+}
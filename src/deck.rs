@@ -1,7 +1,155 @@
 //! The overall deck of cards, discard pile, and player card resources.
 
 use crate::card::{Card, CardColor, CardValue};
+use crate::info::{GameInfo, Opponents};
+use crate::network::MaxPlayers;
+use crate::rules::{GameRules, HandSize};
+use crate::tutorial;
 use bevy::prelude::{Plugin as BevyPlugin, *};
+use bevy_matchbox::prelude::PeerId;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Number of cards each player starts with, unless the host picks another hand size.
+pub const STARTING_HAND_SIZE: usize = 5;
+
+/// Random numbers for everything that has to come out the same for every player.
+///
+/// The host picks the seed when a game starts and shares it, so a game can be played back
+/// from its seed and moves, and peers can check that they dealt the same deck as the host.
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed the game's random numbers come from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Picks an index below `len`.
+    pub fn index(&mut self, len: usize) -> usize {
+        self.rng.gen_range(0..len)
+    }
+
+    /// Returns `true` with the given probability, from 0 to 1.
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.rng.gen_bool(f64::from(probability.clamp(0.0, 1.0)))
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+/// How many copies of each card go into the deck, picked by the host in the deck editor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeckComposition {
+    /// Copies of each value in every color, in the order of [`DeckComposition::VALUES`].
+    pub copies: [u8; 12],
+    /// Number of wilds.
+    pub wilds: u8,
+}
+
+impl DeckComposition {
+    /// Values that come in every color, which is all of them but the wild 7.
+    pub const VALUES: [CardValue; 12] = [
+        CardValue::Zero,
+        CardValue::One,
+        CardValue::Two,
+        CardValue::Three,
+        CardValue::Four,
+        CardValue::Five,
+        CardValue::Six,
+        CardValue::Eight,
+        CardValue::Nine,
+        CardValue::Skip,
+        CardValue::Reverse,
+        CardValue::DrawTwo,
+    ];
+    /// Most copies of a value in each color, since cards are sent as a single byte.
+    pub const MAX_COPIES: u8 = 2;
+    /// Most decks that can be combined into one, for big rooms.
+    pub const MAX_DECKS: u8 = 2;
+    /// Most wilds in the deck.
+    pub const MAX_WILDS: u8 = 8;
+    /// Fewest cards in a deck, which is enough to deal a full room and flip a card to start.
+    pub const MIN_SIZE: usize = MaxPlayers::MAX as usize * HandSize::MAX + 1;
+    /// Length of the composition in the start game packet.
+    pub const BYTES: usize = Self::VALUES.len() + 1;
+
+    /// Returns the number of cards in the deck.
+    pub fn len(&self) -> usize {
+        self.copies.iter().map(|copies| *copies as usize * 4).sum::<usize>() + self.wilds as usize
+    }
+
+    /// Returns the composition of several of these decks shuffled together.
+    pub fn combined(&self, decks: u8) -> Self {
+        Self {
+            copies: self.copies.map(|copies| copies * decks),
+            wilds: self.wilds * decks,
+        }
+    }
+
+    /// Returns `true` if a round can be dealt from the deck.
+    ///
+    /// There has to be a number card for the round to start on, as well as enough cards.
+    pub fn is_valid(&self) -> bool {
+        let has_numbers = Self::VALUES.iter().zip(self.copies).any(|(value, copies)| {
+            copies > 0
+                && !matches!(
+                    value,
+                    CardValue::Skip | CardValue::Reverse | CardValue::DrawTwo
+                )
+        });
+        has_numbers && self.len() >= Self::MIN_SIZE
+    }
+
+    /// Serializes the composition for the start game packet, with a byte for the copies of each
+    /// value and then the number of wilds.
+    pub fn to_bytes(self) -> [u8; Self::BYTES] {
+        let mut bytes = [0; Self::BYTES];
+        bytes[..Self::VALUES.len()].copy_from_slice(&self.copies);
+        bytes[Self::VALUES.len()] = self.wilds;
+        bytes
+    }
+
+    /// Loads the composition from the start game packet, unless it couldn't be dealt from.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: [u8; Self::BYTES] = bytes.get(..Self::BYTES)?.try_into().ok()?;
+        let composition = Self {
+            copies: bytes[..Self::VALUES.len()].try_into().ok()?,
+            wilds: bytes[Self::VALUES.len()],
+        };
+        let in_range = composition
+            .copies
+            .iter()
+            .all(|copies| *copies <= Self::MAX_COPIES)
+            && composition.wilds <= Self::MAX_WILDS;
+        (in_range && composition.is_valid()).then_some(composition)
+    }
+}
+
+impl Default for DeckComposition {
+    /// Two of each card in every color, and four wilds.
+    fn default() -> Self {
+        Self {
+            copies: [2; 12],
+            wilds: 4,
+        }
+    }
+}
 
 /// Deck of cards.
 #[derive(Resource, Debug, Clone)]
@@ -10,8 +158,8 @@ pub struct Deck {
 }
 
 impl Deck {
-    /// The default cards in the deck.
-    fn default_cards() -> Vec<Card> {
+    /// Creates a new deck with the given copies of each card.
+    pub fn from_composition(composition: &DeckComposition) -> Self {
         let mut cards = Vec::new();
         // add regular deck (without 7s)
         for color in [
@@ -20,47 +168,60 @@ impl Deck {
             CardColor::Green,
             CardColor::Blue,
         ] {
-            for value in [
-                CardValue::Zero,
-                CardValue::One,
-                CardValue::Two,
-                CardValue::Three,
-                CardValue::Four,
-                CardValue::Five,
-                CardValue::Six,
-                CardValue::Eight,
-                CardValue::Nine,
-                CardValue::Skip,
-                CardValue::Reverse,
-                CardValue::DrawTwo,
-            ] {
-                cards.push(Card::new(color, value, 1));
-                cards.push(Card::new(color, value, 2));
+            for (value, copies) in DeckComposition::VALUES.iter().zip(composition.copies) {
+                for iteration in 1..=copies {
+                    cards.push(Card::new(color, *value, iteration));
+                }
             }
         }
-        // add four wild cards
-        for i in 0..4 {
+        // add wild cards
+        for i in 0..composition.wilds {
             cards.push(Card::new(CardColor::Wild, CardValue::Seven, i));
         }
-        cards
+        Self { cards }
     }
 
     /// Creates a new deck of cards with the default cards.
     pub fn new() -> Self {
-        let cards = Self::default_cards();
-        Self { cards }
+        Self::from_composition(&DeckComposition::default())
+    }
+
+    /// Creates a new deck of cards for the given rules, with the host's choice of cards, which
+    /// may leave out the number cards.
+    pub fn for_rules(rules: &GameRules) -> Self {
+        let mut deck = Self::from_composition(&rules.deck.combined(rules.decks()));
+        if rules.action_deck {
+            deck.cards.retain(|card| {
+                matches!(
+                    card.value,
+                    CardValue::Skip | CardValue::Reverse | CardValue::DrawTwo | CardValue::Seven
+                )
+            });
+        }
+        deck
+    }
+
+    /// Creates the deck a round is dealt from, shuffled from the game's random numbers.
+    ///
+    /// The tutorial's scripted cards are put back on top after shuffling, so they're drawn in
+    /// order.
+    pub fn shuffled(rules: &GameRules, rng: &mut GameRng) -> Self {
+        let mut deck = Self::for_rules(rules);
+        deck.shuffle(rng);
+        if rules.scripted_deck {
+            deck.stack(&tutorial::SCRIPTED_CARDS);
+        }
+        deck
     }
 
     // Resets the deck to the default cards.
     // pub fn reset(&mut self) {
-    //     self.cards = Self::default_cards();
+    //     self.cards = Self::new().cards;
     // }
 
     /// Shuffles the deck.
-    pub fn shuffle(&mut self) {
-        use rand::seq::SliceRandom;
-        use rand::thread_rng;
-        self.cards.shuffle(&mut thread_rng());
+    pub fn shuffle(&mut self, rng: &mut GameRng) {
+        self.cards.shuffle(&mut rng.rng);
     }
 
     /// Returns the order of the cards in the deck.
@@ -84,6 +245,59 @@ impl Deck {
         cards
     }
 
+    /// Deals starting hands to every player, and returns the hand for the player at the given
+    /// position in the order.
+    ///
+    /// Every player deals the same cards from the same deck, so everyone stays in sync.
+    pub fn deal(&mut self, player_count: usize, position: usize, hand_size: usize) -> Vec<Card> {
+        let mut dealt = self.draw((hand_size * player_count) as i32);
+        let start = position * hand_size;
+        dealt.drain(start..start + hand_size).collect()
+    }
+
+    /// Moves the discarded cards under the top card back into the deck, and returns how many
+    /// cards were taken off the discard pile.
+    ///
+    /// Wilds are reset to their uncolored card, since the colored copy on the discard pile
+    /// only exists to show the chosen color. The deck isn't shuffled.
+    pub fn recycle(&mut self, discard_pile: &mut Vec<Card>) -> usize {
+        let count = discard_pile.len().saturating_sub(1);
+        self.recycle_bottom(discard_pile, count);
+        count
+    }
+
+    /// Moves the given number of cards from the bottom of the discard pile back into the deck.
+    ///
+    /// Peers recycle as many cards as the host did, so cards played since the host recycled stay
+    /// on the pile.
+    pub fn recycle_bottom(&mut self, discard_pile: &mut Vec<Card>, count: usize) {
+        let count = count.min(discard_pile.len());
+        let mut recycled: Vec<_> = discard_pile[count..].iter().map(|card| card.id()).collect();
+        for mut card in discard_pile.drain(..count) {
+            if recycled.contains(&card.id()) {
+                continue;
+            }
+            recycled.push(card.id());
+            if card.value == CardValue::Seven {
+                card.color = CardColor::Wild;
+            }
+            self.cards.push(card);
+        }
+    }
+
+    /// Puts cards on top of the deck, so they're the next to be drawn, in the given order.
+    ///
+    /// The cards are taken out of wherever they were in the deck first.
+    pub fn stack(&mut self, cards: &[Card]) {
+        self.cards.retain(|card| !cards.contains(card));
+        self.cards.extend(cards.iter().rev());
+    }
+
+    /// Puts cards under the deck, so they're the last to be drawn.
+    pub fn return_to_bottom(&mut self, cards: &[Card]) {
+        self.cards.splice(0..0, cards.iter().copied());
+    }
+
     /// Returns `true` if the deck has no cards left.
     pub fn is_empty(&self) -> bool {
         self.cards.is_empty()
@@ -91,9 +305,9 @@ impl Deck {
 }
 
 impl FromWorld for Deck {
-    fn from_world(_: &mut World) -> Self {
+    fn from_world(world: &mut World) -> Self {
         let mut deck = Self::new();
-        deck.shuffle();
+        deck.shuffle(&mut world.get_resource_or_insert_with(GameRng::default));
         return deck;
     }
 }
@@ -102,22 +316,208 @@ impl FromWorld for Deck {
 #[derive(Resource, Default)]
 pub struct DiscardCards {
     pub cards: Vec<Card>,
+    /// Plays the host hasn't confirmed yet, oldest first.
+    pub provisional: Vec<ProvisionalPlay>,
+    /// Cards at the bottom of the pile whose sprites have been cleared away.
+    pub buried: usize,
 }
 
-/// The main player's cards.
-#[derive(Resource, Default)]
-pub struct MainPlayer {
+impl DiscardCards {
+    /// Returns the depth (z) of the card at `count` in the pile, counting from 1 at the bottom.
+    ///
+    /// Buried cards take up no room, so the depths don't keep climbing over a long game.
+    pub fn depth(&self, count: usize) -> f32 {
+        (count.saturating_sub(self.buried) + 1) as f32 * 0.01
+    }
+
+    /// Forgets a play once the host has confirmed it.
+    ///
+    /// Plays are known by the player and the first card they played.
+    pub fn confirm(&mut self, player: PeerId, card: Card) {
+        self.provisional
+            .retain(|play| play.player != player || play.cards.first() != Some(&card));
+    }
+
+    /// Takes out a play the host turned down, along with any plays made after it, which were
+    /// built on top of it.
+    pub fn reject(&mut self, player: PeerId, card: Card) -> Option<ProvisionalPlay> {
+        let index = self
+            .provisional
+            .iter()
+            .position(|play| play.player == player && play.cards.first() == Some(&card))?;
+        self.provisional.drain(index..).next()
+    }
+}
+
+/// A play that's been applied before the host confirmed it, and the game as it was before.
+pub struct ProvisionalPlay {
+    pub player: PeerId,
+    /// Cards played together, in the order they were played.
     pub cards: Vec<Card>,
+    game_info: GameInfo,
+    deck: Deck,
+    discard_len: usize,
+    hand: Vec<(HandCardId, Card)>,
+    card_counts: Vec<(PeerId, usize)>,
+}
+
+impl ProvisionalPlay {
+    /// Remembers the game as it was before the cards were played.
+    ///
+    /// The cards mustn't be in the discard pile yet, but may have already left the hand.
+    pub fn new(
+        player: PeerId,
+        cards: &[Card],
+        game_info: &GameInfo,
+        deck: &Deck,
+        discard_pile: &[Card],
+        main_player: &MainPlayer,
+        opponents: &Opponents,
+    ) -> Self {
+        Self {
+            player,
+            cards: cards.to_vec(),
+            game_info: game_info.clone(),
+            deck: deck.clone(),
+            discard_len: discard_pile.len(),
+            hand: main_player.entries().to_vec(),
+            card_counts: opponents
+                .0
+                .iter()
+                .map(|opponent| (opponent.id, opponent.card_count))
+                .collect(),
+        }
+    }
+
+    /// Keeps the play undoable after the host reshuffled the discard pile into the deck.
+    ///
+    /// The host's new deck stands whether or not the play is turned down, and the cards recycled
+    /// from under the play are gone from the pile, so only the played cards are taken back.
+    pub fn reshuffled(&mut self, deck: &Deck, recycled: usize) {
+        self.deck = deck.clone();
+        self.discard_len = self.discard_len.saturating_sub(recycled);
+    }
+
+    /// Puts the game back the way it was before the cards were played.
+    ///
+    /// If they were our cards, they go back into our hand.
+    pub fn restore(
+        self,
+        own_id: Option<PeerId>,
+        game_info: &mut GameInfo,
+        deck: &mut Deck,
+        discard_pile: &mut Vec<Card>,
+        main_player: &mut MainPlayer,
+        opponents: &mut Opponents,
+    ) {
+        *game_info = self.game_info;
+        *deck = self.deck;
+        discard_pile.truncate(self.discard_len);
+        main_player.hand = self.hand;
+        if own_id == Some(self.player) {
+            for card in self.cards {
+                if !main_player.contains(&card) {
+                    main_player.push(card);
+                }
+            }
+        }
+        for opponent in opponents.0.iter_mut() {
+            if let Some((_, count)) = self.card_counts.iter().find(|(id, _)| *id == opponent.id) {
+                opponent.card_count = *count;
+            }
+        }
+    }
+}
+
+/// Identifies a card in the main player's hand, so that equal cards can be told apart.
+///
+/// Each card entity in the hand holds the id of the card it shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HandCardId(u32);
+
+/// The main player's cards, in the order they're shown.
+#[derive(Resource, Clone, Default)]
+pub struct MainPlayer {
+    hand: Vec<(HandCardId, Card)>,
+    /// Id given to the next card added, which is never reused.
+    next_id: u32,
 }
 
 impl MainPlayer {
     pub fn reset(&mut self) {
-        self.cards.clear();
+        self.hand.clear();
+    }
+
+    /// Returns the cards in the hand.
+    pub fn cards(&self) -> Vec<Card> {
+        self.iter().collect()
+    }
+
+    /// Iterates over the cards in the hand.
+    pub fn iter(&self) -> impl Iterator<Item = Card> + '_ {
+        self.hand.iter().map(|(_, card)| *card)
+    }
+
+    /// Returns the cards in the hand along with their ids.
+    pub fn entries(&self) -> &[(HandCardId, Card)] {
+        &self.hand
+    }
+
+    pub fn len(&self) -> usize {
+        self.hand.len()
+    }
+
+    /// Returns `true` if the hand holds a card equal to `card`.
+    pub fn contains(&self, card: &Card) -> bool {
+        self.hand.iter().any(|(_, held)| held == card)
+    }
+
+    /// Returns where the card is in the hand, if it's still there.
+    pub fn position(&self, id: HandCardId) -> Option<usize> {
+        self.hand.iter().position(|(held, _)| *held == id)
+    }
+
+    /// Adds a card to the end of the hand, returning its id.
+    pub fn push(&mut self, card: Card) -> HandCardId {
+        let id = HandCardId(self.next_id);
+        self.next_id += 1;
+        self.hand.push((id, card));
+        id
+    }
+
+    /// Replaces the hand with newly dealt cards.
+    pub fn set_cards(&mut self, cards: Vec<Card>) {
+        self.hand.clear();
+        for card in cards {
+            self.push(card);
+        }
+    }
+
+    /// Removes the card from the hand, returning it if it was there.
+    pub fn remove(&mut self, id: HandCardId) -> Option<Card> {
+        let index = self.position(id)?;
+        Some(self.hand.remove(index).1)
+    }
+
+    /// Returns the id of a card equal to `card` that isn't in `taken`.
+    ///
+    /// Used for cards that come back into the hand and only their value is known.
+    pub fn find_untaken(&self, card: &Card, taken: &[HandCardId]) -> Option<HandCardId> {
+        self.hand
+            .iter()
+            .find(|(id, held)| held == card && !taken.contains(id))
+            .map(|(id, _)| *id)
+    }
+
+    /// Sorts the hand by a key taken from each card, keeping equal cards in order.
+    pub fn sort_by_key<K: Ord>(&mut self, mut key: impl FnMut(&Card) -> K) {
+        self.hand.sort_by_key(|(_, card)| key(card));
     }
 }
 
 /// Initializes deck and main player cards.
 fn setup(mut commands: Commands) {
+    commands.init_resource::<GameRng>();
     commands.init_resource::<Deck>();
     commands.insert_resource(DiscardCards::default());
     commands.insert_resource(MainPlayer::default());
@@ -130,3 +530,42 @@ impl BevyPlugin for Plugin {
         app.add_systems(Startup, setup);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that the deck has the chosen cards, and that decks too small to deal are turned
+    /// down.
+    #[test]
+    fn test_deck_composition() {
+        let default = DeckComposition::default();
+        assert_eq!(Deck::from_composition(&default).cards.len(), default.len());
+        assert_eq!(default.len(), 100);
+        assert_eq!(DeckComposition::from_bytes(&default.to_bytes()), Some(default));
+
+        // more wilds and no +2s
+        let mut composition = default;
+        composition.copies[11] = 0;
+        composition.wilds = DeckComposition::MAX_WILDS;
+        let deck = Deck::from_composition(&composition);
+        assert_eq!(deck.cards.len(), 100 - 8 + 4);
+        assert!(!deck.cards.iter().any(|card| card.value == CardValue::DrawTwo));
+        assert_eq!(DeckComposition::from_bytes(&composition.to_bytes()), Some(composition));
+
+        // nothing to start the round on
+        let actions_only = DeckComposition {
+            copies: [0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 2, 2],
+            wilds: 8,
+        };
+        assert!(!actions_only.is_valid());
+
+        let small = DeckComposition {
+            copies: [1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0],
+            wilds: 0,
+        };
+        assert!(!small.is_valid());
+        assert_eq!(DeckComposition::from_bytes(&small.to_bytes()), None);
+        assert_eq!(DeckComposition::from_bytes(&[2; 12]), None);
+    }
+}
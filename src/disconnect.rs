@@ -0,0 +1,437 @@
+//! Pausing the game when a player disconnects.
+//!
+//! Everyone waits a while for the player (unless they said they were leaving), then the host
+//! decides whether to carry on without them, let a bot play in their place, or end the game. If
+//! it was the host who left, whoever took over the room decides.
+
+use crate::{
+    away::Away,
+    bot::Bots,
+    card::Card,
+    deck::{Deck, DiscardCards, MainPlayer},
+    despawn_screen,
+    info::{GameInfo, Opponents},
+    menu::MenuState,
+    network::{AbortGame, BotTakeover, DropPlayer, HandShared, PeerLeft, ServerState, ShareHand},
+    pointer::Click,
+    rules::GameRules,
+    toast::Toast,
+    transport::GameTransport,
+    GameScreenState, ScreenState,
+};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    utils::{HashMap, HashSet},
+};
+use bevy_matchbox::prelude::*;
+
+/// How long to wait (in seconds) for a disconnected player before the host decides what to do.
+const RECONNECT_TIMEOUT: f32 = 30.0;
+
+/// A player who disconnected during the game.
+struct MissingPlayer {
+    id: PeerId,
+    name: String,
+    /// Whether the player was hosting, in which case someone else has taken over the room.
+    was_host: bool,
+    /// Whether the player left on purpose, rather than losing their connection.
+    left: bool,
+    timer: Timer,
+}
+
+/// Players we're waiting on, which pauses the game.
+#[derive(Resource, Default)]
+pub struct Paused {
+    missing: Vec<MissingPlayer>,
+    /// Hands shared with us by the remaining players (if we're hosting).
+    hands: HashMap<PeerId, Vec<Card>>,
+}
+
+/// Run condition for systems that shouldn't run while waiting on a disconnected player, or
+/// while a game against bots is paused because we stepped away.
+pub fn not_paused(paused: Res<Paused>, away: Res<Away>) -> bool {
+    paused.missing.is_empty() && !away.is_paused()
+}
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+struct OnScreen;
+
+/// Text on the disconnect banner.
+#[derive(Component)]
+struct BannerText;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum ButtonAction {
+    DropPlayer,
+    BotTakeover,
+    EndGame,
+}
+
+/// Pauses the game when a player in it disconnects.
+fn handle_peer_left(
+    mut events: EventReader<PeerLeft>,
+    mut share_events: EventWriter<ShareHand>,
+    mut paused: ResMut<Paused>,
+    game_info: Res<GameInfo>,
+    opponents: Res<Opponents>,
+    server_state: Res<State<ServerState>>,
+) {
+    for PeerLeft {
+        player,
+        was_host,
+        left,
+    } in events.read()
+    {
+        if !game_info.order.contains(player) {
+            continue;
+        }
+        let name = opponents
+            .0
+            .iter()
+            .find(|opponent| opponent.id == *player)
+            .map_or_else(|| String::from("Unknown"), |opponent| opponent.name.clone());
+        info!("{name} disconnected mid-game");
+        // there's no point waiting for a player who left on purpose
+        let mut timer = Timer::from_seconds(RECONNECT_TIMEOUT, TimerMode::Once);
+        if *left {
+            timer.tick(timer.duration());
+        }
+        paused.missing.push(MissingPlayer {
+            id: *player,
+            name,
+            was_host: *was_host,
+            left: *left,
+            timer,
+        });
+
+        // let the host work out which cards the player was holding
+        // if the host left, this goes to whoever is taking over the room
+        if !matches!(**server_state, ServerState::Server(_)) {
+            share_events.send(ShareHand);
+        }
+    }
+}
+
+/// Stores the hands shared with us by the remaining players.
+fn store_shared_hands(mut events: EventReader<HandShared>, mut paused: ResMut<Paused>) {
+    for HandShared { player, cards } in events.read() {
+        paused.hands.insert(*player, cards.clone());
+    }
+}
+
+/// Counts down while waiting on disconnected players.
+fn tick_timers(mut paused: ResMut<Paused>, time: Res<Time>) {
+    for missing in paused.missing.iter_mut() {
+        missing.timer.tick(time.delta());
+    }
+}
+
+/// Shows a banner while waiting on a disconnected player, with the host's choices once the wait
+/// is over.
+fn update_banner(
+    banner: Query<Entity, With<OnScreen>>,
+    mut text: Query<&mut Text, With<BannerText>>,
+    mut buttons: Query<(&ButtonAction, &mut Style)>,
+    paused: Res<Paused>,
+    server_state: Res<State<ServerState>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Some(missing) = paused.missing.first() else {
+        for entity in banner.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+
+    if banner.is_empty() {
+        spawn_banner(&asset_server, &mut commands);
+        return;
+    }
+
+    let is_host = matches!(**server_state, ServerState::Server(_));
+    let timed_out = missing.timer.finished();
+    let message = if !timed_out {
+        let remaining = missing.timer.remaining_secs().ceil();
+        format!("{} disconnected - waiting {remaining}s", missing.name)
+    } else {
+        let gone = if missing.left {
+            format!("{} left the game", missing.name)
+        } else {
+            format!("{} didn't come back", missing.name)
+        };
+        if missing.was_host && is_host {
+            format!("{gone} - you're the host now")
+        } else if missing.was_host {
+            format!("{gone} - waiting for the new host")
+        } else if is_host {
+            gone
+        } else {
+            format!("{gone} - waiting for the host")
+        }
+    };
+    if let Ok(mut text) = text.get_single_mut() {
+        if text.sections[0].value != message {
+            text.sections[0].value = message;
+        }
+    }
+
+    for (action, mut style) in &mut buttons {
+        let shown = timed_out
+            && match action {
+                ButtonAction::DropPlayer => is_host,
+                ButtonAction::BotTakeover => is_host,
+                ButtonAction::EndGame => is_host,
+            };
+        let display = if shown { Display::Flex } else { Display::None };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+}
+
+/// Draws the disconnect banner along the top of the screen.
+fn spawn_banner(asset_server: &AssetServer, commands: &mut Commands) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(20.0),
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(8.0),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section("", text_style.clone()),
+                        BannerText,
+                    ));
+
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                column_gap: Val::Px(10.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (action, label) in [
+                                (ButtonAction::DropPlayer, "Continue without them"),
+                                (ButtonAction::BotTakeover, "Let a bot play"),
+                                (ButtonAction::EndGame, "End game"),
+                            ] {
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: Style {
+                                                display: Display::None,
+                                                padding: UiRect::axes(
+                                                    Val::Px(12.0),
+                                                    Val::Px(6.0),
+                                                ),
+                                                ..default()
+                                            },
+                                            background_color: Color::WHITE.into(),
+                                            ..default()
+                                        },
+                                        action,
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn(TextBundle::from_section(
+                                            label,
+                                            TextStyle {
+                                                font: asset_server
+                                                    .load("fonts/Lato-BlackItalic.ttf"),
+                                                color: Color::BLACK,
+                                                ..text_style.clone()
+                                            },
+                                        ));
+                                    });
+                            }
+                        });
+                });
+        });
+}
+
+/// Handles the host's choice once the wait is over.
+fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
+    mut drop_events: EventWriter<DropPlayer>,
+    mut bot_events: EventWriter<BotTakeover>,
+    mut abort_events: EventWriter<AbortGame>,
+    paused: Res<Paused>,
+    bots: Res<Bots>,
+    opponents: Res<Opponents>,
+    deck: Res<Deck>,
+    discard_pile: Res<DiscardCards>,
+    main_player: Res<MainPlayer>,
+    rules: Res<GameRules>,
+) {
+    let Some(missing) = paused.missing.first() else { return; };
+    for action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match action {
+            ButtonAction::DropPlayer | ButtonAction::BotTakeover => {
+                // whatever isn't in the deck, the discard pile, or a remaining hand was theirs
+                let mut accounted: HashSet<u8> = deck
+                    .cards
+                    .iter()
+                    .chain(discard_pile.cards.iter())
+                    .chain(paused.hands.values().flatten())
+                    .chain(bots.0.values().flatten())
+                    .copied()
+                    .chain(main_player.iter())
+                    .map(|card| card.id())
+                    .collect();
+                let held = opponents
+                    .0
+                    .iter()
+                    .find(|opponent| opponent.id == missing.id)
+                    .map_or(0, |opponent| opponent.card_count);
+                let cards = Deck::for_rules(&rules)
+                    .cards
+                    .into_iter()
+                    .filter(|card| accounted.insert(card.id()))
+                    .take(held)
+                    .collect();
+                if *action == ButtonAction::BotTakeover {
+                    bot_events.send(BotTakeover {
+                        player: missing.id,
+                        cards,
+                    });
+                } else {
+                    drop_events.send(DropPlayer {
+                        player: missing.id,
+                        cards,
+                    });
+                }
+            }
+            ButtonAction::EndGame => {
+                abort_events.send(AbortGame);
+            }
+        }
+    }
+}
+
+/// Removes a player from the game, putting their cards at the bottom of the deck.
+fn drop_player(
+    mut drop_events: EventReader<DropPlayer>,
+    mut paused: ResMut<Paused>,
+    mut game_info: ResMut<GameInfo>,
+    mut opponents: ResMut<Opponents>,
+    mut deck: ResMut<Deck>,
+) {
+    for DropPlayer { player, cards } in drop_events.read() {
+        info!("Removing {player} from the game");
+        game_info.remove_player(*player);
+        opponents.0.retain(|opponent| opponent.id != *player);
+        // cards are drawn from the end, so these come up last
+        deck.cards.splice(0..0, cards.iter().copied());
+
+        paused.missing.retain(|missing| missing.id != *player);
+        if paused.missing.is_empty() {
+            paused.hands.clear();
+        }
+    }
+}
+
+/// Gives a player's seat to a bot, which plays the cards they were holding.
+fn hand_to_bot(
+    mut bot_events: EventReader<BotTakeover>,
+    mut toasts: EventWriter<Toast>,
+    mut paused: ResMut<Paused>,
+    mut bots: ResMut<Bots>,
+) {
+    for BotTakeover { player, cards } in bot_events.read() {
+        info!("A bot is taking over for {player}");
+        bots.0.insert(*player, cards.clone());
+
+        let Some(index) = paused.missing.iter().position(|missing| missing.id == *player) else {
+            continue;
+        };
+        let missing = paused.missing.remove(index);
+        if paused.missing.is_empty() {
+            paused.hands.clear();
+        }
+        toasts.send(Toast(format!("A bot is playing for {}", missing.name)));
+    }
+}
+
+/// Leaves the game when it's ended by the host, or when the host is gone.
+fn end_game(
+    mut abort_events: EventReader<AbortGame>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut screen_state: ResMut<NextState<ScreenState>>,
+    mut server_state: ResMut<NextState<ServerState>>,
+    mut game_screen_state: ResMut<NextState<GameScreenState>>,
+) {
+    if abort_events.read().next().is_none() {
+        return;
+    }
+    menu_state.set(MenuState::Main);
+    screen_state.set(ScreenState::Menu);
+    server_state.set(ServerState::None);
+    game_screen_state.set(GameScreenState::Game);
+}
+
+/// Stops waiting when leaving the game.
+fn reset_paused(mut paused: ResMut<Paused>) {
+    *paused = Paused::default();
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Paused>()
+            .add_systems(
+                Update,
+                (
+                    handle_peer_left,
+                    store_shared_hands,
+                    tick_timers,
+                    update_banner,
+                    handle_action,
+                    drop_player,
+                    hand_to_bot,
+                    end_game,
+                )
+                    .chain()
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(
+                OnExit(ScreenState::Game),
+                (reset_paused, despawn_screen::<OnScreen>),
+            );
+    }
+}
@@ -0,0 +1,190 @@
+//! Advertising and finding public rooms.
+//!
+//! Hosts of public rooms join a shared discovery room on the matchbox server and
+//! periodically announce their room code, player count, and rules to everyone browsing.
+
+use crate::{
+    challenge::ActiveChallenge,
+    menu::MenuState,
+    network::{Broadcast, MaxPlayers, PeerNames, RoomPassword, ServerState},
+    rules::GameRules,
+    settings::Settings,
+    transport::GameTransport,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use bevy_matchbox::prelude::*;
+
+/// Name of the room used for finding public rooms.
+const DISCOVERY_ROOM: &str = "v1_discovery";
+/// Seconds between room announcements.
+const ADVERT_INTERVAL: f32 = 1.0;
+/// Seconds after the last announcement before a room is considered closed.
+const ROOM_TIMEOUT: f32 = 5.0;
+
+/// Connection to the discovery room.
+#[derive(Resource)]
+pub struct DiscoverySocket(MatchboxSocket<SingleChannel>);
+
+impl DiscoverySocket {
+    fn new(server_url: &str) -> Self {
+        Self(MatchboxSocket::new_reliable(format!(
+            "{server_url}/{DISCOVERY_ROOM}"
+        )))
+    }
+}
+
+/// A public room announced by its host.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpenRoom {
+    pub code: String,
+    pub players: u8,
+    pub rules: GameRules,
+    host: PeerId,
+    last_seen: f32,
+}
+
+/// Public rooms found in the discovery room.
+#[derive(Resource, Default)]
+pub struct OpenRooms(pub Vec<OpenRoom>);
+
+/// Connects to the discovery room to look for rooms.
+pub fn open_discovery(
+    mut commands: Commands,
+    mut open_rooms: ResMut<OpenRooms>,
+    settings: Res<Settings>,
+) {
+    open_rooms.0.clear();
+    commands.insert_resource(DiscoverySocket::new(&settings.server_url));
+}
+
+/// Closes the connection to the discovery room.
+pub fn close_discovery(mut commands: Commands) {
+    commands.remove_resource::<DiscoverySocket>();
+}
+
+/// Keeps track of rooms announced in the discovery room.
+fn receive_adverts(
+    mut discovery: ResMut<DiscoverySocket>,
+    mut open_rooms: ResMut<OpenRooms>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+    match discovery.0.try_update_peers() {
+        Ok(result) => {
+            for (peer, state) in result {
+                if state == PeerState::Disconnected {
+                    open_rooms.0.retain(|room| room.host != peer);
+                }
+            }
+        }
+        Err(e) => {
+            error!("Error updating discovery peers: {e:?}");
+        }
+    }
+
+    // length of the room code, then the code, then the player count, then the rules
+    for (peer, packet) in discovery.0.receive() {
+        let len = packet.first().copied().unwrap_or_default() as usize;
+        let (Some(code), Some(&players)) = (packet.get(1..1 + len), packet.get(1 + len)) else {
+            error!("Invalid room advert: too short.");
+            continue;
+        };
+        let room = OpenRoom {
+            code: String::from_utf8_lossy(code).into_owned(),
+            players,
+            rules: GameRules::from_bytes(&packet[2 + len..]),
+            host: peer,
+            last_seen: now,
+        };
+        match open_rooms.0.iter_mut().find(|room| room.host == peer) {
+            Some(existing) => *existing = room,
+            None => open_rooms.0.push(room),
+        }
+    }
+
+    // forget rooms whose host has gone quiet
+    open_rooms
+        .0
+        .retain(|room| now - room.last_seen < ROOM_TIMEOUT);
+}
+
+/// Announces our room in the discovery room while we're hosting a public lobby.
+///
+/// Rooms made for playing a challenge aren't open to anyone else.
+fn advertise_room(
+    discovery: Option<ResMut<DiscoverySocket>>,
+    socket: Res<GameTransport>,
+    server_state: Res<State<ServerState>>,
+    password: Res<RoomPassword>,
+    peer_names: Res<PeerNames>,
+    max_players: Res<MaxPlayers>,
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    challenge: Res<ActiveChallenge>,
+    time: Res<Time>,
+    mut since_advert: Local<f32>,
+    mut broadcast: Local<Broadcast>,
+    mut commands: Commands,
+) {
+    let ServerState::Server(code) = server_state.get() else { return; };
+    if !password.0.is_empty() || challenge.is_active() {
+        return;
+    }
+    let Some(mut discovery) = discovery else {
+        commands.insert_resource(DiscoverySocket::new(&settings.server_url));
+        return;
+    };
+
+    // keep the discovery connection going, we don't expect any messages
+    if let Err(e) = discovery.0.try_update_peers() {
+        error!("Error updating discovery peers: {e:?}");
+    }
+    discovery.0.receive();
+
+    *since_advert += time.delta_seconds();
+    if *since_advert < ADVERT_INTERVAL {
+        return;
+    }
+    *since_advert = 0.0;
+
+    let players = socket
+        .connected_peers()
+        .filter(|pid| peer_names.0.contains_key(pid))
+        .count()
+        + 1;
+    // full rooms drop out of the browser once their last advert expires
+    if players >= max_players.0 as usize {
+        return;
+    }
+    let mut packet = vec![code.len() as u8];
+    packet.extend_from_slice(code.as_bytes());
+    packet.push(players as u8);
+    packet.extend(rules.to_bytes());
+    let packet = packet.into_boxed_slice();
+    for peer in broadcast.peers(discovery.0.connected_peers()) {
+        discovery.0.send(packet.clone(), *peer);
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OpenRooms>()
+            .add_systems(OnEnter(MenuState::Browse), open_discovery)
+            .add_systems(OnExit(MenuState::Browse), close_discovery)
+            .add_systems(OnExit(MenuState::Lobby), close_discovery)
+            .add_systems(
+                Update,
+                receive_adverts
+                    .run_if(in_state(MenuState::Browse))
+                    .run_if(resource_exists::<DiscoverySocket>()),
+            )
+            .add_systems(
+                Update,
+                advertise_room
+                    .run_if(in_state(MenuState::Lobby))
+                    .run_if(resource_exists::<GameTransport>()),
+            );
+    }
+}
@@ -1,24 +1,39 @@
 //! Draw and discard piles.
 
-use crate::card::{
-    Card, CardColor, CardPosition, CardSprite, CardType, CardValue, SpawnCard, CARD_ANIMATION_SPEED,
-};
+use crate::animation::Tween;
+use crate::card_art::CardFaces;
+use crate::card::{CardColor, CardPosition, CardTween, CardType, SpawnCard, CARD_SIZE};
 use crate::deck::{Deck, DiscardCards, MainPlayer};
-use crate::game_ui::hand::Hovering;
+use crate::disconnect::not_paused;
+use crate::game_ui::camera::CameraCue;
 use crate::info::GameInfo;
-use crate::network::DrawCard;
-use crate::network::ServerState;
+use crate::network::{DrawCard, Reshuffled};
+use crate::pointer::{Click, Clickable, LongPress};
+use crate::settings::Settings;
+use crate::theme::{table_theme, HostTable, Theme, TABLE_SIZE};
+use crate::toast::Toast;
+use crate::transport::GameTransport;
+use crate::tutorial::Tutorial;
 use crate::GameScreenState;
-use crate::{despawn_screen, ScreenState};
+use crate::{despawn_screen, GameSet, ScreenState};
 use bevy::prelude::{Plugin as BevyPlugin, *};
-use bevy_matchbox::prelude::*;
 
 /// Position of the draw pile.
 pub const DRAW_PILE_POS: Vec3 = Vec3::new(-92.0, 0.0, 0.01);
 /// Position of the discard pile.
 pub const DISCARD_PILE_POS: Vec3 = Vec3::new(92.0, 0.0, 0.01);
-/// Position of the player's hand.
-pub const HAND_POS: Vec3 = Vec3::new(0.0, -250.0, 0.0);
+/// How close (in pixels) a played card gets to the discard pile before it counts as landed.
+const LANDING_DISTANCE: f32 = 12.0;
+/// Number of cards left in the draw pile at which it starts warning that it's running out.
+const LOW_DECK_COUNT: usize = 10;
+/// How fast (in flashes per second) the count flashes when the draw pile is running out.
+const LOW_DECK_FLASH_SPEED: f32 = 1.5;
+/// Most cards shown when looking through the discard pile.
+const RECENT_DISCARD_COUNT: usize = 10;
+/// Size of the cards shown when looking through the discard pile.
+const RECENT_DISCARD_SIZE: Vec2 = Vec2::new(117.0, 153.0);
+/// Most cards kept on top of the discard pile. The ones underneath are hidden anyway.
+const KEPT_DISCARD_CARDS: usize = 20;
 
 /// Component for the draw pile.
 #[derive(Component)]
@@ -28,9 +43,28 @@ pub struct DrawPile;
 #[derive(Component)]
 pub struct DiscardPile;
 
-/// Indicates that the card is meant to be in the discard pile.
+/// Component for the text showing how many cards are left in the draw pile.
 #[derive(Component)]
-pub struct DiscardCard;
+struct DrawPileCount;
+
+/// Indicates that the card is meant to be in the discard pile, at the given index counting from
+/// the bottom.
+///
+/// Cards are matched to their place in the pile by index, since a pile can hold equal cards.
+#[derive(Component)]
+pub struct DiscardCard(pub usize);
+
+/// Indicates that a discarded card has reached the discard pile, so it only wobbles once.
+#[derive(Component)]
+pub struct Landed;
+
+/// Overlay fanning out the last cards played, shown when the discard pile is clicked.
+#[derive(Component)]
+struct RecentDiscards;
+
+/// Row of cards in the recent discards overlay.
+#[derive(Component)]
+struct RecentDiscardsRow;
 
 /// Indicates that the component bundle is for this screen.
 #[derive(Component)]
@@ -40,10 +74,18 @@ pub struct OnScreen;
 #[derive(Component)]
 enum ButtonAction {
     BackToMenu,
+    CloseRecentDiscards,
 }
 
-/// Draws piles and menu button.
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// Draws the table, piles, and menu button.
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    settings: Res<Settings>,
+    host_table: Res<HostTable>,
+    deck: Res<Deck>,
+) {
     // back to menu button
     commands.spawn((
         ButtonBundle {
@@ -66,13 +108,45 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         OnScreen,
     ));
 
+    // table
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: table_theme(&settings, &host_table).felt(),
+                custom_size: Some(TABLE_SIZE),
+                ..default()
+            },
+            texture: asset_server.load("textures/table/felt.png"),
+            transform: Transform::from_xyz(0.0, 0.0, -0.06),
+            ..default()
+        },
+        OnScreen,
+    ));
+
+    // outlines showing where the piles go, even when they're empty
+    for pile_position in [DRAW_PILE_POS, DISCARD_PILE_POS] {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: theme.slot,
+                    custom_size: Some(Vec2::new(164.0, 226.0)),
+                    ..default()
+                },
+                texture: asset_server.load("textures/table/slot.png"),
+                transform: Transform::from_translation(pile_position.truncate().extend(-0.04)),
+                ..default()
+            },
+            OnScreen,
+        ));
+    }
+
     // draw pile
     let mut position = DRAW_PILE_POS;
     position.z = 0.0;
     commands.spawn((
         SpriteBundle {
             sprite: Sprite {
-                // color: Color::WHITE,
+                color: settings.card_back.tint(),
                 custom_size: Some(Vec2::new(156.0, 218.0)),
                 ..default()
             },
@@ -81,6 +155,25 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             ..default()
         },
         DrawPile,
+        Clickable(CARD_SIZE),
+        OnScreen,
+    ));
+
+    // number of cards left, under the draw pile
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                deck.cards.len().to_string(),
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-Black.ttf"),
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                },
+            ),
+            transform: Transform::from_translation(Vec3::new(DRAW_PILE_POS.x, -130.0, 0.5)),
+            ..default()
+        },
+        DrawPileCount,
         OnScreen,
     ));
 
@@ -91,124 +184,304 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         GlobalTransform::default(),
         Transform::from_translation(position),
         DiscardPile,
+        Clickable(CARD_SIZE),
         OnScreen,
     ));
 }
 
 /// Handles button presses.
 fn handle_menu_action(
-    interaction_query: Query<&ButtonAction, (Changed<Interaction>, With<Button>)>,
-    mut screen_state: ResMut<NextState<ScreenState>>,
-    mut server_state: ResMut<NextState<ServerState>>,
-    mouse: Res<Input<MouseButton>>,
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
+    overlays: Query<Entity, With<RecentDiscards>>,
+    mut game_screen_state: ResMut<NextState<GameScreenState>>,
+    mut commands: Commands,
 ) {
-    for menu_button_action in &interaction_query {
-        if mouse.just_released(MouseButton::Left) {
-            match menu_button_action {
-                ButtonAction::BackToMenu => {
-                    screen_state.set(ScreenState::Menu);
-                    server_state.set(ServerState::None);
+    for menu_button_action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match menu_button_action {
+            // leaving affects everyone else, so make sure it wasn't a slip
+            ButtonAction::BackToMenu => game_screen_state.set(GameScreenState::ConfirmQuit),
+            ButtonAction::CloseRecentDiscards => {
+                for entity in &overlays {
+                    commands.entity(entity).despawn_recursive();
                 }
             }
         }
     }
 }
 
+/// Fans out the last cards played over the table when the discard pile is clicked or held,
+/// until the overlay is clicked.
+fn browse_discard_pile(
+    mut clicks: EventReader<Click>,
+    mut long_presses: EventReader<LongPress>,
+    pile: Query<(), With<DiscardPile>>,
+    overlays: Query<(), With<RecentDiscards>>,
+    discard_pile: Res<DiscardCards>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let clicked = clicks.read().any(|Click(entity)| pile.contains(*entity));
+    let held = long_presses
+        .read()
+        .any(|LongPress(entity)| pile.contains(*entity));
+    if !(clicked || held) || !overlays.is_empty() || discard_pile.cards.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(16.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            ButtonAction::CloseRecentDiscards,
+            RecentDiscards,
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                settings.language.tr("Recently played"),
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                },
+            ));
+            parent.spawn((NodeBundle::default(), RecentDiscardsRow));
+        });
+}
+
+/// Lays out the last cards played in the recent discards overlay, oldest on the left and
+/// overlapped by the ones played after it.
+fn fill_recent_discards(
+    rows: Query<(Entity, Ref<RecentDiscardsRow>)>,
+    discard_pile: Res<DiscardCards>,
+    faces: Res<CardFaces>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Ok((row_entity, row)) = rows.get_single() else { return; };
+    if !row.is_added() && !discard_pile.is_changed() {
+        return;
+    }
+
+    let start = discard_pile.cards.len().saturating_sub(RECENT_DISCARD_COUNT);
+    let mut row = commands.entity(row_entity);
+    row.despawn_descendants();
+    row.with_children(|parent| {
+        for (index, card) in discard_pile.cards[start..].iter().enumerate() {
+            parent.spawn(ImageBundle {
+                style: Style {
+                    width: Val::Px(RECENT_DISCARD_SIZE.x),
+                    height: Val::Px(RECENT_DISCARD_SIZE.y),
+                    margin: UiRect::left(if index == 0 {
+                        Val::Px(0.0)
+                    } else {
+                        Val::Px(-RECENT_DISCARD_SIZE.x * 0.45)
+                    }),
+                    ..default()
+                },
+                image: faces.texture(card, &asset_server).into(),
+                ..default()
+            });
+        }
+    });
+}
+
 /// Spawns a new card when the draw pile is clicked.
 fn draw_card(
-    // interaction_query: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
-    pile: Query<Entity, (With<DrawPile>, With<Hovering>)>,
+    mut clicks: EventReader<Click>,
+    pile: Query<(), With<DrawPile>>,
     mut spawn_events: EventWriter<SpawnCard>,
     mut draw_events: EventWriter<DrawCard>,
-    mut socket: ResMut<MatchboxSocket<SingleChannel>>,
+    mut camera_cues: EventWriter<CameraCue>,
+    mut socket: ResMut<GameTransport>,
     mut player: ResMut<MainPlayer>,
     mut deck: ResMut<Deck>,
     discard_pile: Res<DiscardCards>,
-    mouse: Res<Input<MouseButton>>,
     game_info: Res<GameInfo>,
+    tutorial: Res<Tutorial>,
 ) {
-    // ensure that draw pile is being hovered over
-    if pile.iter().next().is_none() {
+    // ensure that the draw pile was clicked
+    let mut clicked = false;
+    for Click(entity) in clicks.read() {
+        clicked |= pile.contains(*entity);
+    }
+    if !clicked {
         return;
-    };
-    if mouse.just_released(MouseButton::Left) {
-        // if top card is an uncolored wild card, don't allow drawing (we need to wait until color is chosen)
-        if let Some(top_card) = discard_pile.cards.last() {
-            if top_card.color == CardColor::Wild {
-                return;
-            }
-        }
+    }
 
-        // ensure it's the player's turn
-        let Some(own_id) = socket.id() else { return; };
-        if game_info.current_player.map_or(true, |id| own_id != id) {
+    // if top card is an uncolored wild card, don't allow drawing (we need to wait until color is chosen)
+    if let Some(top_card) = discard_pile.cards.last() {
+        if top_card.color == CardColor::Wild {
             return;
-        };
+        }
+    }
+
+    // ensure it's the player's turn, and they haven't drawn already
+    let Some(own_id) = socket.id() else { return; };
+    if game_info.current_player.map_or(true, |id| own_id != id) || game_info.drawn_card.is_some() {
+        return;
+    };
 
-        let Some(card) = deck.draw(1).iter().next().copied() else {
-           	println!("No cards left in deck");
-           	return;
-        };
-        player.cards.push(card);
+    // the tutorial only lets us draw when it asks us to
+    if !tutorial.allows_draw() {
+        return;
+    }
+
+    // draw any pending penalty, or a single card
+    let count = game_info.cards_to_draw();
+    if count > 1 {
+        camera_cues.send(CameraCue::Punch);
+    }
+    let cards = deck.draw(count as i32);
+    if cards.is_empty() {
+       	println!("No cards left in deck");
+       	return;
+    };
+    for card in cards.iter() {
+        let id = player.push(*card);
         spawn_events.send(SpawnCard {
-            card,
+            card: *card,
             position: CardPosition::Draw,
-            card_type: CardType::Hand,
+            card_type: CardType::Hand(id),
         });
-        draw_events.send(DrawCard);
+    }
+    draw_events.send(DrawCard(cards));
+}
+
+/// Shows how many cards are left when the draw pile is long pressed.
+fn inspect_draw_pile(
+    mut long_presses: EventReader<LongPress>,
+    pile: Query<(), With<DrawPile>>,
+    deck: Res<Deck>,
+    settings: Res<Settings>,
+    mut toasts: EventWriter<Toast>,
+) {
+    for LongPress(entity) in long_presses.read() {
+        if pile.contains(*entity) {
+            toasts.send(Toast(
+                settings.language.tr_with("{} cards left", deck.cards.len()),
+            ));
+        }
+    }
+}
+
+/// Updates the number of cards shown under the draw pile.
+fn update_draw_pile_count(mut texts: Query<&mut Text, With<DrawPileCount>>, deck: Res<Deck>) {
+    for mut text in &mut texts {
+        text.sections[0].value = deck.cards.len().to_string();
+    }
+}
+
+/// Flashes the draw pile count red when the pile is nearly empty, since the discard pile is
+/// shuffled back in once it runs out.
+fn flash_low_deck_warning(
+    mut texts: Query<&mut Text, With<DrawPileCount>>,
+    deck: Res<Deck>,
+    time: Res<Time>,
+) {
+    let color = if deck.cards.len() <= LOW_DECK_COUNT {
+        let phase = time.elapsed_seconds() * LOW_DECK_FLASH_SPEED * std::f32::consts::TAU;
+        let flash = phase.sin() * 0.5 + 0.5;
+        Color::rgb(1.0, 1.0 - 0.8 * flash, 1.0 - 0.8 * flash)
+    } else {
+        Color::WHITE
     };
+    for mut text in &mut texts {
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
 }
 
-/// Moves the cards from discard pile into draw pile and shuffles if the draw pile is empty.
+/// Removes the cards shuffled back into the deck from the discard pile, moving the rest down to
+/// their new places.
 // TODO: make this not disappear the card underneath immediately if we play a card with no cards in the draw pile
-fn shuffle_discard_pile(
-    mut discard_pile: ResMut<DiscardCards>,
-    mut discard_cards: Query<(Entity, &CardSprite), With<DiscardCard>>,
-    mut deck: ResMut<Deck>,
+fn clear_reshuffled_cards(
+    mut reshuffled: EventReader<Reshuffled>,
+    mut discard_cards: Query<(Entity, &mut DiscardCard)>,
     mut commands: Commands,
 ) {
-    if deck.is_empty() {
-        let len = discard_pile.cards.len();
-        if len <= 1 {
-            return;
-        }
-        let mut cards: Vec<Card> = discard_pile.cards.drain(..len - 1).collect();
-        let top_card = discard_pile.cards[0];
-        // despawn cards we removed from discard pile
-        for (entity, CardSprite(card)) in discard_cards.iter_mut() {
-            if *card == top_card {
-                continue;
+    for Reshuffled(recycled) in reshuffled.read() {
+        for (entity, mut discard) in &mut discard_cards {
+            match discard.0.checked_sub(*recycled) {
+                Some(index) => discard.0 = index,
+                None => commands.entity(entity).despawn_recursive(),
             }
-            commands.entity(entity).despawn_recursive();
         }
-        // reset wild cards
-        for mut card in cards.iter_mut() {
-            if card.value == CardValue::Seven {
-                card.color = CardColor::Wild;
-            }
-        }
-        deck.cards.append(&mut cards);
-        deck.shuffle();
     }
 }
 
-/// Moves discarded cards to the discard pile.
+/// Sends discarded cards to the discard pile, giving them a little wobble as they land.
 fn animate_card_discard(
     discard_pile: Query<&GlobalTransform, With<DiscardPile>>,
-    mut cards: Query<(Entity, &mut Transform), With<DiscardCard>>,
-    time: Res<Time>,
+    mut cards: Query<(Entity, &Transform, &mut Tween, Ref<DiscardCard>), Without<Landed>>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    let pile = discard_pile.single().compute_transform().translation;
+
+    for (entity, transform, mut tween, discard) in &mut cards {
+        // cards keep their place in the pile, and are straightened out on the way
+        let target = pile.truncate().extend(transform.translation.z);
+        tween.retarget(Transform::from_translation(target));
+
+        if transform.translation.distance(target) < LANDING_DISTANCE {
+            commands.entity(entity).insert(Landed);
+            // cards spawned on the pile are already there
+            if !discard.is_added() && !settings.reduce_motion {
+                commands.entity(entity).insert(CardTween::wobble());
+            }
+        }
+    }
+}
+
+/// Clears away the sprites of cards buried deep in the discard pile, and moves the rest down, so
+/// entities and depths don't keep building up over a long game.
+///
+/// The buried cards stay in the pile. This waits until every card has landed, as cards on their
+/// way keep the depth they started with.
+fn bury_discard_cards(
+    mut cards: Query<(Entity, &DiscardCard, &mut Transform, Has<Landed>)>,
+    mut discard_pile: ResMut<DiscardCards>,
+    mut commands: Commands,
 ) {
-    let card_speed = CARD_ANIMATION_SPEED * time.delta_seconds();
-    let target = discard_pile.single().compute_transform().translation;
-
-    for (_, mut transform) in &mut cards {
-        let mut origin = transform.translation;
-        origin.z = 0.0;
-        let distance = target - origin;
-        if distance.length() < 0.1 {
+    if cards.iter().any(|(.., landed)| !landed) {
+        return;
+    }
+    let buried = discard_pile.cards.len().saturating_sub(KEPT_DISCARD_CARDS);
+    if discard_pile.buried != buried {
+        discard_pile.buried = buried;
+    }
+
+    for (entity, DiscardCard(index), mut transform, _) in &mut cards {
+        let index = *index;
+        // cards taken back out of the pile are handled by the rollback
+        if index >= discard_pile.cards.len() {
+            continue;
+        }
+        if index < buried {
+            commands.entity(entity).despawn_recursive();
             continue;
         }
-        transform.translation += distance * card_speed;
+        let z = discard_pile.depth(index + 1);
+        if (transform.translation.z - z).abs() > 0.001 {
+            transform.translation.z = z;
+        }
     }
 }
 
@@ -220,15 +493,46 @@ impl BevyPlugin for Plugin {
             .add_systems(OnExit(ScreenState::Game), despawn_screen::<OnScreen>)
             .add_systems(
                 Update,
-                (handle_menu_action, animate_card_discard).run_if(in_state(ScreenState::Game)),
+                (
+                    handle_menu_action
+                        .in_set(GameSet::Input)
+                        .run_if(in_state(GameScreenState::Game)),
+                    (
+                        clear_reshuffled_cards,
+                        animate_card_discard,
+                        bury_discard_cards.after(animate_card_discard),
+                    )
+                        .in_set(GameSet::Animate),
+                    (
+                        inspect_draw_pile,
+                        update_draw_pile_count.run_if(resource_changed::<Deck>()),
+                        flash_low_deck_warning,
+                        fill_recent_discards,
+                    )
+                        .in_set(GameSet::Ui),
+                )
+                    .run_if(in_state(ScreenState::Game)),
             )
             // systems disabled if a different game screen is shown (winner/wild choose)
             .add_systems(
                 Update,
-                (draw_card, shuffle_discard_pile)
+                browse_discard_pile
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(in_state(GameScreenState::Game)),
+            )
+            .add_systems(
+                OnExit(GameScreenState::Game),
+                despawn_screen::<RecentDiscards>,
+            )
+            .add_systems(
+                Update,
+                draw_card
+                    .in_set(GameSet::Input)
                     .run_if(in_state(ScreenState::Game))
                     .run_if(in_state(GameScreenState::Game))
-                    .run_if(resource_exists::<MatchboxSocket<SingleChannel>>()),
+                    .run_if(not_paused)
+                    .run_if(resource_exists::<GameTransport>()),
             );
     }
 }
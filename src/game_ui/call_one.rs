@@ -0,0 +1,132 @@
+//! Buttons for calling one and catching players who forgot to.
+
+use crate::{
+    disconnect::not_paused,
+    game_ui::board::OnScreen,
+    info::OneCards,
+    network::{CallOne, CatchOne},
+    pointer::Click,
+    transport::GameTransport,
+    GameScreenState, GameSet, ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Indicates the bundle's associated button action.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum ButtonAction {
+    CallOne,
+    Catch,
+}
+
+/// Draws the call one and catch buttons, hidden until they're needed.
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    for (action, label, bottom) in [
+        (ButtonAction::CallOne, "One!", 20.0),
+        (ButtonAction::Catch, "Catch!", 96.0),
+    ] {
+        commands
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        display: Display::None,
+                        position_type: PositionType::Absolute,
+                        bottom: Val::Px(bottom),
+                        right: Val::Px(20.0),
+                        width: Val::Px(160.0),
+                        height: Val::Px(60.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    ..default()
+                },
+                action,
+                OnScreen,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                        font_size: 36.0,
+                        color: Color::BLACK,
+                    },
+                ));
+            });
+    }
+}
+
+/// Shows the call one button while we're holding one card, and the catch button while an
+/// opponent can be caught.
+fn update_buttons(
+    mut buttons: Query<(&ButtonAction, &mut Style)>,
+    mut socket: ResMut<GameTransport>,
+    one_cards: Res<OneCards>,
+) {
+    let Some(own_id) = socket.id() else { return; };
+    let can_call = one_cards
+        .0
+        .get(&own_id)
+        .is_some_and(|one| !one.called && !one.caught);
+    let can_catch = one_cards
+        .0
+        .iter()
+        .any(|(player, one)| *player != own_id && one.catchable());
+
+    for (action, mut style) in &mut buttons {
+        let shown = match action {
+            ButtonAction::CallOne => can_call,
+            ButtonAction::Catch => can_catch,
+        };
+        let display = if shown { Display::Flex } else { Display::None };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+}
+
+/// Handles button presses.
+fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
+    mut call_events: EventWriter<CallOne>,
+    mut catch_events: EventWriter<CatchOne>,
+    mut socket: ResMut<GameTransport>,
+    one_cards: Res<OneCards>,
+) {
+    for action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match action {
+            ButtonAction::CallOne => {
+                call_events.send(CallOne);
+            }
+            ButtonAction::Catch => {
+                // catch everyone who forgot
+                let own_id = socket.id();
+                for (player, one) in one_cards.0.iter() {
+                    if Some(*player) != own_id && one.catchable() {
+                        catch_events.send(CatchOne(*player));
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ScreenState::Game), setup).add_systems(
+            Update,
+            (
+                update_buttons.in_set(GameSet::Ui),
+                handle_action.in_set(GameSet::Input),
+            )
+                .run_if(in_state(ScreenState::Game))
+                .run_if(in_state(GameScreenState::Game))
+                .run_if(not_paused)
+                .run_if(resource_exists::<GameTransport>()),
+        );
+    }
+}
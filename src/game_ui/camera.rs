@@ -0,0 +1,192 @@
+//! Camera shakes and zooms that make big moments in the game land harder.
+//!
+//! The camera punches in when a draw two hits us, shakes when we're skipped, and slowly zooms
+//! in on the winner at the end of the game. These are all turned off by the reduce motion
+//! setting.
+
+use crate::{
+    info::Opponents, layout::Layout, screens::win::Win, settings::Settings, GameScreenState,
+    transport::GameTransport,
+    GameSet, MainCamera, ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// How long (in seconds) the zoom punch lasts.
+const PUNCH_TIME: f32 = 0.3;
+/// How far the camera zooms in at the start of a punch.
+const PUNCH_ZOOM: f32 = 0.06;
+/// How long (in seconds) the shake lasts.
+const SHAKE_TIME: f32 = 0.4;
+/// Furthest the camera moves while shaking, in world units.
+const SHAKE_DISTANCE: f32 = 6.0;
+/// How fast the camera shakes, in radians per second.
+const SHAKE_SPEED: f32 = 60.0;
+/// How long (in seconds) the zoom toward the winner takes.
+const WIN_ZOOM_TIME: f32 = 3.0;
+/// How far the camera ends up zoomed in on the winner.
+const WIN_ZOOM: f32 = 0.2;
+
+/// Sent when something should move the camera.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraCue {
+    /// A draw two landed on us.
+    Punch,
+    /// Our turn was skipped.
+    Shake,
+}
+
+/// Camera effects that are playing.
+#[derive(Resource, Default)]
+struct CameraMotion {
+    /// Time left in the zoom punch.
+    punch: f32,
+    /// Time left in the shake.
+    shake: f32,
+    /// Where the winner is, and how long the camera has been zooming toward them.
+    winner: Option<(Vec2, f32)>,
+}
+
+impl CameraMotion {
+    /// Returns where the camera is moved to and how much it's zoomed, where 1 is no zoom.
+    fn pose(&self, elapsed: f32) -> (Vec2, f32) {
+        let mut offset = Vec2::ZERO;
+        let mut scale = 1.0;
+
+        if self.punch > 0.0 {
+            scale -= PUNCH_ZOOM * self.punch / PUNCH_TIME;
+        }
+        if self.shake > 0.0 {
+            let strength = SHAKE_DISTANCE * self.shake / SHAKE_TIME;
+            let angle = elapsed * SHAKE_SPEED;
+            offset += Vec2::new(angle.sin(), (angle * 1.3).cos()) * strength;
+        }
+        if let Some((position, time)) = self.winner {
+            // ease in and out, so the zoom starts and settles gently
+            let t = (time / WIN_ZOOM_TIME).clamp(0.0, 1.0);
+            let eased = t * t * (3.0 - 2.0 * t);
+            scale -= WIN_ZOOM * eased;
+            offset += position * WIN_ZOOM * eased;
+        }
+        (offset, scale)
+    }
+}
+
+/// Starts effects when they're cued.
+fn start_cued_motion(
+    mut cues: EventReader<CameraCue>,
+    mut motion: ResMut<CameraMotion>,
+    settings: Res<Settings>,
+) {
+    for cue in cues.read() {
+        if settings.reduce_motion {
+            continue;
+        }
+        match cue {
+            CameraCue::Punch => motion.punch = PUNCH_TIME,
+            CameraCue::Shake => motion.shake = SHAKE_TIME,
+        }
+    }
+}
+
+/// Starts zooming toward whoever won.
+fn focus_winner(
+    mut wins: EventReader<Win>,
+    mut motion: ResMut<CameraMotion>,
+    mut socket: ResMut<GameTransport>,
+    opponents: Res<Opponents>,
+    layout: Res<Layout>,
+    settings: Res<Settings>,
+) {
+    let Some(Win(winner)) = wins.read().last() else { return; };
+    if settings.reduce_motion {
+        return;
+    }
+    let position = if socket.id() == Some(*winner) {
+        layout.hand_position()
+    } else {
+        let count = opponents.0.len();
+        let Some(index) = opponents.0.iter().position(|o| o.id == *winner) else { return; };
+        layout.opponent_position(index, count)
+    };
+    motion.winner = Some((position.truncate(), 0.0));
+}
+
+/// Plays the effects on the main camera.
+fn move_camera(
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+    mut motion: ResMut<CameraMotion>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    if settings.reduce_motion {
+        *motion = CameraMotion::default();
+    }
+    let delta = time.delta_seconds();
+    motion.punch = (motion.punch - delta).max(0.0);
+    motion.shake = (motion.shake - delta).max(0.0);
+    if let Some((_, time)) = motion.winner.as_mut() {
+        *time += delta;
+    }
+
+    let (offset, scale) = motion.pose(time.elapsed_seconds());
+    let Ok((mut transform, mut projection)) = camera.get_single_mut() else { return; };
+    if transform.translation.truncate() != offset {
+        transform.translation = offset.extend(transform.translation.z);
+    }
+    if projection.scale != scale {
+        projection.scale = scale;
+    }
+}
+
+/// Stops any effects, so the next game starts with the camera back in place.
+fn reset_motion(mut motion: ResMut<CameraMotion>) {
+    *motion = CameraMotion::default();
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraMotion>()
+            .add_event::<CameraCue>()
+            .add_systems(
+                Update,
+                (
+                    start_cued_motion,
+                    focus_winner.run_if(resource_exists::<GameTransport>()),
+                    move_camera,
+                )
+                    .chain()
+                    .in_set(GameSet::Animate),
+            )
+            .add_systems(OnExit(GameScreenState::Win), reset_motion)
+            .add_systems(OnExit(ScreenState::Game), reset_motion);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that effects start strong, and the camera is back in place once they're over.
+    #[test]
+    fn test_camera_pose() {
+        let still = CameraMotion::default();
+        assert_eq!(still.pose(1.0), (Vec2::ZERO, 1.0));
+
+        let punch = CameraMotion {
+            punch: PUNCH_TIME,
+            ..default()
+        };
+        assert_eq!(punch.pose(1.0).1, 1.0 - PUNCH_ZOOM);
+
+        let winner = Vec2::new(100.0, 50.0);
+        let zoomed = CameraMotion {
+            winner: Some((winner, WIN_ZOOM_TIME * 2.0)),
+            ..default()
+        };
+        let (offset, scale) = zoomed.pose(1.0);
+        assert_eq!(scale, 1.0 - WIN_ZOOM);
+        assert_eq!(offset, winner * WIN_ZOOM);
+    }
+}
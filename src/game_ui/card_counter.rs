@@ -0,0 +1,219 @@
+//! Overlay counting the cards of each color and value we haven't seen yet, for hosts who turn
+//! the card counter on.
+//!
+//! Only cards we can see ourselves are counted as seen: the discard pile and our hand. Cards
+//! shuffled back into the deck are unseen again.
+
+use crate::{
+    card::{Card, CardColor, CardValue},
+    deck::{Deck, DeckComposition, DiscardCards, MainPlayer},
+    game_ui::board::OnScreen,
+    rules::GameRules,
+    settings::Settings,
+    GameSet, ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Colors counted, in the order they're shown.
+const COLORS: [CardColor; 4] = [
+    CardColor::Red,
+    CardColor::Yellow,
+    CardColor::Green,
+    CardColor::Blue,
+];
+
+/// Values shown on each line of the overlay, as ranges of [`DeckComposition::VALUES`].
+const VALUE_LINES: [std::ops::Range<usize>; 2] = [0..6, 6..12];
+
+/// How many cards of each color and value haven't been seen.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct UnseenCards {
+    /// Colored cards, in the order of [`COLORS`].
+    colors: [usize; 4],
+    /// Colored cards, in the order of [`DeckComposition::VALUES`].
+    values: [usize; 12],
+    wilds: usize,
+}
+
+impl UnseenCards {
+    /// Counts the cards in the deck for the rules, less the ones that have been seen.
+    fn new(rules: &GameRules, seen: impl Iterator<Item = Card>) -> Self {
+        let mut unseen = Self::default();
+        for card in Deck::for_rules(rules).cards {
+            unseen.add(&card, 1);
+        }
+        for card in seen {
+            unseen.add(&card, -1);
+        }
+        unseen
+    }
+
+    /// Adds `count` to the card's color and value.
+    ///
+    /// Played wilds take on the color chosen for them, so they're known by their value.
+    fn add(&mut self, card: &Card, count: isize) {
+        if card.value == CardValue::Seven {
+            self.wilds = self.wilds.saturating_add_signed(count);
+            return;
+        }
+        if let Some(index) = COLORS.iter().position(|color| *color == card.color) {
+            self.colors[index] = self.colors[index].saturating_add_signed(count);
+        }
+        if let Some(index) = DeckComposition::VALUES.iter().position(|v| *v == card.value) {
+            self.values[index] = self.values[index].saturating_add_signed(count);
+        }
+    }
+}
+
+/// Text showing the unseen cards.
+#[derive(Component)]
+struct CardCounterText;
+
+/// Returns `true` if the host turned the card counter on.
+fn counter_shown(rules: Res<GameRules>) -> bool {
+    rules.card_counter
+}
+
+/// Draws the overlay in the bottom right corner, above the catch button.
+fn setup(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(172.0),
+                    right: Val::Px(20.0),
+                    padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.4).into(),
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((TextBundle::default(), CardCounterText));
+        });
+}
+
+/// Recounts the unseen cards whenever the discard pile or our hand changes.
+fn update_counter(
+    mut text: Query<&mut Text, With<CardCounterText>>,
+    discard_pile: Res<DiscardCards>,
+    main_player: Res<MainPlayer>,
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    added: Query<(), Added<CardCounterText>>,
+) {
+    let Ok(mut text) = text.get_single_mut() else { return; };
+    if !discard_pile.is_changed() && !main_player.is_changed() && added.is_empty() {
+        return;
+    }
+    let language = settings.language;
+    let seen = discard_pile.cards.iter().copied().chain(main_player.iter());
+    let unseen = UnseenCards::new(&rules, seen);
+
+    let style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 16.0,
+        color: Color::WHITE,
+    };
+    let mut sections = vec![TextSection::new(
+        format!("{}\n", language.tr("Unseen cards")),
+        style.clone(),
+    )];
+    for (color, count) in COLORS.iter().zip(unseen.colors) {
+        let name = match color {
+            CardColor::Red => "Red",
+            CardColor::Yellow => "Yellow",
+            CardColor::Green => "Green",
+            CardColor::Blue => "Blue",
+            CardColor::Wild => "Wilds",
+        };
+        sections.push(TextSection::new(
+            format!("{} {count}  ", language.tr(name)),
+            TextStyle {
+                color: color.display_color(),
+                ..style.clone()
+            },
+        ));
+    }
+    sections.push(TextSection::new(
+        format!("{} {}", language.tr("Wilds"), unseen.wilds),
+        style.clone(),
+    ));
+    for line in VALUE_LINES {
+        let entries: Vec<String> = line
+            .map(|index| {
+                let label = language.tr(DeckComposition::VALUES[index].label());
+                format!("{label}: {}", unseen.values[index])
+            })
+            .collect();
+        sections.push(TextSection::new(
+            format!("\n{}", entries.join("  ")),
+            style.clone(),
+        ));
+    }
+    text.sections = sections;
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ScreenState::Game), setup.run_if(counter_shown))
+            .add_systems(
+                Update,
+                update_counter
+                    .in_set(GameSet::Ui)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(counter_shown),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that cards we've seen come off the counts, with played wilds counted as wilds
+    /// whatever color was chosen.
+    #[test]
+    fn test_unseen_cards() {
+        let rules = GameRules::default();
+        let full = UnseenCards::new(&rules, std::iter::empty());
+        assert_eq!(full.colors, [24; 4]);
+        assert_eq!(full.values, [8; 12]);
+        assert_eq!(full.wilds, 4);
+
+        let seen = [
+            Card::new(CardColor::Red, CardValue::Skip, 1),
+            Card::new(CardColor::Red, CardValue::Zero, 2),
+            Card::new(CardColor::Blue, CardValue::Seven, 0),
+        ];
+        let unseen = UnseenCards::new(&rules, seen.into_iter());
+        assert_eq!(unseen.colors, [22, 24, 24, 24]);
+        assert_eq!(unseen.values[0], 7);
+        assert_eq!(unseen.values[9], 7);
+        assert_eq!(unseen.wilds, 3);
+
+        // the counts follow the host's deck, and never go below nothing
+        let rules = GameRules {
+            double_deck: true,
+            ..default()
+        };
+        let unseen = UnseenCards::new(&rules, seen.into_iter().chain(seen));
+        assert_eq!(unseen.colors, [44, 48, 48, 48]);
+        assert_eq!(unseen.wilds, 6);
+        let rules = GameRules {
+            deck: DeckComposition {
+                wilds: 1,
+                ..default()
+            },
+            ..default()
+        };
+        let unseen = UnseenCards::new(&rules, seen.into_iter().chain(seen));
+        assert_eq!(unseen.wilds, 0);
+    }
+}
@@ -0,0 +1,164 @@
+//! Buttons for playing or keeping a card that was just drawn, with the play after draw rule.
+
+use crate::{
+    card::CardColor,
+    deck::{DiscardCards, MainPlayer},
+    disconnect::not_paused,
+    game_ui::{
+        board::OnScreen,
+        hand::{discard_from_hand, HandCard},
+    },
+    info::GameInfo,
+    network::{KeepDrawn, PlayCard},
+    pointer::Click,
+    screens::wild::Wild,
+    settings::Settings,
+    transport::GameTransport,
+    GameScreenState, GameSet, ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Row holding the buttons, shown while we can still play the card we drew.
+#[derive(Component)]
+struct Prompt;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum ButtonAction {
+    Play,
+    Keep,
+}
+
+/// Draws the play and keep buttons, hidden until they're needed.
+fn setup(mut commands: Commands, settings: Res<Settings>, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(250.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    column_gap: Val::Px(20.0),
+                    ..default()
+                },
+                ..default()
+            },
+            Prompt,
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            for (action, label) in [
+                (ButtonAction::Play, "Play it"),
+                (ButtonAction::Keep, "Keep it"),
+            ] {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(160.0),
+                                height: Val::Px(60.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: Color::WHITE.into(),
+                            ..default()
+                        },
+                        action,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            settings.language.tr(label),
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                                font_size: 36.0,
+                                color: Color::BLACK,
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+/// Shows the buttons while it's our turn and the card we drew can still be played.
+fn update_prompt(
+    mut prompt: Query<&mut Style, With<Prompt>>,
+    mut socket: ResMut<GameTransport>,
+    game_info: Res<GameInfo>,
+) {
+    let shown = game_info.drawn_card.is_some() && game_info.current_player == socket.id();
+    let display = if shown { Display::Flex } else { Display::None };
+    for mut style in &mut prompt {
+        if style.display != display {
+            style.display = display;
+        }
+    }
+}
+
+/// Handles button presses, playing the drawn card straight onto the discard pile or keeping it.
+fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
+    mut cards: Query<(Entity, &HandCard, &mut Transform)>,
+    mut play_events: EventWriter<PlayCard>,
+    mut wild_events: EventWriter<Wild>,
+    mut keep_events: EventWriter<KeepDrawn>,
+    mut player: ResMut<MainPlayer>,
+    mut discard_pile: ResMut<DiscardCards>,
+    game_info: Res<GameInfo>,
+    mut commands: Commands,
+) {
+    let Some(drawn) = game_info.drawn_card else {
+        clicks.clear();
+        return;
+    };
+    // only one choice can be made before the turn moves on
+    let Some(action) = actions.iter_many(clicks.read().map(|click| click.0)).next() else {
+        return;
+    };
+    match action {
+        ButtonAction::Play => {
+            let Some((entity, hand_card, transform)) = cards
+                .iter_mut()
+                .find(|(_, hand_card, _)| *hand_card.card() == drawn)
+            else {
+                return;
+            };
+            let id = hand_card.id();
+            discard_from_hand(
+                entity,
+                id,
+                drawn,
+                Some(transform),
+                &mut player,
+                &mut discard_pile,
+                &mut commands,
+            );
+            if drawn.color == CardColor::Wild {
+                wild_events.send(Wild);
+            }
+            play_events.send(PlayCard(vec![drawn]));
+        }
+        ButtonAction::Keep => keep_events.send(KeepDrawn),
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ScreenState::Game), setup).add_systems(
+            Update,
+            (
+                update_prompt.in_set(GameSet::Ui),
+                handle_action.in_set(GameSet::Input),
+            )
+                .run_if(in_state(ScreenState::Game))
+                .run_if(in_state(GameScreenState::Game))
+                .run_if(not_paused)
+                .run_if(resource_exists::<GameTransport>()),
+        );
+    }
+}
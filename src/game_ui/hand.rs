@@ -1,172 +1,647 @@
 //! The cards in main player's hand.
 
-use crate::card::{Card, CardColor, CARD_ANIMATION_SPEED, CARD_SIZE};
-use crate::deck::{DiscardCards, MainPlayer};
-use crate::game_ui::board::{DiscardCard, DrawPile, HAND_POS};
+use crate::animation::Tween;
+use crate::card::{Card, CardColor, CardSprite, CARD_SIZE};
+use crate::card_art::CardFaces;
+use crate::deck::{DiscardCards, HandCardId, MainPlayer};
+use crate::disconnect::not_paused;
+use crate::game_ui::board::{DiscardCard, Landed, OnScreen};
 use crate::info::GameInfo;
-use crate::network::PlayCard;
+use crate::layout::Layout;
+use crate::network::{PlayCard, PlayRolledBack};
+use crate::pointer::{topmost, Click, Clickable, LongPress, Pointer};
+use crate::rules::GameRules;
+use crate::screens::discard_down::Selected;
 use crate::screens::wild::Wild;
-use crate::{GameScreenState, ScreenState, WorldCoords};
+use crate::settings::Settings;
+use crate::toast::Toast;
+use crate::transport::GameTransport;
+use crate::tutorial::Tutorial;
+use crate::{GameScreenState, GameSet, ScreenState, WorldCoords};
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::prelude::{Plugin as BevyPlugin, *};
-use bevy_matchbox::prelude::*;
+use serde::{Deserialize, Serialize};
 
-/// Offset for hovering cards in hand.
-const HOVER_OFFSET: f32 = 20.0;
+/// Radius of the arc the hand is bent into at full curve.
+const FAN_RADIUS: f32 = 1200.0;
+/// How far cards picked to return to the deck or staged to be played are raised.
+const SELECTED_RAISE: f32 = 60.0;
+/// Where an inspected card is shown, above everything else on the table.
+const INSPECT_POS: Vec3 = Vec3::new(0.0, 20.0, 5.0);
+/// How much bigger an inspected card is shown.
+const INSPECT_SCALE: f32 = 1.8;
+/// Most cards spread across the hand, past which the hand turns into a strip that scrolls.
+const MAX_SPREAD_CARDS: usize = 15;
 
 /// Currently hovering component.
 #[derive(Component)]
 pub struct Hovering;
 
+/// Marks a card in hand picked to be played along with the next card clicked.
+#[derive(Component)]
+pub struct Staged;
+
+/// Bigger copy of a card shown while it's being inspected.
+#[derive(Component)]
+struct Inspected;
+
 /// Card in player's hand component.
 #[derive(Component)]
 pub struct HandCard {
+    id: HandCardId,
     card: Card,
 }
 
 impl HandCard {
-    pub fn new(card: Card) -> Self {
-        Self { card }
+    pub fn new(id: HandCardId, card: Card) -> Self {
+        Self { id, card }
+    }
+
+    /// Id of the card in [`MainPlayer`], which tells it apart from equal cards.
+    pub fn id(&self) -> HandCardId {
+        self.id
+    }
+
+    /// The card being held.
+    pub fn card(&self) -> &Card {
+        &self.card
+    }
+}
+
+/// Order the cards in the player's hand are kept in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HandSort {
+    /// Cards are left where they are, with new ones added at the end.
+    #[default]
+    Unsorted,
+    /// Grouped by color, then by value, with wilds at the end.
+    Color,
+    /// Grouped by value, then by color.
+    Value,
+}
+
+impl HandSort {
+    /// All sort orders, in the order they're picked.
+    pub const ALL: [HandSort; 3] = [HandSort::Unsorted, HandSort::Color, HandSort::Value];
+
+    /// Name shown in the settings and on the sort button.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HandSort::Unsorted => "Unsorted",
+            HandSort::Color => "By color",
+            HandSort::Value => "By value",
+        }
+    }
+
+    /// Returns the sort order after this one.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|s| s == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Sorts the hand, leaving it alone if it's unsorted.
+    pub fn sort(&self, player: &mut MainPlayer) {
+        let key = |card: &Card| {
+            let color: u8 = card.color.into();
+            (color, card.value as u8, card.iteration)
+        };
+        match self {
+            HandSort::Unsorted => {}
+            HandSort::Color => player.sort_by_key(key),
+            HandSort::Value => player.sort_by_key(|card| {
+                let (color, value, iteration) = key(card);
+                (value, color, iteration)
+            }),
+        }
     }
 }
 
+/// Indicates the bundle's associated button action.
+#[derive(Component)]
+enum ButtonAction {
+    Sort,
+}
+
+/// Text on the sort button.
+#[derive(Component)]
+struct SortLabel;
+
 /// Handles clicking on a card in the player's hand.
+///
+/// With the play multiple rule, shift-clicking a card stages it, and it's played along with
+/// the clicked card if they share a value.
 fn handle_card_click(
-    mut cards: Query<(Entity, &HandCard, &mut Transform), With<Hovering>>,
-    mut socket: ResMut<MatchboxSocket<SingleChannel>>,
+    mut clicks: EventReader<Click>,
+    mut cards: Query<(Entity, &HandCard, &mut Transform, Option<&Staged>)>,
+    mut socket: ResMut<GameTransport>,
     mut discard_pile: ResMut<DiscardCards>,
     mut play_events: EventWriter<PlayCard>,
     mut wild_events: EventWriter<Wild>,
     mut player: ResMut<MainPlayer>,
     game_info: Res<GameInfo>,
-    mouse: Res<Input<MouseButton>>,
+    rules: Res<GameRules>,
+    tutorial: Res<Tutorial>,
+    keys: Res<Input<KeyCode>>,
     mut commands: Commands,
 ) {
-    if mouse.just_released(MouseButton::Left) {
+    for Click(entity) in clicks.read() {
         // ensure it's the player's turn
         let Some(own_id) = socket.id() else { return; };
         if game_info.current_player.map_or(true, |id| own_id != id) {
             return;
         };
 
-        let Some((entity, HandCard { card }, mut transform)) = cards.iter_mut().next() else { return; };
+        let Ok((_, &HandCard { id, card }, _, _)) = cards.get(*entity) else { continue; };
 
-        // ensure card can be played
-        if let Some(top_card) = discard_pile.cards.last() {
-            if !card.can_play_on(top_card) {
-                return;
-            }
+        if rules.play_multiple && keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            toggle_staged(*entity, &card, &cards, &mut commands);
+            continue;
         }
 
-        // remove card from player's hand
-        let index = player
-            .cards
+        // play any staged cards of the same value too, leading with one that can be played
+        let mut batch: Vec<(Entity, HandCardId, Card)> = cards
             .iter()
-            .position(|x| *x == *card)
-            .expect("invalid card id");
-        player.cards.remove(index);
-
-        // add card to discard pile card count and set z position to top
-        discard_pile.cards.push(*card);
-        transform.translation.z = (discard_pile.cards.len() as f32 + 1.0) * 0.01;
+            .filter(|(other, staged, _, is_staged)| {
+                is_staged.is_some() && *other != *entity && staged.card.value == card.value
+            })
+            .map(|(other, staged, _, _)| (other, staged.id, staged.card))
+            .collect();
+        batch.push((*entity, id, card));
+        let top_card = discard_pile.cards.last();
+        if let Some(lead) = batch
+            .iter()
+            .position(|(_, _, card)| game_info.can_play(card, top_card, &rules))
+        {
+            let lead = batch.remove(lead);
+            batch.insert(0, lead);
+        }
+        let played: Vec<Card> = batch.iter().map(|(_, _, card)| *card).collect();
 
-        // mark card entity as discarded
-        commands.entity(entity).remove::<Hovering>();
-        commands.entity(entity).remove::<HandCard>();
-        commands.entity(entity).insert(DiscardCard);
+        // ensure cards can be played, which after drawing is only the card drawn, and in the
+        // tutorial only the card it asks for
+        if !game_info.can_play_together(&played, player.len(), top_card, &rules)
+            || game_info.drawn_card.is_some_and(|drawn| played != [drawn])
+            || !tutorial.allows_play(&played)
+        {
+            continue;
+        }
 
-        // send card played event to game flow system
-        // card_events.send(PlayCard::new(*card, 0));
+        for (entity, id, card) in batch {
+            let transform = cards.get_mut(entity).ok().map(|(_, _, transform, _)| transform);
+            discard_from_hand(
+                entity,
+                id,
+                card,
+                transform,
+                &mut player,
+                &mut discard_pile,
+                &mut commands,
+            );
+        }
 
         if card.color == CardColor::Wild {
             wild_events.send(Wild);
         }
 
-        play_events.send(PlayCard(*card));
+        play_events.send(PlayCard(played));
     }
 }
 
-/// Moves cards to correct position in the player's hand.
-fn animate_hand_cards(
-    mut cards: Query<(&mut Transform, &HandCard)>,
+/// Moves a card from the player's hand onto the top of the discard pile.
+pub fn discard_from_hand(
+    entity: Entity,
+    id: HandCardId,
+    card: Card,
+    transform: Option<Mut<Transform>>,
+    player: &mut MainPlayer,
+    discard_pile: &mut DiscardCards,
+    commands: &mut Commands,
+) {
+    // remove card from player's hand
+    player.remove(id).expect("invalid card id");
+
+    // add card to discard pile card count and set z position to top
+    discard_pile.cards.push(card);
+    if let Some(mut transform) = transform {
+        transform.translation.z = discard_pile.depth(discard_pile.cards.len());
+    }
+
+    // mark card entity as discarded
+    commands
+        .entity(entity)
+        .remove::<(Hovering, Clickable, HandCard, Staged)>()
+        .insert(DiscardCard(discard_pile.cards.len() - 1));
+}
+
+/// Stages a long pressed card to be played along with the next card clicked, with the play
+/// multiple rule.
+fn stage_card(
+    mut long_presses: EventReader<LongPress>,
+    cards: Query<(Entity, &HandCard, &mut Transform, Option<&Staged>)>,
+    mut socket: ResMut<GameTransport>,
+    game_info: Res<GameInfo>,
+    rules: Res<GameRules>,
+    mut commands: Commands,
+) {
+    if !rules.play_multiple || game_info.current_player != socket.id() {
+        long_presses.clear();
+        return;
+    }
+    for LongPress(entity) in long_presses.read() {
+        let Ok((_, HandCard { card, .. }, _, _)) = cards.get(*entity) else { continue; };
+        toggle_staged(*entity, card, &cards, &mut commands);
+    }
+}
+
+/// Stages or unstages a card in hand.
+///
+/// Wilds can't be staged, and staging a card of a different value unstages the others.
+fn toggle_staged(
+    entity: Entity,
+    card: &Card,
+    cards: &Query<(Entity, &HandCard, &mut Transform, Option<&Staged>)>,
+    commands: &mut Commands,
+) {
+    for (other, HandCard { card: other_card, .. }, _, staged) in cards.iter() {
+        if staged.is_none() {
+            continue;
+        }
+        if other == entity || other_card.value != card.value {
+            commands.entity(other).remove::<Staged>();
+        }
+    }
+    let already_staged = cards
+        .get(entity)
+        .is_ok_and(|(_, _, _, staged)| staged.is_some());
+    if !already_staged && card.color != CardColor::Wild {
+        commands.entity(entity).insert(Staged);
+    }
+}
+
+/// Unstages cards once it's no longer our turn.
+fn clear_staged(
+    staged: Query<Entity, With<Staged>>,
+    mut socket: ResMut<GameTransport>,
+    game_info: Res<GameInfo>,
+    mut commands: Commands,
+) {
+    if game_info.current_player == socket.id() {
+        return;
+    }
+    for entity in &staged {
+        commands.entity(entity).remove::<Staged>();
+    }
+}
+
+/// Moves cards back where they belong after the host turns down a play, and tells the player
+/// why if it was theirs.
+///
+/// Our cards that left the discard pile go back into our hand, and cards we drew after the
+/// play are put back in the deck.
+fn roll_back_cards(
+    mut rollbacks: EventReader<PlayRolledBack>,
+    discard_cards: Query<(Entity, &CardSprite, &DiscardCard)>,
+    hand_cards: Query<(Entity, &HandCard)>,
+    mut game_screen_state: ResMut<NextState<GameScreenState>>,
+    mut socket: ResMut<GameTransport>,
+    mut toasts: EventWriter<Toast>,
+    discard_pile: Res<DiscardCards>,
     player: Res<MainPlayer>,
-    time: Res<Time>,
+    settings: Res<Settings>,
+    mut commands: Commands,
 ) {
-    let card_speed = CARD_ANIMATION_SPEED * time.delta_seconds();
-    let card_count = player.cards.len();
-    let center_idx = (card_count as f32 - 1.0) / 2.0;
-    let spacing = if card_count <= 7 {
-        CARD_SIZE.x / 2.0
-    } else {
-        CARD_SIZE.x / (2.0 + (card_count - 7) as f32 / 4.0)
-    };
-
-    for (mut transform, HandCard { card }) in &mut cards {
-        // find real index in player cards
-        let Some(index) = player
-            .cards
-            .iter()
-            .position(|x| *x == *card) else { continue; };
-
-        let x_offset = -spacing * (center_idx - index as f32);
-        let target = Vec3::new(x_offset, 0.0, 0.0);
-        let mut target = HAND_POS + target;
-        target.z = 0.01 * index as f32;
-        let origin = transform.translation;
-        let distance = target - origin;
-        if distance.length() < 0.01 {
+    let own_id = socket.id();
+    let mut rolled_back = false;
+    for event in rollbacks.read() {
+        rolled_back = true;
+        if own_id == Some(event.player) {
+            toasts.send(Toast(String::from(
+                settings.language.tr(event.reason.message()),
+            )));
+            // our wild may have been turned down while we were picking its color
+            game_screen_state.set(GameScreenState::Game);
+        }
+    }
+    if !rolled_back {
+        return;
+    }
+
+    // cards coming back from the discard pile take the ids no other card is showing
+    let mut taken: Vec<HandCardId> = hand_cards.iter().map(|(_, card)| card.id).collect();
+    for (entity, CardSprite(card), DiscardCard(index)) in &discard_cards {
+        if discard_pile.cards.get(*index) == Some(card) {
             continue;
         }
-        transform.translation += (target - origin) * card_speed;
+        if let Some(id) = player.find_untaken(card, &taken) {
+            taken.push(id);
+            commands
+                .entity(entity)
+                .remove::<(DiscardCard, Landed)>()
+                .insert((HandCard::new(id, *card), Clickable(CARD_SIZE)));
+        } else {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    for (entity, HandCard { id, .. }) in &hand_cards {
+        if player.position(*id).is_none() {
+            commands.entity(entity).despawn_recursive();
+        }
     }
 }
 
-/// Detects when the mouse is hovering over a card or the draw pile.
+/// Cards scrolled past at the left of a hand too big to spread out.
+#[derive(Resource, Default)]
+struct HandScroll(f32);
+
+/// Number of cards in the player's hand.
+#[derive(Component)]
+struct HandCountText;
+
+/// Returns the space between neighbouring cards in a hand of `count` cards.
+fn hand_spacing(count: usize, settings: &Settings, layout: &Layout) -> f32 {
+    // squeeze large hands together, and never spread past the edges of the screen
+    let count = count.min(MAX_SPREAD_CARDS);
+    let mut spacing = CARD_SIZE.x * (1.0 - settings.card_overlap);
+    if count > 7 {
+        spacing *= 2.0 / (2.0 + (count - 7) as f32 / 4.0);
+    }
+    if count > 1 {
+        let max_spacing = (layout.hand_width() - CARD_SIZE.x).max(0.0) / (count - 1) as f32;
+        spacing = spacing.min(max_spacing);
+    }
+    spacing
+}
+
+/// Returns where the card at `index` sits in a hand of `count` cards scrolled `scroll` cards
+/// along, and its rotation around the z axis.
+///
+/// Only [`MAX_SPREAD_CARDS`] cards are shown at once, and the rest are tucked below the screen.
+fn hand_layout(
+    index: usize,
+    count: usize,
+    scroll: f32,
+    settings: &Settings,
+    layout: &Layout,
+) -> (Vec3, f32) {
+    let shown = count.min(MAX_SPREAD_CARDS);
+    let center_idx = (shown as f32 - 1.0) / 2.0;
+    let slot = index as f32 - scroll;
+
+    // cards follow a parabola, tilted to match its slope
+    let x = -hand_spacing(count, settings, layout) * (center_idx - slot);
+    let bend = settings.fan_curve / FAN_RADIUS;
+    let y = -bend * x * x / 2.0;
+    let angle = (-bend * x).atan();
+
+    let mut position = layout.hand_position() + Vec3::new(x, y, 0.0);
+    if slot < -0.5 || slot > shown as f32 - 0.5 {
+        position.y -= CARD_SIZE.y;
+    }
+    position.z = 0.01 * index as f32;
+    (position, angle)
+}
+
+/// Moves cards to correct position in the player's hand, raising hovered, picked and staged
+/// cards.
+fn animate_hand_cards(
+    mut cards: Query<(&mut Tween, &HandCard, Option<&Hovering>, Option<&Selected>)>,
+    staged: Query<&HandCard, With<Staged>>,
+    player: Res<MainPlayer>,
+    scroll: Res<HandScroll>,
+    settings: Res<Settings>,
+    layout: Res<Layout>,
+    game_screen: Res<State<GameScreenState>>,
+) {
+    let card_count = player.len();
+
+    for (mut tween, HandCard { id, .. }, hovering, selected) in &mut cards {
+        let Some(index) = player.position(*id) else { continue; };
+
+        let (mut target, angle) = hand_layout(index, card_count, scroll.0, &settings, &layout);
+        if hovering.is_some() && **game_screen == GameScreenState::Game {
+            target.y += settings.hover_raise;
+        }
+        if selected.is_some() || staged.iter().any(|staged| staged.id == *id) {
+            target.y += SELECTED_RAISE;
+        }
+        let rotation = Quat::from_rotation_z(angle);
+        tween.retarget(Transform::from_translation(target).with_rotation(rotation));
+    }
+}
+
+/// Scrolls a hand too big to spread out with the mouse wheel, by dragging along it, or with
+/// the left and right arrow keys.
+fn scroll_hand(
+    mut wheel_events: EventReader<MouseWheel>,
+    mut scroll: ResMut<HandScroll>,
+    mut dragged_from: Local<Option<f32>>,
+    keys: Res<Input<KeyCode>>,
+    pointer: Res<Pointer>,
+    coords: Res<WorldCoords>,
+    player: Res<MainPlayer>,
+    settings: Res<Settings>,
+    layout: Res<Layout>,
+) {
+    let count = player.len();
+    let spacing = hand_spacing(count, &settings, &layout).max(1.0);
+    let mut target = scroll.0;
+    for event in wheel_events.read() {
+        target -= match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / spacing,
+        };
+    }
+    if keys.just_pressed(KeyCode::Left) {
+        target -= 1.0;
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        target += 1.0;
+    }
+
+    // the strip follows a drag that starts on the hand
+    let x = coords.0.x;
+    let on_hand = coords.0.y < layout.hand_position().y + CARD_SIZE.y / 2.0;
+    if pointer.just_pressed && on_hand {
+        *dragged_from = Some(x);
+    } else if !pointer.pressed {
+        *dragged_from = None;
+    }
+    if let Some(from) = dragged_from.as_mut() {
+        target -= (x - *from) / spacing;
+        *from = x;
+    }
+
+    // hands that fit don't scroll, and bigger ones stop at either end
+    let max_scroll = count.saturating_sub(MAX_SPREAD_CARDS) as f32;
+    let target = target.clamp(0.0, max_scroll);
+    if target != scroll.0 {
+        scroll.0 = target;
+    }
+}
+
+/// Draws the button for changing how the hand is sorted, in the bottom left corner, with the
+/// number of cards in the hand above it.
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(70.0),
+                    left: Val::Px(20.0),
+                    padding: UiRect::axes(Val::Px(12.0), Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/Lato-Black.ttf"),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                HandCountText,
+            ));
+        });
+    commands
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(20.0),
+                    left: Val::Px(20.0),
+                    height: Val::Px(40.0),
+                    padding: UiRect::horizontal(Val::Px(14.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            },
+            ButtonAction::Sort,
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/Lato-Black.ttf"),
+                        font_size: 20.0,
+                        color: Color::BLACK,
+                    },
+                ),
+                SortLabel,
+            ));
+        });
+}
+
+/// Switches to the next sort order when the sort button is clicked.
+fn handle_sort_click(
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
+    mut settings: ResMut<Settings>,
+) {
+    for action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match action {
+            ButtonAction::Sort => settings.hand_sort = settings.hand_sort.next(),
+        }
+    }
+}
+
+/// Keeps the player's hand in the chosen order as cards come and go.
+///
+/// The cards then move to their new places on their own.
+fn sort_hand(mut player: ResMut<MainPlayer>, settings: Res<Settings>) {
+    if !player.is_changed() && !settings.is_changed() {
+        return;
+    }
+    let mut sorted = player.clone();
+    settings.hand_sort.sort(&mut sorted);
+    // only write back a new order, so this doesn't keep marking the hand as changed
+    if sorted.entries() != player.entries() {
+        *player = sorted;
+    }
+}
+
+/// Shows the sort order on the sort button.
+fn update_sort_label(mut labels: Query<&mut Text, With<SortLabel>>, settings: Res<Settings>) {
+    let Ok(mut text) = labels.get_single_mut() else { return; };
+    // the label starts empty, so it's filled in the first time around
+    if !settings.is_changed() && !text.sections[0].value.is_empty() {
+        return;
+    }
+    let language = settings.language;
+    text.sections[0].value = language.tr_with("Sort: {}", language.tr(settings.hand_sort.name()));
+}
+
+/// Shows how many cards are in the player's hand.
+fn update_hand_count(
+    mut counts: Query<&mut Text, With<HandCountText>>,
+    player: Res<MainPlayer>,
+    settings: Res<Settings>,
+) {
+    let Ok(mut text) = counts.get_single_mut() else { return; };
+    // the count starts empty, so it's filled in the first time around
+    if !player.is_changed() && !settings.is_changed() && !text.sections[0].value.is_empty() {
+        return;
+    }
+    text.sections[0].value = settings.language.tr_with("{} cards", player.len());
+}
+
+/// Detects when the pointer is hovering over a card or the draw pile.
 fn detect_hover(
-    cards: Query<(Entity, &Transform), Or<(With<HandCard>, With<DrawPile>)>>,
+    objects: Query<(Entity, &Transform, &Clickable)>,
+    hovering: Query<Entity, With<Hovering>>,
     coords: Res<WorldCoords>,
     mut commands: Commands,
 ) {
-    let WorldCoords(coords) = *coords;
-    let mut top_entity: Option<Entity> = None;
-    let mut top_z = -1.0;
-    // check if card is hovered
-    for (card, transform) in &cards {
-        if coords.x > transform.translation.x - CARD_SIZE.x / 2.0
-            && coords.x < transform.translation.x + CARD_SIZE.x / 2.0
-            && coords.y > transform.translation.y - CARD_SIZE.y / 2.0
-            && coords.y < transform.translation.y + CARD_SIZE.y / 2.0
-            && transform.translation.z > top_z
-        {
-            if let Some(entity) = top_entity {
-                commands.entity(entity).remove::<Hovering>();
-            }
-            top_entity = Some(card);
-            top_z = transform.translation.z;
-        } else {
-            commands.entity(card).remove::<Hovering>();
+    let top = topmost(objects.iter(), coords.0);
+    for entity in &hovering {
+        if Some(entity) != top {
+            commands.entity(entity).remove::<Hovering>();
         }
     }
-    if let Some(entity) = top_entity {
+    if let Some(entity) = top {
         commands.entity(entity).insert(Hovering);
     }
 }
 
-/// Moves cards in hand up slightly when hovered.
-fn animate_card_hover(
-    // hand: Query<&GlobalTransform, With<PlayerHand>>,
-    mut cards: Query<&mut Transform, (With<HandCard>, With<Hovering>)>,
-    time: Res<Time>,
+/// Shows a bigger copy of a card in the player's hand while it's long pressed.
+fn inspect_card(
+    mut long_presses: EventReader<LongPress>,
+    cards: Query<&HandCard>,
+    faces: Res<CardFaces>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
 ) {
-    let card_speed = CARD_ANIMATION_SPEED * time.delta_seconds();
-    let target = HAND_POS.y + HOVER_OFFSET;
+    for LongPress(entity) in long_presses.read() {
+        let Ok(HandCard { card, .. }) = cards.get(*entity) else { continue; };
+        let mut sprite = card.sprite(INSPECT_POS, &faces, &asset_server);
+        sprite.transform.scale = Vec3::splat(INSPECT_SCALE);
+        commands.spawn((sprite, Inspected, OnScreen));
+    }
+}
 
-    for mut transform in &mut cards {
-        let current = transform.translation.y;
-        let distance = target - current;
-        if distance < 0.1 {
-            continue;
-        }
-        transform.translation.y += distance * card_speed;
+/// Hides the inspected card once the press ends.
+fn hide_inspected(
+    inspected: Query<Entity, With<Inspected>>,
+    pointer: Res<Pointer>,
+    mut commands: Commands,
+) {
+    if pointer.pressed {
+        return;
+    }
+    for entity in &inspected {
+        commands.entity(entity).despawn_recursive();
     }
 }
 
@@ -175,16 +650,132 @@ pub struct Plugin;
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
         app.add_event::<PlayCard>()
+            .init_resource::<HandScroll>()
+            .add_systems(OnEnter(ScreenState::Game), setup)
             .add_systems(
                 Update,
-                animate_hand_cards.run_if(in_state(ScreenState::Game)),
+                (
+                    (sort_hand, animate_hand_cards)
+                        .chain()
+                        .in_set(GameSet::Animate),
+                    handle_sort_click.in_set(GameSet::Input),
+                    (roll_back_cards, clear_staged)
+                        .in_set(GameSet::Rules)
+                        .run_if(resource_exists::<GameTransport>()),
+                    (
+                        inspect_card,
+                        hide_inspected,
+                        update_sort_label,
+                        update_hand_count,
+                    )
+                        .in_set(GameSet::Ui),
+                )
+                    .run_if(in_state(ScreenState::Game)),
             )
             .add_systems(
                 Update,
-                (handle_card_click, detect_hover, animate_card_hover)
+                (handle_card_click, stage_card, detect_hover, scroll_hand)
+                    .in_set(GameSet::Input)
                     .run_if(in_state(ScreenState::Game))
                     .run_if(in_state(GameScreenState::Game))
-                    .run_if(resource_exists::<MatchboxSocket<SingleChannel>>()),
+                    .run_if(not_paused)
+                    .run_if(resource_exists::<GameTransport>()),
             );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardValue;
+
+    /// Ensures that a curved hand is symmetric, with the middle card upright and highest.
+    #[test]
+    fn test_hand_layout() {
+        let settings = Settings {
+            fan_curve: 1.0,
+            ..Settings::default()
+        };
+        let layout = Layout::default();
+        let hand = layout.hand_position();
+        let (left, left_angle) = hand_layout(0, 5, 0.0, &settings, &layout);
+        let (middle, middle_angle) = hand_layout(2, 5, 0.0, &settings, &layout);
+        let (right, right_angle) = hand_layout(4, 5, 0.0, &settings, &layout);
+
+        assert_eq!(middle.x, hand.x);
+        assert_eq!(middle_angle, 0.0);
+        assert!((left.x + right.x - 2.0 * hand.x).abs() < 1e-3);
+        assert!((left.y - right.y).abs() < 1e-3 && left.y < middle.y);
+        // the left card tilts counterclockwise, the right one clockwise
+        assert!(left_angle > 0.0 && (left_angle + right_angle).abs() < 1e-6);
+
+        let flat = Settings {
+            fan_curve: 0.0,
+            ..Settings::default()
+        };
+        assert_eq!(
+            hand_layout(0, 5, 0.0, &flat, &layout),
+            (Vec3::new(left.x, hand.y, 0.0), 0.0)
+        );
+    }
+
+    /// Ensures that a big hand stays on screen, and spreads out more on a wider one.
+    #[test]
+    fn test_hand_fits_screen() {
+        let settings = Settings {
+            card_overlap: 0.0,
+            ..Settings::default()
+        };
+        let narrow = Layout::default();
+        let wide = Layout::new(Vec2::new(3440.0, 1440.0));
+
+        let (first, _) = hand_layout(0, 30, 0.0, &settings, &narrow);
+        assert!(first.x - CARD_SIZE.x / 2.0 >= -narrow.hand_width() / 2.0 - 1e-3);
+        let (wide_first, _) = hand_layout(0, 30, 0.0, &settings, &wide);
+        assert!(wide_first.x < first.x);
+    }
+
+    /// Ensures that a hand too big to spread out only shows the cards scrolled to.
+    #[test]
+    fn test_hand_scroll() {
+        let settings = Settings::default();
+        let layout = Layout::default();
+        let hand = layout.hand_position();
+        let (first, _) = hand_layout(0, 30, 0.0, &settings, &layout);
+        let (last_shown, _) = hand_layout(MAX_SPREAD_CARDS - 1, 30, 0.0, &settings, &layout);
+        let (hidden, _) = hand_layout(MAX_SPREAD_CARDS, 30, 0.0, &settings, &layout);
+        assert!(first.y > hand.y - CARD_SIZE.y && last_shown.y > hand.y - CARD_SIZE.y);
+        assert!(hidden.y <= hand.y - CARD_SIZE.y);
+
+        // scrolling along brings the later cards into the first card's place
+        let (scrolled, _) = hand_layout(MAX_SPREAD_CARDS, 30, 15.0, &settings, &layout);
+        assert_eq!(scrolled, Vec3::new(first.x, first.y, scrolled.z));
+        let (scrolled_past, _) = hand_layout(0, 30, 15.0, &settings, &layout);
+        assert!(scrolled_past.y <= hand.y - CARD_SIZE.y);
+    }
+
+    /// Ensures that sorting groups the hand by color or by value, and leaves it alone otherwise.
+    #[test]
+    fn test_hand_sort() {
+        let hand = vec![
+            Card::new(CardColor::Wild, CardValue::Seven, 0),
+            Card::new(CardColor::Blue, CardValue::Two, 1),
+            Card::new(CardColor::Red, CardValue::Skip, 1),
+            Card::new(CardColor::Red, CardValue::Two, 2),
+        ];
+
+        let mut player = MainPlayer::default();
+        player.set_cards(hand.clone());
+        let ids: Vec<HandCardId> = player.entries().iter().map(|(id, _)| *id).collect();
+        HandSort::Unsorted.sort(&mut player);
+        assert_eq!(player.cards(), hand);
+
+        HandSort::Color.sort(&mut player);
+        assert_eq!(player.cards(), vec![hand[3], hand[2], hand[1], hand[0]]);
+
+        HandSort::Value.sort(&mut player);
+        assert_eq!(player.cards(), vec![hand[3], hand[1], hand[0], hand[2]]);
+        // cards keep their ids as they move around
+        assert_eq!(player.position(ids[0]), Some(2));
+    }
+}
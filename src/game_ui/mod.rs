@@ -1,5 +1,14 @@
 //! UI components for the in-game board.
 
 pub mod board;
+pub mod call_one;
+pub mod camera;
+pub mod card_counter;
+pub mod drawn_card;
 pub mod hand;
 pub mod opponent;
+pub mod play_preview;
+pub mod standings;
+pub mod turn_clock;
+pub mod turn_order;
+pub mod win_odds;
@@ -1,12 +1,19 @@
 //! Opponent UI
 
 use crate::{
+    animation::{Easing, Tween},
     game_ui::board::OnScreen,
-    info::{GameInfo, Opponents},
-    ScreenState,
+    info::{GameInfo, Opponents, TurnTimes},
+    layout::Layout,
+    network::{ConnectionQuality, PeerConnections, PlayerIds},
+    notes::{PlayerNotes, NOTE_MAX_LEN},
+    pointer::{Click, Clickable},
+    settings::Settings,
+    text_fit, GameSet, ScreenState, WorldCoords,
 };
 use bevy::prelude::{Plugin as BevyPlugin, *};
-use bevy::sprite::MaterialMesh2dBundle;
+use bevy::sprite::{Anchor, MaterialMesh2dBundle};
+use bevy::text::Text2dBounds;
 use bevy_matchbox::matchbox_socket::PeerId;
 
 /// Opponent highlight component, shown when it's their turn.
@@ -21,58 +28,191 @@ pub struct OpponentHighlight(PeerId);
 #[derive(Component)]
 pub struct OpponentCardCount(PeerId);
 
-/// Initializes empty opponent list.
-fn setup(mut commands: Commands) {
+/// Opponent name text component.
+#[derive(Component)]
+pub struct OpponentName(PeerId);
+
+/// Tooltip shown when hovering over an opponent.
+#[derive(Component)]
+pub struct OpponentTooltip(PeerId);
+
+/// Note being written on an opponent, along with their player id.
+#[derive(Resource, Default)]
+struct EditingNote(Option<(PeerId, u64, String)>);
+
+/// Dot showing how well the connection to an opponent is doing.
+#[derive(Component)]
+pub struct OpponentConnection(PeerId);
+
+/// A card an opponent drew, on its way from the draw pile to them.
+#[derive(Component)]
+pub struct OpponentCard(pub PeerId);
+
+/// Radius of the opponent circle, including the turn highlight.
+const OPPONENT_RADIUS: f32 = 42.0;
+/// Font size of opponent names, and the smallest they're shrunk to when they're long.
+const NAME_FONT_SIZE: f32 = 30.0;
+const NAME_MIN_FONT_SIZE: f32 = 18.0;
+/// Most lines an opponent's name is broken into.
+const NAME_MAX_LINES: usize = 2;
+/// Space kept between the names of opponents next to each other.
+const NAME_MARGIN: f32 = 12.0;
+/// Widest the tooltip gets before a note on the opponent wraps onto more lines.
+const TOOLTIP_WIDTH: f32 = 240.0;
+/// How close (in pixels) a drawn card gets to the opponent before it's put away.
+const ARRIVAL_DISTANCE: f32 = 20.0;
+/// How much bigger the card count gets when a drawn card arrives, and how long (in seconds) it
+/// takes to shrink back.
+const COUNT_POP_SCALE: f32 = 1.4;
+const COUNT_POP_TIME: f32 = 0.25;
+
+/// Meshes and materials shared by every opponent's circles, so rematches don't keep adding more.
+#[derive(Resource)]
+struct OpponentAssets {
+    highlight_mesh: Handle<Mesh>,
+    icon_mesh: Handle<Mesh>,
+    dot_mesh: Handle<Mesh>,
+    /// Highlight around the current player, and around everyone else.
+    highlight: Handle<ColorMaterial>,
+    no_highlight: Handle<ColorMaterial>,
+    icon: Handle<ColorMaterial>,
+    /// Connection dots for each [`ConnectionQuality`], in the order they're declared.
+    dots: [Handle<ColorMaterial>; 3],
+}
+
+impl OpponentAssets {
+    /// Returns the material of a connection dot.
+    fn dot(&self, quality: ConnectionQuality) -> Handle<ColorMaterial> {
+        let index = match quality {
+            ConnectionQuality::Good => 0,
+            ConnectionQuality::Fair => 1,
+            ConnectionQuality::Poor => 2,
+        };
+        self.dots[index].clone()
+    }
+}
+
+/// Returns an opponent's name fitted into the space they have in the row, and its font size.
+fn fit_name(name: &str, count: usize, layout: &Layout) -> (String, f32) {
+    let max_width = layout.opponent_spacing(count) - NAME_MARGIN;
+    text_fit::fit_lines(
+        name,
+        NAME_FONT_SIZE,
+        NAME_MIN_FONT_SIZE,
+        max_width,
+        NAME_MAX_LINES,
+    )
+}
+
+/// Initializes empty opponent list, and the assets for their circles.
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
     commands.insert_resource(Opponents(Vec::new()));
+
+    let qualities = [
+        ConnectionQuality::Good,
+        ConnectionQuality::Fair,
+        ConnectionQuality::Poor,
+    ];
+    commands.insert_resource(OpponentAssets {
+        highlight_mesh: meshes.add(shape::Circle::new(OPPONENT_RADIUS).into()),
+        icon_mesh: meshes.add(shape::Circle::new(35.0).into()),
+        dot_mesh: meshes.add(shape::Circle::new(7.0).into()),
+        highlight: materials.add(ColorMaterial::from(Color::WHITE.with_a(0.15))),
+        no_highlight: materials.add(ColorMaterial::from(Color::WHITE.with_a(0.0))),
+        icon: materials.add(ColorMaterial::from(Color::WHITE)),
+        dots: qualities.map(|quality| materials.add(ColorMaterial::from(quality.color()))),
+    });
 }
 
 /// Draws circles for each opponent.
 fn draw_opponents(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    opponent_assets: Res<OpponentAssets>,
     asset_server: Res<AssetServer>,
     opponents: Res<Opponents>,
+    layout: Res<Layout>,
 ) {
-    if opponents.0.is_empty() {
-        return;
-    }
     let opponent_count = opponents.0.len();
-    let center_idx = (opponent_count - 1) as f32 / 2.0;
     for (idx, opponent) in opponents.0.iter().enumerate() {
-        let x = -160.0 * (center_idx - idx as f32);
-
         commands
             .spawn((
                 MaterialMesh2dBundle {
-                    mesh: meshes.add(shape::Circle::new(42.0).into()).into(),
-                    material: materials.add(ColorMaterial::from(Color::WHITE.with_a(0.0))),
-                    transform: Transform::from_translation(Vec3::new(x, 160.0, 1.0)),
+                    mesh: opponent_assets.highlight_mesh.clone().into(),
+                    material: opponent_assets.no_highlight.clone(),
+                    transform: Transform::from_translation(
+                        layout.opponent_position(idx, opponent_count),
+                    ),
                     ..default()
                 },
                 OpponentHighlight(opponent.id),
+                Clickable(Vec2::splat(OPPONENT_RADIUS * 2.0)),
                 OnScreen,
             ))
             .with_children(|parent| {
-                // name
-                parent.spawn(Text2dBundle {
-                    text: Text::from_section(
-                        opponent.name.clone(),
-                        TextStyle {
-                            font: asset_server.load("fonts/Lato-Black.ttf"),
-                            font_size: 30.0,
-                            color: Color::WHITE,
+                // name, which grows upwards when it's broken into lines
+                let (name, font_size) = fit_name(&opponent.name, opponent_count, &layout);
+                parent.spawn((
+                    Text2dBundle {
+                        text: Text::from_section(
+                            name,
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-Black.ttf"),
+                                font_size,
+                                color: Color::WHITE,
+                            },
+                        )
+                        .with_alignment(TextAlignment::Center),
+                        text_anchor: Anchor::BottomCenter,
+                        transform: Transform::from_translation(Vec3::new(0.0, 45.0, 2.0)),
+                        ..default()
+                    },
+                    OpponentName(opponent.id),
+                ));
+
+                // tooltip, which grows downwards when there's a note on the opponent
+                parent.spawn((
+                    Text2dBundle {
+                        text: Text::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-Black.ttf"),
+                                font_size: 20.0,
+                                color: Color::WHITE,
+                            },
+                        )
+                        .with_alignment(TextAlignment::Center),
+                        text_anchor: Anchor::TopCenter,
+                        text_2d_bounds: Text2dBounds {
+                            size: Vec2::new(TOOLTIP_WIDTH, f32::INFINITY),
                         },
-                    ),
-                    transform: Transform::from_translation(Vec3::new(0.0, 60.0, 2.0)),
-                    ..default()
-                });
+                        transform: Transform::from_translation(Vec3::new(0.0, -46.0, 2.0)),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    OpponentTooltip(opponent.id),
+                ));
+
+                // connection quality, hidden until the latency has been measured
+                parent.spawn((
+                    MaterialMesh2dBundle {
+                        mesh: opponent_assets.dot_mesh.clone().into(),
+                        material: opponent_assets.dot(ConnectionQuality::Good),
+                        transform: Transform::from_translation(Vec3::new(28.0, 28.0, 3.0)),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    OpponentConnection(opponent.id),
+                ));
 
                 parent
                     .spawn((
                         MaterialMesh2dBundle {
-                            mesh: meshes.add(shape::Circle::new(35.0).into()).into(),
-                            material: materials.add(ColorMaterial::from(Color::WHITE)),
+                            mesh: opponent_assets.icon_mesh.clone().into(),
+                            material: opponent_assets.icon.clone(),
                             transform: Transform::from_translation(Vec3::new(0.0, 0.0, 1.0)),
                             ..default()
                         },
@@ -100,6 +240,43 @@ fn draw_opponents(
     }
 }
 
+/// Moves opponents to their place in the row when the window is resized or someone leaves.
+fn place_opponents(
+    mut highlights: Query<(&mut Transform, &OpponentHighlight)>,
+    opponents: Res<Opponents>,
+    layout: Res<Layout>,
+) {
+    if !layout.is_changed() && !opponents.is_changed() {
+        return;
+    }
+    let count = opponents.0.len();
+    for (mut transform, OpponentHighlight(id)) in &mut highlights {
+        let Some(index) = opponents.0.iter().position(|o| o.id == *id) else { continue; };
+        transform.translation = layout.opponent_position(index, count);
+    }
+}
+
+/// Fits opponent names into their space again when opponents squeeze together or spread out.
+fn fit_opponent_names(
+    mut names: Query<(&mut Text, &OpponentName)>,
+    opponents: Res<Opponents>,
+    layout: Res<Layout>,
+) {
+    if !layout.is_changed() && !opponents.is_changed() {
+        return;
+    }
+    let count = opponents.0.len();
+    for (mut text, OpponentName(id)) in &mut names {
+        let Some(opponent) = opponents.0.iter().find(|o| o.id == *id) else { continue; };
+        let (name, font_size) = fit_name(&opponent.name, count, &layout);
+        let section = &mut text.sections[0];
+        if section.value != name || section.style.font_size != font_size {
+            section.value = name;
+            section.style.font_size = font_size;
+        }
+    }
+}
+
 /// Updates opponent card count text.
 fn update_opponent_card_count(
     mut entities: Query<(&mut Text, &OpponentCardCount)>,
@@ -113,20 +290,174 @@ fn update_opponent_card_count(
     }
 }
 
+/// Sends the cards opponents draw over to them, popping their card count as each one arrives.
+fn animate_opponent_draws(
+    mut cards: Query<(Entity, &Transform, &mut Tween, &OpponentCard)>,
+    mut counts: Query<(Entity, &mut Transform, &OpponentCardCount), Without<OpponentCard>>,
+    avatars: Query<(&Transform, &OpponentHighlight), Without<OpponentCardCount>>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    for (entity, transform, mut tween, OpponentCard(id)) in &mut cards {
+        // the opponent may have left while the card was on its way
+        let Some((avatar, _)) = avatars.iter().find(|(_, avatar)| avatar.0 == *id) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        let target = avatar.translation.truncate().extend(transform.translation.z);
+        tween.retarget(Transform::from_translation(target));
+        if transform.translation.distance(target) >= ARRIVAL_DISTANCE {
+            continue;
+        }
+
+        commands.entity(entity).despawn_recursive();
+        if settings.reduce_motion {
+            continue;
+        }
+        for (count_entity, mut count_transform, OpponentCardCount(count_id)) in &mut counts {
+            if count_id == id {
+                count_transform.scale = Vec3::splat(COUNT_POP_SCALE);
+                commands.entity(count_entity).insert(Tween::scale(
+                    Vec3::ONE,
+                    COUNT_POP_TIME,
+                    Easing::EaseOut,
+                ));
+            }
+        }
+    }
+}
+
 /// Enables opponent highlight when it's their turn.
 fn update_opponent_highlight(
-    entities: Query<(&OpponentHighlight, &Handle<ColorMaterial>)>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut entities: Query<(&OpponentHighlight, &mut Handle<ColorMaterial>)>,
+    opponent_assets: Res<OpponentAssets>,
     game_info: Res<GameInfo>,
 ) {
     let Some(current_player) = game_info.current_player else { return; };
-    for (OpponentHighlight(id), material_handle) in entities.iter() {
-        if let Some(material) = materials.get_mut(material_handle.id()) {
-            material.color = if current_player == *id {
-                Color::WHITE.with_a(0.15)
-            } else {
-                Color::WHITE.with_a(0.0)
-            };
+    for (OpponentHighlight(id), mut material) in &mut entities {
+        let highlight = if current_player == *id {
+            &opponent_assets.highlight
+        } else {
+            &opponent_assets.no_highlight
+        };
+        if *material != *highlight {
+            *material = highlight.clone();
+        }
+    }
+}
+
+/// Colors each opponent's connection dot by how well the connection is doing.
+fn update_opponent_connection(
+    mut dots: Query<(&OpponentConnection, &mut Handle<ColorMaterial>, &mut Visibility)>,
+    opponent_assets: Res<OpponentAssets>,
+    connections: Res<PeerConnections>,
+    time: Res<Time>,
+) {
+    for (OpponentConnection(id), mut material, mut visibility) in &mut dots {
+        let Some(quality) = connections.quality(id, time.elapsed_seconds()) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        let dot = opponent_assets.dot(quality);
+        if *material != dot {
+            *material = dot;
+        }
+    }
+}
+
+/// Starts a note on an opponent when they're clicked, and types into it until Enter is pressed
+/// or they're clicked again.
+fn edit_notes(
+    mut clicks: EventReader<Click>,
+    mut char_evr: EventReader<ReceivedCharacter>,
+    mut editing: ResMut<EditingNote>,
+    mut notes: ResMut<PlayerNotes>,
+    opponents: Query<&OpponentHighlight>,
+    player_ids: Res<PlayerIds>,
+    keys: Res<Input<KeyCode>>,
+) {
+    for Click(entity) in clicks.read() {
+        let Ok(OpponentHighlight(id)) = opponents.get(*entity) else { continue; };
+        // clicking the opponent again finishes the note
+        if let Some((peer, player_id, note)) = editing.0.take() {
+            notes.set(player_id, &note);
+            if peer == *id {
+                continue;
+            }
+        }
+        // bots don't have a player id to keep notes under
+        let Some(player_id) = player_ids.0.get(id).copied() else { continue; };
+        let note = notes.get(player_id).unwrap_or_default().to_string();
+        editing.0 = Some((*id, player_id, note));
+    }
+
+    let Some((_, player_id, note)) = editing.0.as_mut() else {
+        char_evr.clear();
+        return;
+    };
+    if keys.just_pressed(KeyCode::Return) {
+        notes.set(*player_id, note);
+        editing.0 = None;
+    } else if keys.just_pressed(KeyCode::Back) {
+        note.pop();
+    } else {
+        for ev in char_evr.read() {
+            if note.chars().count() < NOTE_MAX_LEN && !ev.char.is_control() {
+                note.push(ev.char);
+            }
+        }
+    }
+}
+
+/// Keeps a note that was still being written when leaving the game.
+fn finish_note(mut editing: ResMut<EditingNote>, mut notes: ResMut<PlayerNotes>) {
+    if let Some((_, player_id, note)) = editing.0.take() {
+        notes.set(player_id, &note);
+    }
+}
+
+/// Shows the tooltip for the opponent being hovered over, or whose note is being written, with
+/// our note on them.
+fn update_opponent_tooltip(
+    opponents: Query<(&OpponentHighlight, &GlobalTransform)>,
+    mut tooltips: Query<(&mut Text, &mut Visibility, &OpponentTooltip)>,
+    turn_times: Res<TurnTimes>,
+    coords: Res<WorldCoords>,
+    editing: Res<EditingNote>,
+    notes: Res<PlayerNotes>,
+    player_ids: Res<PlayerIds>,
+    settings: Res<Settings>,
+) {
+    let hovered = opponents
+        .iter()
+        .find(|(_, transform)| {
+            transform.translation().truncate().distance(coords.0) < OPPONENT_RADIUS
+        })
+        .map(|(OpponentHighlight(id), _)| *id);
+
+    for (mut text, mut visibility, OpponentTooltip(id)) in &mut tooltips {
+        let editing = editing.0.as_ref().filter(|(peer, ..)| peer == id);
+        if hovered != Some(*id) && editing.is_none() {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Inherited;
+        let mut value = match turn_times.average(id) {
+            Some(average) => format!("Avg turn: {average:.1}s"),
+            None => String::from("Avg turn: -"),
+        };
+        let note = player_ids.0.get(id).and_then(|player_id| notes.get(*player_id));
+        match (editing, note) {
+            (Some((.., note)), _) if note.is_empty() => {
+                value = format!("{value}\n{}|", settings.language.tr("Type a note"));
+            }
+            (Some((.., note)), _) => value = format!("{value}\n{note}|"),
+            (None, Some(note)) => value = format!("{value}\n{note}"),
+            (None, None) => {}
+        }
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
         }
     }
 }
@@ -135,11 +466,26 @@ pub struct Plugin;
 
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup)
+        app.init_resource::<EditingNote>()
+            .add_systems(Startup, setup)
             .add_systems(OnEnter(ScreenState::Game), draw_opponents)
+            .add_systems(OnExit(ScreenState::Game), finish_note)
             .add_systems(
                 Update,
-                (update_opponent_card_count, update_opponent_highlight)
+                (
+                    edit_notes.in_set(GameSet::Input),
+                    (place_opponents, animate_opponent_draws)
+                        .chain()
+                        .in_set(GameSet::Animate),
+                    (
+                        fit_opponent_names,
+                        update_opponent_card_count,
+                        update_opponent_highlight,
+                        update_opponent_connection,
+                        update_opponent_tooltip,
+                    )
+                        .in_set(GameSet::Ui),
+                )
                     .run_if(in_state(ScreenState::Game)),
             );
     }
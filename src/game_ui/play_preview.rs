@@ -0,0 +1,134 @@
+//! Enlarged view of the card an opponent just played, so it can be made out on small screens.
+//!
+//! The preview pops up in the middle of the screen for a moment while the card itself flies to
+//! the discard pile underneath.
+
+use crate::{
+    animation::{Easing, Tween},
+    card::CARD_SIZE,
+    card_art::CardFaces,
+    game_ui::board::OnScreen,
+    info::Opponents,
+    network::OpponentPlayed,
+    settings::Settings,
+    text_fit, GameSet, ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// How long (in seconds) the preview stays up, including growing and shrinking.
+const PREVIEW_TIME: f32 = 1.1;
+/// How long (in seconds) the preview takes to grow and to shrink away.
+const POP_TIME: f32 = 0.15;
+/// Size of the previewed card.
+const PREVIEW_CARD_SIZE: Vec2 = Vec2::new(CARD_SIZE.x * 1.2, CARD_SIZE.y * 1.2);
+/// Longest the player's name can be.
+const NAME_WIDTH: f32 = 260.0;
+
+/// The preview, with the time (in seconds) it's been up.
+#[derive(Component)]
+struct PlayPreview(f32);
+
+/// Shows the top card of each opponent play, replacing any preview still up.
+fn show_preview(
+    mut played_events: EventReader<OpponentPlayed>,
+    previews: Query<Entity, With<PlayPreview>>,
+    opponents: Res<Opponents>,
+    settings: Res<Settings>,
+    faces: Res<CardFaces>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Some(OpponentPlayed { player, cards }) = played_events.read().last() else { return; };
+    let Some(card) = cards.last() else { return; };
+    for entity in &previews {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let language = settings.language;
+    let name = opponents
+        .0
+        .iter()
+        .find(|opponent| opponent.id == *player)
+        .map_or(language.tr("Unknown"), |opponent| &opponent.name);
+    let mut caption = language.tr_with("{} played", text_fit::truncate(name, 28.0, NAME_WIDTH));
+    if cards.len() > 1 {
+        caption = format!("{caption}\n{}", language.tr_with("{} cards", cards.len()));
+    }
+
+    // grow in from a little smaller, unless motion is turned down
+    let start = if settings.reduce_motion { 1.0 } else { 0.6 };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                transform: Transform::from_scale(Vec3::splat(start)),
+                ..default()
+            },
+            Tween::scale(Vec3::ONE, POP_TIME, Easing::EaseOut),
+            PlayPreview(0.0),
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    caption,
+                    TextStyle {
+                        font: asset_server.load("fonts/Lato-Black.ttf"),
+                        font_size: 28.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+            );
+            parent.spawn(ImageBundle {
+                style: Style {
+                    width: Val::Px(PREVIEW_CARD_SIZE.x),
+                    height: Val::Px(PREVIEW_CARD_SIZE.y),
+                    ..default()
+                },
+                image: faces.texture(card, &asset_server).into(),
+                ..default()
+            });
+        });
+}
+
+/// Shrinks the preview away once it's been up long enough.
+fn hide_preview(
+    mut previews: Query<(Entity, &mut PlayPreview, &mut Tween)>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut preview, mut tween) in &mut previews {
+        preview.0 += time.delta_seconds();
+        if preview.0 >= PREVIEW_TIME {
+            commands.entity(entity).despawn_recursive();
+        } else if preview.0 >= PREVIEW_TIME - POP_TIME && !settings.reduce_motion {
+            tween.retarget(Transform::from_scale(Vec3::ZERO));
+        }
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (show_preview, hide_preview)
+                .chain()
+                .in_set(GameSet::Ui)
+                .run_if(in_state(ScreenState::Game)),
+        );
+    }
+}
@@ -0,0 +1,281 @@
+//! Match standings, shown over the board during a match.
+
+use crate::{
+    game_ui::board::OnScreen,
+    i18n::Language,
+    info::{GameInfo, Opponents},
+    match_mode::{in_match, MatchFormat, MatchStandings, Standing},
+    pointer::Click,
+    settings::Settings,
+    text_fit, GameSet, ScreenState,
+    transport::GameTransport,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use bevy_matchbox::prelude::*;
+
+/// Width of the names in the standings, and the smallest they're shrunk to.
+const NAME_WIDTH: f32 = 220.0;
+const NAME_MIN_FONT_SIZE: f32 = 16.0;
+
+/// Button that shows and hides the standings.
+#[derive(Component)]
+struct StandingsButton;
+
+/// Panel listing the standings, hidden until it's opened.
+#[derive(Component)]
+struct StandingsPanel;
+
+/// A player's line in the standings.
+pub struct StandingRow {
+    pub name: String,
+    pub standing: Standing,
+}
+
+/// Returns the players in the match with their standings, leader first.
+pub fn standing_rows(
+    standings: &MatchStandings,
+    format: MatchFormat,
+    game_info: &GameInfo,
+    opponents: &Opponents,
+    own_id: Option<PeerId>,
+    settings: &Settings,
+) -> Vec<StandingRow> {
+    standings
+        .ranked(&game_info.order, format)
+        .into_iter()
+        .map(|(player, standing)| {
+            let name = if Some(player) == own_id {
+                settings.username.clone()
+            } else {
+                opponents
+                    .0
+                    .iter()
+                    .find(|opponent| opponent.id == player)
+                    .map_or_else(
+                        || String::from(settings.language.tr("Unknown")),
+                        |opponent| opponent.name.clone(),
+                    )
+            };
+            StandingRow { name, standing }
+        })
+        .collect()
+}
+
+/// Spawns a table of the standings, with each player's wins and points.
+pub fn spawn_standings(
+    parent: &mut ChildBuilder,
+    rows: &[StandingRow],
+    language: Language,
+    font: Handle<Font>,
+) {
+    let text_style = TextStyle {
+        font,
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+    let header_style = TextStyle {
+        color: Color::GRAY,
+        font_size: 18.0,
+        ..text_style.clone()
+    };
+    let cell = |width: f32| Style {
+        width: Val::Px(width),
+        ..default()
+    };
+
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(NodeBundle::default()).with_children(|parent| {
+                parent.spawn(
+                    TextBundle::from_section("", header_style.clone()).with_style(cell(NAME_WIDTH)),
+                );
+                parent.spawn(
+                    TextBundle::from_section(language.tr("Wins"), header_style.clone())
+                        .with_style(cell(80.0)),
+                );
+                parent.spawn(
+                    TextBundle::from_section(language.tr("Points"), header_style.clone())
+                        .with_style(cell(80.0)),
+                );
+            });
+
+            for (place, row) in rows.iter().enumerate() {
+                parent.spawn(NodeBundle::default()).with_children(|parent| {
+                    let (name, font_size) = text_fit::fit(
+                        &format!("{}. {}", place + 1, row.name),
+                        text_style.font_size,
+                        NAME_MIN_FONT_SIZE,
+                        NAME_WIDTH,
+                    );
+                    parent.spawn(
+                        TextBundle::from_section(
+                            name,
+                            TextStyle {
+                                font_size,
+                                ..text_style.clone()
+                            },
+                        )
+                        .with_style(cell(NAME_WIDTH)),
+                    );
+                    parent.spawn(
+                        TextBundle::from_section(row.standing.wins.to_string(), text_style.clone())
+                            .with_style(cell(80.0)),
+                    );
+                    parent.spawn(
+                        TextBundle::from_section(
+                            row.standing.points.to_string(),
+                            text_style.clone(),
+                        )
+                        .with_style(cell(80.0)),
+                    );
+                });
+            }
+        });
+}
+
+/// Draws the standings button, and the panel it opens.
+fn setup(settings: Res<Settings>, asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(40.0),
+                    right: Val::Px(106.0),
+                    width: Val::Px(140.0),
+                    height: Val::Px(36.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            },
+            StandingsButton,
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                settings.language.tr("Standings"),
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                    font_size: 20.0,
+                    color: Color::BLACK,
+                },
+            ));
+        });
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                top: Val::Px(96.0),
+                right: Val::Px(40.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+            z_index: ZIndex::Global(1),
+            ..default()
+        },
+        StandingsPanel,
+        OnScreen,
+    ));
+}
+
+/// Opens and closes the standings with the button or the tab key.
+fn toggle_standings(
+    mut clicks: EventReader<Click>,
+    buttons: Query<(), With<StandingsButton>>,
+    mut panel: Query<&mut Style, With<StandingsPanel>>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let clicked = clicks.read().any(|click| buttons.contains(click.0));
+    if !clicked && !keys.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    for mut style in &mut panel {
+        style.display = match style.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+/// Rebuilds the standings while they're open, when they're opened or change.
+fn update_standings(
+    panel: Query<(Entity, Ref<Style>), With<StandingsPanel>>,
+    mut socket: ResMut<GameTransport>,
+    standings: Res<MatchStandings>,
+    format: Res<MatchFormat>,
+    game_info: Res<GameInfo>,
+    opponents: Res<Opponents>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Ok((panel_entity, style)) = panel.get_single() else { return; };
+    if style.display == Display::None || (!style.is_changed() && !standings.is_changed()) {
+        return;
+    }
+
+    let language = settings.language;
+    let rows = standing_rows(
+        &standings,
+        *format,
+        &game_info,
+        &opponents,
+        socket.id(),
+        &settings,
+    );
+    let font = asset_server.load("fonts/Lato-Black.ttf");
+    let title = format!(
+        "{}, {}",
+        format.label(language),
+        language.tr_with("round {}", standings.round())
+    );
+
+    let mut panel = commands.entity(panel_entity);
+    panel.despawn_descendants();
+    panel.with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            title,
+            TextStyle {
+                font: font.clone(),
+                font_size: 26.0,
+                color: Color::WHITE,
+            },
+        ));
+        spawn_standings(parent, &rows, language, font);
+    });
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ScreenState::Game), setup.run_if(in_match))
+            .add_systems(
+                Update,
+                (
+                    toggle_standings.in_set(GameSet::Input),
+                    update_standings.in_set(GameSet::Ui),
+                )
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(in_match)
+                    .run_if(resource_exists::<MatchStandings>())
+                    .run_if(resource_exists::<GameTransport>()),
+            );
+    }
+}
@@ -0,0 +1,85 @@
+//! Clock showing how long the current turn has been going for.
+
+use crate::{
+    game_ui::board::OnScreen,
+    info::{GameInfo, Opponents, TurnTimes},
+    transport::GameTransport,
+    GameSet, ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Text showing the turn clock.
+#[derive(Component)]
+struct TurnClockText;
+
+/// Draws the turn clock in the top left corner.
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/Lato-Black.ttf"),
+                font_size: 24.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(20.0),
+            left: Val::Px(20.0),
+            ..default()
+        }),
+        TurnClockText,
+        OnScreen,
+    ));
+}
+
+/// Shows whose turn it is and for how long, following the host's clock.
+fn update_turn_clock(
+    mut text: Query<&mut Text, With<TurnClockText>>,
+    mut socket: ResMut<GameTransport>,
+    game_info: Res<GameInfo>,
+    opponents: Res<Opponents>,
+    turn_times: Res<TurnTimes>,
+    time: Res<Time>,
+) {
+    let Ok(mut text) = text.get_single_mut() else { return; };
+    let message = match (
+        game_info.current_player,
+        turn_times.elapsed(time.elapsed_seconds()),
+    ) {
+        (Some(player), Some((_, elapsed))) => {
+            let seconds = elapsed.max(0.0) as u32;
+            let clock = format!("{}:{:02}", seconds / 60, seconds % 60);
+            if socket.id() == Some(player) {
+                format!("Your turn - {clock}")
+            } else {
+                let name = opponents
+                    .0
+                    .iter()
+                    .find(|opponent| opponent.id == player)
+                    .map_or("Unknown", |opponent| opponent.name.as_str());
+                format!("{name}'s turn - {clock}")
+            }
+        }
+        _ => String::new(),
+    };
+    if text.sections[0].value != message {
+        text.sections[0].value = message;
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ScreenState::Game), setup)
+            .add_systems(
+                Update,
+                update_turn_clock
+                    .in_set(GameSet::Ui)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(resource_exists::<GameTransport>()),
+            );
+    }
+}
@@ -0,0 +1,270 @@
+//! Strip showing the order of play, which flips over each time the direction changes.
+//!
+//! Every reverse gets its own flip, even when several land at once, so chains of stacked
+//! reverses can be followed. The sound that goes with them is held back if they come too fast.
+
+use crate::{
+    audio::{PlaySound, Sound},
+    game_ui::board::OnScreen,
+    info::{Direction, GameInfo, Opponents},
+    settings::Settings,
+    text_fit, GameSet, ScreenState,
+    transport::GameTransport,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use bevy_matchbox::prelude::PeerId;
+use std::f32::consts::PI;
+
+/// How long (in seconds) the strip takes to flip over.
+const FLIP_DURATION: f32 = 0.3;
+/// Shortest time (in seconds) between reverse sounds.
+const SOUND_COOLDOWN: f32 = 0.5;
+/// Longest a name in the strip can be.
+const NAME_WIDTH: f32 = 90.0;
+/// Color of the player whose turn it is.
+const CURRENT_COLOR: Color = Color::rgb(0.95, 0.75, 0.2);
+
+/// The strip, which is scaled to flip it.
+#[derive(Component)]
+struct TurnOrderStrip;
+
+/// Text listing the players in the strip.
+#[derive(Component)]
+struct TurnOrderText;
+
+/// Changes of direction being shown by the strip.
+#[derive(Resource)]
+struct StripFlips {
+    /// Changes of direction seen so far.
+    seen: u32,
+    /// Flips still to be played.
+    pending: u32,
+    /// Time (in seconds) through the current flip, if one is playing.
+    elapsed: Option<f32>,
+    /// Direction shown by the strip, which changes halfway through each flip.
+    shown: Direction,
+    /// When the reverse sound last played.
+    last_sound: Option<f32>,
+}
+
+impl Default for StripFlips {
+    fn default() -> Self {
+        Self {
+            seen: 0,
+            pending: 0,
+            elapsed: None,
+            shown: Direction::Clockwise,
+            last_sound: None,
+        }
+    }
+}
+
+/// Returns how wide the strip is (0 to 1) at a point through a flip, easing in and out.
+fn flip_width(progress: f32) -> f32 {
+    let progress = progress.clamp(0.0, 1.0);
+    let eased = progress * progress * (3.0 - 2.0 * progress);
+    (PI * eased).cos().abs()
+}
+
+/// Draws the strip in the top left corner, under the turn clock.
+fn setup(mut commands: Commands, mut flips: ResMut<StripFlips>, asset_server: Res<AssetServer>) {
+    *flips = StripFlips::default();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(84.0),
+                    left: Val::Px(20.0),
+                    padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.4).into(),
+                ..default()
+            },
+            TurnOrderStrip,
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/Lato-Black.ttf"),
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                TurnOrderText,
+            ));
+        });
+}
+
+/// Queues a flip for each change of direction, with a sound that's held back if they come too
+/// fast.
+fn follow_reversals(
+    mut flips: ResMut<StripFlips>,
+    mut sound_events: EventWriter<PlaySound>,
+    game_info: Res<GameInfo>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    if game_info.reversals == flips.seen {
+        return;
+    }
+    // the count goes back down when a game restarts or a play is rolled back
+    let new = game_info.reversals.saturating_sub(flips.seen);
+    flips.seen = game_info.reversals;
+    if new == 0 {
+        return;
+    }
+
+    if !settings.reduce_motion {
+        flips.pending += new;
+    }
+    let now = time.elapsed_seconds();
+    let cooling_down =
+        matches!(flips.last_sound, Some(last_sound) if now - last_sound < SOUND_COOLDOWN);
+    if !cooling_down {
+        sound_events.send(PlaySound(Sound::Reverse));
+        flips.last_sound = Some(now);
+    }
+}
+
+/// Plays the queued flips one after another.
+fn animate_strip(
+    mut strip: Query<&mut Transform, With<TurnOrderStrip>>,
+    mut flips: ResMut<StripFlips>,
+    game_info: Res<GameInfo>,
+    time: Res<Time>,
+) {
+    let Ok(mut transform) = strip.get_single_mut() else { return; };
+    if flips.pending == 0 {
+        // follow the game's direction when there's nothing to show, like after a sync
+        if flips.shown != game_info.direction {
+            flips.shown = game_info.direction;
+        }
+        return;
+    }
+
+    let last_progress = flips.elapsed.unwrap_or(0.0) / FLIP_DURATION;
+    let elapsed = flips.elapsed.unwrap_or(0.0) + time.delta_seconds();
+    let progress = elapsed / FLIP_DURATION;
+    // turn the strip around once it's edge on
+    if progress >= 0.5 && last_progress < 0.5 {
+        flips.shown = match flips.shown {
+            Direction::Clockwise => Direction::CounterClockwise,
+            Direction::CounterClockwise => Direction::Clockwise,
+        };
+    }
+
+    if progress >= 1.0 {
+        flips.pending -= 1;
+        flips.elapsed = None;
+        transform.scale.x = 1.0;
+    } else {
+        flips.elapsed = Some(elapsed);
+        transform.scale.x = flip_width(progress);
+    }
+}
+
+/// Lists the players in order of play, with arrows pointing the way play is going.
+fn update_strip(
+    mut text: Query<&mut Text, With<TurnOrderText>>,
+    mut socket: ResMut<GameTransport>,
+    flips: Res<StripFlips>,
+    game_info: Res<GameInfo>,
+    opponents: Res<Opponents>,
+    settings: Res<Settings>,
+) {
+    let Ok(mut text) = text.get_single_mut() else { return; };
+    if !flips.is_changed() && !game_info.is_changed() && !opponents.is_changed() {
+        return;
+    }
+
+    let style = TextStyle {
+        color: Color::WHITE,
+        ..text.sections[0].style.clone()
+    };
+    let own_id = socket.id();
+    let separator = match flips.shown {
+        Direction::Clockwise => " > ",
+        Direction::CounterClockwise => " < ",
+    };
+    let name = |player: &PeerId| {
+        if Some(*player) == own_id {
+            String::from(settings.language.tr("You"))
+        } else {
+            let name = opponents
+                .0
+                .iter()
+                .find(|opponent| opponent.id == *player)
+                .map_or(settings.language.tr("Unknown"), |opponent| &opponent.name);
+            text_fit::truncate(name, style.font_size, NAME_WIDTH)
+        }
+    };
+    let mut sections = Vec::new();
+    for (index, player) in game_info.order.iter().enumerate() {
+        if index > 0 {
+            sections.push(TextSection::new(separator, style.clone()));
+        }
+        let name = name(player);
+        let color = if game_info.current_player == Some(*player) {
+            CURRENT_COLOR
+        } else {
+            Color::WHITE
+        };
+        sections.push(TextSection::new(
+            name,
+            TextStyle {
+                color,
+                ..style.clone()
+            },
+        ));
+    }
+    // the player before the first one dealt, and the deal passes round the table each round
+    if let [_, .., dealer] = game_info.order[..] {
+        sections.push(TextSection::new(
+            format!("\n{}", settings.language.tr_with("Dealer: {}", name(&dealer))),
+            TextStyle {
+                color: Color::rgb(0.75, 0.75, 0.75),
+                ..style.clone()
+            },
+        ));
+    }
+    if sections.is_empty() {
+        sections.push(TextSection::new("", style));
+    }
+    text.sections = sections;
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StripFlips>()
+            .add_systems(OnEnter(ScreenState::Game), setup)
+            .add_systems(
+                Update,
+                (follow_reversals, animate_strip, update_strip)
+                    .chain()
+                    .in_set(GameSet::Ui)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(resource_exists::<GameTransport>()),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that the strip is full width at either end of a flip, and edge on halfway.
+    #[test]
+    fn test_flip_width() {
+        assert!((flip_width(0.0) - 1.0).abs() < 0.001);
+        assert!((flip_width(1.0) - 1.0).abs() < 0.001);
+        assert!(flip_width(0.5) < 0.001);
+        assert!(flip_width(0.25) > flip_width(0.4));
+    }
+}
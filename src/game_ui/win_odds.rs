@@ -0,0 +1,101 @@
+//! Ticker showing each player's rough chance of winning, under the turn clock.
+
+use crate::{
+    game_ui::board::OnScreen,
+    info::{GameInfo, Opponents},
+    odds::{odds_shown, WinOdds},
+    settings::Settings,
+    text_fit, GameSet, ScreenState,
+    transport::GameTransport,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Widest the ticker can be before it's cut short.
+const TICKER_WIDTH: f32 = 520.0;
+/// Longest a name in the ticker can be.
+const NAME_WIDTH: f32 = 90.0;
+
+/// Text showing the odds.
+#[derive(Component)]
+struct WinOddsText;
+
+/// Draws the ticker in the top left corner, under the turn clock.
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/Lato-Black.ttf"),
+                font_size: 18.0,
+                color: Color::rgba(1.0, 1.0, 1.0, 0.8),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(52.0),
+            left: Val::Px(20.0),
+            ..default()
+        }),
+        WinOddsText,
+        OnScreen,
+    ));
+}
+
+/// Shows the latest odds, in turn order.
+fn update_ticker(
+    mut text: Query<&mut Text, With<WinOddsText>>,
+    mut socket: ResMut<GameTransport>,
+    odds: Res<WinOdds>,
+    game_info: Res<GameInfo>,
+    opponents: Res<Opponents>,
+    settings: Res<Settings>,
+) {
+    let Ok(mut text) = text.get_single_mut() else { return; };
+    if !odds.is_changed() {
+        return;
+    }
+    let language = settings.language;
+    let own_id = socket.id();
+    let entries: Vec<String> = game_info
+        .order
+        .iter()
+        .filter_map(|player| {
+            let chance = odds.0.get(player)?;
+            let name = if Some(*player) == own_id {
+                String::from(language.tr("You"))
+            } else {
+                let name = opponents
+                    .0
+                    .iter()
+                    .find(|opponent| opponent.id == *player)
+                    .map_or(language.tr("Unknown"), |opponent| &opponent.name);
+                text_fit::truncate(name, text.sections[0].style.font_size, NAME_WIDTH)
+            };
+            Some(format!("{name} {:.0}%", chance * 100.0))
+        })
+        .collect();
+
+    let ticker = if entries.is_empty() {
+        String::new()
+    } else {
+        format!("{} {}", language.tr("Win odds:"), entries.join("  "))
+    };
+    text.sections[0].value =
+        text_fit::truncate(&ticker, text.sections[0].style.font_size, TICKER_WIDTH);
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ScreenState::Game), setup.run_if(odds_shown))
+            .add_systems(
+                Update,
+                update_ticker
+                    .in_set(GameSet::Ui)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(odds_shown)
+                    .run_if(resource_exists::<GameTransport>()),
+            );
+    }
+}
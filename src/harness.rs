@@ -0,0 +1,626 @@
+//! Headless games between several apps on an in-memory network, or one app and its bots.
+//!
+//! Each [`Player`] is a copy of the game without windows or rendering, running the network and
+//! rules plugins. Moves are made the way the hand and board would make them, so whole games can
+//! be played between the apps and checked for agreeing with each other.
+
+use crate::{
+    audio::PlaySound,
+    away::Away,
+    bot,
+    bot::Bots,
+    card::{Card, CardColor, DealQueue, DealSpeed, SpawnCard, SpawnQueue},
+    challenge::ActiveChallenge,
+    deck,
+    deck::{Deck, DiscardCards, HandCardId, MainPlayer, STARTING_HAND_SIZE},
+    disconnect::Paused,
+    game_ui::camera::CameraCue,
+    info::{GameInfo, Opponent, Opponents},
+    match_mode,
+    menu::MenuState,
+    network,
+    network::{
+        AuthState, DrawCard, KeepDrawn, PlayCard, RestartGame, ServerState, StartGame, WildColor,
+    },
+    profile::Profiles,
+    rules,
+    rules::GameRules,
+    screens::win::{HandRevealed, Win},
+    settings::Settings,
+    transport::{memory::MemoryNetwork, GameTransport},
+    GameScreenState, GameSet, ScreenState,
+};
+use bevy::{
+    app::ScheduleRunnerPlugin, ecs::event::ManualEventReader, prelude::*, time::TimeUpdateStrategy,
+    utils::Uuid,
+};
+use bevy_matchbox::prelude::PeerId;
+use std::time::Duration;
+
+/// Frames to wait for something to happen before failing the test.
+const MAX_FRAMES: usize = 10_000;
+
+/// Room code the players meet in.
+const ROOM: &str = "K7P2QX";
+
+/// A move picked between frames.
+enum Move {
+    Play(HandCardId, Card),
+    Draw(u32),
+    KeepDrawn,
+    Color(CardColor),
+}
+
+/// The move to make at the start of the next frame, the way the hand and wild screen would.
+///
+/// Moves have to happen inside a frame, since the deck is reshuffled before the events they
+/// send are handled.
+#[derive(Resource, Default)]
+struct NextMove(Option<Move>);
+
+/// Makes the move picked since the last frame, unless the round has ended since.
+fn make_move(world: &mut World) {
+    let Some(next) = world.resource_mut::<NextMove>().0.take() else { return; };
+    if *world.resource::<State<GameScreenState>>() != GameScreenState::Game {
+        return;
+    }
+    match next {
+        Move::Play(id, card) => {
+            world.resource_mut::<MainPlayer>().remove(id);
+            world.resource_mut::<DiscardCards>().cards.push(card);
+            world.send_event(PlayCard(vec![card]));
+        }
+        Move::Draw(count) => {
+            // like the draw pile, wait for the deck to be reshuffled once it runs out
+            let cards = world.resource_mut::<Deck>().draw(count as i32);
+            if cards.is_empty() {
+                return;
+            }
+            let mut player = world.resource_mut::<MainPlayer>();
+            for card in cards.iter() {
+                player.push(*card);
+            }
+            world.send_event(DrawCard(cards));
+        }
+        Move::KeepDrawn => {
+            world.send_event(KeepDrawn);
+        }
+        Move::Color(color) => {
+            let mut discard_pile = world.resource_mut::<DiscardCards>();
+            let Some(mut card) = discard_pile.cards.last().copied() else { return; };
+            card.color = color;
+            discard_pile.cards.push(card);
+            world.send_event(WildColor(color));
+        }
+    }
+}
+
+/// One player's copy of the game.
+pub struct Player {
+    pub app: App,
+    pub id: PeerId,
+    wins: ManualEventReader<Win>,
+    /// Whether we played a wild that still needs a color.
+    choosing_color: bool,
+    /// Winners this player has seen, in order.
+    pub winners: Vec<PeerId>,
+}
+
+impl Player {
+    /// Builds a player that hosts the room, or joins it, over the given transport.
+    pub fn new(mut transport: GameTransport, host: bool) -> Self {
+        let id = transport.id().unwrap();
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_once()))
+            // every frame is a 60th of a second, however long the test takes to run it
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                1.0 / 60.0,
+            )))
+            .add_state::<ScreenState>()
+            .add_state::<GameScreenState>()
+            .add_state::<MenuState>()
+            .configure_sets(
+                Update,
+                (
+                    GameSet::Input,
+                    GameSet::NetIn,
+                    GameSet::Rules,
+                    GameSet::NetOut,
+                    GameSet::Animate,
+                    GameSet::Ui,
+                )
+                    .chain(),
+            )
+            .add_event::<SpawnCard>()
+            .add_event::<CameraCue>()
+            .add_event::<Win>()
+            .add_event::<HandRevealed>()
+            .add_event::<PlaySound>()
+            .init_resource::<Paused>()
+            .init_resource::<Away>()
+            .insert_resource(Opponents(Vec::new()))
+            .init_resource::<DealSpeed>()
+            .init_resource::<SpawnQueue>()
+            .init_resource::<DealQueue>()
+            .init_resource::<ActiveChallenge>()
+            .insert_resource(Settings::default())
+            .insert_resource(Profiles::new("Player"))
+            .init_resource::<NextMove>()
+            .add_systems(Update, make_move.in_set(GameSet::Input))
+            .add_plugins((
+                network::Plugin,
+                deck::Plugin,
+                crate::info::Plugin,
+                rules::Plugin,
+                bot::Plugin,
+                match_mode::Plugin,
+            ))
+            .insert_resource(transport);
+        app.world
+            .resource_mut::<NextState<ServerState>>()
+            .set(if host {
+                ServerState::Server(ROOM.into())
+            } else {
+                ServerState::Client(ROOM.into())
+            });
+        // the host is always in its own room, like the lobby sets up
+        if host {
+            app.insert_resource(AuthState::Accepted);
+        }
+
+        Self {
+            app,
+            id,
+            wins: ManualEventReader::default(),
+            choosing_color: false,
+            winners: Vec::new(),
+        }
+    }
+
+    /// Runs one frame, showing the results once someone wins like the win screen would.
+    pub fn update(&mut self) {
+        self.app.update();
+        let events = self.app.world.resource::<Events<Win>>();
+        let winners: Vec<PeerId> = self.wins.read(events).map(|win| win.0).collect();
+        if !winners.is_empty() {
+            self.winners.extend(winners);
+            self.app
+                .world
+                .resource_mut::<NextState<GameScreenState>>()
+                .set(GameScreenState::Win);
+        }
+    }
+
+    pub fn resource<R: Resource>(&self) -> &R {
+        self.app.world.resource::<R>()
+    }
+
+    pub fn send<E: Event>(&mut self, event: E) {
+        self.app.world.send_event(event);
+    }
+
+    pub fn in_game(&self) -> bool {
+        *self.resource::<State<ScreenState>>() == ScreenState::Game
+            && *self.resource::<State<GameScreenState>>() == GameScreenState::Game
+    }
+
+    /// Picks a move if it's our turn: the first card in hand that can be played, or a draw.
+    ///
+    /// The move is made on the next frame, and wilds are always made red. Returns whether a move
+    /// was picked.
+    pub fn take_turn(&mut self) -> bool {
+        let game_info = self.resource::<GameInfo>();
+        let discard_pile = self.resource::<DiscardCards>();
+        let rules = self.resource::<GameRules>();
+        let player = self.resource::<MainPlayer>();
+        if !self.in_game()
+            || game_info.current_player != Some(self.id)
+            || !discard_pile.provisional.is_empty()
+            || self.resource::<NextMove>().0.is_some()
+        {
+            return false;
+        }
+        let top_card = discard_pile.cards.last().copied();
+        let playable = |card: &Card| {
+            game_info.can_play_together(&[*card], player.len(), top_card.as_ref(), rules)
+        };
+
+        // a wild we just played still needs its color
+        if top_card.is_some_and(|card| card.color == CardColor::Wild) {
+            return false;
+        }
+        let choice = player
+            .entries()
+            .iter()
+            .find(|(_, card)| {
+                game_info.drawn_card.is_none_or(|drawn| drawn == *card) && playable(card)
+            })
+            .map(|(id, card)| (*id, *card));
+
+        let next = match (choice, game_info.drawn_card) {
+            (Some((id, card)), _) => {
+                self.choosing_color = card.color == CardColor::Wild;
+                Move::Play(id, card)
+            }
+            (None, Some(_)) => Move::KeepDrawn,
+            (None, None) => Move::Draw(game_info.cards_to_draw()),
+        };
+        self.app.world.resource_mut::<NextMove>().0 = Some(next);
+        true
+    }
+
+    /// Picks a color for a wild we played, like the wild screen would.
+    pub fn choose_color(&mut self) {
+        if !std::mem::take(&mut self.choosing_color) {
+            return;
+        }
+        // a wild that ends the round never gets a color, the win screen takes over first
+        if self.resource::<MainPlayer>().len() == 0 {
+            return;
+        }
+        self.app.world.resource_mut::<NextMove>().0 = Some(Move::Color(CardColor::Red));
+    }
+}
+
+/// Players sharing a room, with the host first, and any bots the host runs.
+pub struct Table {
+    pub players: Vec<Player>,
+    pub bots: Vec<PeerId>,
+}
+
+impl Table {
+    /// Seats a host and `guests` other players, and waits until everyone has been let in.
+    pub fn new(guests: usize) -> Self {
+        let network = MemoryNetwork::default();
+        let players = (0..=guests)
+            .map(|seat| Player::new(GameTransport::new(network.join()), seat == 0))
+            .collect();
+        let mut table = Self {
+            players,
+            bots: Vec::new(),
+        };
+        // resources are set up on the first frame
+        table.update();
+        table.run_until(|table| {
+            table.players[1..]
+                .iter()
+                .all(|player| *player.resource::<AuthState>() == AuthState::Accepted)
+        });
+        table
+    }
+
+    /// Seats a player alone on this device against `bots` bots, like a challenge.
+    pub fn local(bots: usize) -> Self {
+        let mut table = Self {
+            players: vec![Player::new(GameTransport::local(), true)],
+            bots: (0..bots).map(|_| PeerId(Uuid::new_v4())).collect(),
+        };
+        table.update();
+        let bots = table.bots.clone();
+        let mut seats = table.host().app.world.resource_mut::<Bots>();
+        for bot in bots {
+            seats.0.insert(bot, Vec::new());
+        }
+        table
+    }
+
+    pub fn host(&mut self) -> &mut Player {
+        &mut self.players[0]
+    }
+
+    pub fn update(&mut self) {
+        for player in self.players.iter_mut() {
+            player.update();
+        }
+    }
+
+    /// Runs frames until the condition holds, failing if it never does.
+    pub fn run_until(&mut self, condition: impl Fn(&Self) -> bool) {
+        for _ in 0..MAX_FRAMES {
+            if condition(self) {
+                return;
+            }
+            self.update();
+        }
+        panic!("gave up after {MAX_FRAMES} frames");
+    }
+
+    /// Starts a game in seating order, shuffled from the given seed, like the lobby would.
+    pub fn start(&mut self, seed: u64) {
+        let players = self.players.iter().map(|player| player.id);
+        let order: Vec<PeerId> = players.chain(self.bots.iter().copied()).collect();
+        self.host().app.world.resource_mut::<Opponents>().0 = order[1..]
+            .iter()
+            .map(|pid| Opponent::new(*pid, String::new(), STARTING_HAND_SIZE))
+            .collect();
+        self.host().send(StartGame {
+            order,
+            restart: false,
+            seed: Some(seed),
+        });
+        self.run_until(|table| table.players.iter().all(Player::in_game));
+    }
+
+    /// Plays until someone wins, and returns the winner.
+    pub fn play_round(&mut self) -> PeerId {
+        let rounds = self.players[0].winners.len();
+        for _ in 0..MAX_FRAMES {
+            if self
+                .players
+                .iter()
+                .all(|player| player.winners.len() > rounds)
+            {
+                return self.players[0].winners[rounds];
+            }
+            for player in self.players.iter_mut() {
+                player.choose_color();
+                player.take_turn();
+            }
+            self.update();
+        }
+        panic!("no one won after {MAX_FRAMES} frames");
+    }
+
+    /// Checks that every player agrees on the state of the game.
+    pub fn assert_in_sync(&self) {
+        let host = &self.players[0];
+        let host_info = host.resource::<GameInfo>();
+        for player in self.players[1..].iter() {
+            let info = player.resource::<GameInfo>();
+            assert_eq!(info.order, host_info.order);
+            assert_eq!(info.current_player, host_info.current_player);
+            assert_eq!(info.direction, host_info.direction);
+            assert_eq!(
+                player.resource::<DiscardCards>().cards,
+                host.resource::<DiscardCards>().cards
+            );
+            assert_eq!(
+                player.resource::<Deck>().cards,
+                host.resource::<Deck>().cards
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        card::CardValue,
+        deck::GameRng,
+        network::{PeerNames, PlayerIds, RoomPassword, SubmitPassword},
+        rules::{FirstCard, HandSize},
+    };
+
+    /// Returns the card flipped to start a round dealt to `players` players from the seed.
+    fn first_card(seed: u64, players: usize) -> Card {
+        let rules = GameRules::default();
+        let mut deck = Deck::shuffled(&rules, &mut GameRng::new(seed));
+        deck.deal(players, 0, STARTING_HAND_SIZE);
+        let mut card = deck.draw(1)[0];
+        while rules.first_card.covers(&card) {
+            card = deck.draw(1)[0];
+        }
+        card
+    }
+
+    #[test]
+    fn test_game_start() {
+        let mut table = Table::new(2);
+        table.start(7);
+        table.assert_in_sync();
+
+        let host = table.players[0].id;
+        assert_eq!(
+            table.players[2].resource::<GameInfo>().current_player,
+            Some(host)
+        );
+        for player in table.players.iter() {
+            assert_eq!(player.resource::<MainPlayer>().len(), STARTING_HAND_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_first_card_skip() {
+        let seed = (0..)
+            .find(|seed| first_card(*seed, 3).value == CardValue::Skip)
+            .unwrap();
+        let mut table = Table::new(2);
+        table.start(seed);
+        table.assert_in_sync();
+
+        // everyone skips the first player
+        let order = table.players[0].resource::<GameInfo>().order.clone();
+        for player in table.players.iter() {
+            assert_eq!(player.resource::<GameInfo>().current_player, Some(order[1]));
+        }
+    }
+
+    #[test]
+    fn test_round_and_restart() {
+        let mut table = Table::new(1);
+        table.start(7);
+
+        let winner = table.play_round();
+        assert_eq!(table.players[1].winners, [winner]);
+        table.assert_in_sync();
+        let winner = table
+            .players
+            .iter()
+            .find(|player| player.id == winner)
+            .unwrap();
+        assert_eq!(winner.resource::<MainPlayer>().len(), 0);
+
+        // the next round starts with the next player in the order
+        let first_order = table.players[0].resource::<GameInfo>().order.clone();
+        table.host().send(RestartGame);
+        table.run_until(|table| {
+            table.players.iter().all(|player| {
+                player.in_game() && player.resource::<GameInfo>().order != first_order
+            })
+        });
+        table.assert_in_sync();
+        let order = &table.players[1].resource::<GameInfo>().order;
+        assert_eq!(order[0], first_order[1]);
+        assert_eq!(order[1], first_order[0]);
+
+        table.play_round();
+        table.assert_in_sync();
+    }
+
+    #[test]
+    fn test_two_decks() {
+        let mut table = Table::new(2);
+        table.host().app.world.resource_mut::<GameRules>().double_deck = true;
+        table.start(5);
+        table.assert_in_sync();
+
+        // everyone dealt from both decks, and the rules came along with the start
+        let flipped = table.players[0].resource::<DiscardCards>().cards.len();
+        let dealt = 3 * STARTING_HAND_SIZE + flipped;
+        for player in table.players.iter() {
+            assert!(player.resource::<GameRules>().double_deck);
+            assert_eq!(player.resource::<Deck>().cards.len() + dealt, 200);
+        }
+        table.play_round();
+        table.assert_in_sync();
+    }
+
+    #[test]
+    fn test_larger_hands() {
+        let mut table = Table::new(2);
+        {
+            let mut rules = table.host().app.world.resource_mut::<GameRules>();
+            rules.hand_size = HandSize(7);
+            rules.first_card = FirstCard::Ignore;
+        }
+        table.start(5);
+        table.assert_in_sync();
+
+        // everyone was dealt the bigger hand, and sees everyone else holding it too
+        for player in table.players.iter() {
+            assert_eq!(player.resource::<GameRules>().hand_size, HandSize(7));
+            assert_eq!(player.resource::<MainPlayer>().len(), 7);
+            let opponents = &player.resource::<Opponents>().0;
+            assert!(opponents.iter().all(|opponent| opponent.card_count == 7));
+        }
+        table.play_round();
+        table.assert_in_sync();
+    }
+
+    /// Ensures that everyone learns the player id of each other player's profile, which notes
+    /// on them are kept under.
+    #[test]
+    fn test_player_ids() {
+        let mut table = Table::new(2);
+        table.start(5);
+        table.run_until(|table| {
+            table
+                .players
+                .iter()
+                .all(|player| player.resource::<PlayerIds>().0.len() == 2)
+        });
+        for player in table.players.iter() {
+            let player_ids = &player.resource::<PlayerIds>().0;
+            for other in table.players.iter().filter(|other| other.id != player.id) {
+                let profile = other.resource::<Profiles>().active();
+                assert_eq!(player_ids[&other.id], profile.player_id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_local_game_against_bot() {
+        let mut table = Table::local(1);
+        table.start(11);
+        assert_eq!(table.players[0].resource::<GameInfo>().order.len(), 2);
+
+        // whoever won played out their whole hand
+        let winner = table.play_round();
+        let host = &table.players[0];
+        if winner == host.id {
+            assert_eq!(host.resource::<MainPlayer>().len(), 0);
+        } else {
+            assert_eq!(winner, table.bots[0]);
+            assert!(host.resource::<Bots>().0[&winner].is_empty());
+        }
+    }
+
+    /// Ensures that a guest waiting on the password is left out of broadcasts, and is cut off
+    /// once they get it wrong.
+    #[test]
+    fn test_wrong_password() {
+        let network = MemoryNetwork::default();
+        let mut table = Table {
+            players: vec![Player::new(GameTransport::new(network.join()), true)],
+            bots: Vec::new(),
+        };
+        table.update();
+        table.host().app.world.resource_mut::<RoomPassword>().0 = String::from("secret");
+        table
+            .players
+            .push(Player::new(GameTransport::new(network.join()), false));
+        table.update();
+        table.run_until(|table| {
+            *table.players[1].resource::<AuthState>() == AuthState::Required
+        });
+        let guest = table.players[1].id;
+        let transport = table.players[0].resource::<GameTransport>();
+        assert!(transport.connected_peers().any(|peer| peer == guest));
+        assert!(!transport.room_peers().any(|peer| peer == guest));
+
+        table.players[1].send(SubmitPassword(String::from("wrong")));
+        table.run_until(|table| {
+            *table.players[1].resource::<AuthState>() == AuthState::Rejected
+        });
+        let transport = table.players[0].resource::<GameTransport>();
+        assert!(!transport.connected_peers().any(|peer| peer == guest));
+    }
+
+    /// Ensures that the other guests don't talk to or listen to a peer who got the password
+    /// wrong, even though they're still connected to them.
+    #[test]
+    fn test_outsider_left_out() {
+        let network = MemoryNetwork::default();
+        let mut table = Table {
+            players: vec![Player::new(GameTransport::new(network.join()), true)],
+            bots: Vec::new(),
+        };
+        table.update();
+        table.host().app.world.resource_mut::<RoomPassword>().0 = String::from("secret");
+        for _ in 0..2 {
+            table
+                .players
+                .push(Player::new(GameTransport::new(network.join()), false));
+        }
+        table.update();
+        table.run_until(|table| {
+            table.players[1..]
+                .iter()
+                .all(|player| *player.resource::<AuthState>() == AuthState::Required)
+        });
+        table.players[1].send(SubmitPassword(String::from("secret")));
+        table.players[2].send(SubmitPassword(String::from("wrong")));
+        let host = table.players[0].id;
+        let guest = table.players[1].id;
+        let outsider = table.players[2].id;
+        table.run_until(|table| {
+            *table.players[2].resource::<AuthState>() == AuthState::Rejected
+                && table.players[0].resource::<PeerNames>().0.contains_key(&guest)
+                && table.players[1].resource::<PeerNames>().0.contains_key(&host)
+        });
+
+        // the outsider introduces themselves to the guest anyway
+        let mut transport = table.players[2].app.world.resource_mut::<GameTransport>();
+        network::send_name(&mut transport, "Mallory", 7, guest);
+        for _ in 0..10 {
+            table.update();
+        }
+        assert!(!table.players[1].resource::<PeerNames>().0.contains_key(&outsider));
+        assert!(!table.players[2].resource::<PeerNames>().0.contains_key(&guest));
+        let transport = table.players[1].resource::<GameTransport>();
+        assert!(transport.connected_peers().any(|peer| peer == outsider));
+        assert!(!transport.room_peers().any(|peer| peer == outsider));
+    }
+}
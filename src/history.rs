@@ -0,0 +1,272 @@
+//! Record of the games played, with the last one shown on the main menu.
+
+use crate::{
+    i18n::Language,
+    info::Opponents,
+    profile::{Profile, Profiles},
+    rules::GameRules,
+    screens::win::Win,
+    session::{is_streak_milestone, Session},
+    settings::Settings,
+    storage::{DataFolder, PendingRead, Storage},
+    toast::Toast,
+    transport::GameTransport,
+    GameScreenState, ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// Summary of a finished round.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LastGame {
+    pub won: bool,
+    pub opponents: Vec<String>,
+    pub rules: GameRules,
+    /// Length of the round, in minutes.
+    pub minutes: f32,
+}
+
+impl LastGame {
+    /// Loads the profile's last game from storage, if they've played one.
+    pub fn load(storage: &Storage, profile: &Profile) -> Option<Self> {
+        storage.get(&profile.key("last_game")).ok()
+    }
+
+    /// Saves the game to storage as the profile's last game, replacing the previous one.
+    pub fn save(&self, storage: &mut Storage, profile: &Profile) -> io::Result<()> {
+        storage.set(&profile.key("last_game"), self)
+    }
+
+    /// Returns a one line description of the game, such as
+    /// "Last game: won vs Bob & Carol, Classic rules, 12 min".
+    pub fn summary(&self, language: Language) -> String {
+        let result = language.tr(if self.won { "won" } else { "lost" });
+        let opponents = if self.opponents.is_empty() {
+            String::from(language.tr("Unknown"))
+        } else {
+            self.opponents.join(" & ")
+        };
+        let rules = self.rules.summary_in(language);
+        let minutes = language.tr_with("{} min", self.minutes.round().max(1.0));
+        format!(
+            "{} {result} vs {opponents}, {rules}, {minutes}",
+            language.tr("Last game:")
+        )
+    }
+}
+
+/// Most games kept in a profile's history.
+const HISTORY_LEN: usize = 500;
+
+/// Every game the active profile has finished, oldest first.
+///
+/// The history is kept in a data folder, so it's read in the background. Games finished before
+/// it arrives are added on to the end once it does.
+#[derive(Resource, Default)]
+pub struct GameHistory {
+    games: Vec<LastGame>,
+    /// Profile the history belongs to.
+    profile: Option<u32>,
+    /// The saved history, while it's being read.
+    pending: Option<PendingRead<Vec<LastGame>>>,
+}
+
+impl GameHistory {
+    /// Adds a finished game, forgetting the oldest once there are too many.
+    fn push(&mut self, game: LastGame) {
+        self.games.push(game);
+        let extra = self.games.len().saturating_sub(HISTORY_LEN);
+        self.games.drain(..extra);
+    }
+
+    /// Saves the history, unless it's still being read.
+    fn save(&self, storage: &mut Storage, profile: &Profile) -> io::Result<()> {
+        if self.pending.is_some() {
+            return Ok(());
+        }
+        storage.write_data(DataFolder::History, &profile.key("games"), &self.games)
+    }
+}
+
+/// Starts reading the active profile's history, when the game starts or the profile changes.
+fn load_history(mut history: ResMut<GameHistory>, storage: Res<Storage>, profiles: Res<Profiles>) {
+    let profile = profiles.active();
+    if history.profile == Some(profile.id) {
+        return;
+    }
+    *history = GameHistory {
+        games: Vec::new(),
+        profile: Some(profile.id),
+        pending: Some(storage.read_data(DataFolder::History, &profile.key("games"))),
+    };
+}
+
+/// Picks up the history once it's been read.
+fn finish_loading_history(
+    mut history: ResMut<GameHistory>,
+    mut storage: ResMut<Storage>,
+    profiles: Res<Profiles>,
+) {
+    let Some(pending) = &history.pending else { return; };
+    let Some(result) = pending.poll() else { return; };
+    history.pending = None;
+    let recorded = std::mem::take(&mut history.games);
+    match result {
+        Ok(games) => history.games = games,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => error!("Error loading game history: {err}"),
+    }
+    if recorded.is_empty() {
+        return;
+    }
+    for game in recorded {
+        history.push(game);
+    }
+    if let Err(err) = history.save(&mut storage, profiles.active()) {
+        error!("Error saving game history: {err}");
+    }
+}
+
+/// Time the current round started.
+#[derive(Resource)]
+pub struct RoundStart(pub f32);
+
+/// Starts timing a round.
+fn start_round(time: Res<Time>, mut commands: Commands) {
+    commands.insert_resource(RoundStart(time.elapsed_seconds()));
+}
+
+/// Saves the round as the last game, and counts it in the profile's and session's stats, once
+/// someone wins.
+///
+/// Winning streaks across all of the profile's games are celebrated with a toast.
+fn record_win(
+    mut events: EventReader<Win>,
+    mut socket: ResMut<GameTransport>,
+    mut storage: ResMut<Storage>,
+    mut profiles: ResMut<Profiles>,
+    mut history: ResMut<GameHistory>,
+    mut session: ResMut<Session>,
+    opponents: Res<Opponents>,
+    rules: Res<GameRules>,
+    round_start: Res<RoundStart>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let Some(Win(winner)) = events.read().next() else { return; };
+    let last_game = LastGame {
+        won: socket.id() == Some(*winner),
+        opponents: opponents.0.iter().map(|o| o.name.clone()).collect(),
+        rules: rules.clone(),
+        minutes: (time.elapsed_seconds() - round_start.0) / 60.0,
+    };
+    session.0.record(last_game.won);
+    let stats = &mut profiles.active_mut().stats;
+    stats.record(last_game.won);
+    if is_streak_milestone(stats.streak) {
+        toasts.send(Toast(
+            settings.language.tr_with("{} wins in a row!", stats.streak),
+        ));
+    }
+    if let Err(err) = last_game.save(&mut storage, profiles.active()) {
+        error!("Error saving last game: {err}");
+        toasts.send(Toast(String::from(
+            settings.language.tr("Couldn't save this game"),
+        )));
+    }
+
+    history.push(last_game);
+    if let Err(err) = history.save(&mut storage, profiles.active()) {
+        error!("Error saving game history: {err}");
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameHistory>()
+            .add_systems(OnEnter(ScreenState::Game), start_round)
+            // leaving the results screen starts the next round
+            .add_systems(OnExit(GameScreenState::Win), start_round)
+            .add_systems(
+                Update,
+                record_win
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(resource_exists::<RoundStart>())
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(
+                Update,
+                (load_history, finish_loading_history)
+                    .chain()
+                    .run_if(resource_exists::<Profiles>())
+                    .run_if(resource_exists::<Storage>()),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::FirstCard;
+
+    /// Ensures that the summary names the opponents, rules and length of the game.
+    #[test]
+    fn test_summary() {
+        let mut last_game = LastGame {
+            won: true,
+            opponents: vec![String::from("Bob"), String::from("Carol")],
+            rules: GameRules {
+                first_card: FirstCard::Apply,
+                ..default()
+            },
+            minutes: 11.6,
+        };
+        assert_eq!(
+            last_game.summary(Language::English),
+            "Last game: won vs Bob & Carol, Classic rules, 12 min"
+        );
+
+        last_game.won = false;
+        last_game.rules.stacking = true;
+        last_game.minutes = 0.2;
+        assert_eq!(
+            last_game.summary(Language::English),
+            "Last game: lost vs Bob & Carol, Stack +2s, 1 min"
+        );
+    }
+
+    /// Ensures that only the latest games are kept.
+    #[test]
+    fn test_history_len() {
+        let mut history = GameHistory::default();
+        for minutes in 0..HISTORY_LEN + 2 {
+            history.push(LastGame {
+                won: false,
+                opponents: Vec::new(),
+                rules: GameRules::default(),
+                minutes: minutes as f32,
+            });
+        }
+        assert_eq!(history.games.len(), HISTORY_LEN);
+        assert_eq!(history.games[0].minutes, 2.0);
+    }
+
+    /// Ensures that games saved before a rule was added can still be loaded.
+    #[test]
+    fn test_load_older_game() {
+        let json = r#"{"won":false,"opponents":["Bob"],"rules":{"stacking":true},"minutes":3.0}"#;
+        let last_game: LastGame = serde_json::from_str(json).unwrap();
+        assert_eq!(last_game.opponents, vec![String::from("Bob")]);
+        assert_eq!(
+            last_game.rules,
+            GameRules {
+                stacking: true,
+                ..default()
+            }
+        );
+    }
+}
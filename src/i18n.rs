@@ -0,0 +1,418 @@
+//! Translations for UI text.
+//!
+//! Text is written in English in the code, and looked up in the translation table for the
+//! chosen language when it's shown. Anything missing from a table is shown in English.
+
+use bevy::prelude::*;
+use std::fmt::Display;
+
+/// Language used for UI text.
+///
+/// This is loaded from storage, and changed in the settings menu.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    /// All languages, in the order they're cycled through in the settings menu.
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    /// Returns the name of the language, in that language.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Spanish => "Español",
+        }
+    }
+
+    /// Returns the code the language is saved as.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Spanish => "es",
+        }
+    }
+
+    /// Returns the language with the given code, if there is one.
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.code() == code)
+    }
+
+    /// Returns the language after this one.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|l| l == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Translates English text into this language.
+    pub fn tr(&self, text: &'static str) -> &'static str {
+        match self {
+            Language::English => text,
+            Language::Spanish => spanish(text).unwrap_or(text),
+        }
+    }
+
+    /// Translates English text with a `{}` placeholder, and fills it in with the value.
+    pub fn tr_with(&self, text: &'static str, value: impl Display) -> String {
+        self.tr(text).replacen("{}", &value.to_string(), 1)
+    }
+}
+
+/// Spanish translation table.
+fn spanish(text: &str) -> Option<&'static str> {
+    Some(match text {
+        // main menu
+        "Quick Match" => "Partida rápida",
+        "Rejoin Room {}" => "Volver a la sala {}",
+        "Last game:" => "Última partida:",
+        "won" => "ganada",
+        "lost" => "perdida",
+        "Classic rules" => "Reglas clásicas",
+        "Rematch" => "Revancha",
+        "Tournament" => "Torneo",
+        "{} games" => "{} partidas",
+        // session
+        "{} wins in a row!" => "¡{} victorias seguidas!",
+        "Thanks for playing!" => "¡Gracias por jugar!",
+        "Games played: {}" => "Partidas jugadas: {}",
+        "Wins: {}" => "Victorias: {}",
+        "Time: {}" => "Tiempo: {}",
+        "Best streak: {}" => "Mejor racha: {}",
+        // rule stats
+        "Stats" => "Estadísticas",
+        "Rule Stats" => "Estadísticas de reglas",
+        "No games recorded yet" => "Aún no hay partidas registradas",
+        "Turn on rule stats in the settings to start keeping them" => {
+            "Activa las estadísticas de reglas en la configuración para empezar a guardarlas"
+        }
+        "{}% won" => "{}% ganadas",
+        // tournaments
+        "Enter Hub ID:" => "Código del torneo:",
+        "Join Hub" => "Unirse",
+        "Organize" => "Organizar",
+        "Tournament {}" => "Torneo {}",
+        "{} players waiting" => "{} jugadores esperando",
+        "Waiting for the organizer..." => "Esperando al organizador...",
+        "No results yet" => "Aún no hay resultados",
+        "{} wins" => "{} victorias",
+        "Announcement: {}" => "Anuncio: {}",
+        "Start Round" => "Empezar ronda",
+        "Leave" => "Salir",
+        "Leave game?" => "¿Salir de la partida?",
+        "Other players will be affected" => "Afectará a los demás jugadores",
+        "Stay" => "Quedarse",
+        "Round {} is starting!" => "¡Empieza la ronda {}!",
+        "Next match: room {}" => "Próxima partida: sala {}",
+        "Couldn't save settings" => "No se pudo guardar la configuración",
+        "Couldn't save this game" => "No se pudo guardar esta partida",
+        "Couldn't save profiles" => "No se pudieron guardar los perfiles",
+        "Couldn't save notes" => "No se pudieron guardar las notas",
+        "Type a note" => "Escribe una nota",
+        // room browser
+        "Open Rooms" => "Salas abiertas",
+        "Auto Join" => "Unirse",
+        "Looking for rooms..." => "Buscando salas...",
+        "{} player" => "{} jugador",
+        "{} players" => "{} jugadores",
+        // create and join
+        "Room Password (optional):" => "Contraseña de la sala (opcional):",
+        "Checking room code..." => "Comprobando el código de sala...",
+        "Edit deck" => "Editar mazo",
+        "Deck" => "Mazo",
+        "Wilds" => "Comodines",
+        "Standard deck" => "Mazo estándar",
+        "The deck needs at least {} cards, with some numbers" => "El mazo necesita al menos {} cartas, con algunos números",
+        "Custom deck" => "Mazo personalizado",
+        "Two decks" => "Dos mazos",
+        "Card counter" => "Contador de cartas",
+        "Unseen cards" => "Cartas sin ver",
+        "Enter Room ID:" => "Código de la sala:",
+        "Incorrect room password" => "Contraseña incorrecta",
+        "Room full" => "Sala llena",
+        "Connecting..." => "Conectando...",
+        "Connection failed" => "Error de conexión",
+        "The server couldn't be reached." => "No se pudo contactar con el servidor.",
+        "Room not found" => "Sala no encontrada",
+        "No host answered in this room." => "Ningún anfitrión respondió en esta sala.",
+        "Retry" => "Reintentar",
+        // lobby
+        "Room {}" => "Sala {}",
+        "{} (you)" => "{} (tú)",
+        "Room Password:" => "Contraseña de la sala:",
+        "Max Players: {}" => "Máx. jugadores: {}",
+        "Slow player nudges" => "Avisar a los lentos",
+        "Stack +2s" => "Acumular +2",
+        "Reverse bounces +2" => "Reversa devuelve +2",
+        "Wild color hints" => "Pistas de color",
+        "Hand limit of 20" => "Límite de 20 cartas",
+        "Play matching values together" => "Jugar valores iguales juntos",
+        "Win odds ticker" => "Probabilidades de ganar",
+        "Face value scoring" => "Puntos por valor",
+        "No finishing on action cards" => "No terminar con cartas de acción",
+        "Play a drawn card" => "Jugar la carta robada",
+        "First card" => "Primera carta",
+        "House rules" => "Reglas de la casa",
+        "None" => "Ninguna",
+        "Ignore" => "Ignorar",
+        "Apply" => "Aplicar",
+        "Reflip" => "Voltear otra",
+        "First card ignored" => "La primera carta no cuenta",
+        "First card reflips" => "La primera carta se voltea",
+        "Winner starts" => "Empieza el ganador",
+        "Random starter" => "Empieza alguien al azar",
+        "Starts" => "Empieza",
+        "Hand size" => "Cartas por mano",
+        "{} card hands" => "Manos de {} cartas",
+        "Next player" => "Siguiente",
+        "Winner" => "Ganador",
+        "Random" => "Al azar",
+        "Dealer: {}" => "Reparte: {}",
+        "{} extra cards" => "{} cartas extra",
+        "Action cards only" => "Solo cartas de acción",
+        "On" => "Sí",
+        "Off" => "No",
+        "Unknown" => "Desconocido",
+        // settings
+        "Profile" => "Perfil",
+        "Audio" => "Audio",
+        "Video" => "Video",
+        "Gameplay" => "Juego",
+        "Network" => "Red",
+        "Username" => "Nombre",
+        "Keep rule stats" => "Guardar estadísticas de reglas",
+        "Volume" => "Volumen",
+        "Display" => "Pantalla",
+        "Windowed" => "Ventana",
+        "Borderless" => "Sin bordes",
+        "Fullscreen" => "Pantalla completa",
+        "VSync" => "Sincronización vertical",
+        "Frame limit" => "Límite de FPS",
+        "Unlimited" => "Sin límite",
+        "Table" => "Mesa",
+        "Card back" => "Reverso de las cartas",
+        "Drawn card faces" => "Caras dibujadas",
+        "Use host's table" => "Usar la mesa del anfitrión",
+        "Green" => "Verde",
+        "Yellow" => "Amarillo",
+        "Blue" => "Azul",
+        "Red" => "Rojo",
+        "Purple" => "Morado",
+        "Classic" => "Clásico",
+        "Crimson" => "Carmesí",
+        "Navy" => "Marino",
+        "Emerald" => "Esmeralda",
+        "Gold" => "Dorado",
+        "Color symbols" => "Símbolos de color",
+        "Reduce motion" => "Reducir movimiento",
+        "Card hover height" => "Altura al pasar el ratón",
+        "Hand curve" => "Curva de la mano",
+        "Card overlap" => "Solapamiento de cartas",
+        "Hand order" => "Orden de la mano",
+        "Adaptive bots" => "Bots adaptables",
+        "Unsorted" => "Sin ordenar",
+        "By color" => "Por color",
+        "By value" => "Por valor",
+        "Language" => "Idioma",
+        "Server" => "Servidor",
+        "Room codes" => "Códigos de sala",
+        "Letters" => "Letras",
+        "Words" => "Palabras",
+        // controller hints
+        "Move" => "Mover",
+        "Select" => "Elegir",
+        "Hold" => "Mantener",
+        // game
+        "You won!" => "¡Ganaste!",
+        "{} cards left" => "Quedan {} cartas",
+        "1 card" => "1 carta",
+        "{} cards" => "{} cartas",
+        "Choose {} cards to put under the deck" => "Elige {} cartas para poner bajo el mazo",
+        "Return" => "Devolver",
+        "Play it" => "Jugarla",
+        "Keep it" => "Quedártela",
+        "It's not your turn" => "No es tu turno",
+        "That card can't be played now" => "Esa carta no se puede jugar ahora",
+        "{} won!" => "¡{} ganó!",
+        "{} played" => "{} jugó",
+        "Recently played" => "Jugadas recientes",
+        "{} drew" => "{} robó",
+        "{} drew a card" => "{} robó una carta",
+        "Turn {}" => "Turno {}",
+        "Rematch votes: {}" => "Votos de revancha: {}",
+        "Host is restarting..." => "El anfitrión está reiniciando...",
+        "Win odds:" => "Probabilidades:",
+        "Sort: {}" => "Orden: {}",
+        "You" => "Tú",
+        // matches
+        "Deal" => "Reparto",
+        "Instant" => "Instantáneo",
+        "Fast" => "Rápido",
+        "Cinematic" => "Cinematográfico",
+        "Match" => "Partida",
+        "Single round" => "Una ronda",
+        "Best of {}" => "Al mejor de {}",
+        "First to {} points" => "Primero a {} puntos",
+        "Standings" => "Clasificación",
+        "Wins" => "Victorias",
+        "Points" => "Puntos",
+        "round {}" => "ronda {}",
+        "{} rounds" => "{} rondas",
+        "Next round in {}" => "Siguiente ronda en {}",
+        "The match is over!" => "¡La partida terminó!",
+        "You're the champion!" => "¡Eres el campeón!",
+        "{} is the champion!" => "¡{} es el campeón!",
+        // how to play
+        "How to play" => "Cómo jugar",
+        "Your turn" => "Tu turno",
+        "Play a card matching the color or value of the top card, or draw one." => {
+            "Juega una carta del mismo color o valor que la de arriba, o roba una."
+        }
+        "Sevens are wild" => "Los sietes son comodines",
+        "A 7 can be played on anything, and its player picks the color to follow." => {
+            "Un 7 se puede jugar sobre cualquier carta, y quien lo juega elige el color a seguir."
+        }
+        "Skip" => "Salto",
+        "The next player misses their turn." => "El siguiente jugador pierde su turno.",
+        "Reverse" => "Reversa",
+        "Play turns around." => "El juego cambia de sentido.",
+        "Play turns around. Played onto a +2, it sends the penalty back." => {
+            "El juego cambia de sentido. Jugada sobre un +2, devuelve el castigo."
+        }
+        "+2" => "+2",
+        "The next player draws two cards." => "El siguiente jugador roba dos cartas.",
+        "The next player draws two cards, unless they stack another +2 to pass the \
+         total on." => {
+            "El siguiente jugador roba dos cartas, a menos que acumule otro +2 para pasar el \
+             total."
+        }
+        "Matching values" => "Valores iguales",
+        "Cards of the same value can be played together, adding up their effects." => {
+            "Las cartas del mismo valor se pueden jugar juntas, sumando sus efectos."
+        }
+        "An action card flipped to start does nothing." => {
+            "Una carta de acción volteada al empezar no hace nada."
+        }
+        "An action card flipped to start applies to the first player." => {
+            "Una carta de acción volteada al empezar se aplica al primer jugador."
+        }
+        "An action card flipped to start is covered by another card." => {
+            "Una carta de acción volteada al empezar se cubre con otra carta."
+        }
+        "Cards over the limit at the end of your turn go back under the deck." => {
+            "Las cartas sobre el límite al final de tu turno vuelven debajo del mazo."
+        }
+        "Playing a drawn card" => "Jugar la carta robada",
+        "If the card you draw can be played, you can play it straight away or keep it." => {
+            "Si la carta que robas se puede jugar, puedes jugarla enseguida o quedártela."
+        }
+        "Your last card can't be a skip, reverse, +2 or wild, so you have to draw instead." => {
+            "Tu última carta no puede ser un salto, reversa, +2 ni comodín; tienes que robar."
+        }
+        "Reshuffling" => "Rebarajar",
+        "When the draw pile runs out, the discard pile is shuffled into a new one." => {
+            "Cuando se acaba el mazo, el descarte se baraja para formar uno nuevo."
+        }
+        "One card left" => "Última carta",
+        "Call one when you're down to your last card, or you can be caught and made to \
+         draw." => {
+            "Avisa cuando te quede una sola carta, o te pueden atrapar y hacerte robar."
+        }
+        "Winning" => "Ganar",
+        "The first player out of cards wins, and the cards left in other hands count \
+         as points." => {
+            "El primero en quedarse sin cartas gana, y las cartas en las otras manos cuentan \
+             como puntos."
+        }
+        // challenges
+        "Challenges" => "Desafíos",
+        "Completed" => "Completado",
+        "Locked" => "Bloqueado",
+        "Bot {}" => "Bot {}",
+        "Challenge complete!" => "¡Desafío completado!",
+        "Challenge failed" => "Desafío fallido",
+        "Too slow! Win in {} turns or fewer" => "¡Muy lento! Gana en {} turnos o menos",
+        "Warm up" => "Calentamiento",
+        "Beat a bot." => "Vence a un bot.",
+        "Full hands" => "Manos llenas",
+        "Everyone starts with 10 cards." => "Todos empiezan con 10 cartas.",
+        "All action" => "Pura acción",
+        "The deck only has skips, reverses, draw twos and wilds." => {
+            "El mazo solo tiene saltos, reversas, roba dos y comodines."
+        }
+        "Quick draw" => "Desenfunde rápido",
+        "Win in 8 turns or fewer." => "Gana en 8 turnos o menos.",
+        "Crowded table" => "Mesa llena",
+        "Beat three bots in 12 turns or fewer." => "Vence a tres bots en 12 turnos o menos.",
+        "Gauntlet" => "Prueba de fuego",
+        "Start with 10 cards against three bots, and win in 16 turns or fewer." => {
+            "Empieza con 10 cartas contra tres bots, y gana en 16 turnos o menos."
+        }
+        "Daily deal #{}" => "Reparto diario #{}",
+        "Everyone gets the same deck today. Win in as few turns as you can." => {
+            "Hoy todos reciben el mismo mazo. Gana en la menor cantidad de turnos posible."
+        }
+        "Won in {} turns" => "Ganado en {} turnos",
+        "{} points" => "{} puntos",
+        "Lost after {} turns" => "Perdido tras {} turnos",
+        "Only your first try each day counts" => "Solo cuenta tu primer intento de cada día",
+        "Paused" => "En pausa",
+        "Resuming in {}" => "Continuando en {}",
+        // tutorial
+        "Tutorial" => "Tutorial",
+        "Tutorial deck" => "Mazo del tutorial",
+        "Learn to play against a bot." => "Aprende a jugar contra un bot.",
+        "None of your cards match the red 3's color or value. Click the deck to draw a \
+         card." => {
+            "Ninguna de tus cartas coincide en color o valor con el 3 rojo. Haz clic en el mazo \
+             para robar una carta."
+        }
+        "A card can be played on one of the same color or value. Play your blue 5 on the \
+         red 5." => {
+            "Una carta se puede jugar sobre otra del mismo color o valor. Juega tu 5 azul sobre \
+             el 5 rojo."
+        }
+        "7s are wild: they go on anything, and you pick the color to follow. Play your 7 \
+         and pick green." => {
+            "Los 7 son comodines: van sobre cualquier carta, y tú eliges el color a seguir. \
+             Juega tu 7 y elige verde."
+        }
+        "The bot played a +2, so you drew two cards. Keep matching to play out your hand, \
+         and press One! when you're down to your last card." => {
+            "El bot jugó un +2, así que robaste dos cartas. Sigue combinando para vaciar tu \
+             mano, y pulsa One! cuando te quede la última carta."
+        }
+        "Tutorial complete! You're ready for a real game" => {
+            "¡Tutorial completado! Ya estás listo para una partida de verdad"
+        }
+        "The bot won this time, but now you know how to play" => {
+            "El bot ganó esta vez, pero ya sabes cómo jugar"
+        }
+        "red" => "rojo",
+        "yellow" => "amarillo",
+        "green" => "verde",
+        "blue" => "azul",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that languages survive being saved, and that placeholders are filled in.
+    #[test]
+    fn test_translation() {
+        for language in Language::ALL {
+            assert_eq!(Language::from_code(language.code()), Some(language));
+        }
+        assert_eq!(Language::Spanish.tr_with("{} won!", "Ana"), "¡Ana ganó!");
+        assert_eq!(Language::English.tr_with("Room {}", 1234), "Room 1234");
+        // missing translations fall back to English
+        assert_eq!(Language::Spanish.tr("crazy 7s"), "crazy 7s");
+    }
+}
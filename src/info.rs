@@ -1,19 +1,98 @@
 //! Game info and opponents resources.
 
-use bevy::prelude::{Plugin as BevyPlugin, *};
-use bevy_matchbox::matchbox_socket::PeerId;
+use crate::{
+    audio::{PlaySound, Sound},
+    card::{Card, CardColor, CardValue, DealSpeed},
+    deck::MainPlayer,
+    network::TurnClockSynced,
+    rules::{FirstCard, GameRules},
+    transport::GameTransport,
+    GameScreenState, GameSet, ScreenState,
+};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    utils::HashMap,
+};
+use bevy_matchbox::prelude::*;
 
-#[derive(Debug)]
+/// Number of turns a player needs to take before we know their usual pace.
+const NUDGE_MIN_TURNS: u32 = 2;
+/// Shortest turn length (in seconds) that can be considered slow.
+const NUDGE_MIN_SECONDS: f32 = 10.0;
+/// Time (in seconds) a player has to call one after going down to their last card.
+pub const CALL_ONE_GRACE: f32 = 3.0;
+/// Number of cards a player draws when caught without calling one.
+pub const CATCH_PENALTY: u32 = 2;
+/// How quickly (per second) the turn clock catches up with the host's.
+const CLOCK_SMOOTHING: f32 = 4.0;
+/// Difference (in seconds) from the host's turn clock that's corrected straight away.
+const CLOCK_SNAP: f32 = 2.0;
+/// Number of colors remembered for each player that they couldn't follow.
+const MISSED_COLOR_MEMORY: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Clockwise,
     CounterClockwise,
 }
 
-#[derive(Resource)]
+impl Into<u8> for Direction {
+    fn into(self) -> u8 {
+        match self {
+            Direction::Clockwise => 0,
+            Direction::CounterClockwise => 1,
+        }
+    }
+}
+
+impl From<u8> for Direction {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Direction::CounterClockwise,
+            _ => Direction::Clockwise,
+        }
+    }
+}
+
+/// Something a player did with their turn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TurnAction {
+    /// Played cards together, in the order they were played.
+    Played(Vec<Card>),
+    /// Drew cards instead of playing.
+    Drew(u32),
+}
+
+/// A turn in the game's history.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TurnRecord {
+    pub turn: u32,
+    pub player: PeerId,
+    pub action: TurnAction,
+}
+
+#[derive(Resource, Clone)]
 pub struct GameInfo {
     pub current_player: Option<PeerId>,
     pub order: Vec<PeerId>,
     pub direction: Direction,
+    /// Number of times the turn has advanced this game.
+    pub turn: u32,
+    /// Number of times the direction has changed this game, so chains of reverses can be
+    /// followed even when they cancel out.
+    pub reversals: u32,
+    /// Cards the current player has to draw, unless they can pass the penalty on.
+    ///
+    /// This is only used with rules that let players answer a draw two.
+    pub pending_draw: u32,
+    /// Card the current player just drew and can still play before their turn ends.
+    ///
+    /// This is only used with the play after draw rule.
+    pub drawn_card: Option<Card>,
+    /// Colors each player recently drew on instead of playing, most recent last.
+    pub missed_colors: HashMap<PeerId, Vec<CardColor>>,
+    /// Every play and draw this game, oldest first.
+    pub history: Vec<TurnRecord>,
 }
 
 impl FromWorld for GameInfo {
@@ -22,6 +101,12 @@ impl FromWorld for GameInfo {
             current_player: None,
             order: Vec::new(),
             direction: Direction::Clockwise,
+            turn: 0,
+            reversals: 0,
+            pending_draw: 0,
+            drawn_card: None,
+            missed_colors: HashMap::new(),
+            history: Vec::new(),
         }
     }
 }
@@ -31,26 +116,35 @@ impl GameInfo {
         self.current_player = None;
         self.order = Vec::new();
         self.direction = Direction::Clockwise;
+        self.turn = 0;
+        self.reversals = 0;
+        self.pending_draw = 0;
+        self.drawn_card = None;
+        self.missed_colors.clear();
+        self.history.clear();
+    }
+
+    /// Returns the player after the current one in the current direction.
+    pub fn next_player(&self) -> Option<PeerId> {
+        let current_player = self.current_player?;
+        let current_index = self
+            .order
+            .iter()
+            .position(|&p| p == current_player)
+            .unwrap();
+        let next_index = match self.direction {
+            Direction::Clockwise => current_index + 1,
+            Direction::CounterClockwise => current_index + self.order.len() - 1,
+        } % self.order.len();
+        Some(self.order[next_index])
     }
 
     // moves to the next player in the order and returns the new current player
     pub fn advance_turn(&mut self) -> Option<PeerId> {
-        let next_player = match self.current_player {
-            Some(current_player) => {
-                let current_index = self
-                    .order
-                    .iter()
-                    .position(|&p| p == current_player)
-                    .unwrap();
-                let next_index = match self.direction {
-                    Direction::Clockwise => current_index + 1,
-                    Direction::CounterClockwise => current_index + self.order.len() - 1,
-                } % self.order.len();
-                Some(self.order[next_index])
-            }
-            None => None,
-        };
+        let next_player = self.next_player();
         self.current_player = next_player;
+        self.turn += 1;
+        self.drawn_card = None;
         next_player
     }
 
@@ -58,6 +152,243 @@ impl GameInfo {
         self.direction = match self.direction {
             Direction::Clockwise => Direction::CounterClockwise,
             Direction::CounterClockwise => Direction::Clockwise,
+        };
+        self.reversals += 1;
+    }
+
+    /// Advances the turn after the current player plays cards of the same value together,
+    /// performing their effect.
+    ///
+    /// Handles skips, reverses, and draw twos. Each skip skips another player, each reverse
+    /// flips the direction again, and draw twos add up. Returns the player that has to draw
+    /// cards right away and how many, if any.
+    pub fn play_cards(&mut self, cards: &[Card], rules: &GameRules) -> Option<(PeerId, u32)> {
+        let card_player = self.current_player?;
+        let card = cards.first()?;
+        let count = cards.len() as u32;
+        self.history.push(TurnRecord {
+            turn: self.turn,
+            player: card_player,
+            action: TurnAction::Played(cards.to_vec()),
+        });
+        self.advance_turn();
+        match card.value {
+            CardValue::Skip => {
+                for _ in 0..count {
+                    self.advance_turn();
+                }
+            }
+            CardValue::Reverse => {
+                // if a draw two is pending, this sends it back to the player who played it
+                for _ in 0..count {
+                    self.swap_direction();
+                    self.advance_turn();
+                    self.advance_turn();
+                }
+            }
+            CardValue::DrawTwo => {
+                if rules.draw_penalties_pending() {
+                    self.pending_draw += 2 * count;
+                    return None;
+                }
+                // make sure we don't draw cards for ourselves
+                let next_player = self.current_player?;
+                if next_player != card_player {
+                    return Some((next_player, 2 * count));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Performs the effect of the card flipped to start the round on the first player, if the
+    /// rules say to.
+    ///
+    /// A skip skips them, and a reverse turns play around so the player before them starts. A
+    /// draw two makes them draw two, or leaves the penalty for them to answer with the rules
+    /// that allow it. Returns the player that has to draw cards right away and how many, if any.
+    pub fn apply_first_card(&mut self, card: &Card, rules: &GameRules) -> Option<(PeerId, u32)> {
+        let first_player = self.current_player?;
+        if rules.first_card != FirstCard::Apply {
+            return None;
+        }
+        match card.value {
+            CardValue::Skip => {
+                self.advance_turn();
+            }
+            CardValue::Reverse => {
+                self.swap_direction();
+                self.advance_turn();
+            }
+            CardValue::DrawTwo => {
+                if rules.draw_penalties_pending() {
+                    self.pending_draw += 2;
+                    return None;
+                }
+                return Some((first_player, 2));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Returns `true` if the card can be played onto the top of the discard pile right now.
+    pub fn can_play(&self, card: &Card, top_card: Option<&Card>, rules: &GameRules) -> bool {
+        if top_card.is_some_and(|top_card| !card.can_play_on(top_card)) {
+            return false;
+        }
+        // a pending draw two can only be answered with certain cards
+        self.pending_draw == 0 || rules.can_answer_penalty(card)
+    }
+
+    /// Returns `true` if the cards can be played together, in this order, right now, by a
+    /// player holding `hand_size` cards.
+    ///
+    /// The first card has to be playable onto the discard pile, and the rest have to share its
+    /// value. Wilds can only be played on their own, and more than one card needs the play
+    /// multiple rule. Playing out the last cards in hand has to be allowed by the rules.
+    pub fn can_play_together(
+        &self,
+        cards: &[Card],
+        hand_size: usize,
+        top_card: Option<&Card>,
+        rules: &GameRules,
+    ) -> bool {
+        let Some(first) = cards.first() else { return false; };
+        if cards.len() >= hand_size && !rules.can_finish_on(cards) {
+            return false;
+        }
+        if cards.len() > 1
+            && (!rules.play_multiple
+                || cards
+                    .iter()
+                    .any(|card| card.value != first.value || card.color == CardColor::Wild))
+        {
+            return false;
+        }
+        self.can_play(first, top_card, rules)
+    }
+
+    /// Returns the next `count` players after the current one, in the current direction.
+    pub fn upcoming_players(&self, count: usize) -> Vec<PeerId> {
+        let mut upcoming = self.clone();
+        (0..count).filter_map(|_| upcoming.advance_turn()).collect()
+    }
+
+    /// Returns the number of cards the current player takes when drawing.
+    pub fn cards_to_draw(&self) -> u32 {
+        self.pending_draw.max(1)
+    }
+
+    /// Removes a player from the game, passing the turn on if it was theirs.
+    pub fn remove_player(&mut self, player: PeerId) {
+        if self.current_player == Some(player) {
+            // a penalty meant for them is dropped with them
+            self.pending_draw = 0;
+            self.advance_turn();
+        }
+        self.order.retain(|p| *p != player);
+        if self.order.is_empty() {
+            self.current_player = None;
+        }
+    }
+
+    /// Remembers that a player drew instead of following the color of the top card.
+    ///
+    /// Draws made to take a penalty don't say anything about the player's hand, so they're
+    /// ignored.
+    pub fn record_draw(&mut self, player: PeerId, top_card: Option<&Card>) {
+        let Some(top_card) = top_card else { return; };
+        if self.pending_draw > 0 || top_card.color == CardColor::Wild {
+            return;
+        }
+        let missed = self.missed_colors.entry(player).or_default();
+        missed.push(top_card.color);
+        if missed.len() > MISSED_COLOR_MEMORY {
+            missed.remove(0);
+        }
+    }
+
+    /// Forgets that a player couldn't follow the color of a card they just played.
+    pub fn record_play(&mut self, player: PeerId, card: &Card) {
+        if let Some(missed) = self.missed_colors.get_mut(&player) {
+            missed.retain(|color| *color != card.color);
+        }
+    }
+
+    /// Returns the color the player has most often been unable to follow recently, preferring
+    /// the most recent on ties.
+    pub fn hardest_color(&self, player: PeerId) -> Option<CardColor> {
+        let missed = self.missed_colors.get(&player)?;
+        missed
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, color)| {
+                let count = missed.iter().filter(|c| c == color).count();
+                (count, *index)
+            })
+            .map(|(_, color)| *color)
+    }
+
+    /// Returns the player who won the round, if anyone has, given how many cards each player
+    /// is holding.
+    ///
+    /// A round is won by playing the last card in a hand, even one that makes the next player
+    /// draw, so empty hands only count once a card has been played. Should more than one hand be
+    /// empty, the player who played most recently wins. The last player left in the game wins
+    /// too.
+    pub fn winner(&self, hand_size: impl Fn(PeerId) -> Option<usize>) -> Option<PeerId> {
+        if let [last] = self.order[..] {
+            return Some(last);
+        }
+        let last_player = self.history.iter().rev().find_map(|record| match record.action {
+            TurnAction::Played(_) => Some(record.player),
+            TurnAction::Drew(_) => None,
+        })?;
+        let mut empty = self
+            .order
+            .iter()
+            .copied()
+            .filter(|player| hand_size(*player) == Some(0));
+        if hand_size(last_player) == Some(0) {
+            Some(last_player)
+        } else {
+            empty.next()
+        }
+    }
+
+    /// Clears any pending penalty and advances the turn after the current player draws.
+    ///
+    /// With the play after draw rule, a single card drawn that can be played holds the turn
+    /// until the player plays it or keeps it.
+    pub fn finish_draw(&mut self, drawn: &[Card], top_card: Option<&Card>, rules: &GameRules) {
+        if let Some(player) = self.current_player {
+            self.history.push(TurnRecord {
+                turn: self.turn,
+                player,
+                action: TurnAction::Drew(self.cards_to_draw()),
+            });
+        }
+        let playable = match drawn {
+            [card] if rules.play_after_draw && self.pending_draw == 0 => {
+                self.can_play(card, top_card, rules).then_some(*card)
+            }
+            _ => None,
+        };
+        self.pending_draw = 0;
+        match playable {
+            Some(card) => self.drawn_card = Some(card),
+            None => {
+                self.advance_turn();
+            }
+        }
+    }
+
+    /// Ends the current player's turn after they keep the card they drew instead of playing it.
+    pub fn keep_drawn(&mut self) {
+        if self.drawn_card.is_some() {
+            self.advance_turn();
         }
     }
 }
@@ -84,15 +415,900 @@ impl Opponent {
 #[derive(Resource)]
 pub struct Opponents(pub Vec<Opponent>);
 
+/// Time spent by each player on their turns.
+#[derive(Resource, Default)]
+pub struct TurnTimes {
+    /// Total seconds spent and number of turns taken by each player.
+    totals: HashMap<PeerId, (f32, u32)>,
+    /// The turn number and player currently being timed, and when their turn started.
+    current: Option<(u32, PeerId, f32)>,
+    /// Whether the current player has already been nudged this turn.
+    nudged: bool,
+    /// Seconds the current turn's start still has to move to match the host's clock.
+    drift: f32,
+}
+
+impl TurnTimes {
+    /// Returns the average turn length of the player in seconds.
+    pub fn average(&self, player: &PeerId) -> Option<f32> {
+        let (total, count) = self.totals.get(player)?;
+        Some(total / *count as f32)
+    }
+
+    /// Returns the current turn and how long (in seconds) it has been going for.
+    pub fn elapsed(&self, now: f32) -> Option<(u32, f32)> {
+        let (turn, _, started) = self.current?;
+        Some((turn, now - started))
+    }
+
+    /// Lines the current turn's clock up with the host's, catching up gradually unless it's
+    /// far off.
+    pub fn sync(&mut self, turn: u32, elapsed: f32, now: f32) {
+        let Some((current_turn, _, started)) = self.current.as_mut() else { return; };
+        if *current_turn != turn {
+            return;
+        }
+        let drift = (now - elapsed) - *started;
+        if drift.abs() > CLOCK_SNAP {
+            *started += drift;
+            self.drift = 0.0;
+        } else {
+            self.drift = drift;
+        }
+    }
+
+    /// Moves the current turn's start towards the host's clock.
+    fn catch_up(&mut self, delta: f32) {
+        let Some((_, _, started)) = self.current.as_mut() else { return; };
+        let step = self.drift * (CLOCK_SMOOTHING * delta).min(1.0);
+        *started += step;
+        self.drift -= step;
+    }
+
+    /// Clears all recorded turns.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// A player holding their last card.
+#[derive(Debug, Clone, Default)]
+pub struct OneCard {
+    /// Seconds since the player went down to one card.
+    pub held: f32,
+    /// Whether the player has called one.
+    pub called: bool,
+    /// Whether the player has already been caught.
+    pub caught: bool,
+}
+
+impl OneCard {
+    /// Whether the grace window has passed without the player calling one.
+    pub fn catchable(&self) -> bool {
+        !self.called && !self.caught && self.held >= CALL_ONE_GRACE
+    }
+}
+
+/// Players down to their last card.
+#[derive(Resource, Default)]
+pub struct OneCards(pub HashMap<PeerId, OneCard>);
+
 /// Initializes the game info and discard pile resource.
 fn setup(mut commands: Commands) {
     commands.init_resource::<GameInfo>();
+    commands.init_resource::<TurnTimes>();
+    commands.init_resource::<OneCards>();
+}
+
+/// Records how long the previous player took when the turn changes.
+///
+/// The first turn of a round starts once the opening hands have been dealt.
+fn track_turn_times(
+    game_info: Res<GameInfo>,
+    mut turn_times: ResMut<TurnTimes>,
+    deal_speed: Res<DealSpeed>,
+    rules: Res<GameRules>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+    let turn = game_info.current_player.map(|player| (game_info.turn, player));
+    if turn == turn_times.current.map(|(turn, player, _)| (turn, player)) {
+        return;
+    }
+
+    if let Some((_, player, started)) = turn_times.current {
+        let (total, count) = turn_times.totals.entry(player).or_insert((0.0, 0));
+        *total += now - started;
+        *count += 1;
+    }
+    let started = if game_info.turn == 0 {
+        now + deal_speed.deal_length(rules.hand_size.cards())
+    } else {
+        now
+    };
+    turn_times.current = turn.map(|(turn, player)| (turn, player, started));
+    turn_times.nudged = false;
+    turn_times.drift = 0.0;
+}
+
+/// Follows the host's turn clock.
+fn sync_turn_clock(
+    mut clock_events: EventReader<TurnClockSynced>,
+    mut turn_times: ResMut<TurnTimes>,
+    time: Res<Time>,
+) {
+    for event in clock_events.read() {
+        turn_times.sync(event.turn, event.elapsed, time.elapsed_seconds());
+    }
+    turn_times.catch_up(time.delta_seconds());
+}
+
+/// Plays a nudge sound if our turn has taken more than twice as long as usual.
+fn nudge_slow_player(
+    mut turn_times: ResMut<TurnTimes>,
+    mut sound_events: EventWriter<PlaySound>,
+    mut socket: ResMut<GameTransport>,
+    rules: Res<GameRules>,
+    time: Res<Time>,
+) {
+    if !rules.nudges || turn_times.nudged {
+        return;
+    }
+    let Some((_, player, started)) = turn_times.current else { return; };
+    if socket.id() != Some(player) {
+        return;
+    }
+    let Some((total, count)) = turn_times.totals.get(&player).copied() else { return; };
+    if count < NUDGE_MIN_TURNS {
+        return;
+    }
+
+    let limit = (total / count as f32 * 2.0).max(NUDGE_MIN_SECONDS);
+    if time.elapsed_seconds() - started > limit {
+        sound_events.send(PlaySound(Sound::Nudge));
+        turn_times.nudged = true;
+    }
+}
+
+/// Clears turn times when leaving the game.
+fn reset_turn_times(mut turn_times: ResMut<TurnTimes>) {
+    turn_times.reset();
+}
+
+/// Keeps track of which players are holding a single card, and for how long.
+fn track_one_cards(
+    mut one_cards: ResMut<OneCards>,
+    mut socket: ResMut<GameTransport>,
+    main_player: Res<MainPlayer>,
+    opponents: Res<Opponents>,
+    time: Res<Time>,
+) {
+    let Some(own_id) = socket.id() else { return; };
+    let counts = opponents
+        .0
+        .iter()
+        .map(|opponent| (opponent.id, opponent.card_count))
+        .chain([(own_id, main_player.len())]);
+    for (player, count) in counts {
+        if count == 1 {
+            one_cards.0.entry(player).or_default().held += time.delta_seconds();
+        } else {
+            one_cards.0.remove(&player);
+        }
+    }
+}
+
+/// Forgets who was holding one card when leaving the game.
+fn reset_one_cards(mut one_cards: ResMut<OneCards>) {
+    one_cards.0.clear();
 }
 
 pub struct Plugin;
 
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup);
+        app.add_systems(Startup, setup)
+            .add_systems(OnExit(ScreenState::Game), (reset_turn_times, reset_one_cards))
+            .add_systems(
+                Update,
+                (track_turn_times, sync_turn_clock)
+                    .chain()
+                    .in_set(GameSet::Rules)
+                    .run_if(in_state(ScreenState::Game)),
+            )
+            .add_systems(
+                Update,
+                nudge_slow_player
+                    .after(track_turn_times)
+                    .in_set(GameSet::Rules)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(in_state(GameScreenState::Game))
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(
+                FixedUpdate,
+                track_one_cards
+                    .in_set(GameSet::Rules)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(resource_exists::<GameTransport>()),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::CardColor;
+    use crate::deck::{Deck, DiscardCards, GameRng, ProvisionalPlay, STARTING_HAND_SIZE};
+    use bevy::utils::{HashSet, Uuid};
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    fn players(count: u128) -> Vec<PeerId> {
+        (1..=count).map(|i| PeerId(Uuid::from_u128(i))).collect()
+    }
+
+    fn game(order: &[PeerId]) -> GameInfo {
+        GameInfo {
+            current_player: order.first().copied(),
+            order: order.to_vec(),
+            direction: Direction::Clockwise,
+            turn: 0,
+            reversals: 0,
+            pending_draw: 0,
+            drawn_card: None,
+            missed_colors: HashMap::new(),
+            history: Vec::new(),
+        }
+    }
+
+    fn card(value: CardValue) -> Card {
+        Card::new(CardColor::Red, value, 1)
+    }
+
+    /// Without any penalty rules, the next player draws two right away.
+    #[test]
+    fn test_draw_two_immediate() {
+        let order = players(3);
+        let mut info = game(&order);
+        let rules = GameRules::default();
+
+        let penalty = info.play_cards(&[card(CardValue::DrawTwo)], &rules);
+        assert_eq!(penalty, Some((order[1], 2)));
+        assert_eq!(info.current_player, Some(order[1]));
+        assert_eq!(info.pending_draw, 0);
+    }
+
+    /// Stacked draw twos add up and land on whoever can't answer.
+    #[test]
+    fn test_draw_two_stacking() {
+        let order = players(3);
+        let mut info = game(&order);
+        let rules = GameRules {
+            stacking: true,
+            ..default()
+        };
+
+        assert_eq!(info.play_cards(&[card(CardValue::DrawTwo)], &rules), None);
+        assert_eq!(info.play_cards(&[card(CardValue::DrawTwo)], &rules), None);
+        assert_eq!(info.current_player, Some(order[2]));
+        assert_eq!(info.cards_to_draw(), 4);
+
+        info.finish_draw(&[], None, &rules);
+        assert_eq!(info.current_player, Some(order[0]));
+        assert_eq!(info.cards_to_draw(), 1);
+    }
+
+    /// Ensures that the flipped card only affects the first player when the rules say so.
+    #[test]
+    fn test_apply_first_card() {
+        let order = players(3);
+        let mut rules = GameRules {
+            first_card: FirstCard::Ignore,
+            ..default()
+        };
+        let mut info = game(&order);
+        assert_eq!(info.apply_first_card(&card(CardValue::Skip), &rules), None);
+        assert_eq!(info.current_player, Some(order[0]));
+
+        rules.first_card = FirstCard::Apply;
+        info.apply_first_card(&card(CardValue::Skip), &rules);
+        assert_eq!(info.current_player, Some(order[1]));
+
+        // the player before the first starts, going the other way
+        let mut info = game(&order);
+        info.apply_first_card(&card(CardValue::Reverse), &rules);
+        assert_eq!(info.current_player, Some(order[2]));
+        assert_eq!(info.next_player(), Some(order[1]));
+
+        let mut info = game(&order);
+        let penalty = info.apply_first_card(&card(CardValue::DrawTwo), &rules);
+        assert_eq!(penalty, Some((order[0], 2)));
+
+        rules.stacking = true;
+        let mut info = game(&order);
+        assert_eq!(info.apply_first_card(&card(CardValue::DrawTwo), &rules), None);
+        assert_eq!(info.current_player, Some(order[0]));
+        assert_eq!(info.cards_to_draw(), 2);
+    }
+
+    /// Ensures that plays and draws are logged against the player whose turn it was.
+    #[test]
+    fn test_history() {
+        let order = players(3);
+        let mut info = game(&order);
+        let rules = GameRules {
+            stacking: true,
+            ..default()
+        };
+
+        let skips = [card(CardValue::Skip), card(CardValue::Skip)];
+        info.play_cards(&skips, &rules);
+        info.play_cards(&[card(CardValue::DrawTwo)], &rules);
+        info.finish_draw(&[], None, &rules);
+        assert_eq!(
+            info.history,
+            vec![
+                TurnRecord {
+                    turn: 0,
+                    player: order[0],
+                    action: TurnAction::Played(skips.to_vec()),
+                },
+                TurnRecord {
+                    turn: 3,
+                    player: order[0],
+                    action: TurnAction::Played(vec![card(CardValue::DrawTwo)]),
+                },
+                TurnRecord {
+                    turn: 4,
+                    player: order[1],
+                    action: TurnAction::Drew(2),
+                },
+            ]
+        );
+
+        info.reset();
+        assert!(info.history.is_empty());
+    }
+
+    /// Ensures that emptying a hand wins, even on a draw two, but not before any card is played.
+    #[test]
+    fn test_winner() {
+        let order = players(3);
+        let mut info = game(&order);
+        let rules = GameRules::default();
+        let sizes = |sizes: [usize; 3]| {
+            let order = order.clone();
+            move |player| order.iter().position(|p| *p == player).map(|i| sizes[i])
+        };
+
+        // hands are empty before the deal
+        assert_eq!(info.winner(sizes([0, 0, 0])), None);
+
+        // the last card is a draw two, so the next player draws before the round ends
+        let penalty = info.play_cards(&[card(CardValue::DrawTwo)], &rules);
+        assert_eq!(penalty, Some((order[1], 2)));
+        assert_eq!(info.winner(sizes([5, 7, 5])), None);
+        assert_eq!(info.winner(sizes([0, 7, 5])), Some(order[0]));
+
+        // whoever played last wins when more than one hand is empty
+        info.current_player = Some(order[2]);
+        info.play_cards(&[card(CardValue::Five)], &rules);
+        assert_eq!(info.winner(sizes([0, 6, 0])), Some(order[2]));
+        assert_eq!(info.winner(sizes([0, 6, 2])), Some(order[0]));
+
+        // the last player left wins
+        info.remove_player(order[1]);
+        info.remove_player(order[2]);
+        assert_eq!(info.winner(sizes([3, 6, 2])), Some(order[0]));
+    }
+
+    /// A reverse sends a pending penalty back to the player who played the draw two.
+    #[test]
+    fn test_reverse_bounces_penalty() {
+        let order = players(3);
+        let mut info = game(&order);
+        let rules = GameRules {
+            reverse_bounces: true,
+            ..default()
+        };
+
+        info.play_cards(&[card(CardValue::DrawTwo)], &rules);
+        assert_eq!(info.current_player, Some(order[1]));
+
+        info.play_cards(&[card(CardValue::Reverse)], &rules);
+        assert_eq!(info.current_player, Some(order[0]));
+        assert_eq!(info.direction, Direction::CounterClockwise);
+        assert_eq!(info.cards_to_draw(), 2);
+
+        // bouncing it again sends it back the other way
+        info.play_cards(&[card(CardValue::Reverse)], &rules);
+        assert_eq!(info.current_player, Some(order[1]));
+        assert_eq!(info.direction, Direction::Clockwise);
+        assert_eq!(info.cards_to_draw(), 2);
+    }
+
+    /// A bounced penalty can be stacked onto, continuing in the new direction.
+    #[test]
+    fn test_reverse_then_stack() {
+        let order = players(3);
+        let mut info = game(&order);
+        let rules = GameRules {
+            stacking: true,
+            reverse_bounces: true,
+            ..default()
+        };
+
+        info.play_cards(&[card(CardValue::DrawTwo)], &rules);
+        info.play_cards(&[card(CardValue::Reverse)], &rules);
+        info.play_cards(&[card(CardValue::DrawTwo)], &rules);
+        assert_eq!(info.current_player, Some(order[2]));
+        assert_eq!(info.cards_to_draw(), 4);
+
+        info.finish_draw(&[], None, &rules);
+        assert_eq!(info.current_player, Some(order[1]));
+        assert_eq!(info.pending_draw, 0);
+    }
+
+    /// Cards played together stack their effects, and have to share a value.
+    #[test]
+    fn test_play_multiple() {
+        let order = players(4);
+        let mut info = game(&order);
+        let rules = GameRules {
+            play_multiple: true,
+            ..default()
+        };
+        let top = Card::new(CardColor::Red, CardValue::Five, 1);
+        let blue_skip = Card::new(CardColor::Blue, CardValue::Skip, 1);
+        let skips = [card(CardValue::Skip), blue_skip];
+
+        assert!(info.can_play_together(&skips, 7, Some(&top), &rules));
+        // the first card has to be playable on its own
+        assert!(!info.can_play_together(&[blue_skip, skips[0]], 7, Some(&top), &rules));
+        assert!(!info.can_play_together(&skips, 7, Some(&top), &GameRules::default()));
+        let mixed = [card(CardValue::Skip), card(CardValue::Reverse)];
+        assert!(!info.can_play_together(&mixed, 7, Some(&top), &rules));
+
+        // going out on action cards can be ruled out
+        let house_rules = GameRules {
+            no_action_finish: true,
+            ..rules.clone()
+        };
+        assert!(info.can_play_together(&skips, 2, Some(&top), &rules));
+        assert!(!info.can_play_together(&skips, 2, Some(&top), &house_rules));
+        assert!(info.can_play_together(&skips, 3, Some(&top), &house_rules));
+
+        assert_eq!(info.upcoming_players(2), vec![order[1], order[2]]);
+        info.play_cards(&skips, &rules);
+        assert_eq!(info.current_player, Some(order[3]));
+
+        // two reverses keep the direction, but both are counted
+        let reverses = [card(CardValue::Reverse), card(CardValue::Reverse)];
+        info.play_cards(&reverses, &rules);
+        assert_eq!(info.current_player, Some(order[0]));
+        assert_eq!(info.direction, Direction::Clockwise);
+        assert_eq!(info.reversals, 2);
+
+        let draw_twos = [card(CardValue::DrawTwo), card(CardValue::DrawTwo)];
+        assert_eq!(info.play_cards(&draw_twos, &rules), Some((order[1], 4)));
+    }
+
+    /// A playable card drawn holds the turn with the play after draw rule, until it's played or
+    /// kept.
+    #[test]
+    fn test_play_after_draw() {
+        let order = players(3);
+        let mut info = game(&order);
+        let mut rules = GameRules::default();
+        let top = card(CardValue::Five);
+        let playable = [card(CardValue::Two)];
+        let unplayable = [Card::new(CardColor::Blue, CardValue::Two, 1)];
+
+        info.finish_draw(&playable, Some(&top), &rules);
+        assert_eq!(info.current_player, Some(order[1]));
+        assert_eq!(info.drawn_card, None);
+
+        rules.play_after_draw = true;
+        info.finish_draw(&unplayable, Some(&top), &rules);
+        assert_eq!(info.current_player, Some(order[2]));
+
+        info.finish_draw(&playable, Some(&top), &rules);
+        assert_eq!(info.current_player, Some(order[2]));
+        assert_eq!(info.drawn_card, Some(playable[0]));
+        info.keep_drawn();
+        assert_eq!(info.current_player, Some(order[0]));
+        assert_eq!(info.drawn_card, None);
+
+        // playing the drawn card ends the turn as usual
+        info.finish_draw(&playable, Some(&top), &rules);
+        info.play_cards(&playable, &rules);
+        assert_eq!(info.current_player, Some(order[1]));
+        assert_eq!(info.drawn_card, None);
+        info.keep_drawn();
+        assert_eq!(info.current_player, Some(order[1]));
+    }
+
+    /// Removing the current player passes the turn on in the current direction.
+    #[test]
+    fn test_remove_current_player() {
+        let order = players(3);
+        let mut info = game(&order);
+        info.direction = Direction::CounterClockwise;
+        info.pending_draw = 2;
+
+        info.remove_player(order[0]);
+        assert_eq!(info.current_player, Some(order[2]));
+        assert_eq!(info.order, vec![order[1], order[2]]);
+        assert_eq!(info.pending_draw, 0);
+
+        // removing someone else leaves the turn alone
+        info.remove_player(order[1]);
+        assert_eq!(info.current_player, Some(order[2]));
+        assert_eq!(info.order, vec![order[2]]);
+    }
+
+    /// Players can only be caught once the grace window passes without a call.
+    #[test]
+    fn test_one_card_catchable() {
+        let mut one = OneCard::default();
+        assert!(!one.catchable());
+
+        one.held = CALL_ONE_GRACE;
+        assert!(one.catchable());
+
+        one.called = true;
+        assert!(!one.catchable());
+
+        one.called = false;
+        one.caught = true;
+        assert!(!one.catchable());
+    }
+
+    /// Plays random games with every combination of rules, checking that no cards go missing
+    /// and the turn always belongs to someone in the game.
+    #[test]
+    fn test_random_games() {
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let rules = GameRules {
+                stacking: seed % 2 == 0,
+                reverse_bounces: seed % 4 < 2,
+                ..default()
+            };
+            let order = players(rng.gen_range(2..=8));
+            play_random_game(&mut rng, &order, &rules);
+        }
+    }
+
+    fn play_random_game(rng: &mut StdRng, order: &[PeerId], rules: &GameRules) {
+        let mut info = game(order);
+        let mut deck = Deck::new();
+        deck.cards.shuffle(rng);
+        // every player deals from their own copy of the same deck
+        let mut hands: Vec<Vec<Card>> = (0..order.len())
+            .map(|position| deck.clone().deal(order.len(), position, STARTING_HAND_SIZE))
+            .collect();
+        deck.deal(order.len(), 0, STARTING_HAND_SIZE);
+        assert!(hands.iter().all(|hand| hand.len() == STARTING_HAND_SIZE));
+
+        let mut discard_pile = deck.draw(1);
+        if discard_pile[0].color == CardColor::Wild {
+            discard_pile.extend(deck.draw(1));
+        }
+
+        for _ in 0..500 {
+            let current = info.current_player.expect("someone should always have the turn");
+            let position = order.iter().position(|p| *p == current).unwrap();
+            let hand = &mut hands[position];
+
+            // try a random card, which may or may not be allowed
+            let index = rng.gen_range(0..=hand.len());
+            let playable = index < hand.len()
+                && info.can_play(&hand[index], discard_pile.last(), rules);
+            if playable {
+                let card = hand.remove(index);
+                discard_pile.push(card);
+                if card.color == CardColor::Wild {
+                    let color = [
+                        CardColor::Red,
+                        CardColor::Yellow,
+                        CardColor::Green,
+                        CardColor::Blue,
+                    ]
+                    .choose(rng)
+                    .copied()
+                    .unwrap();
+                    discard_pile.push(Card::new(color, card.value, card.iteration));
+                }
+                if hand.is_empty() {
+                    break;
+                }
+                if let Some((player, count)) = info.play_cards(&[card], rules) {
+                    let position = order.iter().position(|p| *p == player).unwrap();
+                    draw(&mut deck, &mut discard_pile, &mut hands[position], count);
+                }
+            } else {
+                let count = info.cards_to_draw();
+                draw(&mut deck, &mut discard_pile, hand, count);
+                info.finish_draw(&[], None, rules);
+            }
+
+            assert!(order.contains(&info.current_player.unwrap()));
+            assert_eq!(info.pending_draw % 2, 0);
+            assert_all_cards_accounted(&deck, &discard_pile, &hands);
+        }
+    }
+
+    fn draw(deck: &mut Deck, discard_pile: &mut Vec<Card>, hand: &mut Vec<Card>, count: u32) {
+        for _ in 0..count {
+            if deck.is_empty() {
+                deck.recycle(discard_pile);
+            }
+            hand.extend(deck.draw(1));
+        }
+    }
+
+    fn assert_all_cards_accounted(deck: &Deck, discard_pile: &[Card], hands: &[Vec<Card>]) {
+        let held: Vec<u8> = deck
+            .cards
+            .iter()
+            .chain(hands.iter().flatten())
+            .map(Card::id)
+            .collect();
+        let discarded: HashSet<u8> = discard_pile.iter().map(Card::id).collect();
+        let unique: HashSet<u8> = held.iter().copied().collect();
+        assert_eq!(unique.len(), held.len(), "a card is in two places");
+        assert!(unique.is_disjoint(&discarded), "a card is in two places");
+        assert_eq!(held.len() + discarded.len(), Deck::new().cards.len());
+    }
+
+    /// Ensures that a peer loading the host's reshuffled order ends up with the same deck and
+    /// discard pile as the host.
+    #[test]
+    fn test_reshuffle_sync() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut host_deck = Deck::new();
+        let mut host_discard = host_deck.draw(30);
+        let mut peer_deck = Deck { cards: Vec::new() };
+        let mut peer_discard = host_discard.clone();
+        host_deck.cards.clear();
+
+        host_deck.recycle(&mut host_discard);
+        host_deck.cards.shuffle(&mut rng);
+        peer_deck.recycle(&mut peer_discard);
+        peer_deck.load_from(&host_deck.get_card_order());
+
+        assert_eq!(peer_deck.get_card_order(), host_deck.get_card_order());
+        assert_eq!(peer_discard, host_discard);
+        assert_eq!(host_discard.len(), 1);
+    }
+
+    /// Ensures that a card played before the reshuffle reached a peer stays on their pile, like
+    /// it will on the host's once the play arrives.
+    #[test]
+    fn test_reshuffle_after_play() {
+        let mut host_deck = Deck::new();
+        let mut host_discard = host_deck.draw(30);
+        let played = host_deck.draw(1);
+        let mut peer_deck = Deck { cards: Vec::new() };
+        let mut peer_discard = [host_discard.clone(), played.clone()].concat();
+        host_deck.cards.clear();
+
+        let count = host_deck.recycle(&mut host_discard);
+        peer_deck.recycle_bottom(&mut peer_discard, count);
+        peer_deck.load_from(&host_deck.get_card_order());
+        host_discard.extend(played);
+
+        assert_eq!(peer_deck.get_card_order(), host_deck.get_card_order());
+        assert_eq!(peer_discard, host_discard);
+    }
+
+    /// Ensures that the same seed always deals the same deck.
+    #[test]
+    fn test_seeded_shuffle() {
+        let deal = |seed| {
+            let mut deck = Deck::new();
+            deck.shuffle(&mut GameRng::new(seed));
+            deck.get_card_order()
+        };
+        assert_eq!(deal(42), deal(42));
+        assert_ne!(deal(42), deal(43));
+    }
+
+    /// Ensures that undoing a play the host turned down puts back the turn, deck, discard pile
+    /// and everyone's cards, including the penalty it made us draw.
+    #[test]
+    fn test_rollback_play() {
+        let order = players(2);
+        let rules = GameRules::default();
+        let mut info = game(&order);
+        let mut deck = Deck::new();
+        let deck_size = deck.cards.len();
+        let mut discard_pile = deck.draw(1);
+        let mut main_player = MainPlayer::default();
+        main_player.set_cards(deck.draw(3));
+        let mut opponents = Opponents(vec![Opponent::new(order[0], String::new(), 4)]);
+        let mut discards = DiscardCards::default();
+
+        // the opponent's draw two makes us draw before the host turns it down
+        let played = card(CardValue::DrawTwo);
+        discards.provisional.push(ProvisionalPlay::new(
+            order[0],
+            &[played],
+            &info,
+            &deck,
+            &discard_pile,
+            &main_player,
+            &opponents,
+        ));
+        discard_pile.push(played);
+        opponents.0[0].card_count -= 1;
+        let (next_player, count) = info.play_cards(&[played], &rules).unwrap();
+        assert_eq!(next_player, order[1]);
+        for card in deck.draw(count as i32) {
+            main_player.push(card);
+        }
+
+        let play = discards.reject(order[0], played).unwrap();
+        assert!(discards.provisional.is_empty());
+        play.restore(
+            Some(order[1]),
+            &mut info,
+            &mut deck,
+            &mut discard_pile,
+            &mut main_player,
+            &mut opponents,
+        );
+        assert_eq!(info.current_player, Some(order[0]));
+        assert_eq!(discard_pile.len(), 1);
+        assert_eq!(main_player.len(), 3);
+        assert_eq!(deck.cards.len(), deck_size - 4);
+        assert_eq!(opponents.0[0].card_count, 4);
+
+        // our own card goes back into our hand
+        let (id, own_card) = *main_player.entries().last().unwrap();
+        main_player.remove(id);
+        let play = ProvisionalPlay::new(
+            order[1],
+            &[own_card],
+            &info,
+            &deck,
+            &discard_pile,
+            &main_player,
+            &opponents,
+        );
+        discard_pile.push(own_card);
+        play.restore(
+            Some(order[1]),
+            &mut info,
+            &mut deck,
+            &mut discard_pile,
+            &mut main_player,
+            &mut opponents,
+        );
+        assert!(main_player.contains(&own_card));
+        assert_eq!(discard_pile.len(), 1);
+    }
+
+    /// Ensures that undoing a play after the host reshuffled keeps the host's new deck, and only
+    /// takes the played card off what's left of the pile.
+    #[test]
+    fn test_rollback_across_reshuffle() {
+        let order = players(2);
+        let rules = GameRules::default();
+        let mut info = game(&order);
+        let mut host_deck = Deck::new();
+        let mut host_discard = host_deck.draw(30);
+        let mut deck = host_deck.clone();
+        let mut discard_pile = host_discard.clone();
+        let mut main_player = MainPlayer::default();
+        main_player.set_cards(deck.draw(3));
+        host_deck.draw(3);
+        let mut opponents = Opponents(vec![Opponent::new(order[0], String::new(), 4)]);
+        let mut discards = DiscardCards::default();
+
+        // the opponent's play is applied, then the host reshuffles before turning it down
+        let played = card(CardValue::Five);
+        discards.provisional.push(ProvisionalPlay::new(
+            order[0],
+            &[played],
+            &info,
+            &deck,
+            &discard_pile,
+            &main_player,
+            &opponents,
+        ));
+        discard_pile.push(played);
+        opponents.0[0].card_count -= 1;
+        info.play_cards(&[played], &rules);
+
+        host_deck.cards.clear();
+        let count = host_deck.recycle(&mut host_discard);
+        host_deck.shuffle(&mut GameRng::new(3));
+        deck.recycle_bottom(&mut discard_pile, count);
+        deck.load_from(&host_deck.get_card_order());
+        for play in discards.provisional.iter_mut() {
+            play.reshuffled(&deck, count);
+        }
+
+        let play = discards.reject(order[0], played).unwrap();
+        play.restore(
+            Some(order[1]),
+            &mut info,
+            &mut deck,
+            &mut discard_pile,
+            &mut main_player,
+            &mut opponents,
+        );
+        assert_eq!(deck.get_card_order(), host_deck.get_card_order());
+        assert_eq!(discard_pile, host_discard);
+        assert_eq!(info.current_player, Some(order[0]));
+        assert_eq!(main_player.len(), 3);
+        assert_eq!(opponents.0[0].card_count, 4);
+    }
+
+    /// The hardest color is the one drawn on most, and playing a color forgets it.
+    #[test]
+    fn test_hardest_color() {
+        let order = players(2);
+        let mut info = game(&order);
+        let blue = Card::new(CardColor::Blue, CardValue::Five, 1);
+        let green = Card::new(CardColor::Green, CardValue::Five, 1);
+        assert_eq!(info.hardest_color(order[1]), None);
+
+        info.record_draw(order[1], Some(&blue));
+        info.record_draw(order[1], Some(&green));
+        info.record_draw(order[1], Some(&blue));
+        assert_eq!(info.hardest_color(order[1]), Some(CardColor::Blue));
+
+        info.record_play(order[1], &blue);
+        assert_eq!(info.hardest_color(order[1]), Some(CardColor::Green));
+
+        // penalty draws don't count
+        info.pending_draw = 2;
+        info.record_draw(order[1], Some(&blue));
+        assert_eq!(info.hardest_color(order[1]), Some(CardColor::Green));
+    }
+
+    /// The turn clock catches up with the host's gradually, but jumps when far off.
+    #[test]
+    fn test_turn_clock_sync() {
+        let order = players(2);
+        let mut turn_times = TurnTimes {
+            current: Some((3, order[0], 10.0)),
+            ..default()
+        };
+
+        // updates for other turns are ignored
+        turn_times.sync(2, 5.0, 12.0);
+        assert_eq!(turn_times.elapsed(12.0), Some((3, 2.0)));
+
+        // we started the turn half a second after the host
+        turn_times.sync(3, 2.5, 12.0);
+        assert_eq!(turn_times.elapsed(12.0), Some((3, 2.0)));
+        for _ in 0..60 {
+            turn_times.catch_up(1.0 / 60.0);
+        }
+        let (_, elapsed) = turn_times.elapsed(12.0).unwrap();
+        assert!((elapsed - 2.5).abs() < 0.05);
+
+        turn_times.sync(3, 10.0, 12.0);
+        assert_eq!(turn_times.elapsed(12.0), Some((3, 10.0)));
+    }
+
+    /// With two players, a bounced penalty goes straight back.
+    #[test]
+    fn test_reverse_bounce_two_players() {
+        let order = players(2);
+        let mut info = game(&order);
+        let rules = GameRules {
+            reverse_bounces: true,
+            ..default()
+        };
+
+        info.play_cards(&[card(CardValue::DrawTwo)], &rules);
+        info.play_cards(&[card(CardValue::Reverse)], &rules);
+        assert_eq!(info.current_player, Some(order[0]));
+        assert_eq!(info.cards_to_draw(), 2);
     }
 }
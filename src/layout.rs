@@ -0,0 +1,150 @@
+//! Fits the game to the window.
+//!
+//! The camera always shows at least [`DESIGN_SIZE`] of the world, zooming in on bigger
+//! windows. Anything extra on wide or tall windows is shown around it, and the hand and
+//! opponents move out to the edges of what's visible. The UI is scaled by the same amount.
+
+use crate::BackgroundMaterial;
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    window::PrimaryWindow,
+};
+
+/// Size of the world area the game is laid out for, which is always visible.
+pub const DESIGN_SIZE: Vec2 = Vec2::new(800.0, 500.0);
+/// Smallest size of the background, so its tiles stay the same size on small windows.
+pub const BACKGROUND_SIZE: Vec2 = Vec2::new(1600.0, 1000.0);
+
+/// Space between opponents when there's room for it.
+const OPPONENT_SPACING: f32 = 160.0;
+/// Distance from the top of the screen to the opponent row.
+const OPPONENT_TOP_OFFSET: f32 = 90.0;
+/// Space kept free at the sides of the screen.
+const SIDE_MARGIN: f32 = 40.0;
+
+/// Visible area of the world, and how it maps to the window.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    /// Size of the visible part of the world.
+    pub size: Vec2,
+    /// Window pixels per world unit.
+    pub scale: f32,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            size: DESIGN_SIZE,
+            scale: 1.0,
+        }
+    }
+}
+
+impl Layout {
+    /// Returns the layout for a window of the given size, in logical pixels.
+    pub fn new(window: Vec2) -> Self {
+        let scale = (window.x / DESIGN_SIZE.x).min(window.y / DESIGN_SIZE.y);
+        if scale <= 0.0 {
+            return Self::default();
+        }
+        Self {
+            size: window / scale,
+            scale,
+        }
+    }
+
+    /// Center of the player's hand, on the bottom edge of the screen.
+    pub fn hand_position(&self) -> Vec3 {
+        Vec3::new(0.0, -self.size.y / 2.0, 0.0)
+    }
+
+    /// Widest the player's hand can be spread.
+    pub fn hand_width(&self) -> f32 {
+        self.size.x - 2.0 * SIDE_MARGIN
+    }
+
+    /// Space between opponents in the row along the top of the screen.
+    pub fn opponent_spacing(&self, count: usize) -> f32 {
+        if count > 1 {
+            OPPONENT_SPACING.min((self.size.x - 2.0 * SIDE_MARGIN) / count as f32)
+        } else {
+            OPPONENT_SPACING
+        }
+    }
+
+    /// Position of an opponent in the row along the top of the screen.
+    pub fn opponent_position(&self, index: usize, count: usize) -> Vec3 {
+        let center_idx = (count as f32 - 1.0) / 2.0;
+        let spacing = self.opponent_spacing(count);
+        Vec3::new(
+            -spacing * (center_idx - index as f32),
+            self.size.y / 2.0 - OPPONENT_TOP_OFFSET,
+            1.0,
+        )
+    }
+}
+
+/// Updates the layout and UI scale when the window is resized.
+fn update_layout(
+    window: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+    mut layout: ResMut<Layout>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Ok(window) = window.get_single() else { return; };
+    layout.set_if_neq(Layout::new(Vec2::new(window.width(), window.height())));
+    if ui_scale.0 != layout.scale as f64 {
+        ui_scale.0 = layout.scale as f64;
+    }
+}
+
+/// Stretches the background to cover the visible area.
+fn fit_background(
+    mut backgrounds: Query<&mut Transform, With<Handle<BackgroundMaterial>>>,
+    layout: Res<Layout>,
+) {
+    let size = layout.size.max(BACKGROUND_SIZE);
+    for mut transform in &mut backgrounds {
+        transform.scale = size.extend(0.0);
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Layout>().add_systems(
+            Update,
+            (
+                update_layout,
+                fit_background.run_if(resource_changed::<Layout>()),
+            )
+                .chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that the design area always fits, and extra room goes to the longer side.
+    #[test]
+    fn test_layout() {
+        let default = Layout::new(DESIGN_SIZE);
+        assert_eq!(default, Layout::default());
+
+        let fullscreen = Layout::new(Vec2::new(1600.0, 1000.0));
+        assert_eq!(fullscreen.size, DESIGN_SIZE);
+        assert_eq!(fullscreen.scale, 2.0);
+
+        let ultrawide = Layout::new(Vec2::new(3440.0, 1440.0));
+        assert!((ultrawide.size.y - DESIGN_SIZE.y).abs() < 1e-3);
+        assert!(ultrawide.size.x > DESIGN_SIZE.x);
+        assert!((ultrawide.hand_position().y + DESIGN_SIZE.y / 2.0).abs() < 1e-3);
+
+        // opponents squeeze together to stay on a narrow screen
+        let last = default.opponent_position(5, 6);
+        assert!(last.x + SIDE_MARGIN <= default.size.x / 2.0);
+        assert_eq!(ultrawide.opponent_position(5, 6).x, OPPONENT_SPACING * 2.5);
+    }
+}
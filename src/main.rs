@@ -2,34 +2,71 @@
 
 use bevy::{
     prelude::*,
-    render::render_resource::{AsBindGroup, ShaderRef},
+    render::{
+        camera::ScalingMode,
+        render_resource::{AsBindGroup, ShaderRef},
+    },
     sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
-    window::{PresentMode, PrimaryWindow},
+    window::PresentMode,
     winit::WinitSettings,
 };
-use rand::Rng;
 
 pub const SERVER_URL: &str = "ws://127.0.0.1:3536";
+/// How many times a second the fixed timestep game rules run.
+const RULES_TICK_RATE: f64 = 60.0;
 
-const SCREEN_WIDTH_DEFAULT: f32 = 800.0;
-const SCREEN_HEIGHT_DEFAULT: f32 = 500.0;
-const SCREEN_MAX_SCALE: f32 = 2.0; // needs to also be used in background.wgsl
 
+mod analytics;
+mod animation;
+mod audio;
+mod away;
+mod bot;
 mod button;
 mod card;
+mod card_art;
+mod challenge;
+mod controller;
+#[cfg(feature = "debug_overlay")]
+mod debug;
 mod deck;
+mod disconnect;
+mod discovery;
 mod game_ui;
+#[cfg(test)]
+mod harness;
+mod history;
+mod i18n;
 mod info;
+mod layout;
+mod match_mode;
 mod menu;
 mod network;
+mod notes;
+mod odds;
+mod platform;
+mod pointer;
+mod profile;
+mod resume;
+mod room_check;
+mod room_code;
+mod rules;
+mod scoring;
 mod screens;
+mod session;
+mod settings;
 mod storage;
+mod text_fit;
+mod theme;
+mod toast;
+mod tournament;
+mod transport;
+mod tutorial;
 
 /// The global screen state.
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 pub enum ScreenState {
-    // Splash,
     #[default]
+    Splash,
     Menu,
     Game,
 }
@@ -40,7 +77,36 @@ pub enum GameScreenState {
     #[default]
     Game,
     WildColor,
+    /// Picking cards to return over the hand limit.
+    DiscardDown,
     Win,
+    /// Crowning the winner of a match.
+    Champion,
+    /// Asking whether to leave the game.
+    ConfirmQuit,
+    /// Reading the rules from the leave game overlay.
+    HowToPlay,
+}
+
+/// Stages of a game frame, run in this order.
+///
+/// Systems within a stage have no order between them, so they can run in parallel. Rules that
+/// don't react to events also run in [`FixedUpdate`], so they tick at the same rate on every
+/// machine, before any of these.
+#[derive(SystemSet, Clone, Copy, Eq, PartialEq, Debug, Hash)]
+pub enum GameSet {
+    /// Reacting to clicks on the board, hand and game screens.
+    Input,
+    /// Reading packets from peers.
+    NetIn,
+    /// Applying game rules to what happened this frame.
+    Rules,
+    /// Sending the results to peers.
+    NetOut,
+    /// Moving cards and other objects toward where they belong.
+    Animate,
+    /// Updating text and highlights to match the game.
+    Ui,
 }
 
 /// Tiled background shader material.
@@ -49,6 +115,9 @@ pub struct BackgroundMaterial {
     #[texture(0)]
     #[sampler(1)]
     image: Option<Handle<Image>>,
+    /// Color multiplied with the image, set from the table theme.
+    #[uniform(2)]
+    tint: Color,
 }
 
 impl Material2d for BackgroundMaterial {
@@ -61,78 +130,55 @@ impl Material2d for BackgroundMaterial {
 #[derive(Component)]
 pub struct MainCamera;
 
-/// Coordinates of the mouse cursor in world space.
+/// Coordinates of the pointer in world space.
 #[derive(Resource, Default)]
 struct WorldCoords(Vec2);
 
-/// The username of the player.
-///
-/// This is loaded from storage, or generated if it doesn't exist.
-#[derive(Resource)]
-pub struct Username(String);
-
 /// Draws background and sets up camera and storage.
 fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<BackgroundMaterial>>,
-    mut framepace_settings: ResMut<bevy_framepace::FramepaceSettings>,
     asset_server: Res<AssetServer>,
 ) {
-    framepace_settings.limiter = bevy_framepace::Limiter::from_framerate(120.0);
-
     let mut storage = storage::Storage::new();
 
-    let username = if let Ok(username) = storage.get("username") {
-        username
-    } else {
-        let user_num = rand::thread_rng().gen_range(1000..10000);
-        let username = format!("User {user_num}");
-        if let Err(err) = storage.set("username", &username.clone()) {
-            println!("Error saving username: {:?}", err);
-        }
-        username
-    };
+    // save the settings right away, so a generated username is kept
+    let mut settings = settings::Settings::load(&storage);
+    let profiles = profile::Profiles::load(&storage, &settings.username);
+    settings.username = profiles.active().name.clone();
+    if let Err(err) = settings.save(&mut storage) {
+        println!("Error saving settings: {:?}", err);
+    }
+    if let Err(err) = profiles.save(&mut storage) {
+        println!("Error saving profiles: {:?}", err);
+    }
 
-    commands.insert_resource(Username(username));
+    commands.insert_resource(settings);
+    commands.insert_resource(profiles);
     commands.insert_resource(storage);
-    commands.init_resource::<WorldCoords>();
 
     // draw background
     commands.spawn(MaterialMesh2dBundle {
         // mesh: meshes.add(shape::Plane { size: 3.0 }.into()).into(),
         mesh: meshes.add(Mesh::from(shape::Quad::default())).into(),
-        transform: Transform::default().with_scale(Vec3::new(
-            SCREEN_WIDTH_DEFAULT * SCREEN_MAX_SCALE,
-            SCREEN_HEIGHT_DEFAULT * SCREEN_MAX_SCALE,
-            0.0,
-        )),
+        // keep the background behind the table
+        transform: Transform::from_xyz(0.0, 0.0, -0.09)
+            .with_scale(layout::BACKGROUND_SIZE.extend(0.0)),
         material: materials.add(BackgroundMaterial {
             image: Some(asset_server.load("textures/background.png")),
+            tint: Color::WHITE,
         }),
         ..default()
     });
 
-    commands.spawn((Camera2dBundle::default(), MainCamera));
-}
-
-/// Tracks the mouse cursor position in world space.
-fn handle_cursor(
-    window: Query<&Window, With<PrimaryWindow>>,
-    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-    mut coords: ResMut<WorldCoords>,
-) {
-    let (camera, camera_transform) = camera.single();
-    let window = window.single();
-
-    // convert cursor position into world coordinates and truncate to get rid of z
-    if let Some(world_position) = window
-        .cursor_position()
-        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
-        .map(|ray| ray.origin.truncate())
-    {
-        coords.0 = world_position;
-    }
+    // always show the whole board, zooming in on bigger windows
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = ScalingMode::AutoMin {
+        min_width: layout::DESIGN_SIZE.x,
+        min_height: layout::DESIGN_SIZE.y,
+    };
+    commands.spawn((camera, MainCamera));
 }
 
 /// Generic system that takes a component as a parameter, and will despawn all entities with that component
@@ -143,53 +189,117 @@ fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands
 }
 
 fn main() {
-    App::new()
-        .add_plugins((
-            DefaultPlugins
-                .set(WindowPlugin {
-                    primary_window: Some(Window {
-                        title: "crazy 7s".into(),
-                        resolution: (800., 500.).into(),
-                        resize_constraints: WindowResizeConstraints {
-                            min_width: SCREEN_WIDTH_DEFAULT,
-                            max_width: SCREEN_WIDTH_DEFAULT * SCREEN_MAX_SCALE,
-                            min_height: SCREEN_HEIGHT_DEFAULT,
-                            max_height: SCREEN_HEIGHT_DEFAULT * SCREEN_MAX_SCALE,
-                        },
-                        present_mode: PresentMode::AutoVsync,
-                        // Tells wasm to resize the window according to the available canvas
-                        fit_canvas_to_parent: true,
-                        // Tells wasm not to override default event handling, like F5, Ctrl+R etc.
-                        prevent_default_event_handling: false,
+    let mut app = App::new();
+    app.add_plugins((
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "crazy 7s".into(),
+                    resolution: (layout::DESIGN_SIZE.x, layout::DESIGN_SIZE.y).into(),
+                    // the layout scales to any size, but text gets too small below half size
+                    resize_constraints: WindowResizeConstraints {
+                        min_width: layout::DESIGN_SIZE.x / 2.0,
+                        min_height: layout::DESIGN_SIZE.y / 2.0,
                         ..default()
-                    }),
+                    },
+                    present_mode: PresentMode::AutoVsync,
+                    // Tells wasm to resize the window according to the available canvas
+                    fit_canvas_to_parent: true,
+                    // Tells wasm not to override default event handling, like F5, Ctrl+R etc.
+                    prevent_default_event_handling: false,
                     ..default()
-                })
-                .set(ImagePlugin::default_nearest()),
-            Material2dPlugin::<BackgroundMaterial>::default(),
-            bevy_framepace::FramepacePlugin,
-        ))
-        // .add_plugins((
-        //     bevy::diagnostic::FrameTimeDiagnosticsPlugin::default(),
-        //     bevy::diagnostic::LogDiagnosticsPlugin::default(),
-        // ))
-        .insert_resource(WinitSettings::game())
-        .add_state::<ScreenState>()
-        .add_state::<GameScreenState>()
-        .add_systems(Startup, setup)
-        .add_systems(Update, handle_cursor)
-        .add_plugins((
-            menu::Plugin,
-            info::Plugin,
-            card::Plugin,
-            deck::Plugin,
-            network::Plugin,
-            button::Plugin,
-            game_ui::board::Plugin,
-            game_ui::hand::Plugin,
-            game_ui::opponent::Plugin,
-            screens::win::Plugin,
-            screens::wild::Plugin,
-        ))
-        .run();
+                }),
+                // closing shows the session summary first
+                close_when_requested: false,
+                ..default()
+            })
+            .set(ImagePlugin::default_nearest()),
+        Material2dPlugin::<BackgroundMaterial>::default(),
+        bevy_framepace::FramepacePlugin,
+    ))
+    // .add_plugins((
+    //     bevy::diagnostic::FrameTimeDiagnosticsPlugin::default(),
+    //     bevy::diagnostic::LogDiagnosticsPlugin::default(),
+    // ))
+    .insert_resource(WinitSettings::game())
+    .add_state::<ScreenState>()
+    .add_state::<GameScreenState>()
+    .insert_resource(Time::<Fixed>::from_hz(RULES_TICK_RATE))
+    .configure_sets(
+        Update,
+        (
+            GameSet::Input,
+            GameSet::NetIn,
+            GameSet::Rules,
+            GameSet::NetOut,
+            GameSet::Animate,
+            GameSet::Ui,
+        )
+            .chain(),
+    )
+    .add_systems(Startup, setup)
+    .add_plugins((
+        audio::Plugin,
+        menu::Plugin,
+        info::Plugin,
+        card::Plugin,
+        deck::Plugin,
+        disconnect::Plugin,
+        discovery::Plugin,
+        network::Plugin,
+        platform::Plugin,
+        resume::Plugin,
+        rules::Plugin,
+        theme::Plugin,
+        button::Plugin,
+        screens::win::Plugin,
+        screens::wild::Plugin,
+    ))
+    .add_plugins((
+        game_ui::board::Plugin,
+        game_ui::call_one::Plugin,
+        game_ui::hand::Plugin,
+        game_ui::opponent::Plugin,
+        screens::splash::Plugin,
+        game_ui::turn_clock::Plugin,
+        toast::Plugin,
+        settings::Plugin,
+        history::Plugin,
+        layout::Plugin,
+        pointer::Plugin,
+        profile::Plugin,
+        session::Plugin,
+        tournament::Plugin,
+        screens::discard_down::Plugin,
+    ))
+    .add_plugins((
+        game_ui::camera::Plugin,
+        controller::Plugin,
+        game_ui::standings::Plugin,
+        game_ui::win_odds::Plugin,
+        analytics::Plugin,
+        match_mode::Plugin,
+        odds::Plugin,
+        screens::champion::Plugin,
+        screens::confirm_quit::Plugin,
+        screens::how_to_play::Plugin,
+        game_ui::turn_order::Plugin,
+        animation::Plugin,
+        card_art::Plugin,
+        game_ui::play_preview::Plugin,
+        screens::turn_history::Plugin,
+    ))
+    .add_plugins((
+        bot::Plugin,
+        challenge::Plugin,
+        game_ui::drawn_card::Plugin,
+        tutorial::Plugin,
+        room_check::Plugin,
+        game_ui::card_counter::Plugin,
+        away::Plugin,
+        notes::Plugin,
+    ));
+    #[cfg(feature = "debug_overlay")]
+    app.add_plugins(debug::Plugin);
+    app.run();
 }
@@ -0,0 +1,431 @@
+//! Matches of several rounds, played until someone reaches the goal the host picked.
+//!
+//! Every player keeps their own standings from the wins and revealed hands they see, so they
+//! agree without the host having to send them. The host deals the next round a few seconds after
+//! each win, until there's a champion.
+
+use crate::{
+    deck::MainPlayer,
+    i18n::Language,
+    info::GameInfo,
+    rules::GameRules,
+    network::{RestartGame, ServerState},
+    screens::win::{HandRevealed, OnScreen as WinScreen, Win},
+    settings::Settings,
+    transport::GameTransport,
+    GameScreenState, GameSet, ScreenState,
+};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    utils::HashMap,
+};
+use bevy_matchbox::prelude::*;
+
+/// Seconds the results are shown before the next round of a match is dealt.
+const NEXT_ROUND_DELAY: f32 = 8.0;
+
+/// How long a match lasts, picked by the host in the lobby.
+///
+/// This is sent to peers in the start game packet.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatchFormat {
+    /// A single round, with no standings kept.
+    #[default]
+    Single,
+    /// Whoever wins the most of this many rounds, ending early once nobody can catch up.
+    BestOf(u8),
+    /// Whoever reaches this many points first, winning the cards left in everyone else's hands.
+    FirstTo(u16),
+}
+
+impl MatchFormat {
+    /// All formats, in the order they're cycled through in the lobby.
+    pub const ALL: [MatchFormat; 6] = [
+        MatchFormat::Single,
+        MatchFormat::BestOf(3),
+        MatchFormat::BestOf(5),
+        MatchFormat::FirstTo(100),
+        MatchFormat::FirstTo(250),
+        MatchFormat::FirstTo(500),
+    ];
+
+    /// Returns the format after this one.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|f| f == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Returns `true` if the format is played over several rounds.
+    pub fn is_match(&self) -> bool {
+        *self != MatchFormat::Single
+    }
+
+    /// Short description of the format, such as "Best of 3".
+    pub fn label(&self, language: Language) -> String {
+        match self {
+            MatchFormat::Single => String::from(language.tr("Single round")),
+            MatchFormat::BestOf(rounds) => language.tr_with("Best of {}", rounds),
+            MatchFormat::FirstTo(points) => language.tr_with("First to {} points", points),
+        }
+    }
+
+    /// Serializes the format for the start game packet.
+    pub fn to_bytes(self) -> [u8; 3] {
+        let (kind, target) = match self {
+            MatchFormat::Single => (0, 0),
+            MatchFormat::BestOf(rounds) => (1, rounds as u16),
+            MatchFormat::FirstTo(points) => (2, points),
+        };
+        let [low, high] = target.to_le_bytes();
+        [kind, low, high]
+    }
+
+    /// Loads the format from the start game packet.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let [kind, low, high] = *bytes.get(..3)? else { return None; };
+        let target = u16::from_le_bytes([low, high]);
+        match kind {
+            0 => Some(MatchFormat::Single),
+            1 => Some(MatchFormat::BestOf(target.min(u8::MAX as u16) as u8)),
+            2 => Some(MatchFormat::FirstTo(target)),
+            _ => None,
+        }
+    }
+}
+
+/// A player's wins and points so far in the match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Standing {
+    pub wins: u32,
+    pub points: u32,
+}
+
+/// The winner of a round, and the points left in each player's hand once it ended.
+#[derive(Clone, Debug, Default)]
+struct Round {
+    winner: Option<PeerId>,
+    hands: HashMap<PeerId, u32>,
+}
+
+/// Rounds played so far in the match.
+///
+/// Hands can be revealed before the win that ended the round arrives, so the points are only
+/// added up when they're needed.
+#[derive(Resource, Clone, Debug)]
+pub struct MatchStandings {
+    rounds: Vec<Round>,
+}
+
+impl Default for MatchStandings {
+    fn default() -> Self {
+        Self {
+            rounds: vec![Round::default()],
+        }
+    }
+}
+
+impl MatchStandings {
+    /// Records the winner of the current round.
+    pub fn record_win(&mut self, winner: PeerId) {
+        self.current().winner = Some(winner);
+    }
+
+    /// Records the points left in a player's hand at the end of the current round.
+    pub fn record_hand(&mut self, player: PeerId, points: u32) {
+        self.current().hands.insert(player, points);
+    }
+
+    /// Starts the next round, unless the current one hasn't been won yet.
+    pub fn next_round(&mut self) {
+        if self.current().winner.is_some() {
+            self.rounds.push(Round::default());
+        }
+    }
+
+    /// Number of the round being played, from 1.
+    pub fn round(&self) -> u32 {
+        self.rounds.len() as u32
+    }
+
+    /// Number of rounds that have been won.
+    pub fn rounds_played(&self) -> u32 {
+        self.rounds.iter().filter(|r| r.winner.is_some()).count() as u32
+    }
+
+    /// Returns the player's wins and the points from the hands left in the rounds they won.
+    pub fn standing(&self, player: PeerId) -> Standing {
+        self.rounds
+            .iter()
+            .filter(|round| round.winner == Some(player))
+            .fold(Standing::default(), |standing, round| Standing {
+                wins: standing.wins + 1,
+                points: standing.points
+                    + round
+                        .hands
+                        .iter()
+                        .filter(|(hand_player, _)| **hand_player != player)
+                        .map(|(_, points)| points)
+                        .sum::<u32>(),
+            })
+    }
+
+    /// Returns the players' standings, leader first.
+    pub fn ranked(&self, players: &[PeerId], format: MatchFormat) -> Vec<(PeerId, Standing)> {
+        let mut ranked: Vec<(PeerId, Standing)> = players
+            .iter()
+            .map(|player| (*player, self.standing(*player)))
+            .collect();
+        ranked.sort_by_key(|(_, standing)| {
+            std::cmp::Reverse(match format {
+                MatchFormat::FirstTo(_) => (standing.points, standing.wins),
+                _ => (standing.wins, standing.points),
+            })
+        });
+        ranked
+    }
+
+    /// Returns the winner of the match, once someone has reached the goal.
+    pub fn champion(&self, players: &[PeerId], format: MatchFormat) -> Option<PeerId> {
+        let ranked = self.ranked(players, format);
+        let (leader, standing) = ranked.first()?;
+        let decided = match format {
+            MatchFormat::Single => false,
+            MatchFormat::BestOf(rounds) => {
+                let rounds = rounds as u32;
+                let runner_up = ranked.get(1).map_or(0, |(_, s)| s.wins);
+                let rounds_left = rounds.saturating_sub(self.rounds_played());
+                // over once nobody can catch up, with a tie going on until it's broken
+                standing.wins > runner_up + rounds_left
+            }
+            MatchFormat::FirstTo(points) => standing.points >= points as u32,
+        };
+        decided.then_some(*leader)
+    }
+
+    /// Returns the current round.
+    fn current(&mut self) -> &mut Round {
+        if self.rounds.is_empty() {
+            self.rounds.push(Round::default());
+        }
+        self.rounds
+            .last_mut()
+            .expect("there's always a current round")
+    }
+}
+
+/// Shows how long until the next round of the match is dealt.
+#[derive(Component)]
+struct NextRoundText;
+
+/// Counts down to the next round of the match while the results are shown.
+#[derive(Resource)]
+struct NextRound(Timer);
+
+/// Returns `true` if the game is part of a match.
+pub fn in_match(format: Res<MatchFormat>) -> bool {
+    format.is_match()
+}
+
+/// Initializes the match format.
+fn setup(mut commands: Commands) {
+    commands.init_resource::<MatchFormat>();
+}
+
+/// Starts fresh standings for a new match.
+fn reset_standings(mut commands: Commands) {
+    commands.insert_resource(MatchStandings::default());
+}
+
+/// Starts the next round in the standings once the results are closed.
+fn next_round(mut standings: ResMut<MatchStandings>) {
+    standings.next_round();
+}
+
+/// Records round wins, along with the points left in our own hand.
+fn record_win(
+    mut events: EventReader<Win>,
+    mut standings: ResMut<MatchStandings>,
+    mut socket: ResMut<GameTransport>,
+    main_player: Res<MainPlayer>,
+    rules: Res<GameRules>,
+) {
+    let Some(Win(winner)) = events.read().next() else { return; };
+    standings.record_win(*winner);
+    if let Some(own_id) = socket.id() {
+        let points = rules.scoring().hand_points(&main_player.cards());
+        standings.record_hand(own_id, points);
+    }
+}
+
+/// Records the points left in opponents' hands as they're revealed.
+fn record_revealed_hands(
+    mut events: EventReader<HandRevealed>,
+    mut standings: ResMut<MatchStandings>,
+    rules: Res<GameRules>,
+) {
+    for HandRevealed { player, cards } in events.read() {
+        let points = rules.scoring().hand_points(cards);
+        standings.record_hand(*player, points);
+    }
+}
+
+/// Starts counting down to the next round, and shows the countdown under the results.
+fn start_countdown(
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    commands.insert_resource(NextRound(Timer::from_seconds(
+        NEXT_ROUND_DELAY,
+        TimerMode::Once,
+    )));
+    commands.spawn((
+        TextBundle::from_section(
+            settings
+                .language
+                .tr_with("Next round in {}", NEXT_ROUND_DELAY),
+            TextStyle {
+                font: asset_server.load("fonts/Lato-Black.ttf"),
+                font_size: 26.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(30.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_text_alignment(TextAlignment::Center),
+        NextRoundText,
+        WinScreen,
+    ));
+}
+
+/// Moves on once the results have been shown, either to the champion screen or, if we're
+/// hosting, by dealing the next round.
+fn chain_rounds(
+    mut next_round: ResMut<NextRound>,
+    mut text: Query<&mut Text, With<NextRoundText>>,
+    mut restart_events: EventWriter<RestartGame>,
+    mut game_screen_state: ResMut<NextState<GameScreenState>>,
+    standings: Res<MatchStandings>,
+    format: Res<MatchFormat>,
+    game_info: Res<GameInfo>,
+    server_state: Res<State<ServerState>>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let champion = standings.champion(&game_info.order, *format);
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = if champion.is_some() {
+            String::from(settings.language.tr("The match is over!"))
+        } else {
+            let seconds = next_round.0.remaining_secs().ceil();
+            settings.language.tr_with("Next round in {}", seconds)
+        };
+    }
+
+    if !next_round.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    if champion.is_some() {
+        game_screen_state.set(GameScreenState::Champion);
+    } else if let ServerState::Server(_) = **server_state {
+        restart_events.send(RestartGame);
+        game_screen_state.set(GameScreenState::Game);
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup)
+            .add_systems(OnEnter(ScreenState::Game), reset_standings)
+            .add_systems(OnExit(GameScreenState::Win), next_round)
+            // a new match starts after the champion is crowned
+            .add_systems(OnExit(GameScreenState::Champion), reset_standings)
+            .add_systems(
+                OnEnter(GameScreenState::Win),
+                start_countdown.run_if(in_match),
+            )
+            .add_systems(
+                Update,
+                (record_win, record_revealed_hands)
+                    .in_set(GameSet::Rules)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(resource_exists::<MatchStandings>())
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(
+                Update,
+                chain_rounds
+                    .in_set(GameSet::Ui)
+                    .run_if(in_state(GameScreenState::Win))
+                    .run_if(in_match)
+                    .run_if(resource_exists::<NextRound>()),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::utils::Uuid;
+
+    fn peer(id: u128) -> PeerId {
+        PeerId(Uuid::from_u128(id))
+    }
+
+    /// Ensures that the format survives being sent in a packet.
+    #[test]
+    fn test_format_serialization() {
+        for format in MatchFormat::ALL {
+            assert_eq!(MatchFormat::from_bytes(&format.to_bytes()), Some(format));
+        }
+        assert_eq!(MatchFormat::from_bytes(&[1]), None);
+    }
+
+    /// Ensures that points come from the hands left in rounds a player won, and that matches
+    /// end once the goal is reached or nobody can catch up.
+    #[test]
+    fn test_standings() {
+        let players = [peer(1), peer(2)];
+        let mut standings = MatchStandings::default();
+        // hands can be revealed before the win arrives
+        standings.record_hand(peer(2), 30);
+        standings.record_win(peer(1));
+        standings.record_hand(peer(1), 0);
+        standings.next_round();
+        standings.record_win(peer(2));
+        standings.record_hand(peer(1), 12);
+
+        assert_eq!(
+            standings.standing(peer(1)),
+            Standing {
+                wins: 1,
+                points: 30
+            }
+        );
+        assert_eq!(standings.rounds_played(), 2);
+        assert_eq!(
+            standings.champion(&players, MatchFormat::FirstTo(25)),
+            Some(peer(1))
+        );
+        assert_eq!(
+            standings.champion(&players, MatchFormat::FirstTo(100)),
+            None
+        );
+        assert_eq!(standings.champion(&players, MatchFormat::BestOf(3)), None);
+
+        standings.next_round();
+        standings.record_win(peer(2));
+        assert_eq!(
+            standings.champion(&players, MatchFormat::BestOf(3)),
+            Some(peer(2))
+        );
+        assert_eq!(standings.champion(&players, MatchFormat::Single), None);
+    }
+}
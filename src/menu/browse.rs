@@ -0,0 +1,253 @@
+use super::{ButtonEnabled, MenuState, ScrollingList, ServerState};
+use crate::discovery::{OpenRoom, OpenRooms};
+use crate::pointer::Click;
+use crate::settings::Settings;
+use bevy::prelude::*;
+
+/// Height of a row in the room list.
+const ROOM_ROW_HEIGHT: f32 = 56.0;
+
+/// List of open rooms.
+#[derive(Component)]
+pub struct RoomList;
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+pub struct OnScreen;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component, Clone)]
+pub enum ButtonAction {
+    BackToMain,
+    JoinRoom(String),
+    AutoJoin,
+}
+
+/// Draws the room browser screen.
+pub fn setup(mut commands: Commands, settings: Res<Settings>, asset_server: Res<AssetServer>) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_content: AlignContent::Center,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            // back button
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(26.0),
+                        left: Val::Px(26.0),
+                        width: Val::Px(120.0),
+                        height: Val::Px(46.0),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    image: asset_server.load("textures/buttons/back.png").into(),
+                    ..default()
+                },
+                ButtonAction::BackToMain,
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                settings.language.tr("Open Rooms"),
+                text_style,
+            ));
+
+            // room list
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(520.0),
+                        height: Val::Px(ROOM_ROW_HEIGHT * 4.0),
+                        margin: UiRect::top(Val::Px(10.0)),
+                        flex_direction: FlexDirection::Column,
+                        overflow: Overflow::clip_y(),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.2).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                width: Val::Percent(100.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        RoomList,
+                        ScrollingList::new(ROOM_ROW_HEIGHT),
+                    ));
+                });
+
+            // auto join button
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(274.0),
+                            height: Val::Px(72.0),
+                            margin: UiRect::all(Val::Px(20.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::AutoJoin,
+                    ButtonEnabled(false),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        settings.language.tr("Auto Join"),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                            font_size: 40.0,
+                            color: Color::BLACK,
+                        },
+                    ));
+                });
+        });
+}
+
+/// Rebuilds the room list when rooms open or close.
+pub fn update_room_list(
+    list: Query<(Entity, Ref<RoomList>)>,
+    mut buttons: Query<(&ButtonAction, &mut ButtonEnabled)>,
+    open_rooms: Res<OpenRooms>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut shown_rooms: Local<Vec<(String, u8, String)>>,
+    mut commands: Commands,
+) {
+    let Ok((list_entity, list)) = list.get_single() else { return; };
+
+    // auto join needs a room to join
+    for (action, mut enabled) in &mut buttons {
+        if let ButtonAction::AutoJoin = action {
+            enabled.0 = !open_rooms.0.is_empty();
+        }
+    }
+
+    let rooms: Vec<(String, u8, String)> = open_rooms
+        .0
+        .iter()
+        .map(|room| (room.code.clone(), room.players, room.rules.summary()))
+        .collect();
+    if !list.is_added() && *shown_rooms == rooms {
+        return;
+    }
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 22.0,
+        color: Color::BLACK,
+    };
+    let mut list = commands.entity(list_entity);
+    list.despawn_descendants();
+    list.with_children(|parent| {
+        if rooms.is_empty() {
+            parent.spawn(
+                TextBundle::from_section(
+                    settings.language.tr("Looking for rooms..."),
+                    TextStyle {
+                        color: Color::WHITE,
+                        ..text_style.clone()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                }),
+            );
+        }
+        for (code, players, rules) in rooms.iter() {
+            let players = if *players == 1 {
+                settings.language.tr_with("{} player", players)
+            } else {
+                settings.language.tr_with("{} players", players)
+            };
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            height: Val::Px(ROOM_ROW_HEIGHT - 8.0),
+                            margin: UiRect::all(Val::Px(4.0)),
+                            padding: UiRect::horizontal(Val::Px(10.0)),
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::Center,
+                            flex_shrink: 0.0,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::JoinRoom(code.clone()),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        format!("{} - {players}", settings.language.tr_with("Room {}", code)),
+                        text_style.clone(),
+                    ));
+                    parent.spawn(TextBundle::from_section(
+                        rules.clone(),
+                        TextStyle {
+                            font_size: 16.0,
+                            ..text_style.clone()
+                        },
+                    ));
+                });
+        }
+    });
+
+    *shown_rooms = rooms;
+}
+
+/// Handles button presses.
+pub fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<(&ButtonAction, Option<&ButtonEnabled>)>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut server_state: ResMut<NextState<ServerState>>,
+    open_rooms: Res<OpenRooms>,
+) {
+    for (action, enabled) in actions.iter_many(clicks.read().map(|click| click.0)) {
+        if !enabled.map_or(true, |e| e.0) {
+            continue;
+        }
+        let code = match action {
+            ButtonAction::BackToMain => {
+                menu_state.set(MenuState::Main);
+                continue;
+            }
+            ButtonAction::JoinRoom(code) => code.clone(),
+            ButtonAction::AutoJoin => {
+                let Some(OpenRoom { code, .. }) = open_rooms.0.first() else { continue; };
+                code.clone()
+            }
+        };
+        server_state.set(ServerState::Client(code));
+        menu_state.set(MenuState::Lobby);
+    }
+}
@@ -0,0 +1,192 @@
+use super::{ButtonEnabled, MenuState};
+use crate::challenge::{
+    daily_number, ActiveChallenge, ChallengeProgress, DailyResults, CHALLENGES, DAILY_DEAL,
+};
+use crate::network::ServerState;
+use crate::platform;
+use crate::pointer::Click;
+use crate::profile::Profiles;
+use crate::rules::GameRules;
+use crate::settings::Settings;
+use crate::storage::Storage;
+use bevy::prelude::*;
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+pub struct OnScreen;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component)]
+pub enum ButtonAction {
+    BackToMain,
+    Daily,
+    Play(usize),
+}
+
+/// Draws the challenge select page, with a button for the daily deal and each challenge.
+pub fn setup(
+    mut commands: Commands,
+    storage: Res<Storage>,
+    profiles: Res<Profiles>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+) {
+    let language = settings.language;
+    let progress = ChallengeProgress::load(&storage, profiles.active());
+    let today = platform::today();
+    let daily = DailyResults::load(&storage, profiles.active());
+
+    let title_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+    let name_style = TextStyle {
+        font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+        font_size: 26.0,
+        color: Color::BLACK,
+    };
+    let detail_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 18.0,
+        color: Color::DARK_GRAY,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            // back button
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(26.0),
+                        left: Val::Px(26.0),
+                        width: Val::Px(120.0),
+                        height: Val::Px(46.0),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    image: asset_server.load("textures/buttons/back.png").into(),
+                    ..default()
+                },
+                ButtonAction::BackToMain,
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                language.tr("Challenges"),
+                title_style,
+            ));
+
+            // today's deal, showing how it went once it's been played
+            let description = match daily.get(today) {
+                Some(result) => result.share_text(language),
+                None => String::from(language.tr(DAILY_DEAL.description)),
+            };
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(640.0),
+                            flex_direction: FlexDirection::Column,
+                            padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                            margin: UiRect::bottom(Val::Px(12.0)),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::Daily,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        language.tr_with("Daily deal #{}", daily_number(today)),
+                        name_style.clone(),
+                    ));
+                    parent.spawn(TextBundle::from_section(description, detail_style.clone()));
+                });
+
+            for (index, challenge) in CHALLENGES.iter().enumerate() {
+                let unlocked = progress.is_unlocked(index);
+                let status = if progress.is_completed(index) {
+                    language.tr("Completed")
+                } else if unlocked {
+                    ""
+                } else {
+                    language.tr("Locked")
+                };
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(640.0),
+                                flex_direction: FlexDirection::Column,
+                                padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                                ..default()
+                            },
+                            background_color: Color::WHITE.into(),
+                            ..default()
+                        },
+                        ButtonAction::Play(index),
+                        ButtonEnabled(unlocked),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_sections([
+                            TextSection::new(language.tr(challenge.name), name_style.clone()),
+                            TextSection::new(format!("  {status}"), detail_style.clone()),
+                        ]));
+                        parent.spawn(TextBundle::from_section(
+                            language.tr(challenge.description),
+                            detail_style.clone(),
+                        ));
+                    });
+            }
+        });
+}
+
+/// Handles button presses, opening a room of our own for the chosen challenge.
+pub fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<(&ButtonAction, Option<&ButtonEnabled>)>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut server_state: ResMut<NextState<ServerState>>,
+    mut challenge: ResMut<ActiveChallenge>,
+    mut rules: ResMut<GameRules>,
+    settings: Res<Settings>,
+) {
+    for (action, enabled) in actions.iter_many(clicks.read().map(|click| click.0)) {
+        if matches!(enabled, Some(ButtonEnabled(false))) {
+            continue;
+        }
+        match action {
+            ButtonAction::BackToMain => {
+                menu_state.set(MenuState::Main);
+            }
+            ButtonAction::Daily => {
+                challenge.start_daily(&mut rules);
+            }
+            ButtonAction::Play(index) => {
+                challenge.start(*index, &mut rules);
+            }
+        }
+        if challenge.is_active() {
+            let code = settings.room_codes.generate(&mut rand::thread_rng());
+            server_state.set(ServerState::Server(code));
+            menu_state.set(MenuState::Lobby);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use super::{ButtonEnabled, MenuState};
+use crate::network::RoomPassword;
+use crate::pointer::Click;
+use crate::room_check::RoomCheck;
+use crate::settings::Settings;
+use bevy::prelude::*;
+
+/// Longest password that can be entered.
+pub const PASSWORD_MAX_LEN: usize = 15;
+
+/// Password text component.
+#[derive(Component)]
+pub struct PasswordText;
+
+/// Text shown while the room code is being checked.
+#[derive(Component)]
+pub struct CheckText;
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+pub struct OnScreen;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component, Clone, Copy)]
+pub enum ButtonAction {
+    BackToMain,
+    Host,
+    EditDeck,
+}
+
+/// Starts a new room without a password.
+///
+/// This isn't done on setup, so the password isn't lost going to the deck editor and back.
+pub fn clear_password(mut password: ResMut<RoomPassword>) {
+    password.0.clear();
+}
+
+/// Draws the lobby creation screen.
+pub fn setup(mut commands: Commands, settings: Res<Settings>, asset_server: Res<AssetServer>) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_content: AlignContent::Center,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            // back button
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(26.0),
+                        left: Val::Px(26.0),
+                        width: Val::Px(120.0),
+                        height: Val::Px(46.0),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    image: asset_server.load("textures/buttons/back.png").into(),
+                    ..default()
+                },
+                ButtonAction::BackToMain,
+            ));
+
+            // password text
+            parent.spawn((
+                TextBundle {
+                    style: Style {
+                        align_self: AlignSelf::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    text: Text {
+                        sections: vec![
+                            TextSection {
+                                value: format!(
+                                    "{}\n",
+                                    settings.language.tr("Room Password (optional):")
+                                ),
+                                style: text_style.clone(),
+                            },
+                            TextSection {
+                                value: String::new(),
+                                style: text_style.clone(),
+                            },
+                        ],
+                        alignment: TextAlignment::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+                PasswordText,
+            ));
+
+            // deck editor button
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(250.0),
+                            height: Val::Px(36.0),
+                            margin: UiRect::top(Val::Px(20.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::EditDeck,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        settings.language.tr("Edit deck"),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                            font_size: 20.0,
+                            color: Color::BLACK,
+                        },
+                    ));
+                });
+
+            // host button
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(274.0),
+                        height: Val::Px(72.0),
+                        margin: UiRect::all(Val::Px(20.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    image: asset_server.load("textures/buttons/host.png").into(),
+                    ..default()
+                },
+                ButtonAction::Host,
+                ButtonEnabled(true),
+            ));
+
+            // room code check status
+            parent.spawn((
+                TextBundle::from_section(
+                    String::new(),
+                    TextStyle {
+                        font_size: 26.0,
+                        ..text_style
+                    },
+                ),
+                CheckText,
+            ));
+        });
+}
+
+/// Updates the room password on key press.
+pub fn update_password(
+    mut char_evr: EventReader<ReceivedCharacter>,
+    mut password: ResMut<RoomPassword>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let password = &mut password.0;
+    if keys.just_pressed(KeyCode::Back) {
+        password.pop();
+    } else {
+        for ev in char_evr.read() {
+            if password.len() < PASSWORD_MAX_LEN && ev.char.is_ascii_graphic() {
+                password.push(ev.char);
+            }
+        }
+    }
+}
+
+/// Copies the room password to the text display.
+pub fn update_password_display(
+    mut text: Query<&mut Text, With<PasswordText>>,
+    password: Res<RoomPassword>,
+) {
+    let mut text = text.single_mut();
+    text.sections[1].value = if password.0.is_empty() {
+        String::from("(none)")
+    } else {
+        password.0.clone()
+    };
+}
+
+/// Disables the host button and lets the player know while the room code is being checked.
+pub fn update_check_display(
+    mut text: Query<&mut Text, With<CheckText>>,
+    mut buttons: Query<&mut ButtonEnabled>,
+    check: Option<Res<RoomCheck>>,
+    settings: Res<Settings>,
+) {
+    let mut text = text.single_mut();
+    text.sections[0].value = if check.is_some() {
+        String::from(settings.language.tr("Checking room code..."))
+    } else {
+        String::new()
+    };
+    buttons.single_mut().0 = check.is_none();
+}
+
+/// Handles button presses.
+pub fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<(&ButtonAction, Option<&ButtonEnabled>)>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    for (action, enabled) in actions.iter_many(clicks.read().map(|click| click.0)) {
+        if matches!(enabled, Some(ButtonEnabled(false))) {
+            continue;
+        }
+        match action {
+            ButtonAction::BackToMain => {
+                commands.remove_resource::<RoomCheck>();
+                menu_state.set(MenuState::Main);
+            }
+            // the lobby opens once the code turns out to be free
+            ButtonAction::Host => RoomCheck::start(&mut commands, &settings),
+            ButtonAction::EditDeck => {
+                commands.remove_resource::<RoomCheck>();
+                menu_state.set(MenuState::DeckEditor);
+            }
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use super::{ButtonEnabled, MenuState};
+use crate::deck::DeckComposition;
+use crate::pointer::Click;
+use crate::rules::GameRules;
+use crate::settings::Settings;
+use bevy::prelude::*;
+
+/// A count of cards that can be changed in the editor.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Entry {
+    /// Copies of the value at this index of [`DeckComposition::VALUES`], in every color.
+    Value(usize),
+    Wilds,
+}
+
+impl Entry {
+    /// Returns the label shown on the entry's button.
+    fn label(&self) -> &'static str {
+        let Entry::Value(index) = self else { return "Wilds"; };
+        DeckComposition::VALUES[*index].label()
+    }
+
+    /// Returns how many of the entry's cards are in the deck.
+    fn count(&self, deck: &DeckComposition) -> u8 {
+        match self {
+            Entry::Value(index) => deck.copies[*index],
+            Entry::Wilds => deck.wilds,
+        }
+    }
+}
+
+/// Text on an entry's button.
+#[derive(Component)]
+pub struct EntryText(Entry);
+
+/// Text showing the size of the deck, and whether it's too small to play with.
+#[derive(Component)]
+pub struct DeckSizeText;
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+pub struct OnScreen;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component, Clone, Copy)]
+pub enum ButtonAction {
+    BackToCreate,
+    Cycle(Entry),
+    Reset,
+}
+
+/// Draws the deck editor.
+pub fn setup(mut commands: Commands, settings: Res<Settings>, asset_server: Res<AssetServer>) {
+    let button_style = TextStyle {
+        font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+        font_size: 20.0,
+        color: Color::BLACK,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            // back button, which waits for a deck that can be played with
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(26.0),
+                        left: Val::Px(26.0),
+                        width: Val::Px(120.0),
+                        height: Val::Px(46.0),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    image: asset_server.load("textures/buttons/back.png").into(),
+                    ..default()
+                },
+                ButtonAction::BackToCreate,
+                ButtonEnabled(true),
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                settings.language.tr("Deck"),
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-Black.ttf"),
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                },
+            ));
+
+            // a button for each value, then the wilds
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        max_width: Val::Px(780.0),
+                        margin: UiRect::top(Val::Px(20.0)),
+                        flex_wrap: FlexWrap::Wrap,
+                        justify_content: JustifyContent::Center,
+                        column_gap: Val::Px(10.0),
+                        row_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let entries = (0..DeckComposition::VALUES.len())
+                        .map(Entry::Value)
+                        .chain([Entry::Wilds]);
+                    for entry in entries {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        width: Val::Px(180.0),
+                                        height: Val::Px(36.0),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: Color::WHITE.into(),
+                                    ..default()
+                                },
+                                ButtonAction::Cycle(entry),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    TextBundle::from_section("", button_style.clone()),
+                                    EntryText(entry),
+                                ));
+                            });
+                    }
+                });
+
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/Lato-Black.ttf"),
+                        font_size: 26.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::top(Val::Px(20.0)),
+                    ..default()
+                }),
+                DeckSizeText,
+            ));
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(250.0),
+                            height: Val::Px(36.0),
+                            margin: UiRect::top(Val::Px(20.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::Reset,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        settings.language.tr("Standard deck"),
+                        button_style,
+                    ));
+                });
+        });
+}
+
+/// Shows the count of each card and the size of the deck, and only lets the host leave with a
+/// deck that can be played with.
+pub fn update_deck_text(
+    mut entries: Query<(&mut Text, &EntryText), Without<DeckSizeText>>,
+    mut size_text: Query<&mut Text, With<DeckSizeText>>,
+    mut back: Query<&mut ButtonEnabled>,
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    added: Query<(), Added<DeckSizeText>>,
+) {
+    if !rules.is_changed() && added.is_empty() {
+        return;
+    }
+    let language = settings.language;
+    for (mut text, EntryText(entry)) in &mut entries {
+        text.sections[0].value =
+            format!("{}: {}", language.tr(entry.label()), entry.count(&rules.deck));
+    }
+
+    let valid = rules.deck.is_valid();
+    for mut text in &mut size_text {
+        let size = language.tr_with("{} cards", rules.deck.len());
+        text.sections[0].value = if valid {
+            size
+        } else {
+            format!(
+                "{size}\n{}",
+                language.tr_with(
+                    "The deck needs at least {} cards, with some numbers",
+                    DeckComposition::MIN_SIZE
+                )
+            )
+        };
+    }
+    for mut enabled in &mut back {
+        enabled.0 = valid;
+    }
+}
+
+/// Handles button presses.
+pub fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<(&ButtonAction, Option<&ButtonEnabled>)>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut rules: ResMut<GameRules>,
+) {
+    for (action, enabled) in actions.iter_many(clicks.read().map(|click| click.0)) {
+        if matches!(enabled, Some(ButtonEnabled(false))) {
+            continue;
+        }
+        match action {
+            ButtonAction::BackToCreate => {
+                menu_state.set(MenuState::Create);
+            }
+            ButtonAction::Cycle(Entry::Value(index)) => {
+                let copies = &mut rules.deck.copies[*index];
+                *copies = (*copies + 1) % (DeckComposition::MAX_COPIES + 1);
+            }
+            ButtonAction::Cycle(Entry::Wilds) => {
+                rules.deck.wilds = (rules.deck.wilds + 1) % (DeckComposition::MAX_WILDS + 1);
+            }
+            ButtonAction::Reset => {
+                rules.deck = DeckComposition::default();
+            }
+        }
+    }
+}
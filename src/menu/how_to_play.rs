@@ -0,0 +1,152 @@
+use super::{MenuState, ScrollingList};
+use crate::i18n::Language;
+use crate::pointer::Click;
+use crate::rules::GameRules;
+use crate::settings::Settings;
+use bevy::prelude::*;
+
+/// Distance scrolled for each line of mouse wheel movement.
+const LINE_HEIGHT: f32 = 40.0;
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+pub struct OnScreen;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component)]
+pub enum ButtonAction {
+    BackToMain,
+}
+
+/// Draws the how to play page.
+pub fn setup(
+    mut commands: Commands,
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            // back button
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(26.0),
+                        left: Val::Px(26.0),
+                        width: Val::Px(120.0),
+                        height: Val::Px(46.0),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    image: asset_server.load("textures/buttons/back.png").into(),
+                    ..default()
+                },
+                ButtonAction::BackToMain,
+            ));
+
+            spawn_reference(parent, &rules, settings.language, &asset_server);
+        });
+}
+
+/// Draws the title and a scrolling list explaining each of the rules.
+///
+/// This is shared with the in-game how to play screen.
+pub fn spawn_reference(
+    parent: &mut ChildBuilder,
+    rules: &GameRules,
+    language: Language,
+    asset_server: &AssetServer,
+) {
+    let heading_style = TextStyle {
+        font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+        font_size: 28.0,
+        color: Color::WHITE,
+    };
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 22.0,
+        color: Color::rgb(0.85, 0.85, 0.85),
+    };
+
+    parent.spawn(TextBundle::from_section(
+        language.tr("How to play"),
+        TextStyle {
+            font: asset_server.load("fonts/Lato-Black.ttf"),
+            font_size: 40.0,
+            color: Color::WHITE,
+        },
+    ));
+
+    // panel clipping the scrolling list of rules
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(640.0),
+                height: Val::Percent(70.0),
+                margin: UiRect::top(Val::Px(16.0)),
+                flex_direction: FlexDirection::Column,
+                overflow: Overflow::clip_y(),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.3).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.0),
+                            flex_direction: FlexDirection::Column,
+                            flex_shrink: 0.0,
+                            padding: UiRect::all(Val::Px(16.0)),
+                            row_gap: Val::Px(14.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ScrollingList::new(LINE_HEIGHT),
+                ))
+                .with_children(|parent| {
+                    for (heading, text) in rules.reference() {
+                        parent.spawn(TextBundle::from_sections([
+                            TextSection::new(
+                                format!("{}\n", language.tr(heading)),
+                                heading_style.clone(),
+                            ),
+                            TextSection::new(language.tr(text), text_style.clone()),
+                        ]));
+                    }
+                });
+        });
+}
+
+/// Handles button presses.
+pub fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+) {
+    for action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match action {
+            ButtonAction::BackToMain => {
+                menu_state.set(MenuState::Main);
+            }
+        }
+    }
+}
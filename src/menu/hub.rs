@@ -0,0 +1,296 @@
+use super::{ButtonEnabled, MenuState};
+use crate::pointer::Click;
+use crate::settings::Settings;
+use crate::tournament::{announce, leave_hub, start_round, HubSocket, Tournament};
+use bevy::prelude::*;
+
+/// Longest announcement that can be typed.
+const ANNOUNCEMENT_MAX_LEN: usize = 60;
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+pub struct OnScreen;
+
+/// Text showing the organizer's last announcement.
+#[derive(Component)]
+pub struct AnnouncementText;
+
+/// Text showing who's in the hub.
+#[derive(Component)]
+pub struct StatusText;
+
+/// Node holding the standings.
+#[derive(Component)]
+pub struct StandingsList;
+
+/// Text showing the announcement being typed by the organizer.
+#[derive(Component)]
+pub struct DraftText;
+
+/// Announcement being typed by the organizer.
+#[derive(Resource, Default)]
+pub struct Draft(String);
+
+/// Indicates the bundle's associated button action.
+#[derive(Component, Clone, Copy)]
+pub enum ButtonAction {
+    BackToMain,
+    StartRound,
+    Leave,
+}
+
+/// Draws the tournament hub screen.
+pub fn setup(
+    mut commands: Commands,
+    tournament: Res<Tournament>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+) {
+    commands.init_resource::<Draft>();
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 28.0,
+        color: Color::WHITE,
+    };
+    let button_text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+        font_size: 32.0,
+        color: Color::BLACK,
+    };
+    let button_style = Style {
+        width: Val::Px(220.0),
+        height: Val::Px(60.0),
+        margin: UiRect::all(Val::Px(10.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            // back button, which keeps us in the hub
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(26.0),
+                        left: Val::Px(26.0),
+                        width: Val::Px(120.0),
+                        height: Val::Px(46.0),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    image: asset_server.load("textures/buttons/back.png").into(),
+                    ..default()
+                },
+                ButtonAction::BackToMain,
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                settings.language.tr_with("Tournament {}", tournament.code),
+                TextStyle {
+                    font_size: 48.0,
+                    ..text_style.clone()
+                },
+            ));
+            parent.spawn((TextBundle::from_section("", text_style.clone()), StatusText));
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        color: Color::rgb(1.0, 0.84, 0.0),
+                        ..text_style.clone()
+                    },
+                ),
+                AnnouncementText,
+            ));
+
+            // standings, filled in as results come in
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        min_height: Val::Px(120.0),
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.3).into(),
+                    ..default()
+                },
+                StandingsList,
+            ));
+
+            if tournament.is_organizer {
+                parent.spawn((TextBundle::from_section("", text_style.clone()), DraftText));
+            }
+
+            parent.spawn(NodeBundle::default()).with_children(|parent| {
+                let mut buttons = vec![("Leave", ButtonAction::Leave, true)];
+                if tournament.is_organizer {
+                    // enabled once enough players have joined
+                    buttons.insert(0, ("Start Round", ButtonAction::StartRound, false));
+                }
+                for (label, action, enabled) in buttons {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: Color::WHITE.into(),
+                                ..default()
+                            },
+                            action,
+                            ButtonEnabled(enabled),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                settings.language.tr(label),
+                                button_text_style.clone(),
+                            ));
+                        });
+                }
+            });
+        });
+}
+
+/// Shows the hub's status, announcement and standings when they change.
+pub fn update_hub(
+    mut status: Query<&mut Text, (With<StatusText>, Without<AnnouncementText>)>,
+    mut announcement: Query<&mut Text, (With<AnnouncementText>, Without<StatusText>)>,
+    list: Query<(Entity, Ref<StandingsList>)>,
+    mut buttons: Query<(&mut ButtonEnabled, &ButtonAction)>,
+    tournament: Res<Tournament>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Ok((list_entity, list)) = list.get_single() else { return; };
+    if !tournament.is_changed() && !list.is_added() {
+        return;
+    }
+
+    if let Ok(mut text) = status.get_single_mut() {
+        text.sections[0].value = if tournament.is_organizer {
+            settings
+                .language
+                .tr_with("{} players waiting", tournament.player_count())
+        } else {
+            String::from(settings.language.tr("Waiting for the organizer..."))
+        };
+    }
+    if let Ok(mut text) = announcement.get_single_mut() {
+        text.sections[0].value = tournament.announcement.clone().unwrap_or_default();
+    }
+    // a match needs at least two players
+    for (mut enabled, action) in &mut buttons {
+        if let ButtonAction::StartRound = action {
+            enabled.0 = tournament.player_count() >= 2;
+        }
+    }
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+    let mut list = commands.entity(list_entity);
+    list.despawn_descendants();
+    list.with_children(|parent| {
+        if tournament.standings.is_empty() {
+            parent.spawn(TextBundle::from_section(
+                settings.language.tr("No results yet"),
+                text_style.clone(),
+            ));
+        }
+        for (place, standing) in tournament.standings.iter().enumerate() {
+            parent.spawn(TextBundle::from_section(
+                format!(
+                    "{}. {} - {}",
+                    place + 1,
+                    standing.name,
+                    settings.language.tr_with("{} wins", standing.wins)
+                ),
+                text_style.clone(),
+            ));
+        }
+    });
+}
+
+/// Types the organizer's announcement, and sends it on enter.
+pub fn type_announcement(
+    mut char_evr: EventReader<ReceivedCharacter>,
+    mut text: Query<&mut Text, With<DraftText>>,
+    mut draft: ResMut<Draft>,
+    mut hub: ResMut<HubSocket>,
+    mut tournament: ResMut<Tournament>,
+    settings: Res<Settings>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let Ok(mut text) = text.get_single_mut() else {
+        char_evr.clear();
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::Return) {
+        if !draft.0.is_empty() {
+            let message = std::mem::take(&mut draft.0);
+            announce(&mut hub, &mut tournament, message);
+        }
+    } else if keys.just_pressed(KeyCode::Back) {
+        draft.0.pop();
+    } else {
+        for ev in char_evr.read() {
+            if draft.0.chars().count() < ANNOUNCEMENT_MAX_LEN && !ev.char.is_control() {
+                draft.0.push(ev.char);
+            }
+        }
+    }
+
+    let value = settings.language.tr_with("Announcement: {}", &draft.0);
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+/// Handles button presses.
+pub fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<(&ButtonAction, Option<&ButtonEnabled>)>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut hub: ResMut<HubSocket>,
+    mut tournament: ResMut<Tournament>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    for (action, enabled) in actions.iter_many(clicks.read().map(|click| click.0)) {
+        if enabled.map_or(true, |e| e.0) {
+            match action {
+                ButtonAction::BackToMain => {
+                    menu_state.set(MenuState::Main);
+                }
+                ButtonAction::StartRound => {
+                    start_round(&mut hub, &mut tournament, &settings);
+                }
+                ButtonAction::Leave => {
+                    leave_hub(&mut commands);
+                    menu_state.set(MenuState::Main);
+                }
+            }
+        }
+    }
+}
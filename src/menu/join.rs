@@ -1,11 +1,16 @@
 use super::ButtonEnabled;
 use super::MenuState;
 use super::ServerState;
+use crate::network::AuthState;
+use crate::platform::Paste;
+use crate::pointer::Click;
+use crate::room_code;
+use crate::settings::Settings;
 use bevy::prelude::*;
 
 /// The code entered by the user.
-#[derive(Resource)]
-pub struct Code(String);
+#[derive(Resource, Default)]
+pub struct Code(pub(super) String);
 
 /// The text that displays the code.
 #[derive(Component)]
@@ -20,12 +25,19 @@ pub struct OnScreen;
 pub enum ButtonAction {
     BackToMain,
     Join,
+    /// Types a character from the on-screen keys.
+    Key(char),
+    /// Erases the last character from the on-screen keys.
+    Erase,
 }
 
-/// Draws the join screen and initializes code resource.
-pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.insert_resource(Code(String::default()));
-
+/// Draws the join screen.
+pub fn setup(
+    mut commands: Commands,
+    auth_state: Res<AuthState>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+) {
     let text_style = TextStyle {
         font: asset_server.load("fonts/Lato-Black.ttf"),
         font_size: 40.0,
@@ -78,11 +90,11 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                     text: Text {
                         sections: vec![
                             TextSection {
-                                value: String::from("Enter Room ID:\n"),
+                                value: format!("{}\n", settings.language.tr("Enter Room ID:")),
                                 style: text_style.clone(),
                             },
                             TextSection {
-                                value: String::from(" _ _ _ _"),
+                                value: " _".repeat(room_code::LETTERS_LEN),
                                 style: text_style.clone(),
                             },
                         ],
@@ -94,6 +106,70 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                 CodeText,
             ));
 
+            // let the player know why they were sent back from the room
+            let message = match *auth_state {
+                AuthState::Rejected => Some("Incorrect room password"),
+                AuthState::Full => Some("Room full"),
+                _ => None,
+            };
+            if let Some(message) = message {
+                parent.spawn(TextBundle::from_section(
+                    settings.language.tr(message),
+                    TextStyle {
+                        font_size: 26.0,
+                        color: Color::rgb(1.0, 0.6, 0.6),
+                        ..text_style.clone()
+                    },
+                ));
+            }
+
+            // on-screen keys, for entering the code without a keyboard
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::Grid,
+                        grid_template_columns: RepeatedGridTrack::px(11, 52.0),
+                        row_gap: Val::Px(6.0),
+                        column_gap: Val::Px(6.0),
+                        margin: UiRect::top(Val::Px(16.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let keys = room_code::ALPHABET
+                        .chars()
+                        .chain(['-'])
+                        .map(|c| (ButtonAction::Key(c), String::from(c)))
+                        .chain([(ButtonAction::Erase, String::from("<"))]);
+                    for (action, label) in keys {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        height: Val::Px(48.0),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: Color::WHITE.into(),
+                                    ..default()
+                                },
+                                action,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    label,
+                                    TextStyle {
+                                        font_size: 28.0,
+                                        color: Color::BLACK,
+                                        ..text_style.clone()
+                                    },
+                                ));
+                            });
+                    }
+                });
+
             // start button
             parent.spawn((
                 ButtonBundle {
@@ -115,16 +191,26 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         });
 }
 
-/// Updates stored code on key press.
+/// Clears the entered code when leaving the screen.
+pub fn clear_code(mut code: ResMut<Code>) {
+    code.0.clear();
+}
+
+/// Updates stored code on key press, or when a code is pasted.
 pub fn update_code(
     mut char_evr: EventReader<ReceivedCharacter>,
+    mut pastes: EventReader<Paste>,
     mut code: ResMut<Code>,
     keys: Res<Input<KeyCode>>,
 ) {
     let code = &mut code.0;
     for ev in char_evr.read() {
-        if code.len() < 4 && ev.char.is_ascii_digit() {
-            code.push(ev.char);
+        room_code::push_char(code, ev.char);
+    }
+    // a pasted code replaces what was typed, so "Room K7P2QX" works too
+    for Paste(text) in pastes.read() {
+        if let Some(pasted) = room_code::from_pasted(text) {
+            *code = pasted;
         }
     }
     if keys.just_pressed(KeyCode::Back) {
@@ -137,43 +223,46 @@ pub fn update_code(
 /// Updates the displayed code text.
 pub fn update_code_display(mut text: Query<&mut Text, With<CodeText>>, code: ResMut<Code>) {
     let mut text = text.single_mut();
-    // fills unused digits with underscores
+    // fills the rest of a letter code with underscores, word codes are shown as typed
     let mut code = code.0.clone();
-    for _ in 0..(4 - code.len()) {
-        code.push(' ');
-        code.push('_');
+    if !code.contains('-') {
+        for _ in code.len()..room_code::LETTERS_LEN {
+            code.push_str(" _");
+        }
     }
     text.sections[1].value = code;
 }
 
-/// Enables or disables the start button depending on if code is 4 digits long or not.
+/// Enables or disables the start button depending on if the code is complete or not.
 pub fn update_button_enabled(mut buttons: Query<&mut ButtonEnabled>, code: ResMut<Code>) {
     let mut button = buttons.single_mut();
-    button.0 = code.0.len() == 4;
+    button.0 = room_code::is_valid(&code.0);
 }
 
 /// Handles button presses.
 pub fn handle_action(
-    interaction_query: Query<
-        (&ButtonAction, Option<&ButtonEnabled>),
-        (Changed<Interaction>, With<Button>),
-    >,
+    mut clicks: EventReader<Click>,
+    actions: Query<(&ButtonAction, Option<&ButtonEnabled>)>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut server_state: ResMut<NextState<ServerState>>,
-    mouse: Res<Input<MouseButton>>,
-    code: Res<Code>,
+    mut code: ResMut<Code>,
 ) {
-    for (action, enabled) in &interaction_query {
-        if enabled.map_or(true, |e| e.0) && mouse.just_released(MouseButton::Left) {
+    for (action, enabled) in actions.iter_many(clicks.read().map(|click| click.0)) {
+        if enabled.map_or(true, |e| e.0) {
             match action {
                 ButtonAction::BackToMain => {
                     menu_state.set(MenuState::Main);
                 }
                 ButtonAction::Join => {
-                    let code = code.0.parse::<u16>().expect("integer");
-                    server_state.set(ServerState::Client(code));
+                    server_state.set(ServerState::Client(code.0.clone()));
                     menu_state.set(MenuState::Lobby);
                 }
+                ButtonAction::Key(c) => {
+                    room_code::push_char(&mut code.0, *c);
+                }
+                ButtonAction::Erase => {
+                    code.0.pop();
+                }
             }
         }
     }
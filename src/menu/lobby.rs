@@ -1,30 +1,129 @@
-use super::{MenuState, ServerState};
+use super::{MenuState, ScrollingList, ServerState};
 use crate::info::{Opponent, Opponents};
-use crate::network::{PeerNames, StartGame};
-use crate::SERVER_URL;
+use super::create::PASSWORD_MAX_LEN;
+use crate::card::DealSpeed;
+use crate::challenge::ActiveChallenge;
+use crate::match_mode::MatchFormat;
+use crate::network::{
+    AuthState, ConnectionQuality, ConnectionState, MaxPlayers, PeerConnections, PeerNames,
+    RoomHost, StartGame, SubmitPassword,
+};
+use crate::pointer::Click;
+use crate::room_code;
+use crate::rules::{GameRules, Rule};
+use crate::settings::Settings;
+use crate::storage::Storage;
+use crate::text_fit;
+use crate::transport::GameTransport;
 use bevy::prelude::*;
 use bevy_matchbox::prelude::*;
 
-/// Player count text component.
+/// Height of a row in the player list.
+const PLAYER_ROW_HEIGHT: f32 = 40.0;
+/// Widest a name in the player list can be, next to the host's crown.
+const PLAYER_NAME_WIDTH: f32 = 300.0;
+/// Smallest font size long names in the player list are shrunk to.
+const PLAYER_NAME_MIN_FONT_SIZE: f32 = 22.0;
+
+/// List of player names.
+#[derive(Component)]
+pub struct PlayerList;
+
+/// Password being typed into the password prompt.
+#[derive(Resource, Default)]
+pub struct EnteredPassword(String);
+
+/// Overlay asking for the room password.
+#[derive(Component)]
+pub struct PasswordPrompt;
+
+/// Text showing the password being typed.
+#[derive(Component)]
+pub struct PasswordText;
+
+/// Overlay showing that we're still connecting to the room, or that we couldn't.
+#[derive(Component)]
+pub struct ConnectionStatus;
+
+/// Button that connects to the room again after the connection failed.
+#[derive(Component)]
+pub struct RetryButton;
+
+/// Text on a rule toggle button.
+#[derive(Component)]
+pub struct RuleText(Rule);
+
+/// Text on the max players button.
+#[derive(Component)]
+pub struct MaxPlayersText;
+
+/// Text on the match format button.
+#[derive(Component)]
+pub struct MatchFormatText;
+
+/// Text showing the deal speed.
+#[derive(Component)]
+pub struct DealSpeedText;
+
+/// Text showing what happens to an action card flipped to start the round.
 #[derive(Component)]
-pub struct PlayersText;
+pub struct FirstCardText;
+
+/// Text showing who goes first in each round after the first.
+#[derive(Component)]
+pub struct StartingPlayerText;
+
+/// Text showing how many cards each player is dealt.
+#[derive(Component)]
+pub struct HandSizeText;
+
+/// Summary of the host's options, shown to everyone else in the room.
+#[derive(Component)]
+pub struct RuleSummary;
+
+/// A row shown in the player list.
+#[derive(Clone, PartialEq)]
+pub struct PlayerRow {
+    name: String,
+    is_host: bool,
+    is_self: bool,
+    /// Round trip time (in milliseconds) to the player and how well the connection is doing,
+    /// once it's been measured.
+    ping: Option<(u32, ConnectionQuality)>,
+}
 
 /// Indicates that the component bundle is for this screen.
 #[derive(Component)]
 pub struct OnScreen;
 
+/// Node holding the lobby screen.
+#[derive(Component)]
+pub struct LobbyRoot;
+
 /// Indicates the bundle's associated button action.
 #[derive(Component, Clone, Copy)]
 pub enum ButtonAction {
     Back,
     Start,
+    ToggleRule(Rule),
+    CycleMaxPlayers,
+    CycleMatchFormat,
+    CycleDealSpeed,
+    CycleFirstCard,
+    CycleStartingPlayer,
+    CycleHandSize,
+    SubmitPassword,
 }
 
 /// Draws lobby screen and connects to the server.
 pub fn setup(
     mut commands: Commands,
+    mut auth_state: ResMut<AuthState>,
+    mut storage: ResMut<Storage>,
+    settings: Res<Settings>,
     asset_server: Res<AssetServer>,
     server_state: Res<State<ServerState>>,
+    challenge: Res<ActiveChallenge>,
 ) {
     let text_style = TextStyle {
         font: asset_server.load("fonts/Lato-Black.ttf"),
@@ -32,13 +131,30 @@ pub fn setup(
         color: Color::WHITE,
     };
 
-    let server_state = *server_state.get();
-    let code = match server_state {
-        ServerState::Server(code) => code,
-        ServerState::Client(code) => code,
-        _ => 0,
+    let server_state = server_state.get().clone();
+    // the host is always in its own room, everyone else waits to be let in
+    *auth_state = match server_state {
+        ServerState::Server(_) => AuthState::Accepted,
+        _ => AuthState::Waiting,
+    };
+    commands.init_resource::<EnteredPassword>();
+
+    let code = match &server_state {
+        ServerState::Server(code) | ServerState::Client(code) => code.clone(),
+        ServerState::None => String::new(),
     };
 
+    // remember the room so it can be rejoined after a refresh, unless it's only on this device
+    let is_host = matches!(server_state, ServerState::Server(_));
+    if !challenge.is_active() {
+        if let Err(err) = storage
+            .set("last_room", &code)
+            .and_then(|_| storage.set("last_room_host", &is_host))
+        {
+            println!("Error saving last room: {:?}", err);
+        }
+    }
+
     commands
         .spawn((
             NodeBundle {
@@ -53,6 +169,7 @@ pub fn setup(
                 },
                 ..default()
             },
+            LobbyRoot,
             OnScreen,
         ))
         .with_children(|parent| {
@@ -81,27 +198,427 @@ pub fn setup(
                     justify_content: JustifyContent::Center,
                     ..default()
                 },
-                text: Text::from_section(format!("Room {code}"), text_style.clone()),
+                text: Text::from_section(settings.language.tr_with("Room {}", &code), text_style),
                 ..Default::default()
             });
 
-            // players text
+            // player list
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(360.0),
+                        height: Val::Px(PLAYER_ROW_HEIGHT * 4.0),
+                        margin: UiRect::top(Val::Px(10.0)),
+                        flex_direction: FlexDirection::Column,
+                        overflow: Overflow::clip_y(),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.2).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                width: Val::Percent(100.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        PlayerList,
+                        ScrollingList::new(PLAYER_ROW_HEIGHT),
+                    ));
+                });
+
+            // rule toggles and start button, or what the host has picked
+            if let ServerState::Server(_) = server_state {
+                spawn_host_controls(parent, &asset_server);
+            } else {
+                parent.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                            font_size: 22.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_text_alignment(TextAlignment::Center)
+                    .with_style(Style {
+                        max_width: Val::Px(780.0),
+                        margin: UiRect::top(Val::Px(20.0)),
+                        ..default()
+                    }),
+                    RuleSummary,
+                ));
+            }
+        });
+
+    // games against bots on this device don't need the server
+    if challenge.is_active() {
+        commands.insert_resource(GameTransport::local());
+    } else {
+        start_socket(commands, &settings.server_url, &code);
+    }
+}
+
+/// Spawns the rule toggles and start button, which only the host has.
+fn spawn_host_controls(parent: &mut ChildBuilder, asset_server: &AssetServer) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                max_width: Val::Px(780.0),
+                margin: UiRect::top(Val::Px(20.0)),
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(10.0),
+                row_gap: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for rule in Rule::ALL {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(250.0),
+                                height: Val::Px(36.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: Color::WHITE.into(),
+                            ..default()
+                        },
+                        ButtonAction::ToggleRule(rule),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font: asset_server
+                                        .load("fonts/Lato-BlackItalic.ttf"),
+                                    font_size: 20.0,
+                                    color: Color::BLACK,
+                                },
+                            ),
+                            RuleText(rule),
+                        ));
+                    });
+            }
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(250.0),
+                            height: Val::Px(36.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::CycleMaxPlayers,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                                font_size: 20.0,
+                                color: Color::BLACK,
+                            },
+                        ),
+                        MaxPlayersText,
+                    ));
+                });
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(250.0),
+                            height: Val::Px(36.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::CycleMatchFormat,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                                font_size: 20.0,
+                                color: Color::BLACK,
+                            },
+                        ),
+                        MatchFormatText,
+                    ));
+                });
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(250.0),
+                            height: Val::Px(36.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::CycleDealSpeed,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                                font_size: 20.0,
+                                color: Color::BLACK,
+                            },
+                        ),
+                        DealSpeedText,
+                    ));
+                });
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(250.0),
+                            height: Val::Px(36.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::CycleFirstCard,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                                font_size: 20.0,
+                                color: Color::BLACK,
+                            },
+                        ),
+                        FirstCardText,
+                    ));
+                });
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(250.0),
+                            height: Val::Px(36.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::CycleStartingPlayer,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                                font_size: 20.0,
+                                color: Color::BLACK,
+                            },
+                        ),
+                        StartingPlayerText,
+                    ));
+                });
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(250.0),
+                            height: Val::Px(36.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::CycleHandSize,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                                font_size: 20.0,
+                                color: Color::BLACK,
+                            },
+                        ),
+                        HandSizeText,
+                    ));
+                });
+        });
+
+    parent.spawn((
+        ButtonBundle {
+            style: Style {
+                width: Val::Px(274.0),
+                height: Val::Px(72.0),
+                margin: UiRect::all(Val::Px(20.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::WHITE.into(),
+            image: asset_server.load("textures/buttons/start.png").into(),
+            ..default()
+        },
+        ButtonAction::Start,
+    ));
+}
+
+/// Gives the host controls to a player who took over the room.
+pub fn show_host_controls(
+    screen: Query<Entity, With<LobbyRoot>>,
+    summary: Query<Entity, With<RuleSummary>>,
+    actions: Query<&ButtonAction>,
+    server_state: Res<State<ServerState>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let ServerState::Server(_) = **server_state else { return; };
+    let Ok(screen) = screen.get_single() else { return; };
+    if actions
+        .iter()
+        .any(|action| matches!(action, ButtonAction::Start))
+    {
+        return;
+    }
+    for entity in summary.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands
+        .entity(screen)
+        .with_children(|parent| spawn_host_controls(parent, &asset_server));
+}
+
+/// Connects to the server, with a reliable channel for game events and an unreliable one for
+/// traffic that's fine to lose.
+fn start_socket(mut commands: Commands, server_url: &str, code: &str) {
+    let builder = WebRtcSocketBuilder::new(room_code::room_url(server_url, code))
+        .add_reliable_channel()
+        .add_unreliable_channel();
+    commands.insert_resource(GameTransport::new(MatchboxSocket::from(builder)));
+    commands.insert_resource(ConnectionState::Connecting);
+}
+
+/// Covers the lobby while we connect to the room, or with a way to retry if we couldn't.
+pub fn update_connection_status(
+    status: Query<Entity, With<ConnectionStatus>>,
+    connection: Res<ConnectionState>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    if !connection.is_changed() {
+        return;
+    }
+    for entity in status.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let (message, reason) = match *connection {
+        ConnectionState::Connected => return,
+        ConnectionState::Connecting => ("Connecting...", None),
+        ConnectionState::Failed => ("Connection failed", Some("The server couldn't be reached.")),
+        ConnectionState::RoomNotFound => ("Room not found", Some("No host answered in this room.")),
+    };
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.9).into(),
+                z_index: ZIndex::Global(1),
+                ..default()
+            },
+            ConnectionStatus,
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            // the lobby's back button is under the overlay
             parent.spawn((
-                TextBundle {
+                ButtonBundle {
                     style: Style {
-                        align_self: AlignSelf::Center,
-                        justify_content: JustifyContent::Center,
-                        ..Default::default()
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(26.0),
+                        left: Val::Px(26.0),
+                        width: Val::Px(120.0),
+                        height: Val::Px(46.0),
+                        ..default()
                     },
-                    text: Text::from_section("Players: 1", text_style),
-                    ..Default::default()
+                    background_color: Color::WHITE.into(),
+                    image: asset_server.load("textures/buttons/back.png").into(),
+                    ..default()
                 },
-                PlayersText,
+                ButtonAction::Back,
             ));
 
-            // start button
-            if let ServerState::Server(_) = server_state {
-                parent.spawn((
+            parent.spawn(TextBundle::from_section(
+                settings.language.tr(message),
+                text_style.clone(),
+            ));
+            let Some(reason) = reason else { return; };
+            parent.spawn(TextBundle::from_section(
+                settings.language.tr(reason),
+                TextStyle {
+                    font_size: 26.0,
+                    color: Color::rgb(1.0, 0.6, 0.6),
+                    ..text_style.clone()
+                },
+            ));
+
+            parent
+                .spawn((
                     ButtonBundle {
                         style: Style {
                             width: Val::Px(274.0),
@@ -112,92 +629,585 @@ pub fn setup(
                             ..default()
                         },
                         background_color: Color::WHITE.into(),
-                        image: asset_server.load("textures/buttons/start.png").into(),
                         ..default()
                     },
-                    ButtonAction::Start,
-                ));
-            }
+                    RetryButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        settings.language.tr("Retry"),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                            font_size: 40.0,
+                            color: Color::BLACK,
+                        },
+                    ));
+                });
         });
-
-    start_socket(commands, code);
 }
 
-/// Connects to the server.
-fn start_socket(mut commands: Commands, code: u16) {
-    let room_url = format!("{SERVER_URL}/v1_{code}");
-    commands.insert_resource(MatchboxSocket::new_reliable(room_url));
+/// Connects to the room again when the retry button is pressed.
+pub fn retry_connection(
+    mut clicks: EventReader<Click>,
+    retry: Query<(), With<RetryButton>>,
+    server_state: Res<State<ServerState>>,
+    settings: Res<Settings>,
+    mut auth_state: ResMut<AuthState>,
+    mut peer_names: ResMut<PeerNames>,
+    mut room_host: ResMut<RoomHost>,
+    mut commands: Commands,
+) {
+    if !clicks.read().any(|click| retry.contains(click.0)) {
+        return;
+    }
+    let code = match server_state.get() {
+        ServerState::Server(code) => code,
+        ServerState::Client(code) => {
+            *auth_state = AuthState::Waiting;
+            code
+        }
+        ServerState::None => return,
+    };
+    peer_names.0.clear();
+    room_host.0 = None;
+    // removing the old connection first makes the new one count as added, so peers are forgotten
+    commands.remove_resource::<GameTransport>();
+    start_socket(commands, &settings.server_url, code);
 }
 
 /// Closes the server connection.
-pub fn close_socket(mut commands: Commands) {
-    commands.remove_resource::<MatchboxSocket<SingleChannel>>();
+pub fn close_socket(
+    mut commands: Commands,
+    mut peer_names: ResMut<PeerNames>,
+    mut room_host: ResMut<RoomHost>,
+) {
+    commands.remove_resource::<GameTransport>();
+    peer_names.0.clear();
+    room_host.0 = None;
+}
+
+/// Rebuilds the player list when players join or leave.
+pub fn update_player_list(
+    list: Query<(Entity, Ref<PlayerList>)>,
+    socket: Res<GameTransport>,
+    server_state: Res<State<ServerState>>,
+    peer_names: Res<PeerNames>,
+    room_host: Res<RoomHost>,
+    connections: Res<PeerConnections>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    mut shown_rows: Local<Vec<PlayerRow>>,
+    mut commands: Commands,
+) {
+    let Ok((list_entity, list)) = list.get_single() else { return; };
+
+    let mut rows = vec![PlayerRow {
+        name: settings.username.clone(),
+        is_host: matches!(**server_state, ServerState::Server(_)),
+        is_self: true,
+        ping: None,
+    }];
+    // peers only send their name once they've been let into the room
+    for peer in socket.connected_peers() {
+        let Some(name) = peer_names.0.get(&peer) else { continue; };
+        let ping = connections
+            .latency(&peer)
+            .zip(connections.quality(&peer, time.elapsed_seconds()))
+            .map(|(latency, quality)| ((latency * 2000.0).round() as u32, quality));
+        rows.push(PlayerRow {
+            name: name.clone(),
+            is_host: room_host.0 == Some(peer),
+            is_self: false,
+            ping,
+        });
+    }
+    // host first, then us, then everyone else by name
+    rows.sort_by(|a, b| {
+        b.is_host
+            .cmp(&a.is_host)
+            .then(b.is_self.cmp(&a.is_self))
+            .then(a.name.cmp(&b.name))
+    });
+
+    if !list.is_added() && *shown_rows == rows {
+        return;
+    }
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 30.0,
+        color: Color::WHITE,
+    };
+    let mut list = commands.entity(list_entity);
+    list.despawn_descendants();
+    list.with_children(|parent| {
+        for row in rows.iter() {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        height: Val::Px(PLAYER_ROW_HEIGHT),
+                        padding: UiRect::horizontal(Val::Px(10.0)),
+                        column_gap: Val::Px(10.0),
+                        align_items: AlignItems::Center,
+                        flex_shrink: 0.0,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    // crown next to the host, or empty space to keep names aligned
+                    let icon_style = Style {
+                        width: Val::Px(24.0),
+                        height: Val::Px(24.0),
+                        ..default()
+                    };
+                    if row.is_host {
+                        parent.spawn(ImageBundle {
+                            style: icon_style,
+                            image: asset_server.load("textures/icons/crown.png").into(),
+                            ..default()
+                        });
+                    } else {
+                        parent.spawn(NodeBundle {
+                            style: icon_style,
+                            ..default()
+                        });
+                    }
+
+                    let template = if row.is_self {
+                        settings.language.tr("{} (you)")
+                    } else {
+                        "{}"
+                    };
+                    let (name, font_size) = text_fit::fit_filled(
+                        template,
+                        &row.name,
+                        text_style.font_size,
+                        PLAYER_NAME_MIN_FONT_SIZE,
+                        PLAYER_NAME_WIDTH,
+                    );
+                    parent.spawn(TextBundle::from_section(
+                        name,
+                        TextStyle {
+                            font_size,
+                            ..text_style.clone()
+                        },
+                    ));
+
+                    if let Some((ping, quality)) = row.ping {
+                        parent.spawn(TextBundle::from_section(
+                            format!("{ping} ms"),
+                            TextStyle {
+                                font_size: 20.0,
+                                color: quality.color(),
+                                ..text_style.clone()
+                            },
+                        ));
+                    }
+                });
+        }
+    });
+
+    *shown_rows = rows;
+}
+
+/// Shows the password prompt when the host asks for one, and leaves if the password was wrong.
+pub fn update_auth_prompt(
+    prompt: Query<Entity, With<PasswordPrompt>>,
+    auth_state: Res<AuthState>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut server_state: ResMut<NextState<ServerState>>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    if !auth_state.is_changed() {
+        return;
+    }
+    match *auth_state {
+        AuthState::Required => {
+            if !prompt.is_empty() {
+                return;
+            }
+            let text_style = TextStyle {
+                font: asset_server.load("fonts/Lato-Black.ttf"),
+                font_size: 40.0,
+                color: Color::WHITE,
+            };
+            commands
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        background_color: Color::rgba(0.0, 0.0, 0.0, 0.9).into(),
+                        z_index: ZIndex::Global(1),
+                        ..default()
+                    },
+                    PasswordPrompt,
+                    OnScreen,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle {
+                            text: Text {
+                                sections: vec![
+                                    TextSection {
+                                        value: format!(
+                                            "{}\n",
+                                            settings.language.tr("Room Password:")
+                                        ),
+                                        style: text_style.clone(),
+                                    },
+                                    TextSection {
+                                        value: String::new(),
+                                        style: text_style,
+                                    },
+                                ],
+                                alignment: TextAlignment::Center,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        PasswordText,
+                    ));
+
+                    parent.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(274.0),
+                                height: Val::Px(72.0),
+                                margin: UiRect::all(Val::Px(20.0)),
+                                ..default()
+                            },
+                            background_color: Color::WHITE.into(),
+                            image: asset_server.load("textures/buttons/join.png").into(),
+                            ..default()
+                        },
+                        ButtonAction::SubmitPassword,
+                    ));
+                });
+        }
+        AuthState::Rejected | AuthState::Full => {
+            menu_state.set(MenuState::Join);
+            server_state.set(ServerState::None);
+        }
+        AuthState::Waiting | AuthState::Accepted => {
+            for entity in prompt.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
 }
 
-/// Updates the player count text.
-pub fn update_players_text(
-    mut query: Query<&mut Text, With<PlayersText>>,
-    socket: Res<MatchboxSocket<SingleChannel>>,
+/// Updates the entered password on key press and shows it masked.
+pub fn update_entered_password(
+    mut char_evr: EventReader<ReceivedCharacter>,
+    mut text: Query<&mut Text, With<PasswordText>>,
+    mut entered: ResMut<EnteredPassword>,
+    keys: Res<Input<KeyCode>>,
 ) {
-    let count = socket.connected_peers().collect::<Vec<_>>().len() + 1;
-    let mut text = query.single_mut();
-    text.sections[0].value = format!("Players: {count}");
+    let Ok(mut text) = text.get_single_mut() else {
+        char_evr.clear();
+        return;
+    };
+    let password = &mut entered.0;
+    if keys.just_pressed(KeyCode::Back) {
+        password.pop();
+    } else {
+        for ev in char_evr.read() {
+            if password.len() < PASSWORD_MAX_LEN && ev.char.is_ascii_graphic() {
+                password.push(ev.char);
+            }
+        }
+    }
+    text.sections[1].value = "*".repeat(password.len());
+}
+
+/// Shows whether each rule is enabled.
+pub fn update_rules_text(
+    mut text: Query<(&mut Text, &RuleText)>,
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    added: Query<(), Added<RuleText>>,
+) {
+    if !rules.is_changed() && added.is_empty() {
+        return;
+    }
+    for (mut text, RuleText(rule)) in &mut text {
+        let state = if rule.is_enabled(&rules) { "On" } else { "Off" };
+        text.sections[0].value = format!(
+            "{}: {}",
+            settings.language.tr(rule.label()),
+            settings.language.tr(state)
+        );
+    }
+}
+
+/// Shows the maximum number of players.
+pub fn update_max_players_text(
+    mut text: Query<&mut Text, With<MaxPlayersText>>,
+    max_players: Res<MaxPlayers>,
+    settings: Res<Settings>,
+    added: Query<(), Added<MaxPlayersText>>,
+) {
+    if !max_players.is_changed() && added.is_empty() {
+        return;
+    }
+    for mut text in &mut text {
+        text.sections[0].value = settings.language.tr_with("Max Players: {}", max_players.0);
+    }
+}
+
+/// Shows the match format.
+pub fn update_match_format_text(
+    mut text: Query<&mut Text, With<MatchFormatText>>,
+    format: Res<MatchFormat>,
+    settings: Res<Settings>,
+    added: Query<(), Added<MatchFormatText>>,
+) {
+    if !format.is_changed() && added.is_empty() {
+        return;
+    }
+    for mut text in &mut text {
+        text.sections[0].value = format!(
+            "{}: {}",
+            settings.language.tr("Match"),
+            format.label(settings.language)
+        );
+    }
+}
+
+/// Shows the deal speed.
+pub fn update_deal_speed_text(
+    mut text: Query<&mut Text, With<DealSpeedText>>,
+    deal_speed: Res<DealSpeed>,
+    settings: Res<Settings>,
+    added: Query<(), Added<DealSpeedText>>,
+) {
+    if !deal_speed.is_changed() && added.is_empty() {
+        return;
+    }
+    for mut text in &mut text {
+        text.sections[0].value = format!(
+            "{}: {}",
+            settings.language.tr("Deal"),
+            settings.language.tr(deal_speed.name())
+        );
+    }
+}
+
+/// Shows what happens to an action card flipped to start the round.
+pub fn update_first_card_text(
+    mut text: Query<&mut Text, With<FirstCardText>>,
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    added: Query<(), Added<FirstCardText>>,
+) {
+    if !rules.is_changed() && added.is_empty() {
+        return;
+    }
+    for mut text in &mut text {
+        text.sections[0].value = format!(
+            "{}: {}",
+            settings.language.tr("First card"),
+            settings.language.tr(rules.first_card.name())
+        );
+    }
+}
+
+/// Shows who goes first in each round after the first.
+pub fn update_starting_player_text(
+    mut text: Query<&mut Text, With<StartingPlayerText>>,
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    added: Query<(), Added<StartingPlayerText>>,
+) {
+    if !rules.is_changed() && added.is_empty() {
+        return;
+    }
+    for mut text in &mut text {
+        text.sections[0].value = format!(
+            "{}: {}",
+            settings.language.tr("Starts"),
+            settings.language.tr(rules.starting_player.name())
+        );
+    }
+}
+
+/// Shows how many cards each player is dealt.
+pub fn update_hand_size_text(
+    mut text: Query<&mut Text, With<HandSizeText>>,
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    added: Query<(), Added<HandSizeText>>,
+) {
+    if !rules.is_changed() && added.is_empty() {
+        return;
+    }
+    for mut text in &mut text {
+        text.sections[0].value = format!(
+            "{}: {}",
+            settings.language.tr("Hand size"),
+            rules.hand_size.0
+        );
+    }
+}
+
+/// Shows the options the host has picked, which are sent again whenever they change.
+pub fn update_rule_summary(
+    mut text: Query<&mut Text, With<RuleSummary>>,
+    rules: Res<GameRules>,
+    max_players: Res<MaxPlayers>,
+    format: Res<MatchFormat>,
+    deal_speed: Res<DealSpeed>,
+    settings: Res<Settings>,
+    added: Query<(), Added<RuleSummary>>,
+) {
+    if !(rules.is_changed()
+        || max_players.is_changed()
+        || format.is_changed()
+        || deal_speed.is_changed())
+        && added.is_empty()
+    {
+        return;
+    }
+    let language = settings.language;
+    let house_rules = Rule::ALL
+        .iter()
+        .filter(|rule| rule.is_enabled(&rules))
+        .map(|rule| language.tr(rule.label()))
+        .collect::<Vec<_>>();
+    let house_rules = if house_rules.is_empty() {
+        String::from(language.tr("None"))
+    } else {
+        house_rules.join(", ")
+    };
+    for mut text in &mut text {
+        text.sections[0].value = [
+            language.tr_with("Max Players: {}", max_players.0),
+            format!("{}: {}", language.tr("Match"), format.label(language)),
+            format!("{}: {}", language.tr("Deal"), language.tr(deal_speed.name())),
+            format!(
+                "{}: {}",
+                language.tr("First card"),
+                language.tr(rules.first_card.name())
+            ),
+            format!(
+                "{}: {}",
+                language.tr("Starts"),
+                language.tr(rules.starting_player.name())
+            ),
+            format!(
+                "{}: {}",
+                language.tr("Deck"),
+                language.tr_with("{} cards", rules.deck.combined(rules.decks()).len())
+            ),
+            format!("{}: {}", language.tr("House rules"), house_rules),
+        ]
+        .join("\n");
+    }
 }
 
 /// Handles button presses.
 pub fn handle_action(
-    interaction_query: Query<&ButtonAction, (Changed<Interaction>, With<Button>)>,
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
     mut start_events: EventWriter<StartGame>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut server_state: ResMut<NextState<ServerState>>,
-    mut socket: ResMut<MatchboxSocket<SingleChannel>>,
+    mut socket: ResMut<GameTransport>,
+    mut password_events: EventWriter<SubmitPassword>,
     mut opponents: ResMut<Opponents>,
-    mouse: Res<Input<MouseButton>>,
+    mut rules: ResMut<GameRules>,
+    mut max_players: ResMut<MaxPlayers>,
+    mut match_format: ResMut<MatchFormat>,
+    mut deal_speed: ResMut<DealSpeed>,
+    mut entered: ResMut<EnteredPassword>,
     peer_names: Res<PeerNames>,
 ) {
-    for menu_button_action in &interaction_query {
-        if mouse.just_released(MouseButton::Left) {
-            match menu_button_action {
-                ButtonAction::Back => {
-                    menu_state.set(MenuState::Main);
-                    server_state.set(ServerState::None);
-                }
-                ButtonAction::Start => {
-                    // get peer ids and randomly shuffle for player order
-                    let Some(own_pid) = socket.id() else { return; };
-                    let mut order = socket.connected_peers().collect::<Vec<_>>();
-
-                    order.push(own_pid);
-                    use rand::seq::SliceRandom;
-                    use rand::thread_rng;
-                    order.shuffle(&mut thread_rng());
-
-                    // set opponents
-                    opponents.0 = order
-                        .iter()
-                        .filter_map(|pid| {
-                            if *pid == own_pid {
-                                None
-                            } else {
-                                Some(Opponent::new(
-                                    *pid,
-                                    peer_names
-                                        .0
-                                        .get(pid)
-                                        .cloned()
-                                        .unwrap_or_else(|| String::from("Unknown")),
-                                    5,
-                                ))
-                            }
-                        })
-                        .collect();
-
-                    // send start game event to connected peers
-                    start_events.send(StartGame {
-                        order,
-                        restart: false,
-                    });
-                }
+    for menu_button_action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match menu_button_action {
+            ButtonAction::Back => {
+                menu_state.set(MenuState::Main);
+                server_state.set(ServerState::None);
+            }
+            ButtonAction::Start => {
+                // get peer ids and randomly shuffle for player order
+                let Some(own_pid) = socket.id() else { return; };
+                // only peers that have been let into the room are playing
+                let mut order = socket
+                    .connected_peers()
+                    .filter(|pid| peer_names.0.contains_key(pid))
+                    .collect::<Vec<_>>();
+
+                order.push(own_pid);
+                use rand::seq::SliceRandom;
+                use rand::thread_rng;
+                order.shuffle(&mut thread_rng());
+
+                // set opponents
+                opponents.0 = order
+                    .iter()
+                    .filter_map(|pid| {
+                        if *pid == own_pid {
+                            None
+                        } else {
+                            Some(Opponent::new(
+                                *pid,
+                                peer_names
+                                    .0
+                                    .get(pid)
+                                    .cloned()
+                                    .unwrap_or_else(|| String::from("Unknown")),
+                                rules.hand_size.cards(),
+                            ))
+                        }
+                    })
+                    .collect();
+
+                // send start game event to connected peers
+                start_events.send(StartGame {
+                    order,
+                    restart: false,
+                    seed: None,
+                });
+            }
+            ButtonAction::ToggleRule(rule) => {
+                let enabled = rule.is_enabled(&rules);
+                rule.set(&mut rules, !enabled);
+            }
+            ButtonAction::CycleMaxPlayers => {
+                max_players.0 = if max_players.0 >= MaxPlayers::MAX {
+                    MaxPlayers::MIN
+                } else {
+                    max_players.0 + 1
+                };
+            }
+            ButtonAction::CycleMatchFormat => {
+                *match_format = match_format.next();
+            }
+            ButtonAction::CycleFirstCard => {
+                rules.first_card = rules.first_card.next();
+            }
+            ButtonAction::CycleStartingPlayer => {
+                rules.starting_player = rules.starting_player.next();
+            }
+            ButtonAction::CycleHandSize => {
+                rules.hand_size = rules.hand_size.next();
+            }
+            ButtonAction::CycleDealSpeed => {
+                *deal_speed = deal_speed.next();
+            }
+            ButtonAction::SubmitPassword => {
+                password_events.send(SubmitPassword(std::mem::take(&mut entered.0)));
             }
         }
     }
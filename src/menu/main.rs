@@ -1,6 +1,15 @@
-use super::{MenuState, ServerState};
+use super::MenuState;
+use crate::challenge::ActiveChallenge;
+use crate::history::LastGame;
+use crate::network::ServerState;
+use crate::pointer::Click;
+use crate::profile::{Profiles, AVATAR_COLORS};
+use crate::room_check::RoomCheck;
+use crate::rules::GameRules;
+use crate::settings::{random_username, Settings};
+use crate::storage::Storage;
+use crate::tournament::Tournament;
 use bevy::prelude::*;
-use rand::Rng;
 
 const TEXT_COLOR: Color = Color::WHITE;
 
@@ -13,11 +22,37 @@ pub struct OnScreen;
 pub enum ButtonAction {
     Host,
     Join,
+    QuickMatch,
+    Rejoin { code: String, is_host: bool },
+    Rematch,
     Settings,
+    Stats,
+    HowToPlay,
+    Challenges,
+    Tutorial,
+    Tournament,
+    SwitchProfile,
+    ChangeAvatar,
+    NewProfile,
+}
+
+/// Returns the code of the room we were last in, and whether we were hosting it.
+fn last_room(storage: &Storage) -> Option<(String, bool)> {
+    let code = storage.get("last_room").ok()?;
+    let is_host = storage.get("last_room_host").ok()?;
+    Some((code, is_host))
 }
 
 /// Draws the main menu.
-pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn setup(
+    mut commands: Commands,
+    storage: Res<Storage>,
+    settings: Res<Settings>,
+    profiles: Res<Profiles>,
+    asset_server: Res<AssetServer>,
+) {
+    let profile = profiles.active();
+
     // Common style for all buttons on the screen
     let button_style = Style {
         width: Val::Px(274.0),
@@ -60,6 +95,304 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                 ButtonAction::Settings,
             ));
 
+            // stats on the rules used in our games
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            top: Val::Px(26.0),
+                            right: Val::Px(90.0),
+                            padding: UiRect::axes(Val::Px(14.0), Val::Px(8.0)),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::Stats,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        settings.language.tr("Stats"),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                            font_size: 28.0,
+                            color: Color::BLACK,
+                        },
+                    ));
+                });
+
+            // how the rules of the game work
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            top: Val::Px(90.0),
+                            right: Val::Px(26.0),
+                            padding: UiRect::axes(Val::Px(14.0), Val::Px(8.0)),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::HowToPlay,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        settings.language.tr("How to play"),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                            font_size: 28.0,
+                            color: Color::BLACK,
+                        },
+                    ));
+                });
+
+            // games against bots with their own rules
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            top: Val::Px(154.0),
+                            right: Val::Px(26.0),
+                            padding: UiRect::axes(Val::Px(14.0), Val::Px(8.0)),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::Challenges,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        settings.language.tr("Challenges"),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                            font_size: 28.0,
+                            color: Color::BLACK,
+                        },
+                    ));
+                });
+
+            // a guided game for new players
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            top: Val::Px(218.0),
+                            right: Val::Px(26.0),
+                            padding: UiRect::axes(Val::Px(14.0), Val::Px(8.0)),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::Tutorial,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        settings.language.tr("Tutorial"),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                            font_size: 28.0,
+                            color: Color::BLACK,
+                        },
+                    ));
+                });
+
+            // tournaments, which show the hub instead if we're already in one
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            top: Val::Px(26.0),
+                            left: Val::Px(26.0),
+                            padding: UiRect::axes(Val::Px(14.0), Val::Px(8.0)),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::Tournament,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        settings.language.tr("Tournament"),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                            font_size: 28.0,
+                            color: Color::BLACK,
+                        },
+                    ));
+                });
+
+            // profile switcher: the avatar changes color, the name switches to the next profile
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(90.0),
+                        left: Val::Px(26.0),
+                        column_gap: Val::Px(10.0),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    // buttons are drawn white, so the avatar's color goes on a node inside
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(44.0),
+                                    height: Val::Px(44.0),
+                                    padding: UiRect::all(Val::Px(3.0)),
+                                    ..default()
+                                },
+                                background_color: Color::WHITE.into(),
+                                ..default()
+                            },
+                            ButtonAction::ChangeAvatar,
+                        ))
+                        .with_children(|parent| {
+                            parent
+                                .spawn(NodeBundle {
+                                    style: Style {
+                                        width: Val::Percent(100.0),
+                                        height: Val::Percent(100.0),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: profile.color().into(),
+                                    ..default()
+                                })
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        profile.initial(),
+                                        TextStyle {
+                                            font: asset_server.load("fonts/Lato-Black.ttf"),
+                                            font_size: 26.0,
+                                            color: TEXT_COLOR,
+                                        },
+                                    ));
+                                });
+                        });
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    padding: UiRect::axes(Val::Px(12.0), Val::Px(4.0)),
+                                    ..default()
+                                },
+                                background_color: Color::WHITE.into(),
+                                ..default()
+                            },
+                            ButtonAction::SwitchProfile,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                profile.name.clone(),
+                                TextStyle {
+                                    font: asset_server.load("fonts/Lato-Black.ttf"),
+                                    font_size: 24.0,
+                                    color: Color::BLACK,
+                                },
+                            ));
+                            parent.spawn(TextBundle::from_section(
+                                format!(
+                                    "{}, {}",
+                                    settings.language.tr_with("{} games", profile.stats.games),
+                                    settings.language.tr_with("{} wins", profile.stats.wins)
+                                ),
+                                TextStyle {
+                                    font: asset_server.load("fonts/Lato-Black.ttf"),
+                                    font_size: 18.0,
+                                    color: Color::DARK_GRAY,
+                                },
+                            ));
+                        });
+                    if profiles.can_add() {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        padding: UiRect::axes(Val::Px(12.0), Val::Px(2.0)),
+                                        ..default()
+                                    },
+                                    background_color: Color::WHITE.into(),
+                                    ..default()
+                                },
+                                ButtonAction::NewProfile,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    "+",
+                                    TextStyle {
+                                        font: asset_server.load("fonts/Lato-Black.ttf"),
+                                        font_size: 28.0,
+                                        color: Color::BLACK,
+                                    },
+                                ));
+                            });
+                    }
+                });
+
+            // summary of the last game, with a shortcut to host another with the same rules
+            if let Some(last_game) = LastGame::load(&storage, profile) {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            bottom: Val::Px(26.0),
+                            padding: UiRect::axes(Val::Px(16.0), Val::Px(10.0)),
+                            column_gap: Val::Px(16.0),
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::rgba(0.0, 0.0, 0.0, 0.3).into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            last_game.summary(settings.language),
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-Black.ttf"),
+                                font_size: 24.0,
+                                color: TEXT_COLOR,
+                            },
+                        ));
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        padding: UiRect::axes(Val::Px(14.0), Val::Px(6.0)),
+                                        ..default()
+                                    },
+                                    background_color: Color::WHITE.into(),
+                                    ..default()
+                                },
+                                ButtonAction::Rematch,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    settings.language.tr("Rematch"),
+                                    TextStyle {
+                                        font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                                        font_size: 24.0,
+                                        color: Color::BLACK,
+                                    },
+                                ));
+                            });
+                    });
+            }
+
             parent
                 .spawn(NodeBundle {
                     style: Style {
@@ -107,31 +440,130 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         },
                         ButtonAction::Join,
                     ));
+
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: Color::WHITE.into(),
+                                ..default()
+                            },
+                            ButtonAction::QuickMatch,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                settings.language.tr("Quick Match"),
+                                TextStyle {
+                                    font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                                    font_size: 40.0,
+                                    color: Color::BLACK,
+                                },
+                            ));
+                        });
+
+                    if let Some((code, is_host)) = last_room(&storage) {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: Color::WHITE.into(),
+                                    ..default()
+                                },
+                                ButtonAction::Rejoin {
+                                    code: code.clone(),
+                                    is_host,
+                                },
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    settings.language.tr_with("Rejoin Room {}", code),
+                                    TextStyle {
+                                        font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                                        font_size: 36.0,
+                                        color: Color::BLACK,
+                                    },
+                                ));
+                            });
+                    }
                 });
         });
 }
 
 /// Handles button presses.
 pub fn handle_action(
-    interaction_query: Query<&ButtonAction, (Changed<Interaction>, With<Button>)>,
-    mouse: Res<Input<MouseButton>>,
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut server_state: ResMut<NextState<ServerState>>,
+    mut rules: ResMut<GameRules>,
+    mut profiles: ResMut<Profiles>,
+    mut challenge: ResMut<ActiveChallenge>,
+    storage: Res<Storage>,
+    settings: Res<Settings>,
+    tournament: Option<Res<Tournament>>,
+    mut commands: Commands,
 ) {
-    for menu_button_action in &interaction_query {
-        if mouse.just_released(MouseButton::Left) {
-            match menu_button_action {
-                ButtonAction::Host => {
-                    let code = rand::thread_rng().gen_range(1000..10000);
-                    server_state.set(ServerState::Server(code));
-                    menu_state.set(MenuState::Lobby);
-                }
-                ButtonAction::Join => {
-                    menu_state.set(MenuState::Join);
-                }
-                ButtonAction::Settings => {
-                    menu_state.set(MenuState::Settings);
+    for menu_button_action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match menu_button_action {
+            ButtonAction::Host => {
+                menu_state.set(MenuState::Create);
+            }
+            ButtonAction::Join => {
+                menu_state.set(MenuState::Join);
+            }
+            ButtonAction::QuickMatch => {
+                menu_state.set(MenuState::Browse);
+            }
+            ButtonAction::Rejoin { code, is_host } => {
+                server_state.set(if *is_host {
+                    ServerState::Server(code.clone())
+                } else {
+                    ServerState::Client(code.clone())
+                });
+                menu_state.set(MenuState::Lobby);
+            }
+            ButtonAction::Rematch => {
+                // host a new room with the rules from the last game
+                if let Some(last_game) = LastGame::load(&storage, profiles.active()) {
+                    *rules = last_game.rules;
                 }
+                RoomCheck::start(&mut commands, &settings);
+            }
+            ButtonAction::Settings => {
+                menu_state.set(MenuState::Settings);
+            }
+            ButtonAction::Stats => {
+                menu_state.set(MenuState::Stats);
+            }
+            ButtonAction::HowToPlay => {
+                menu_state.set(MenuState::HowToPlay);
+            }
+            ButtonAction::Challenges => {
+                menu_state.set(MenuState::Challenges);
+            }
+            ButtonAction::Tutorial => {
+                // played in a room of our own, like a challenge
+                challenge.start_tutorial(&mut rules);
+                let code = settings.room_codes.generate(&mut rand::thread_rng());
+                server_state.set(ServerState::Server(code));
+                menu_state.set(MenuState::Lobby);
+            }
+            ButtonAction::Tournament => {
+                menu_state.set(if tournament.is_some() {
+                    MenuState::Hub
+                } else {
+                    MenuState::Tournament
+                });
+            }
+            ButtonAction::SwitchProfile => {
+                profiles.switch();
+            }
+            ButtonAction::ChangeAvatar => {
+                let profile = profiles.active_mut();
+                profile.avatar = (profile.avatar + 1) % AVATAR_COLORS.len();
+            }
+            ButtonAction::NewProfile => {
+                profiles.add(random_username());
             }
         }
     }
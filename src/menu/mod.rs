@@ -2,29 +2,101 @@
 
 use crate::button::ButtonEnabled;
 use crate::network::ServerState;
+use crate::profile::Profiles;
+use crate::storage::Storage;
+use crate::tournament::{HubSocket, Tournament};
+use crate::transport::GameTransport;
 use crate::{despawn_screen, ScreenState};
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::prelude::{Plugin as BevyPlugin, *};
-use bevy_matchbox::prelude::*;
+use serde::{Deserialize, Serialize};
 
+mod browse;
+mod challenges;
+mod create;
+mod deck_editor;
+pub mod how_to_play;
+mod hub;
 mod join;
 mod lobby;
 mod main;
 mod settings;
+mod snapshot;
+mod stats;
+mod tournament;
 
 /// State used for the current menu screen.
-#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States, Serialize, Deserialize)]
 pub enum MenuState {
     #[default]
     Disabled,
     Main,
+    Browse,
+    Create,
     Join,
     Lobby,
     Settings,
+    Stats,
+    HowToPlay,
+    Challenges,
+    Tournament,
+    Hub,
+    DeckEditor,
 }
 
-/// Initializes the menu state to the main menu.
-fn setup(mut menu_state: ResMut<NextState<MenuState>>) {
-    menu_state.set(MenuState::Main);
+/// A list that can be scrolled with the mouse wheel.
+///
+/// The list node should be the child of a node that clips its overflow.
+#[derive(Component)]
+pub struct ScrollingList {
+    position: f32,
+    /// Distance scrolled for each line of mouse wheel movement.
+    line_height: f32,
+}
+
+impl ScrollingList {
+    pub fn new(line_height: f32) -> Self {
+        Self {
+            position: 0.0,
+            line_height,
+        }
+    }
+}
+
+/// Opens the main menu, or wherever the user was before the page was refreshed.
+fn setup(
+    mut menu_state: ResMut<NextState<MenuState>>,
+    storage: Res<Storage>,
+    mut code: ResMut<join::Code>,
+    mut focused: ResMut<settings::FocusedField>,
+    mut restored: Local<bool>,
+) {
+    menu_state.set(snapshot::restore(
+        &storage,
+        &mut code,
+        &mut focused,
+        &mut restored,
+    ));
+}
+
+/// Scrolls lists with the mouse wheel.
+fn scroll_lists(
+    mut wheel_events: EventReader<MouseWheel>,
+    mut lists: Query<(&mut ScrollingList, &mut Style, &Parent, &Node)>,
+    nodes: Query<&Node>,
+) {
+    for event in wheel_events.read() {
+        for (mut list, mut style, parent, list_node) in &mut lists {
+            let Ok(container) = nodes.get(parent.get()) else { continue; };
+            let max_scroll = (list_node.size().y - container.size().y).max(0.0);
+            let dy = match event.unit {
+                MouseScrollUnit::Line => event.y * list.line_height,
+                MouseScrollUnit::Pixel => event.y,
+            };
+            list.position = (list.position + dy).clamp(-max_scroll, 0.0);
+            style.top = Val::Px(list.position);
+        }
+    }
 }
 
 pub struct Plugin;
@@ -32,17 +104,71 @@ pub struct Plugin;
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
         app.add_state::<MenuState>()
+            .init_resource::<join::Code>()
+            .init_resource::<settings::FocusedField>()
             .add_systems(OnEnter(ScreenState::Menu), setup)
+            .add_systems(Update, scroll_lists)
             // main menu
             .add_systems(OnEnter(MenuState::Main), main::setup)
             .add_systems(OnExit(MenuState::Main), despawn_screen::<main::OnScreen>)
             .add_systems(
                 Update,
-                main::handle_action.run_if(in_state(MenuState::Main)),
+                (
+                    main::handle_action,
+                    // redraw to show the new profile
+                    (despawn_screen::<main::OnScreen>, main::setup)
+                        .chain()
+                        .run_if(resource_changed::<Profiles>()),
+                )
+                    .chain()
+                    .run_if(in_state(MenuState::Main)),
+            )
+            // room browser menu
+            .add_systems(OnEnter(MenuState::Browse), browse::setup)
+            .add_systems(OnExit(MenuState::Browse), despawn_screen::<browse::OnScreen>)
+            .add_systems(
+                Update,
+                (browse::handle_action, browse::update_room_list)
+                    .run_if(in_state(MenuState::Browse)),
+            )
+            // lobby creation menu
+            .add_systems(
+                OnTransition {
+                    from: MenuState::Main,
+                    to: MenuState::Create,
+                },
+                create::clear_password,
+            )
+            .add_systems(OnEnter(MenuState::Create), create::setup)
+            .add_systems(OnExit(MenuState::Create), despawn_screen::<create::OnScreen>)
+            .add_systems(
+                Update,
+                (
+                    create::handle_action,
+                    create::update_password,
+                    create::update_password_display,
+                    create::update_check_display,
+                )
+                    .run_if(in_state(MenuState::Create)),
+            )
+            // deck editor, opened from the lobby creation menu
+            .add_systems(OnEnter(MenuState::DeckEditor), deck_editor::setup)
+            .add_systems(
+                OnExit(MenuState::DeckEditor),
+                despawn_screen::<deck_editor::OnScreen>,
+            )
+            .add_systems(
+                Update,
+                (deck_editor::handle_action, deck_editor::update_deck_text)
+                    .chain()
+                    .run_if(in_state(MenuState::DeckEditor)),
             )
             // join menu
             .add_systems(OnEnter(MenuState::Join), join::setup)
-            .add_systems(OnExit(MenuState::Join), despawn_screen::<join::OnScreen>)
+            .add_systems(
+                OnExit(MenuState::Join),
+                (despawn_screen::<join::OnScreen>, join::clear_code),
+            )
             .add_systems(
                 Update,
                 (
@@ -60,11 +186,27 @@ impl BevyPlugin for Plugin {
             .add_systems(OnExit(MenuState::Lobby), despawn_screen::<lobby::OnScreen>)
             .add_systems(
                 OnEnter(ServerState::None),
-                lobby::close_socket.run_if(resource_exists::<MatchboxSocket<SingleChannel>>()),
+                lobby::close_socket.run_if(resource_exists::<GameTransport>()),
             )
             .add_systems(
                 Update,
-                (lobby::handle_action, lobby::update_players_text)
+                (
+                    lobby::handle_action,
+                    lobby::update_player_list,
+                    lobby::update_rules_text,
+                    lobby::update_max_players_text,
+                    lobby::update_match_format_text,
+                    lobby::update_deal_speed_text,
+                    lobby::update_first_card_text,
+                    lobby::update_starting_player_text,
+                    lobby::update_hand_size_text,
+                    lobby::update_rule_summary,
+                    lobby::update_auth_prompt,
+                    lobby::update_entered_password,
+                    lobby::update_connection_status,
+                    lobby::retry_connection,
+                    lobby::show_host_controls.run_if(state_changed::<ServerState>()),
+                )
                     .run_if(in_state(MenuState::Lobby)),
             )
             // settings menu
@@ -77,10 +219,65 @@ impl BevyPlugin for Plugin {
                 Update,
                 (
                     settings::handle_action,
-                    settings::update_name,
-                    settings::update_name_display,
+                    settings::handle_widgets,
+                    settings::type_text,
+                    settings::update_widgets,
                 )
+                    .chain()
                     .run_if(in_state(MenuState::Settings)),
+            )
+            // rule stats
+            .add_systems(OnEnter(MenuState::Stats), stats::setup)
+            .add_systems(OnExit(MenuState::Stats), despawn_screen::<stats::OnScreen>)
+            .add_systems(
+                Update,
+                stats::handle_action.run_if(in_state(MenuState::Stats)),
+            )
+            // how to play
+            .add_systems(OnEnter(MenuState::HowToPlay), how_to_play::setup)
+            .add_systems(
+                OnExit(MenuState::HowToPlay),
+                despawn_screen::<how_to_play::OnScreen>,
+            )
+            .add_systems(
+                Update,
+                how_to_play::handle_action.run_if(in_state(MenuState::HowToPlay)),
+            )
+            // challenge select
+            .add_systems(OnEnter(MenuState::Challenges), challenges::setup)
+            .add_systems(
+                OnExit(MenuState::Challenges),
+                despawn_screen::<challenges::OnScreen>,
+            )
+            .add_systems(
+                Update,
+                challenges::handle_action.run_if(in_state(MenuState::Challenges)),
+            )
+            // tournament menu
+            .add_systems(OnEnter(MenuState::Tournament), tournament::setup)
+            .add_systems(
+                OnExit(MenuState::Tournament),
+                despawn_screen::<tournament::OnScreen>,
+            )
+            .add_systems(
+                Update,
+                (tournament::handle_action, tournament::update_code)
+                    .run_if(in_state(MenuState::Tournament)),
+            )
+            // tournament hub
+            .add_systems(OnEnter(MenuState::Hub), hub::setup)
+            .add_systems(OnExit(MenuState::Hub), despawn_screen::<hub::OnScreen>)
+            .add_systems(
+                Update,
+                (hub::handle_action, hub::update_hub, hub::type_announcement)
+                    .run_if(in_state(MenuState::Hub))
+                    .run_if(resource_exists::<HubSocket>())
+                    .run_if(resource_exists::<Tournament>()),
             );
+
+        // refreshing the page is only a risk in the browser
+        if cfg!(target_arch = "wasm32") {
+            app.add_systems(Update, snapshot::save_snapshot);
+        }
     }
 }
@@ -1,10 +1,13 @@
-use super::MenuState;
-use crate::{storage::Storage, Username};
+use super::{MenuState, ScrollingList};
+use crate::pointer::{Click, Pointer};
+use crate::settings::{Section, Setting, Settings, Widget};
 use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
 
-/// Username text component.
-#[derive(Component)]
-pub struct UsernameText;
+/// Height of a row in the options panel.
+const ROW_HEIGHT: f32 = 50.0;
+/// Color of the unfilled part of a slider.
+const SLIDER_TRACK_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.25);
 
 /// Indicates that the component bundle is for this screen.
 #[derive(Component)]
@@ -16,13 +19,41 @@ pub enum ButtonAction {
     BackToMain,
 }
 
+/// Title of a section, translated when the language changes.
+#[derive(Component)]
+pub struct SectionTitle(Section);
+
+/// Label next to a setting, translated when the language changes.
+#[derive(Component)]
+pub struct SettingLabel(Setting);
+
+/// Node that edits a setting when clicked.
+#[derive(Component)]
+pub struct SettingWidget(Setting);
+
+/// Text showing a setting's value.
+#[derive(Component)]
+pub struct SettingValue(Setting);
+
+/// Filled part of a slider.
+#[derive(Component)]
+pub struct SliderFill(Setting);
+
+/// Text field currently being typed into.
+#[derive(Resource, Default)]
+pub struct FocusedField(pub(super) Option<Setting>);
+
 /// Draws settings screen.
 pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     let text_style = TextStyle {
         font: asset_server.load("fonts/Lato-Black.ttf"),
-        font_size: 40.0,
+        font_size: 28.0,
         color: Color::WHITE,
     };
+    let widget_text_style = TextStyle {
+        color: Color::BLACK,
+        ..text_style.clone()
+    };
 
     commands
         .spawn((
@@ -31,7 +62,6 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                     width: Val::Percent(100.0),
                     height: Val::Percent(100.0),
                     flex_direction: FlexDirection::Column,
-                    align_content: AlignContent::Center,
                     align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
                     ..default()
@@ -59,75 +89,295 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                 ButtonAction::BackToMain,
             ));
 
-            // enter id text
-            parent.spawn((
-                TextBundle {
+            // options panel, which clips the scrolling list of settings
+            parent
+                .spawn(NodeBundle {
                     style: Style {
-                        align_self: AlignSelf::Center,
-                        justify_content: JustifyContent::Center,
+                        width: Val::Px(620.0),
+                        height: Val::Percent(75.0),
+                        flex_direction: FlexDirection::Column,
+                        overflow: Overflow::clip_y(),
                         ..default()
                     },
-                    text: Text {
-                        sections: vec![
-                            TextSection {
-                                value: String::from("Username:\n"),
-                                style: text_style.clone(),
-                            },
-                            TextSection {
-                                value: String::new(),
-                                style: text_style.clone(),
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.3).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Percent(100.0),
+                                    flex_direction: FlexDirection::Column,
+                                    flex_shrink: 0.0,
+                                    padding: UiRect::all(Val::Px(16.0)),
+                                    ..default()
+                                },
+                                ..default()
                             },
-                        ],
+                            ScrollingList::new(ROW_HEIGHT),
+                        ))
+                        .with_children(|parent| {
+                            for section in Section::ALL {
+                                spawn_section(parent, section, &text_style, &widget_text_style);
+                            }
+                        });
+                });
+        });
+}
+
+/// Draws a section title and the settings in it.
+fn spawn_section(
+    parent: &mut ChildBuilder,
+    section: Section,
+    text_style: &TextStyle,
+    widget_text_style: &TextStyle,
+) {
+    parent.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 34.0,
+                ..text_style.clone()
+            },
+        )
+        .with_style(Style {
+            margin: UiRect::vertical(Val::Px(8.0)),
+            ..default()
+        }),
+        SectionTitle(section),
+    ));
+
+    for setting in Setting::ALL.into_iter().filter(|s| s.section() == section) {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    height: Val::Px(ROW_HEIGHT),
+                    flex_shrink: 0.0,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                parent.spawn((
+                    TextBundle::from_section("", text_style.clone()),
+                    SettingLabel(setting),
+                ));
+                spawn_widget(parent, setting, text_style, widget_text_style);
+            });
+    }
+}
+
+/// Draws the widget used to edit a setting.
+fn spawn_widget(
+    parent: &mut ChildBuilder,
+    setting: Setting,
+    text_style: &TextStyle,
+    widget_text_style: &TextStyle,
+) {
+    match setting.widget() {
+        Widget::Toggle | Widget::TextField { .. } => {
+            let width = if matches!(setting.widget(), Widget::Toggle) {
+                200.0
+            } else {
+                320.0
+            };
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(width),
+                            height: Val::Px(ROW_HEIGHT - 10.0),
+                            padding: UiRect::horizontal(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            overflow: Overflow::clip(),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    SettingWidget(setting),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section("", widget_text_style.clone()),
+                        SettingValue(setting),
+                    ));
+                });
+        }
+        Widget::Slider { .. } => {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(12.0),
                         ..default()
                     },
                     ..default()
-                },
-                UsernameText,
-            ));
-        });
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Px(200.0),
+                                    height: Val::Px(16.0),
+                                    ..default()
+                                },
+                                background_color: SLIDER_TRACK_COLOR.into(),
+                                ..default()
+                            },
+                            Interaction::default(),
+                            RelativeCursorPosition::default(),
+                            SettingWidget(setting),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        height: Val::Percent(100.0),
+                                        ..default()
+                                    },
+                                    background_color: Color::WHITE.into(),
+                                    ..default()
+                                },
+                                SliderFill(setting),
+                            ));
+                        });
+                    parent.spawn((
+                        TextBundle::from_section("", text_style.clone()).with_style(Style {
+                            width: Val::Px(100.0),
+                            ..default()
+                        }),
+                        SettingValue(setting),
+                    ));
+                });
+        }
+    }
+}
+
+/// Shows the current value of every setting, and translates labels when the language changes.
+pub fn update_widgets(
+    mut titles: Query<(&mut Text, &SectionTitle), Without<SettingValue>>,
+    mut labels: Query<(&mut Text, &SettingLabel), (Without<SectionTitle>, Without<SettingValue>)>,
+    mut values: Query<(&mut Text, &SettingValue)>,
+    mut fills: Query<(&mut Style, &SliderFill)>,
+    focused: Res<FocusedField>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    for (mut text, SectionTitle(section)) in &mut titles {
+        let title = settings.language.tr(section.title());
+        if text.sections[0].value != title {
+            text.sections[0].value = String::from(title);
+        }
+    }
+    for (mut text, SettingLabel(setting)) in &mut labels {
+        let label = settings.language.tr(setting.label());
+        if text.sections[0].value != label {
+            text.sections[0].value = String::from(label);
+        }
+    }
+    for (mut text, SettingValue(setting)) in &mut values {
+        let mut value = settings.display(*setting);
+        // blink a cursor in the field being typed into
+        if focused.0 == Some(*setting) && time.elapsed_seconds() % 1.0 < 0.5 {
+            value.push('|');
+        }
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+    for (mut style, SliderFill(setting)) in &mut fills {
+        let width = Val::Percent(settings.fraction(*setting) * 100.0);
+        if style.width != width {
+            style.width = width;
+        }
+    }
+}
+
+/// Edits settings when their widgets are clicked or dragged.
+pub fn handle_widgets(
+    widgets: Query<(
+        Entity,
+        &Interaction,
+        &SettingWidget,
+        Option<&RelativeCursorPosition>,
+    )>,
+    mut clicks: EventReader<Click>,
+    mut focused: ResMut<FocusedField>,
+    mut settings: ResMut<Settings>,
+    pointer: Res<Pointer>,
+) {
+    // clicking anywhere finishes editing a text field
+    if pointer.just_pressed {
+        focused.0 = None;
+    }
+
+    let clicked: Vec<Entity> = clicks.read().map(|click| click.0).collect();
+    for (entity, interaction, SettingWidget(setting), cursor) in &widgets {
+        match setting.widget() {
+            Widget::Toggle => {
+                if clicked.contains(&entity) {
+                    settings.toggle(*setting);
+                }
+            }
+            Widget::TextField { .. } => {
+                if *interaction == Interaction::Pressed && pointer.just_pressed {
+                    focused.0 = Some(*setting);
+                }
+            }
+            Widget::Slider { .. } => {
+                // follow the cursor while the slider is held
+                if *interaction != Interaction::Pressed {
+                    continue;
+                }
+                if let Some(position) = cursor.and_then(|cursor| cursor.normalized) {
+                    // only mark the settings as changed if the value moved a step
+                    let mut changed = settings.clone();
+                    changed.set_fraction(*setting, position.x);
+                    settings.set_if_neq(changed);
+                }
+            }
+        }
+    }
 }
 
-/// Updates stored username on key press.
-pub fn update_name(
+/// Types into the focused text field.
+pub fn type_text(
     mut char_evr: EventReader<ReceivedCharacter>,
-    mut name: ResMut<Username>,
+    mut focused: ResMut<FocusedField>,
+    mut settings: ResMut<Settings>,
     keys: Res<Input<KeyCode>>,
 ) {
-    let name = &mut name.0;
-    if keys.just_pressed(KeyCode::Back) {
-        name.pop();
+    let Some(setting) = focused.0 else {
+        char_evr.clear();
+        return;
+    };
+    if keys.just_pressed(KeyCode::Return) || keys.just_pressed(KeyCode::Escape) {
+        focused.0 = None;
+    } else if keys.just_pressed(KeyCode::Back) {
+        settings.pop_char(setting);
     } else {
         for ev in char_evr.read() {
-            if name.len() < 15 && (ev.char.is_alphanumeric() || ev.char == '_' || ev.char == ' ') {
-                name.push(ev.char);
-            }
+            settings.push_char(setting, ev.char);
         }
     }
 }
 
-/// Copies stored username to text display.
-pub fn update_name_display(mut text: Query<&mut Text, With<UsernameText>>, name: Res<Username>) {
-    let mut text = text.single_mut();
-    text.sections[1].value = name.0.clone();
-}
-
 /// Handles button presses.
 pub fn handle_action(
-    interaction_query: Query<&ButtonAction, (Changed<Interaction>, With<Button>)>,
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
     mut menu_state: ResMut<NextState<MenuState>>,
-    mut storage: ResMut<Storage>,
-    mouse: Res<Input<MouseButton>>,
-    name: Res<Username>,
 ) {
-    for action in interaction_query.iter() {
-        if mouse.just_released(MouseButton::Left) {
-            match action {
-                ButtonAction::BackToMain => {
-                    menu_state.set(MenuState::Main);
-                    storage
-                        .set("username", &name.0)
-                        .expect("failed to save username");
-                }
+    for action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match action {
+            ButtonAction::BackToMain => {
+                menu_state.set(MenuState::Main);
             }
         }
     }
@@ -0,0 +1,108 @@
+//! Snapshot of where the user is in the menus, so a page refresh on the web lands them back
+//! there instead of on the main menu.
+//!
+//! Only saved in the browser, where refreshing the page by accident is easy. Screens that need a
+//! live connection, like the lobby, aren't restored; the main menu offers to rejoin the room.
+
+use super::join::Code;
+use super::settings::FocusedField;
+use super::MenuState;
+use crate::settings::Setting;
+use crate::storage::Storage;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Key the snapshot is stored under.
+const SNAPSHOT_KEY: &str = "menu_snapshot";
+/// How often (in seconds) the snapshot is saved.
+const SAVE_INTERVAL: f32 = 3.0;
+
+/// Menu screen and anything typed into it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MenuSnapshot {
+    menu: MenuState,
+    /// Room code entered on the join screen.
+    code: String,
+    /// Settings text field being typed into.
+    editing: Option<Setting>,
+}
+
+impl MenuSnapshot {
+    /// Returns the screen to go back to, or the main menu if it can't be shown again.
+    fn restored_menu(&self) -> MenuState {
+        match self.menu {
+            MenuState::Disabled | MenuState::Lobby => MenuState::Main,
+            // the hub's connection is gone, so start from picking one again
+            MenuState::Hub => MenuState::Tournament,
+            menu => menu,
+        }
+    }
+}
+
+/// Returns the screen to open the menus on, restoring the last snapshot the first time.
+pub fn restore(
+    storage: &Storage,
+    code: &mut Code,
+    focused: &mut FocusedField,
+    restored: &mut bool,
+) -> MenuState {
+    if !cfg!(target_arch = "wasm32") || *restored {
+        return MenuState::Main;
+    }
+    *restored = true;
+    let Ok(snapshot) = storage.get::<MenuSnapshot>(SNAPSHOT_KEY) else { return MenuState::Main; };
+    let menu = snapshot.restored_menu();
+    if menu == MenuState::Join {
+        code.0 = snapshot.code;
+    }
+    if menu == MenuState::Settings {
+        focused.0 = snapshot.editing;
+    }
+    menu
+}
+
+/// Saves a snapshot of the menus every few seconds, if anything changed.
+pub fn save_snapshot(
+    menu_state: Res<State<MenuState>>,
+    code: Res<Code>,
+    focused: Res<FocusedField>,
+    mut storage: ResMut<Storage>,
+    time: Res<Time>,
+    mut since_save: Local<f32>,
+    mut saved: Local<Option<MenuSnapshot>>,
+) {
+    *since_save += time.delta_seconds();
+    if *since_save < SAVE_INTERVAL {
+        return;
+    }
+    *since_save = 0.0;
+
+    let snapshot = MenuSnapshot {
+        menu: *menu_state.get(),
+        code: code.0.clone(),
+        editing: focused.0,
+    };
+    if saved.as_ref() == Some(&snapshot) {
+        return;
+    }
+    if let Err(err) = storage.set(SNAPSHOT_KEY, &snapshot) {
+        error!("Error saving menu snapshot: {err}");
+    }
+    *saved = Some(snapshot);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that screens needing a connection fall back to the main menu.
+    #[test]
+    fn test_restored_menu() {
+        let snapshot = |menu| MenuSnapshot { menu, ..default() };
+        assert_eq!(snapshot(MenuState::Join).restored_menu(), MenuState::Join);
+        assert_eq!(snapshot(MenuState::Settings).restored_menu(), MenuState::Settings);
+        assert_eq!(snapshot(MenuState::Lobby).restored_menu(), MenuState::Main);
+        assert_eq!(snapshot(MenuState::Disabled).restored_menu(), MenuState::Main);
+        assert_eq!(snapshot(MenuState::Hub).restored_menu(), MenuState::Tournament);
+    }
+}
@@ -0,0 +1,202 @@
+use super::MenuState;
+use crate::analytics::RuleStats;
+use crate::pointer::Click;
+use crate::profile::Profiles;
+use crate::rules::Rule;
+use crate::settings::Settings;
+use crate::storage::Storage;
+use bevy::prelude::*;
+
+/// Most sets of rules shown.
+const MAX_PRESETS: usize = 6;
+/// Width of the bar for the longest average game.
+const BAR_WIDTH: f32 = 200.0;
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+pub struct OnScreen;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component)]
+pub enum ButtonAction {
+    BackToMain,
+}
+
+/// Draws the rule stats page, with a bar for the average game length with each set of rules.
+pub fn setup(
+    mut commands: Commands,
+    storage: Res<Storage>,
+    profiles: Res<Profiles>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+) {
+    let language = settings.language;
+    let stats = RuleStats::load(&storage, profiles.active());
+    let presets = stats.most_played();
+    let longest = presets
+        .iter()
+        .map(|preset| preset.average_minutes())
+        .fold(0.0, f32::max);
+
+    let title_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 22.0,
+        color: Color::WHITE,
+    };
+    let detail_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 18.0,
+        color: Color::GRAY,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            // back button
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(26.0),
+                        left: Val::Px(26.0),
+                        width: Val::Px(120.0),
+                        height: Val::Px(46.0),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    image: asset_server.load("textures/buttons/back.png").into(),
+                    ..default()
+                },
+                ButtonAction::BackToMain,
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                language.tr("Rule Stats"),
+                title_style,
+            ));
+
+            if presets.is_empty() {
+                let message = if settings.rule_stats {
+                    "No games recorded yet"
+                } else {
+                    "Turn on rule stats in the settings to start keeping them"
+                };
+                parent.spawn(TextBundle::from_section(language.tr(message), text_style));
+                return;
+            }
+
+            // how often each rule is turned on
+            let usage: Vec<String> = Rule::ALL
+                .iter()
+                .map(|rule| (rule, stats.games_with(*rule)))
+                .filter(|(_, games)| *games > 0)
+                .map(|(rule, games)| {
+                    format!(
+                        "{}: {}",
+                        language.tr(rule.label()),
+                        language.tr_with("{} games", games)
+                    )
+                })
+                .collect();
+            parent.spawn(TextBundle::from_section(
+                language.tr_with("{} games", stats.games()),
+                detail_style.clone(),
+            ));
+            if !usage.is_empty() {
+                parent.spawn(TextBundle::from_section(
+                    usage.join(", "),
+                    detail_style.clone(),
+                ));
+            }
+
+            for preset in presets.into_iter().take(MAX_PRESETS) {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(640.0),
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(4.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            preset.rules.summary_in(language),
+                            text_style.clone(),
+                        ));
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    column_gap: Val::Px(10.0),
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                // bars are scaled against the longest average game
+                                let fraction = preset.average_minutes() / longest.max(1.0);
+                                parent.spawn(NodeBundle {
+                                    style: Style {
+                                        width: Val::Px(BAR_WIDTH * fraction),
+                                        height: Val::Px(14.0),
+                                        ..default()
+                                    },
+                                    background_color: Color::rgb(0.95, 0.6, 0.15).into(),
+                                    ..default()
+                                });
+                                parent.spawn(TextBundle::from_section(
+                                    format!(
+                                        "{}, {}, {}",
+                                        language.tr_with(
+                                            "{} min",
+                                            preset.average_minutes().round().max(1.0)
+                                        ),
+                                        language.tr_with("{} games", preset.games),
+                                        language.tr_with(
+                                            "{}% won",
+                                            (preset.win_rate() * 100.0).round()
+                                        ),
+                                    ),
+                                    detail_style.clone(),
+                                ));
+                            });
+                    });
+            }
+        });
+}
+
+/// Handles button presses.
+pub fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+) {
+    for action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match action {
+            ButtonAction::BackToMain => {
+                menu_state.set(MenuState::Main);
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use super::{ButtonEnabled, MenuState};
+use crate::pointer::Click;
+use crate::settings::Settings;
+use crate::tournament::join_hub;
+use bevy::prelude::*;
+use rand::Rng;
+
+/// The hub code entered by the user.
+#[derive(Resource, Default)]
+pub struct HubCode(String);
+
+/// The text that displays the hub code.
+#[derive(Component)]
+pub struct HubCodeText;
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+pub struct OnScreen;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component, Clone, Copy)]
+pub enum ButtonAction {
+    BackToMain,
+    Join,
+    Organize,
+}
+
+/// Draws the screen for joining or organizing a tournament.
+pub fn setup(mut commands: Commands, settings: Res<Settings>, asset_server: Res<AssetServer>) {
+    commands.init_resource::<HubCode>();
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+    let button_text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+        font_size: 36.0,
+        color: Color::BLACK,
+    };
+    let button_style = Style {
+        width: Val::Px(274.0),
+        height: Val::Px(72.0),
+        margin: UiRect::all(Val::Px(10.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            // back button
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(26.0),
+                        left: Val::Px(26.0),
+                        width: Val::Px(120.0),
+                        height: Val::Px(46.0),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    image: asset_server.load("textures/buttons/back.png").into(),
+                    ..default()
+                },
+                ButtonAction::BackToMain,
+            ));
+
+            // hub code text
+            parent.spawn((
+                TextBundle {
+                    text: Text {
+                        sections: vec![
+                            TextSection {
+                                value: format!("{}\n", settings.language.tr("Enter Hub ID:")),
+                                style: text_style.clone(),
+                            },
+                            TextSection {
+                                value: String::from(" _ _ _ _"),
+                                style: text_style,
+                            },
+                        ],
+                        alignment: TextAlignment::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+                HubCodeText,
+            ));
+
+            for (label, action, enabled) in [
+                ("Join Hub", ButtonAction::Join, false),
+                ("Organize", ButtonAction::Organize, true),
+            ] {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: Color::WHITE.into(),
+                            ..default()
+                        },
+                        action,
+                        ButtonEnabled(enabled),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            settings.language.tr(label),
+                            button_text_style.clone(),
+                        ));
+                    });
+            }
+        });
+}
+
+/// Updates the hub code on key press, and shows it.
+pub fn update_code(
+    mut char_evr: EventReader<ReceivedCharacter>,
+    mut text: Query<&mut Text, With<HubCodeText>>,
+    mut buttons: Query<(&mut ButtonEnabled, &ButtonAction)>,
+    mut code: ResMut<HubCode>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let code = &mut code.0;
+    for ev in char_evr.read() {
+        if code.len() < 4 && ev.char.is_ascii_digit() {
+            code.push(ev.char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        code.pop();
+    }
+
+    // fills unused digits with underscores
+    let mut display: String = code.chars().flat_map(|c| [' ', c]).collect();
+    for _ in code.len()..4 {
+        display.push_str(" _");
+    }
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[1].value = display;
+    }
+
+    for (mut enabled, action) in &mut buttons {
+        if let ButtonAction::Join = action {
+            enabled.0 = code.len() == 4;
+        }
+    }
+}
+
+/// Handles button presses.
+pub fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<(&ButtonAction, Option<&ButtonEnabled>)>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    code: Res<HubCode>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    for (action, enabled) in actions.iter_many(clicks.read().map(|click| click.0)) {
+        if enabled.map_or(true, |e| e.0) {
+            match action {
+                ButtonAction::BackToMain => {
+                    menu_state.set(MenuState::Main);
+                }
+                ButtonAction::Join => {
+                    let code = code.0.parse::<u16>().expect("integer");
+                    join_hub(&mut commands, &settings, code, false);
+                    menu_state.set(MenuState::Hub);
+                }
+                ButtonAction::Organize => {
+                    let code = rand::thread_rng().gen_range(1000..10000);
+                    join_hub(&mut commands, &settings, code, true);
+                    menu_state.set(MenuState::Hub);
+                }
+            }
+        }
+    }
+}
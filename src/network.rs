@@ -1,36 +1,278 @@
 //! Peer to peer communication and game events.
 
 use crate::{
-    card::{Card, CardColor, CardPosition, CardType, CardValue, SpawnCard},
-    deck::{Deck, DiscardCards, MainPlayer},
+    bot::{BotMoves, Bots},
+    card::{
+        clear_queued_cards, Card, CardColor, CardPosition, CardType, CardValue, DealQueue,
+        DealSpeed, SpawnCard, SpawnQueue,
+    },
+    challenge::ActiveChallenge,
+    deck::{Deck, DiscardCards, GameRng, MainPlayer, ProvisionalPlay},
+    disconnect::not_paused,
     game_ui::board::DiscardCard,
+    game_ui::camera::CameraCue,
     game_ui::hand::HandCard,
-    info::{GameInfo, Opponent, Opponents},
+    info::{Direction, GameInfo, OneCards, Opponent, Opponents, TurnTimes, CATCH_PENALTY},
+    match_mode::MatchFormat,
     menu::MenuState,
-    screens::win::Win,
-    GameScreenState, ScreenState, Username,
+    profile::Profiles,
+    rules::GameRules,
+    screens::win::{HandRevealed, Win},
+    settings::Settings,
+    theme::{HostTable, TableTheme},
+    transport::GameTransport,
+    GameScreenState, GameSet, ScreenState,
 };
 use bevy::{
+    ecs::system::SystemParam,
     prelude::{Plugin as BevyPlugin, *},
-    utils::{HashMap, Uuid},
+    utils::{HashMap, HashSet, Uuid},
 };
-use bevy_matchbox::prelude::*;
+use bevy_matchbox::{matchbox_socket::Packet, prelude::*};
+use std::collections::BTreeMap;
 
-/// Server state.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash, States)]
+/// Server state, with the code of the room we're hosting or in.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Hash, States)]
 pub enum ServerState {
     #[default]
     None,
-    Server(u16),
-    Client(u16),
+    Server(String),
+    Client(String),
 }
 
 /// Storage of names for connected peers.
 #[derive(Resource)]
 pub struct PeerNames(pub HashMap<PeerId, String>);
 
+/// Player ids of connected peers' profiles, sent along with their names.
+///
+/// Unlike a [`PeerId`], a player id stays the same from one game to the next.
+#[derive(Resource, Default)]
+pub struct PlayerIds(pub HashMap<PeerId, u64>);
+
+/// The peer hosting the room.
+///
+/// This is `None` if we're the host, or if the host hasn't announced itself yet.
+#[derive(Resource, Default)]
+pub struct RoomHost(pub Option<PeerId>);
+
+/// Password required to join the room we're hosting.
+///
+/// An empty password means the room is public.
+#[derive(Resource, Default)]
+pub struct RoomPassword(pub String);
+
+/// Most players the host lets into the room, including the host.
+#[derive(Resource)]
+pub struct MaxPlayers(pub u8);
+
+impl MaxPlayers {
+    /// Smallest allowed maximum player count.
+    pub const MIN: u8 = 2;
+    /// Largest allowed maximum player count.
+    pub const MAX: u8 = 8;
+}
+
+impl Default for MaxPlayers {
+    fn default() -> Self {
+        Self(Self::MAX)
+    }
+}
+
+/// Whether we've been let into the room by the host.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthState {
+    /// Waiting to hear from the host.
+    #[default]
+    Waiting,
+    /// The host wants a password.
+    Required,
+    /// We're a member of the room.
+    Accepted,
+    /// The host rejected our password.
+    Rejected,
+    /// The room has no space left.
+    Full,
+}
+
+/// How far along our connection to the room is.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Waiting on the server, or on the room's host to answer.
+    #[default]
+    Connecting,
+    /// We're hosting the room, or the host has answered.
+    Connected,
+    /// The server couldn't be reached, or dropped us.
+    Failed,
+    /// No host answered in time, so the room doesn't exist or its host is gone.
+    RoomNotFound,
+}
+
+/// Longest time (in seconds) to wait in a room for its host to answer before giving up on it.
+const HOST_TIMEOUT: f32 = 10.0;
+/// Longest time (in seconds) the host waits for players to clear the last round before dealing.
+const RESTART_TIMEOUT: f32 = 5.0;
+
+/// Time (in seconds) between the host's turn clock updates.
+const TURN_CLOCK_INTERVAL: f32 = 1.0;
+/// Time (in seconds) between pings to each peer.
+const PING_INTERVAL: f32 = 1.0;
+/// How long (in seconds) a peer can go without being heard from before the connection is marked
+/// as poor.
+const QUIET_TIME: f32 = 2.5;
+/// How long (in seconds) a peer can go without being heard from before they're treated as gone,
+/// which is much sooner than the connection itself gives up.
+const PEER_TIMEOUT: f32 = 8.0;
+/// How much each new round trip time measurement counts towards a peer's latency.
+const LATENCY_SMOOTHING: f32 = 0.2;
+/// One-way latencies (in seconds) above which connections are shown as fair and poor.
+const FAIR_LATENCY: f32 = 0.06;
+const POOR_LATENCY: f32 = 0.15;
+/// Channel for game events, which must all arrive, and in order.
+const RELIABLE_CHANNEL: usize = 0;
+/// Channel for frequent traffic where a lost or late packet is soon replaced, like pings.
+const UNRELIABLE_CHANNEL: usize = 1;
+/// Most packets held back from a peer while waiting on an earlier one, before it's given up on.
+const REORDER_LIMIT: usize = 64;
+/// Most packets kept from a peer the host hasn't let into the room yet.
+const HELD_LIMIT: usize = 64;
+
+/// How well the connection to a peer is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionQuality {
+    Good,
+    Fair,
+    Poor,
+}
+
+impl ConnectionQuality {
+    /// Returns the color of the connection icon.
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Good => Color::rgb(0.3, 0.8, 0.3),
+            Self::Fair => Color::rgb(0.95, 0.75, 0.2),
+            Self::Poor => Color::rgb(0.85, 0.25, 0.2),
+        }
+    }
+}
+
+/// Latency to each peer and when they were last heard from, kept up to date with pings.
+#[derive(Resource, Default)]
+pub struct PeerConnections {
+    /// Estimated one-way latency (in seconds) to each peer.
+    latency: HashMap<PeerId, f32>,
+    /// Time each peer last sent us anything.
+    last_heard: HashMap<PeerId, f32>,
+}
+
+impl PeerConnections {
+    /// Returns the estimated one-way latency (in seconds) to a peer, once it's been measured.
+    pub fn latency(&self, peer: &PeerId) -> Option<f32> {
+        self.latency.get(peer).copied()
+    }
+
+    /// Returns how well the connection to a peer is doing, once its latency has been measured.
+    pub fn quality(&self, peer: &PeerId, now: f32) -> Option<ConnectionQuality> {
+        let latency = self.latency(peer)?;
+        let quiet = matches!(self.last_heard.get(peer), Some(heard) if now - heard > QUIET_TIME);
+        Some(if quiet || latency > POOR_LATENCY {
+            ConnectionQuality::Poor
+        } else if latency > FAIR_LATENCY {
+            ConnectionQuality::Fair
+        } else {
+            ConnectionQuality::Good
+        })
+    }
+
+    /// Notes that a peer was heard from.
+    fn heard(&mut self, peer: PeerId, now: f32) {
+        self.last_heard.insert(peer, now);
+    }
+
+    /// Updates a peer's latency from the round trip time of a ping.
+    fn measure(&mut self, peer: PeerId, round_trip: f32) {
+        let one_way = round_trip.max(0.0) / 2.0;
+        self.latency
+            .entry(peer)
+            .and_modify(|latency| *latency += (one_way - *latency) * LATENCY_SMOOTHING)
+            .or_insert(one_way);
+    }
+
+    /// Forgets a peer that's gone.
+    fn forget(&mut self, peer: &PeerId) {
+        self.latency.remove(peer);
+        self.last_heard.remove(peer);
+    }
+
+    /// Returns the peers that haven't been heard from in too long.
+    fn timed_out(&self, now: f32) -> Vec<PeerId> {
+        self.last_heard
+            .iter()
+            .filter(|(_, heard)| now - **heard > PEER_TIMEOUT)
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+}
+
+/// Puts the packets from each peer back in the order they were sent, dropping duplicates.
+///
+/// Every packet on the reliable channel starts with a little endian `u32` sequence number, counting
+/// up from zero for each peer it's sent to.
+#[derive(Default)]
+struct PacketOrder {
+    /// Next sequence number expected from each peer.
+    expected: HashMap<PeerId, u32>,
+    /// Packets that arrived before ones still missing, by sequence number.
+    early: HashMap<PeerId, BTreeMap<u32, Packet>>,
+}
+
+impl PacketOrder {
+    /// Takes a packet from a peer, moving it and any packets it was holding up into `ready`
+    /// without their sequence numbers.
+    fn receive(&mut self, peer: PeerId, packet: Packet, ready: &mut Vec<(PeerId, Packet)>) {
+        let Some(sequence) = read_u32(&packet, 0) else {
+            error!("Received packet without a sequence number.");
+            return;
+        };
+        let expected = self.expected.entry(peer).or_default();
+        if sequence < *expected {
+            // already handled
+            return;
+        }
+        let early = self.early.entry(peer).or_default();
+        early.insert(sequence, packet[4..].into());
+        // stop waiting for a missing packet once too many have piled up behind it
+        if early.len() > REORDER_LIMIT {
+            if let Some((first, _)) = early.first_key_value() {
+                warn!("Gave up on packets {}..{first} from {peer}.", *expected);
+                *expected = *first;
+            }
+        }
+        while let Some(packet) = early.remove(expected) {
+            ready.push((peer, packet));
+            *expected += 1;
+        }
+    }
+
+    /// Forgets the sequence numbers for a peer that left.
+    fn forget(&mut self, peer: &PeerId) {
+        self.expected.remove(peer);
+        self.early.remove(peer);
+    }
+}
+
+/// Auth packet status: the host requires a password.
+const AUTH_REQUIRED: u8 = 0;
+/// Auth packet status: the peer is a member of the room.
+const AUTH_ACCEPTED: u8 = 1;
+/// Auth packet status: the password was wrong.
+const AUTH_REJECTED: u8 = 2;
+/// Auth packet status: a password attempt, followed by the password.
+const AUTH_ATTEMPT: u8 = 3;
+
 /// Socket event, which corresponds to one byte.
-#[derive(PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum SocketEvent {
     Start,
     Draw,
@@ -38,6 +280,37 @@ pub enum SocketEvent {
     Restart,
     Name,
     Wild,
+    SyncRequest,
+    Sync,
+    Host,
+    Auth,
+    LobbyFull,
+    CallOne,
+    CatchOne,
+    RevealHand,
+    ShareHand,
+    DropPlayer,
+    AbortGame,
+    TurnClock,
+    RestartPrepare,
+    RestartReady,
+    TableTheme,
+    ReturnCards,
+    Reshuffle,
+    PlayConfirmed,
+    PlayRejected,
+    PlayMulti,
+    HostChange,
+    Leave,
+    Ping,
+    Pong,
+    RematchVote,
+    RematchVotes,
+    BotTakeover,
+    BotMove,
+    KeepDrawn,
+    LobbyOptions,
+    Admitted,
 }
 
 impl Into<u8> for SocketEvent {
@@ -49,6 +322,74 @@ impl Into<u8> for SocketEvent {
             Self::Restart => 3,
             Self::Name => 4,
             Self::Wild => 5,
+            Self::SyncRequest => 6,
+            Self::Sync => 7,
+            Self::Host => 8,
+            Self::Auth => 9,
+            Self::LobbyFull => 10,
+            Self::CallOne => 11,
+            Self::CatchOne => 12,
+            Self::RevealHand => 13,
+            Self::ShareHand => 14,
+            Self::DropPlayer => 15,
+            Self::AbortGame => 16,
+            Self::TurnClock => 17,
+            Self::RestartPrepare => 18,
+            Self::RestartReady => 19,
+            Self::TableTheme => 20,
+            Self::ReturnCards => 21,
+            Self::Reshuffle => 22,
+            Self::PlayConfirmed => 23,
+            Self::PlayRejected => 24,
+            Self::PlayMulti => 25,
+            Self::HostChange => 26,
+            Self::Leave => 27,
+            Self::Ping => 28,
+            Self::Pong => 29,
+            Self::RematchVote => 30,
+            Self::RematchVotes => 31,
+            Self::BotTakeover => 32,
+            Self::BotMove => 33,
+            Self::KeepDrawn => 34,
+            Self::LobbyOptions => 35,
+            Self::Admitted => 36,
+        }
+    }
+}
+
+/// Why the host turned down a play.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// It was someone else's turn.
+    NotYourTurn,
+    /// The card can't be played on the discard pile.
+    NotPlayable,
+}
+
+impl RejectReason {
+    /// Returns the message shown to the player whose card was turned down.
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::NotYourTurn => "It's not your turn",
+            Self::NotPlayable => "That card can't be played now",
+        }
+    }
+}
+
+impl Into<u8> for RejectReason {
+    fn into(self) -> u8 {
+        match self {
+            Self::NotYourTurn => 0,
+            Self::NotPlayable => 1,
+        }
+    }
+}
+
+impl From<u8> for RejectReason {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::NotPlayable,
+            _ => Self::NotYourTurn,
         }
     }
 }
@@ -66,6 +407,37 @@ impl TryFrom<u8> for SocketEvent {
             3 => Ok(Self::Restart),
             4 => Ok(Self::Name),
             5 => Ok(Self::Wild),
+            6 => Ok(Self::SyncRequest),
+            7 => Ok(Self::Sync),
+            8 => Ok(Self::Host),
+            9 => Ok(Self::Auth),
+            10 => Ok(Self::LobbyFull),
+            11 => Ok(Self::CallOne),
+            12 => Ok(Self::CatchOne),
+            13 => Ok(Self::RevealHand),
+            14 => Ok(Self::ShareHand),
+            15 => Ok(Self::DropPlayer),
+            16 => Ok(Self::AbortGame),
+            17 => Ok(Self::TurnClock),
+            18 => Ok(Self::RestartPrepare),
+            19 => Ok(Self::RestartReady),
+            20 => Ok(Self::TableTheme),
+            21 => Ok(Self::ReturnCards),
+            22 => Ok(Self::Reshuffle),
+            23 => Ok(Self::PlayConfirmed),
+            24 => Ok(Self::PlayRejected),
+            25 => Ok(Self::PlayMulti),
+            26 => Ok(Self::HostChange),
+            27 => Ok(Self::Leave),
+            28 => Ok(Self::Ping),
+            29 => Ok(Self::Pong),
+            30 => Ok(Self::RematchVote),
+            31 => Ok(Self::RematchVotes),
+            32 => Ok(Self::BotTakeover),
+            33 => Ok(Self::BotMove),
+            34 => Ok(Self::KeepDrawn),
+            35 => Ok(Self::LobbyOptions),
+            36 => Ok(Self::Admitted),
             _ => Err(SocketEventInitError::InvalidByte),
         }
     }
@@ -73,120 +445,747 @@ impl TryFrom<u8> for SocketEvent {
     type Error = SocketEventInitError;
 }
 
+/// Builds packets and sends them to several peers, reusing its buffers between packets.
+#[derive(Default)]
+pub struct Broadcast {
+    packet: Vec<u8>,
+    peers: Vec<PeerId>,
+}
+
+impl Broadcast {
+    /// Starts a new packet for the given event and returns it so the payload can be added.
+    pub fn packet(&mut self, event: SocketEvent) -> &mut Vec<u8> {
+        self.packet.clear();
+        self.packet.push(event.into());
+        &mut self.packet
+    }
+
+    /// Sends the current packet to every peer in the room.
+    pub fn send(&mut self, socket: &mut GameTransport) {
+        self.peers(socket.room_peers());
+        for peer in self.peers.iter() {
+            send_packet(socket, &self.packet, *peer);
+        }
+    }
+
+    /// Sends the current packet to every peer in the room on the unreliable channel.
+    pub fn send_unreliable(&mut self, socket: &mut GameTransport) {
+        self.peers(socket.room_peers());
+        for peer in self.peers.iter() {
+            send_unreliable(socket, self.packet.as_slice().into(), *peer);
+        }
+    }
+
+    /// Copies the given peers into a buffer kept between calls, so packets can be sent to them
+    /// while the socket they came from is borrowed again.
+    pub fn peers(&mut self, peers: impl IntoIterator<Item = PeerId>) -> &[PeerId] {
+        self.peers.clear();
+        self.peers.extend(peers);
+        &self.peers
+    }
+
+    /// Sends the current packet to the given peers.
+    pub fn send_to<'a>(
+        &self,
+        socket: &mut GameTransport,
+        peers: impl IntoIterator<Item = &'a PeerId>,
+    ) {
+        for peer in peers {
+            send_packet(socket, &self.packet, *peer);
+        }
+    }
+}
+
 /// Start game event triggered by host.
 #[derive(Event)]
 pub struct StartGame {
     pub order: Vec<PeerId>,
     pub restart: bool,
+    /// Seed to shuffle the deck with, instead of a random one.
+    pub seed: Option<u64>,
 }
 
 /// Draw card event triggered by a client.
+///
+/// Holds the cards drawn.
+#[derive(Event)]
+pub struct DrawCard(pub Vec<Card>);
+
+/// Posted when the main player keeps the card they drew instead of playing it.
 #[derive(Event)]
-pub struct DrawCard;
+pub struct KeepDrawn;
 
 /// Play card event triggered by a client.
+///
+/// Holds every card played together this turn, in the order they were played.
+#[derive(Event)]
+pub struct PlayCard(pub Vec<Card>);
+
+/// Posted locally when the host turns down a play and the game has been put back the way it
+/// was before it.
+#[derive(Event)]
+pub struct PlayRolledBack {
+    pub player: PeerId,
+    pub reason: RejectReason,
+}
+
+/// Posted locally when an opponent's cards go onto the discard pile.
 #[derive(Event)]
-pub struct PlayCard(pub Card);
+pub struct OpponentPlayed {
+    pub player: PeerId,
+    pub cards: Vec<Card>,
+}
+
+/// Posted when the main player returns cards over the hand limit to the bottom of the deck.
+#[derive(Event)]
+pub struct ReturnCards(pub Vec<Card>);
 
 /// Restart game event.
 #[derive(Event)]
 pub struct RestartGame;
 
+/// Posted locally when the host receives a player's reply to a restart.
+#[derive(Event)]
+pub struct RestartReady(pub PeerId);
+
+/// Posted locally when the main player votes for a rematch from the results.
+#[derive(Event)]
+pub struct VoteRematch;
+
+/// Posted locally when the host receives a player's rematch vote.
+#[derive(Event)]
+struct RematchVoted(PeerId);
+
+/// Posted locally whenever the host's count of rematch votes changes.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RematchVotes {
+    /// Players who want a rematch.
+    pub votes: u8,
+    /// Players who can vote, which is everyone but the host.
+    pub players: u8,
+}
+
+/// Posted locally when the host starts clearing the last round to deal the next one.
+#[derive(Event)]
+pub struct HostRestarting;
+
+/// Players who voted for a rematch, counted by the host.
+#[derive(Resource, Default)]
+struct RematchVoters(HashSet<PeerId>);
+
+/// Players the host is waiting on before dealing the next round.
+///
+/// The host tells everyone to clear the last round, and only deals once each player has
+/// answered that they're done, so no cards from the last round are left around.
+#[derive(Resource)]
+struct RestartBarrier {
+    order: Vec<PeerId>,
+    waiting: HashSet<PeerId>,
+    timer: Timer,
+}
+
+/// Tells the host we're ready for the next round once the last one has been cleared.
+#[derive(Resource)]
+struct RestartAck(PeerId);
+
 /// Wild color selection event.
 ///
 /// This event is triggered by the host after a wild card is played and the color is selected.
 #[derive(Event)]
 pub struct WildColor(pub CardColor);
 
+/// Requests the current turn state from the host.
+///
+/// Sent after the app resumes from being suspended, since we may have missed turn changes.
+#[derive(Event)]
+pub struct RequestSync;
+
+/// Posted locally once the host has answered a sync request.
+#[derive(Event)]
+pub struct Synced;
+
+/// Posted locally when the discard pile has been shuffled back into the deck, with how many cards
+/// from the bottom of the pile went back in.
+#[derive(Event)]
+pub struct Reshuffled(pub usize);
+
+/// Posted when the main player calls one.
+#[derive(Event)]
+pub struct CallOne;
+
+/// Posted when the main player tries to catch a player who didn't call one.
+///
+/// Catches are checked by the host, who tells everyone when a catch goes through.
+#[derive(Event)]
+pub struct CatchOne(pub PeerId);
+
+/// Shows our remaining cards to everyone at the end of a round.
+#[derive(Event)]
+pub struct RevealHand;
+
+/// Posted locally when a peer disconnects.
+#[derive(Event)]
+pub struct PeerLeft {
+    pub player: PeerId,
+    /// Whether the peer was hosting the room.
+    pub was_host: bool,
+    /// Whether the peer said they were leaving, so there's no point waiting for them.
+    pub left: bool,
+}
+
+/// Posted locally when we leave the game, to let peers know it wasn't a dropped connection.
+#[derive(Event)]
+pub struct LeaveGame;
+
+/// Sends our hand to the host, so it can work out which cards a disconnected player was holding.
+#[derive(Event)]
+pub struct ShareHand;
+
+/// Posted locally when the host receives a player's hand.
+#[derive(Event)]
+pub struct HandShared {
+    pub player: PeerId,
+    pub cards: Vec<Card>,
+}
+
+/// Removes a disconnected player from the game, returning their cards to the deck.
+///
+/// This is sent by the host, and posted locally when received from the host.
+#[derive(Event)]
+pub struct DropPlayer {
+    pub player: PeerId,
+    pub cards: Vec<Card>,
+}
+
+/// Ends the game for everyone.
+///
+/// This is sent by the host, and posted locally when received from the host.
+#[derive(Event)]
+pub struct AbortGame;
+
+/// Hands a disconnected player's seat to a bot run by the host, along with the cards they
+/// were holding.
+///
+/// This is sent by the host, and posted locally when received from the host.
+#[derive(Event)]
+pub struct BotTakeover {
+    pub player: PeerId,
+    pub cards: Vec<Card>,
+}
+
+/// Posted locally when the host's turn clock arrives.
+#[derive(Event)]
+pub struct TurnClockSynced {
+    pub turn: u32,
+    /// Seconds the host says the turn has been going for, including the time the packet
+    /// took to arrive.
+    pub elapsed: f32,
+}
+
+/// Password entered to join a private room.
+#[derive(Event)]
+pub struct SubmitPassword(pub String);
+
+/// Peers in the room and our role in it.
+#[derive(SystemParam)]
+pub struct Room<'w, 's> {
+    names: ResMut<'w, PeerNames>,
+    player_ids: ResMut<'w, PlayerIds>,
+    profiles: Res<'w, Profiles>,
+    host: ResMut<'w, RoomHost>,
+    password: Res<'w, RoomPassword>,
+    max_players: ResMut<'w, MaxPlayers>,
+    match_format: ResMut<'w, MatchFormat>,
+    deal_speed: ResMut<'w, DealSpeed>,
+    auth_state: ResMut<'w, AuthState>,
+    /// Peers that still need to send the room password (if we're hosting).
+    pending: Local<'s, HashSet<PeerId>>,
+    /// Peers that have been let into the room (if we're hosting).
+    admitted: Local<'s, HashSet<PeerId>>,
+    /// Packets from peers that haven't been let into the room, kept until they are.
+    held: Local<'s, HashMap<PeerId, Vec<Packet>>>,
+    /// Peers that said they're leaving.
+    leaving: Local<'s, HashSet<PeerId>>,
+    /// Peers that stopped answering pings, whose connection hasn't closed yet.
+    timed_out: Local<'s, HashSet<PeerId>>,
+    order: Local<'s, PacketOrder>,
+    broadcast: Local<'s, Broadcast>,
+    connections: ResMut<'w, PeerConnections>,
+    time: Res<'w, Time>,
+    server_state: Res<'w, State<ServerState>>,
+    next_server_state: ResMut<'w, NextState<ServerState>>,
+}
+
+impl Room<'_, '_> {
+    /// Whether we're hosting the room.
+    fn is_hosting(&self) -> bool {
+        matches!(**self.server_state, ServerState::Server(_))
+    }
+
+    /// Whether there's no space left in the room we're hosting.
+    fn is_full(&self) -> bool {
+        // count ourselves too
+        self.admitted.len() + 1 >= self.max_players.0 as usize
+    }
+
+    /// Lets a peer into the room, or tells them it's full.
+    fn admit(&mut self, socket: &mut GameTransport, peer: PeerId) {
+        self.pending.remove(&peer);
+        let packet = if self.is_full() {
+            info!("Room is full, turning away {peer}");
+            Vec::from([SocketEvent::LobbyFull.into()])
+        } else {
+            self.admitted.insert(peer);
+            socket.let_in(peer);
+            // everyone only talks to the peers we've let in, including the one joining
+            let packet = self.broadcast.packet(SocketEvent::Admitted);
+            for admitted in self.admitted.iter() {
+                packet.extend_from_slice(admitted.0.as_bytes());
+            }
+            self.broadcast.send(socket);
+            Vec::from([SocketEvent::Auth.into(), AUTH_ACCEPTED])
+        };
+        send_packet(socket, &packet, peer);
+    }
+
+    /// Starts talking to a peer the host has let into the room, introducing ourselves if we're
+    /// in the room too.
+    fn let_in(&mut self, socket: &mut GameTransport, username: &str, peer: PeerId) {
+        if !socket.is_outside(peer) {
+            return;
+        }
+        socket.let_in(peer);
+        // they weren't expected to answer until now
+        self.connections.heard(peer, self.time.elapsed_seconds());
+        if *self.auth_state == AuthState::Accepted {
+            send_name(socket, username, self.profiles.active().player_id, peer);
+        }
+    }
+
+    /// Keeps a packet from a peer that hasn't been let into the room, until they are.
+    fn hold(&mut self, peer: PeerId, packet: Packet) {
+        let held = self.held.entry(peer).or_default();
+        if held.len() < HELD_LIMIT {
+            held.push(packet);
+        }
+    }
+
+    /// Forgets a peer that's gone, picking a new host if it was the host.
+    fn remove_peer(
+        &mut self,
+        socket: &mut GameTransport,
+        peer_left: &mut EventWriter<PeerLeft>,
+        peer: PeerId,
+    ) {
+        let was_host = self.host.0 == Some(peer);
+        peer_left.send(PeerLeft {
+            player: peer,
+            was_host,
+            left: self.leaving.remove(&peer),
+        });
+        // remove stored peer name
+        self.names.0.remove(&peer);
+        self.player_ids.0.remove(&peer);
+        self.pending.remove(&peer);
+        self.admitted.remove(&peer);
+        self.held.remove(&peer);
+        self.connections.forget(&peer);
+        self.order.forget(&peer);
+        socket.forget_sequence(&peer);
+        if was_host {
+            self.host.0 = None;
+            self.elect_host(socket);
+        }
+    }
+
+    /// Picks a new host once the old one has left, taking over the room if it's us.
+    fn elect_host(&mut self, socket: &mut GameTransport) {
+        let Some(own_id) = socket.id() else { return; };
+        let ServerState::Client(code) = self.server_state.get().clone() else { return; };
+        let new_host = next_host(own_id, self.names.0.keys().copied());
+        if new_host != own_id {
+            info!("{new_host} is taking over the room");
+            self.host.0 = Some(new_host);
+            return;
+        }
+
+        info!("Taking over the room");
+        self.next_server_state.set(ServerState::Server(code));
+        self.host.0 = None;
+        // everyone still here was let in by the old host
+        self.admitted.extend(self.names.0.keys().copied());
+        self.broadcast.packet(SocketEvent::HostChange);
+        self.broadcast.send(socket);
+    }
+}
+
+/// Returns the player that takes over the room when the host leaves.
+///
+/// Every peer works this out from the players still in the room, so they all agree on the lowest
+/// id without having to talk it over.
+pub fn next_host(own_id: PeerId, peers: impl IntoIterator<Item = PeerId>) -> PeerId {
+    peers.into_iter().fold(own_id, PeerId::min)
+}
+
+/// Local events posted in response to network messages.
+#[derive(SystemParam)]
+pub struct GameEvents<'w> {
+    spawn: EventWriter<'w, SpawnCard>,
+    synced: EventWriter<'w, Synced>,
+    revealed: EventWriter<'w, HandRevealed>,
+    peer_left: EventWriter<'w, PeerLeft>,
+    hand_shared: EventWriter<'w, HandShared>,
+    decisions: HostDecisions<'w>,
+    clock_synced: EventWriter<'w, TurnClockSynced>,
+    restart: RestartEvents<'w>,
+    reshuffled: EventWriter<'w, Reshuffled>,
+    rolled_back: EventWriter<'w, PlayRolledBack>,
+    opponent_played: EventWriter<'w, OpponentPlayed>,
+    camera: EventWriter<'w, CameraCue>,
+    #[cfg(feature = "debug_overlay")]
+    received: EventWriter<'w, crate::debug::PacketReceived>,
+}
+
+/// Local events about the next round, posted in response to network messages.
+#[derive(SystemParam)]
+pub struct RestartEvents<'w> {
+    ready: EventWriter<'w, RestartReady>,
+    voted: EventWriter<'w, RematchVoted>,
+    votes: EventWriter<'w, RematchVotes>,
+    restarting: EventWriter<'w, HostRestarting>,
+}
+
+/// Local events for the host's decisions about a disconnected player.
+#[derive(SystemParam)]
+pub struct HostDecisions<'w> {
+    dropped: EventWriter<'w, DropPlayer>,
+    aborted: EventWriter<'w, AbortGame>,
+    bot_takeover: EventWriter<'w, BotTakeover>,
+}
+
+/// Entities for the cards of the round, which are despawned when it's over, along with the
+/// cards still waiting to be spawned.
+#[derive(SystemParam)]
+pub struct RoundCards<'w, 's> {
+    hand: Query<'w, 's, Entity, With<HandCard>>,
+    discard: Query<'w, 's, Entity, With<DiscardCard>>,
+    spawn_queue: ResMut<'w, SpawnQueue>,
+    deal_queue: ResMut<'w, DealQueue>,
+}
+
+/// Screen states that are changed when a game starts or restarts.
+#[derive(SystemParam)]
+pub struct ScreenStates<'w> {
+    menu: ResMut<'w, NextState<MenuState>>,
+    screen: ResMut<'w, NextState<ScreenState>>,
+    game_screen: ResMut<'w, NextState<GameScreenState>>,
+}
+
 /// Initializes the peer names hashmap.
 fn setup(mut commands: Commands) {
     commands.insert_resource(PeerNames(HashMap::new()));
+    commands.init_resource::<PlayerIds>();
+    commands.init_resource::<RoomHost>();
+    commands.init_resource::<RoomPassword>();
+    commands.init_resource::<MaxPlayers>();
+    commands.init_resource::<AuthState>();
+    commands.init_resource::<ConnectionState>();
+}
+
+/// Follows the connection to the room, giving up on it if the server fails or no host answers.
+///
+/// Only a new connection starts over, so a failure stays on screen until the player retries.
+fn update_connection_state(
+    mut connection: ResMut<ConnectionState>,
+    mut socket: ResMut<GameTransport>,
+    server_state: Res<State<ServerState>>,
+    room_host: Res<RoomHost>,
+    time: Res<Time>,
+    mut waited: Local<f32>,
+) {
+    if socket.is_added() {
+        *waited = 0.0;
+        connection.set_if_neq(ConnectionState::Connecting);
+    }
+    let state = match *connection {
+        _ if socket.is_closed() => ConnectionState::Failed,
+        // the server gives us an id once we're in the room
+        ConnectionState::Connecting if socket.id().is_some() => {
+            *waited += time.delta_seconds();
+            if matches!(**server_state, ServerState::Server(_)) || room_host.0.is_some() {
+                ConnectionState::Connected
+            } else if *waited >= HOST_TIMEOUT {
+                ConnectionState::RoomNotFound
+            } else {
+                ConnectionState::Connecting
+            }
+        }
+        state => state,
+    };
+    connection.set_if_neq(state);
+}
+
+/// Sends the lobby options to everyone in the room we're hosting whenever they change, and to
+/// players as they're let in, so they can see what they're about to play.
+fn broadcast_lobby_options(
+    mut socket: ResMut<GameTransport>,
+    mut broadcast: Local<Broadcast>,
+    server_state: Res<State<ServerState>>,
+    peer_names: Res<PeerNames>,
+    rules: Res<GameRules>,
+    max_players: Res<MaxPlayers>,
+    match_format: Res<MatchFormat>,
+    deal_speed: Res<DealSpeed>,
+) {
+    let ServerState::Server(_) = **server_state else { return; };
+    // a name only arrives from players who've been let in
+    if !(peer_names.is_changed()
+        || rules.is_changed()
+        || max_players.is_changed()
+        || match_format.is_changed()
+        || deal_speed.is_changed())
+    {
+        return;
+    }
+
+    let packet = broadcast.packet(SocketEvent::LobbyOptions);
+    packet.push(max_players.0);
+    let rule_bytes = rules.to_bytes();
+    packet.push(rule_bytes.len() as u8);
+    packet.extend(rule_bytes);
+    packet.extend(match_format.to_bytes());
+    packet.push((*deal_speed).into());
+    broadcast.send_to(&mut socket, peer_names.0.keys());
 }
 
 /// Receives messages from the network and handles peer connections.
 fn receive_messages(
-    hand_cards: Query<Entity, With<HandCard>>,
-    discard_cards: Query<Entity, With<DiscardCard>>,
+    mut round_cards: RoundCards,
     mut discard_pile: ResMut<DiscardCards>,
-    mut spawn_events: EventWriter<SpawnCard>,
-    mut win_events: EventWriter<Win>,
-    mut socket: ResMut<MatchboxSocket<SingleChannel>>,
+    mut events: GameEvents,
+    mut socket: ResMut<GameTransport>,
     mut main_player: ResMut<MainPlayer>,
     mut game_info: ResMut<GameInfo>,
-    mut menu_state: ResMut<NextState<MenuState>>,
-    mut screen_state: ResMut<NextState<ScreenState>>,
-    mut game_screen_state: ResMut<NextState<GameScreenState>>,
-    mut peer_names: ResMut<PeerNames>,
+    mut states: ScreenStates,
+    mut room: Room,
     mut opponents: ResMut<Opponents>,
     mut deck: ResMut<Deck>,
-    username: Res<Username>,
+    mut rules: ResMut<GameRules>,
+    mut one_cards: ResMut<OneCards>,
+    mut broadcast: Local<Broadcast>,
+    mut bot_moves: ResMut<BotMoves>,
+    settings: Res<Settings>,
     mut commands: Commands,
 ) {
+    // forget peers from any previous room
+    if socket.is_added() {
+        room.pending.clear();
+        room.admitted.clear();
+        room.held.clear();
+        room.leaving.clear();
+        room.timed_out.clear();
+        room.player_ids.0.clear();
+        *room.order = PacketOrder::default();
+        *room.connections = PeerConnections::default();
+    }
+    let now = room.time.elapsed_seconds();
+
     // Check for new connections
-    match socket.try_update_peers() {
-        Ok(result) => {
-            for (peer, state) in result {
-                match state {
-                    PeerState::Connected => {
-                        info!("Peer joined: {peer}");
-                        // send our username to the peer
-                        let mut packet = username.0.as_bytes().to_vec();
-                        packet.insert(0, SocketEvent::Name.into());
-                        socket.send(packet.into_boxed_slice(), peer);
-                    }
-                    PeerState::Disconnected => {
-                        info!("Peer left: {peer}");
-                        // remove stored peer name
-                        peer_names.0.remove(&peer);
-                    }
+    for (peer, state) in socket.update_peers() {
+        match state {
+            PeerState::Connected => {
+                info!("Peer joined: {peer}");
+                room.timed_out.remove(&peer);
+                room.connections.heard(peer, now);
+                // nothing is broadcast to or taken from the peer until the host lets them in
+                socket.hold_back(peer);
+                if !room.is_hosting() {
+                    continue;
+                }
+                // send our username to the peer
+                if *room.auth_state == AuthState::Accepted {
+                    let player_id = room.profiles.active().player_id;
+                    send_name(&mut socket, &settings.username, player_id, peer);
+                }
+                // let the peer know we're hosting, and whether they need a password
+                let packet = Vec::from([SocketEvent::Host.into()]);
+                send_packet(&mut socket, &packet, peer);
+
+                if room.password.0.is_empty() || room.is_full() {
+                    room.admit(&mut socket, peer);
+                } else {
+                    room.pending.insert(peer);
+                    let packet = Vec::from([SocketEvent::Auth.into(), AUTH_REQUIRED]);
+                    send_packet(&mut socket, &packet, peer);
+                }
+            }
+            PeerState::Disconnected => {
+                // peers that timed out have already been dealt with
+                if room.timed_out.remove(&peer) {
+                    continue;
                 }
+                info!("Peer left: {peer}");
+                room.remove_peer(&mut socket, &mut events.peer_left, peer);
             }
         }
-        Err(e) => {
-            error!("Error updating peers: {e:?}");
+    }
+
+    // give up on peers that have stopped answering pings, which only peers in the room are sent
+    for peer in room.connections.timed_out(now) {
+        if socket.is_outside(peer) {
+            continue;
+        }
+        info!("Peer timed out: {peer}");
+        room.timed_out.insert(peer);
+        room.remove_peer(&mut socket, &mut events.peer_left, peer);
+    }
+
+    // messages kept from peers that have since been let in come before anything newer from them
+    let mut packets = Vec::new();
+    room.held.retain(|peer, held| {
+        if socket.is_outside(*peer) {
+            return true;
+        }
+        packets.extend(held.drain(..).map(|packet| (*peer, packet)));
+        false
+    });
+    // put incoming messages in the order they were sent
+    for (peer, packet) in socket.receive(RELIABLE_CHANNEL) {
+        if room.timed_out.contains(&peer) {
+            continue;
+        }
+        room.connections.heard(peer, now);
+        room.order.receive(peer, packet, &mut packets);
+    }
+    // unreliable messages have no order to keep, and only the host hears them from peers it hasn't
+    // let in, so they can ping it while typing the password
+    for (peer, packet) in socket.receive(UNRELIABLE_CHANNEL) {
+        if room.timed_out.contains(&peer) || (socket.is_outside(peer) && !room.is_hosting()) {
+            continue;
+        }
+        room.connections.heard(peer, now);
+        packets.push((peer, packet));
+    }
+    // moves for the bots we're running are sent to everyone, and made here as well
+    for (bot, bot_move) in bot_moves.0.drain(..) {
+        let packet = broadcast.packet(SocketEvent::BotMove);
+        packet.extend_from_slice(bot.0.as_bytes());
+        packet.extend_from_slice(&bot_move);
+        broadcast.send(&mut socket);
+        packets.push((bot, bot_move.into_boxed_slice()));
+    }
+    // a move the host made for a bot is handled as if the bot sent it
+    for (peer, packet) in packets.iter_mut() {
+        let is_bot_move = packet.first().copied() == Some(SocketEvent::BotMove.into());
+        if room.host.0 != Some(*peer) || !is_bot_move {
+            continue;
         }
+        // 16 bytes for the bot, then its packet
+        let Some(pid) = packet.get(1..17) else { continue; };
+        let pid: [u8; 16] = pid.try_into().expect("slice should be 16 bytes");
+        *peer = PeerId(Uuid::from_bytes(pid));
+        *packet = packet[17..].into();
     }
 
     // Accept any messages incoming
-    for (peer, packet) in socket.receive() {
-        let Some(event_code) = packet.first() else { return; };
+    for (peer, packet) in packets {
+        #[cfg(feature = "debug_overlay")]
+        events.received.send(crate::debug::PacketReceived {
+            peer,
+            packet: packet.clone(),
+        });
+
+        let Some(event_code) = packet.first() else { continue; };
         let Ok(event): Result<SocketEvent, _> = (*event_code).try_into() else {
         	error!("Received invalid event code: {event_code}");
-        	return;
+        	continue;
+        };
+        // peers the host hasn't let in can only knock, and the rest of what they send waits until
+        // they're in, after anything that's already waiting
+        let knocking = match event {
+            SocketEvent::Host => !room.is_hosting() && room.host.0.is_none(),
+            SocketEvent::Auth | SocketEvent::Ping | SocketEvent::Pong => room.is_hosting(),
+            _ => false,
         };
+        if !knocking && (socket.is_outside(peer) || room.held.contains_key(&peer)) {
+            room.hold(peer, packet);
+            continue;
+        }
         match event {
             SocketEvent::Start | SocketEvent::Restart => {
-                // reset the game start before starting the game if we're restarting
+                // reset the game state before starting the game if we're restarting
+                // this was already done when the host asked, unless it stopped waiting for us
                 if event == SocketEvent::Restart {
                     reset_game_state(
-                        &discard_cards,
-                        &hand_cards,
-                        &mut game_screen_state,
+                        &mut round_cards,
                         &mut discard_pile,
                         &mut main_player,
                         &mut opponents,
                         &mut game_info,
+                        &rules,
                         &mut commands,
                     );
+                    // hide win screen, show playing screen
+                    states.game_screen.set(GameScreenState::Game);
                 }
 
                 // load player order
                 // first byte is the number of players, then 16 bytes for each player id
-                let player_count = packet[1];
-                let mut order: Vec<PeerId> = Vec::new();
-                let mut current_pid: [u8; 16] = [0; 16];
-                let mut packet_pos = 2;
-                for _ in 0..player_count {
-                    for i in 0..16 {
-                        if packet_pos >= packet.len() {
-                            error!("Invalid start game packet: ran out of bytes.");
-                            return;
-                        }
-                        current_pid[i] = packet[packet_pos];
-                        packet_pos += 1;
-                    }
-                    order.push(PeerId(Uuid::from_bytes(current_pid)));
-                }
+                let Some(player_count) = packet.get(1).map(|count| *count as usize) else {
+                    error!("Invalid start game packet: missing player count.");
+                    continue;
+                };
+                let mut packet_pos = 2 + player_count * 16;
+                let Some(pids) = packet.get(2..packet_pos) else {
+                    error!("Invalid start game packet: ran out of bytes.");
+                    continue;
+                };
+                let order: Vec<PeerId> = pids
+                    .chunks_exact(16)
+                    .map(|pid| {
+                        let pid: [u8; 16] = pid.try_into().expect("chunk should be 16 bytes");
+                        PeerId(Uuid::from_bytes(pid))
+                    })
+                    .collect();
+
+                // load rules
+                // first byte is the length of the rules, then the rules themselves
+                let Some(rules_len) = packet.get(packet_pos).map(|len| *len as usize) else {
+                    error!("Invalid start game packet: missing rules.");
+                    continue;
+                };
+                let Some(rules_bytes) = packet.get(packet_pos + 1..packet_pos + 1 + rules_len) else {
+                    error!("Invalid start game packet: ran out of bytes.");
+                    continue;
+                };
+                *rules = GameRules::from_bytes(rules_bytes);
+                packet_pos += 1 + rules_len;
+
+                // load seed
+                let Some(seed) = read_u64(&packet, packet_pos) else {
+                    error!("Invalid start game packet: missing seed.");
+                    continue;
+                };
+                packet_pos += 8;
+
+                // load match format
+                let Some(match_format) = MatchFormat::from_bytes(&packet[packet_pos..]) else {
+                    error!("Invalid start game packet: missing match format.");
+                    continue;
+                };
+                *room.match_format = match_format;
+                packet_pos += 3;
+
+                // load deal speed
+                let Some(deal_speed) = packet
+                    .get(packet_pos)
+                    .and_then(|speed| DealSpeed::try_from(*speed).ok())
+                else {
+                    error!("Invalid start game packet: missing deal speed.");
+                    continue;
+                };
+                *room.deal_speed = deal_speed;
+                packet_pos += 1;
 
                 // load opponents
                 let own_pid = socket.id().expect("server should assign us a peer id");
@@ -197,12 +1196,13 @@ fn receive_messages(
                         if *pid == own_pid {
                             None
                         } else {
-                            let name = peer_names
+                            let name = room
+                                .names
                                 .0
                                 .get(pid)
                                 .cloned()
                                 .unwrap_or_else(|| String::from("Unknown"));
-                            Some(Opponent::new(*pid, name, 5))
+                            Some(Opponent::new(*pid, name, rules.hand_size.cards()))
                         }
                     })
                     .collect();
@@ -215,75 +1215,179 @@ fn receive_messages(
                 // the remaining bytes should be the deck, with a byte for each card
                 deck.load_from(&packet[packet_pos..]);
 
+                // the same seed should deal the same deck, unless the host is out of sync
+                let mut game_rng = GameRng::new(seed);
+                let expected = Deck::shuffled(&rules, &mut game_rng);
+                if expected.cards != deck.cards {
+                    warn!("Deck from the host doesn't match seed {seed}");
+                }
+                commands.insert_resource(game_rng);
+
                 initialize_game_start(
                     &own_pid,
-                    &mut spawn_events,
+                    &mut events.spawn,
                     &mut game_info,
                     &mut main_player,
+                    &mut opponents,
                     &mut deck,
                     &mut discard_pile,
-                    &mut screen_state,
-                    &mut menu_state,
+                    &mut states.screen,
+                    &mut states.menu,
+                    &rules,
                 )
             }
             SocketEvent::Draw => {
-                deck.draw(1);
+                game_info.record_draw(peer, discard_pile.cards.last());
+                let drawn = deck.draw(game_info.cards_to_draw() as i32);
 
                 // increment card count for opponent
                 for opponent in opponents.0.iter_mut() {
                     if opponent.id == peer {
-                        opponent.card_count += 1;
+                        opponent.card_count += drawn.len();
                         break;
                     }
                 }
+                for card in drawn.iter() {
+                    events.spawn.send(SpawnCard {
+                        card: *card,
+                        position: CardPosition::Draw,
+                        card_type: CardType::Opponent(peer),
+                    });
+                }
 
-                game_info.advance_turn();
+                game_info.finish_draw(&drawn, discard_pile.cards.last(), &rules);
+            }
+            SocketEvent::KeepDrawn => {
+                if game_info.current_player == Some(peer) {
+                    game_info.keep_drawn();
+                }
             }
-            SocketEvent::Play => {
-                let card = Card::from(packet[1]);
+            SocketEvent::Play | SocketEvent::PlayMulti => {
+                // a single card, or a byte for the number of cards and then the cards
+                let cards: Vec<Card> = if event == SocketEvent::Play {
+                    packet.get(1..2)
+                } else {
+                    packet
+                        .get(1)
+                        .and_then(|count| packet.get(2..2 + *count as usize))
+                }
+                .unwrap_or_default()
+                .iter()
+                .map(|card| Card::from(*card))
+                .collect();
+                let Some(card) = cards.first().copied() else {
+                    error!("Invalid play packet.");
+                    continue;
+                };
+
+                // the host has the final say on whether a play counts, and tells everyone
+                if room.is_hosting() {
+                    let hand_size = opponents
+                        .0
+                        .iter()
+                        .find(|opponent| opponent.id == peer)
+                        .map_or(0, |opponent| opponent.card_count);
+                    let reason = if game_info.current_player != Some(peer) {
+                        Some(RejectReason::NotYourTurn)
+                    } else if game_info.drawn_card.is_some_and(|drawn| cards != [drawn]) {
+                        // only the card just drawn can be played after drawing
+                        Some(RejectReason::NotPlayable)
+                    } else if !game_info.can_play_together(
+                        &cards,
+                        hand_size,
+                        discard_pile.cards.last(),
+                        &rules,
+                    ) {
+                        Some(RejectReason::NotPlayable)
+                    } else {
+                        None
+                    };
+                    let packet = broadcast.packet(match reason {
+                        Some(_) => SocketEvent::PlayRejected,
+                        None => SocketEvent::PlayConfirmed,
+                    });
+                    packet.extend_from_slice(peer.0.as_bytes());
+                    packet.push(card.into());
+                    packet.extend(reason.map(|reason| -> u8 { reason.into() }));
+                    broadcast.send(&mut socket);
+                    if let Some(reason) = reason {
+                        warn!("Rejected play of {cards:?} from {peer}: {reason:?}");
+                        continue;
+                    }
+                } else if room.host.0 != Some(peer) {
+                    let play = ProvisionalPlay::new(
+                        peer,
+                        &cards,
+                        &game_info,
+                        &deck,
+                        &discard_pile.cards,
+                        &main_player,
+                        &opponents,
+                    );
+                    discard_pile.provisional.push(play);
+                }
 
-                // add to discard pile
-                discard_pile.cards.push(card);
+                for card in cards.iter() {
+                    game_info.record_play(peer, card);
 
-                // spawn card
-                spawn_events.send(SpawnCard {
-                    card,
-                    position: CardPosition::OpponentDiscard(discard_pile.cards.len()),
-                    card_type: CardType::Discard,
+                    // add to discard pile
+                    discard_pile.cards.push(*card);
+
+                    // spawn card
+                    events.spawn.send(SpawnCard {
+                        card: *card,
+                        position: CardPosition::OpponentDiscard(discard_pile.cards.len()),
+                        card_type: CardType::Discard(discard_pile.cards.len() - 1),
+                    });
+                }
+                events.opponent_played.send(OpponentPlayed {
+                    player: peer,
+                    cards: cards.clone(),
                 });
 
                 // decrement card count for opponent
                 for opponent in opponents.0.iter_mut() {
                     if opponent.id == peer {
-                        opponent.card_count -= 1;
-                        // check for win
-                        if opponent.card_count == 0 {
-                            win_events.send(Win(opponent.id));
-                        }
+                        opponent.card_count = opponent.card_count.saturating_sub(cards.len());
                         break;
                     }
                 }
 
-                game_info.advance_turn();
-
                 handle_card_effect(
-                    &card,
-                    &peer,
-                    &mut spawn_events,
+                    &cards,
+                    &mut events.spawn,
+                    &mut events.camera,
                     &mut socket,
                     &mut game_info,
                     &mut main_player,
                     &mut opponents,
                     &mut deck,
+                    &rules,
                 )
             }
             SocketEvent::Name => {
-                // update peer names hashmap
-                let name = String::from_utf8_lossy(&packet[1..]);
-                peer_names.0.insert(peer, name.to_string());
+                // the player id comes before the name
+                let Some(player_id) = read_u64(&packet, 1) else {
+                    error!("Invalid name packet: missing player id.");
+                    continue;
+                };
+                let name = String::from_utf8_lossy(&packet[9..]);
+                room.names.0.insert(peer, name.to_string());
+                room.player_ids.0.insert(peer, player_id);
             }
             SocketEvent::Wild => {
-                let card_color = CardColor::from(packet[1]);
+                // the wild may have been turned down by the host before its color arrived
+                if !discard_pile
+                    .cards
+                    .last()
+                    .is_some_and(|card| card.color == CardColor::Wild)
+                {
+                    continue;
+                }
+                let Some(card_color) = packet.get(1).map(|color| CardColor::from(*color)) else {
+                    error!("Invalid wild packet: missing color.");
+                    continue;
+                };
 
                 // add the colored wild to top of discard pile
                 let mut new_card = discard_pile
@@ -294,61 +1398,464 @@ fn receive_messages(
                 new_card.color = card_color;
                 discard_pile.cards.push(new_card);
 
-                spawn_events.send(SpawnCard {
+                events.spawn.send(SpawnCard {
                     card: new_card,
                     position: CardPosition::Discard(discard_pile.cards.len()),
-                    card_type: CardType::Discard,
+                    card_type: CardType::Discard(discard_pile.cards.len() - 1),
+                });
+            }
+            SocketEvent::SyncRequest => {
+                // only the host answers sync requests
+                if let ServerState::Server(_) = **room.server_state {
+                    let mut packet = vec![SocketEvent::Sync.into(), game_info.direction.into()];
+                    if let Some(current_player) = game_info.current_player {
+                        packet.extend_from_slice(current_player.0.as_bytes());
+                    }
+                    send_packet(&mut socket, &packet, peer);
+                }
+            }
+            SocketEvent::Sync => {
+                // first byte is the direction, then 16 bytes for the current player (if any)
+                let Some(direction) = packet.get(1) else {
+                    error!("Invalid sync packet: missing direction.");
+                    continue;
+                };
+                game_info.direction = Direction::from(*direction);
+                if let Some(pid) = packet.get(2..18) {
+                    let pid: [u8; 16] = pid.try_into().expect("slice should be 16 bytes");
+                    game_info.current_player = Some(PeerId(Uuid::from_bytes(pid)));
+                }
+                events.synced.send(Synced);
+            }
+            SocketEvent::Host => {
+                room.host.0 = Some(peer);
+                room.let_in(&mut socket, &settings.username, peer);
+            }
+            SocketEvent::Admitted => {
+                // only the host decides who's in the room
+                if room.host.0 != Some(peer) {
+                    continue;
+                }
+                // 16 bytes for each peer the host has let in
+                for pid in packet[1..].chunks_exact(16) {
+                    let pid: [u8; 16] = pid.try_into().expect("chunk should be 16 bytes");
+                    room.let_in(&mut socket, &settings.username, PeerId(Uuid::from_bytes(pid)));
+                }
+            }
+            SocketEvent::HostChange => {
+                // if two peers both took over, the one with the lower id keeps the room
+                if let ServerState::Server(code) = room.server_state.get().clone() {
+                    let outranked = matches!(socket.id(), Some(own_id) if own_id > peer);
+                    if !outranked {
+                        continue;
+                    }
+                    room.next_server_state.set(ServerState::Client(code));
+                }
+                info!("{peer} took over the room");
+                room.host.0 = Some(peer);
+            }
+            SocketEvent::Leave => {
+                info!("{peer} is leaving");
+                room.leaving.insert(peer);
+            }
+            SocketEvent::Auth => {
+                let Some(status) = packet.get(1) else {
+                    error!("Invalid auth packet: missing status.");
+                    continue;
+                };
+                match *status {
+                    AUTH_ATTEMPT => {
+                        // only check passwords for peers we're waiting on
+                        if !room.pending.contains(&peer) {
+                            continue;
+                        }
+                        if packet[2..] != *room.password.0.as_bytes() {
+                            info!("Rejected password from {peer}");
+                            let packet = Vec::from([SocketEvent::Auth.into(), AUTH_REJECTED]);
+                            send_packet(&mut socket, &packet, peer);
+                            // they leave once they hear, but we stop talking to them right away
+                            room.pending.remove(&peer);
+                            socket.cut_off(peer);
+                            continue;
+                        }
+                        // the room may have filled up while they were typing
+                        room.admit(&mut socket, peer);
+                    }
+                    AUTH_REQUIRED => *room.auth_state = AuthState::Required,
+                    AUTH_ACCEPTED => {
+                        *room.auth_state = AuthState::Accepted;
+                        // now that we're in, introduce ourselves to everyone
+                        let player_id = room.profiles.active().player_id;
+                        let packet = broadcast.packet(SocketEvent::Name);
+                        packet.extend(player_id.to_le_bytes());
+                        packet.extend_from_slice(settings.username.as_bytes());
+                        broadcast.send(&mut socket);
+                    }
+                    AUTH_REJECTED => *room.auth_state = AuthState::Rejected,
+                    _ => error!("Received invalid auth status: {status}"),
+                }
+            }
+            SocketEvent::LobbyFull => {
+                *room.auth_state = AuthState::Full;
+            }
+            SocketEvent::RevealHand => {
+                // the remaining bytes are the cards in the peer's hand
+                let cards = packet[1..].iter().map(|card| Card::from(*card)).collect();
+                events.revealed.send(HandRevealed { player: peer, cards });
+            }
+            SocketEvent::ShareHand => {
+                if room.is_hosting() {
+                    let cards = packet[1..].iter().map(|card| Card::from(*card)).collect();
+                    events.hand_shared.send(HandShared { player: peer, cards });
+                }
+            }
+            SocketEvent::DropPlayer => {
+                // only the host can remove players
+                if room.host.0 != Some(peer) {
+                    continue;
+                }
+                // 16 bytes for the player, then the cards they were holding
+                let Some(pid) = packet.get(1..17) else {
+                    error!("Invalid drop player packet: missing player.");
+                    continue;
+                };
+                let pid: [u8; 16] = pid.try_into().expect("slice should be 16 bytes");
+                let cards = packet[17..].iter().map(|card| Card::from(*card)).collect();
+                events.decisions.dropped.send(DropPlayer {
+                    player: PeerId(Uuid::from_bytes(pid)),
+                    cards,
+                });
+            }
+            SocketEvent::BotTakeover => {
+                // only the host can hand seats to bots
+                if room.host.0 != Some(peer) {
+                    continue;
+                }
+                // 16 bytes for the player, then the cards they were holding
+                let Some(pid) = packet.get(1..17) else {
+                    error!("Invalid bot takeover packet: missing player.");
+                    continue;
+                };
+                let pid: [u8; 16] = pid.try_into().expect("slice should be 16 bytes");
+                let cards = packet[17..].iter().map(|card| Card::from(*card)).collect();
+                events.decisions.bot_takeover.send(BotTakeover {
+                    player: PeerId(Uuid::from_bytes(pid)),
+                    cards,
+                });
+            }
+            SocketEvent::BotMove => {
+                // only moves from the host are let through, as the bot's own
+                warn!("Ignored bot move from {peer}, who isn't hosting");
+            }
+            SocketEvent::AbortGame => {
+                if room.host.0 == Some(peer) {
+                    events.decisions.aborted.send(AbortGame);
+                }
+            }
+            SocketEvent::TurnClock => {
+                if room.host.0 != Some(peer) {
+                    continue;
+                }
+                // turn, elapsed seconds, and our latency as measured by the host
+                let (Some(turn), Some(elapsed), Some(latency)) = (
+                    read_u32(&packet, 1),
+                    read_f32(&packet, 5),
+                    read_f32(&packet, 9),
+                ) else {
+                    error!("Invalid turn clock packet.");
+                    continue;
+                };
+                events.clock_synced.send(TurnClockSynced {
+                    turn,
+                    elapsed: elapsed + latency,
+                });
+            }
+            SocketEvent::Ping => {
+                // send the time straight back so the peer can measure the round trip
+                let mut pong = packet.clone();
+                pong[0] = SocketEvent::Pong.into();
+                send_unreliable(&mut socket, pong, peer);
+            }
+            SocketEvent::Pong => {
+                let Some(sent_at) = read_f32(&packet, 1) else {
+                    error!("Invalid pong packet.");
+                    continue;
+                };
+                room.connections.measure(peer, now - sent_at);
+            }
+            SocketEvent::RestartPrepare => {
+                // the results stay up until the next round is dealt
+                reset_game_state(
+                    &mut round_cards,
+                    &mut discard_pile,
+                    &mut main_player,
+                    &mut opponents,
+                    &mut game_info,
+                    &rules,
+                    &mut commands,
+                );
+                // answer once the cards have been despawned
+                commands.insert_resource(RestartAck(peer));
+                events.restart.restarting.send(HostRestarting);
+            }
+            SocketEvent::RestartReady => {
+                if room.is_hosting() {
+                    events.restart.ready.send(RestartReady(peer));
+                }
+            }
+            SocketEvent::RematchVote => {
+                if room.is_hosting() {
+                    events.restart.voted.send(RematchVoted(peer));
+                }
+            }
+            SocketEvent::RematchVotes => {
+                if room.host.0 != Some(peer) {
+                    continue;
+                }
+                let (Some(votes), Some(players)) = (packet.get(1), packet.get(2)) else {
+                    error!("Invalid rematch votes packet.");
+                    continue;
+                };
+                events.restart.votes.send(RematchVotes {
+                    votes: *votes,
+                    players: *players,
+                });
+            }
+            SocketEvent::LobbyOptions => {
+                if room.host.0 != Some(peer) {
+                    continue;
+                }
+                // a byte for the max players, then the rules with their length, the match format
+                // and the deal speed, as in the start packet
+                let Some(max_players) = packet.get(1) else {
+                    error!("Invalid lobby options packet.");
+                    continue;
+                };
+                let Some(rules_len) = packet.get(2).map(|len| *len as usize) else {
+                    error!("Invalid lobby options packet: missing rules.");
+                    continue;
+                };
+                let Some(rules_bytes) = packet.get(3..3 + rules_len) else {
+                    error!("Invalid lobby options packet: ran out of bytes.");
+                    continue;
+                };
+                let packet_pos = 3 + rules_len;
+                let Some(match_format) = packet
+                    .get(packet_pos..)
+                    .and_then(MatchFormat::from_bytes)
+                else {
+                    error!("Invalid lobby options packet: missing match format.");
+                    continue;
+                };
+                let Some(deal_speed) = packet
+                    .get(packet_pos + 3)
+                    .and_then(|speed| DealSpeed::try_from(*speed).ok())
+                else {
+                    error!("Invalid lobby options packet: missing deal speed.");
+                    continue;
+                };
+                room.max_players.0 = *max_players;
+                *rules = GameRules::from_bytes(rules_bytes);
+                *room.match_format = match_format;
+                *room.deal_speed = deal_speed;
+            }
+            SocketEvent::TableTheme => {
+                let Some(theme) = packet.get(1) else {
+                    error!("Invalid table theme packet.");
+                    continue;
+                };
+                commands.insert_resource(HostTable(Some(TableTheme::from(*theme))));
+            }
+            SocketEvent::ReturnCards => {
+                // a byte for the number of cards, then the cards themselves
+                let Some(cards) = packet
+                    .get(1)
+                    .and_then(|count| packet.get(2..2 + *count as usize))
+                else {
+                    error!("Invalid return cards packet.");
+                    continue;
+                };
+                let cards: Vec<Card> = cards.iter().map(|card| Card::from(*card)).collect();
+                deck.return_to_bottom(&cards);
+                for opponent in opponents.0.iter_mut() {
+                    if opponent.id == peer {
+                        opponent.card_count = opponent.card_count.saturating_sub(cards.len());
+                        break;
+                    }
+                }
+            }
+            SocketEvent::Reshuffle => {
+                // the new deck order, which only the host decides so every deck stays the same
+                if room.host.0 != Some(peer) {
+                    continue;
+                }
+                // two bytes for how many discarded cards the host recycled, then the deck order
+                let Some(&[high, low]) = packet.get(1..3) else {
+                    error!("Invalid reshuffle packet.");
+                    continue;
+                };
+                let count = u16::from_be_bytes([high, low]) as usize;
+                deck.recycle_bottom(&mut discard_pile.cards, count);
+                deck.load_from(&packet[3..]);
+                for play in discard_pile.provisional.iter_mut() {
+                    play.reshuffled(&deck, count);
+                }
+                events.reshuffled.send(Reshuffled(count));
+            }
+            SocketEvent::PlayConfirmed => {
+                // 16 bytes for the player, then the card
+                if room.host.0 != Some(peer) {
+                    continue;
+                }
+                let Some((player, card)) = read_play(&packet) else {
+                    error!("Invalid play confirmed packet.");
+                    continue;
+                };
+                discard_pile.confirm(player, card);
+            }
+            SocketEvent::PlayRejected => {
+                // 16 bytes for the player, then the card and why it was turned down
+                if room.host.0 != Some(peer) {
+                    continue;
+                }
+                let (Some((player, card)), Some(reason)) = (read_play(&packet), packet.get(18))
+                else {
+                    error!("Invalid play rejected packet.");
+                    continue;
+                };
+                let Some(play) = discard_pile.reject(player, card) else { continue; };
+                play.restore(
+                    socket.id(),
+                    &mut game_info,
+                    &mut deck,
+                    &mut discard_pile.cards,
+                    &mut main_player,
+                    &mut opponents,
+                );
+                events.rolled_back.send(PlayRolledBack {
+                    player,
+                    reason: RejectReason::from(*reason),
                 });
             }
+            SocketEvent::CallOne => {
+                one_cards.0.entry(peer).or_default().called = true;
+            }
+            SocketEvent::CatchOne => {
+                // 16 bytes for the player being caught
+                let Some(pid) = packet.get(1..17) else {
+                    error!("Invalid catch packet: missing player.");
+                    continue;
+                };
+                let pid: [u8; 16] = pid.try_into().expect("slice should be 16 bytes");
+                let target = PeerId(Uuid::from_bytes(pid));
+
+                if room.is_hosting() {
+                    // a catch request, which only goes through once the grace window has passed
+                    if !one_cards.0.get(&target).is_some_and(|one| one.catchable()) {
+                        continue;
+                    }
+                    broadcast.packet(SocketEvent::CatchOne).extend_from_slice(&pid);
+                    broadcast.send(&mut socket);
+                } else if room.host.0 != Some(peer) {
+                    // only the host can confirm a catch
+                    continue;
+                }
+                one_cards.0.entry(target).or_default().caught = true;
+                let own_pid = socket.id().expect("server should assign us a peer id");
+                draw_for_player(
+                    target,
+                    CATCH_PENALTY,
+                    own_pid,
+                    &mut events.spawn,
+                    &mut main_player,
+                    &mut opponents,
+                    &mut deck,
+                );
+            }
         }
     }
 }
 
+/// Sends a packet to a peer behind its sequence number, counting it for the debug overlay.
+///
+/// The packet is copied once, into the sequenced packet that's sent.
+fn send_packet(socket: &mut GameTransport, packet: &[u8], peer: PeerId) {
+    #[cfg(feature = "debug_overlay")]
+    crate::debug::record_sent(packet);
+    let sequence = socket.next_sequence(peer);
+    let mut sequenced = Vec::with_capacity(packet.len() + 4);
+    sequenced.extend_from_slice(&sequence.to_le_bytes());
+    sequenced.extend_from_slice(packet);
+    socket.send(RELIABLE_CHANNEL, sequenced.into_boxed_slice(), peer);
+}
+
+/// Sends a packet to a peer without waiting for it to arrive, counting it for the debug overlay.
+///
+/// Only for packets that are soon replaced by newer ones, since they can be lost or arrive out of
+/// order.
+fn send_unreliable(socket: &mut GameTransport, packet: Packet, peer: PeerId) {
+    #[cfg(feature = "debug_overlay")]
+    crate::debug::record_sent(&packet);
+    socket.send(UNRELIABLE_CHANNEL, packet, peer);
+}
+
+/// Sends our username to a peer, after our profile's player id.
+pub fn send_name(socket: &mut GameTransport, username: &str, player_id: u64, peer: PeerId) {
+    let mut packet = vec![SocketEvent::Name.into()];
+    packet.extend(player_id.to_le_bytes());
+    packet.extend_from_slice(username.as_bytes());
+    send_packet(socket, &packet, peer);
+}
+
 /// Resets the game state to the initial state.
 fn reset_game_state(
-    discard_cards: &Query<Entity, With<DiscardCard>>,
-    hand_cards: &Query<Entity, With<HandCard>>,
-    game_screen_state: &mut ResMut<NextState<GameScreenState>>,
+    round_cards: &mut RoundCards,
     discard_pile: &mut ResMut<DiscardCards>,
     main_player: &mut ResMut<MainPlayer>,
     opponents: &mut ResMut<Opponents>,
     game_info: &mut ResMut<GameInfo>,
+    rules: &GameRules,
     commands: &mut Commands,
 ) {
     // reset game state
     game_info.reset();
     main_player.reset();
     discard_pile.cards.clear();
+    discard_pile.provisional.clear();
+    discard_pile.buried = 0;
 
     // reset opponent card counts
     for opponent in opponents.0.iter_mut() {
-        opponent.card_count = 5;
+        opponent.card_count = rules.hand_size.cards();
     }
 
     // despawn discard cards
-    for entity in discard_cards.iter() {
+    for entity in round_cards.discard.iter() {
         commands.entity(entity).despawn_recursive();
     }
 
     // despawn hand cards
-    for entity in hand_cards.iter() {
+    for entity in round_cards.hand.iter() {
         commands.entity(entity).despawn_recursive();
     }
 
-    // hide win screen, show playing screen
-    game_screen_state.set(GameScreenState::Game);
+    // the last round's cards still waiting to be dealt don't get to come back
+    clear_queued_cards(&mut round_cards.spawn_queue, &mut round_cards.deal_queue);
 }
 
-/// Loads deck, player cards, and the top discard card.
+/// Loads deck, player cards, and the top discard card, applying the top card's effect if the
+/// rules say to.
 fn initialize_game_start(
     our_pid: &PeerId,
     spawn_events: &mut EventWriter<SpawnCard>,
     game_info: &mut ResMut<GameInfo>,
     main_player: &mut ResMut<MainPlayer>,
+    opponents: &mut ResMut<Opponents>,
     deck: &mut ResMut<Deck>,
     discard_pile: &mut ResMut<DiscardCards>,
     screen_state: &mut ResMut<NextState<ScreenState>>,
     menu_state: &mut ResMut<NextState<MenuState>>,
+    rules: &GameRules,
 ) {
     // fetch cards for our hand based on order
     let our_position = game_info
@@ -356,18 +1863,18 @@ fn initialize_game_start(
         .iter()
         .position(|pid| *pid == *our_pid)
         .expect("our pid should be in the order");
-    // get a new vector of the next 5 cards located at index our_position*5 from deck.cards
-    let cards = deck.cards[our_position * 5..(our_position + 1) * 5].to_vec();
-    main_player.cards = cards;
-
-    // discard the cards given to the players
-    deck.draw(5 * game_info.order.len() as i32);
+    let hand_size = rules.hand_size.cards();
+    main_player.set_cards(deck.deal(game_info.order.len(), our_position, hand_size));
+    // opponents were dealt as many cards as us, whatever they held last round
+    for opponent in opponents.0.iter_mut() {
+        opponent.card_count = hand_size;
+    }
 
     // spawn top card for discard pile
     let expect_msg = "complete deck should be loaded from packet";
     let mut card = deck.draw(1).first().copied().expect(expect_msg);
-    if card.color == CardColor::Wild {
-        // if the first card is a wild, draw another card
+    while rules.first_card.covers(&card) {
+        // wilds, and action cards when reflipping, go under another card
         discard_pile.cards.push(card);
         card = deck.draw(1).first().copied().expect(expect_msg);
     }
@@ -375,85 +1882,134 @@ fn initialize_game_start(
     spawn_events.send(SpawnCard {
         card,
         position: CardPosition::Draw,
-        card_type: CardType::Discard,
+        card_type: CardType::Discard(discard_pile.cards.len() - 1),
     });
 
-    // spawn cards in hand
-    for card in main_player.cards.iter() {
+    // deal cards into our hand
+    for (id, card) in main_player.entries() {
         spawn_events.send(SpawnCard {
             card: *card,
-            position: CardPosition::Hand,
-            card_type: CardType::Hand,
+            position: CardPosition::Deal,
+            card_type: CardType::Hand(*id),
         })
     }
 
+    // everyone gets any extra cards the rules start them with, in turn order
+    if rules.extra_cards > 0 {
+        for player in game_info.order.clone() {
+            draw_for_player(
+                player,
+                rules.extra_cards as u32,
+                *our_pid,
+                spawn_events,
+                main_player,
+                opponents,
+                deck,
+            );
+        }
+    }
+
+    // the first player may have to draw for the top card, after their hand is dealt
+    if let Some((player, count)) = game_info.apply_first_card(&card, rules) {
+        draw_for_player(
+            player,
+            count,
+            *our_pid,
+            spawn_events,
+            main_player,
+            opponents,
+            deck,
+        );
+    }
+
     // show game ui
     screen_state.set(ScreenState::Game);
     menu_state.set(MenuState::Disabled);
 }
 
-/// Performs the card effect for the given card.
+/// Advances the turn after the current player plays the given cards and performs their
+/// effect.
 ///
-/// Handles skips, reverses, and draw twos.
+/// Handles skips, reverses, and draw twos, stacked when several are played together.
 pub fn handle_card_effect(
-    card: &Card,
-    card_player: &PeerId,
+    cards: &[Card],
     spawn_events: &mut EventWriter<SpawnCard>,
-    socket: &mut ResMut<MatchboxSocket<SingleChannel>>,
+    camera_cues: &mut EventWriter<CameraCue>,
+    socket: &mut ResMut<GameTransport>,
     game_info: &mut ResMut<GameInfo>,
     main_player: &mut ResMut<MainPlayer>,
     opponents: &mut ResMut<Opponents>,
     deck: &mut ResMut<Deck>,
+    rules: &GameRules,
 ) {
-    match card.value {
-        CardValue::Skip => {
-            game_info.advance_turn();
-        }
-        CardValue::Reverse => {
-            game_info.swap_direction();
-            game_info.advance_turn();
-            game_info.advance_turn();
-        }
-        CardValue::DrawTwo => {
-            let next_player = game_info
-                .current_player
-                .expect("can't play a card without a current player");
-            let own_pid = socket.id().expect("server should've assigned our peer id");
-
-            // make sure we don't draw cards for ourselves
-            if next_player == *card_player {
-                return;
-            }
-
-            if next_player == own_pid {
-                // draw cards for main player
-                let cards = deck.draw(2);
-                if cards.is_empty() {
-                    // no cards left in deck
-                    // TODO: there should be some indicator of this
-                    return;
-                };
-                main_player.cards.extend(&cards);
+    // the players after the one who played skips miss their turn
+    let own_pid = socket.id();
+    if let (Some(card), Some(own_id)) = (cards.first(), own_pid) {
+        if card.value == CardValue::Skip
+            && game_info.upcoming_players(cards.len()).contains(&own_id)
+        {
+            camera_cues.send(CameraCue::Shake);
+        }
+    }
 
-                for card in cards {
-                    spawn_events.send(SpawnCard {
-                        card,
-                        position: CardPosition::Draw,
-                        card_type: CardType::Hand,
-                    });
-                }
-            } else {
-                // increment card count for opponent
-                for opponent in opponents.0.iter_mut() {
-                    if opponent.id == next_player {
-                        opponent.card_count += 2;
-                        break;
-                    }
-                }
-                deck.draw(2);
+    let Some((next_player, count)) = game_info.play_cards(cards, rules) else { return; };
+    let own_pid = own_pid.expect("server should've assigned our peer id");
+    if next_player == own_pid {
+        camera_cues.send(CameraCue::Punch);
+    }
+    draw_for_player(
+        next_player,
+        count,
+        own_pid,
+        spawn_events,
+        main_player,
+        opponents,
+        deck,
+    );
+}
+
+/// Draws penalty cards for a player outside of their turn.
+fn draw_for_player(
+    player: PeerId,
+    count: u32,
+    own_pid: PeerId,
+    spawn_events: &mut EventWriter<SpawnCard>,
+    main_player: &mut ResMut<MainPlayer>,
+    opponents: &mut ResMut<Opponents>,
+    deck: &mut ResMut<Deck>,
+) {
+    if player == own_pid {
+        // draw cards for main player
+        let cards = deck.draw(count as i32);
+        if cards.is_empty() {
+            // no cards left in deck
+            // TODO: there should be some indicator of this
+            return;
+        };
+        for card in cards {
+            let id = main_player.push(card);
+            spawn_events.send(SpawnCard {
+                card,
+                position: CardPosition::Draw,
+                card_type: CardType::Hand(id),
+            });
+        }
+    } else {
+        // increment card count for opponent
+        let drawn = deck.draw(count as i32);
+        for opponent in opponents.0.iter_mut() {
+            if opponent.id == player {
+                opponent.card_count += drawn.len();
+                break;
             }
         }
-        _ => {}
+        for card in drawn {
+            spawn_events.send(SpawnCard {
+                card,
+                position: CardPosition::Draw,
+                card_type: CardType::Opponent(player),
+            });
+        }
     }
 }
 
@@ -461,161 +2017,696 @@ pub fn handle_card_effect(
 fn handle_start_game(
     mut events: EventReader<StartGame>,
     mut spawn_events: EventWriter<SpawnCard>,
-    mut socket: ResMut<MatchboxSocket<SingleChannel>>,
+    mut socket: ResMut<GameTransport>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut screen_state: ResMut<NextState<ScreenState>>,
     mut discard_pile: ResMut<DiscardCards>,
     mut main_player: ResMut<MainPlayer>,
+    mut opponents: ResMut<Opponents>,
     mut game_info: ResMut<GameInfo>,
     mut deck: ResMut<Deck>,
+    mut game_rng: ResMut<GameRng>,
+    mut broadcast: Local<Broadcast>,
+    rules: Res<GameRules>,
+    match_format: Res<MatchFormat>,
+    deal_speed: Res<DealSpeed>,
+    settings: Res<Settings>,
 ) {
     let Some(event) = events.read().next() else {
 		return;
 	};
+    let own_pid = socket.id().expect("server should assign us a peer id");
+    let players: Vec<PeerId> = event
+        .order
+        .iter()
+        .copied()
+        .filter(|pid| *pid != own_pid)
+        .collect();
+
+    // share our table theme, for players who'd like to see the same table
+    broadcast
+        .packet(SocketEvent::TableTheme)
+        .push(settings.table_theme.into());
+    broadcast.send_to(&mut socket, players.iter());
 
     game_info.order = event.order.clone();
     game_info.current_player = event.order.first().copied();
 
+    // deal from a fresh deck, shuffled from a new seed that's shared with everyone
+    *game_rng = GameRng::new(event.seed.unwrap_or_else(rand::random));
+    *deck = Deck::shuffled(&rules, &mut game_rng);
+
     // construct start event packet
-    let mut packet: Vec<u8> = Vec::new();
-    packet.push(
-        if event.restart {
-            SocketEvent::Restart
-        } else {
-            SocketEvent::Start
-        }
-        .into(),
-    );
+    let packet = broadcast.packet(if event.restart {
+        SocketEvent::Restart
+    } else {
+        SocketEvent::Start
+    });
     // add player order
     packet.push(event.order.len() as u8);
     for player_id in event.order.iter() {
         packet.extend_from_slice(player_id.0.as_bytes());
     }
+    // add rules
+    let rule_bytes = rules.to_bytes();
+    packet.push(rule_bytes.len() as u8);
+    packet.extend(rule_bytes);
+    // add seed
+    packet.extend_from_slice(&game_rng.seed().to_le_bytes());
+    // add match format
+    packet.extend(match_format.to_bytes());
+    // add deal speed
+    packet.push((*deal_speed).into());
     // add deck
     packet.extend(deck.get_card_order());
-    let packet = packet.into_boxed_slice();
-
-    // send packet to all peers
-    for peer in socket.connected_peers().collect::<Vec<_>>().iter() {
-        println!("sending packet: {packet:?}");
-        socket.send(packet.clone(), *peer);
-    }
 
-    let own_pid = socket.id().expect("server should assign us a peer id");
+    // send packet to all players (peers still entering the password aren't playing)
+    broadcast.send_to(&mut socket, players.iter());
 
     initialize_game_start(
         &own_pid,
         &mut spawn_events,
         &mut game_info,
         &mut main_player,
+        &mut opponents,
         &mut deck,
         &mut discard_pile,
         &mut screen_state,
         &mut menu_state,
+        &rules,
     )
 }
 
-/// Sends draw card event to all peers and advances turn.
+/// Sends draw card event to all peers and advances turn, unless the card drawn can be played.
 fn handle_draw_card(
     mut events: EventReader<DrawCard>,
-    mut socket: ResMut<MatchboxSocket<SingleChannel>>,
+    mut socket: ResMut<GameTransport>,
     mut game_info: ResMut<GameInfo>,
+    mut broadcast: Local<Broadcast>,
+    discard_pile: Res<DiscardCards>,
+    rules: Res<GameRules>,
 ) {
-    for _ in events.read() {
-        let packet = Vec::from([SocketEvent::Draw.into()]).into_boxed_slice();
-        for peer in socket.connected_peers().collect::<Vec<_>>().iter() {
-            socket.send(packet.clone(), *peer);
-        }
-        game_info.advance_turn();
+    for DrawCard(cards) in events.read() {
+        broadcast.packet(SocketEvent::Draw);
+        broadcast.send(&mut socket);
+        game_info.finish_draw(cards, discard_pile.cards.last(), &rules);
+    }
+}
+
+/// Tells everyone that we're keeping the card we drew, and advances turn.
+fn handle_keep_drawn(
+    mut events: EventReader<KeepDrawn>,
+    mut socket: ResMut<GameTransport>,
+    mut game_info: ResMut<GameInfo>,
+    mut broadcast: Local<Broadcast>,
+) {
+    if events.read().next().is_none() {
+        return;
     }
+    broadcast.packet(SocketEvent::KeepDrawn);
+    broadcast.send(&mut socket);
+    game_info.keep_drawn();
 }
 
 /// Sends play card event to all peers and advances turn.
 fn handle_play_card(
     mut play_events: EventReader<PlayCard>,
-    mut win_events: EventWriter<Win>,
     mut spawn_events: EventWriter<SpawnCard>,
+    mut camera_cues: EventWriter<CameraCue>,
     mut main_player: ResMut<MainPlayer>,
     mut opponents: ResMut<Opponents>,
     mut deck: ResMut<Deck>,
-    mut socket: ResMut<MatchboxSocket<SingleChannel>>,
+    mut socket: ResMut<GameTransport>,
     mut game_info: ResMut<GameInfo>,
+    mut discard_pile: ResMut<DiscardCards>,
+    mut broadcast: Local<Broadcast>,
+    server_state: Res<State<ServerState>>,
+    rules: Res<GameRules>,
 ) {
-    for event in play_events.read() {
-        let packet = Vec::from([SocketEvent::Play.into(), event.0.into()]).into_boxed_slice();
-        for peer in socket.connected_peers().collect::<Vec<_>>().iter() {
-            socket.send(packet.clone(), *peer);
+    for PlayCard(cards) in play_events.read() {
+        if let [card] = cards.as_slice() {
+            broadcast.packet(SocketEvent::Play).push((*card).into());
+        } else {
+            let packet = broadcast.packet(SocketEvent::PlayMulti);
+            packet.push(cards.len() as u8);
+            packet.extend(cards.iter().map(|card| -> u8 { (*card).into() }));
+        }
+        broadcast.send(&mut socket);
+
+        // the play counts right away, but can be undone until the host confirms it
+        if let (ServerState::Client(_), Some(own_id)) = (server_state.get(), socket.id()) {
+            // the cards are already on the discard pile
+            let before = discard_pile.cards.len().saturating_sub(cards.len());
+            let play = ProvisionalPlay::new(
+                own_id,
+                cards,
+                &game_info,
+                &deck,
+                &discard_pile.cards[..before],
+                &main_player,
+                &opponents,
+            );
+            discard_pile.provisional.push(play);
         }
-        game_info.advance_turn();
 
-        let Some(pid) = socket.id() else { return; };
         handle_card_effect(
-            &event.0,
-            &pid,
+            cards,
             &mut spawn_events,
+            &mut camera_cues,
             &mut socket,
             &mut game_info,
             &mut main_player,
             &mut opponents,
             &mut deck,
+            &rules,
         );
+    }
+}
 
-        if main_player.cards.is_empty() {
-            let Some(id) = socket.id() else { return; };
-            win_events.send(Win(id));
-        }
+/// Announces the winner once a hand is empty, or everyone else has left.
+///
+/// Runs after the effect of the winning play, so a draw two played as the last card is still
+/// drawn. Our own plays only count once the host has confirmed them, since a play it turns down
+/// is undone.
+fn check_win(
+    mut win_events: EventWriter<Win>,
+    mut socket: ResMut<GameTransport>,
+    game_info: Res<GameInfo>,
+    main_player: Res<MainPlayer>,
+    opponents: Res<Opponents>,
+    discard_pile: Res<DiscardCards>,
+    game_screen_state: Res<State<GameScreenState>>,
+) {
+    let changed = game_info.is_changed()
+        || main_player.is_changed()
+        || opponents.is_changed()
+        || discard_pile.is_changed();
+    if !changed || !discard_pile.provisional.is_empty() {
+        return;
+    }
+    if matches!(
+        **game_screen_state,
+        GameScreenState::Win | GameScreenState::Champion
+    ) {
+        return;
     }
+    if let Some(winner) = round_winner(socket.id(), &game_info, &main_player, &opponents) {
+        win_events.send(Win(winner));
+    }
+}
+
+/// Returns the winner of the round, if there is one yet.
+fn round_winner(
+    own_pid: Option<PeerId>,
+    game_info: &GameInfo,
+    main_player: &MainPlayer,
+    opponents: &Opponents,
+) -> Option<PeerId> {
+    game_info.winner(|player| {
+        if Some(player) == own_pid {
+            return Some(main_player.len());
+        }
+        opponents
+            .0
+            .iter()
+            .find(|opponent| opponent.id == player)
+            .map(|opponent| opponent.card_count)
+    })
 }
 
 /// Handles the restart game event from host.
+///
+/// Everyone is told to clear the last round first, and the next round is dealt by
+/// [`release_restart_barrier`] once they have.
 fn handle_restart_game(
-    hand_cards: Query<Entity, With<HandCard>>,
-    discard_cards: Query<Entity, With<DiscardCard>>,
+    mut round_cards: RoundCards,
     mut restart_events: EventReader<RestartGame>,
-    mut start_events: EventWriter<StartGame>,
+    mut socket: ResMut<GameTransport>,
+    mut broadcast: Local<Broadcast>,
     mut game_screen_state: ResMut<NextState<GameScreenState>>,
     mut discard_pile: ResMut<DiscardCards>,
     mut game_info: ResMut<GameInfo>,
     mut main_player: ResMut<MainPlayer>,
     mut opponents: ResMut<Opponents>,
+    mut voters: ResMut<RematchVoters>,
+    mut game_rng: ResMut<GameRng>,
+    bots: Res<Bots>,
+    challenge: Res<ActiveChallenge>,
+    rules: Res<GameRules>,
     mut commands: Commands,
 ) {
     if restart_events.read().next().is_none() {
         return;
     }
 
-    // rotate player order for new game
-    let mut order = game_info.order.clone();
-    order.rotate_left(1);
+    // pass the deal on for the new game, without seats the bots were filling in for, unless
+    // they're the opponents in a challenge
+    let winner = round_winner(socket.id(), &game_info, &main_player, &opponents);
+    let mut order = rules
+        .starting_player
+        .next_order(&game_info.order, winner, &mut game_rng);
+    if !challenge.is_active() {
+        order.retain(|pid| !bots.0.contains_key(pid));
+    }
 
     reset_game_state(
-        &discard_cards,
-        &hand_cards,
-        &mut game_screen_state,
+        &mut round_cards,
         &mut discard_pile,
         &mut main_player,
         &mut opponents,
         &mut game_info,
+        &rules,
         &mut commands,
     );
+    game_screen_state.set(GameScreenState::Game);
+    voters.0.clear();
 
-    start_events.send(StartGame {
+    let own_pid = socket.id().expect("server should assign us a peer id");
+    let waiting: HashSet<PeerId> = order
+        .iter()
+        .copied()
+        .filter(|pid| *pid != own_pid)
+        .collect();
+    broadcast.packet(SocketEvent::RestartPrepare);
+    broadcast.send_to(&mut socket, waiting.iter());
+    commands.insert_resource(RestartBarrier {
         order,
-        restart: true,
+        waiting,
+        timer: Timer::from_seconds(RESTART_TIMEOUT, TimerMode::Once),
     });
 }
 
+/// Deals the next round once every player has cleared the last one.
+///
+/// Players who leave, or don't answer in time, aren't waited for.
+fn release_restart_barrier(
+    hand_cards: Query<(), With<HandCard>>,
+    discard_cards: Query<(), With<DiscardCard>>,
+    mut ready_events: EventReader<RestartReady>,
+    mut start_events: EventWriter<StartGame>,
+    socket: Res<GameTransport>,
+    barrier: Option<ResMut<RestartBarrier>>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let Some(mut barrier) = barrier else {
+        ready_events.clear();
+        return;
+    };
+    for RestartReady(player) in ready_events.read() {
+        barrier.waiting.remove(player);
+    }
+    let connected: HashSet<PeerId> = socket.connected_peers().collect();
+    barrier.waiting.retain(|pid| connected.contains(pid));
+
+    let timed_out = barrier.timer.tick(time.delta()).finished();
+    if timed_out && !barrier.waiting.is_empty() {
+        warn!("Restarting without {} players.", barrier.waiting.len());
+    }
+    // our own cards need to be gone too
+    let cleared = hand_cards.is_empty() && discard_cards.is_empty();
+    if cleared && (barrier.waiting.is_empty() || timed_out) {
+        start_events.send(StartGame {
+            order: barrier.order.clone(),
+            restart: true,
+            seed: None,
+        });
+        commands.remove_resource::<RestartBarrier>();
+    }
+}
+
+/// Tells the host we're ready for the next round, once the last one has been cleared.
+fn send_restart_ack(
+    hand_cards: Query<(), With<HandCard>>,
+    discard_cards: Query<(), With<DiscardCard>>,
+    mut socket: ResMut<GameTransport>,
+    ack: Option<Res<RestartAck>>,
+    mut commands: Commands,
+) {
+    let Some(ack) = ack else { return; };
+    if !hand_cards.is_empty() || !discard_cards.is_empty() {
+        return;
+    }
+    let packet = Vec::from([SocketEvent::RestartReady.into()]);
+    send_packet(&mut socket, &packet, ack.0);
+    commands.remove_resource::<RestartAck>();
+}
+
+/// Tells the host we want a rematch.
+fn handle_vote_rematch(
+    mut vote_events: EventReader<VoteRematch>,
+    mut socket: ResMut<GameTransport>,
+    room_host: Res<RoomHost>,
+) {
+    for VoteRematch in vote_events.read() {
+        let Some(host) = room_host.0 else { continue; };
+        let packet = Vec::from([SocketEvent::RematchVote.into()]);
+        send_packet(&mut socket, &packet, host);
+    }
+}
+
+/// Counts rematch votes as they reach the host, and lets everyone know the new count.
+fn tally_rematch_votes(
+    mut voted_events: EventReader<RematchVoted>,
+    mut peer_left_events: EventReader<PeerLeft>,
+    mut votes_events: EventWriter<RematchVotes>,
+    mut socket: ResMut<GameTransport>,
+    mut voters: ResMut<RematchVoters>,
+    mut broadcast: Local<Broadcast>,
+    server_state: Res<State<ServerState>>,
+) {
+    // forget votes from any previous room
+    if socket.is_added() {
+        voters.0.clear();
+    }
+    let ServerState::Server(_) = **server_state else {
+        voted_events.clear();
+        peer_left_events.clear();
+        return;
+    };
+    let mut changed = false;
+    for RematchVoted(player) in voted_events.read() {
+        changed |= voters.0.insert(*player);
+    }
+    // anyone leaving changes how many players there are to vote
+    for PeerLeft { player, .. } in peer_left_events.read() {
+        voters.0.remove(player);
+        changed |= !voters.0.is_empty();
+    }
+    if !changed {
+        return;
+    }
+
+    let votes = RematchVotes {
+        votes: voters.0.len() as u8,
+        players: socket.room_peers().count() as u8,
+    };
+    broadcast
+        .packet(SocketEvent::RematchVotes)
+        .extend([votes.votes, votes.players]);
+    broadcast.send(&mut socket);
+    votes_events.send(votes);
+}
+
+/// Asks the host for the current turn state.
+fn handle_request_sync(
+    mut sync_events: EventReader<RequestSync>,
+    mut socket: ResMut<GameTransport>,
+    mut broadcast: Local<Broadcast>,
+) {
+    if sync_events.read().next().is_none() {
+        return;
+    }
+    // we don't know which peer is hosting, so ask everyone and only the host will answer
+    broadcast.packet(SocketEvent::SyncRequest);
+    broadcast.send(&mut socket);
+}
+
+/// Tells everyone that we've called one.
+fn handle_call_one(
+    mut call_events: EventReader<CallOne>,
+    mut socket: ResMut<GameTransport>,
+    mut one_cards: ResMut<OneCards>,
+    mut broadcast: Local<Broadcast>,
+) {
+    if call_events.read().next().is_none() {
+        return;
+    }
+    let Some(own_pid) = socket.id() else { return; };
+    one_cards.0.entry(own_pid).or_default().called = true;
+    broadcast.packet(SocketEvent::CallOne);
+    broadcast.send(&mut socket);
+}
+
+/// Asks the host to catch a player who didn't call one, or catches them if we're hosting.
+fn handle_catch_one(
+    mut catch_events: EventReader<CatchOne>,
+    mut spawn_events: EventWriter<SpawnCard>,
+    mut socket: ResMut<GameTransport>,
+    mut one_cards: ResMut<OneCards>,
+    mut main_player: ResMut<MainPlayer>,
+    mut opponents: ResMut<Opponents>,
+    mut deck: ResMut<Deck>,
+    mut broadcast: Local<Broadcast>,
+    server_state: Res<State<ServerState>>,
+    room_host: Res<RoomHost>,
+) {
+    for CatchOne(target) in catch_events.read() {
+        broadcast
+            .packet(SocketEvent::CatchOne)
+            .extend_from_slice(target.0.as_bytes());
+
+        let ServerState::Server(_) = **server_state else {
+            if let Some(host) = room_host.0 {
+                broadcast.send_to(&mut socket, [&host]);
+            }
+            continue;
+        };
+        if !one_cards.0.get(target).is_some_and(|one| one.catchable()) {
+            continue;
+        }
+        broadcast.send(&mut socket);
+        one_cards.0.entry(*target).or_default().caught = true;
+        let Some(own_pid) = socket.id() else { return; };
+        draw_for_player(
+            *target,
+            CATCH_PENALTY,
+            own_pid,
+            &mut spawn_events,
+            &mut main_player,
+            &mut opponents,
+            &mut deck,
+        );
+    }
+}
+
+/// Sends the cards left in our hand to all peers.
+fn handle_reveal_hand(
+    mut reveal_events: EventReader<RevealHand>,
+    mut socket: ResMut<GameTransport>,
+    mut broadcast: Local<Broadcast>,
+    main_player: Res<MainPlayer>,
+) {
+    if reveal_events.read().next().is_none() {
+        return;
+    }
+    let packet = broadcast.packet(SocketEvent::RevealHand);
+    packet.extend(main_player.iter().map(|card| -> u8 { card.into() }));
+    broadcast.send(&mut socket);
+}
+
+/// Sends our hand to the host after a player disconnects.
+fn handle_share_hand(
+    mut share_events: EventReader<ShareHand>,
+    mut socket: ResMut<GameTransport>,
+    mut broadcast: Local<Broadcast>,
+    main_player: Res<MainPlayer>,
+    room_host: Res<RoomHost>,
+) {
+    if share_events.read().next().is_none() {
+        return;
+    }
+    let Some(host) = room_host.0 else { return; };
+    let packet = broadcast.packet(SocketEvent::ShareHand);
+    packet.extend(main_player.iter().map(|card| -> u8 { card.into() }));
+    broadcast.send_to(&mut socket, [&host]);
+}
+
+/// Tells peers that the host has removed a player, given their seat to a bot, or ended the game.
+fn handle_host_decision(
+    mut drop_events: EventReader<DropPlayer>,
+    mut abort_events: EventReader<AbortGame>,
+    mut bot_events: EventReader<BotTakeover>,
+    mut socket: ResMut<GameTransport>,
+    mut broadcast: Local<Broadcast>,
+    server_state: Res<State<ServerState>>,
+) {
+    // these events are also posted on clients when the host's packets arrive
+    let ServerState::Server(_) = **server_state else {
+        drop_events.clear();
+        abort_events.clear();
+        bot_events.clear();
+        return;
+    };
+    for DropPlayer { player, cards } in drop_events.read() {
+        let packet = broadcast.packet(SocketEvent::DropPlayer);
+        packet.extend_from_slice(player.0.as_bytes());
+        packet.extend(cards.iter().map(|card| -> u8 { (*card).into() }));
+        broadcast.send(&mut socket);
+    }
+    for BotTakeover { player, cards } in bot_events.read() {
+        let packet = broadcast.packet(SocketEvent::BotTakeover);
+        packet.extend_from_slice(player.0.as_bytes());
+        packet.extend(cards.iter().map(|card| -> u8 { (*card).into() }));
+        broadcast.send(&mut socket);
+    }
+    if abort_events.read().next().is_some() {
+        broadcast.packet(SocketEvent::AbortGame);
+        broadcast.send(&mut socket);
+    }
+}
+
+/// Tells peers that we're leaving, before the connection is closed.
+fn handle_leave_game(
+    mut leave_events: EventReader<LeaveGame>,
+    mut socket: ResMut<GameTransport>,
+    mut broadcast: Local<Broadcast>,
+) {
+    if leave_events.read().next().is_none() {
+        return;
+    }
+    broadcast.packet(SocketEvent::Leave);
+    broadcast.send(&mut socket);
+}
+
+/// Sends the entered password to the host.
+fn handle_submit_password(
+    mut password_events: EventReader<SubmitPassword>,
+    mut socket: ResMut<GameTransport>,
+    mut auth_state: ResMut<AuthState>,
+    room_host: Res<RoomHost>,
+) {
+    for SubmitPassword(password) in password_events.read() {
+        let Some(host) = room_host.0 else { continue; };
+        let mut packet = Vec::from([SocketEvent::Auth.into(), AUTH_ATTEMPT]);
+        packet.extend_from_slice(password.as_bytes());
+        send_packet(&mut socket, &packet, host);
+        *auth_state = AuthState::Waiting;
+    }
+}
+
+/// Puts cards returned over the hand limit under the deck and tells peers which ones.
+fn handle_return_cards(
+    mut return_events: EventReader<ReturnCards>,
+    mut socket: ResMut<GameTransport>,
+    mut deck: ResMut<Deck>,
+    mut broadcast: Local<Broadcast>,
+) {
+    for ReturnCards(cards) in return_events.read() {
+        deck.return_to_bottom(cards);
+        let packet = broadcast.packet(SocketEvent::ReturnCards);
+        packet.push(cards.len() as u8);
+        packet.extend(cards.iter().map(|card| -> u8 { (*card).into() }));
+        broadcast.send(&mut socket);
+    }
+}
+
+/// Shuffles the discard pile back into the deck once it runs out, and sends the new order
+/// to peers.
+///
+/// Only the host shuffles, since every peer's own random order would be different.
+fn reshuffle_deck(
+    mut reshuffled: EventWriter<Reshuffled>,
+    mut socket: ResMut<GameTransport>,
+    mut deck: ResMut<Deck>,
+    mut discard_pile: ResMut<DiscardCards>,
+    mut game_rng: ResMut<GameRng>,
+    mut broadcast: Local<Broadcast>,
+    server_state: Res<State<ServerState>>,
+) {
+    let ServerState::Server(_) = **server_state else { return; };
+    if !deck.is_empty() || discard_pile.cards.len() <= 1 {
+        return;
+    }
+    // cards played since then may reach peers before the new order, so they're told how many
+    // cards to recycle rather than keeping just their top card
+    let count = deck.recycle(&mut discard_pile.cards) as u16;
+    deck.shuffle(&mut game_rng);
+    let packet = broadcast.packet(SocketEvent::Reshuffle);
+    packet.extend(count.to_be_bytes());
+    packet.extend(deck.get_card_order());
+    broadcast.send(&mut socket);
+    reshuffled.send(Reshuffled(count as usize));
+}
+
 /// Sends wild color choice to peers.
 fn handle_wild_color(
     mut wild_events: EventReader<WildColor>,
-    mut socket: ResMut<MatchboxSocket<SingleChannel>>,
+    mut socket: ResMut<GameTransport>,
+    mut broadcast: Local<Broadcast>,
 ) {
     for event in wild_events.read() {
-        let packet = Vec::from([SocketEvent::Wild.into(), event.0.into()]).into_boxed_slice();
-        for peer in socket.connected_peers().collect::<Vec<_>>().iter() {
-            socket.send(packet.clone(), *peer);
-        }
+        broadcast.packet(SocketEvent::Wild).push(event.0.into());
+        broadcast.send(&mut socket);
+    }
+}
+
+/// Reads the player and first card played from a play confirmed or rejected packet.
+fn read_play(packet: &[u8]) -> Option<(PeerId, Card)> {
+    let pid: [u8; 16] = packet.get(1..17)?.try_into().ok()?;
+    let card = Card::from(*packet.get(17)?);
+    Some((PeerId(Uuid::from_bytes(pid)), card))
+}
+
+/// Reads a little endian `u64` from the packet at the given index.
+fn read_u64(packet: &[u8], start: usize) -> Option<u64> {
+    let bytes = packet.get(start..start + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Reads a little endian `u32` from the packet at the given index.
+fn read_u32(packet: &[u8], start: usize) -> Option<u32> {
+    let bytes = packet.get(start..start + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Reads a little endian `f32` from the packet at the given index.
+fn read_f32(packet: &[u8], start: usize) -> Option<f32> {
+    let bytes = packet.get(start..start + 4)?;
+    Some(f32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Sends the host's turn clock to every peer, so everyone's clock shows the same time.
+///
+/// Updates go out whenever the turn changes and then every [`TURN_CLOCK_INTERVAL`] seconds.
+fn broadcast_turn_clock(
+    mut socket: ResMut<GameTransport>,
+    mut broadcast: Local<Broadcast>,
+    mut last_sent: Local<Option<(u32, f32)>>,
+    turn_times: Res<TurnTimes>,
+    connections: Res<PeerConnections>,
+    server_state: Res<State<ServerState>>,
+    time: Res<Time>,
+) {
+    let ServerState::Server(_) = **server_state else { return; };
+    let now = time.elapsed_seconds();
+    let Some((turn, elapsed)) = turn_times.elapsed(now) else { return; };
+    if last_sent.is_some_and(|(last_turn, sent_at)| {
+        last_turn == turn && now - sent_at < TURN_CLOCK_INTERVAL
+    }) {
+        return;
+    }
+    *last_sent = Some((turn, now));
+
+    // each peer is sent its own latency
+    for peer in broadcast.peers(socket.room_peers()) {
+        let mut packet = vec![SocketEvent::TurnClock.into()];
+        packet.extend_from_slice(&turn.to_le_bytes());
+        packet.extend_from_slice(&elapsed.to_le_bytes());
+        let latency = connections.latency(peer).unwrap_or_default();
+        packet.extend_from_slice(&latency.to_le_bytes());
+        send_unreliable(&mut socket, packet.into_boxed_slice(), *peer);
+    }
+}
+
+/// Pings every peer every [`PING_INTERVAL`] seconds, to measure latency and notice peers that
+/// have gone quiet.
+fn send_pings(
+    mut socket: ResMut<GameTransport>,
+    mut broadcast: Local<Broadcast>,
+    mut last_sent: Local<Option<f32>>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+    if last_sent.is_some_and(|sent_at| now - sent_at < PING_INTERVAL) {
+        return;
     }
+    *last_sent = Some(now);
+
+    broadcast
+        .packet(SocketEvent::Ping)
+        .extend_from_slice(&now.to_le_bytes());
+    broadcast.send_unreliable(&mut socket);
 }
 
 pub struct Plugin;
@@ -624,22 +2715,186 @@ impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
         app.add_event::<StartGame>()
             .add_event::<DrawCard>()
+            .add_event::<KeepDrawn>()
             .add_event::<PlayCard>()
+            .add_event::<ReturnCards>()
             .add_event::<RestartGame>()
+            .add_event::<RestartReady>()
             .add_event::<WildColor>()
+            .add_event::<RequestSync>()
+            .add_event::<Synced>()
+            .add_event::<SubmitPassword>()
+            .add_event::<CallOne>()
+            .add_event::<CatchOne>()
+            .add_event::<RevealHand>()
+            .add_event::<PeerLeft>()
+            .add_event::<ShareHand>()
+            .add_event::<HandShared>()
+            .add_event::<DropPlayer>()
+            .add_event::<AbortGame>()
+            .add_event::<BotTakeover>()
+            .add_event::<LeaveGame>()
+            .add_event::<TurnClockSynced>()
+            .add_event::<Reshuffled>()
+            .add_event::<PlayRolledBack>()
+            .add_event::<OpponentPlayed>()
+            .add_event::<VoteRematch>()
+            .add_event::<RematchVoted>()
+            .add_event::<RematchVotes>()
+            .add_event::<HostRestarting>()
+            .init_resource::<PeerConnections>()
+            .init_resource::<RematchVoters>()
             .add_state::<ServerState>()
             .add_systems(Startup, setup)
             .add_systems(
                 Update,
                 (
-                    receive_messages,
-                    handle_start_game,
-                    handle_draw_card,
-                    handle_play_card,
-                    handle_restart_game,
-                    handle_wild_color,
+                    receive_messages.in_set(GameSet::NetIn),
+                    update_connection_state
+                        .after(receive_messages)
+                        .in_set(GameSet::NetIn)
+                        .run_if(in_state(MenuState::Lobby)),
+                    (
+                        handle_start_game,
+                        handle_draw_card,
+                        handle_play_card,
+                        handle_return_cards,
+                        handle_restart_game,
+                        release_restart_barrier,
+                        send_restart_ack,
+                        handle_wild_color,
+                        handle_request_sync,
+                        handle_submit_password,
+                        handle_call_one,
+                        handle_catch_one,
+                        handle_reveal_hand,
+                        handle_share_hand,
+                        handle_host_decision,
+                        handle_leave_game,
+                    )
+                        .in_set(GameSet::NetOut),
+                )
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(
+                Update,
+                (
+                    send_pings,
+                    handle_keep_drawn,
+                    handle_vote_rematch,
+                    tally_rematch_votes,
+                    broadcast_turn_clock.run_if(in_state(ScreenState::Game)),
+                    broadcast_lobby_options.run_if(in_state(MenuState::Lobby)),
                 )
-                    .run_if(resource_exists::<MatchboxSocket<SingleChannel>>()),
+                    .in_set(GameSet::NetOut)
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(
+                Update,
+                check_win
+                    .in_set(GameSet::Rules)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(
+                Update,
+                reshuffle_deck
+                    .in_set(GameSet::Rules)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(in_state(GameScreenState::Game))
+                    .run_if(not_paused)
+                    .run_if(resource_exists::<GameTransport>()),
             );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::utils::Uuid;
+
+    /// Ensures that everyone left in the room picks the same new host.
+    #[test]
+    fn test_next_host() {
+        let peers: Vec<PeerId> = (1..=3).map(|id| PeerId(Uuid::from_u128(id))).collect();
+        for own_id in peers.iter() {
+            let others = peers.iter().copied().filter(|peer| peer != own_id);
+            assert_eq!(next_host(*own_id, others), peers[0]);
+        }
+        assert_eq!(next_host(peers[2], []), peers[2]);
+    }
+
+    /// Ensures that latency is smoothed, and that quiet peers look poor before they time out.
+    #[test]
+    fn test_peer_connections() {
+        let peer = PeerId(Uuid::from_u128(1));
+        let mut connections = PeerConnections::default();
+        connections.heard(peer, 0.0);
+        assert_eq!(connections.quality(&peer, 0.0), None);
+
+        connections.measure(peer, 0.04);
+        assert_eq!(connections.latency(&peer), Some(0.02));
+        assert_eq!(
+            connections.quality(&peer, 0.0),
+            Some(ConnectionQuality::Good)
+        );
+        // one slow round trip doesn't ruin the connection
+        connections.measure(peer, 1.0);
+        assert_eq!(
+            connections.quality(&peer, 0.0),
+            Some(ConnectionQuality::Fair)
+        );
+
+        assert_eq!(
+            connections.quality(&peer, QUIET_TIME + 0.1),
+            Some(ConnectionQuality::Poor)
+        );
+        assert!(connections.timed_out(QUIET_TIME + 0.1).is_empty());
+        assert_eq!(connections.timed_out(PEER_TIMEOUT + 0.1), vec![peer]);
+
+        connections.forget(&peer);
+        assert_eq!(connections.latency(&peer), None);
+        assert!(connections.timed_out(PEER_TIMEOUT + 0.1).is_empty());
+    }
+
+    /// Ensures that packets come out in the order they were sent, without duplicates, and that a
+    /// lost packet doesn't hold the rest up forever.
+    #[test]
+    fn test_packet_order() {
+        let peer = PeerId(Uuid::from_u128(1));
+        let packet = |sequence: u32, code: u8| {
+            let mut packet = sequence.to_le_bytes().to_vec();
+            packet.push(code);
+            packet.into_boxed_slice()
+        };
+        let codes = |ready: &mut Vec<(PeerId, Packet)>| {
+            ready.drain(..).map(|(_, packet)| packet[0]).collect::<Vec<_>>()
+        };
+        let mut order = PacketOrder::default();
+        let mut ready = Vec::new();
+
+        order.receive(peer, packet(0, 10), &mut ready);
+        assert_eq!(codes(&mut ready), vec![10]);
+        // held until the packet before it arrives
+        order.receive(peer, packet(2, 12), &mut ready);
+        assert!(ready.is_empty());
+        order.receive(peer, packet(1, 11), &mut ready);
+        assert_eq!(codes(&mut ready), vec![11, 12]);
+        // duplicates are dropped
+        order.receive(peer, packet(1, 11), &mut ready);
+        order.receive(peer, packet(2, 12), &mut ready);
+        assert!(ready.is_empty());
+
+        // packet 3 never arrives
+        for sequence in 4..5 + REORDER_LIMIT as u32 {
+            order.receive(peer, packet(sequence, 0), &mut ready);
+        }
+        assert_eq!(ready.len(), REORDER_LIMIT + 1);
+
+        // a peer that rejoins starts over
+        order.forget(&peer);
+        ready.clear();
+        order.receive(peer, packet(0, 10), &mut ready);
+        assert_eq!(codes(&mut ready), vec![10]);
+    }
+}
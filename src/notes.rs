@@ -0,0 +1,135 @@
+//! Private notes on other players, such as "always hoards wilds".
+//!
+//! Notes are kept on this device for the active profile, under the player id each player sends
+//! along with their name, so they come back whenever we play the same person again. Nobody else
+//! ever sees them.
+
+use crate::{
+    profile::{Profile, Profiles},
+    settings::Settings,
+    storage::Storage,
+    toast::Toast,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// Longest allowed note.
+pub const NOTE_MAX_LEN: usize = 60;
+
+/// A note on another player.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct PlayerNote {
+    player_id: u64,
+    note: String,
+}
+
+/// Notes the active profile has written on other players.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerNotes {
+    notes: Vec<PlayerNote>,
+}
+
+impl PlayerNotes {
+    /// Loads the profile's notes, or none if they haven't written any.
+    pub fn load(storage: &Storage, profile: &Profile) -> Self {
+        storage.get(&profile.key("notes")).unwrap_or_default()
+    }
+
+    /// Saves the profile's notes.
+    pub fn save(&self, storage: &mut Storage, profile: &Profile) -> io::Result<()> {
+        storage.set(&profile.key("notes"), self)
+    }
+
+    /// Returns the note on a player, if there is one.
+    pub fn get(&self, player_id: u64) -> Option<&str> {
+        self.notes
+            .iter()
+            .find(|note| note.player_id == player_id)
+            .map(|note| note.note.as_str())
+    }
+
+    /// Sets the note on a player, or removes it if it's blank.
+    pub fn set(&mut self, player_id: u64, note: &str) {
+        self.notes.retain(|note| note.player_id != player_id);
+        let note = note.trim();
+        if !note.is_empty() {
+            self.notes.push(PlayerNote {
+                player_id,
+                note: note.chars().take(NOTE_MAX_LEN).collect(),
+            });
+        }
+    }
+}
+
+/// Loads the active profile's notes, and again whenever we switch profiles.
+///
+/// Loading doesn't count as a change, so the notes aren't saved straight back.
+fn load_notes(
+    mut notes: ResMut<PlayerNotes>,
+    mut active: Local<Option<u32>>,
+    profiles: Res<Profiles>,
+    storage: Res<Storage>,
+) {
+    let profile = profiles.active();
+    if *active == Some(profile.id) {
+        return;
+    }
+    *active = Some(profile.id);
+    *notes.bypass_change_detection() = PlayerNotes::load(&storage, profile);
+}
+
+/// Saves the notes whenever they change.
+fn save_notes(
+    notes: Res<PlayerNotes>,
+    profiles: Res<Profiles>,
+    settings: Res<Settings>,
+    mut storage: ResMut<Storage>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if !notes.is_changed() || notes.is_added() {
+        return;
+    }
+    if let Err(err) = notes.save(&mut storage, profiles.active()) {
+        error!("Error saving notes: {err}");
+        toasts.send(Toast(String::from(
+            settings.language.tr("Couldn't save notes"),
+        )));
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerNotes>().add_systems(
+            Update,
+            (load_notes, save_notes)
+                .chain()
+                .run_if(resource_exists::<Profiles>())
+                .run_if(resource_exists::<Storage>()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that notes are kept per player, trimmed and limited, and removed when cleared.
+    #[test]
+    fn test_notes() {
+        let mut notes = PlayerNotes::default();
+        notes.set(7, "  always hoards wilds ");
+        notes.set(8, "calls one early");
+        assert_eq!(notes.get(7), Some("always hoards wilds"));
+        assert_eq!(notes.get(9), None);
+
+        notes.set(8, &"x".repeat(NOTE_MAX_LEN + 10));
+        assert_eq!(notes.get(8).map(str::len), Some(NOTE_MAX_LEN));
+
+        notes.set(7, " ");
+        assert_eq!(notes.get(7), None);
+        assert_eq!(notes.get(8).map(str::len), Some(NOTE_MAX_LEN));
+    }
+}
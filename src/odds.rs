@@ -0,0 +1,258 @@
+//! Rough live estimate of each player's chance of winning, for casual games.
+//!
+//! Only what everyone can see is used: how many cards each player holds, the draw penalty
+//! waiting on the current player, and how many cards are left to draw. The rest of the round is
+//! played out many times with made up cards on a background task, so the estimate never holds up
+//! a frame. It's only shown when the host turns it on, and never in matches or tournaments, where
+//! it'd be a little too much help.
+
+use crate::{
+    deck::{Deck, MainPlayer},
+    info::{GameInfo, Opponents},
+    match_mode::MatchFormat,
+    rules::GameRules,
+    tournament::Tournament,
+    transport::GameTransport,
+    ScreenState,
+};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    tasks::AsyncComputeTaskPool,
+    utils::HashMap,
+};
+use bevy_matchbox::prelude::*;
+use rand::Rng;
+use std::sync::{Arc, Mutex};
+
+/// Number of times the rest of the round is played out for each estimate.
+const TRIALS: u32 = 2000;
+/// Most turns played out in a trial before giving up on it.
+const MAX_TURNS: u32 = 400;
+/// Chance that any one card in a hand can be played.
+const PLAYABLE_CHANCE: f64 = 0.3;
+/// Chance that a played card is a skip, reverse or draw two.
+const SKIP_CHANCE: f64 = 0.08;
+const REVERSE_CHANCE: f64 = 0.08;
+const DRAW_TWO_CHANCE: f64 = 0.08;
+
+/// What everyone can see of the round, with players in turn order starting with the current one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    pub hands: Vec<usize>,
+    pub pending_draw: u32,
+    pub deck_size: usize,
+    /// Whether a draw penalty can be passed on instead of drawn.
+    pub can_answer_penalty: bool,
+}
+
+impl Table {
+    /// Returns the chance of each player winning, in the same order as the hands.
+    pub fn estimate(&self, trials: u32, rng: &mut impl Rng) -> Vec<f32> {
+        let mut wins = vec![0; self.hands.len()];
+        let mut finished = 0;
+        for _ in 0..trials {
+            if let Some(winner) = self.play_out(rng) {
+                wins[winner] += 1;
+                finished += 1;
+            }
+        }
+        wins.into_iter()
+            .map(|w| w as f32 / finished.max(1) as f32)
+            .collect()
+    }
+
+    /// Plays out the rest of the round once, returning the winner.
+    fn play_out(&self, rng: &mut impl Rng) -> Option<usize> {
+        let count = self.hands.len();
+        if count == 0 {
+            return None;
+        }
+        let mut hands = self.hands.clone();
+        let mut pending = self.pending_draw;
+        let mut deck = self.deck_size;
+        let mut current = 0;
+        let mut forward = true;
+        let step = |index: usize, forward: bool| {
+            if forward {
+                (index + 1) % count
+            } else {
+                (index + count - 1) % count
+            }
+        };
+
+        for _ in 0..MAX_TURNS {
+            let playable_chance = 1.0 - (1.0 - PLAYABLE_CHANCE).powi(hands[current] as i32);
+            if pending > 0 {
+                if self.can_answer_penalty && rng.gen_bool(playable_chance * DRAW_TWO_CHANCE) {
+                    hands[current] -= 1;
+                    pending += 2;
+                } else {
+                    hands[current] += pending as usize;
+                    deck = deck.saturating_sub(pending as usize);
+                    pending = 0;
+                }
+            } else if hands[current] > 0 && rng.gen_bool(playable_chance) {
+                hands[current] -= 1;
+                let effect: f64 = rng.gen();
+                if effect < SKIP_CHANCE {
+                    current = step(current, forward);
+                } else if effect < SKIP_CHANCE + REVERSE_CHANCE {
+                    forward = !forward;
+                } else if effect < SKIP_CHANCE + REVERSE_CHANCE + DRAW_TWO_CHANCE {
+                    pending = 2;
+                }
+            } else if deck > 0 {
+                hands[current] += 1;
+                deck -= 1;
+            } else {
+                // the discard pile is shuffled back in when the deck runs out
+                deck = self.hands.iter().sum::<usize>().max(10);
+            }
+
+            if hands[current] == 0 {
+                return Some(current);
+            }
+            current = step(current, forward);
+        }
+        None
+    }
+}
+
+/// Latest estimate of each player's chance of winning.
+#[derive(Resource, Default)]
+pub struct WinOdds(pub HashMap<PeerId, f32>);
+
+/// Estimate being worked out on a background task.
+#[derive(Resource, Default)]
+struct OddsTask {
+    /// Filled in by the task once it's done.
+    result: Arc<Mutex<Option<HashMap<PeerId, f32>>>>,
+    running: bool,
+    /// Whether the round changed while the task was running.
+    stale: bool,
+}
+
+/// Returns `true` if the odds are shown in this game.
+///
+/// They're a house rule for casual games, and are never shown in a match or a tournament.
+pub fn odds_shown(
+    rules: Res<GameRules>,
+    format: Res<MatchFormat>,
+    tournament: Option<Res<Tournament>>,
+) -> bool {
+    rules.win_odds && !format.is_match() && tournament.is_none()
+}
+
+/// Starts a new estimate when the round changes, once the last one is done.
+fn start_estimate(
+    mut task: ResMut<OddsTask>,
+    mut socket: ResMut<GameTransport>,
+    game_info: Res<GameInfo>,
+    opponents: Res<Opponents>,
+    main_player: Res<MainPlayer>,
+    deck: Res<Deck>,
+    rules: Res<GameRules>,
+) {
+    if game_info.is_changed() || opponents.is_changed() || main_player.is_changed() {
+        task.stale = true;
+    }
+    if task.running || !task.stale {
+        return;
+    }
+    let Some(current) = game_info.current_player else { return; };
+
+    let mut players = vec![current];
+    players.extend(game_info.upcoming_players(game_info.order.len().saturating_sub(1)));
+    let own_id = socket.id();
+    let hands = players
+        .iter()
+        .map(|player| {
+            if Some(*player) == own_id {
+                main_player.len()
+            } else {
+                opponents
+                    .0
+                    .iter()
+                    .find(|opponent| opponent.id == *player)
+                    .map_or(0, |opponent| opponent.card_count)
+            }
+        })
+        .collect();
+    let table = Table {
+        hands,
+        pending_draw: game_info.pending_draw,
+        deck_size: deck.cards.len(),
+        can_answer_penalty: rules.draw_penalties_pending(),
+    };
+
+    task.running = true;
+    task.stale = false;
+    let result = task.result.clone();
+    AsyncComputeTaskPool::get()
+        .spawn(async move {
+            let odds = table.estimate(TRIALS, &mut rand::thread_rng());
+            let odds = players.into_iter().zip(odds).collect();
+            *result.lock().unwrap() = Some(odds);
+        })
+        .detach();
+}
+
+/// Picks up the estimate once the background task is done.
+fn finish_estimate(mut task: ResMut<OddsTask>, mut odds: ResMut<WinOdds>) {
+    let Some(result) = task.result.lock().unwrap().take() else { return; };
+    odds.0 = result;
+    task.running = false;
+}
+
+/// Forgets the odds from the last round.
+fn reset_odds(mut odds: ResMut<WinOdds>, mut task: ResMut<OddsTask>) {
+    odds.0.clear();
+    task.stale = true;
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WinOdds>()
+            .init_resource::<OddsTask>()
+            .add_systems(OnEnter(ScreenState::Game), reset_odds)
+            .add_systems(
+                Update,
+                (start_estimate, finish_estimate)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(odds_shown)
+                    .run_if(resource_exists::<GameTransport>()),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Ensures that the odds add up, and favor the player closest to going out.
+    #[test]
+    fn test_estimate() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let table = Table {
+            hands: vec![1, 7, 7],
+            pending_draw: 0,
+            deck_size: 40,
+            can_answer_penalty: false,
+        };
+        let odds = table.estimate(500, &mut rng);
+        assert!((odds.iter().sum::<f32>() - 1.0).abs() < 0.01);
+        assert!(odds[0] > odds[1] && odds[0] > odds[2]);
+
+        // a big penalty waiting on the leader hands the lead over
+        let penalized = Table {
+            hands: vec![1, 2],
+            pending_draw: 8,
+            ..table
+        };
+        let odds = penalized.estimate(500, &mut rng);
+        assert!(odds[1] > odds[0]);
+    }
+}
@@ -0,0 +1,42 @@
+//! Integration with the window or browser tab the game runs in.
+//!
+//! Sets the window icon for native, and the favicon and page title for WASM. Also tells the
+//! date, which comes from the browser on WASM, and reads text pasted from the clipboard.
+
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+/// Text pasted from the clipboard, with Ctrl+V or Cmd+V on native, or into the page on WASM.
+#[derive(Event)]
+pub struct Paste(pub String);
+
+/// Returns the number of days since the Unix epoch in UTC, so everyone shares the same day.
+pub fn today() -> u32 {
+    #[cfg(target_arch = "wasm32")]
+    let millis = js_sys::Date::now();
+    #[cfg(not(target_arch = "wasm32"))]
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|since| since.as_millis() as f64)
+        .unwrap_or_default();
+    (millis / 86_400_000.0) as u32
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Paste>();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, (native::set_window_icon, native::read_paste));
+
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Startup, (wasm::set_favicon, wasm::listen_for_paste))
+            .add_systems(Update, (wasm::update_document_title, wasm::forward_paste));
+    }
+}
@@ -0,0 +1,63 @@
+//! Native window integration.
+
+use super::Paste;
+use bevy::{
+    prelude::*,
+    render::texture::{CompressedImageFormats, ImageSampler, ImageType},
+    window::PrimaryWindow,
+    winit::WinitWindows,
+};
+use winit::window::Icon;
+
+/// The logo, embedded so the icon is set before any assets load.
+const ICON: &[u8] = include_bytes!("../../assets/textures/icons/logo.png");
+
+/// Sets the window icon once the window has been created.
+pub fn set_window_icon(
+    window: Query<Entity, With<PrimaryWindow>>,
+    winit_windows: NonSend<WinitWindows>,
+    mut done: Local<bool>,
+) {
+    if *done {
+        return;
+    }
+    let Ok(entity) = window.get_single() else { return; };
+    let Some(window) = winit_windows.get_window(entity) else { return; };
+    *done = true;
+
+    let image = match Image::from_buffer(
+        ICON,
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+    ) {
+        Ok(image) => image,
+        Err(e) => {
+            error!("Failed to decode window icon: {e:?}");
+            return;
+        }
+    };
+    let size = image.size();
+    match Icon::from_rgba(image.data, size.x as u32, size.y as u32) {
+        Ok(icon) => window.set_window_icon(Some(icon)),
+        Err(e) => error!("Failed to create window icon: {e:?}"),
+    }
+}
+
+/// Reads the clipboard when the paste shortcut is pressed.
+pub fn read_paste(keys: Res<Input<KeyCode>>, mut pastes: EventWriter<Paste>) {
+    let modifiers = [
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ];
+    if !keys.any_pressed(modifiers) || !keys.just_pressed(KeyCode::V) {
+        return;
+    }
+    match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+        Ok(text) => pastes.send(Paste(text)),
+        Err(e) => warn!("Failed to read the clipboard: {e}"),
+    }
+}
@@ -0,0 +1,109 @@
+//! Browser tab integration for WebAssembly.
+
+use super::Paste;
+use crate::{info::GameInfo, network::ServerState, transport::GameTransport, ScreenState};
+use bevy::prelude::*;
+use bevy_matchbox::prelude::*;
+use std::sync::Mutex;
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::ClipboardEvent;
+
+/// Name of the game, shown in the title.
+const GAME_TITLE: &str = "crazy 7s";
+/// Path to the favicon, relative to the page.
+const FAVICON_PATH: &str = "assets/textures/icons/logo.png";
+
+/// Text pasted into the page, waiting to be sent as [`Paste`] events.
+static PASTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Returns the title describing the current game state.
+///
+/// This includes the room code, and a marker when it's our turn.
+fn current_title(
+    server_state: &ServerState,
+    screen_state: &ScreenState,
+    game_info: &GameInfo,
+    own_id: Option<PeerId>,
+) -> String {
+    let code = match server_state {
+        ServerState::Server(code) | ServerState::Client(code) => code,
+        ServerState::None => return GAME_TITLE.to_string(),
+    };
+    let your_turn = *screen_state == ScreenState::Game
+        && own_id.is_some()
+        && game_info.current_player == own_id;
+    if your_turn {
+        format!("(Your turn) Room {code} - {GAME_TITLE}")
+    } else {
+        format!("Room {code} - {GAME_TITLE}")
+    }
+}
+
+// get reference to the web document
+fn document() -> web_sys::Document {
+    web_sys::window()
+        .expect("No window")
+        .document()
+        .expect("No document")
+}
+
+/// Points the page favicon at the logo, adding a link element if the page doesn't have one.
+pub fn set_favicon() {
+    let document = document();
+    let link = match document.query_selector("link[rel~='icon']") {
+        Ok(Some(link)) => link,
+        _ => {
+            let Ok(link) = document.create_element("link") else { return; };
+            let Some(head) = document.head() else { return; };
+            if head.append_child(&link).is_err() {
+                return;
+            }
+            link
+        }
+    };
+    let _ = link.set_attribute("rel", "icon");
+    let _ = link.set_attribute("href", FAVICON_PATH);
+}
+
+/// Keeps the page title in sync with the room and turn.
+pub fn update_document_title(
+    server_state: Res<State<ServerState>>,
+    screen_state: Res<State<ScreenState>>,
+    game_info: Res<GameInfo>,
+    mut socket: Option<ResMut<GameTransport>>,
+    mut shown: Local<String>,
+) {
+    let own_id = socket.as_mut().and_then(|socket| socket.id());
+    let title = current_title(server_state.get(), screen_state.get(), &game_info, own_id);
+    if *shown != title {
+        document().set_title(&title);
+        *shown = title;
+    }
+}
+
+/// Listens for text pasted into the page, since the browser only lets the clipboard be read
+/// while a paste is being handled.
+pub fn listen_for_paste() {
+    let on_paste = Closure::<dyn FnMut(ClipboardEvent)>::new(|event: ClipboardEvent| {
+        let text = event
+            .clipboard_data()
+            .and_then(|data| data.get_data("text").ok());
+        if let Some(text) = text {
+            PASTED.lock().unwrap().push(text);
+        }
+    });
+    let added = document()
+        .add_event_listener_with_callback("paste", on_paste.as_ref().unchecked_ref())
+        .is_ok();
+    if added {
+        // the listener is kept for as long as the page is open
+        on_paste.forget();
+    }
+}
+
+/// Sends the text pasted since the last frame.
+pub fn forward_paste(mut pastes: EventWriter<Paste>) {
+    for text in PASTED.lock().unwrap().drain(..) {
+        pastes.send(Paste(text));
+    }
+}
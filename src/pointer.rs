@@ -0,0 +1,196 @@
+//! Mouse and touch input, turned into clicks and long presses.
+//!
+//! A click is a press that starts and ends on the same button, hand card or pile, so dragging
+//! off something before letting go cancels it. Holding a press for a while is a long press,
+//! which inspects things instead of clicking them.
+
+use crate::{MainCamera, WorldCoords};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    ui::UiSystem,
+    window::PrimaryWindow,
+};
+
+/// How long (in seconds) a press is held before it becomes a long press.
+const LONG_PRESS_TIME: f32 = 0.5;
+
+/// The mouse, or the first finger on a touch screen.
+#[derive(Resource, Default)]
+pub struct Pointer {
+    /// Position in the window, if the pointer is over it.
+    pub position: Option<Vec2>,
+    pub pressed: bool,
+    pub just_pressed: bool,
+    pub just_released: bool,
+}
+
+/// Object in the world that can be clicked, with the size of the area that reacts.
+#[derive(Component)]
+pub struct Clickable(pub Vec2);
+
+/// Sent when a button or clickable object is clicked.
+#[derive(Event)]
+pub struct Click(pub Entity);
+
+/// Sent when a button or clickable object is pressed and held.
+#[derive(Event)]
+pub struct LongPress(pub Entity);
+
+/// What the current press started on.
+#[derive(Resource, Default)]
+struct Press {
+    targets: Vec<Entity>,
+    held: f32,
+    long_pressed: bool,
+}
+
+/// Returns the clickable object at a point in the world, picking the top one if they overlap.
+pub fn topmost<'a>(
+    objects: impl Iterator<Item = (Entity, &'a Transform, &'a Clickable)>,
+    point: Vec2,
+) -> Option<Entity> {
+    objects
+        .filter(|(_, transform, Clickable(size))| {
+            Rect::from_center_size(transform.translation.truncate(), *size).contains(point)
+        })
+        .max_by(|(_, a, _), (_, b, _)| a.translation.z.total_cmp(&b.translation.z))
+        .map(|(entity, ..)| entity)
+}
+
+/// Reads the mouse and touch screen into the pointer.
+fn read_pointer(
+    window: Query<&Window, With<PrimaryWindow>>,
+    mouse: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    mut pointer: ResMut<Pointer>,
+) {
+    // a finger that was just lifted still says where the press ended
+    pointer.position = touches
+        .first_pressed_position()
+        .or_else(|| touches.iter_just_released().next().map(|t| t.position()))
+        .or_else(|| window.get_single().ok().and_then(Window::cursor_position));
+    pointer.pressed = mouse.pressed(MouseButton::Left) || touches.iter().next().is_some();
+    pointer.just_pressed = mouse.just_pressed(MouseButton::Left) || touches.any_just_pressed();
+    pointer.just_released = mouse.just_released(MouseButton::Left) || touches.any_just_released();
+}
+
+/// Tracks the pointer position in world space.
+fn track_world_position(
+    pointer: Res<Pointer>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut coords: ResMut<WorldCoords>,
+) {
+    let Ok((camera, camera_transform)) = camera.get_single() else { return; };
+
+    // convert pointer position into world coordinates and truncate to get rid of z
+    if let Some(world_position) = pointer
+        .position
+        .and_then(|position| camera.viewport_to_world(camera_transform, position))
+        .map(|ray| ray.origin.truncate())
+    {
+        coords.0 = world_position;
+    }
+}
+
+/// Sends clicks and long presses for whatever the pointer pressed.
+///
+/// UI nodes are pressed through their [`Interaction`], and take priority over clickable
+/// objects in the world.
+fn detect_clicks(
+    pointer: Res<Pointer>,
+    mut press: ResMut<Press>,
+    nodes: Query<(Entity, &Interaction, &Node, &GlobalTransform)>,
+    objects: Query<(Entity, &Transform, &Clickable)>,
+    coords: Res<WorldCoords>,
+    ui_scale: Res<UiScale>,
+    time: Res<Time>,
+    mut clicks: EventWriter<Click>,
+    mut long_presses: EventWriter<LongPress>,
+) {
+    let is_over = |entity: Entity| match nodes.get(entity) {
+        Ok((_, _, node, transform)) => pointer.position.map_or(false, |position| {
+            node.logical_rect(transform)
+                .contains(position / ui_scale.0 as f32)
+        }),
+        Err(_) => topmost(objects.iter(), coords.0) == Some(entity),
+    };
+
+    if pointer.just_pressed {
+        let mut targets: Vec<Entity> = nodes
+            .iter()
+            .filter(|(_, interaction, ..)| **interaction == Interaction::Pressed)
+            .map(|(entity, ..)| entity)
+            .collect();
+        if targets.is_empty() {
+            targets.extend(topmost(objects.iter(), coords.0));
+        }
+        *press = Press {
+            targets,
+            held: 0.0,
+            long_pressed: false,
+        };
+    } else if pointer.pressed && !press.long_pressed && !press.targets.is_empty() {
+        press.held += time.delta_seconds();
+        if press.held >= LONG_PRESS_TIME {
+            press.long_pressed = true;
+            for &entity in press.targets.iter().filter(|entity| is_over(**entity)) {
+                long_presses.send(LongPress(entity));
+            }
+        }
+    }
+
+    if pointer.just_released {
+        // a long press was an inspection, not a click
+        if !press.long_pressed {
+            for &entity in press.targets.iter().filter(|entity| is_over(**entity)) {
+                clicks.send(Click(entity));
+            }
+        }
+        press.targets.clear();
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pointer>()
+            .init_resource::<Press>()
+            .init_resource::<WorldCoords>()
+            .add_event::<Click>()
+            .add_event::<LongPress>()
+            .add_systems(
+                PreUpdate,
+                (read_pointer, track_world_position, detect_clicks)
+                    .chain()
+                    .after(UiSystem::Focus),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that the top object under a point is picked, and nothing is picked off to the side.
+    #[test]
+    fn test_topmost() {
+        let size = Clickable(Vec2::new(100.0, 140.0));
+        let below = Transform::from_xyz(0.0, 0.0, 0.01);
+        let above = Transform::from_xyz(60.0, 0.0, 0.02);
+        let objects = [
+            (Entity::from_raw(1), &below, &size),
+            (Entity::from_raw(2), &above, &size),
+        ];
+
+        assert_eq!(
+            topmost(objects.into_iter(), Vec2::new(-20.0, 0.0)),
+            Some(Entity::from_raw(1))
+        );
+        assert_eq!(
+            topmost(objects.into_iter(), Vec2::new(30.0, 0.0)),
+            Some(Entity::from_raw(2))
+        );
+        assert_eq!(topmost(objects.into_iter(), Vec2::new(0.0, 100.0)), None);
+    }
+}
@@ -0,0 +1,263 @@
+//! Local player profiles, so several people can share one computer.
+//!
+//! Each profile has its own name, avatar and stats. The active profile's name is used as the
+//! username in [`Settings`], and per-player data like the last game is saved under keys
+//! belonging to the active profile.
+
+use crate::{settings::Settings, storage::Storage, toast::Toast};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// Most profiles that can be created.
+pub const MAX_PROFILES: usize = 6;
+
+/// Colors that can be picked as a profile's avatar.
+pub const AVATAR_COLORS: [Color; 6] = [
+    Color::rgb(0.85, 0.25, 0.25),
+    Color::rgb(0.95, 0.6, 0.15),
+    Color::rgb(0.9, 0.8, 0.2),
+    Color::rgb(0.3, 0.7, 0.35),
+    Color::rgb(0.25, 0.5, 0.85),
+    Color::rgb(0.6, 0.35, 0.8),
+];
+
+/// Games played and won by a profile, or during a session.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub games: u32,
+    pub wins: u32,
+    /// Games won in a row if positive, or lost in a row if negative.
+    #[serde(default)]
+    pub streak: i32,
+    /// Most games won in a row.
+    #[serde(default)]
+    pub best_streak: u32,
+}
+
+impl Stats {
+    /// Counts a finished game.
+    pub fn record(&mut self, won: bool) {
+        self.games += 1;
+        if won {
+            self.wins += 1;
+            self.streak = self.streak.max(0) + 1;
+            self.best_streak = self.best_streak.max(self.streak as u32);
+        } else {
+            self.streak = self.streak.min(0) - 1;
+        }
+    }
+}
+
+/// A local player.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Profile {
+    /// Number that stays the same for the life of the profile, used in its storage keys.
+    pub id: u32,
+    /// Random number other players know the profile by from one game to the next, which their
+    /// notes about us are kept under.
+    #[serde(default = "rand::random")]
+    pub player_id: u64,
+    pub name: String,
+    /// Index into [`AVATAR_COLORS`].
+    pub avatar: usize,
+    #[serde(default)]
+    pub stats: Stats,
+}
+
+impl Profile {
+    /// Returns the storage key for this profile's copy of `key`.
+    ///
+    /// The first profile uses the key as is, so data saved before profiles existed carries over.
+    pub fn key(&self, key: &str) -> String {
+        if self.id == 0 {
+            String::from(key)
+        } else {
+            format!("{key}_{}", self.id)
+        }
+    }
+
+    /// Color of the profile's avatar.
+    pub fn color(&self) -> Color {
+        AVATAR_COLORS[self.avatar % AVATAR_COLORS.len()]
+    }
+
+    /// Letter shown on the profile's avatar.
+    pub fn initial(&self) -> String {
+        self.name
+            .chars()
+            .next()
+            .map_or(String::from("?"), |c| c.to_uppercase().collect())
+    }
+}
+
+/// All local profiles, and which one is playing.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Profiles {
+    pub list: Vec<Profile>,
+    pub active: usize,
+}
+
+impl Profiles {
+    /// A single new profile with the given username.
+    pub fn new(username: &str) -> Self {
+        Self {
+            list: vec![Profile {
+                id: 0,
+                player_id: rand::random(),
+                name: String::from(username),
+                avatar: 0,
+                stats: Stats::default(),
+            }],
+            active: 0,
+        }
+    }
+
+    /// Loads the saved profiles.
+    ///
+    /// If there are none yet, a single profile is created with the given username.
+    pub fn load(storage: &Storage, username: &str) -> Self {
+        match storage.get::<Profiles>("profiles") {
+            Ok(profiles) if !profiles.list.is_empty() => Self {
+                active: profiles.active.min(profiles.list.len() - 1),
+                ..profiles
+            },
+            _ => Self::new(username),
+        }
+    }
+
+    /// Saves the profiles to storage.
+    pub fn save(&self, storage: &mut Storage) -> io::Result<()> {
+        storage.set("profiles", self)
+    }
+
+    /// The profile that's playing.
+    pub fn active(&self) -> &Profile {
+        &self.list[self.active]
+    }
+
+    /// The profile that's playing.
+    pub fn active_mut(&mut self) -> &mut Profile {
+        &mut self.list[self.active]
+    }
+
+    /// Switches to the next profile.
+    pub fn switch(&mut self) {
+        self.active = (self.active + 1) % self.list.len();
+    }
+
+    /// Returns `true` if another profile can be created.
+    pub fn can_add(&self) -> bool {
+        self.list.len() < MAX_PROFILES
+    }
+
+    /// Creates a new profile and switches to it.
+    pub fn add(&mut self, name: String) {
+        if !self.can_add() {
+            return;
+        }
+        let id = self.list.iter().map(|p| p.id + 1).max().unwrap_or(0);
+        self.list.push(Profile {
+            id,
+            player_id: rand::random(),
+            name,
+            avatar: self.list.len() % AVATAR_COLORS.len(),
+            stats: Stats::default(),
+        });
+        self.active = self.list.len() - 1;
+    }
+}
+
+/// Keeps the username in the settings and the active profile's name the same.
+///
+/// Switching profiles changes the username, and editing the username renames the profile.
+fn sync_username(mut profiles: ResMut<Profiles>, mut settings: ResMut<Settings>) {
+    if profiles.is_changed() {
+        if settings.username != profiles.active().name {
+            settings.username = profiles.active().name.clone();
+        }
+    } else if settings.is_changed() && settings.username != profiles.active().name {
+        profiles.active_mut().name = settings.username.clone();
+    }
+}
+
+/// Saves the profiles whenever they change.
+fn save_profiles(
+    profiles: Res<Profiles>,
+    settings: Res<Settings>,
+    mut storage: ResMut<Storage>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if !profiles.is_changed() || profiles.is_added() {
+        return;
+    }
+    if let Err(err) = profiles.save(&mut storage) {
+        error!("Error saving profiles: {err}");
+        toasts.send(Toast(String::from(
+            settings.language.tr("Couldn't save profiles"),
+        )));
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (sync_username, save_profiles)
+                .chain()
+                .run_if(resource_exists::<Profiles>()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that new profiles get their own storage keys, and that profiles are limited.
+    #[test]
+    fn test_add_profiles() {
+        let mut profiles = Profiles {
+            list: vec![Profile {
+                id: 0,
+                player_id: 1,
+                name: String::from("Alice"),
+                avatar: 0,
+                stats: Stats::default(),
+            }],
+            active: 0,
+        };
+        assert_eq!(profiles.active().key("last_game"), "last_game");
+
+        profiles.add(String::from("Bob"));
+        assert_eq!(profiles.active().name, "Bob");
+        assert_eq!(profiles.active().key("last_game"), "last_game_1");
+
+        for _ in 0..MAX_PROFILES {
+            profiles.add(String::from("Carol"));
+        }
+        assert_eq!(profiles.list.len(), MAX_PROFILES);
+
+        profiles.active = profiles.list.len() - 1;
+        profiles.switch();
+        assert_eq!(profiles.active().name, "Alice");
+    }
+
+    /// Ensures that streaks count wins and losses in a row, and the best streak is kept.
+    #[test]
+    fn test_streaks() {
+        let mut stats = Stats::default();
+        for won in [true, true, true, false, false, true] {
+            stats.record(won);
+        }
+        assert_eq!(stats.games, 6);
+        assert_eq!(stats.wins, 4);
+        assert_eq!(stats.streak, 1);
+        assert_eq!(stats.best_streak, 3);
+
+        stats.record(false);
+        assert_eq!(stats.streak, -1);
+    }
+}
@@ -0,0 +1,142 @@
+//! Recovery after the app has been suspended.
+//!
+//! Browsers stop running background tabs, so when the tab is shown again the game may have
+//! missed turns and any audio may have been stopped.
+
+use crate::{
+    despawn_screen,
+    network::{RequestSync, Synced},
+    transport::GameTransport,
+    ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Time between frames (in seconds) after which we assume the app was suspended.
+const SUSPEND_THRESHOLD: f32 = 1.0;
+/// How long to wait for the host to answer before hiding the catching up indicator.
+const CATCH_UP_TIMEOUT: f32 = 3.0;
+/// Rotation speed of the spinner, in radians per second.
+const SPINNER_SPEED: f32 = 6.0;
+
+/// Indicates that we're waiting for the host to send the current game state.
+#[derive(Resource)]
+struct CatchingUp(Timer);
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+struct OnScreen;
+
+/// Spinning square shown while catching up.
+#[derive(Component)]
+struct Spinner;
+
+/// Detects long gaps between frames, restarting audio and resyncing the game state.
+fn detect_resume(
+    time: Res<Time<Real>>,
+    screen_state: Res<State<ScreenState>>,
+    socket: Option<Res<GameTransport>>,
+    sinks: Query<&AudioSink>,
+    mut sync_events: EventWriter<RequestSync>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    if time.delta_seconds() < SUSPEND_THRESHOLD {
+        return;
+    }
+    info!("Resumed after {:.1}s", time.delta_seconds());
+
+    // browsers may stop audio in background tabs
+    for sink in sinks.iter() {
+        sink.play();
+    }
+
+    // any queued packets are handled as usual, but ask the host for the turn state in case
+    // something was missed
+    if *screen_state.get() != ScreenState::Game || socket.is_none() {
+        return;
+    }
+    sync_events.send(RequestSync);
+    commands.insert_resource(CatchingUp(Timer::from_seconds(
+        CATCH_UP_TIMEOUT,
+        TimerMode::Once,
+    )));
+
+    // draw catching up indicator
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(20.0),
+                    left: Val::Px(20.0),
+                    padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                    column_gap: Val::Px(12.0),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(16.0),
+                        height: Val::Px(16.0),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    ..default()
+                },
+                Spinner,
+            ));
+            parent.spawn(TextBundle::from_section(
+                "catching up…",
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-Black.ttf"),
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+/// Rotates the catching up spinner.
+fn animate_spinner(mut spinners: Query<&mut Transform, With<Spinner>>, time: Res<Time>) {
+    for mut transform in &mut spinners {
+        transform.rotate_z(-SPINNER_SPEED * time.delta_seconds());
+    }
+}
+
+/// Hides the catching up indicator once the host answers or the request times out.
+fn finish_catching_up(
+    mut synced_events: EventReader<Synced>,
+    mut catching_up: ResMut<CatchingUp>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let synced = synced_events.read().next().is_some();
+    if synced || catching_up.0.tick(time.delta()).finished() {
+        commands.remove_resource::<CatchingUp>();
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (detect_resume, animate_spinner))
+            .add_systems(
+                Update,
+                finish_catching_up.run_if(resource_exists::<CatchingUp>()),
+            )
+            .add_systems(
+                Update,
+                despawn_screen::<OnScreen>.run_if(resource_removed::<CatchingUp>()),
+            )
+            .add_systems(OnExit(ScreenState::Game), despawn_screen::<OnScreen>);
+    }
+}
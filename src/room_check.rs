@@ -0,0 +1,97 @@
+//! Making sure a new room code isn't already taken before hosting it.
+//!
+//! Codes are picked at random, so two hosts could end up in the same room and mix their lobbies.
+//! Before a host is sent to their lobby, a throwaway socket joins the room on the server. If
+//! anyone answers, the code is in use and another one is tried.
+
+use crate::{menu::MenuState, network::ServerState, room_code, settings::Settings};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use bevy_matchbox::prelude::*;
+
+/// Seconds to wait for someone in the room before taking the code as free.
+const CHECK_TIME: f32 = 3.0;
+/// Codes to try before hosting the last one anyway.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// A code being checked before we host it.
+#[derive(Resource)]
+pub struct RoomCheck {
+    socket: MatchboxSocket<MultipleChannels>,
+    code: String,
+    /// Seconds spent waiting on the current code.
+    elapsed: f32,
+    attempts: u32,
+}
+
+impl RoomCheck {
+    /// Starts checking a new random code in the style from the settings.
+    pub fn start(commands: &mut Commands, settings: &Settings) {
+        let code = settings.room_codes.generate(&mut rand::thread_rng());
+        commands.insert_resource(Self {
+            socket: probe(&settings.server_url, &code),
+            code,
+            elapsed: 0.0,
+            attempts: 1,
+        });
+    }
+}
+
+/// Joins a room with the same channels as the game, so the peers in it connect to us.
+fn probe(server_url: &str, code: &str) -> MatchboxSocket<MultipleChannels> {
+    let builder = WebRtcSocketBuilder::new(room_code::room_url(server_url, code))
+        .add_reliable_channel()
+        .add_unreliable_channel();
+    MatchboxSocket::from(builder)
+}
+
+/// Tries another code if someone is in the room, or hosts it once no one has shown up in time.
+fn check_room(
+    mut check: ResMut<RoomCheck>,
+    mut server_state: ResMut<NextState<ServerState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let taken = match check.socket.try_update_peers() {
+        Ok(peers) => peers
+            .iter()
+            .any(|(_, state)| *state == PeerState::Connected),
+        Err(e) => {
+            error!("Error checking room code: {e:?}");
+            false
+        }
+    };
+    // whatever the room sends us isn't meant for us
+    for channel in 0..2 {
+        check.socket.channel(channel).receive();
+    }
+
+    if taken && check.attempts < MAX_ATTEMPTS {
+        info!("Room {} is taken, trying another code", check.code);
+        let code = settings.room_codes.generate(&mut rand::thread_rng());
+        check.socket = probe(&settings.server_url, &code);
+        check.code = code;
+        check.elapsed = 0.0;
+        check.attempts += 1;
+        return;
+    }
+
+    check.elapsed += time.delta_seconds();
+    if taken || check.elapsed >= CHECK_TIME {
+        server_state.set(ServerState::Server(check.code.clone()));
+        menu_state.set(MenuState::Lobby);
+        commands.remove_resource::<RoomCheck>();
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            check_room.run_if(resource_exists::<RoomCheck>()),
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Room codes, which players share to meet in the same room on the server.
+//!
+//! Hosts give their room either six letters and digits, leaving out the ones that are easy to mix
+//! up, or three short words, which are easier to read out loud. Codes are kept in uppercase, and
+//! whatever is typed or pasted is put in the same form so it matches.
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+/// Number of characters in a letter code.
+pub const LETTERS_LEN: usize = 6;
+
+/// Longest code that can be entered, which fits three of the longest words.
+pub const MAX_LEN: usize = 17;
+
+/// Characters letter codes are made from, without 0, 1, I, L and O.
+pub const ALPHABET: &str = "ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+/// Words that word codes are made from, all short and hard to mishear.
+const WORDS: [&str; 64] = [
+    "ACORN", "AMBER", "APPLE", "BADGE", "BEACH", "BERRY", "BLOOM", "BRICK", "CAMEL", "CANDY",
+    "CEDAR", "CHALK", "CLOUD", "COMET", "CORAL", "DAISY", "DRUM", "EAGLE", "EMBER", "FERN",
+    "FLUTE", "FROST", "GHOST", "GRAPE", "HONEY", "IGLOO", "JELLY", "KAYAK", "KOALA", "LEMON",
+    "LLAMA", "MANGO", "MAPLE", "MOOSE", "NINJA", "OCEAN", "OLIVE", "OTTER", "PANDA", "PEARL",
+    "PIANO", "PLUM", "QUILT", "RAVEN", "RIVER", "ROBIN", "SALSA", "SHELL", "SKATE", "SNAIL",
+    "SPOON", "STONE", "SWAN", "TIGER", "TOAST", "TULIP", "VIOLA", "WAGON", "WHALE", "WOLF",
+    "YACHT", "YOGA", "ZEBRA", "ZINC",
+];
+
+/// Number of words in a word code.
+const WORD_COUNT: usize = 3;
+
+/// Kind of code a host gives their room.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodeStyle {
+    #[default]
+    Letters,
+    Words,
+}
+
+impl CodeStyle {
+    /// All code styles, in the order they're picked.
+    pub const ALL: [CodeStyle; 2] = [CodeStyle::Letters, CodeStyle::Words];
+
+    /// Name shown in the settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CodeStyle::Letters => "Letters",
+            CodeStyle::Words => "Words",
+        }
+    }
+
+    /// Returns the code style after this one.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|s| s == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Makes a new random code in this style.
+    pub fn generate(&self, rng: &mut impl Rng) -> String {
+        match self {
+            CodeStyle::Letters => (0..LETTERS_LEN)
+                .map(|_| *ALPHABET.as_bytes().choose(rng).unwrap() as char)
+                .collect(),
+            CodeStyle::Words => (0..WORD_COUNT)
+                .map(|_| *WORDS.choose(rng).unwrap())
+                .collect::<Vec<_>>()
+                .join("-"),
+        }
+    }
+}
+
+/// Address of the room with the given code on the matchbox server.
+pub fn room_url(server_url: &str, code: &str) -> String {
+    format!("{server_url}/v1_{code}")
+}
+
+/// Returns the character a typed one stands for in a code, or `None` if it can't be in one.
+///
+/// Letters are made uppercase, and spaces separate words just like dashes.
+pub fn code_char(c: char) -> Option<char> {
+    match c {
+        c if c.is_ascii_alphanumeric() => Some(c.to_ascii_uppercase()),
+        '-' | ' ' | '_' => Some('-'),
+        _ => None,
+    }
+}
+
+/// Adds a typed character to the end of a code, if it can go there.
+pub fn push_char(code: &mut String, c: char) {
+    let Some(c) = code_char(c) else { return; };
+    // words are separated by a single dash
+    if c == '-' && code.chars().last().is_none_or(|last| last == '-') {
+        return;
+    }
+    if code.len() < MAX_LEN {
+        code.push(c);
+    }
+}
+
+/// Returns `true` if the code is six letters and digits, or three words.
+pub fn is_valid(code: &str) -> bool {
+    let letters = code.len() == LETTERS_LEN && code.chars().all(|c| ALPHABET.contains(c));
+    let words = code.split('-').count() == WORD_COUNT
+        && code
+            .split('-')
+            .all(|word| !word.is_empty() && word.chars().all(|c| c.is_ascii_uppercase()));
+    letters || words
+}
+
+/// Finds a room code in pasted text, which may have other words around it, like "Room K7P2QX".
+pub fn from_pasted(text: &str) -> Option<String> {
+    let normalize = |text: &str| {
+        let mut code = String::new();
+        for c in text.trim().chars() {
+            push_char(&mut code, c);
+        }
+        code.trim_end_matches('-').to_owned()
+    };
+    let whole = normalize(text);
+    if is_valid(&whole) {
+        return Some(whole);
+    }
+    text.split_whitespace()
+        .map(normalize)
+        .find(|code| is_valid(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_codes_are_valid() {
+        let mut rng = rand::thread_rng();
+        for style in CodeStyle::ALL {
+            for _ in 0..100 {
+                let code = style.generate(&mut rng);
+                assert!(is_valid(&code), "{code}");
+                assert!(code.len() <= MAX_LEN, "{code}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_typing_codes() {
+        let mut code = String::new();
+        for c in "k7p 2qx".chars() {
+            push_char(&mut code, c);
+        }
+        assert_eq!(code, "K7P-2QX");
+        assert!(!is_valid(&code));
+
+        let mut code = String::new();
+        for c in " maple  otter-drum".chars() {
+            push_char(&mut code, c);
+        }
+        assert_eq!(code, "MAPLE-OTTER-DRUM");
+        assert!(is_valid(&code));
+    }
+
+    #[test]
+    fn test_pasted_codes() {
+        assert_eq!(from_pasted("k7p2qx\n").as_deref(), Some("K7P2QX"));
+        assert_eq!(from_pasted("Room K7P2QX").as_deref(), Some("K7P2QX"));
+        assert_eq!(
+            from_pasted("maple otter drum").as_deref(),
+            Some("MAPLE-OTTER-DRUM")
+        );
+        assert_eq!(from_pasted("Join my game, please!"), None);
+        // codes leave out letters that look like digits
+        assert_eq!(from_pasted("K0P1QX"), None);
+    }
+}
@@ -0,0 +1,754 @@
+//! House rules chosen by the host.
+
+use crate::card::{Card, CardColor, CardValue};
+use crate::deck::{DeckComposition, GameRng, STARTING_HAND_SIZE};
+use crate::i18n::Language;
+use crate::scoring::ScoringTable;
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use bevy_matchbox::prelude::PeerId;
+use serde::{Deserialize, Serialize};
+
+/// Most cards a player can keep in their hand with the hand limit rule.
+pub const HAND_CAP: usize = 20;
+
+/// Rules for the current game.
+///
+/// These are set by the host in the lobby and sent to peers in the start game packet.
+/// Rules missing from saved games are left as the default.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameRules {
+    /// Play a sound for players taking much longer than usual on their turn.
+    pub nudges: bool,
+    /// Draw twos can be stacked onto a draw two, passing the total on to the next player.
+    pub stacking: bool,
+    /// A reverse played onto a draw two sends the penalty back to the player who played it.
+    pub reverse_bounces: bool,
+    /// Suggest the color the next player has recently been unable to follow when choosing a
+    /// wild color.
+    pub color_hints: bool,
+    /// Players holding more than [`HAND_CAP`] cards at the end of their turn return the extra
+    /// cards to the bottom of the deck.
+    pub hand_cap: bool,
+    /// Players can play several cards of the same value together in one turn, stacking their
+    /// effects.
+    pub play_multiple: bool,
+    /// Show a rough estimate of each player's chance of winning, in casual games.
+    pub win_odds: bool,
+    /// Count every card left in hand as the number on it, with action cards worth 10, instead
+    /// of the classic 20 for action cards and 50 for wilds.
+    pub face_value_scoring: bool,
+    /// A player can't play out their last cards if they're action cards or wilds, and has to
+    /// draw instead.
+    pub no_action_finish: bool,
+    /// A player who draws a card they can play may play it straight away, instead of their turn
+    /// ending.
+    pub play_after_draw: bool,
+    /// Two of the host's decks are shuffled together, so big rooms don't run through the cards.
+    pub double_deck: bool,
+    /// Show how many of each color and value haven't been seen yet, from the cards in the deck,
+    /// the discard pile and our hand.
+    pub card_counter: bool,
+    /// What happens when the card flipped to start the round is a skip, reverse or draw two.
+    pub first_card: FirstCard,
+    /// Who goes first in each round after the first, which is always a random player.
+    pub starting_player: StartingPlayer,
+    /// How many copies of each card are in the deck.
+    pub deck: DeckComposition,
+    /// Cards dealt to each player to start the round.
+    pub hand_size: HandSize,
+    /// Cards dealt to each player on top of the usual starting hand.
+    ///
+    /// This and the action deck are only set by challenges, so they're left out of saved games.
+    #[serde(skip)]
+    pub extra_cards: u8,
+    /// The deck only has action cards and wilds.
+    #[serde(skip)]
+    pub action_deck: bool,
+    /// The tutorial's scripted cards are dealt from the top of the deck.
+    ///
+    /// This is only set by the tutorial, so it's left out of saved games too.
+    #[serde(skip)]
+    pub scripted_deck: bool,
+}
+
+/// What happens when the card flipped onto the discard pile to start the round is an action card.
+///
+/// Wilds are always put under another card, whichever is chosen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FirstCard {
+    /// The card is treated like a number card.
+    #[default]
+    Ignore,
+    /// The card's effect applies to the first player, as if it had been played before them, as
+    /// in the standard rules.
+    Apply,
+    /// The card is put under another one, until a number card comes up.
+    Reflip,
+}
+
+impl FirstCard {
+    /// All choices, in the order they're cycled through in the lobby.
+    pub const ALL: [FirstCard; 3] = [FirstCard::Ignore, FirstCard::Apply, FirstCard::Reflip];
+
+    /// Returns the choice after this one.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|c| c == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Name shown in the lobby.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FirstCard::Ignore => "Ignore",
+            FirstCard::Apply => "Apply",
+            FirstCard::Reflip => "Reflip",
+        }
+    }
+
+    /// Returns `true` if the flipped card has to be covered by another one.
+    pub fn covers(&self, card: &Card) -> bool {
+        let action = matches!(
+            card.value,
+            CardValue::Skip | CardValue::Reverse | CardValue::DrawTwo
+        );
+        card.color == CardColor::Wild || (action && *self == FirstCard::Reflip)
+    }
+}
+
+impl Into<u8> for FirstCard {
+    fn into(self) -> u8 {
+        match self {
+            FirstCard::Ignore => 0,
+            FirstCard::Apply => 1,
+            FirstCard::Reflip => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for FirstCard {
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Ignore),
+            1 => Ok(Self::Apply),
+            2 => Ok(Self::Reflip),
+            _ => Err(()),
+        }
+    }
+
+    type Error = ();
+}
+
+/// Who goes first in the next round.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StartingPlayer {
+    /// The deal passes to the next seat, so the player after the last one to start goes first.
+    #[default]
+    Next,
+    /// The winner of the last round goes first.
+    Winner,
+    /// A random player goes first.
+    Random,
+}
+
+impl StartingPlayer {
+    /// All choices, in the order they're cycled through in the lobby.
+    pub const ALL: [StartingPlayer; 3] = [
+        StartingPlayer::Next,
+        StartingPlayer::Winner,
+        StartingPlayer::Random,
+    ];
+
+    /// Returns the choice after this one.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|c| c == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Name shown in the lobby.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StartingPlayer::Next => "Next player",
+            StartingPlayer::Winner => "Winner",
+            StartingPlayer::Random => "Random",
+        }
+    }
+
+    /// Returns the order of play for the next round, from the order of the last one.
+    ///
+    /// Everyone keeps their seat, so only who goes first changes. The player before them is the
+    /// dealer. A random first player is picked with the game's seeded rng.
+    pub fn next_order(
+        &self,
+        order: &[PeerId],
+        winner: Option<PeerId>,
+        rng: &mut GameRng,
+    ) -> Vec<PeerId> {
+        let mut order = order.to_vec();
+        if order.is_empty() {
+            return order;
+        }
+        let first = match self {
+            StartingPlayer::Next => 1,
+            StartingPlayer::Winner => winner
+                .and_then(|winner| order.iter().position(|pid| *pid == winner))
+                .unwrap_or(1),
+            StartingPlayer::Random => rng.index(order.len()),
+        };
+        let len = order.len();
+        order.rotate_left(first % len);
+        order
+    }
+}
+
+impl Into<u8> for StartingPlayer {
+    fn into(self) -> u8 {
+        match self {
+            StartingPlayer::Next => 0,
+            StartingPlayer::Winner => 1,
+            StartingPlayer::Random => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for StartingPlayer {
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Next),
+            1 => Ok(Self::Winner),
+            2 => Ok(Self::Random),
+            _ => Err(()),
+        }
+    }
+
+    type Error = ();
+}
+
+/// Number of cards each player is dealt to start the round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandSize(pub u8);
+
+impl HandSize {
+    /// All choices, in the order they're cycled through in the lobby.
+    pub const ALL: [HandSize; 3] = [HandSize(5), HandSize(6), HandSize(7)];
+    /// Largest hand, which every deck has to have enough cards to deal to a full room.
+    pub const MAX: usize = 7;
+
+    /// Returns the choice after this one.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|c| c == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Returns the number of cards in the hand.
+    pub fn cards(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Default for HandSize {
+    fn default() -> Self {
+        Self(STARTING_HAND_SIZE as u8)
+    }
+}
+
+impl GameRules {
+    /// Where each setting after the rules that can be turned on or off sits in
+    /// [`GameRules::to_bytes`].
+    const FIRST_CARD_BYTE: usize = Rule::ALL.len();
+    const EXTRA_CARDS_BYTE: usize = Self::FIRST_CARD_BYTE + 1;
+    const ACTION_DECK_BYTE: usize = Self::EXTRA_CARDS_BYTE + 1;
+    const SCRIPTED_DECK_BYTE: usize = Self::ACTION_DECK_BYTE + 1;
+    const STARTING_PLAYER_BYTE: usize = Self::SCRIPTED_DECK_BYTE + 1;
+    const DECK_BYTE: usize = Self::STARTING_PLAYER_BYTE + 1;
+    const HAND_SIZE_BYTE: usize = Self::DECK_BYTE + DeckComposition::BYTES;
+
+    /// Serializes the rules for the start game packet.
+    ///
+    /// The rules that can be turned on or off come first, then the first card choice, the extra
+    /// cards dealt, whether the deck only has action cards, whether it's scripted, who starts
+    /// each round, the cards in the deck, and the size of the starting hands.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Rule::ALL
+            .iter()
+            .map(|rule| rule.is_enabled(self) as u8)
+            .collect();
+        bytes.push(self.first_card.into());
+        bytes.push(self.extra_cards);
+        bytes.push(self.action_deck as u8);
+        bytes.push(self.scripted_deck as u8);
+        bytes.push(self.starting_player.into());
+        bytes.extend(self.deck.to_bytes());
+        bytes.push(self.hand_size.0);
+        bytes
+    }
+
+    /// Loads rules from the start game packet.
+    ///
+    /// Any rules missing from the packet are left as the default.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut rules = Self::default();
+        for (rule, byte) in Rule::ALL.iter().zip(bytes) {
+            rule.set(&mut rules, *byte != 0);
+        }
+        // hosts from before the first card choice always ignored it
+        rules.first_card = bytes
+            .get(Self::FIRST_CARD_BYTE)
+            .and_then(|byte| FirstCard::try_from(*byte).ok())
+            .unwrap_or(FirstCard::Ignore);
+        if let Some(extra_cards) = bytes.get(Self::EXTRA_CARDS_BYTE) {
+            rules.extra_cards = *extra_cards;
+        }
+        if let Some(action_deck) = bytes.get(Self::ACTION_DECK_BYTE) {
+            rules.action_deck = *action_deck != 0;
+        }
+        if let Some(scripted_deck) = bytes.get(Self::SCRIPTED_DECK_BYTE) {
+            rules.scripted_deck = *scripted_deck != 0;
+        }
+        if let Some(starting_player) = bytes
+            .get(Self::STARTING_PLAYER_BYTE)
+            .and_then(|byte| StartingPlayer::try_from(*byte).ok())
+        {
+            rules.starting_player = starting_player;
+        }
+        if let Some(deck) = bytes
+            .get(Self::DECK_BYTE..)
+            .and_then(DeckComposition::from_bytes)
+        {
+            rules.deck = deck;
+        }
+        if let Some(hand_size) = bytes
+            .get(Self::HAND_SIZE_BYTE)
+            .map(|byte| HandSize(*byte))
+            .filter(|hand_size| HandSize::ALL.contains(hand_size))
+        {
+            rules.hand_size = hand_size;
+        }
+        rules
+    }
+
+    /// Returns a short description of the enabled rules.
+    pub fn summary(&self) -> String {
+        self.summary_in(Language::English)
+    }
+
+    /// Returns a short description of the enabled rules, in the given language.
+    pub fn summary_in(&self, language: Language) -> String {
+        let mut enabled: Vec<&str> = Rule::ALL
+            .iter()
+            .filter(|rule| rule.is_enabled(self))
+            .map(|rule| language.tr(rule.label()))
+            .collect();
+        match self.first_card {
+            FirstCard::Ignore => enabled.push(language.tr("First card ignored")),
+            FirstCard::Apply => {}
+            FirstCard::Reflip => enabled.push(language.tr("First card reflips")),
+        }
+        match self.starting_player {
+            StartingPlayer::Next => {}
+            StartingPlayer::Winner => enabled.push(language.tr("Winner starts")),
+            StartingPlayer::Random => enabled.push(language.tr("Random starter")),
+        }
+        let hand_size;
+        if self.hand_size != HandSize::default() {
+            hand_size = language.tr_with("{} card hands", self.hand_size.0);
+            enabled.push(&hand_size);
+        }
+        let extra_cards;
+        if self.extra_cards > 0 {
+            extra_cards = language.tr_with("{} extra cards", self.extra_cards);
+            enabled.push(&extra_cards);
+        }
+        if self.action_deck {
+            enabled.push(language.tr("Action cards only"));
+        }
+        if self.scripted_deck {
+            enabled.push(language.tr("Tutorial deck"));
+        }
+        if self.deck != DeckComposition::default() {
+            enabled.push(language.tr("Custom deck"));
+        }
+        if enabled.is_empty() {
+            String::from(language.tr("Classic rules"))
+        } else {
+            enabled.join(", ")
+        }
+    }
+
+    /// Returns `true` if draw twos leave a penalty for the next player to answer,
+    /// instead of the next player drawing right away.
+    pub fn draw_penalties_pending(&self) -> bool {
+        self.stacking || self.reverse_bounces
+    }
+
+    /// Returns `true` if the card can be played in answer to a pending draw penalty.
+    pub fn can_answer_penalty(&self, card: &Card) -> bool {
+        match card.value {
+            CardValue::DrawTwo => self.stacking,
+            CardValue::Reverse => self.reverse_bounces,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if a player can play these cards as the last ones in their hand.
+    pub fn can_finish_on(&self, cards: &[Card]) -> bool {
+        !self.no_action_finish
+            || cards.iter().all(|card| {
+                card.color != CardColor::Wild
+                    && !matches!(
+                        card.value,
+                        CardValue::Skip | CardValue::Reverse | CardValue::DrawTwo
+                    )
+            })
+    }
+
+    /// Returns how many cards a player with this many cards has to return to the deck.
+    pub fn cards_over_cap(&self, hand_size: usize) -> usize {
+        if self.hand_cap {
+            hand_size.saturating_sub(HAND_CAP)
+        } else {
+            0
+        }
+    }
+
+    /// Returns the headings and explanations shown on the how to play screen, following these
+    /// rules.
+    pub fn reference(&self) -> Vec<(&'static str, &'static str)> {
+        let mut sections = vec![
+            (
+                "Your turn",
+                "Play a card matching the color or value of the top card, or draw one.",
+            ),
+            (
+                "Sevens are wild",
+                "A 7 can be played on anything, and its player picks the color to follow.",
+            ),
+            ("Skip", "The next player misses their turn."),
+            (
+                "Reverse",
+                if self.reverse_bounces {
+                    "Play turns around. Played onto a +2, it sends the penalty back."
+                } else {
+                    "Play turns around."
+                },
+            ),
+            (
+                "+2",
+                if self.stacking {
+                    "The next player draws two cards, unless they stack another +2 to pass the \
+                     total on."
+                } else {
+                    "The next player draws two cards."
+                },
+            ),
+        ];
+        if self.play_multiple {
+            sections.push((
+                "Matching values",
+                "Cards of the same value can be played together, adding up their effects.",
+            ));
+        }
+        sections.push((
+            "First card",
+            match self.first_card {
+                FirstCard::Ignore => "An action card flipped to start does nothing.",
+                FirstCard::Apply => "An action card flipped to start applies to the first player.",
+                FirstCard::Reflip => "An action card flipped to start is covered by another card.",
+            },
+        ));
+        if self.hand_cap {
+            sections.push((
+                "Hand limit of 20",
+                "Cards over the limit at the end of your turn go back under the deck.",
+            ));
+        }
+        if self.play_after_draw {
+            sections.push((
+                "Playing a drawn card",
+                "If the card you draw can be played, you can play it straight away or keep it.",
+            ));
+        }
+        if self.no_action_finish {
+            sections.push((
+                "No finishing on action cards",
+                "Your last card can't be a skip, reverse, +2 or wild, so you have to draw instead.",
+            ));
+        }
+        sections.extend([
+            (
+                "Reshuffling",
+                "When the draw pile runs out, the discard pile is shuffled into a new one.",
+            ),
+            (
+                "One card left",
+                "Call one when you're down to your last card, or you can be caught and made to \
+                 draw.",
+            ),
+            (
+                "Winning",
+                "The first player out of cards wins, and the cards left in other hands count \
+                 as points.",
+            ),
+        ]);
+        sections
+    }
+
+    /// Returns how many of the host's decks are shuffled together.
+    pub fn decks(&self) -> u8 {
+        if self.double_deck {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Returns the table used to count the points left in players' hands.
+    pub fn scoring(&self) -> ScoringTable {
+        if self.face_value_scoring {
+            ScoringTable::FaceValue
+        } else {
+            ScoringTable::Classic
+        }
+    }
+}
+
+/// A rule that can be turned on or off by the host.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    Nudges,
+    Stacking,
+    ReverseBounces,
+    ColorHints,
+    HandCap,
+    PlayMultiple,
+    WinOdds,
+    FaceValueScoring,
+    NoActionFinish,
+    PlayAfterDraw,
+    DoubleDeck,
+    CardCounter,
+}
+
+impl Rule {
+    /// All rules, in the order they're sent in the start game packet.
+    pub const ALL: [Rule; 12] = [
+        Rule::Nudges,
+        Rule::Stacking,
+        Rule::ReverseBounces,
+        Rule::ColorHints,
+        Rule::HandCap,
+        Rule::PlayMultiple,
+        Rule::WinOdds,
+        Rule::FaceValueScoring,
+        Rule::NoActionFinish,
+        Rule::PlayAfterDraw,
+        Rule::DoubleDeck,
+        Rule::CardCounter,
+    ];
+
+    /// Short description of the rule shown in the lobby.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Rule::Nudges => "Slow player nudges",
+            Rule::Stacking => "Stack +2s",
+            Rule::ReverseBounces => "Reverse bounces +2",
+            Rule::ColorHints => "Wild color hints",
+            Rule::HandCap => "Hand limit of 20",
+            Rule::PlayMultiple => "Play matching values together",
+            Rule::WinOdds => "Win odds ticker",
+            Rule::FaceValueScoring => "Face value scoring",
+            Rule::NoActionFinish => "No finishing on action cards",
+            Rule::PlayAfterDraw => "Play a drawn card",
+            Rule::DoubleDeck => "Two decks",
+            Rule::CardCounter => "Card counter",
+        }
+    }
+
+    /// Returns `true` if the rule is turned on.
+    pub fn is_enabled(&self, rules: &GameRules) -> bool {
+        match self {
+            Rule::Nudges => rules.nudges,
+            Rule::Stacking => rules.stacking,
+            Rule::ReverseBounces => rules.reverse_bounces,
+            Rule::ColorHints => rules.color_hints,
+            Rule::HandCap => rules.hand_cap,
+            Rule::PlayMultiple => rules.play_multiple,
+            Rule::WinOdds => rules.win_odds,
+            Rule::FaceValueScoring => rules.face_value_scoring,
+            Rule::NoActionFinish => rules.no_action_finish,
+            Rule::PlayAfterDraw => rules.play_after_draw,
+            Rule::DoubleDeck => rules.double_deck,
+            Rule::CardCounter => rules.card_counter,
+        }
+    }
+
+    /// Turns the rule on or off.
+    pub fn set(&self, rules: &mut GameRules, enabled: bool) {
+        match self {
+            Rule::Nudges => rules.nudges = enabled,
+            Rule::Stacking => rules.stacking = enabled,
+            Rule::ReverseBounces => rules.reverse_bounces = enabled,
+            Rule::ColorHints => rules.color_hints = enabled,
+            Rule::HandCap => rules.hand_cap = enabled,
+            Rule::PlayMultiple => rules.play_multiple = enabled,
+            Rule::WinOdds => rules.win_odds = enabled,
+            Rule::FaceValueScoring => rules.face_value_scoring = enabled,
+            Rule::NoActionFinish => rules.no_action_finish = enabled,
+            Rule::PlayAfterDraw => rules.play_after_draw = enabled,
+            Rule::DoubleDeck => rules.double_deck = enabled,
+            Rule::CardCounter => rules.card_counter = enabled,
+        }
+    }
+}
+
+/// Initializes the game rules, with a flipped action card applying to the first player in new
+/// rooms, as in the standard rules.
+fn setup(mut commands: Commands) {
+    commands.insert_resource(GameRules {
+        first_card: FirstCard::Apply,
+        ..default()
+    });
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that rules survive being sent in a packet.
+    #[test]
+    fn test_rules_serialization() {
+        let rules = GameRules {
+            nudges: true,
+            stacking: false,
+            reverse_bounces: true,
+            color_hints: false,
+            hand_cap: true,
+            play_multiple: false,
+            win_odds: true,
+            face_value_scoring: false,
+            no_action_finish: true,
+            play_after_draw: false,
+            double_deck: true,
+            card_counter: false,
+            first_card: FirstCard::Reflip,
+            starting_player: StartingPlayer::Winner,
+            deck: DeckComposition {
+                copies: [1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 0],
+                wilds: 8,
+            },
+            hand_size: HandSize(7),
+            extra_cards: 5,
+            action_deck: true,
+            scripted_deck: true,
+        };
+        assert_eq!(GameRules::from_bytes(&rules.to_bytes()), rules);
+        assert_eq!(GameRules::from_bytes(&[]), GameRules::default());
+        // packets from before the first card choice leave it as the default
+        let bytes = rules.to_bytes();
+        let old = GameRules::from_bytes(&bytes[..Rule::ALL.len()]);
+        assert_eq!(old.first_card, FirstCard::Ignore);
+        assert_eq!(old.extra_cards, 0);
+        assert!(!old.action_deck);
+        assert!(!old.scripted_deck);
+        assert_eq!(old.starting_player, StartingPlayer::Next);
+        assert_eq!(old.deck, DeckComposition::default());
+        assert_eq!(old.hand_size, HandSize::default());
+    }
+
+    /// Ensures that everyone keeps their seat from round to round, with the right player first.
+    #[test]
+    fn test_next_order() {
+        let order: Vec<PeerId> = (1..=4)
+            .map(|id| PeerId(bevy::utils::Uuid::from_u128(id)))
+            .collect();
+        let rng = &mut GameRng::new(7);
+        let rotated = [order[1], order[2], order[3], order[0]];
+        assert_eq!(StartingPlayer::Next.next_order(&order, None, rng), rotated);
+        assert_eq!(
+            StartingPlayer::Winner.next_order(&order, Some(order[2]), rng),
+            [order[2], order[3], order[0], order[1]]
+        );
+        // the deal passes on if the winner is gone
+        assert_eq!(StartingPlayer::Winner.next_order(&order, None, rng), rotated);
+
+        let random = StartingPlayer::Random.next_order(&order, None, rng);
+        let first = order.iter().position(|pid| *pid == random[0]).unwrap();
+        let mut expected = order.clone();
+        expected.rotate_left(first);
+        assert_eq!(random, expected);
+        assert!(StartingPlayer::Random.next_order(&[], None, rng).is_empty());
+
+        // the same seed always picks the same first player
+        let pick = |seed| StartingPlayer::Random.next_order(&order, None, &mut GameRng::new(seed));
+        assert_eq!(pick(42), pick(42));
+    }
+
+    /// Ensures that the how to play screen follows the rules.
+    #[test]
+    fn test_reference() {
+        let explanation = |rules: &GameRules, heading: &str| {
+            rules
+                .reference()
+                .into_iter()
+                .find(|(title, _)| *title == heading)
+                .map(|(_, text)| text)
+        };
+        let mut rules = GameRules::default();
+        assert_eq!(explanation(&rules, "Hand limit of 20"), None);
+        assert_eq!(explanation(&rules, "Matching values"), None);
+
+        rules.hand_cap = true;
+        rules.play_multiple = true;
+        rules.stacking = true;
+        assert!(explanation(&rules, "Hand limit of 20").is_some());
+        assert!(explanation(&rules, "Matching values").is_some());
+        assert!(explanation(&rules, "+2").is_some_and(|text| text.contains("stack")));
+    }
+
+    /// Ensures that wilds are always covered, and action cards only when reflipping.
+    #[test]
+    fn test_first_card_covers() {
+        let wild = Card::new(CardColor::Wild, CardValue::Seven, 0);
+        let skip = Card::new(CardColor::Red, CardValue::Skip, 0);
+        let nine = Card::new(CardColor::Blue, CardValue::Nine, 0);
+        for first_card in FirstCard::ALL {
+            assert!(first_card.covers(&wild));
+            assert!(!first_card.covers(&nine));
+            assert_eq!(first_card.covers(&skip), first_card == FirstCard::Reflip);
+        }
+    }
+
+    /// Ensures that only hands over the limit return cards, and only with the rule on.
+    #[test]
+    fn test_hand_cap() {
+        let mut rules = GameRules::default();
+        assert_eq!(rules.cards_over_cap(HAND_CAP + 3), 0);
+
+        rules.hand_cap = true;
+        assert_eq!(rules.cards_over_cap(HAND_CAP), 0);
+        assert_eq!(rules.cards_over_cap(HAND_CAP + 3), 3);
+    }
+
+    /// Ensures that action cards and wilds can't be played out last, and only with the rule on.
+    #[test]
+    fn test_can_finish_on() {
+        let wild = Card::new(CardColor::Wild, CardValue::Seven, 0);
+        let skip = Card::new(CardColor::Red, CardValue::Skip, 0);
+        let nine = Card::new(CardColor::Blue, CardValue::Nine, 0);
+        let mut rules = GameRules::default();
+        assert!(rules.can_finish_on(&[skip]) && rules.can_finish_on(&[wild]));
+
+        rules.no_action_finish = true;
+        assert!(rules.can_finish_on(&[nine]));
+        assert!(!rules.can_finish_on(&[wild]));
+        assert!(!rules.can_finish_on(&[skip, skip]));
+    }
+}
@@ -0,0 +1,70 @@
+//! Points that cards left in a player's hand are worth at the end of a round.
+//!
+//! Groups are used to different conventions, so there are a couple of tables to pick from. The
+//! host picks one with the face value scoring rule.
+
+use crate::card::Card;
+
+/// Points for each card value, in the order the values are declared in [`CardValue`].
+///
+/// [`CardValue`]: crate::card::CardValue
+type PointsTable = [u32; 13];
+
+/// Numbers are worth their value, action cards 20, and wild sevens 50.
+const CLASSIC_POINTS: PointsTable = [0, 1, 2, 3, 4, 5, 6, 50, 8, 9, 20, 20, 20];
+/// Every card is worth the number on it, with action cards worth 10.
+const FACE_VALUE_POINTS: PointsTable = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 10, 10];
+
+/// Table used to count the points left in a hand.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScoringTable {
+    #[default]
+    Classic,
+    FaceValue,
+}
+
+impl ScoringTable {
+    /// Returns the points the card is worth when left in a player's hand.
+    pub fn points(&self, card: &Card) -> u32 {
+        let table = match self {
+            ScoringTable::Classic => &CLASSIC_POINTS,
+            ScoringTable::FaceValue => &FACE_VALUE_POINTS,
+        };
+        table[card.value as usize]
+    }
+
+    /// Returns the points held in a hand.
+    pub fn hand_points(&self, cards: &[Card]) -> u32 {
+        cards.iter().map(|card| self.points(card)).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        card::{CardColor, CardValue},
+        deck::Deck,
+    };
+
+    /// Ensures a full deck adds up to the expected number of points with each table.
+    #[test]
+    fn test_hand_points() {
+        let deck = Deck::new();
+        // numbers (twice per color), 24 action cards, 4 wilds
+        assert_eq!(
+            ScoringTable::Classic.hand_points(&deck.cards),
+            4 * 2 * 38 + 24 * 20 + 4 * 50
+        );
+        assert_eq!(
+            ScoringTable::FaceValue.hand_points(&deck.cards),
+            4 * 2 * 38 + 24 * 10 + 4 * 7
+        );
+
+        let wild = Card::new(CardColor::Wild, CardValue::Seven, 0);
+        let skip = Card::new(CardColor::Red, CardValue::Skip, 1);
+        let nine = Card::new(CardColor::Blue, CardValue::Nine, 1);
+        assert_eq!(ScoringTable::Classic.hand_points(&[wild, skip, nine]), 79);
+        assert_eq!(ScoringTable::FaceValue.hand_points(&[wild, skip, nine]), 26);
+    }
+}
@@ -0,0 +1,189 @@
+//! End of match screen, crowning the champion.
+
+use crate::{
+    despawn_screen,
+    game_ui::standings::{spawn_standings, standing_rows},
+    info::{GameInfo, Opponents},
+    match_mode::{MatchFormat, MatchStandings},
+    menu::MenuState,
+    network::{RestartGame, ServerState},
+    pointer::Click,
+    settings::Settings,
+    text_fit, GameScreenState, GameSet, ScreenState,
+    transport::GameTransport,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Widest the champion title can be, and the smallest it's shrunk to for long names.
+const TITLE_WIDTH: f32 = 720.0;
+const TITLE_MIN_FONT_SIZE: f32 = 40.0;
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+pub struct OnScreen;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component)]
+pub enum ButtonAction {
+    NewMatch,
+    Quit,
+}
+
+/// Draws the champion and the final standings.
+fn setup(
+    mut socket: ResMut<GameTransport>,
+    standings: Res<MatchStandings>,
+    format: Res<MatchFormat>,
+    game_info: Res<GameInfo>,
+    opponents: Res<Opponents>,
+    server_state: Res<State<ServerState>>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let language = settings.language;
+    let own_id = socket.id();
+    let rows = standing_rows(
+        &standings, *format, &game_info, &opponents, own_id, &settings,
+    );
+    let champion = standings.champion(&game_info.order, *format);
+    let (title, title_size) = if champion.is_some() && champion == own_id {
+        (String::from(language.tr("You're the champion!")), 72.0)
+    } else {
+        let name = rows.first().map_or("", |row| row.name.as_str());
+        text_fit::fit_filled(
+            language.tr("{} is the champion!"),
+            name,
+            72.0,
+            TITLE_MIN_FONT_SIZE,
+            TITLE_WIDTH,
+        )
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(16.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.9).into(),
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                    font_size: title_size,
+                    color: Color::rgb(0.95, 0.75, 0.2),
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                format!(
+                    "{}, {}",
+                    format.label(language),
+                    language.tr_with("{} rounds", standings.rounds_played())
+                ),
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-Black.ttf"),
+                    font_size: 22.0,
+                    color: Color::GRAY,
+                },
+            ));
+
+            spawn_standings(
+                parent,
+                &rows,
+                language,
+                asset_server.load("fonts/Lato-Black.ttf"),
+            );
+
+            let button_style = Style {
+                width: Val::Px(274.0),
+                height: Val::Px(72.0),
+                margin: UiRect::all(Val::Px(20.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            };
+            parent.spawn(NodeBundle::default()).with_children(|parent| {
+                // the host can start a new match with the same players
+                if let ServerState::Server(_) = **server_state {
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: Color::WHITE.into(),
+                            image: asset_server.load("textures/buttons/play_again.png").into(),
+                            ..default()
+                        },
+                        ButtonAction::NewMatch,
+                    ));
+                }
+
+                parent.spawn((
+                    ButtonBundle {
+                        style: button_style,
+                        background_color: Color::WHITE.into(),
+                        image: asset_server.load("textures/buttons/main_menu.png").into(),
+                        ..default()
+                    },
+                    ButtonAction::Quit,
+                ));
+            });
+        });
+}
+
+/// Handles button presses.
+pub fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
+    mut restart_events: EventWriter<RestartGame>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut screen_state: ResMut<NextState<ScreenState>>,
+    mut server_state: ResMut<NextState<ServerState>>,
+    mut game_screen_state: ResMut<NextState<GameScreenState>>,
+) {
+    for action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match action {
+            ButtonAction::Quit => {
+                menu_state.set(MenuState::Main);
+                screen_state.set(ScreenState::Menu);
+                server_state.set(ServerState::None);
+                game_screen_state.set(GameScreenState::Game);
+            }
+            ButtonAction::NewMatch => {
+                restart_events.send(RestartGame);
+                game_screen_state.set(GameScreenState::Game);
+            }
+        }
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameScreenState::Champion),
+            setup.run_if(resource_exists::<GameTransport>()),
+        )
+        .add_systems(
+            Update,
+            handle_action
+                .in_set(GameSet::Input)
+                .run_if(in_state(GameScreenState::Champion)),
+        )
+        .add_systems(
+            OnExit(GameScreenState::Champion),
+            despawn_screen::<OnScreen>,
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Confirmation shown before leaving a game in progress.
+
+use crate::{
+    despawn_screen,
+    menu::MenuState,
+    network::{LeaveGame, ServerState},
+    pointer::Click,
+    settings::Settings,
+    GameScreenState, GameSet, ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+pub struct OnScreen;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component, Clone, Copy)]
+pub enum ButtonAction {
+    Leave,
+    Stay,
+    HowToPlay,
+}
+
+/// Asks whether to leave the game.
+fn setup(settings: Res<Settings>, asset_server: Res<AssetServer>, mut commands: Commands) {
+    let language = settings.language;
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.9).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                language.tr("Leave game?"),
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                    font_size: 60.0,
+                    color: Color::WHITE,
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                language.tr("Other players will be affected"),
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-Black.ttf"),
+                    font_size: 24.0,
+                    color: Color::GRAY,
+                },
+            ));
+
+            parent.spawn(NodeBundle::default()).with_children(|parent| {
+                for (action, label) in
+                    [(ButtonAction::Stay, "Stay"), (ButtonAction::Leave, "Leave")]
+                {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(222.0),
+                                    height: Val::Px(72.0),
+                                    margin: UiRect::all(Val::Px(20.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::WHITE.into(),
+                                ..default()
+                            },
+                            action,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                language.tr(label),
+                                TextStyle {
+                                    font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                                    font_size: 40.0,
+                                    color: Color::BLACK,
+                                },
+                            ));
+                        });
+                }
+            });
+
+            // rules of the game in progress
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            padding: UiRect::axes(Val::Px(14.0), Val::Px(8.0)),
+                            ..default()
+                        },
+                        background_color: Color::NONE.into(),
+                        ..default()
+                    },
+                    ButtonAction::HowToPlay,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        language.tr("How to play"),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                            font_size: 28.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                });
+        });
+}
+
+/// Handles button presses, with escape to stay.
+fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
+    keys: Res<Input<KeyCode>>,
+    mut leave_events: EventWriter<LeaveGame>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut screen_state: ResMut<NextState<ScreenState>>,
+    mut server_state: ResMut<NextState<ServerState>>,
+    mut game_screen_state: ResMut<NextState<GameScreenState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        game_screen_state.set(GameScreenState::Game);
+        return;
+    }
+    for action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match action {
+            ButtonAction::Leave => {
+                // sent before the connection is closed when the state changes
+                leave_events.send(LeaveGame);
+                menu_state.set(MenuState::Main);
+                screen_state.set(ScreenState::Menu);
+                server_state.set(ServerState::None);
+                game_screen_state.set(GameScreenState::Game);
+            }
+            ButtonAction::Stay => game_screen_state.set(GameScreenState::Game),
+            ButtonAction::HowToPlay => game_screen_state.set(GameScreenState::HowToPlay),
+        }
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameScreenState::ConfirmQuit), setup)
+            .add_systems(
+                Update,
+                handle_action
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(GameScreenState::ConfirmQuit)),
+            )
+            .add_systems(
+                OnExit(GameScreenState::ConfirmQuit),
+                despawn_screen::<OnScreen>,
+            );
+    }
+}
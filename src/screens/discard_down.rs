@@ -0,0 +1,226 @@
+//! Returning cards over the hand limit to the deck.
+//!
+//! With the hand limit rule, a player holding too many cards at the end of their turn picks
+//! the extra cards from their hand, and they're put under the deck. They can't play until
+//! they have.
+
+use crate::{
+    button::ButtonEnabled, deck::MainPlayer, despawn_screen, disconnect::not_paused,
+    game_ui::hand::HandCard, info::GameInfo, network::ReturnCards, pointer::Click,
+    rules::GameRules, settings::Settings, GameScreenState, GameSet, ScreenState,
+    transport::GameTransport,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Marks a card in hand picked to be returned to the deck.
+#[derive(Component)]
+pub struct Selected;
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+struct OnScreen;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component)]
+enum ButtonAction {
+    Return,
+}
+
+/// Starts returning cards once our turn is over and we're holding too many.
+fn check_hand_cap(
+    mut game_screen_state: ResMut<NextState<GameScreenState>>,
+    mut socket: ResMut<GameTransport>,
+    main_player: Res<MainPlayer>,
+    game_info: Res<GameInfo>,
+    rules: Res<GameRules>,
+) {
+    if rules.cards_over_cap(main_player.len()) == 0 {
+        return;
+    }
+    if game_info.current_player.is_some() && game_info.current_player != socket.id() {
+        game_screen_state.set(GameScreenState::DiscardDown);
+    }
+}
+
+/// Draws the banner asking which cards to return.
+fn setup(
+    main_player: Res<MainPlayer>,
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let count = rules.cards_over_cap(main_player.len());
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(20.0),
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        settings
+                            .language
+                            .tr_with("Choose {} cards to put under the deck", count),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-Black.ttf"),
+                            font_size: 28.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                });
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(160.0),
+                            height: Val::Px(60.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    ButtonAction::Return,
+                    ButtonEnabled(false),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        settings.language.tr("Return"),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                            font_size: 36.0,
+                            color: Color::BLACK,
+                        },
+                    ));
+                });
+        });
+}
+
+/// Picks or unpicks a card in hand when it's clicked, up to the number that has to go back.
+fn toggle_selection(
+    mut clicks: EventReader<Click>,
+    cards: Query<Option<&Selected>, With<HandCard>>,
+    selected: Query<(), (With<Selected>, With<HandCard>)>,
+    main_player: Res<MainPlayer>,
+    rules: Res<GameRules>,
+    mut commands: Commands,
+) {
+    let count = rules.cards_over_cap(main_player.len());
+    let mut picked = selected.iter().count();
+    for Click(entity) in clicks.read() {
+        match cards.get(*entity) {
+            Ok(Some(_)) => {
+                commands.entity(*entity).remove::<Selected>();
+                picked -= 1;
+            }
+            Ok(None) if picked < count => {
+                commands.entity(*entity).insert(Selected);
+                picked += 1;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Enables the return button once the right number of cards is picked.
+fn update_button_enabled(
+    mut buttons: Query<&mut ButtonEnabled, With<ButtonAction>>,
+    selected: Query<(), (With<Selected>, With<HandCard>)>,
+    main_player: Res<MainPlayer>,
+    rules: Res<GameRules>,
+) {
+    let ready = selected.iter().count() == rules.cards_over_cap(main_player.len());
+    for mut enabled in &mut buttons {
+        if enabled.0 != ready {
+            enabled.0 = ready;
+        }
+    }
+}
+
+/// Handles button presses.
+fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<(&ButtonAction, &ButtonEnabled)>,
+    selected: Query<(Entity, &HandCard), With<Selected>>,
+    mut return_events: EventWriter<ReturnCards>,
+    mut game_screen_state: ResMut<NextState<GameScreenState>>,
+    mut main_player: ResMut<MainPlayer>,
+    mut commands: Commands,
+) {
+    for (action, enabled) in actions.iter_many(clicks.read().map(|click| click.0)) {
+        if !enabled.0 {
+            continue;
+        }
+        match action {
+            ButtonAction::Return => {
+                let mut cards = Vec::new();
+                for (entity, hand_card) in &selected {
+                    let card = *hand_card.card();
+                    main_player.remove(hand_card.id());
+                    commands.entity(entity).despawn_recursive();
+                    cards.push(card);
+                }
+                return_events.send(ReturnCards(cards));
+                game_screen_state.set(GameScreenState::Game);
+            }
+        }
+    }
+}
+
+/// Unpicks any cards left picked when the screen closes.
+fn clear_selection(selected: Query<Entity, With<Selected>>, mut commands: Commands) {
+    for entity in &selected {
+        commands.entity(entity).remove::<Selected>();
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            check_hand_cap
+                .in_set(GameSet::Rules)
+                .run_if(in_state(ScreenState::Game))
+                .run_if(in_state(GameScreenState::Game))
+                .run_if(not_paused)
+                .run_if(resource_exists::<GameTransport>()),
+        )
+        .add_systems(OnEnter(GameScreenState::DiscardDown), setup)
+        .add_systems(
+            Update,
+            (toggle_selection, update_button_enabled, handle_action)
+                .chain()
+                .in_set(GameSet::Input)
+                .run_if(in_state(GameScreenState::DiscardDown)),
+        )
+        .add_systems(
+            OnExit(GameScreenState::DiscardDown),
+            (despawn_screen::<OnScreen>, clear_selection),
+        );
+    }
+}
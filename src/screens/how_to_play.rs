@@ -0,0 +1,100 @@
+//! Rules reference opened from the leave game overlay, for the rules of the game in progress.
+
+use crate::{
+    despawn_screen, menu::how_to_play::spawn_reference, pointer::Click, rules::GameRules,
+    settings::Settings, GameScreenState, GameSet,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+pub struct OnScreen;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component)]
+pub enum ButtonAction {
+    Back,
+}
+
+/// Draws the rules reference over the game.
+fn setup(
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.9).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            // back button
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(26.0),
+                        left: Val::Px(26.0),
+                        width: Val::Px(120.0),
+                        height: Val::Px(46.0),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    image: asset_server.load("textures/buttons/back.png").into(),
+                    ..default()
+                },
+                ButtonAction::Back,
+            ));
+
+            spawn_reference(parent, &rules, settings.language, &asset_server);
+        });
+}
+
+/// Goes back to the leave game overlay with the back button or escape.
+fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
+    keys: Res<Input<KeyCode>>,
+    mut game_screen_state: ResMut<NextState<GameScreenState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        game_screen_state.set(GameScreenState::ConfirmQuit);
+        return;
+    }
+    for action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match action {
+            ButtonAction::Back => game_screen_state.set(GameScreenState::ConfirmQuit),
+        }
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameScreenState::HowToPlay), setup)
+            .add_systems(
+                Update,
+                handle_action
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(GameScreenState::HowToPlay)),
+            )
+            .add_systems(
+                OnExit(GameScreenState::HowToPlay),
+                despawn_screen::<OnScreen>,
+            );
+    }
+}
@@ -1,4 +1,10 @@
 //! In-game screens.
 
+pub mod champion;
+pub mod confirm_quit;
+pub mod discard_down;
+pub mod how_to_play;
+pub mod splash;
+pub mod turn_history;
 pub mod wild;
 pub mod win;
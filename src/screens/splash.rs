@@ -0,0 +1,127 @@
+//! Splash screen shown while the game's assets load.
+//!
+//! Switching to the menu before textures and fonts are ready shows blank frames, which is
+//! especially noticeable on wasm where every asset is fetched over the network.
+
+use crate::{
+    card::{Card, CardColor, CardValue},
+    despawn_screen,
+    settings::Settings,
+    ScreenState,
+};
+use bevy::{
+    asset::LoadState,
+    prelude::{Plugin as BevyPlugin, *},
+};
+
+/// Assets that aren't loaded from a card.
+const ASSET_PATHS: [&str; 17] = [
+    "fonts/Lato-Black.ttf",
+    "fonts/Lato-BlackItalic.ttf",
+    "shaders/background.wgsl",
+    "textures/background.png",
+    "textures/drawpile.png",
+    "textures/buttons/back.png",
+    "textures/buttons/host.png",
+    "textures/buttons/join.png",
+    "textures/buttons/main_menu.png",
+    "textures/buttons/menu.png",
+    "textures/buttons/play_again.png",
+    "textures/buttons/settings.png",
+    "textures/buttons/start.png",
+    "textures/icons/crown.png",
+    "textures/table/felt.png",
+    "textures/table/shadow.png",
+    "textures/table/slot.png",
+];
+
+/// Handles to every asset the game uses.
+///
+/// Keeping these around also stops assets being unloaded while they aren't on screen.
+#[derive(Resource)]
+struct GameAssets(Vec<UntypedHandle>);
+
+/// Indicates that the component bundle is for this screen.
+#[derive(Component)]
+struct OnScreen;
+
+/// Draws the logo and starts loading the game's assets.
+fn setup(mut commands: Commands, settings: Res<Settings>, asset_server: Res<AssetServer>) {
+    let mut handles: Vec<UntypedHandle> = ASSET_PATHS
+        .iter()
+        .map(|path| asset_server.load_untyped(*path).untyped())
+        .collect();
+    // one of each card texture, including the colored wilds, unless cards are drawn instead
+    if !settings.drawn_cards {
+        let wild = Card::new(CardColor::Wild, CardValue::Seven, 0);
+        for card in (0..52).map(Card::from).chain([wild]) {
+            handles.push(asset_server.load::<Image>(card.texture_path()).untyped());
+        }
+    }
+    commands.insert_resource(GameAssets(handles));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(20.0),
+                    ..default()
+                },
+                background_color: Color::rgb(0.08, 0.08, 0.1).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(ImageBundle {
+                style: Style {
+                    width: Val::Px(128.0),
+                    height: Val::Px(128.0),
+                    ..default()
+                },
+                image: asset_server.load("textures/icons/logo.png").into(),
+                ..default()
+            });
+            parent.spawn(TextBundle::from_section(
+                "Loading...",
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                    font_size: 32.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+/// Moves on to the menu once everything has either loaded or failed to.
+fn check_loaded(
+    assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    mut screen_state: ResMut<NextState<ScreenState>>,
+) {
+    let loading = assets.0.iter().any(|handle| {
+        !matches!(
+            asset_server.load_state(handle.id()),
+            LoadState::Loaded | LoadState::Failed
+        )
+    });
+    if !loading {
+        screen_state.set(ScreenState::Menu);
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ScreenState::Splash), setup)
+            .add_systems(Update, check_loaded.run_if(in_state(ScreenState::Splash)))
+            .add_systems(OnExit(ScreenState::Splash), despawn_screen::<OnScreen>);
+    }
+}
@@ -0,0 +1,273 @@
+//! Scrubber under the results for stepping back through the turns of the round.
+//!
+//! Each turn is shown from the game's history, so anyone who looked away can see how the round
+//! played out.
+
+use crate::{
+    card_art::CardFaces,
+    info::{GameInfo, Opponents, TurnAction},
+    network::PeerNames,
+    pointer::Click,
+    screens::win::OnScreen,
+    settings::Settings,
+    transport::GameTransport,
+    GameScreenState, GameSet, ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Size of the cards shown for a play.
+const HISTORY_CARD_SIZE: Vec2 = Vec2::new(48.0, 62.0);
+
+/// Turn being shown, as an index into the game's history.
+#[derive(Resource, Default)]
+struct ShownTurn(usize);
+
+/// Text describing the turn being shown.
+#[derive(Component)]
+struct TurnText;
+
+/// Row of cards played on the turn being shown.
+#[derive(Component)]
+struct TurnCards;
+
+/// Indicates the bundle's associated button action.
+#[derive(Component, Clone, Copy)]
+enum ButtonAction {
+    Back,
+    Forward,
+}
+
+/// Draws the scrubber along the bottom of the results, starting on the last turn.
+fn setup(
+    mut shown: ResMut<ShownTurn>,
+    game_info: Res<GameInfo>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    if game_info.history.is_empty() {
+        return;
+    }
+    shown.0 = game_info.history.len() - 1;
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 22.0,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(70.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                z_index: ZIndex::Global(1),
+                ..default()
+            },
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(16.0),
+                        padding: UiRect::all(Val::Px(8.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(1.0, 1.0, 1.0, 0.08).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_step_button(parent, ButtonAction::Back, "<", &asset_server);
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                row_gap: Val::Px(6.0),
+                                min_width: Val::Px(260.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section("", text_style)
+                                    .with_text_alignment(TextAlignment::Center),
+                                TurnText,
+                            ));
+                            parent.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        column_gap: Val::Px(4.0),
+                                        height: Val::Px(HISTORY_CARD_SIZE.y),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                TurnCards,
+                            ));
+                        });
+                    spawn_step_button(parent, ButtonAction::Forward, ">", &asset_server);
+                });
+        });
+}
+
+/// Spawns a button stepping through the turns.
+fn spawn_step_button(
+    parent: &mut ChildBuilder,
+    action: ButtonAction,
+    label: &str,
+    asset_server: &AssetServer,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(56.0),
+                    height: Val::Px(56.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::WHITE.into(),
+                ..default()
+            },
+            action,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
+                    font_size: 36.0,
+                    color: Color::BLACK,
+                },
+            ));
+        });
+}
+
+/// Steps through the turns with the buttons or the left and right arrow keys.
+fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
+    keys: Res<Input<KeyCode>>,
+    mut shown: ResMut<ShownTurn>,
+    game_info: Res<GameInfo>,
+) {
+    let mut steps: Vec<ButtonAction> = actions
+        .iter_many(clicks.read().map(|click| click.0))
+        .copied()
+        .collect();
+    if keys.just_pressed(KeyCode::Left) {
+        steps.push(ButtonAction::Back);
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        steps.push(ButtonAction::Forward);
+    }
+    let last = game_info.history.len().saturating_sub(1);
+    for step in steps {
+        let index = match step {
+            ButtonAction::Back => shown.0.saturating_sub(1),
+            ButtonAction::Forward => (shown.0 + 1).min(last),
+        };
+        // only mark a change when there's somewhere to go
+        if index != shown.0 {
+            shown.0 = index;
+        }
+    }
+}
+
+/// Describes the turn being shown, and lays out the cards played on it.
+fn update_turn(
+    mut text: Query<(Ref<TurnText>, &mut Text)>,
+    cards: Query<Entity, With<TurnCards>>,
+    mut socket: ResMut<GameTransport>,
+    shown: Res<ShownTurn>,
+    game_info: Res<GameInfo>,
+    opponents: Res<Opponents>,
+    peer_names: Res<PeerNames>,
+    settings: Res<Settings>,
+    faces: Res<CardFaces>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Ok((added, mut text)) = text.get_single_mut() else { return; };
+    let Ok(row) = cards.get_single() else { return; };
+    if !added.is_added() && !shown.is_changed() && !game_info.is_changed() {
+        return;
+    }
+    let mut row = commands.entity(row);
+    row.despawn_descendants();
+    // the history is cleared once the host starts the next round
+    let Some(record) = game_info.history.get(shown.0) else {
+        text.sections[0].value.clear();
+        return;
+    };
+
+    let language = settings.language;
+    let name = if socket.id() == Some(record.player) {
+        language.tr("You")
+    } else {
+        opponents
+            .0
+            .iter()
+            .find(|opponent| opponent.id == record.player)
+            .map(|opponent| opponent.name.as_str())
+            .or_else(|| peer_names.0.get(&record.player).map(String::as_str))
+            .unwrap_or(language.tr("Unknown"))
+    };
+    let position = format!("{}/{}", shown.0 + 1, game_info.history.len());
+    let action = match &record.action {
+        TurnAction::Played(_) => language.tr_with("{} played", name),
+        TurnAction::Drew(1) => language.tr_with("{} drew a card", name),
+        TurnAction::Drew(count) => format!(
+            "{} {}",
+            language.tr_with("{} drew", name),
+            language.tr_with("{} cards", count)
+        ),
+    };
+    text.sections[0].value = format!("{}\n{action}", language.tr_with("Turn {}", position));
+
+    let TurnAction::Played(played) = &record.action else { return; };
+    row.with_children(|parent| {
+        for card in played {
+            parent.spawn(ImageBundle {
+                style: Style {
+                    width: Val::Px(HISTORY_CARD_SIZE.x),
+                    height: Val::Px(HISTORY_CARD_SIZE.y),
+                    ..default()
+                },
+                image: faces.texture(card, &asset_server).into(),
+                ..default()
+            });
+        }
+    });
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShownTurn>()
+            .add_systems(OnEnter(GameScreenState::Win), setup)
+            .add_systems(
+                Update,
+                handle_action
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(GameScreenState::Win)),
+            )
+            .add_systems(
+                Update,
+                update_turn
+                    .in_set(GameSet::Ui)
+                    .run_if(in_state(GameScreenState::Win))
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(resource_exists::<GameTransport>()),
+            );
+    }
+}
@@ -1,14 +1,24 @@
 //! Wild card color selection screen.
 
 use crate::{
+    button::ButtonEnabled,
     card::{CardColor, CardType, SpawnCard},
     deck::DiscardCards,
     despawn_screen,
+    info::GameInfo,
     network::WildColor,
-    GameScreenState, ScreenState,
+    pointer::Click,
+    rules::GameRules,
+    settings::Settings,
+    transport::GameTransport,
+    tutorial::Tutorial,
+    GameScreenState, GameSet, ScreenState,
 };
 use bevy::prelude::{Plugin as BevyPlugin, *};
 
+/// Color of the outline marking the suggested color.
+const HINT_COLOR: Color = Color::rgb(1.0, 0.84, 0.0);
+
 /// Event posted when a wild card is played by the local player.
 #[derive(Event)]
 pub struct Wild;
@@ -18,7 +28,7 @@ pub struct Wild;
 pub struct OnScreen;
 
 /// Indicates the bundle's associated button action.
-#[derive(Component)]
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
 pub enum ButtonAction {
     Red,
     Yellow,
@@ -26,13 +36,24 @@ pub enum ButtonAction {
     Blue,
 }
 
-impl ToString for ButtonAction {
-    fn to_string(&self) -> String {
+impl ButtonAction {
+    /// Returns the name of the color chosen by the button.
+    fn label(&self) -> &'static str {
+        match self {
+            ButtonAction::Red => "red",
+            ButtonAction::Yellow => "yellow",
+            ButtonAction::Green => "green",
+            ButtonAction::Blue => "blue",
+        }
+    }
+
+    /// Returns the color chosen by the button.
+    fn color(&self) -> CardColor {
         match self {
-            ButtonAction::Red => "red".to_string(),
-            ButtonAction::Yellow => "yellow".to_string(),
-            ButtonAction::Green => "green".to_string(),
-            ButtonAction::Blue => "blue".to_string(),
+            ButtonAction::Red => CardColor::Red,
+            ButtonAction::Yellow => CardColor::Yellow,
+            ButtonAction::Green => CardColor::Green,
+            ButtonAction::Blue => CardColor::Blue,
         }
     }
 }
@@ -41,6 +62,11 @@ impl ToString for ButtonAction {
 fn handle_wild(
     mut events: EventReader<Wild>,
     mut game_screen_state: ResMut<NextState<GameScreenState>>,
+    mut socket: ResMut<GameTransport>,
+    game_info: Res<GameInfo>,
+    rules: Res<GameRules>,
+    tutorial: Res<Tutorial>,
+    settings: Res<Settings>,
     asset_server: Res<AssetServer>,
     mut commands: Commands,
 ) {
@@ -49,6 +75,26 @@ fn handle_wild(
     };
     game_screen_state.set(GameScreenState::WildColor);
 
+    // suggest the color the next player has been struggling with
+    let hint = if rules.color_hints {
+        // the turn may or may not have moved on from us yet
+        let next_player = if game_info.current_player == socket.id() {
+            game_info.next_player()
+        } else {
+            game_info.current_player
+        };
+        next_player
+            .and_then(|player| game_info.hardest_color(player))
+            .map(|color| match color {
+                CardColor::Red => ButtonAction::Red,
+                CardColor::Yellow => ButtonAction::Yellow,
+                CardColor::Green => ButtonAction::Green,
+                _ => ButtonAction::Blue,
+            })
+    } else {
+        None
+    };
+
     // draw wild screen
     commands
         .spawn((
@@ -96,15 +142,31 @@ fn handle_wild(
                         ButtonAction::Green,
                         ButtonAction::Blue,
                     ] {
-                        let title = action.to_string();
+                        let mut title = settings.language.tr(action.label()).to_string();
+                        if settings.color_symbols {
+                            if let Some(symbol) = action.color().symbol() {
+                                title = format!("{symbol} - {title}");
+                            }
+                        }
+                        let hinted = hint == Some(action);
                         parent
                             .spawn((
                                 ButtonBundle {
-                                    style: button_style.clone(),
+                                    style: Style {
+                                        border: UiRect::all(Val::Px(if hinted {
+                                            5.0
+                                        } else {
+                                            0.0
+                                        })),
+                                        ..button_style.clone()
+                                    },
                                     background_color: Color::WHITE.into(),
+                                    border_color: HINT_COLOR.into(),
                                     ..default()
                                 },
                                 action,
+                                // the tutorial asks for a particular color
+                                ButtonEnabled(tutorial.allows_color(action.color())),
                             ))
                             .with_children(|parent| {
                                 parent.spawn(TextBundle::from_section(
@@ -119,38 +181,34 @@ fn handle_wild(
 
 /// Handles button presses.
 pub fn handle_action(
-    interaction_query: Query<&ButtonAction, (Changed<Interaction>, With<Button>)>,
+    mut clicks: EventReader<Click>,
+    actions: Query<(&ButtonAction, &ButtonEnabled)>,
     mut discard_pile: ResMut<DiscardCards>,
     mut spawn_events: EventWriter<SpawnCard>,
     mut wild_events: EventWriter<WildColor>,
     mut game_screen_state: ResMut<NextState<GameScreenState>>,
-    mouse: Res<Input<MouseButton>>,
 ) {
-    for menu_button_action in &interaction_query {
-        if mouse.just_released(MouseButton::Left) {
-            let card_color = match menu_button_action {
-                ButtonAction::Red => CardColor::Red,
-                ButtonAction::Yellow => CardColor::Yellow,
-                ButtonAction::Green => CardColor::Green,
-                ButtonAction::Blue => CardColor::Blue,
-            };
-
-            // add the colored 7 to top of discard pile
-            let mut new_card = discard_pile.cards.last().unwrap().clone();
-            new_card.color = card_color;
-            discard_pile.cards.push(new_card);
-
-            // spawn a new seven on top of the discard pile with proper color
-            spawn_events.send(SpawnCard {
-                card: new_card,
-                position: crate::card::CardPosition::Discard(discard_pile.cards.len()),
-                card_type: CardType::Discard,
-            });
-
-            wild_events.send(WildColor(card_color));
-
-            game_screen_state.set(GameScreenState::Game);
+    for (menu_button_action, enabled) in actions.iter_many(clicks.read().map(|click| click.0)) {
+        if !enabled.0 {
+            continue;
         }
+        let card_color = menu_button_action.color();
+
+        // add the colored 7 to top of discard pile
+        let mut new_card = discard_pile.cards.last().unwrap().clone();
+        new_card.color = card_color;
+        discard_pile.cards.push(new_card);
+
+        // spawn a new seven on top of the discard pile with proper color
+        spawn_events.send(SpawnCard {
+            card: new_card,
+            position: crate::card::CardPosition::Discard(discard_pile.cards.len()),
+            card_type: CardType::Discard(discard_pile.cards.len() - 1),
+        });
+
+        wild_events.send(WildColor(card_color));
+
+        game_screen_state.set(GameScreenState::Game);
     }
 }
 
@@ -159,10 +217,18 @@ pub struct Plugin;
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
         app.add_event::<Wild>()
-            .add_systems(Update, handle_wild.run_if(in_state(ScreenState::Game)))
             .add_systems(
                 Update,
-                handle_action.run_if(in_state(GameScreenState::WildColor)),
+                handle_wild
+                    .in_set(GameSet::Rules)
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(
+                Update,
+                handle_action
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(GameScreenState::WildColor)),
             )
             .add_systems(
                 OnExit(GameScreenState::WildColor),
@@ -1,18 +1,98 @@
-//! Win/lose screen.
+//! End of round results screen.
 
 use crate::{
+    card::Card,
+    card_art::CardFaces,
+    deck::MainPlayer,
     despawn_screen,
+    info::Opponents,
     menu::MenuState,
-    network::{RestartGame, ServerState},
-    GameScreenState, ScreenState,
+    network::{
+        HostRestarting, PeerLeft, PeerNames, RematchVotes, RestartGame, RevealHand, ServerState,
+        VoteRematch,
+    },
+    pointer::Click,
+    rules::GameRules,
+    scoring::ScoringTable,
+    settings::Settings,
+    text_fit, GameScreenState, GameSet, ScreenState,
+    transport::GameTransport,
+};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    utils::HashMap,
 };
-use bevy::prelude::{Plugin as BevyPlugin, *};
 use bevy_matchbox::prelude::*;
 
+/// Size of the revealed cards.
+const REVEALED_CARD_SIZE: Vec2 = Vec2::new(30.0, 39.0);
+/// Widest the winner title can be, and the smallest it's shrunk to for long names.
+const TITLE_WIDTH: f32 = 720.0;
+const TITLE_MIN_FONT_SIZE: f32 = 40.0;
+/// Width of the names in the results, and the smallest they're shrunk to.
+const RESULT_NAME_WIDTH: f32 = 200.0;
+const RESULT_NAME_MIN_FONT_SIZE: f32 = 18.0;
+
 /// Win event posted locally when a player wins.
 #[derive(Event)]
 pub struct Win(pub PeerId);
 
+/// Posted locally when a peer shows the cards left in their hand.
+#[derive(Event)]
+pub struct HandRevealed {
+    pub player: PeerId,
+    pub cards: Vec<Card>,
+}
+
+/// The winner of the round.
+#[derive(Resource)]
+struct Winner(PeerId);
+
+/// Cards left in each opponent's hand at the end of the round.
+#[derive(Resource, Default)]
+struct RevealedHands(HashMap<PeerId, Vec<Card>>);
+
+/// What's been heard about the next round since the results came up.
+#[derive(Resource, Default)]
+struct RematchStatus {
+    /// The host's latest count of rematch votes.
+    votes: Option<RematchVotes>,
+    /// Whether we've voted for a rematch.
+    voted: bool,
+    /// Whether the host has started clearing the round to deal the next one.
+    restarting: bool,
+}
+
+/// List of players and their remaining cards.
+#[derive(Component)]
+struct ResultsList;
+
+/// Line under the results saying how the next round is coming along.
+#[derive(Component)]
+struct RematchText;
+
+/// Row of buttons under the results.
+#[derive(Component)]
+struct ResultButtons;
+
+/// A player's line in the results list.
+struct ResultRow {
+    name: String,
+    is_winner: bool,
+    /// Number of cards left in the player's hand, which is known before the hand is revealed.
+    card_count: usize,
+    /// The cards left in the player's hand, if we know them yet.
+    cards: Option<Vec<Card>>,
+}
+
+impl ResultRow {
+    /// Returns the points held in the player's hand.
+    fn points(&self, scoring: ScoringTable) -> Option<u32> {
+        let cards = self.cards.as_ref()?;
+        Some(scoring.hand_points(cards))
+    }
+}
+
 /// Indicates that the component bundle is for this screen.
 #[derive(Component)]
 pub struct OnScreen;
@@ -21,21 +101,57 @@ pub struct OnScreen;
 #[derive(Component)]
 pub enum ButtonAction {
     PlayAgain,
+    Rematch,
     Quit,
 }
 
+/// Returns the name of a player, looking in the room for players no longer in the game.
+fn player_name<'a>(
+    player: PeerId,
+    opponents: &'a Opponents,
+    peer_names: &'a PeerNames,
+    settings: &'a Settings,
+) -> &'a str {
+    opponents
+        .0
+        .iter()
+        .find(|opponent| opponent.id == player)
+        .map(|opponent| opponent.name.as_str())
+        .or_else(|| peer_names.0.get(&player).map(String::as_str))
+        .unwrap_or(settings.language.tr("Unknown"))
+}
+
 /// Draws win screen when Win event is received.
 fn handle_win(
     mut events: EventReader<Win>,
     mut game_screen_state: ResMut<NextState<GameScreenState>>,
-    mut socket: ResMut<MatchboxSocket<SingleChannel>>,
+    mut reveal_events: EventWriter<RevealHand>,
+    mut socket: ResMut<GameTransport>,
     server_state: Res<State<ServerState>>,
+    opponents: Res<Opponents>,
+    peer_names: Res<PeerNames>,
+    settings: Res<Settings>,
     asset_server: Res<AssetServer>,
     mut commands: Commands,
 ) {
     let Some(Win(id)) = events.read().next() else { return; };
     let is_self = socket.id() == Some(*id);
     game_screen_state.set(GameScreenState::Win);
+    commands.insert_resource(Winner(*id));
+    reveal_events.send(RevealHand);
+
+    let (title, title_size) = if is_self {
+        (String::from(settings.language.tr("You won!")), 72.0)
+    } else {
+        let name = player_name(*id, &opponents, &peer_names, &settings);
+        text_fit::fit_filled(
+            settings.language.tr("{} won!"),
+            name,
+            72.0,
+            TITLE_MIN_FONT_SIZE,
+            TITLE_WIDTH,
+        )
+    };
 
     // draw win screen
     commands
@@ -67,77 +183,386 @@ fn handle_win(
                     // winner text
                     parent.spawn(
                         TextBundle::from_section(
-                            if is_self { "You won!" } else { "You lost!" }, // TODO: show winner name if we lost?
+                            title,
                             TextStyle {
                                 font: asset_server.load("fonts/Lato-BlackItalic.ttf"),
-                                font_size: 112.0,
+                                font_size: title_size,
                                 color: Color::WHITE,
                             },
                         )
                         .with_style(Style {
-                            margin: UiRect::all(Val::Px(30.0)),
+                            margin: UiRect::all(Val::Px(10.0)),
                             ..default()
                         }),
                     );
 
-                    let button_style = Style {
-                        width: Val::Px(274.0),
-                        height: Val::Px(72.0),
-                        margin: UiRect::all(Val::Px(20.0)),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    };
-
-                    // show play again button on the peer hosting
-                    if let ServerState::Server(_) = **server_state {
-                        parent.spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: Color::WHITE.into(),
-                                image: asset_server.load("textures/buttons/play_again.png").into(),
+                    // placements, filled in as hands are revealed
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                row_gap: Val::Px(6.0),
                                 ..default()
                             },
-                            ButtonAction::PlayAgain,
-                        ));
-                    }
+                            ..default()
+                        },
+                        ResultsList,
+                    ));
 
+                    // filled in as the host hears from players
                     parent.spawn((
-                        ButtonBundle {
-                            style: button_style,
-                            background_color: Color::WHITE.into(),
-                            image: asset_server.load("textures/buttons/main_menu.png").into(),
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-Black.ttf"),
+                                font_size: 24.0,
+                                color: Color::GRAY,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::top(Val::Px(16.0)),
                             ..default()
-                        },
-                        ButtonAction::Quit,
+                        }),
+                        RematchText,
                     ));
+
+                    parent
+                        .spawn((NodeBundle::default(), ResultButtons))
+                        .with_children(|parent| {
+                            // show play again button on the peer hosting, and let everyone
+                            // else ask for a rematch
+                            if let ServerState::Server(_) = **server_state {
+                                parent.spawn(play_again_button(&asset_server));
+                            } else {
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: button_style(),
+                                            background_color: Color::WHITE.into(),
+                                            ..default()
+                                        },
+                                        ButtonAction::Rematch,
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn(TextBundle::from_section(
+                                            settings.language.tr("Rematch"),
+                                            TextStyle {
+                                                font: asset_server
+                                                    .load("fonts/Lato-BlackItalic.ttf"),
+                                                font_size: 40.0,
+                                                color: Color::BLACK,
+                                            },
+                                        ));
+                                    });
+                            }
+
+                            parent.spawn((
+                                ButtonBundle {
+                                    style: button_style(),
+                                    background_color: Color::WHITE.into(),
+                                    image: asset_server.load("textures/buttons/main_menu.png").into(),
+                                    ..default()
+                                },
+                                ButtonAction::Quit,
+                            ));
+                        });
                 });
         });
 }
 
+/// Style of the buttons under the results.
+fn button_style() -> Style {
+    Style {
+        width: Val::Px(274.0),
+        height: Val::Px(72.0),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    }
+}
+
+/// Returns the button that starts the next round, which only the host has.
+fn play_again_button(asset_server: &AssetServer) -> (ButtonBundle, ButtonAction) {
+    (
+        ButtonBundle {
+            style: button_style(),
+            background_color: Color::WHITE.into(),
+            image: asset_server.load("textures/buttons/play_again.png").into(),
+            ..default()
+        },
+        ButtonAction::PlayAgain,
+    )
+}
+
+/// Gives the play again button to a player who took over the room after the results came up.
+fn show_play_again(
+    rows: Query<Entity, With<ResultButtons>>,
+    actions: Query<(Entity, &ButtonAction)>,
+    server_state: Res<State<ServerState>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let ServerState::Server(_) = **server_state else { return; };
+    let Ok(row) = rows.get_single() else { return; };
+    if actions
+        .iter()
+        .any(|(_, action)| matches!(action, ButtonAction::PlayAgain))
+    {
+        return;
+    }
+    // the host doesn't need to ask itself for a rematch
+    for (entity, action) in &actions {
+        if matches!(action, ButtonAction::Rematch) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    let button = commands.spawn(play_again_button(&asset_server)).id();
+    commands.entity(row).insert_children(0, &[button]);
+}
+
+/// Stores the hands revealed by peers.
+fn store_revealed_hands(
+    mut events: EventReader<HandRevealed>,
+    mut revealed: ResMut<RevealedHands>,
+) {
+    for HandRevealed { player, cards } in events.read() {
+        revealed.0.insert(*player, cards.clone());
+    }
+}
+
+/// Keeps track of rematch votes and the host getting the next round ready.
+fn store_rematch_status(
+    mut votes_events: EventReader<RematchVotes>,
+    mut restarting_events: EventReader<HostRestarting>,
+    mut peer_left_events: EventReader<PeerLeft>,
+    mut status: ResMut<RematchStatus>,
+) {
+    for votes in votes_events.read() {
+        status.votes = Some(*votes);
+    }
+    if restarting_events.read().last().is_some() {
+        status.restarting = true;
+    }
+    // a new host starts its own count, and won't carry on a restart
+    if peer_left_events.read().any(|peer_left| peer_left.was_host) {
+        status.votes = None;
+        status.restarting = false;
+    }
+}
+
+/// Shows how the next round is coming along, and takes away the rematch button once it's no use.
+fn update_rematch(
+    mut text: Query<(Ref<RematchText>, &mut Text)>,
+    actions: Query<(Entity, &ButtonAction)>,
+    status: Res<RematchStatus>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    let Ok((added, mut text)) = text.get_single_mut() else { return; };
+    if !added.is_added() && !status.is_changed() {
+        return;
+    }
+
+    let language = settings.language;
+    text.sections[0].value = if status.restarting {
+        String::from(language.tr("Host is restarting..."))
+    } else if let Some(RematchVotes { votes, players }) = status.votes {
+        language.tr_with("Rematch votes: {}", format!("{votes}/{players}"))
+    } else {
+        String::new()
+    };
+
+    if status.voted || status.restarting {
+        for (entity, action) in &actions {
+            if matches!(action, ButtonAction::Rematch) {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+/// Rebuilds the results list when hands are revealed.
+fn update_results(
+    list: Query<(Entity, Ref<ResultsList>)>,
+    mut socket: ResMut<GameTransport>,
+    revealed: Res<RevealedHands>,
+    winner: Res<Winner>,
+    opponents: Res<Opponents>,
+    main_player: Res<MainPlayer>,
+    rules: Res<GameRules>,
+    settings: Res<Settings>,
+    faces: Res<CardFaces>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Ok((list_entity, list)) = list.get_single() else { return; };
+    if !list.is_added() && !revealed.is_changed() {
+        return;
+    }
+
+    let own_id = socket.id();
+    let mut rows = vec![ResultRow {
+        name: settings.username.clone(),
+        is_winner: own_id == Some(winner.0),
+        card_count: main_player.len(),
+        cards: Some(main_player.cards()),
+    }];
+    rows.extend(opponents.0.iter().map(|opponent| {
+        let cards = revealed.0.get(&opponent.id).cloned();
+        ResultRow {
+            name: opponent.name.clone(),
+            is_winner: opponent.id == winner.0,
+            card_count: cards.as_ref().map_or(opponent.card_count, Vec::len),
+            cards,
+        }
+    }));
+    // winner first, then whoever is holding the fewest points
+    let scoring = rules.scoring();
+    rows.sort_by_key(|row| (!row.is_winner, row.points(scoring).unwrap_or(u32::MAX)));
+    let gained: u32 = rows.iter().filter_map(|row| row.points(scoring)).sum();
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Lato-Black.ttf"),
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+    let mut list = commands.entity(list_entity);
+    list.despawn_descendants();
+    list.with_children(|parent| {
+        for (place, row) in rows.iter().enumerate() {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(10.0),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let (name, font_size) = text_fit::fit(
+                        &format!("{}. {}", place + 1, row.name),
+                        text_style.font_size,
+                        RESULT_NAME_MIN_FONT_SIZE,
+                        RESULT_NAME_WIDTH,
+                    );
+                    parent.spawn(
+                        TextBundle::from_section(
+                            name,
+                            TextStyle {
+                                font_size,
+                                // the winner stands out, for anyone just watching
+                                color: if row.is_winner {
+                                    Color::rgb(0.95, 0.75, 0.2)
+                                } else {
+                                    Color::WHITE
+                                },
+                                ..text_style.clone()
+                            },
+                        )
+                        .with_style(Style {
+                            width: Val::Px(200.0),
+                            ..default()
+                        }),
+                    );
+
+                    let points = if row.is_winner {
+                        format!("+{gained}")
+                    } else {
+                        String::from("+0")
+                    };
+                    parent.spawn(
+                        TextBundle::from_section(points, text_style.clone()).with_style(Style {
+                            width: Val::Px(60.0),
+                            ..default()
+                        }),
+                    );
+
+                    let card_count = if row.card_count == 1 {
+                        String::from(settings.language.tr("1 card"))
+                    } else {
+                        settings.language.tr_with("{} cards", row.card_count)
+                    };
+                    parent.spawn(
+                        TextBundle::from_section(
+                            card_count,
+                            TextStyle {
+                                color: Color::GRAY,
+                                ..text_style.clone()
+                            },
+                        )
+                        .with_style(Style {
+                            width: Val::Px(100.0),
+                            ..default()
+                        }),
+                    );
+
+                    // remaining cards
+                    let Some(cards) = &row.cards else {
+                        parent.spawn(TextBundle::from_section("...", text_style.clone()));
+                        return;
+                    };
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                max_width: Val::Px(REVEALED_CARD_SIZE.x * 14.0),
+                                flex_wrap: FlexWrap::Wrap,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for card in cards {
+                                parent.spawn(ImageBundle {
+                                    style: Style {
+                                        width: Val::Px(REVEALED_CARD_SIZE.x),
+                                        height: Val::Px(REVEALED_CARD_SIZE.y),
+                                        ..default()
+                                    },
+                                    image: faces.texture(card, &asset_server).into(),
+                                    ..default()
+                                });
+                            }
+                        });
+                });
+        }
+    });
+}
+
+/// Forgets revealed hands and rematch votes once the results are closed.
+fn clear_results(mut revealed: ResMut<RevealedHands>, mut status: ResMut<RematchStatus>) {
+    revealed.0.clear();
+    *status = RematchStatus::default();
+}
+
 /// Handles button presses.
-pub fn handle_action(
-    interaction_query: Query<&ButtonAction, (Changed<Interaction>, With<Button>)>,
+fn handle_action(
+    mut clicks: EventReader<Click>,
+    actions: Query<&ButtonAction>,
     mut restart_events: EventWriter<RestartGame>,
-    mouse: Res<Input<MouseButton>>,
+    mut vote_events: EventWriter<VoteRematch>,
+    mut status: ResMut<RematchStatus>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut screen_state: ResMut<NextState<ScreenState>>,
     mut server_state: ResMut<NextState<ServerState>>,
     mut game_screen_state: ResMut<NextState<GameScreenState>>,
 ) {
-    for menu_button_action in &interaction_query {
-        if mouse.just_released(MouseButton::Left) {
-            match menu_button_action {
-                ButtonAction::Quit => {
-                    menu_state.set(MenuState::Main);
-                    screen_state.set(ScreenState::Menu);
-                    server_state.set(ServerState::None);
-                    game_screen_state.set(GameScreenState::Game);
-                }
-                ButtonAction::PlayAgain => {
-                    restart_events.send(RestartGame);
-                    game_screen_state.set(GameScreenState::Game);
-                }
+    for menu_button_action in actions.iter_many(clicks.read().map(|click| click.0)) {
+        match menu_button_action {
+            ButtonAction::Quit => {
+                menu_state.set(MenuState::Main);
+                screen_state.set(ScreenState::Menu);
+                server_state.set(ServerState::None);
+                game_screen_state.set(GameScreenState::Game);
+            }
+            ButtonAction::PlayAgain => {
+                restart_events.send(RestartGame);
+                game_screen_state.set(GameScreenState::Game);
+            }
+            ButtonAction::Rematch => {
+                vote_events.send(VoteRematch);
+                status.voted = true;
             }
         }
     }
@@ -148,13 +573,47 @@ pub struct Plugin;
 impl BevyPlugin for Plugin {
     fn build(&self, app: &mut App) {
         app.add_event::<Win>()
+            .add_event::<HandRevealed>()
+            .init_resource::<RevealedHands>()
+            .init_resource::<RematchStatus>()
             .add_systems(
                 Update,
-                handle_win
+                (handle_win, store_revealed_hands, store_rematch_status)
+                    .in_set(GameSet::Rules)
                     .run_if(in_state(ScreenState::Game))
-                    .run_if(resource_exists::<MatchboxSocket<SingleChannel>>()),
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(
+                Update,
+                handle_action
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(GameScreenState::Win)),
+            )
+            .add_systems(
+                Update,
+                show_play_again
+                    .in_set(GameSet::Ui)
+                    .run_if(in_state(GameScreenState::Win))
+                    .run_if(state_changed::<ServerState>()),
+            )
+            .add_systems(
+                Update,
+                update_rematch
+                    .in_set(GameSet::Ui)
+                    .run_if(in_state(GameScreenState::Win)),
+            )
+            .add_systems(
+                Update,
+                update_results
+                    .in_set(GameSet::Ui)
+                    .run_if(in_state(GameScreenState::Win))
+                    .run_if(resource_exists::<Winner>())
+                    .run_if(resource_exists::<GameTransport>()),
+            )
+            .add_systems(
+                OnExit(GameScreenState::Win),
+                (despawn_screen::<OnScreen>, clear_results),
             )
-            .add_systems(Update, handle_action.run_if(in_state(GameScreenState::Win)))
-            .add_systems(OnExit(GameScreenState::Win), despawn_screen::<OnScreen>);
+            .add_systems(OnExit(ScreenState::Game), clear_results);
     }
 }
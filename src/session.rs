@@ -0,0 +1,124 @@
+//! Stats for the current session, and a summary of them shown when quitting.
+//!
+//! Closing the window shows the summary for a few seconds before the app exits. Closing it
+//! again exits straight away.
+
+use crate::{profile::Stats, settings::Settings};
+use bevy::{
+    app::AppExit,
+    prelude::{Plugin as BevyPlugin, *},
+    window::WindowCloseRequested,
+};
+
+/// How long (in seconds) the session summary is shown before exiting.
+const SUMMARY_DURATION: f32 = 3.0;
+
+/// Games played since the app was opened.
+#[derive(Resource, Default)]
+pub struct Session(pub Stats);
+
+/// Returns `true` if winning this many games in a row is worth celebrating.
+pub fn is_streak_milestone(streak: i32) -> bool {
+    matches!(streak, 3 | 5) || (streak >= 10 && streak % 5 == 0)
+}
+
+/// Time left before exiting, while the summary is shown.
+#[derive(Component)]
+struct ExitTimer(Timer);
+
+/// Shows the session summary when the window is closed, or exits if there's nothing to show.
+fn handle_close(
+    mut close_events: EventReader<WindowCloseRequested>,
+    mut exit_events: EventWriter<AppExit>,
+    summary: Query<(), With<ExitTimer>>,
+    session: Res<Session>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    if close_events.read().next().is_none() {
+        return;
+    }
+    let stats = session.0;
+    if stats.games == 0 || !summary.is_empty() {
+        exit_events.send(AppExit);
+        return;
+    }
+
+    let language = settings.language;
+    let minutes = (time.elapsed_seconds() / 60.0).round().max(1.0);
+    let lines = [
+        String::from(language.tr("Thanks for playing!")),
+        language.tr_with("Games played: {}", stats.games),
+        language.tr_with("Wins: {}", stats.wins),
+        language.tr_with("Time: {}", language.tr_with("{} min", minutes)),
+        language.tr_with("Best streak: {}", stats.best_streak),
+    ];
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                z_index: ZIndex::Global(50),
+                ..default()
+            },
+            ExitTimer(Timer::from_seconds(SUMMARY_DURATION, TimerMode::Once)),
+        ))
+        .with_children(|parent| {
+            for (i, line) in lines.into_iter().enumerate() {
+                parent.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font: asset_server.load("fonts/Lato-Black.ttf"),
+                        font_size: if i == 0 { 48.0 } else { 28.0 },
+                        color: Color::WHITE,
+                    },
+                ));
+            }
+        });
+}
+
+/// Exits once the summary has been shown.
+fn exit_after_summary(
+    mut summary: Query<&mut ExitTimer>,
+    mut exit_events: EventWriter<AppExit>,
+    time: Res<Time>,
+) {
+    for mut timer in &mut summary {
+        if timer.0.tick(time.delta()).finished() {
+            exit_events.send(AppExit);
+        }
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Session>()
+            .add_systems(Update, (handle_close, exit_after_summary));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that only some streaks are celebrated, and losing streaks never are.
+    #[test]
+    fn test_streak_milestones() {
+        let milestones: Vec<i32> = (-10..=20).filter(|s| is_streak_milestone(*s)).collect();
+        assert_eq!(milestones, vec![3, 5, 10, 15, 20]);
+    }
+}
@@ -0,0 +1,709 @@
+//! Player settings, loaded from and saved to storage.
+//!
+//! All settings live in the [`Settings`] resource, which is loaded once at startup and saved
+//! whenever it changes. [`Setting`] describes how each one is shown in the settings menu.
+
+use crate::{
+    game_ui::hand::HandSort,
+    i18n::Language,
+    room_code::CodeStyle,
+    storage::Storage,
+    theme::{CardBack, TableTheme},
+    toast::Toast,
+    SERVER_URL,
+};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use bevy_framepace::{FramepaceSettings, Limiter};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// Longest allowed username.
+pub const USERNAME_MAX_LEN: usize = 15;
+/// Longest allowed server address.
+const SERVER_URL_MAX_LEN: usize = 64;
+/// Frame limit at the top of the slider, which turns the limit off.
+const UNLIMITED_FRAME_LIMIT: f32 = 250.0;
+
+/// Group of settings shown together in the settings menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Section {
+    Profile,
+    Audio,
+    Video,
+    Gameplay,
+    Network,
+}
+
+impl Section {
+    /// All sections, in the order they're shown.
+    pub const ALL: [Section; 5] = [
+        Section::Profile,
+        Section::Audio,
+        Section::Video,
+        Section::Gameplay,
+        Section::Network,
+    ];
+
+    /// Title shown above the section.
+    pub fn title(&self) -> &'static str {
+        match self {
+            Section::Profile => "Profile",
+            Section::Audio => "Audio",
+            Section::Video => "Video",
+            Section::Gameplay => "Gameplay",
+            Section::Network => "Network",
+        }
+    }
+}
+
+/// How the game window is shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    /// A borderless window covering the whole screen.
+    Borderless,
+    /// Exclusive fullscreen, which can change the screen's resolution.
+    Fullscreen,
+}
+
+impl DisplayMode {
+    /// All display modes, in the order they're picked.
+    pub const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Borderless,
+        DisplayMode::Fullscreen,
+    ];
+
+    /// Name shown in the settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "Windowed",
+            DisplayMode::Borderless => "Borderless",
+            DisplayMode::Fullscreen => "Fullscreen",
+        }
+    }
+
+    /// Returns the display mode after this one.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|m| m == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Returns the window mode to use for this display mode.
+    pub fn window_mode(&self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+}
+
+/// How a setting is edited.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Widget {
+    /// Switched on or off, or to the next option, when clicked.
+    Toggle,
+    /// A number picked by clicking along a bar.
+    Slider { min: f32, max: f32, step: f32 },
+    /// Text typed in while the field is selected.
+    TextField { max_len: usize },
+}
+
+/// A setting that can be changed in the settings menu.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Setting {
+    Username,
+    RuleStats,
+    Volume,
+    DisplayMode,
+    VSync,
+    FrameLimit,
+    TableTheme,
+    CardBack,
+    DrawnCards,
+    UseHostTable,
+    ColorSymbols,
+    ReduceMotion,
+    HoverRaise,
+    FanCurve,
+    CardOverlap,
+    HandSort,
+    AdaptiveBots,
+    Language,
+    ServerUrl,
+    RoomCodes,
+}
+
+impl Setting {
+    /// All settings, in the order they're shown.
+    pub const ALL: [Setting; 20] = [
+        Setting::Username,
+        Setting::RuleStats,
+        Setting::Volume,
+        Setting::DisplayMode,
+        Setting::VSync,
+        Setting::FrameLimit,
+        Setting::TableTheme,
+        Setting::CardBack,
+        Setting::DrawnCards,
+        Setting::UseHostTable,
+        Setting::ColorSymbols,
+        Setting::ReduceMotion,
+        Setting::HoverRaise,
+        Setting::FanCurve,
+        Setting::CardOverlap,
+        Setting::HandSort,
+        Setting::AdaptiveBots,
+        Setting::Language,
+        Setting::ServerUrl,
+        Setting::RoomCodes,
+    ];
+
+    /// Section the setting is shown in.
+    pub fn section(&self) -> Section {
+        match self {
+            Setting::Username | Setting::RuleStats => Section::Profile,
+            Setting::Volume => Section::Audio,
+            Setting::DisplayMode
+            | Setting::VSync
+            | Setting::FrameLimit
+            | Setting::TableTheme
+            | Setting::CardBack
+            | Setting::DrawnCards
+            | Setting::UseHostTable => Section::Video,
+            Setting::ColorSymbols
+            | Setting::ReduceMotion
+            | Setting::HoverRaise
+            | Setting::FanCurve
+            | Setting::CardOverlap
+            | Setting::HandSort
+            | Setting::AdaptiveBots
+            | Setting::Language => Section::Gameplay,
+            Setting::ServerUrl | Setting::RoomCodes => Section::Network,
+        }
+    }
+
+    /// Label shown next to the setting.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Setting::Username => "Username",
+            Setting::RuleStats => "Keep rule stats",
+            Setting::Volume => "Volume",
+            Setting::DisplayMode => "Display",
+            Setting::VSync => "VSync",
+            Setting::FrameLimit => "Frame limit",
+            Setting::TableTheme => "Table",
+            Setting::CardBack => "Card back",
+            Setting::DrawnCards => "Drawn card faces",
+            Setting::UseHostTable => "Use host's table",
+            Setting::ColorSymbols => "Color symbols",
+            Setting::ReduceMotion => "Reduce motion",
+            Setting::HoverRaise => "Card hover height",
+            Setting::FanCurve => "Hand curve",
+            Setting::CardOverlap => "Card overlap",
+            Setting::HandSort => "Hand order",
+            Setting::AdaptiveBots => "Adaptive bots",
+            Setting::Language => "Language",
+            Setting::ServerUrl => "Server",
+            Setting::RoomCodes => "Room codes",
+        }
+    }
+
+    /// How the setting is edited.
+    pub fn widget(&self) -> Widget {
+        match self {
+            Setting::Username => Widget::TextField {
+                max_len: USERNAME_MAX_LEN,
+            },
+            Setting::Volume => Widget::Slider {
+                min: 0.0,
+                max: 1.0,
+                step: 0.05,
+            },
+            Setting::FrameLimit => Widget::Slider {
+                min: 30.0,
+                max: UNLIMITED_FRAME_LIMIT,
+                step: 10.0,
+            },
+            Setting::HoverRaise => Widget::Slider {
+                min: 0.0,
+                max: 60.0,
+                step: 5.0,
+            },
+            Setting::FanCurve => Widget::Slider {
+                min: 0.0,
+                max: 1.0,
+                step: 0.1,
+            },
+            Setting::CardOverlap => Widget::Slider {
+                min: 0.2,
+                max: 0.8,
+                step: 0.05,
+            },
+            Setting::RuleStats
+            | Setting::DisplayMode
+            | Setting::VSync
+            | Setting::TableTheme
+            | Setting::CardBack
+            | Setting::DrawnCards
+            | Setting::UseHostTable
+            | Setting::ColorSymbols
+            | Setting::ReduceMotion
+            | Setting::HandSort
+            | Setting::AdaptiveBots
+            | Setting::Language
+            | Setting::RoomCodes => Widget::Toggle,
+            Setting::ServerUrl => Widget::TextField {
+                max_len: SERVER_URL_MAX_LEN,
+            },
+        }
+    }
+
+    /// Key the setting is saved under.
+    fn key(&self) -> &'static str {
+        match self {
+            Setting::Username => "username",
+            Setting::RuleStats => "rule_stats",
+            Setting::Volume => "volume",
+            Setting::DisplayMode => "display_mode",
+            Setting::VSync => "vsync",
+            Setting::FrameLimit => "frame_limit",
+            Setting::TableTheme => "table_theme",
+            Setting::CardBack => "card_back",
+            Setting::DrawnCards => "drawn_cards",
+            Setting::UseHostTable => "use_host_table",
+            Setting::ColorSymbols => "color_symbols",
+            Setting::ReduceMotion => "reduce_motion",
+            Setting::HoverRaise => "hover_raise",
+            Setting::FanCurve => "fan_curve",
+            Setting::CardOverlap => "card_overlap",
+            Setting::HandSort => "hand_sort",
+            Setting::AdaptiveBots => "adaptive_bots",
+            Setting::Language => "language",
+            Setting::ServerUrl => "server_url",
+            Setting::RoomCodes => "room_codes",
+        }
+    }
+
+    /// Returns `true` if the character can be typed into the setting.
+    pub fn accepts(&self, c: char) -> bool {
+        match self {
+            Setting::Username => c.is_alphanumeric() || c == '_' || c == ' ',
+            Setting::ServerUrl => c.is_ascii_graphic(),
+            _ => false,
+        }
+    }
+}
+
+/// The player's settings.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Name shown to other players.
+    pub username: String,
+    /// Whether to keep stats on the rules used in our games, shown on the stats page.
+    pub rule_stats: bool,
+    /// Volume of sound effects, from 0 to 1.
+    pub volume: f32,
+    /// Whether the game is windowed or fullscreen.
+    pub display_mode: DisplayMode,
+    /// Whether frames wait for the screen to refresh, which stops tearing.
+    pub vsync: bool,
+    /// Most frames drawn per second, or no limit at the top of the slider.
+    pub frame_limit: f32,
+    /// Colors of the table.
+    pub table_theme: TableTheme,
+    /// Design on the back of the cards.
+    pub card_back: CardBack,
+    /// Whether to draw card faces in game instead of using the card images.
+    pub drawn_cards: bool,
+    /// Whether to show the host's table theme instead of our own during games.
+    pub use_host_table: bool,
+    /// Whether to show a symbol for each color on cards, for color-blind players.
+    pub color_symbols: bool,
+    /// Whether to turn off camera shakes and zooms.
+    pub reduce_motion: bool,
+    /// How far a hovered card in hand is raised, in pixels.
+    pub hover_raise: f32,
+    /// How much the hand bends into an arc, from 0 (a straight line) to 1.
+    pub fan_curve: f32,
+    /// How much of each card in hand is covered by the next one, from 0 to 1.
+    pub card_overlap: f32,
+    /// Order the cards in hand are kept in.
+    pub hand_sort: HandSort,
+    /// Whether bots in challenges make more or fewer mistakes depending on how we've been doing.
+    pub adaptive_bots: bool,
+    /// Language used for UI text.
+    pub language: Language,
+    /// Address of the matchbox server used to find and join rooms.
+    pub server_url: String,
+    /// Kind of code given to the rooms we host.
+    pub room_codes: CodeStyle,
+}
+
+/// Returns a name for a new player, such as "User 1234".
+pub fn random_username() -> String {
+    let user_num = rand::thread_rng().gen_range(1000..10000);
+    format!("User {user_num}")
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            username: random_username(),
+            rule_stats: false,
+            volume: 1.0,
+            display_mode: DisplayMode::default(),
+            vsync: true,
+            frame_limit: 120.0,
+            table_theme: TableTheme::default(),
+            card_back: CardBack::default(),
+            drawn_cards: false,
+            use_host_table: true,
+            color_symbols: false,
+            reduce_motion: false,
+            hover_raise: 20.0,
+            fan_curve: 0.0,
+            card_overlap: 0.5,
+            hand_sort: HandSort::default(),
+            adaptive_bots: true,
+            language: Language::default(),
+            server_url: String::from(SERVER_URL),
+            room_codes: CodeStyle::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the saved settings, falling back to the defaults for any that are missing.
+    pub fn load(storage: &Storage) -> Self {
+        let default = Self::default();
+        Self {
+            username: storage
+                .get(Setting::Username.key())
+                .unwrap_or(default.username),
+            rule_stats: storage
+                .get(Setting::RuleStats.key())
+                .unwrap_or(default.rule_stats),
+            volume: storage.get(Setting::Volume.key()).unwrap_or(default.volume),
+            display_mode: storage
+                .get(Setting::DisplayMode.key())
+                .unwrap_or(default.display_mode),
+            vsync: storage.get(Setting::VSync.key()).unwrap_or(default.vsync),
+            frame_limit: storage
+                .get(Setting::FrameLimit.key())
+                .unwrap_or(default.frame_limit),
+            table_theme: storage
+                .get(Setting::TableTheme.key())
+                .unwrap_or(default.table_theme),
+            card_back: storage
+                .get(Setting::CardBack.key())
+                .unwrap_or(default.card_back),
+            drawn_cards: storage
+                .get(Setting::DrawnCards.key())
+                .unwrap_or(default.drawn_cards),
+            use_host_table: storage
+                .get(Setting::UseHostTable.key())
+                .unwrap_or(default.use_host_table),
+            color_symbols: storage
+                .get(Setting::ColorSymbols.key())
+                .unwrap_or(default.color_symbols),
+            reduce_motion: storage
+                .get(Setting::ReduceMotion.key())
+                .unwrap_or(default.reduce_motion),
+            hover_raise: storage
+                .get(Setting::HoverRaise.key())
+                .unwrap_or(default.hover_raise),
+            fan_curve: storage
+                .get(Setting::FanCurve.key())
+                .unwrap_or(default.fan_curve),
+            card_overlap: storage
+                .get(Setting::CardOverlap.key())
+                .unwrap_or(default.card_overlap),
+            hand_sort: storage
+                .get(Setting::HandSort.key())
+                .unwrap_or(default.hand_sort),
+            adaptive_bots: storage
+                .get(Setting::AdaptiveBots.key())
+                .unwrap_or(default.adaptive_bots),
+            language: storage
+                .get::<String>(Setting::Language.key())
+                .ok()
+                .and_then(|code| Language::from_code(&code))
+                .unwrap_or(default.language),
+            server_url: storage
+                .get(Setting::ServerUrl.key())
+                .unwrap_or(default.server_url),
+            room_codes: storage
+                .get(Setting::RoomCodes.key())
+                .unwrap_or(default.room_codes),
+        }
+    }
+
+    /// Saves every setting to storage.
+    pub fn save(&self, storage: &mut Storage) -> io::Result<()> {
+        storage.set(Setting::Username.key(), &self.username)?;
+        storage.set(Setting::RuleStats.key(), &self.rule_stats)?;
+        storage.set(Setting::Volume.key(), &self.volume)?;
+        storage.set(Setting::DisplayMode.key(), &self.display_mode)?;
+        storage.set(Setting::VSync.key(), &self.vsync)?;
+        storage.set(Setting::FrameLimit.key(), &self.frame_limit)?;
+        storage.set(Setting::TableTheme.key(), &self.table_theme)?;
+        storage.set(Setting::CardBack.key(), &self.card_back)?;
+        storage.set(Setting::DrawnCards.key(), &self.drawn_cards)?;
+        storage.set(Setting::UseHostTable.key(), &self.use_host_table)?;
+        storage.set(Setting::ColorSymbols.key(), &self.color_symbols)?;
+        storage.set(Setting::ReduceMotion.key(), &self.reduce_motion)?;
+        storage.set(Setting::HoverRaise.key(), &self.hover_raise)?;
+        storage.set(Setting::FanCurve.key(), &self.fan_curve)?;
+        storage.set(Setting::CardOverlap.key(), &self.card_overlap)?;
+        storage.set(Setting::HandSort.key(), &self.hand_sort)?;
+        storage.set(Setting::AdaptiveBots.key(), &self.adaptive_bots)?;
+        storage.set(Setting::Language.key(), &self.language.code())?;
+        storage.set(Setting::ServerUrl.key(), &self.server_url)?;
+        storage.set(Setting::RoomCodes.key(), &self.room_codes)
+    }
+
+    /// Returns the setting's value as shown in the settings menu.
+    pub fn display(&self, setting: Setting) -> String {
+        match setting {
+            Setting::Username => self.username.clone(),
+            Setting::RuleStats => {
+                let state = if self.rule_stats { "On" } else { "Off" };
+                String::from(self.language.tr(state))
+            }
+            Setting::Volume => format!("{:.0}%", self.volume * 100.0),
+            Setting::DisplayMode => String::from(self.language.tr(self.display_mode.name())),
+            Setting::VSync => {
+                let state = if self.vsync { "On" } else { "Off" };
+                String::from(self.language.tr(state))
+            }
+            Setting::FrameLimit if self.frame_limit >= UNLIMITED_FRAME_LIMIT => {
+                String::from(self.language.tr("Unlimited"))
+            }
+            Setting::FrameLimit => format!("{:.0} fps", self.frame_limit),
+            Setting::TableTheme => String::from(self.language.tr(self.table_theme.name())),
+            Setting::CardBack => String::from(self.language.tr(self.card_back.name())),
+            Setting::DrawnCards => {
+                let state = if self.drawn_cards { "On" } else { "Off" };
+                String::from(self.language.tr(state))
+            }
+            Setting::UseHostTable => {
+                let state = if self.use_host_table { "On" } else { "Off" };
+                String::from(self.language.tr(state))
+            }
+            Setting::ColorSymbols => {
+                let state = if self.color_symbols { "On" } else { "Off" };
+                String::from(self.language.tr(state))
+            }
+            Setting::ReduceMotion => {
+                let state = if self.reduce_motion { "On" } else { "Off" };
+                String::from(self.language.tr(state))
+            }
+            Setting::HoverRaise => format!("{:.0} px", self.hover_raise),
+            Setting::FanCurve => format!("{:.0}%", self.fan_curve * 100.0),
+            Setting::CardOverlap => format!("{:.0}%", self.card_overlap * 100.0),
+            Setting::HandSort => String::from(self.language.tr(self.hand_sort.name())),
+            Setting::AdaptiveBots => {
+                let state = if self.adaptive_bots { "On" } else { "Off" };
+                String::from(self.language.tr(state))
+            }
+            Setting::Language => String::from(self.language.name()),
+            Setting::ServerUrl => self.server_url.clone(),
+            Setting::RoomCodes => String::from(self.language.tr(self.room_codes.name())),
+        }
+    }
+
+    /// Returns where a slider setting sits between its minimum and maximum, from 0 to 1.
+    pub fn fraction(&self, setting: Setting) -> f32 {
+        let Widget::Slider { min, max, .. } = setting.widget() else { return 0.0; };
+        let value = match setting {
+            Setting::Volume => self.volume,
+            Setting::FrameLimit => self.frame_limit,
+            Setting::HoverRaise => self.hover_raise,
+            Setting::FanCurve => self.fan_curve,
+            Setting::CardOverlap => self.card_overlap,
+            _ => return 0.0,
+        };
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    }
+
+    /// Switches a toggle setting to its next value.
+    pub fn toggle(&mut self, setting: Setting) {
+        match setting {
+            Setting::RuleStats => self.rule_stats = !self.rule_stats,
+            Setting::DisplayMode => self.display_mode = self.display_mode.next(),
+            Setting::VSync => self.vsync = !self.vsync,
+            Setting::TableTheme => self.table_theme = self.table_theme.next(),
+            Setting::CardBack => self.card_back = self.card_back.next(),
+            Setting::DrawnCards => self.drawn_cards = !self.drawn_cards,
+            Setting::UseHostTable => self.use_host_table = !self.use_host_table,
+            Setting::ColorSymbols => self.color_symbols = !self.color_symbols,
+            Setting::ReduceMotion => self.reduce_motion = !self.reduce_motion,
+            Setting::HandSort => self.hand_sort = self.hand_sort.next(),
+            Setting::AdaptiveBots => self.adaptive_bots = !self.adaptive_bots,
+            Setting::Language => self.language = self.language.next(),
+            Setting::RoomCodes => self.room_codes = self.room_codes.next(),
+            _ => {}
+        }
+    }
+
+    /// Sets a slider setting from a position along the slider, from 0 to 1.
+    pub fn set_fraction(&mut self, setting: Setting, fraction: f32) {
+        let Widget::Slider { min, max, step } = setting.widget() else { return; };
+        let value = min + ((max - min) * fraction.clamp(0.0, 1.0) / step).round() * step;
+        match setting {
+            Setting::Volume => self.volume = value,
+            Setting::FrameLimit => self.frame_limit = value,
+            Setting::HoverRaise => self.hover_raise = value,
+            Setting::FanCurve => self.fan_curve = value,
+            Setting::CardOverlap => self.card_overlap = value,
+            _ => {}
+        }
+    }
+
+    /// Returns the text edited by a text field setting.
+    fn text_mut(&mut self, setting: Setting) -> Option<&mut String> {
+        match setting {
+            Setting::Username => Some(&mut self.username),
+            Setting::ServerUrl => Some(&mut self.server_url),
+            _ => None,
+        }
+    }
+
+    /// Types a character into a text field setting.
+    pub fn push_char(&mut self, setting: Setting, c: char) {
+        let Widget::TextField { max_len } = setting.widget() else { return; };
+        if !setting.accepts(c) {
+            return;
+        }
+        let Some(text) = self.text_mut(setting) else { return; };
+        if text.chars().count() < max_len {
+            text.push(c);
+        }
+    }
+
+    /// Deletes the last character of a text field setting.
+    pub fn pop_char(&mut self, setting: Setting) {
+        if let Some(text) = self.text_mut(setting) {
+            text.pop();
+        }
+    }
+}
+
+/// Saves the settings whenever they change.
+fn save_settings(
+    settings: Res<Settings>,
+    mut storage: ResMut<Storage>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+    if let Err(err) = settings.save(&mut storage) {
+        error!("Error saving settings: {err}");
+        toasts.send(Toast(String::from(
+            settings.language.tr("Couldn't save settings"),
+        )));
+    }
+}
+
+/// Applies the frame limit whenever it changes.
+fn apply_frame_limit(settings: Res<Settings>, mut framepace: ResMut<FramepaceSettings>) {
+    if settings.is_changed() {
+        framepace.limiter = if settings.frame_limit >= UNLIMITED_FRAME_LIMIT {
+            Limiter::Off
+        } else {
+            Limiter::from_framerate(settings.frame_limit as f64)
+        };
+    }
+}
+
+/// Applies the display mode and vsync to the window whenever they change.
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let Ok(mut window) = window.get_single_mut() else { return; };
+    // only touch the window if something changed, so it isn't marked as resized
+    let mode = settings.display_mode.window_mode();
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    let present_mode = if settings.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+}
+
+/// Switches between windowed and borderless fullscreen when F11 is pressed.
+fn toggle_fullscreen(keys: Res<Input<KeyCode>>, mut settings: ResMut<Settings>) {
+    if keys.just_pressed(KeyCode::F11) {
+        settings.display_mode = match settings.display_mode {
+            DisplayMode::Windowed => DisplayMode::Borderless,
+            _ => DisplayMode::Windowed,
+        };
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_fullscreen,
+                save_settings,
+                apply_frame_limit,
+                apply_window_settings,
+            )
+                .chain()
+                .run_if(resource_exists::<Settings>()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that slider settings can reach both ends and that their defaults are in range.
+    #[test]
+    fn test_slider_ranges() {
+        let defaults = Settings::default();
+        for (setting, default) in [
+            (Setting::Volume, defaults.volume),
+            (Setting::FrameLimit, defaults.frame_limit),
+            (Setting::HoverRaise, defaults.hover_raise),
+            (Setting::FanCurve, defaults.fan_curve),
+            (Setting::CardOverlap, defaults.card_overlap),
+        ] {
+            let Widget::Slider { min, max, step } = setting.widget() else {
+                panic!("{setting:?} should be a slider");
+            };
+            assert!(min <= default && default <= max);
+            // both ends should be a whole number of steps apart
+            let steps = (max - min) / step;
+            assert!((steps - steps.round()).abs() < 1e-4);
+
+            let mut settings = defaults.clone();
+            settings.set_fraction(setting, 0.0);
+            assert_eq!(settings.fraction(setting), 0.0);
+            settings.set_fraction(setting, 1.0);
+            assert_eq!(settings.fraction(setting), 1.0);
+        }
+    }
+}
@@ -1,58 +1,165 @@
 //! Simple persistent key/value storage for Bevy.
 //!
 //! Uses a local config file for native and LocalStorage for WASM.
+//!
+//! Values are stored as JSON, so anything that implements serde's `Serialize` and
+//! `Deserialize` can be saved, including structs, enums and lists.
+//!
+//! Data that keeps growing, like the history of past games, goes in a [`DataFolder`] instead, as
+//! LocalStorage only holds a few megabytes. Natively that's a folder next to the config file, and
+//! on the web it's IndexedDB. Reads from data folders finish in the background.
 
 use bevy::prelude::*;
-
-mod serialize;
-pub use serialize::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    io,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
 
 #[cfg(not(target_arch = "wasm32"))]
 mod native;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
 
-/// Generic store trait.
+/// Generic store trait, holding values as JSON strings.
 ///
 /// This is implemented for both native and wasm.
 trait Store {
-    fn get<T: Deserialize>(&self, key: &str) -> Result<T, ()>;
-    fn set<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), ()>;
+    fn get(&self, key: &str) -> io::Result<String>;
+    fn set(&mut self, key: &str, value: String) -> io::Result<()>;
+}
+
+/// Folder of data kept apart from the key/value store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFolder {
+    /// Past games of each profile.
+    History,
+}
+
+impl DataFolder {
+    /// Returns the name of the folder.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::History => "history",
+        }
+    }
+}
+
+/// Where a background read puts its result.
+type ReadSlot = Arc<Mutex<Option<io::Result<String>>>>;
+
+/// Store for data folders, holding values as JSON strings.
+///
+/// This is implemented for both native and wasm.
+trait DataStore {
+    /// Starts reading a value, putting the result in `slot` once it's done.
+    fn read(&self, folder: DataFolder, name: &str, slot: ReadSlot);
+    fn write(&mut self, folder: DataFolder, name: &str, value: String) -> io::Result<()>;
+}
+
+/// A value being read from a data folder in the background.
+pub struct PendingRead<T> {
+    slot: ReadSlot,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> PendingRead<T> {
+    /// Returns the value once it's been read, or `None` while it's still being read.
+    ///
+    /// Fails like [`Storage::get`]. The result is only returned once.
+    pub fn poll(&self) -> Option<io::Result<T>> {
+        let json = self.slot.lock().unwrap().take()?;
+        Some(json.and_then(|json| Ok(serde_json::from_str(&json)?)))
+    }
+}
+
+/// Returns an error if a value can't be stored under `name`, which has to be usable as a file
+/// name.
+fn check_name(name: &str) -> io::Result<()> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid data name {name:?}"),
+        ));
+    }
+    Ok(())
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 #[derive(Resource)]
-pub struct Storage(native::FileStore);
+pub struct Storage {
+    store: native::FileStore,
+    data: native::DataDir,
+}
 
 #[cfg(target_arch = "wasm32")]
 #[derive(Resource)]
-pub struct Storage(wasm::LocalStorage);
+pub struct Storage {
+    store: wasm::LocalStorage,
+    data: wasm::IndexedDb,
+}
 
 impl Storage {
     /// Creates a new storage object.
     #[cfg(target_arch = "wasm32")]
     pub fn new() -> Self {
-        Self(wasm::LocalStorage)
+        Self {
+            store: wasm::LocalStorage,
+            data: wasm::IndexedDb,
+        }
     }
 
     /// Creates a new storage object.
     #[cfg(not(target_arch = "wasm32"))]
     pub fn new() -> Self {
-        let path = directories::ProjectDirs::from("com", "cbratland", "crazy7s")
+        let dir = directories::ProjectDirs::from("com", "cbratland", "crazy7s")
             .expect("failed to get project dir")
             .data_dir()
-            .to_path_buf()
-            .join("settings.config");
-        Self(native::FileStore::new(path))
+            .to_path_buf();
+        Self {
+            store: native::FileStore::new(dir.join("settings.config")),
+            data: native::DataDir::new(dir.join("data")),
+        }
     }
 
     /// Gets a value from the store.
-    pub fn get<T: Deserialize>(&self, key: &str) -> Result<T, ()> {
-        self.0.get(key)
+    ///
+    /// Fails with [`io::ErrorKind::NotFound`] if the key is missing, or
+    /// [`io::ErrorKind::InvalidData`] if the stored value isn't a `T`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> io::Result<T> {
+        let json = self.store.get(key)?;
+        Ok(serde_json::from_str(&json)?)
     }
 
     /// Sets a value in the store.
-    pub fn set<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), ()> {
-        self.0.set(key, value)
+    pub fn set<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> io::Result<()> {
+        let json = serde_json::to_string(value)?;
+        self.store.set(key, json)
+    }
+
+    /// Starts reading a value from a data folder.
+    pub fn read_data<T: DeserializeOwned>(&self, folder: DataFolder, name: &str) -> PendingRead<T> {
+        let slot = ReadSlot::default();
+        match check_name(name) {
+            Ok(()) => self.data.read(folder, name, slot.clone()),
+            Err(err) => *slot.lock().unwrap() = Some(Err(err)),
+        }
+        PendingRead {
+            slot,
+            value: PhantomData,
+        }
+    }
+
+    /// Writes a value to a data folder, replacing any value already stored under `name`.
+    pub fn write_data<T: Serialize + ?Sized>(
+        &mut self,
+        folder: DataFolder,
+        name: &str,
+        value: &T,
+    ) -> io::Result<()> {
+        check_name(name)?;
+        let json = serde_json::to_string(value)?;
+        self.data.write(folder, name, json)
     }
 }
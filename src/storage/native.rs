@@ -1,66 +1,198 @@
-//! Native implementation of the store trait
+//! Native implementation of the store traits
 
-use super::{Deserialize, Serialize, Store};
+use super::{DataFolder, DataStore, ReadSlot, Store};
+use bevy::tasks::IoTaskPool;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
-// key/value store that can serialize and deseralize to a file?
-// example:
+// key/value store that serializes to a file, one entry per line:
 // key = "value"
 // key_2 = 3
+//
+// Values are JSON, which escapes newlines inside strings, so every entry fits on one line.
+// Lines are split on the first '=', since keys never contain one but values might.
 pub struct FileStore {
     path: PathBuf,
     map: HashMap<String, String>,
 }
 
 impl FileStore {
-    pub fn new(path: std::path::PathBuf) -> Self {
-        // create config file if it doesn't exist
-        if !path.exists() {
-            // make sure path directories exists
-            fs::create_dir_all(&path.parent().unwrap()).expect("failed to create settings dir");
-            fs::File::create(&path).expect("failed to crate and open settings file");
-        }
-
-        // read file into hashmap
-        let string = fs::read_to_string(&path).expect("failed to read file");
-        let mut map = HashMap::new();
-        for line in string.lines() {
-            let mut split = line.split('=');
-            let key = split.next().expect("failed to get key").trim();
-            let value = split.next().expect("failed to get value").trim();
-            map.insert(key.to_string(), value.to_string());
-        }
-
+    /// Opens the store at the given path.
+    ///
+    /// If the file can't be read, the store starts empty and is created on the first write.
+    pub fn new(path: PathBuf) -> Self {
+        let map = match fs::read_to_string(&path) {
+            Ok(string) => Self::parse(&string),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => {
+                eprintln!("Failed to read {}: {err}", path.display());
+                HashMap::new()
+            }
+        };
         Self { path, map }
     }
 
+    /// Reads entries from the contents of the file, skipping malformed lines.
+    fn parse(string: &str) -> HashMap<String, String> {
+        string
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .filter(|(key, _)| !key.is_empty())
+            .collect()
+    }
+
     // write the map to the file
-    fn write(&mut self) {
+    fn write(&self) -> io::Result<()> {
         let mut string = String::new();
         // serialize hashmap into a toml-style string
         for (key, value) in self.map.iter() {
             string.push_str(&format!("{} = {}\n", key, value));
         }
+        write_file(&self.path, &string)
+    }
+}
 
-        // write string to file
-        fs::write(&self.path, string).expect("failed to write to file");
+// write the contents to a file, creating its folder if needed
+//
+// the new contents are written to a temporary file first, then moved over the old file, so
+// a crash part way through never leaves a half written file behind
+fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
     }
+    let temp_path = path.with_extension("tmp");
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)
 }
 
 impl Store for FileStore {
-    #[cfg(not(target_arch = "wasm32"))]
-    fn set<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), ()> {
-        let string = value.serialize();
-        self.map.insert(key.to_string(), string);
-        self.write();
-        Ok(())
+    fn set(&mut self, key: &str, value: String) -> io::Result<()> {
+        if key.contains(['=', '\n']) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid storage key {key:?}"),
+            ));
+        }
+        let old = self.map.insert(key.to_string(), value);
+        let result = self.write();
+        // keep what's in memory matching what's on disk
+        if result.is_err() {
+            match old {
+                Some(old) => self.map.insert(key.to_string(), old),
+                None => self.map.remove(key),
+            };
+        }
+        result
+    }
+
+    fn get(&self, key: &str) -> io::Result<String> {
+        self.map
+            .get(key)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no value for {key}")))
+    }
+}
+
+// folder with a subfolder for each kind of data, holding a JSON file per value:
+// data/history/games.json
+pub struct DataDir {
+    path: PathBuf,
+}
+
+impl DataDir {
+    /// Uses the folder at the given path, which is created on the first write.
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    // path of the file holding a value
+    fn file(&self, folder: DataFolder, name: &str) -> PathBuf {
+        self.path.join(folder.name()).join(format!("{name}.json"))
     }
+}
+
+impl DataStore for DataDir {
+    fn read(&self, folder: DataFolder, name: &str, slot: ReadSlot) {
+        let path = self.file(folder, name);
+        IoTaskPool::get()
+            .spawn(async move {
+                *slot.lock().unwrap() = Some(fs::read_to_string(path));
+            })
+            .detach();
+    }
+
+    fn write(&mut self, folder: DataFolder, name: &str, value: String) -> io::Result<()> {
+        write_file(&self.file(folder, name), &value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that values with newlines and equals signs survive being written and read back,
+    /// and that malformed lines are skipped.
+    #[test]
+    fn test_file_store() {
+        let dir = std::env::temp_dir().join(format!("crazy7s-store-{}", std::process::id()));
+        let path = dir.join("settings.config");
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut store = FileStore::new(path.clone());
+        let value = serde_json::to_string("ws://a.b/?c=d\nline two").unwrap();
+        store.set("server_url", value.clone()).unwrap();
+        store.set("volume", String::from("0.5")).unwrap();
+        assert!(store.set("bad=key", String::from("1")).is_err());
+
+        // add a corrupted line to the file
+        let mut contents = fs::read_to_string(&path).unwrap();
+        contents.push_str("not an entry\n");
+        fs::write(&path, contents).unwrap();
+
+        let store = FileStore::new(path);
+        assert_eq!(store.get("server_url").unwrap(), value);
+        assert_eq!(store.get("volume").unwrap(), "0.5");
+        assert_eq!(
+            store.get("not an entry").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// Ensures that values written to a data folder can be read back in the background.
+    #[test]
+    fn test_data_dir() {
+        IoTaskPool::get_or_init(Default::default);
+        let dir = std::env::temp_dir().join(format!("crazy7s-data-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        // waits for a read to finish
+        let read = |data: &DataDir, name: &str| {
+            let slot = ReadSlot::default();
+            data.read(DataFolder::History, name, slot.clone());
+            for _ in 0..200 {
+                if let Some(result) = slot.lock().unwrap().take() {
+                    return result;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+            panic!("read never finished");
+        };
 
-    fn get<T: Deserialize>(&self, key: &str) -> Result<T, ()> {
-        let entry = self.map.get(key).ok_or(())?;
-        let value = T::deserialize(entry.to_string());
-        Ok(value)
+        let mut data = DataDir::new(dir.clone());
+        assert_eq!(
+            read(&data, "games").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        data.write(DataFolder::History, "games", String::from("[1,2]"))
+            .unwrap();
+        assert!(dir.join("history").join("games.json").exists());
+        assert_eq!(read(&data, "games").unwrap(), "[1,2]");
+        let _ = fs::remove_dir_all(&dir);
     }
 }
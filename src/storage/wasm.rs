@@ -1,6 +1,17 @@
-//! WebAssembly specific implementation of the Store trait.
+//! WebAssembly specific implementation of the store traits.
 
-use super::{Deserialize, Serialize, Store};
+use super::{DataFolder, DataStore, ReadSlot, Store};
+use bevy::prelude::error;
+use js_sys::Promise;
+use std::io;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode};
+
+/// Name of the IndexedDB database holding data folders.
+const DATABASE: &str = "crazy7s";
+/// Object store in the database, with keys like "history/games".
+const OBJECT_STORE: &str = "data";
 
 pub struct LocalStorage;
 
@@ -16,18 +27,100 @@ impl LocalStorage {
 }
 
 impl Store for LocalStorage {
-    fn get<T: Deserialize>(&self, key: &str) -> Result<T, ()> {
+    fn get(&self, key: &str) -> io::Result<String> {
         let storage = Self::storage();
-        let entry = storage.get_item(&key).map_err(|_| ())?;
-        let string = entry.as_ref().ok_or(())?;
-        let value = T::deserialize(string.to_string());
-        Ok(value)
+        let entry = storage.get_item(key).map_err(js_error)?;
+        entry.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no value for {key}")))
     }
 
-    fn set<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), ()> {
-        let string = value.serialize();
+    fn set(&mut self, key: &str, value: String) -> io::Result<()> {
         let storage = Self::storage();
-        storage.set_item(&key, &string).map_err(|_| ())?;
+        // fails when the browser's storage quota is used up
+        storage.set_item(key, &value).map_err(js_error)
+    }
+}
+
+/// Data folders kept in IndexedDB, which has room for far more than LocalStorage.
+pub struct IndexedDb;
+
+impl IndexedDb {
+    // open the database, creating the object store the first time
+    async fn open() -> Result<IdbDatabase, JsValue> {
+        let factory = web_sys::window()
+            .expect("No window")
+            .indexed_db()?
+            .ok_or_else(|| JsValue::from_str("No IndexedDB"))?;
+        let request = factory.open_with_u32(DATABASE, 1)?;
+        let upgrading = request.clone();
+        let on_upgrade = Closure::once_into_js(move || {
+            if let Ok(db) = upgrading.result() {
+                let db: IdbDatabase = db.unchecked_into();
+                if let Err(err) = db.create_object_store(OBJECT_STORE) {
+                    error!("Failed to create object store: {err:?}");
+                }
+            }
+        });
+        request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+        Ok(finish(&request).await?.unchecked_into())
+    }
+
+    // open the object store for a single transaction
+    async fn object_store(mode: IdbTransactionMode) -> Result<IdbObjectStore, JsValue> {
+        Self::open()
+            .await?
+            .transaction_with_str_and_mode(OBJECT_STORE, mode)?
+            .object_store(OBJECT_STORE)
+    }
+
+    async fn get(key: &str) -> Result<JsValue, JsValue> {
+        let store = Self::object_store(IdbTransactionMode::Readonly).await?;
+        finish(&store.get(&JsValue::from_str(key))?).await
+    }
+
+    async fn put(key: &str, value: &str) -> Result<JsValue, JsValue> {
+        let store = Self::object_store(IdbTransactionMode::Readwrite).await?;
+        finish(&store.put_with_key(&JsValue::from_str(value), &JsValue::from_str(key))?).await
+    }
+}
+
+impl DataStore for IndexedDb {
+    fn read(&self, folder: DataFolder, name: &str, slot: ReadSlot) {
+        let key = format!("{}/{name}", folder.name());
+        spawn_local(async move {
+            let result = match Self::get(&key).await {
+                // missing keys come back as undefined
+                Ok(value) => value.as_string().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, format!("no value for {key}"))
+                }),
+                Err(err) => Err(js_error(err)),
+            };
+            *slot.lock().unwrap() = Some(result);
+        });
+    }
+
+    fn write(&mut self, folder: DataFolder, name: &str, value: String) -> io::Result<()> {
+        let key = format!("{}/{name}", folder.name());
+        // the write finishes in the background, so failures can only be logged
+        spawn_local(async move {
+            if let Err(err) = Self::put(&key, &value).await {
+                error!("Failed to write {key}: {err:?}");
+            }
+        });
         Ok(())
     }
 }
+
+/// Waits for an IndexedDB request to finish, returning its result.
+async fn finish(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    JsFuture::from(promise).await?;
+    request.result()
+}
+
+/// Converts an error thrown by the browser into an IO error.
+fn js_error(err: impl std::fmt::Debug) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{err:?}"))
+}
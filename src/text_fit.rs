@@ -0,0 +1,186 @@
+//! Fits text into a limited width, for names and other text that can be any length.
+//!
+//! Text is measured with an estimate of each character's width in our fonts, so it can be
+//! fitted when it's spawned instead of after it's been laid out. Text that's too wide is shrunk
+//! down to a smallest font size first, and then cut short with an ellipsis.
+
+/// Shown at the end of text that's been cut short.
+const ELLIPSIS: char = '…';
+/// How much the font size goes down by each time text is shrunk to fit.
+const SHRINK_STEP: f32 = 2.0;
+
+/// Returns the estimated width of a character, as a fraction of the font size.
+fn char_width(c: char) -> f32 {
+    match c {
+        'i' | 'j' | 'l' | 'I' | '.' | ',' | ':' | ';' | '\'' | '!' | '|' => 0.28,
+        'f' | 'r' | 't' | ' ' | '(' | ')' | '1' => 0.38,
+        'm' | 'w' | 'M' | 'W' | '@' => 0.85,
+        c if c.is_uppercase() => 0.66,
+        _ => 0.56,
+    }
+}
+
+/// Returns the estimated width of a line of text at the given font size.
+pub fn text_width(text: &str, font_size: f32) -> f32 {
+    text.chars().map(char_width).sum::<f32>() * font_size
+}
+
+/// Cuts text short with an ellipsis so it fits in the width, or returns it as is if it fits.
+pub fn truncate(text: &str, font_size: f32, max_width: f32) -> String {
+    if text_width(text, font_size) <= max_width {
+        return String::from(text);
+    }
+    let budget = max_width - char_width(ELLIPSIS) * font_size;
+    let mut width = 0.0;
+    let mut truncated: String = text
+        .chars()
+        .take_while(|c| {
+            width += char_width(*c) * font_size;
+            width <= budget
+        })
+        .collect();
+    truncated.truncate(truncated.trim_end().len());
+    truncated.push(ELLIPSIS);
+    truncated
+}
+
+/// Returns the largest font size between `min_font_size` and `font_size` that the text fits
+/// at, and the text cut short if it doesn't fit even at the smallest size.
+pub fn fit(text: &str, font_size: f32, min_font_size: f32, max_width: f32) -> (String, f32) {
+    let size = shrink(font_size, min_font_size, |size| {
+        text_width(text, size) <= max_width
+    });
+    (truncate(text, size, max_width), size)
+}
+
+/// Fills the `{}` placeholder in translated text with a value, like [`Language::tr_with`],
+/// shrinking the text to fit and then cutting the value short if it still doesn't.
+///
+/// [`Language::tr_with`]: crate::i18n::Language::tr_with
+pub fn fit_filled(
+    template: &str,
+    value: &str,
+    font_size: f32,
+    min_font_size: f32,
+    max_width: f32,
+) -> (String, f32) {
+    let filled = template.replacen("{}", value, 1);
+    let size = shrink(font_size, min_font_size, |size| {
+        text_width(&filled, size) <= max_width
+    });
+    let rest = text_width(&template.replacen("{}", "", 1), size);
+    let value = truncate(value, size, (max_width - rest).max(0.0));
+    (template.replacen("{}", &value, 1), size)
+}
+
+/// Breaks text into lines that fit in the width, at spaces where possible.
+///
+/// Anything past the last line is dropped, and the last line ends with an ellipsis.
+pub fn wrap(text: &str, font_size: f32, max_width: f32, max_lines: usize) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            String::from(word)
+        } else {
+            format!("{line} {word}")
+        };
+        if text_width(&candidate, font_size) <= max_width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        // words too long for a line of their own are split between characters
+        for c in word.chars() {
+            if !line.is_empty()
+                && text_width(&line, font_size) + char_width(c) * font_size > max_width
+            {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(c);
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    if lines.len() > max_lines.max(1) {
+        let rest = lines.split_off(max_lines.max(1) - 1).join(" ");
+        // always cut short, since the rest of the text is missing
+        let last = truncate(
+            &rest,
+            font_size,
+            max_width - char_width(ELLIPSIS) * font_size,
+        );
+        lines.push(if last.ends_with(ELLIPSIS) {
+            last
+        } else {
+            format!("{last}{ELLIPSIS}")
+        });
+    }
+    lines.join("\n")
+}
+
+/// Returns the largest font size between `min_font_size` and `font_size` that the text fits in
+/// the given number of lines at, and the text broken into lines at that size.
+pub fn fit_lines(
+    text: &str,
+    font_size: f32,
+    min_font_size: f32,
+    max_width: f32,
+    max_lines: usize,
+) -> (String, f32) {
+    let size = shrink(font_size, min_font_size, |size| {
+        !wrap(text, size, max_width, max_lines).ends_with(ELLIPSIS)
+    });
+    (wrap(text, size, max_width, max_lines), size)
+}
+
+/// Steps the font size down from `font_size` until the text fits, stopping at `min_font_size`.
+fn shrink(font_size: f32, min_font_size: f32, fits: impl Fn(f32) -> bool) -> f32 {
+    let mut size = font_size;
+    while size > min_font_size && !fits(size) {
+        size = (size - SHRINK_STEP).max(min_font_size);
+    }
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that long text is shrunk first, then cut short, and short text is left alone.
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit("Bob", 30.0, 20.0, 100.0), (String::from("Bob"), 30.0));
+
+        let name = "Maximilian";
+        let (shrunk, size) = fit(name, 30.0, 10.0, 150.0);
+        assert_eq!(shrunk, name);
+        assert!(size < 30.0 && text_width(name, size) <= 150.0);
+
+        let (cut, size) = fit("A very long username indeed", 30.0, 24.0, 150.0);
+        assert_eq!(size, 24.0);
+        assert!(cut.ends_with(ELLIPSIS) && text_width(&cut, size) <= 150.0);
+
+        // only the filled in value is cut short
+        let (title, _) = fit_filled("{} won!", "Bartholomew the Great", 72.0, 40.0, 500.0);
+        assert!(title.ends_with("… won!"));
+    }
+
+    /// Ensures that names are broken at spaces, and anything past the last line is cut.
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("Ana Maria", 20.0, 60.0, 2), "Ana\nMaria");
+        assert_eq!(wrap("Ana Maria", 20.0, 200.0, 2), "Ana Maria");
+
+        let wrapped = wrap("Ana Maria Lopez Garcia", 20.0, 60.0, 2);
+        assert_eq!(wrapped.lines().count(), 2);
+        assert!(wrapped.ends_with(ELLIPSIS));
+
+        let (lines, size) = fit_lines("Ana Maria Lopez", 30.0, 20.0, 120.0, 2);
+        assert!(size <= 30.0 && !lines.ends_with(ELLIPSIS));
+    }
+}
@@ -0,0 +1,222 @@
+//! Colors used to theme the table and cards.
+//!
+//! The table theme and card back are picked in the settings. The host's table theme can be
+//! shown to everyone in the room instead, so all players see the same table.
+
+use crate::{settings::Settings, BackgroundMaterial, ScreenState};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+use serde::{Deserialize, Serialize};
+
+/// Size of the table the game is played on.
+pub const TABLE_SIZE: Vec2 = Vec2::new(640.0, 320.0);
+/// Offset of the shadow drawn under cards.
+pub const CARD_SHADOW_OFFSET: Vec3 = Vec3::new(4.0, -6.0, -0.005);
+
+/// Colors of the decorations on the table.
+#[derive(Resource, Debug, Clone)]
+pub struct Theme {
+    /// Color of the outlines marking where the piles go.
+    pub slot: Color,
+    /// Color of the shadows under cards.
+    pub card_shadow: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            slot: Color::rgba(1.0, 1.0, 1.0, 0.35),
+            card_shadow: Color::rgba(0.0, 0.0, 0.0, 0.3),
+        }
+    }
+}
+
+/// Color scheme of the table and the background around it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TableTheme {
+    #[default]
+    Green,
+    Blue,
+    Red,
+    Purple,
+}
+
+impl TableTheme {
+    /// All table themes, in the order they're picked.
+    pub const ALL: [TableTheme; 4] = [
+        TableTheme::Green,
+        TableTheme::Blue,
+        TableTheme::Red,
+        TableTheme::Purple,
+    ];
+
+    /// Name shown in the settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TableTheme::Green => "Green",
+            TableTheme::Blue => "Blue",
+            TableTheme::Red => "Red",
+            TableTheme::Purple => "Purple",
+        }
+    }
+
+    /// Tint of the felt table surface.
+    pub fn felt(&self) -> Color {
+        match self {
+            TableTheme::Green => Color::rgb(0.18, 0.52, 0.32),
+            TableTheme::Blue => Color::rgb(0.16, 0.36, 0.6),
+            TableTheme::Red => Color::rgb(0.56, 0.18, 0.2),
+            TableTheme::Purple => Color::rgb(0.4, 0.24, 0.56),
+        }
+    }
+
+    /// Tint of the background behind the table.
+    pub fn background(&self) -> Color {
+        match self {
+            TableTheme::Green => Color::WHITE,
+            TableTheme::Blue => Color::rgb(0.75, 0.85, 1.0),
+            TableTheme::Red => Color::rgb(1.0, 0.78, 0.78),
+            TableTheme::Purple => Color::rgb(0.88, 0.8, 1.0),
+        }
+    }
+
+    /// Returns the theme after this one.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|t| t == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl Into<u8> for TableTheme {
+    fn into(self) -> u8 {
+        match self {
+            TableTheme::Green => 0,
+            TableTheme::Blue => 1,
+            TableTheme::Red => 2,
+            TableTheme::Purple => 3,
+        }
+    }
+}
+
+impl From<u8> for TableTheme {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => TableTheme::Blue,
+            2 => TableTheme::Red,
+            3 => TableTheme::Purple,
+            _ => TableTheme::Green,
+        }
+    }
+}
+
+/// Design on the back of the cards in the draw pile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CardBack {
+    #[default]
+    Classic,
+    Crimson,
+    Navy,
+    Emerald,
+    Gold,
+}
+
+impl CardBack {
+    /// All card backs, in the order they're picked.
+    pub const ALL: [CardBack; 5] = [
+        CardBack::Classic,
+        CardBack::Crimson,
+        CardBack::Navy,
+        CardBack::Emerald,
+        CardBack::Gold,
+    ];
+
+    /// Name shown in the settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CardBack::Classic => "Classic",
+            CardBack::Crimson => "Crimson",
+            CardBack::Navy => "Navy",
+            CardBack::Emerald => "Emerald",
+            CardBack::Gold => "Gold",
+        }
+    }
+
+    /// Tint applied to the card back texture.
+    pub fn tint(&self) -> Color {
+        match self {
+            CardBack::Classic => Color::WHITE,
+            CardBack::Crimson => Color::rgb(1.0, 0.55, 0.55),
+            CardBack::Navy => Color::rgb(0.55, 0.65, 1.0),
+            CardBack::Emerald => Color::rgb(0.55, 1.0, 0.7),
+            CardBack::Gold => Color::rgb(1.0, 0.85, 0.4),
+        }
+    }
+
+    /// Returns the card back after this one.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|b| b == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// Table theme of the host of the current game, if they've shared it.
+#[derive(Resource, Default)]
+pub struct HostTable(pub Option<TableTheme>);
+
+/// Returns the table theme to show, which is the host's if the player wants to see it.
+pub fn table_theme(settings: &Settings, host_table: &HostTable) -> TableTheme {
+    match host_table.0 {
+        Some(theme) if settings.use_host_table => theme,
+        _ => settings.table_theme,
+    }
+}
+
+/// Tints the background to match the table theme.
+fn apply_background_tint(
+    backgrounds: Query<&Handle<BackgroundMaterial>>,
+    mut materials: ResMut<Assets<BackgroundMaterial>>,
+    settings: Res<Settings>,
+    host_table: Res<HostTable>,
+) {
+    if !settings.is_changed() && !host_table.is_changed() {
+        return;
+    }
+    let tint = table_theme(&settings, &host_table).background();
+    for handle in &backgrounds {
+        if let Some(material) = materials.get_mut(handle) {
+            material.tint = tint;
+        }
+    }
+}
+
+/// Goes back to our own table theme after a game.
+fn forget_host_table(mut host_table: ResMut<HostTable>) {
+    host_table.0 = None;
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>()
+            .init_resource::<HostTable>()
+            .add_systems(OnExit(ScreenState::Game), forget_host_table)
+            .add_systems(
+                Update,
+                apply_background_tint.run_if(resource_exists::<Settings>()),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that table themes survive being sent to peers.
+    #[test]
+    fn test_table_theme_bytes() {
+        for theme in TableTheme::ALL {
+            let byte: u8 = theme.into();
+            assert_eq!(TableTheme::from(byte), theme);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Short messages shown at the bottom of the screen for a few seconds.
+
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// How long (in seconds) a toast stays on screen.
+const TOAST_DURATION: f32 = 4.0;
+
+/// Shows a message to the player for a few seconds.
+#[derive(Event)]
+pub struct Toast(pub String);
+
+/// Node that holds the toasts on screen.
+#[derive(Component)]
+struct ToastList;
+
+/// Time left before a toast is removed.
+#[derive(Component)]
+struct ToastTimer(Timer);
+
+/// Draws the container for toasts, which stays across screens.
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(140.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            z_index: ZIndex::Global(20),
+            ..default()
+        },
+        ToastList,
+    ));
+}
+
+/// Adds new toasts to the list.
+fn show_toasts(
+    mut events: EventReader<Toast>,
+    list: Query<Entity, With<ToastList>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Ok(list) = list.get_single() else { return; };
+    for Toast(message) in events.read() {
+        info!("{message}");
+        commands.entity(list).with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            padding: UiRect::axes(Val::Px(14.0), Val::Px(6.0)),
+                            ..default()
+                        },
+                        background_color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+                        ..default()
+                    },
+                    ToastTimer(Timer::from_seconds(TOAST_DURATION, TimerMode::Once)),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        message.clone(),
+                        TextStyle {
+                            font: asset_server.load("fonts/Lato-Black.ttf"),
+                            font_size: 22.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                });
+        });
+    }
+}
+
+/// Removes toasts once their time is up.
+fn expire_toasts(
+    mut toasts: Query<(Entity, &mut ToastTimer)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut timer) in &mut toasts {
+        if timer.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Toast>()
+            .add_systems(Startup, setup)
+            .add_systems(Update, (show_toasts, expire_toasts));
+    }
+}
@@ -0,0 +1,442 @@
+//! Tournament hubs.
+//!
+//! A hub is a second matchbox room that players stay connected to alongside their game room.
+//! The organizer uses it to post announcements and standings, and to send each player the
+//! code of their next match, so nobody has to type room codes between rounds.
+
+use crate::{
+    info::Opponents,
+    menu::MenuState,
+    network::{Broadcast, ServerState},
+    screens::win::Win,
+    settings::Settings,
+    toast::Toast,
+    transport::GameTransport,
+    ScreenState,
+};
+use bevy::{
+    prelude::{Plugin as BevyPlugin, *},
+    utils::HashMap,
+};
+use bevy_matchbox::prelude::*;
+
+/// Most players put in the same match.
+const MAX_MATCH_SIZE: usize = 4;
+
+/// Hub event, which corresponds to one byte.
+#[derive(PartialEq, Eq)]
+enum HubEvent {
+    /// A player's name, sent to everyone they connect to.
+    Name,
+    /// A message from the organizer.
+    Announcement,
+    /// Wins for every player, sent by the organizer.
+    Standings,
+    /// The room code of the player's next match, and whether they host it.
+    Match,
+    /// The name of the winner of a match, sent by the match's host.
+    Result,
+}
+
+impl Into<u8> for HubEvent {
+    fn into(self) -> u8 {
+        match self {
+            Self::Name => 0,
+            Self::Announcement => 1,
+            Self::Standings => 2,
+            Self::Match => 3,
+            Self::Result => 4,
+        }
+    }
+}
+
+impl TryFrom<u8> for HubEvent {
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Name),
+            1 => Ok(Self::Announcement),
+            2 => Ok(Self::Standings),
+            3 => Ok(Self::Match),
+            4 => Ok(Self::Result),
+            _ => Err(()),
+        }
+    }
+
+    type Error = ();
+}
+
+/// Connection to the tournament hub room, with the buffers for sending to everyone in it.
+#[derive(Resource)]
+pub struct HubSocket(MatchboxSocket<SingleChannel>, Broadcast);
+
+impl HubSocket {
+    pub fn new(server_url: &str, code: u16) -> Self {
+        Self(
+            MatchboxSocket::new_reliable(format!("{server_url}/v1_hub_{code}")),
+            Broadcast::default(),
+        )
+    }
+
+    /// Sends a packet to everyone in the hub.
+    fn broadcast(&mut self, event: HubEvent, payload: &[u8]) {
+        let mut packet = vec![event.into()];
+        packet.extend_from_slice(payload);
+        let packet = packet.into_boxed_slice();
+        let Self(socket, broadcast) = self;
+        for peer in broadcast.peers(socket.connected_peers()) {
+            socket.send(packet.clone(), *peer);
+        }
+    }
+}
+
+/// A player's record in the tournament.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Standing {
+    pub name: String,
+    pub wins: u16,
+}
+
+/// The tournament we're taking part in.
+#[derive(Resource)]
+pub struct Tournament {
+    pub code: u16,
+    /// Whether we're running the tournament, rather than playing in it.
+    pub is_organizer: bool,
+    /// Players ordered by wins, most first.
+    pub standings: Vec<Standing>,
+    /// Last message from the organizer.
+    pub announcement: Option<String>,
+    /// Number of rounds started so far.
+    pub round: u32,
+    /// Names of the players in the hub.
+    players: HashMap<PeerId, String>,
+    /// Match we've been sent to, joined once we're back in the menu.
+    next_match: Option<ServerState>,
+}
+
+impl Tournament {
+    pub fn new(code: u16, is_organizer: bool) -> Self {
+        Self {
+            code,
+            is_organizer,
+            standings: Vec::new(),
+            announcement: None,
+            round: 0,
+            players: HashMap::new(),
+            next_match: None,
+        }
+    }
+
+    /// Returns the number of players in the hub, not counting the organizer.
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Adds a win for the player, adding them to the standings if needed.
+    fn record_win(&mut self, name: &str) {
+        match self.standings.iter_mut().find(|s| s.name == name) {
+            Some(standing) => standing.wins += 1,
+            None => self.standings.push(Standing {
+                name: String::from(name),
+                wins: 1,
+            }),
+        }
+        self.standings.sort_by_key(|s| std::cmp::Reverse(s.wins));
+    }
+
+    /// Returns the number of wins recorded for the player.
+    fn wins(&self, name: &str) -> u16 {
+        self.standings
+            .iter()
+            .find(|s| s.name == name)
+            .map_or(0, |s| s.wins)
+    }
+}
+
+/// Serializes standings for the standings packet.
+///
+/// Each player is their win count as two bytes, then the length of their name, then the name.
+fn standings_to_bytes(standings: &[Standing]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for standing in standings {
+        let name = standing.name.as_bytes();
+        bytes.extend(standing.wins.to_be_bytes());
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name);
+    }
+    bytes
+}
+
+/// Loads standings from the standings packet, stopping at the first malformed entry.
+fn standings_from_bytes(mut bytes: &[u8]) -> Vec<Standing> {
+    let mut standings = Vec::new();
+    while bytes.len() >= 3 {
+        let wins = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let len = bytes[2] as usize;
+        let Some(name) = bytes.get(3..3 + len) else { break; };
+        standings.push(Standing {
+            name: String::from_utf8_lossy(name).into_owned(),
+            wins,
+        });
+        bytes = &bytes[3 + len..];
+    }
+    standings
+}
+
+/// Splits players into matches of at most [`MAX_MATCH_SIZE`], keeping neighbours together.
+///
+/// Nobody is left to play alone: a single player left over joins the last full match, or
+/// takes a seat from it if it's already full.
+fn group_players<T: Clone>(players: &[T]) -> Vec<Vec<T>> {
+    let mut groups: Vec<Vec<T>> = players
+        .chunks(MAX_MATCH_SIZE)
+        .map(|chunk| chunk.to_vec())
+        .collect();
+    if groups.len() > 1 && groups.last().is_some_and(|g| g.len() == 1) {
+        let single = groups.pop().unwrap();
+        let previous = groups.last_mut().unwrap();
+        if previous.len() < MAX_MATCH_SIZE {
+            previous.extend(single);
+        } else {
+            let moved = previous.pop().unwrap();
+            groups.push(vec![moved, single[0].clone()]);
+        }
+    }
+    groups
+}
+
+/// Starts a tournament hub, either as the organizer or as a player.
+pub fn join_hub(commands: &mut Commands, settings: &Settings, code: u16, is_organizer: bool) {
+    commands.insert_resource(HubSocket::new(&settings.server_url, code));
+    commands.insert_resource(Tournament::new(code, is_organizer));
+}
+
+/// Leaves the tournament hub.
+pub fn leave_hub(commands: &mut Commands) {
+    commands.remove_resource::<HubSocket>();
+    commands.remove_resource::<Tournament>();
+}
+
+/// Handles packets from the hub.
+fn receive_hub(
+    mut hub: ResMut<HubSocket>,
+    mut tournament: ResMut<Tournament>,
+    mut toasts: EventWriter<Toast>,
+    settings: Res<Settings>,
+) {
+    match hub.0.try_update_peers() {
+        Ok(result) => {
+            for (peer, state) in result {
+                match state {
+                    // introduce ourselves, so the organizer knows who to send to matches
+                    PeerState::Connected if !tournament.is_organizer => {
+                        let mut packet = vec![HubEvent::Name.into()];
+                        packet.extend_from_slice(settings.username.as_bytes());
+                        hub.0.send(packet.into_boxed_slice(), peer);
+                    }
+                    // catch late arrivals up on the standings
+                    PeerState::Connected => {
+                        let mut packet = vec![HubEvent::Standings.into()];
+                        packet.extend(standings_to_bytes(&tournament.standings));
+                        hub.0.send(packet.into_boxed_slice(), peer);
+                    }
+                    PeerState::Disconnected => {
+                        tournament.players.remove(&peer);
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            error!("Error updating hub peers: {e:?}");
+        }
+    }
+
+    for (peer, packet) in hub.0.receive() {
+        let Some((&event, payload)) = packet.split_first() else { continue; };
+        let Ok(event) = HubEvent::try_from(event) else {
+            error!("Invalid hub event: {event}");
+            continue;
+        };
+        match event {
+            HubEvent::Name => {
+                let name = String::from_utf8_lossy(payload).into_owned();
+                tournament.players.insert(peer, name);
+            }
+            HubEvent::Announcement => {
+                let message = String::from_utf8_lossy(payload).into_owned();
+                toasts.send(Toast(message.clone()));
+                tournament.announcement = Some(message);
+            }
+            HubEvent::Standings => {
+                tournament.standings = standings_from_bytes(payload);
+            }
+            HubEvent::Match => {
+                // whether the player hosts the match, then the room code
+                let Some((is_host, code)) = payload.split_first() else {
+                    error!("Invalid match packet: wrong length.");
+                    continue;
+                };
+                let code = String::from_utf8_lossy(code).into_owned();
+                toasts.send(Toast(
+                    settings.language.tr_with("Next match: room {}", &code),
+                ));
+                tournament.next_match = Some(if *is_host != 0 {
+                    ServerState::Server(code)
+                } else {
+                    ServerState::Client(code)
+                });
+            }
+            HubEvent::Result if tournament.is_organizer => {
+                let winner = String::from_utf8_lossy(payload).into_owned();
+                tournament.record_win(&winner);
+                let standings = standings_to_bytes(&tournament.standings);
+                hub.broadcast(HubEvent::Standings, &standings);
+            }
+            HubEvent::Result => {}
+        }
+    }
+}
+
+/// Sends an announcement to everyone in the hub.
+pub fn announce(hub: &mut HubSocket, tournament: &mut Tournament, message: String) {
+    hub.broadcast(HubEvent::Announcement, message.as_bytes());
+    tournament.announcement = Some(message);
+}
+
+/// Puts the players in the hub into matches, best records together, and sends them their
+/// room codes.
+pub fn start_round(hub: &mut HubSocket, tournament: &mut Tournament, settings: &Settings) {
+    let mut players: Vec<(PeerId, String)> = tournament
+        .players
+        .iter()
+        .map(|(peer, name)| (*peer, name.clone()))
+        .collect();
+    players.sort_by_key(|(_, name)| std::cmp::Reverse(tournament.wins(name)));
+
+    let mut rng = rand::thread_rng();
+    for group in group_players(&players) {
+        let code = settings.room_codes.generate(&mut rng);
+        // the first player in each match hosts it
+        for (i, (peer, _)) in group.iter().enumerate() {
+            let mut packet = vec![HubEvent::Match.into(), (i == 0) as u8];
+            packet.extend_from_slice(code.as_bytes());
+            hub.0.send(packet.into_boxed_slice(), *peer);
+        }
+    }
+
+    tournament.round += 1;
+    let message = settings
+        .language
+        .tr_with("Round {} is starting!", tournament.round);
+    announce(hub, tournament, message);
+}
+
+/// Reports the winner of our match to the organizer, if we hosted it.
+fn report_result(
+    mut events: EventReader<Win>,
+    mut hub: ResMut<HubSocket>,
+    mut socket: ResMut<GameTransport>,
+    server_state: Res<State<ServerState>>,
+    opponents: Res<Opponents>,
+    settings: Res<Settings>,
+) {
+    let Some(Win(winner)) = events.read().next() else { return; };
+    // every player sees the win, so only the match host reports it
+    if !matches!(**server_state, ServerState::Server(_)) {
+        return;
+    }
+    let name = if socket.id() == Some(*winner) {
+        settings.username.clone()
+    } else {
+        let Some(opponent) = opponents.0.iter().find(|o| o.id == *winner) else { return; };
+        opponent.name.clone()
+    };
+    hub.broadcast(HubEvent::Result, name.as_bytes());
+}
+
+/// Takes us to our next match once we're back in the menu.
+fn join_next_match(
+    mut tournament: ResMut<Tournament>,
+    mut server_state: ResMut<NextState<ServerState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    current_server_state: Res<State<ServerState>>,
+) {
+    // wait until we've left the last match
+    if **current_server_state != ServerState::None {
+        return;
+    }
+    let Some(next_match) = tournament.next_match.take() else { return; };
+    server_state.set(next_match);
+    menu_state.set(MenuState::Lobby);
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                // the hub stays connected on every screen
+                receive_hub,
+                report_result
+                    .run_if(in_state(ScreenState::Game))
+                    .run_if(resource_exists::<GameTransport>()),
+                join_next_match.run_if(in_state(ScreenState::Menu)),
+            )
+                .run_if(resource_exists::<HubSocket>())
+                .run_if(resource_exists::<Tournament>()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that players are split into matches without anyone playing alone.
+    #[test]
+    fn test_group_players() {
+        let sizes = |count: usize| -> Vec<usize> {
+            let players: Vec<usize> = (0..count).collect();
+            group_players(&players).iter().map(Vec::len).collect()
+        };
+        assert_eq!(sizes(0), Vec::<usize>::new());
+        assert_eq!(sizes(3), vec![3]);
+        assert_eq!(sizes(4), vec![4]);
+        assert_eq!(sizes(5), vec![3, 2]);
+        assert_eq!(sizes(7), vec![4, 3]);
+        assert_eq!(sizes(9), vec![4, 3, 2]);
+
+        // neighbours in the standings stay together
+        assert_eq!(
+            group_players(&[1, 2, 3, 4, 5, 6]),
+            vec![vec![1, 2, 3, 4], vec![5, 6]]
+        );
+    }
+
+    /// Ensures that standings survive being sent in a packet.
+    #[test]
+    fn test_standings_serialization() {
+        let standings = vec![
+            Standing {
+                name: String::from("Ana"),
+                wins: 3,
+            },
+            Standing {
+                name: String::from("Bob"),
+                wins: 0,
+            },
+        ];
+        assert_eq!(
+            standings_from_bytes(&standings_to_bytes(&standings)),
+            standings
+        );
+        // a cut off packet keeps the entries that arrived whole
+        let bytes = standings_to_bytes(&standings);
+        assert_eq!(
+            standings_from_bytes(&bytes[..bytes.len() - 1]),
+            standings[..1]
+        );
+    }
+}
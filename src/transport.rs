@@ -0,0 +1,281 @@
+//! The connection that carries game events between the players in a room.
+//!
+//! Game systems talk to [`GameTransport`] rather than a WebRTC socket, so the same code runs over
+//! matchbox in online rooms, and over an in-memory network for games on this device and in tests.
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_matchbox::{
+    matchbox_socket::{ChannelError, Packet},
+    prelude::*,
+};
+
+/// A way of exchanging packets with the other peers in a room.
+pub trait Transport: Send + Sync {
+    /// Our own peer id, once one has been assigned.
+    fn id(&mut self) -> Option<PeerId>;
+
+    /// Returns the peers that connected or disconnected since the last call, or an error once
+    /// the connection to the server has closed.
+    fn update_peers(&mut self) -> Result<Vec<(PeerId, PeerState)>, ChannelError>;
+
+    /// Peers we're currently connected to.
+    fn connected_peers(&self) -> Box<dyn Iterator<Item = PeerId> + '_>;
+
+    /// Sends a packet to a peer on the given channel.
+    fn send(&mut self, channel: usize, packet: Packet, peer: PeerId);
+
+    /// Takes the packets that have arrived on the given channel.
+    fn receive(&mut self, channel: usize) -> Vec<(PeerId, Packet)>;
+}
+
+impl Transport for MatchboxSocket<MultipleChannels> {
+    fn id(&mut self) -> Option<PeerId> {
+        (**self).id()
+    }
+
+    fn update_peers(&mut self) -> Result<Vec<(PeerId, PeerState)>, ChannelError> {
+        self.try_update_peers()
+    }
+
+    fn connected_peers(&self) -> Box<dyn Iterator<Item = PeerId> + '_> {
+        Box::new((**self).connected_peers())
+    }
+
+    fn send(&mut self, channel: usize, packet: Packet, peer: PeerId) {
+        self.channel(channel).send(packet, peer);
+    }
+
+    fn receive(&mut self, channel: usize) -> Vec<(PeerId, Packet)> {
+        self.channel(channel).receive()
+    }
+}
+
+/// Connection to the other players in the room.
+#[derive(Resource)]
+pub struct GameTransport {
+    transport: Box<dyn Transport>,
+    /// Next sequence number to put on the reliable packets sent to each peer.
+    next_sequence: HashMap<PeerId, u32>,
+    /// Whether the connection to the server has closed, because it couldn't be reached or
+    /// dropped us.
+    closed: bool,
+    /// Peers that haven't been let into the room, who are left out of broadcasts.
+    outside: HashSet<PeerId>,
+    /// Peers we've cut off, who are sent nothing and whose packets are dropped.
+    cut_off: HashSet<PeerId>,
+}
+
+impl GameTransport {
+    pub fn new(transport: impl Transport + 'static) -> Self {
+        Self {
+            transport: Box::new(transport),
+            next_sequence: HashMap::new(),
+            closed: false,
+            outside: HashSet::new(),
+            cut_off: HashSet::new(),
+        }
+    }
+
+    /// A transport with no one else on it, for playing against bots without a server.
+    pub fn local() -> Self {
+        Self::new(memory::MemoryNetwork::default().join())
+    }
+
+    /// Our own peer id, once one has been assigned.
+    pub fn id(&mut self) -> Option<PeerId> {
+        self.transport.id()
+    }
+
+    /// Returns the peers that connected or disconnected since the last call.
+    pub fn update_peers(&mut self) -> Vec<(PeerId, PeerState)> {
+        if self.closed {
+            return Vec::new();
+        }
+        let peers = self.transport.update_peers().unwrap_or_else(|e| {
+            error!("Error updating peers: {e:?}");
+            self.closed = true;
+            Vec::new()
+        });
+        for (peer, state) in peers.iter() {
+            if *state == PeerState::Disconnected {
+                self.outside.remove(peer);
+                self.cut_off.remove(peer);
+            }
+        }
+        peers
+    }
+
+    /// Returns `true` once the connection to the server has closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Peers we're currently connected to, other than the ones we've cut off.
+    pub fn connected_peers(&self) -> impl Iterator<Item = PeerId> + '_ {
+        self.transport
+            .connected_peers()
+            .filter(|peer| !self.cut_off.contains(peer))
+    }
+
+    /// Peers in the room, who broadcasts go to.
+    pub fn room_peers(&self) -> impl Iterator<Item = PeerId> + '_ {
+        self.connected_peers()
+            .filter(|peer| !self.outside.contains(peer))
+    }
+
+    /// Leaves a peer out of broadcasts until they're let into the room.
+    pub fn hold_back(&mut self, peer: PeerId) {
+        self.outside.insert(peer);
+    }
+
+    /// Includes a peer in broadcasts, now that they're in the room.
+    pub fn let_in(&mut self, peer: PeerId) {
+        self.outside.remove(&peer);
+    }
+
+    /// Whether a peer is left out of the room, because they haven't been let in or were cut off.
+    pub fn is_outside(&self, peer: PeerId) -> bool {
+        self.outside.contains(&peer) || self.cut_off.contains(&peer)
+    }
+
+    /// Stops talking to a peer. Matchbox can't close the connection to a single peer, so instead
+    /// nothing more is sent to them and anything they send is dropped.
+    pub fn cut_off(&mut self, peer: PeerId) {
+        self.outside.remove(&peer);
+        self.cut_off.insert(peer);
+    }
+
+    /// Sends a packet to a peer on the given channel, unless they've been cut off.
+    pub fn send(&mut self, channel: usize, packet: Packet, peer: PeerId) {
+        if self.cut_off.contains(&peer) {
+            return;
+        }
+        self.transport.send(channel, packet, peer);
+    }
+
+    /// Takes the packets that have arrived on the given channel from peers we haven't cut off.
+    pub fn receive(&mut self, channel: usize) -> Vec<(PeerId, Packet)> {
+        let mut packets = self.transport.receive(channel);
+        packets.retain(|(peer, _)| !self.cut_off.contains(peer));
+        packets
+    }
+
+    /// Returns the sequence number for the next reliable packet to a peer, and counts it as sent.
+    pub fn next_sequence(&mut self, peer: PeerId) -> u32 {
+        let next = self.next_sequence.entry(peer).or_default();
+        *next += 1;
+        *next - 1
+    }
+
+    /// Forgets the sequence numbers sent to a peer that left.
+    pub fn forget_sequence(&mut self, peer: &PeerId) {
+        self.next_sequence.remove(peer);
+    }
+}
+
+/// An in-memory network, for games on this device and running several apps against each other.
+pub mod memory {
+    use super::Transport;
+    use bevy::utils::{HashMap, HashSet, Uuid};
+    use bevy_matchbox::{
+        matchbox_socket::{ChannelError, Packet},
+        prelude::*,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// Packets and peer changes waiting to be picked up by a peer on a [`MemoryNetwork`].
+    #[derive(Default)]
+    struct Mailbox {
+        peer_changes: Vec<(PeerId, PeerState)>,
+        channels: HashMap<usize, Vec<(PeerId, Packet)>>,
+    }
+
+    /// Network of peers in the same process, which delivers every packet in order on the next read.
+    #[derive(Clone, Default)]
+    pub struct MemoryNetwork(Arc<Mutex<HashMap<PeerId, Mailbox>>>);
+
+    impl MemoryNetwork {
+        /// Adds a peer to the network, connected to everyone already on it.
+        pub fn join(&self) -> MemoryTransport {
+            let id = PeerId(Uuid::new_v4());
+            let mut mailboxes = self.0.lock().unwrap();
+            let mut mailbox = Mailbox::default();
+            for (peer, other) in mailboxes.iter_mut() {
+                other.peer_changes.push((id, PeerState::Connected));
+                mailbox.peer_changes.push((*peer, PeerState::Connected));
+            }
+            mailboxes.insert(id, mailbox);
+            MemoryTransport {
+                id,
+                network: self.clone(),
+                connected: HashSet::new(),
+            }
+        }
+    }
+
+    /// One peer's connection to a [`MemoryNetwork`], which leaves the network when dropped.
+    pub struct MemoryTransport {
+        id: PeerId,
+        network: MemoryNetwork,
+        connected: HashSet<PeerId>,
+    }
+
+    impl Transport for MemoryTransport {
+        fn id(&mut self) -> Option<PeerId> {
+            Some(self.id)
+        }
+
+        fn update_peers(&mut self) -> Result<Vec<(PeerId, PeerState)>, ChannelError> {
+            let mut mailboxes = self.network.0.lock().unwrap();
+            let Some(mailbox) = mailboxes.get_mut(&self.id) else {
+                return Ok(Vec::new());
+            };
+            let changes: Vec<_> = mailbox.peer_changes.drain(..).collect();
+            for (peer, state) in changes.iter() {
+                match state {
+                    PeerState::Connected => self.connected.insert(*peer),
+                    PeerState::Disconnected => self.connected.remove(peer),
+                };
+            }
+            Ok(changes)
+        }
+
+        fn connected_peers(&self) -> Box<dyn Iterator<Item = PeerId> + '_> {
+            Box::new(self.connected.iter().copied())
+        }
+
+        fn send(&mut self, channel: usize, packet: Packet, peer: PeerId) {
+            let mut mailboxes = self.network.0.lock().unwrap();
+            if let Some(mailbox) = mailboxes.get_mut(&peer) {
+                mailbox
+                    .channels
+                    .entry(channel)
+                    .or_default()
+                    .push((self.id, packet));
+            }
+        }
+
+        fn receive(&mut self, channel: usize) -> Vec<(PeerId, Packet)> {
+            let mut mailboxes = self.network.0.lock().unwrap();
+            mailboxes
+                .get_mut(&self.id)
+                .and_then(|mailbox| mailbox.channels.remove(&channel))
+                .unwrap_or_default()
+        }
+    }
+
+    impl Drop for MemoryTransport {
+        fn drop(&mut self) {
+            let mut mailboxes = self.network.0.lock().unwrap();
+            mailboxes.remove(&self.id);
+            for mailbox in mailboxes.values_mut() {
+                mailbox
+                    .peer_changes
+                    .push((self.id, PeerState::Disconnected));
+            }
+        }
+    }
+}
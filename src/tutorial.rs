@@ -0,0 +1,383 @@
+//! A guided first game against a bot, for players who are new to the game.
+//!
+//! The tutorial is hosted like a challenge, but its deck deals a script of cards that walks
+//! through drawing, matching a color or value, playing a wild 7 and being hit by a draw two.
+//! A callout explains each step, and only the move it asks for can be made, until the script
+//! runs out and the rest of the round is played as usual.
+
+use crate::{
+    card::{Card, CardColor, CardValue},
+    challenge::{ActiveChallenge, Challenge},
+    game_ui::board::OnScreen,
+    info::GameInfo,
+    network::{DrawCard, PlayCard, ServerState, StartGame, WildColor},
+    screens::win::Win,
+    settings::Settings,
+    toast::Toast,
+    transport::GameTransport,
+    GameScreenState, GameSet, ScreenState,
+};
+use bevy::prelude::{Plugin as BevyPlugin, *};
+
+/// Color of the callout's outline.
+const CALLOUT_BORDER_COLOR: Color = Color::rgb(1.0, 0.84, 0.0);
+
+/// The tutorial, played against one bot with the classic rules.
+pub const TUTORIAL: Challenge = Challenge {
+    name: "Tutorial",
+    description: "Learn to play against a bot.",
+    bots: 1,
+    extra_cards: 0,
+    action_deck: false,
+    turn_limit: None,
+};
+
+/// Cards on top of the tutorial's deck, in the order they're drawn.
+///
+/// We're dealt the first five and the bot the next five. A red 3 is flipped, which we have
+/// nothing for, so we draw the wild. The bot can only follow with its red 5, which our blue 5
+/// matches, and then only with its blue 8. We play the wild and pick green, which only the
+/// bot's draw two can follow.
+pub const SCRIPTED_CARDS: [Card; 12] = [
+    Card::new(CardColor::Blue, CardValue::Five, 1),
+    Card::new(CardColor::Green, CardValue::Eight, 1),
+    Card::new(CardColor::Yellow, CardValue::Nine, 1),
+    Card::new(CardColor::Blue, CardValue::One, 1),
+    Card::new(CardColor::Green, CardValue::Four, 1),
+    Card::new(CardColor::Red, CardValue::Five, 1),
+    Card::new(CardColor::Blue, CardValue::Eight, 1),
+    Card::new(CardColor::Yellow, CardValue::Two, 1),
+    Card::new(CardColor::Green, CardValue::DrawTwo, 1),
+    Card::new(CardColor::Yellow, CardValue::Four, 1),
+    Card::new(CardColor::Red, CardValue::Three, 1),
+    Card::new(CardColor::Wild, CardValue::Seven, 0),
+];
+
+/// The card we're asked to play to match the bot's red 5.
+const MATCHING_CARD: Card = SCRIPTED_CARDS[0];
+
+/// A step of the tutorial, each waiting on one move from us.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TutorialStep {
+    /// Drawing, since nothing in our hand can be played.
+    #[default]
+    Draw,
+    /// Matching the top card's value with a card of another color.
+    Match,
+    /// Playing the wild and picking green.
+    Wild,
+    /// Carrying on after the bot's draw two.
+    Penalty,
+    /// The script is over, and anything can be played.
+    Free,
+}
+
+impl TutorialStep {
+    /// Returns the step after this one.
+    fn next(&self) -> Self {
+        match self {
+            TutorialStep::Draw => TutorialStep::Match,
+            TutorialStep::Match => TutorialStep::Wild,
+            TutorialStep::Wild => TutorialStep::Penalty,
+            TutorialStep::Penalty | TutorialStep::Free => TutorialStep::Free,
+        }
+    }
+
+    /// Returns the explanation shown in the callout, if the step has one.
+    pub fn message(&self) -> Option<&'static str> {
+        Some(match self {
+            TutorialStep::Draw => {
+                "None of your cards match the red 3's color or value. Click the deck to draw a \
+                 card."
+            }
+            TutorialStep::Match => {
+                "A card can be played on one of the same color or value. Play your blue 5 on the \
+                 red 5."
+            }
+            TutorialStep::Wild => {
+                "7s are wild: they go on anything, and you pick the color to follow. Play your 7 \
+                 and pick green."
+            }
+            TutorialStep::Penalty => {
+                "The bot played a +2, so you drew two cards. Keep matching to play out your hand, \
+                 and press One! when you're down to your last card."
+            }
+            TutorialStep::Free => return None,
+        })
+    }
+
+    /// Returns `true` if the callout points at the hand, rather than the piles.
+    fn near_hand(&self) -> bool {
+        matches!(self, TutorialStep::Match | TutorialStep::Penalty)
+    }
+
+    /// Returns `true` if we can draw during this step.
+    pub fn allows_draw(&self) -> bool {
+        matches!(
+            self,
+            TutorialStep::Draw | TutorialStep::Penalty | TutorialStep::Free
+        )
+    }
+
+    /// Returns `true` if we can play these cards during this step.
+    pub fn allows_play(&self, cards: &[Card]) -> bool {
+        match self {
+            TutorialStep::Draw => false,
+            TutorialStep::Match => cards == [MATCHING_CARD],
+            TutorialStep::Wild => cards.first().is_some_and(|card| card.color == CardColor::Wild),
+            TutorialStep::Penalty | TutorialStep::Free => true,
+        }
+    }
+
+    /// Returns `true` if we can pick this color for a wild during this step.
+    pub fn allows_color(&self, color: CardColor) -> bool {
+        *self != TutorialStep::Wild || color == CardColor::Green
+    }
+}
+
+/// Step of the tutorial we're on, if we're playing it.
+///
+/// Only the first round follows the script, so a rematch is played freely.
+#[derive(Resource, Default)]
+pub struct Tutorial {
+    step: Option<TutorialStep>,
+}
+
+impl Tutorial {
+    /// Returns `true` if we can draw right now.
+    pub fn allows_draw(&self) -> bool {
+        self.step.is_none_or(|step| step.allows_draw())
+    }
+
+    /// Returns `true` if we can play these cards right now.
+    pub fn allows_play(&self, cards: &[Card]) -> bool {
+        self.step.is_none_or(|step| step.allows_play(cards))
+    }
+
+    /// Returns `true` if we can pick this color for a wild right now.
+    pub fn allows_color(&self, color: CardColor) -> bool {
+        self.step.is_none_or(|step| step.allows_color(color))
+    }
+}
+
+/// Box explaining the current step.
+#[derive(Component)]
+struct Callout;
+
+/// Text in the callout.
+#[derive(Component)]
+struct CalloutText;
+
+/// Starts the script when the tutorial's first round is dealt.
+fn start_tutorial(
+    mut start_events: EventReader<StartGame>,
+    mut tutorial: ResMut<Tutorial>,
+    challenge: Res<ActiveChallenge>,
+) {
+    for event in start_events.read() {
+        tutorial.step = (challenge.is_tutorial() && !event.restart).then_some(TutorialStep::Draw);
+    }
+}
+
+/// Moves on to the next step once we've made the move the current one asks for.
+fn advance_tutorial(
+    mut draw_events: EventReader<DrawCard>,
+    mut play_events: EventReader<PlayCard>,
+    mut color_events: EventReader<WildColor>,
+    mut tutorial: ResMut<Tutorial>,
+) {
+    let drew = draw_events.read().count() > 0;
+    let played = play_events.read().count() > 0;
+    // the wild step lasts until the color is picked, which comes after the play
+    let picked = color_events.read().count() > 0;
+    let Some(step) = tutorial.step else { return; };
+    let done = match step {
+        TutorialStep::Draw => drew,
+        TutorialStep::Match => played,
+        TutorialStep::Wild => picked,
+        TutorialStep::Penalty => drew || played,
+        TutorialStep::Free => false,
+    };
+    if done {
+        tutorial.step = Some(step.next());
+    }
+}
+
+/// Lets us know how the tutorial went once the round is won.
+fn finish_tutorial(
+    mut win_events: EventReader<Win>,
+    mut toasts: EventWriter<Toast>,
+    mut tutorial: ResMut<Tutorial>,
+    mut socket: ResMut<GameTransport>,
+    settings: Res<Settings>,
+) {
+    let Some(Win(winner)) = win_events.read().next() else { return; };
+    if tutorial.step.take().is_none() {
+        return;
+    }
+    let message = if Some(*winner) == socket.id() {
+        "Tutorial complete! You're ready for a real game"
+    } else {
+        "The bot won this time, but now you know how to play"
+    };
+    toasts.send(Toast(String::from(settings.language.tr(message))));
+}
+
+/// Stops the script once the tutorial's room is closed.
+fn end_tutorial(mut tutorial: ResMut<Tutorial>) {
+    tutorial.step = None;
+}
+
+/// Draws the callout, hidden until there's a step to explain.
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                z_index: ZIndex::Global(15),
+                ..default()
+            },
+            Callout,
+            OnScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        max_width: Val::Px(460.0),
+                        padding: UiRect::axes(Val::Px(16.0), Val::Px(10.0)),
+                        border: UiRect::all(Val::Px(3.0)),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    border_color: CALLOUT_BORDER_COLOR.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/Lato-Black.ttf"),
+                                font_size: 22.0,
+                                color: Color::BLACK,
+                            },
+                        ),
+                        CalloutText,
+                    ));
+                });
+        });
+}
+
+/// Shows the current step's explanation on our turn, next to what it's about.
+///
+/// The wild step stays up while picking a color, since the turn may have moved on by then.
+fn update_callout(
+    mut callout: Query<&mut Style, With<Callout>>,
+    mut text: Query<&mut Text, With<CalloutText>>,
+    mut socket: ResMut<GameTransport>,
+    tutorial: Res<Tutorial>,
+    game_info: Res<GameInfo>,
+    game_screen_state: Res<State<GameScreenState>>,
+    settings: Res<Settings>,
+) {
+    let Ok(mut style) = callout.get_single_mut() else { return; };
+    let our_turn = game_info.current_player.is_some() && game_info.current_player == socket.id();
+    let shown = match **game_screen_state {
+        GameScreenState::Game => our_turn,
+        GameScreenState::WildColor => true,
+        _ => false,
+    };
+    let Some((step, message)) = tutorial
+        .step
+        .and_then(|step| Some((step, step.message()?)))
+        .filter(|_| shown)
+    else {
+        if style.display != Display::None {
+            style.display = Display::None;
+        }
+        return;
+    };
+
+    style.display = Display::Flex;
+    (style.top, style.bottom) = if step.near_hand() {
+        (Val::Auto, Val::Percent(38.0))
+    } else {
+        (Val::Percent(10.0), Val::Auto)
+    };
+    for mut text in &mut text {
+        let message = settings.language.tr(message);
+        if text.sections[0].value != message {
+            text.sections[0].value = String::from(message);
+        }
+    }
+}
+
+pub struct Plugin;
+
+impl BevyPlugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tutorial>()
+            .add_systems(OnEnter(ScreenState::Game), setup)
+            .add_systems(OnEnter(ServerState::None), end_tutorial)
+            .add_systems(
+                Update,
+                (
+                    start_tutorial.in_set(GameSet::Rules),
+                    advance_tutorial.in_set(GameSet::Rules),
+                    (finish_tutorial, update_callout)
+                        .in_set(GameSet::Ui)
+                        .run_if(in_state(ScreenState::Game))
+                        .run_if(resource_exists::<GameTransport>()),
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::{Deck, GameRng};
+    use crate::rules::GameRules;
+
+    /// Ensures that the scripted cards are dealt in order, whatever the deck was shuffled into.
+    #[test]
+    fn test_scripted_deal() {
+        let rules = GameRules {
+            scripted_deck: true,
+            ..default()
+        };
+        let mut deck = Deck::shuffled(&rules, &mut GameRng::new(3));
+        assert_eq!(deck.cards.len(), Deck::new().cards.len());
+        assert_eq!(deck.clone().deal(2, 0, 5), SCRIPTED_CARDS[..5]);
+        assert_eq!(deck.deal(2, 1, 5), SCRIPTED_CARDS[5..10]);
+        assert_eq!(deck.draw(2), SCRIPTED_CARDS[10..]);
+    }
+
+    /// Ensures that each step only lets through the move it asks for.
+    #[test]
+    fn test_step_gating() {
+        let wild = SCRIPTED_CARDS[11];
+        let green_eight = SCRIPTED_CARDS[1];
+        assert!(TutorialStep::Draw.allows_draw());
+        assert!(!TutorialStep::Draw.allows_play(&[MATCHING_CARD]));
+
+        assert!(!TutorialStep::Match.allows_draw());
+        assert!(TutorialStep::Match.allows_play(&[MATCHING_CARD]));
+        assert!(!TutorialStep::Match.allows_play(&[wild]));
+
+        assert!(TutorialStep::Wild.allows_play(&[wild]));
+        assert!(!TutorialStep::Wild.allows_play(&[green_eight]));
+        assert!(TutorialStep::Wild.allows_color(CardColor::Green));
+        assert!(!TutorialStep::Wild.allows_color(CardColor::Red));
+
+        assert!(TutorialStep::Free.allows_play(&[green_eight]) && TutorialStep::Free.allows_draw());
+        assert!(Tutorial::default().allows_play(&[green_eight]));
+    }
+}